@@ -1,7 +1,71 @@
+pub use sea_orm_migration::MigrationStatus;
 pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_initial;
 mod m20260115_073352_rich_welcome_channel_configuration;
+mod m20261016_000001_emoji_stats;
+mod m20261016_000002_raid_protection;
+mod m20261016_000003_member_notification_variants;
+mod m20261016_000004_lyrics_cache;
+mod m20261016_000005_retention_policy;
+mod m20261016_000006_aliases;
+mod m20261016_000007_asset_storage;
+mod m20261016_000008_auto_response_channels;
+mod m20261016_000009_nickname_filter;
+mod m20261016_000010_play_history;
+mod m20261016_000011_voice_limits;
+mod m20261016_000012_mc_server_owner;
+mod m20261016_000013_mc_server_samples;
+mod m20261016_000014_blocked_user;
+mod m20261016_000015_scheduled_announcement;
+mod m20261016_000016_definitions;
+mod m20261016_000017_bot_member_settings;
+mod m20261016_000018_auto_response_toggle;
+mod m20261016_000019_voice_sessions;
+mod m20261016_000020_guild_branding;
+mod m20261016_000021_confessions;
+mod m20261016_000022_game_sessions;
+mod m20261016_000023_gallery_channels;
+mod m20261016_000024_unfurl_domains;
+mod m20261016_000025_temp_roles;
+mod m20261016_000026_color_roles;
+mod m20261016_000027_leave_staff_mode;
+mod m20261016_000028_recent_joins;
+mod m20261016_000029_known_members;
+mod m20261016_000030_ephemeral_settings;
+mod m20261016_000031_ban_sync;
+mod m20261016_000032_snipe_settings;
+mod m20261016_000033_ai_settings;
+mod m20261016_000034_mod_digest;
+mod m20261016_000035_playlists;
+mod m20261016_000036_mc_status_channel;
+mod m20261016_000037_webhooks;
+mod m20261016_000038_bot_settings;
+mod m20261016_000039_screening;
+mod m20261016_000040_name_history;
+mod m20261016_000041_saved_embeds;
+mod m20261016_000042_auto_slowmode;
+mod m20261016_000043_movie_night;
+mod m20261016_000044_user_content_blobs;
+mod m20261016_000045_reaction_map;
+mod m20261016_000046_jobs;
+mod m20261016_000047_leader_lease;
+mod m20261016_000048_greeting_settings;
+mod m20261016_000049_mc_modpack_metadata;
+mod m20261016_000050_game_servers;
+mod m20261016_000051_wordle;
+mod m20261016_000052_user_timezones;
+mod m20261016_000053_channel_activity;
+mod m20261016_000054_game_nights;
+mod m20261016_000055_voice_announcements;
+mod m20261016_000056_guild_media;
+mod m20261016_000057_coinflip_settings;
+mod m20261016_000058_roll_history;
+mod m20261016_000059_character_sheets;
+mod m20261016_000060_asset_message_ids;
+mod m20261016_000061_mc_server_id;
+mod m20261016_000062_raid_lockdown;
+mod m20261016_000063_confession_author_hash;
 
 pub struct Migrator;
 
@@ -11,6 +75,69 @@ impl MigratorTrait for Migrator {
         vec![
             Box::new(m20220101_000001_initial::Migration),
             Box::new(m20260115_073352_rich_welcome_channel_configuration::Migration),
+            Box::new(m20261016_000001_emoji_stats::Migration),
+            Box::new(m20261016_000002_raid_protection::Migration),
+            Box::new(m20261016_000003_member_notification_variants::Migration),
+            Box::new(m20261016_000004_lyrics_cache::Migration),
+            Box::new(m20261016_000005_retention_policy::Migration),
+            Box::new(m20261016_000006_aliases::Migration),
+            Box::new(m20261016_000007_asset_storage::Migration),
+            Box::new(m20261016_000008_auto_response_channels::Migration),
+            Box::new(m20261016_000009_nickname_filter::Migration),
+            Box::new(m20261016_000010_play_history::Migration),
+            Box::new(m20261016_000011_voice_limits::Migration),
+            Box::new(m20261016_000012_mc_server_owner::Migration),
+            Box::new(m20261016_000013_mc_server_samples::Migration),
+            Box::new(m20261016_000014_blocked_user::Migration),
+            Box::new(m20261016_000015_scheduled_announcement::Migration),
+            Box::new(m20261016_000016_definitions::Migration),
+            Box::new(m20261016_000017_bot_member_settings::Migration),
+            Box::new(m20261016_000018_auto_response_toggle::Migration),
+            Box::new(m20261016_000019_voice_sessions::Migration),
+            Box::new(m20261016_000020_guild_branding::Migration),
+            Box::new(m20261016_000021_confessions::Migration),
+            Box::new(m20261016_000022_game_sessions::Migration),
+            Box::new(m20261016_000023_gallery_channels::Migration),
+            Box::new(m20261016_000024_unfurl_domains::Migration),
+            Box::new(m20261016_000025_temp_roles::Migration),
+            Box::new(m20261016_000026_color_roles::Migration),
+            Box::new(m20261016_000027_leave_staff_mode::Migration),
+            Box::new(m20261016_000028_recent_joins::Migration),
+            Box::new(m20261016_000029_known_members::Migration),
+            Box::new(m20261016_000030_ephemeral_settings::Migration),
+            Box::new(m20261016_000031_ban_sync::Migration),
+            Box::new(m20261016_000032_snipe_settings::Migration),
+            Box::new(m20261016_000033_ai_settings::Migration),
+            Box::new(m20261016_000034_mod_digest::Migration),
+            Box::new(m20261016_000035_playlists::Migration),
+            Box::new(m20261016_000036_mc_status_channel::Migration),
+            Box::new(m20261016_000037_webhooks::Migration),
+            Box::new(m20261016_000038_bot_settings::Migration),
+            Box::new(m20261016_000039_screening::Migration),
+            Box::new(m20261016_000040_name_history::Migration),
+            Box::new(m20261016_000041_saved_embeds::Migration),
+            Box::new(m20261016_000042_auto_slowmode::Migration),
+            Box::new(m20261016_000043_movie_night::Migration),
+            Box::new(m20261016_000044_user_content_blobs::Migration),
+            Box::new(m20261016_000045_reaction_map::Migration),
+            Box::new(m20261016_000046_jobs::Migration),
+            Box::new(m20261016_000047_leader_lease::Migration),
+            Box::new(m20261016_000048_greeting_settings::Migration),
+            Box::new(m20261016_000049_mc_modpack_metadata::Migration),
+            Box::new(m20261016_000050_game_servers::Migration),
+            Box::new(m20261016_000051_wordle::Migration),
+            Box::new(m20261016_000052_user_timezones::Migration),
+            Box::new(m20261016_000053_channel_activity::Migration),
+            Box::new(m20261016_000054_game_nights::Migration),
+            Box::new(m20261016_000055_voice_announcements::Migration),
+            Box::new(m20261016_000056_guild_media::Migration),
+            Box::new(m20261016_000057_coinflip_settings::Migration),
+            Box::new(m20261016_000058_roll_history::Migration),
+            Box::new(m20261016_000059_character_sheets::Migration),
+            Box::new(m20261016_000060_asset_message_ids::Migration),
+            Box::new(m20261016_000061_mc_server_id::Migration),
+            Box::new(m20261016_000062_raid_lockdown::Migration),
+            Box::new(m20261016_000063_confession_author_hash::Migration),
         ]
     }
 }
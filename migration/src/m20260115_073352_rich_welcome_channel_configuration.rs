@@ -1,6 +1,6 @@
 use sea_orm_migration::{prelude::*, schema::*};
 
-use crate::m20220101_000001_initial;
+use crate::m20220101_000001_initial::WelcomeChannel;
 
 #[derive(DeriveMigrationName)]
 pub struct Migration;
@@ -89,14 +89,6 @@ impl MigrationTrait for Migration {
             )
             .await?;
 
-        manager
-            .drop_table(
-                Table::drop()
-                    .table(m20220101_000001_initial::WelcomeChannel::Table)
-                    .to_owned(),
-            )
-            .await?;
-
         manager
             .create_table(
                 Table::create()
@@ -125,32 +117,90 @@ impl MigrationTrait for Migration {
                     .to_owned(),
             )
             .await?;
+
+        // The welcome channel was used for both join and leave messages
+        for join in [true, false] {
+            manager
+                .exec_stmt(
+                    Query::insert()
+                        .into_table(MemberNotificationChannel::Table)
+                        .columns([
+                            MemberNotificationChannel::GuildId,
+                            MemberNotificationChannel::Join,
+                            MemberNotificationChannel::ChannelId,
+                        ])
+                        .select_from(
+                            Query::select()
+                                .column(WelcomeChannel::GuildId)
+                                .expr(Expr::val(join))
+                                .column(WelcomeChannel::ChannelId)
+                                .from(WelcomeChannel::Table)
+                                .to_owned(),
+                        )
+                        .map_err(|e| DbErr::Migration(e.to_string()))?
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .drop_table(Table::drop().table(WelcomeChannel::Table).to_owned())
+            .await?;
         Ok(())
     }
 
     async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
         manager
-            .drop_table(
-                Table::drop()
-                    .table(MemberNotificationMessage::Table)
+            .create_table(
+                Table::create()
+                    .table(WelcomeChannel::Table)
+                    .col(string(WelcomeChannel::GuildId).primary_key())
+                    .col(string(WelcomeChannel::ChannelId))
                     .to_owned(),
             )
             .await?;
 
+        // Guilds keep their join channel, or their leave channel if they only configured that one
+        for join in [true, false] {
+            manager
+                .exec_stmt(
+                    Query::insert()
+                        .into_table(WelcomeChannel::Table)
+                        .columns([WelcomeChannel::GuildId, WelcomeChannel::ChannelId])
+                        .select_from(
+                            Query::select()
+                                .column(MemberNotificationChannel::GuildId)
+                                .column(MemberNotificationChannel::ChannelId)
+                                .from(MemberNotificationChannel::Table)
+                                .and_where(Expr::col(MemberNotificationChannel::Join).eq(join))
+                                .and_where(
+                                    Expr::col(MemberNotificationChannel::GuildId).not_in_subquery(
+                                        Query::select()
+                                            .column(WelcomeChannel::GuildId)
+                                            .from(WelcomeChannel::Table)
+                                            .to_owned(),
+                                    ),
+                                )
+                                .to_owned(),
+                        )
+                        .map_err(|e| DbErr::Migration(e.to_string()))?
+                        .to_owned(),
+                )
+                .await?;
+        }
+
         manager
             .drop_table(
                 Table::drop()
-                    .table(MemberNotificationChannel::Table)
+                    .table(MemberNotificationMessage::Table)
                     .to_owned(),
             )
             .await?;
 
         manager
-            .create_table(
-                Table::create()
-                    .table(m20220101_000001_initial::WelcomeChannel::Table)
-                    .col(string(m20220101_000001_initial::WelcomeChannel::GuildId).primary_key())
-                    .col(string(m20220101_000001_initial::WelcomeChannel::ChannelId))
+            .drop_table(
+                Table::drop()
+                    .table(MemberNotificationChannel::Table)
                     .to_owned(),
             )
             .await?;
@@ -184,6 +234,5 @@ enum MemberNotificationChannel {
     Table,
     GuildId, // Primary Key
     Join,    // Primary Key
-    #[allow(unused)]
     ChannelId,
 }
@@ -0,0 +1,45 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(EmojiStats::Table)
+                    .col(string(EmojiStats::GuildId).not_null())
+                    .col(string(EmojiStats::EmojiId).not_null())
+                    .col(big_integer(EmojiStats::Day).not_null())
+                    .col(integer(EmojiStats::Uses).not_null().default(0))
+                    .primary_key(
+                        IndexCreateStatement::new()
+                            .col(EmojiStats::GuildId)
+                            .col(EmojiStats::EmojiId)
+                            .col(EmojiStats::Day)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(EmojiStats::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum EmojiStats {
+    Table,
+    GuildId, // Primary Key
+    EmojiId, // Primary Key
+    Day,     // Primary Key, days since unix epoch
+    Uses,
+}
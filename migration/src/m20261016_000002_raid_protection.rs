@@ -0,0 +1,82 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RaidProtection::Table)
+                    .col(string(RaidProtection::GuildId).primary_key())
+                    .col(boolean(RaidProtection::Enabled).not_null().default(false))
+                    .col(
+                        integer(RaidProtection::JoinThreshold)
+                            .not_null()
+                            .default(10),
+                    )
+                    .col(
+                        integer(RaidProtection::WindowSeconds)
+                            .not_null()
+                            .default(60),
+                    )
+                    .col(
+                        integer(RaidProtection::AccountAgeHours)
+                            .not_null()
+                            .default(24),
+                    )
+                    .col(
+                        integer(RaidProtection::NewAccountThreshold)
+                            .not_null()
+                            .default(5),
+                    )
+                    .col(
+                        boolean(RaidProtection::RaiseVerification)
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(
+                        boolean(RaidProtection::PauseDefaultRoles)
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(
+                        string(RaidProtection::AlertChannelId)
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        integer(RaidProtection::LockdownMinutes)
+                            .not_null()
+                            .default(30),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RaidProtection::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum RaidProtection {
+    Table,
+    GuildId, // Primary Key
+    Enabled,
+    JoinThreshold,
+    WindowSeconds,
+    AccountAgeHours,
+    NewAccountThreshold,
+    RaiseVerification,
+    PauseDefaultRoles,
+    AlertChannelId,
+    LockdownMinutes,
+}
@@ -0,0 +1,221 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Columns shared between the old and new member notification message tables.
+const SHARED_COLUMNS: [MemberNotificationMessage; 15] = [
+    MemberNotificationMessage::GuildId,
+    MemberNotificationMessage::Join,
+    MemberNotificationMessage::Content,
+    MemberNotificationMessage::Title,
+    MemberNotificationMessage::Description,
+    MemberNotificationMessage::ThumbnailIsFile,
+    MemberNotificationMessage::ThumbnailUrl,
+    MemberNotificationMessage::ImageIsFile,
+    MemberNotificationMessage::ImageUrl,
+    MemberNotificationMessage::Author,
+    MemberNotificationMessage::AuthorIconIsFile,
+    MemberNotificationMessage::AuthorIconUrl,
+    MemberNotificationMessage::Footer,
+    MemberNotificationMessage::FooterIconIsFile,
+    MemberNotificationMessage::FooterIconUrl,
+];
+
+/// Sqlite cannot alter the primary key of an existing table, so the table is rebuilt under a temporary name, then
+/// swapped in place of the original.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(create_message_table(
+                MemberNotificationMessage::TableNew,
+                true,
+            ))
+            .await?;
+
+        let mut columns = SHARED_COLUMNS.to_vec();
+        columns.push(MemberNotificationMessage::Variant);
+        let mut select = Query::select();
+        select
+            .columns(SHARED_COLUMNS)
+            .expr(Expr::val(0))
+            .from(MemberNotificationMessage::Table);
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(MemberNotificationMessage::TableNew)
+                    .columns(columns)
+                    .select_from(select)
+                    .map_err(|e| DbErr::Migration(e.to_string()))?
+                    .to_owned(),
+            )
+            .await?;
+
+        swap_tables(manager).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(create_message_table(
+                MemberNotificationMessage::TableNew,
+                false,
+            ))
+            .await?;
+
+        // Only the primary variant can be preserved by the single-message schema.
+        let mut select = Query::select();
+        select
+            .columns(SHARED_COLUMNS)
+            .from(MemberNotificationMessage::Table)
+            .and_where(Expr::col(MemberNotificationMessage::Variant).eq(0));
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(MemberNotificationMessage::TableNew)
+                    .columns(SHARED_COLUMNS)
+                    .select_from(select)
+                    .map_err(|e| DbErr::Migration(e.to_string()))?
+                    .to_owned(),
+            )
+            .await?;
+
+        swap_tables(manager).await
+    }
+}
+
+async fn swap_tables(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    manager
+        .drop_table(
+            Table::drop()
+                .table(MemberNotificationMessage::Table)
+                .to_owned(),
+        )
+        .await?;
+    manager
+        .rename_table(
+            Table::rename()
+                .table(
+                    MemberNotificationMessage::TableNew,
+                    MemberNotificationMessage::Table,
+                )
+                .to_owned(),
+        )
+        .await
+}
+
+fn create_message_table(
+    table: MemberNotificationMessage,
+    with_variant: bool,
+) -> TableCreateStatement {
+    let mut statement = Table::create();
+    statement
+        .table(table)
+        .col(string(MemberNotificationMessage::GuildId).not_null())
+        .col(boolean(MemberNotificationMessage::Join).not_null());
+    if with_variant {
+        statement.col(
+            integer(MemberNotificationMessage::Variant)
+                .not_null()
+                .default(0),
+        );
+    }
+    statement
+        .col(
+            text(MemberNotificationMessage::Content)
+                .not_null()
+                .default(""),
+        )
+        .col(
+            text(MemberNotificationMessage::Title)
+                .not_null()
+                .default(""),
+        )
+        .col(
+            text(MemberNotificationMessage::Description)
+                .not_null()
+                .default(""),
+        )
+        .col(
+            boolean(MemberNotificationMessage::ThumbnailIsFile)
+                .not_null()
+                .default(false),
+        )
+        .col(
+            text(MemberNotificationMessage::ThumbnailUrl)
+                .not_null()
+                .default(""),
+        )
+        .col(
+            boolean(MemberNotificationMessage::ImageIsFile)
+                .not_null()
+                .default(false),
+        )
+        .col(
+            text(MemberNotificationMessage::ImageUrl)
+                .not_null()
+                .default(""),
+        )
+        .col(
+            text(MemberNotificationMessage::Author)
+                .not_null()
+                .default(""),
+        )
+        .col(
+            boolean(MemberNotificationMessage::AuthorIconIsFile)
+                .not_null()
+                .default(false),
+        )
+        .col(
+            text(MemberNotificationMessage::AuthorIconUrl)
+                .not_null()
+                .default(""),
+        )
+        .col(
+            text(MemberNotificationMessage::Footer)
+                .not_null()
+                .default(""),
+        )
+        .col(
+            boolean(MemberNotificationMessage::FooterIconIsFile)
+                .not_null()
+                .default(false),
+        )
+        .col(
+            text(MemberNotificationMessage::FooterIconUrl)
+                .not_null()
+                .default(""),
+        );
+
+    let mut primary_key = IndexCreateStatement::new();
+    primary_key
+        .col(MemberNotificationMessage::GuildId)
+        .col(MemberNotificationMessage::Join);
+    if with_variant {
+        primary_key.col(MemberNotificationMessage::Variant);
+    }
+    statement.primary_key(primary_key.unique()).to_owned()
+}
+
+#[derive(DeriveIden, Clone, Copy)]
+enum MemberNotificationMessage {
+    Table,
+    #[sea_orm(iden = "member_notification_message_new")]
+    TableNew,
+    GuildId, // Primary Key
+    Join,    // Primary Key
+    Variant, // Primary Key
+    Content,
+    Title,
+    Description,
+    ThumbnailIsFile,
+    ThumbnailUrl,
+    ImageIsFile,
+    ImageUrl,
+    Author,
+    AuthorIconIsFile,
+    AuthorIconUrl,
+    Footer,
+    FooterIconIsFile,
+    FooterIconUrl,
+}
@@ -0,0 +1,42 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(LyricsCache::Table)
+                    .col(text(LyricsCache::Artist).not_null())
+                    .col(text(LyricsCache::Title).not_null())
+                    .col(text(LyricsCache::Lyrics).not_null())
+                    .primary_key(
+                        IndexCreateStatement::new()
+                            .col(LyricsCache::Artist)
+                            .col(LyricsCache::Title)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LyricsCache::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum LyricsCache {
+    Table,
+    Artist, // Primary Key
+    Title,  // Primary Key
+    Lyrics,
+}
@@ -0,0 +1,42 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Aliases::Table)
+                    .col(string(Aliases::GuildId).not_null())
+                    .col(string(Aliases::Alias).not_null())
+                    .col(string(Aliases::Command).not_null())
+                    .primary_key(
+                        IndexCreateStatement::new()
+                            .col(Aliases::GuildId)
+                            .col(Aliases::Alias)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Aliases::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Aliases {
+    Table,
+    GuildId, // Primary Key
+    Alias,   // Primary Key
+    Command,
+}
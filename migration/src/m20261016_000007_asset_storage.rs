@@ -0,0 +1,61 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AssetStorageChannel::Table)
+                    .col(string(AssetStorageChannel::GuildId).primary_key())
+                    .col(string(AssetStorageChannel::ChannelId).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserContentAsset::Table)
+                    .col(string(UserContentAsset::GuildId).not_null())
+                    .col(string(UserContentAsset::Filename).not_null())
+                    .col(text(UserContentAsset::Url).not_null())
+                    .primary_key(
+                        IndexCreateStatement::new()
+                            .col(UserContentAsset::GuildId)
+                            .col(UserContentAsset::Filename)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserContentAsset::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(AssetStorageChannel::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum AssetStorageChannel {
+    Table,
+    GuildId, // Primary Key
+    ChannelId,
+}
+
+#[derive(DeriveIden)]
+enum UserContentAsset {
+    Table,
+    GuildId,  // Primary Key
+    Filename, // Primary Key
+    Url,
+}
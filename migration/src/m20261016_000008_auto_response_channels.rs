@@ -0,0 +1,42 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AutoResponseChannel::Table)
+                    .col(string(AutoResponseChannel::GuildId).not_null())
+                    .col(string(AutoResponseChannel::ChannelId).not_null())
+                    .col(boolean(AutoResponseChannel::Allow).not_null())
+                    .primary_key(
+                        IndexCreateStatement::new()
+                            .col(AutoResponseChannel::GuildId)
+                            .col(AutoResponseChannel::ChannelId)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AutoResponseChannel::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum AutoResponseChannel {
+    Table,
+    GuildId,   // Primary Key
+    ChannelId, // Primary Key
+    Allow,
+}
@@ -0,0 +1,46 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(NicknameFilter::Table)
+                    .col(string(NicknameFilter::GuildId).primary_key())
+                    .col(boolean(NicknameFilter::Enabled).not_null().default(false))
+                    .col(boolean(NicknameFilter::Dehoist).not_null().default(true))
+                    .col(text(NicknameFilter::DisallowedWords).not_null().default(""))
+                    .col(
+                        string(NicknameFilter::Replacement)
+                            .not_null()
+                            .default("Member {id}"),
+                    )
+                    .col(string(NicknameFilter::LogChannelId).not_null().default(""))
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(NicknameFilter::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum NicknameFilter {
+    Table,
+    GuildId, // Primary Key
+    Enabled,
+    Dehoist,
+    DisallowedWords,
+    Replacement,
+    LogChannelId,
+}
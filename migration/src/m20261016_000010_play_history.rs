@@ -0,0 +1,51 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PlayHistory::Table)
+                    .col(pk_auto(PlayHistory::Id))
+                    .col(string(PlayHistory::GuildId).not_null())
+                    .col(string(PlayHistory::UserId).not_null())
+                    .col(text(PlayHistory::Title).not_null())
+                    .col(text(PlayHistory::Url).not_null())
+                    .col(big_integer(PlayHistory::PlayedAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_play_history_guild_id")
+                    .table(PlayHistory::Table)
+                    .col(PlayHistory::GuildId)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PlayHistory::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum PlayHistory {
+    Table,
+    Id, // Primary Key
+    GuildId,
+    UserId,
+    Title,
+    Url,      // Empty for tracks that can't be replayed
+    PlayedAt, // Unix timestamp in seconds
+}
@@ -0,0 +1,46 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(VoiceLimits::Table)
+                    .col(string(VoiceLimits::GuildId).primary_key())
+                    .col(
+                        integer(VoiceLimits::MaxDurationSeconds)
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(text(VoiceLimits::BlockedDomains).not_null().default(""))
+                    .col(
+                        boolean(VoiceLimits::AllowLivestreams)
+                            .not_null()
+                            .default(true),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(VoiceLimits::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum VoiceLimits {
+    Table,
+    GuildId,            // Primary Key
+    MaxDurationSeconds, // 0 is unlimited
+    BlockedDomains,     // Comma separated
+    AllowLivestreams,
+}
@@ -0,0 +1,56 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(McServer::Table)
+                    .add_column(string(McServer::OwnerId).not_null().default(""))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(McSettings::Table)
+                    .col(string(McSettings::GuildId).primary_key())
+                    .col(string(McSettings::ManagerRoleId).not_null().default(""))
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(McSettings::Table).to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(McServer::Table)
+                    .drop_column(McServer::OwnerId)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum McServer {
+    Table,
+    OwnerId, // Empty for servers added before ownership was recorded
+}
+
+#[derive(DeriveIden)]
+enum McSettings {
+    Table,
+    GuildId, // Primary Key
+    ManagerRoleId,
+}
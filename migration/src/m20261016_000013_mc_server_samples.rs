@@ -0,0 +1,55 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(McServerSamples::Table)
+                    .col(pk_auto(McServerSamples::Id))
+                    .col(string(McServerSamples::GuildId).not_null())
+                    .col(text(McServerSamples::Name).not_null())
+                    .col(big_integer(McServerSamples::SampledAt).not_null())
+                    .col(boolean(McServerSamples::Online).not_null())
+                    .col(integer(McServerSamples::PlayersOnline).not_null())
+                    .col(integer(McServerSamples::PlayersMax).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_mc_server_samples_server")
+                    .table(McServerSamples::Table)
+                    .col(McServerSamples::GuildId)
+                    .col(McServerSamples::Name)
+                    .col(McServerSamples::SampledAt)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(McServerSamples::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum McServerSamples {
+    Table,
+    Id, // Primary Key
+    GuildId,
+    Name,
+    SampledAt, // Unix timestamp in seconds
+    Online,
+    PlayersOnline, // Zero while offline
+    PlayersMax,    // Zero while offline
+}
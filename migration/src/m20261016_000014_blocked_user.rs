@@ -0,0 +1,36 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BlockedUser::Table)
+                    .col(string(BlockedUser::UserId).primary_key())
+                    .col(text(BlockedUser::Reason).not_null().default(""))
+                    .col(big_integer(BlockedUser::BlockedAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BlockedUser::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum BlockedUser {
+    Table,
+    UserId, // Primary Key
+    Reason,
+    BlockedAt, // Unix timestamp in seconds
+}
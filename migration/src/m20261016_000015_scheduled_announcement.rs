@@ -0,0 +1,53 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ScheduledAnnouncement::Table)
+                    .col(pk_auto(ScheduledAnnouncement::Id))
+                    .col(string(ScheduledAnnouncement::GuildId).not_null())
+                    .col(string(ScheduledAnnouncement::ChannelId).not_null())
+                    .col(string(ScheduledAnnouncement::AuthorId).not_null())
+                    .col(text(ScheduledAnnouncement::Message).not_null())
+                    .col(string(ScheduledAnnouncement::RoleId).not_null().default(""))
+                    .col(big_integer(ScheduledAnnouncement::SendAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_scheduled_announcement_send_at")
+                    .table(ScheduledAnnouncement::Table)
+                    .col(ScheduledAnnouncement::SendAt)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ScheduledAnnouncement::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ScheduledAnnouncement {
+    Table,
+    Id, // Primary Key
+    GuildId,
+    ChannelId,
+    AuthorId,
+    Message,
+    RoleId, // Empty if no role is pinged
+    SendAt, // Unix timestamp in seconds
+}
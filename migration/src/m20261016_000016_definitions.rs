@@ -0,0 +1,63 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(DefinitionCache::Table)
+                    .col(string(DefinitionCache::Provider).not_null())
+                    .col(text(DefinitionCache::Term).not_null())
+                    .col(text(DefinitionCache::Definitions).not_null())
+                    .col(big_integer(DefinitionCache::CachedAt).not_null())
+                    .primary_key(
+                        IndexCreateStatement::new()
+                            .col(DefinitionCache::Provider)
+                            .col(DefinitionCache::Term)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(DefineSettings::Table)
+                    .col(string(DefineSettings::GuildId).primary_key())
+                    .col(boolean(DefineSettings::Sfw).not_null().default(true))
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(DefinitionCache::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(DefineSettings::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum DefinitionCache {
+    Table,
+    Provider,    // Primary Key
+    Term,        // Primary Key
+    Definitions, // JSON array of definitions
+    CachedAt,    // Unix timestamp in seconds
+}
+
+#[derive(DeriveIden)]
+enum DefineSettings {
+    Table,
+    GuildId, // Primary Key
+    Sfw,     // Hides providers that aren't safe for work
+}
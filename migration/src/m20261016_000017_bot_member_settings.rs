@@ -0,0 +1,40 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BotMemberSettings::Table)
+                    .col(string(BotMemberSettings::GuildId).primary_key())
+                    .col(
+                        boolean(BotMemberSettings::Suppress)
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(string(BotMemberSettings::ChannelId).not_null().default(""))
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BotMemberSettings::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum BotMemberSettings {
+    Table,
+    GuildId,   // Primary Key
+    Suppress,  // Skip notifications and default roles for bot accounts
+    ChannelId, // Channel bot notifications are sent to instead, empty to use the member notification channel
+}
@@ -0,0 +1,42 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AutoResponseToggle::Table)
+                    .col(string(AutoResponseToggle::GuildId).not_null())
+                    .col(string(AutoResponseToggle::Responder).not_null())
+                    .col(boolean(AutoResponseToggle::Enabled).not_null())
+                    .primary_key(
+                        IndexCreateStatement::new()
+                            .col(AutoResponseToggle::GuildId)
+                            .col(AutoResponseToggle::Responder)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AutoResponseToggle::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum AutoResponseToggle {
+    Table,
+    GuildId,   // Primary Key
+    Responder, // Primary Key, name of the responder
+    Enabled,
+}
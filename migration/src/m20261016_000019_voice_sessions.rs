@@ -0,0 +1,52 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(VoiceSessions::Table)
+                    .col(pk_auto(VoiceSessions::Id))
+                    .col(string(VoiceSessions::GuildId).not_null())
+                    .col(string(VoiceSessions::UserId).not_null())
+                    .col(string(VoiceSessions::ChannelId).not_null())
+                    .col(big_integer(VoiceSessions::JoinedAt).not_null())
+                    .col(big_integer_null(VoiceSessions::LeftAt))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_voice_sessions_guild_id_user_id")
+                    .table(VoiceSessions::Table)
+                    .col(VoiceSessions::GuildId)
+                    .col(VoiceSessions::UserId)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(VoiceSessions::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum VoiceSessions {
+    Table,
+    Id, // Primary Key
+    GuildId,
+    UserId,
+    ChannelId,
+    JoinedAt, // Unix timestamp in seconds
+    LeftAt,   // Unix timestamp in seconds, null while the user is still in the channel
+}
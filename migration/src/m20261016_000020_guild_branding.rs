@@ -0,0 +1,38 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(GuildBranding::Table)
+                    .col(string(GuildBranding::GuildId).primary_key())
+                    .col(big_integer_null(GuildBranding::AccentColor))
+                    .col(string_null(GuildBranding::Footer))
+                    .col(string_null(GuildBranding::Nickname))
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GuildBranding::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum GuildBranding {
+    Table,
+    GuildId,     // Primary Key
+    AccentColor, // RGB color of embeds, null for the default color
+    Footer,      // Footer text of embeds, null for no footer
+    Nickname,    // Nickname of the bot on the guild, null to keep its name
+}
@@ -0,0 +1,63 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ConfessionSettings::Table)
+                    .col(string(ConfessionSettings::GuildId).primary_key())
+                    .col(string(ConfessionSettings::ChannelId).not_null())
+                    .col(string_null(ConfessionSettings::ReviewChannelId))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(Confessions::Table)
+                    .col(pk_auto(Confessions::Id))
+                    .col(string(Confessions::GuildId).not_null())
+                    .col(blob(Confessions::Author).not_null())
+                    .col(text(Confessions::Content).not_null())
+                    .col(string(Confessions::Status).not_null())
+                    .col(big_integer(Confessions::CreatedAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Confessions::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(ConfessionSettings::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ConfessionSettings {
+    Table,
+    GuildId,         // Primary Key
+    ChannelId,       // Channel confessions are posted in
+    ReviewChannelId, // Staff channel confessions are reviewed in before posting, null to post immediately
+}
+
+#[derive(DeriveIden)]
+enum Confessions {
+    Table,
+    Id, // Primary Key, shown as the confession number
+    GuildId,
+    Author, // Encrypted user id of the author, only readable with the confession key
+    Content,
+    Status,    // pending, approved or denied
+    CreatedAt, // Unix timestamp in seconds
+}
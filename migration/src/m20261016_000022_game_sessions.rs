@@ -0,0 +1,40 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(GameSessions::Table)
+                    .col(string(GameSessions::ChannelId).primary_key())
+                    .col(string(GameSessions::GuildId).not_null())
+                    .col(string(GameSessions::StartedBy).not_null())
+                    .col(text(GameSessions::State).not_null())
+                    .col(big_integer(GameSessions::StartedAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GameSessions::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum GameSessions {
+    Table,
+    ChannelId, // Primary Key, a channel runs at most one game at a time
+    GuildId,
+    StartedBy, // User id of the member who started the game
+    State,     // JSON serialized state of the game
+    StartedAt, // Unix timestamp in seconds
+}
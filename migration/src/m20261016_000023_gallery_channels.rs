@@ -0,0 +1,40 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(GalleryChannel::Table)
+                    .col(string(GalleryChannel::GuildId).not_null())
+                    .col(string(GalleryChannel::ChannelId).not_null())
+                    .primary_key(
+                        IndexCreateStatement::new()
+                            .col(GalleryChannel::GuildId)
+                            .col(GalleryChannel::ChannelId)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GalleryChannel::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum GalleryChannel {
+    Table,
+    GuildId,   // Primary Key
+    ChannelId, // Primary Key, a channel where every media post gets a discussion thread
+}
@@ -0,0 +1,40 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UnfurlDomain::Table)
+                    .col(string(UnfurlDomain::GuildId).not_null())
+                    .col(string(UnfurlDomain::Domain).not_null())
+                    .primary_key(
+                        IndexCreateStatement::new()
+                            .col(UnfurlDomain::GuildId)
+                            .col(UnfurlDomain::Domain)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UnfurlDomain::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum UnfurlDomain {
+    Table,
+    GuildId, // Primary Key
+    Domain,  // Primary Key, a domain whose links get a preview embed, such as `example.com`
+}
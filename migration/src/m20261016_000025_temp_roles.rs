@@ -0,0 +1,77 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ModLogChannel::Table)
+                    .col(string(ModLogChannel::GuildId).primary_key())
+                    .col(string(ModLogChannel::ChannelId).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(TempRoles::Table)
+                    .col(string(TempRoles::GuildId).not_null())
+                    .col(string(TempRoles::UserId).not_null())
+                    .col(string(TempRoles::RoleId).not_null())
+                    .col(string(TempRoles::AssignedBy).not_null())
+                    .col(string_null(TempRoles::Reason))
+                    .col(big_integer(TempRoles::ExpiresAt).not_null())
+                    .primary_key(
+                        IndexCreateStatement::new()
+                            .col(TempRoles::GuildId)
+                            .col(TempRoles::UserId)
+                            .col(TempRoles::RoleId)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_temp_roles_expires_at")
+                    .table(TempRoles::Table)
+                    .col(TempRoles::ExpiresAt)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TempRoles::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(ModLogChannel::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ModLogChannel {
+    Table,
+    GuildId,   // Primary Key
+    ChannelId, // Channel moderation events are logged to
+}
+
+#[derive(DeriveIden)]
+enum TempRoles {
+    Table,
+    GuildId, // Primary Key
+    UserId,  // Primary Key
+    RoleId,  // Primary Key
+    AssignedBy,
+    Reason,
+    ExpiresAt, // Unix timestamp in seconds the role is removed at
+}
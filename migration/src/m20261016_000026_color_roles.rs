@@ -0,0 +1,63 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ColorRoles::Table)
+                    .col(string(ColorRoles::GuildId).not_null())
+                    .col(string(ColorRoles::UserId).not_null())
+                    .col(string(ColorRoles::RoleId).not_null())
+                    .col(big_integer(ColorRoles::Color).not_null())
+                    .primary_key(
+                        IndexCreateStatement::new()
+                            .col(ColorRoles::GuildId)
+                            .col(ColorRoles::UserId)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(ColorRoleSettings::Table)
+                    .col(string(ColorRoleSettings::GuildId).primary_key())
+                    .col(integer(ColorRoleSettings::MaxRoles).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ColorRoleSettings::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(ColorRoles::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ColorRoles {
+    Table,
+    GuildId, // Primary Key
+    UserId,  // Primary Key, a member wears at most one color role
+    RoleId,  // Color role created by the bot, shared by every member with the same color
+    Color,   // RGB value of the role color
+}
+
+#[derive(DeriveIden)]
+enum ColorRoleSettings {
+    Table,
+    GuildId,  // Primary Key
+    MaxRoles, // Maximum number of distinct color roles on the guild, 0 disables /colorrole
+}
@@ -0,0 +1,36 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(LeaveNotificationSettings::Table)
+                    .col(string(LeaveNotificationSettings::GuildId).primary_key())
+                    .col(boolean(LeaveNotificationSettings::StaffMode).not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(LeaveNotificationSettings::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum LeaveNotificationSettings {
+    Table,
+    GuildId,   // Primary Key
+    StaffMode, // Leave notifications of kicked or banned members show the moderator and reason
+}
@@ -0,0 +1,72 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RecentJoins::Table)
+                    .col(string(RecentJoins::GuildId).not_null())
+                    .col(string(RecentJoins::UserId).not_null())
+                    .col(big_integer(RecentJoins::JoinedAt).not_null())
+                    .primary_key(
+                        IndexCreateStatement::new()
+                            .col(RecentJoins::GuildId)
+                            .col(RecentJoins::UserId)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_recent_joins_joined_at")
+                    .table(RecentJoins::Table)
+                    .col(RecentJoins::JoinedAt)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(RejoinSettings::Table)
+                    .col(string(RejoinSettings::GuildId).primary_key())
+                    .col(integer(RejoinSettings::WindowHours).not_null())
+                    .col(boolean(RejoinSettings::WelcomeBack).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RejoinSettings::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(RecentJoins::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum RecentJoins {
+    Table,
+    GuildId,  // Primary Key
+    UserId,   // Primary Key
+    JoinedAt, // Unix timestamp in seconds of the member's latest join
+}
+
+#[derive(DeriveIden)]
+enum RejoinSettings {
+    Table,
+    GuildId,     // Primary Key
+    WindowHours, // Rejoins within this many hours of the previous join don't get the welcome message, 0 disables
+    WelcomeBack, // Rejoins get the welcome back variant instead of no message
+}
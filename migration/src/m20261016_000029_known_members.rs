@@ -0,0 +1,79 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Joins are kept to recognize returning members, not only recent rejoins
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_recent_joins_joined_at")
+                    .table(RecentJoins::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .rename_table(
+                Table::rename()
+                    .table(RecentJoins::Table, KnownMembers::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_known_members_joined_at")
+                    .table(KnownMembers::Table)
+                    .col(KnownMembers::JoinedAt)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_known_members_joined_at")
+                    .table(KnownMembers::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .rename_table(
+                Table::rename()
+                    .table(KnownMembers::Table, RecentJoins::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_recent_joins_joined_at")
+                    .table(RecentJoins::Table)
+                    .col(KnownMembers::JoinedAt)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum RecentJoins {
+    Table,
+}
+
+#[derive(DeriveIden)]
+enum KnownMembers {
+    Table,
+    #[allow(unused)]
+    GuildId, // Primary Key
+    #[allow(unused)]
+    UserId, // Primary Key
+    JoinedAt, // Unix timestamp in seconds of the member's latest join
+}
@@ -0,0 +1,40 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(EphemeralSettings::Table)
+                    .col(string(EphemeralSettings::GuildId).not_null())
+                    .col(string(EphemeralSettings::Command).not_null())
+                    .col(boolean(EphemeralSettings::Ephemeral).not_null())
+                    .primary_key(
+                        IndexCreateStatement::new()
+                            .col(EphemeralSettings::GuildId)
+                            .col(EphemeralSettings::Command)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(EphemeralSettings::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum EphemeralSettings {
+    Table,
+    GuildId,   // Primary Key
+    Command,   // Primary Key, qualified name of the command, e.g. `mc status`
+    Ephemeral, // Replies of the command are always (or never) visible to the invoking user only
+}
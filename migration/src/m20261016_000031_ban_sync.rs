@@ -0,0 +1,92 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BanSyncMember::Table)
+                    .col(string(BanSyncMember::GuildId).primary_key())
+                    .col(string(BanSyncMember::GroupKey).not_null())
+                    .col(boolean(BanSyncMember::AutoApply).not_null())
+                    .col(string_null(BanSyncMember::ReviewChannelId))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_ban_sync_member_group_key")
+                    .table(BanSyncMember::Table)
+                    .col(BanSyncMember::GroupKey)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(BanSync::Table)
+                    .col(pk_auto(BanSync::Id))
+                    .col(string(BanSync::SourceGuildId).not_null())
+                    .col(string(BanSync::TargetGuildId).not_null())
+                    .col(string(BanSync::UserId).not_null())
+                    .col(string_null(BanSync::ModeratorId))
+                    .col(string_null(BanSync::Reason))
+                    .col(string(BanSync::Status).not_null())
+                    .col(big_integer(BanSync::CreatedAt).not_null())
+                    .col(string_null(BanSync::ResolvedBy))
+                    .col(big_integer_null(BanSync::ResolvedAt))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_ban_sync_target_user")
+                    .table(BanSync::Table)
+                    .col(BanSync::TargetGuildId)
+                    .col(BanSync::UserId)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BanSync::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(BanSyncMember::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum BanSyncMember {
+    Table,
+    GuildId,         // Primary Key
+    GroupKey, // SHA-256 of the shared secret of the sync group, so the secret itself is never stored
+    AutoApply, // Bans of partner guilds are mirrored immediately instead of proposed for approval
+    ReviewChannelId, // Staff channel ban proposals are posted in, null if bans are mirrored automatically
+}
+
+#[derive(DeriveIden)]
+enum BanSync {
+    Table,
+    Id,            // Primary Key
+    SourceGuildId, // Guild the user was banned on
+    TargetGuildId, // Partner guild the ban is mirrored to
+    UserId,
+    ModeratorId, // Moderator of the original ban, if found in the audit log
+    Reason,      // Reason of the original ban
+    Status,      // pending, applied, rejected or failed
+    CreatedAt,   // Unix timestamp in seconds
+    ResolvedBy,  // Moderator who approved or rejected the proposal, null if mirrored automatically
+    ResolvedAt,  // Unix timestamp in seconds
+}
@@ -0,0 +1,34 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SnipeSettings::Table)
+                    .col(string(SnipeSettings::GuildId).primary_key())
+                    .col(boolean(SnipeSettings::Enabled).not_null())
+                    .col(integer(SnipeSettings::RetentionMinutes).not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SnipeSettings::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum SnipeSettings {
+    Table,
+    GuildId,          // Primary Key
+    Enabled,          // Deleted and edited messages are remembered for /snipe and /editsnipe
+    RetentionMinutes, // How long deleted and edited messages are remembered
+}
@@ -0,0 +1,38 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AiSettings::Table)
+                    .col(string(AiSettings::GuildId).primary_key())
+                    .col(boolean(AiSettings::Enabled).not_null())
+                    .col(big_integer(AiSettings::DailyTokenBudget).not_null())
+                    .col(big_integer(AiSettings::TokensUsed).not_null())
+                    .col(big_integer(AiSettings::BudgetDay).not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AiSettings::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum AiSettings {
+    Table,
+    GuildId,          // Primary Key
+    Enabled,          // The bot replies with the language model when it is mentioned
+    DailyTokenBudget, // Tokens the guild may use per day
+    TokensUsed,       // Tokens used on BudgetDay
+    BudgetDay,        // Days since the unix epoch the TokensUsed were counted on
+}
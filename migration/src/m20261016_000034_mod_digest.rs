@@ -0,0 +1,69 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ModActivity::Table)
+                    .col(pk_auto(ModActivity::Id))
+                    .col(string(ModActivity::GuildId).not_null())
+                    .col(string(ModActivity::Kind).not_null())
+                    .col(big_integer(ModActivity::CreatedAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_mod_activity_guild_created_at")
+                    .table(ModActivity::Table)
+                    .col(ModActivity::GuildId)
+                    .col(ModActivity::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(ModDigestSettings::Table)
+                    .col(string(ModDigestSettings::GuildId).primary_key())
+                    .col(string(ModDigestSettings::Frequency).not_null())
+                    .col(big_integer(ModDigestSettings::LastSentAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ModDigestSettings::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(ModActivity::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ModActivity {
+    Table,
+    Id, // Primary Key
+    GuildId,
+    Kind,      // join, leave, ban, unban, nickname_filtered or raid_lockdown
+    CreatedAt, // Unix timestamp in seconds
+}
+
+#[derive(DeriveIden)]
+enum ModDigestSettings {
+    Table,
+    GuildId,    // Primary Key
+    Frequency,  // daily or weekly; guilds without a digest have no row
+    LastSentAt, // Unix timestamp in seconds, the digest covers the activity since
+}
@@ -0,0 +1,83 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserPlaylists::Table)
+                    .col(pk_auto(UserPlaylists::Id))
+                    .col(string(UserPlaylists::UserId).not_null())
+                    .col(string(UserPlaylists::Name).not_null())
+                    .col(big_integer(UserPlaylists::CreatedAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_playlists_user_name")
+                    .table(UserPlaylists::Table)
+                    .col(UserPlaylists::UserId)
+                    .col(UserPlaylists::Name)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(PlaylistTracks::Table)
+                    .col(pk_auto(PlaylistTracks::Id))
+                    .col(integer(PlaylistTracks::PlaylistId).not_null())
+                    .col(string(PlaylistTracks::Title).not_null())
+                    .col(string(PlaylistTracks::Url).not_null())
+                    .col(integer_null(PlaylistTracks::DurationSeconds))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_playlist_tracks_playlist_id")
+                    .table(PlaylistTracks::Table)
+                    .col(PlaylistTracks::PlaylistId)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PlaylistTracks::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(UserPlaylists::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserPlaylists {
+    Table,
+    Id,        // Primary Key
+    UserId,    // Owner of the playlist
+    Name,      // Unique per user
+    CreatedAt, // Unix timestamp in seconds
+}
+
+#[derive(DeriveIden)]
+enum PlaylistTracks {
+    Table,
+    Id,         // Primary Key, also the order of the tracks within their playlist
+    PlaylistId, // Id of the playlist in user_playlists
+    Title,
+    Url,             // Source the track is played from
+    DurationSeconds, // Null for livestreams and tracks without a reported duration
+}
@@ -0,0 +1,42 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(McStatusChannel::Table)
+                    .col(string(McStatusChannel::GuildId).not_null())
+                    .col(text(McStatusChannel::Name).not_null())
+                    .col(string(McStatusChannel::ChannelId).not_null())
+                    .primary_key(
+                        IndexCreateStatement::new()
+                            .col(McStatusChannel::GuildId)
+                            .col(McStatusChannel::Name)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(McStatusChannel::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum McStatusChannel {
+    Table,
+    GuildId,   // Primary Key
+    Name,      // Primary Key, name of the server in mc_server
+    ChannelId, // Voice channel whose name mirrors the status of the server
+}
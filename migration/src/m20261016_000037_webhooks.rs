@@ -0,0 +1,94 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(GuildWebhooks::Table)
+                    .col(pk_auto(GuildWebhooks::Id))
+                    .col(string(GuildWebhooks::GuildId).not_null())
+                    .col(text(GuildWebhooks::Url).not_null())
+                    .col(string(GuildWebhooks::Secret).not_null())
+                    .col(string(GuildWebhooks::Events).not_null())
+                    .col(big_integer(GuildWebhooks::CreatedAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_guild_webhooks_guild_id")
+                    .table(GuildWebhooks::Table)
+                    .col(GuildWebhooks::GuildId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(WebhookDeliveries::Table)
+                    .col(pk_auto(WebhookDeliveries::Id))
+                    .col(integer(WebhookDeliveries::WebhookId).not_null())
+                    .col(string(WebhookDeliveries::GuildId).not_null())
+                    .col(string(WebhookDeliveries::Event).not_null())
+                    .col(big_integer(WebhookDeliveries::DeliveredAt).not_null())
+                    .col(integer(WebhookDeliveries::Attempts).not_null())
+                    .col(integer_null(WebhookDeliveries::StatusCode))
+                    .col(boolean(WebhookDeliveries::Success).not_null())
+                    .col(text(WebhookDeliveries::Error).not_null().default(""))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_webhook_deliveries_guild_delivered_at")
+                    .table(WebhookDeliveries::Table)
+                    .col(WebhookDeliveries::GuildId)
+                    .col(WebhookDeliveries::DeliveredAt)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WebhookDeliveries::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(GuildWebhooks::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum GuildWebhooks {
+    Table,
+    Id, // Primary Key
+    GuildId,
+    Url,
+    Secret,    // Key the payloads are signed with
+    Events,    // Comma separated: member_join, member_leave, mod_action, mc_status
+    CreatedAt, // Unix timestamp in seconds
+}
+
+#[derive(DeriveIden)]
+enum WebhookDeliveries {
+    Table,
+    Id,        // Primary Key
+    WebhookId, // Id of the webhook in guild_webhooks
+    GuildId,
+    Event,
+    DeliveredAt, // Unix timestamp in seconds of the last attempt
+    Attempts,
+    StatusCode, // Null when no response was received
+    Success,
+    Error, // Empty for successful deliveries
+}
@@ -0,0 +1,64 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ScreeningSettings::Table)
+                    .col(string(ScreeningSettings::GuildId).primary_key())
+                    .col(string(ScreeningSettings::ReviewChannelId).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(ScreeningQuestions::Table)
+                    .col(pk_auto(ScreeningQuestions::Id))
+                    .col(string(ScreeningQuestions::GuildId).not_null())
+                    .col(string(ScreeningQuestions::Question).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_screening_questions_guild_id")
+                    .table(ScreeningQuestions::Table)
+                    .col(ScreeningQuestions::GuildId)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ScreeningQuestions::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(ScreeningSettings::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ScreeningSettings {
+    Table,
+    GuildId,         // Primary Key
+    ReviewChannelId, // Staff channel answers are posted to
+}
+
+#[derive(DeriveIden)]
+enum ScreeningQuestions {
+    Table,
+    Id, // Primary Key, questions are asked in id order
+    GuildId,
+    Question,
+}
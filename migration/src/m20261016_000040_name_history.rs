@@ -0,0 +1,54 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(NameHistory::Table)
+                    .col(pk_auto(NameHistory::Id))
+                    .col(string(NameHistory::GuildId).not_null())
+                    .col(string(NameHistory::UserId).not_null())
+                    .col(string(NameHistory::Kind).not_null())
+                    .col(text(NameHistory::OldName).not_null())
+                    .col(text(NameHistory::NewName).not_null())
+                    .col(big_integer(NameHistory::ChangedAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_name_history_guild_id_user_id")
+                    .table(NameHistory::Table)
+                    .col(NameHistory::GuildId)
+                    .col(NameHistory::UserId)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(NameHistory::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum NameHistory {
+    Table,
+    Id, // Primary Key
+    GuildId,
+    UserId,
+    Kind,      // username, global_name or nickname
+    OldName,   // Empty when there was no name, e.g. no nickname
+    NewName,   // Empty when the name was removed
+    ChangedAt, // Unix timestamp
+}
@@ -0,0 +1,52 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SavedEmbeds::Table)
+                    .col(string(SavedEmbeds::GuildId).not_null())
+                    .col(string(SavedEmbeds::Name).not_null())
+                    .col(text(SavedEmbeds::Title).not_null())
+                    .col(text(SavedEmbeds::Description).not_null())
+                    .col(string(SavedEmbeds::Color).not_null())
+                    .col(text(SavedEmbeds::ImageUrl).not_null())
+                    .col(text(SavedEmbeds::Fields).not_null())
+                    .col(big_integer(SavedEmbeds::UpdatedAt).not_null())
+                    .primary_key(
+                        IndexCreateStatement::new()
+                            .col(SavedEmbeds::GuildId)
+                            .col(SavedEmbeds::Name)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SavedEmbeds::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum SavedEmbeds {
+    Table,
+    GuildId,     // Primary Key
+    Name,        // Primary Key
+    Title,       // Empty when the embed has no title
+    Description, // Empty when the embed has no description
+    Color,       // Hex color or color name as typed, empty for the guild's branding color
+    ImageUrl,    // Empty when the embed has no image
+    Fields,      // One field per line, as `name | value`
+    UpdatedAt,   // Unix timestamp
+}
@@ -0,0 +1,49 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AutoSlowmode::Table)
+                    .col(string(AutoSlowmode::ChannelId).primary_key())
+                    .col(string(AutoSlowmode::GuildId).not_null())
+                    .col(integer(AutoSlowmode::MessagesPerMinute).not_null())
+                    .col(integer(AutoSlowmode::MinSeconds).not_null())
+                    .col(integer(AutoSlowmode::MaxSeconds).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_auto_slowmode_guild_id")
+                    .table(AutoSlowmode::Table)
+                    .col(AutoSlowmode::GuildId)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AutoSlowmode::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum AutoSlowmode {
+    Table,
+    ChannelId, // Primary Key
+    GuildId,
+    MessagesPerMinute, // Slowmode is raised when a channel gets at least this many messages per minute
+    MinSeconds,        // First slowmode step, lower slowmodes are turned off when relaxing
+    MaxSeconds,        // Slowmode is never raised above this
+}
@@ -0,0 +1,38 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MovieNight::Table)
+                    .col(string(MovieNight::GuildId).primary_key())
+                    .col(string(MovieNight::ChannelId).not_null())
+                    .col(string(MovieNight::Server).not_null())
+                    .col(string(MovieNight::MessageId).not_null().default(""))
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MovieNight::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum MovieNight {
+    Table,
+    GuildId, // Primary Key
+    ChannelId,
+    Server,    // jellyfin or plex
+    MessageId, // Message kept up to date with what is playing, empty until it is posted
+}
@@ -0,0 +1,34 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserContentBlobs::Table)
+                    .col(string(UserContentBlobs::Filename).primary_key())
+                    .col(integer(UserContentBlobs::RefCount).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserContentBlobs::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserContentBlobs {
+    Table,
+    Filename, // Primary Key, SHA-256 of the content followed by the extension of the uploaded file
+    RefCount, // Number of stored references to the file, the row is removed once it reaches zero
+}
@@ -0,0 +1,42 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ReactionMap::Table)
+                    .col(string(ReactionMap::GuildId).not_null())
+                    .col(string(ReactionMap::Pattern).not_null())
+                    .col(string(ReactionMap::Emoji).not_null())
+                    .primary_key(
+                        IndexCreateStatement::new()
+                            .col(ReactionMap::GuildId)
+                            .col(ReactionMap::Pattern)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ReactionMap::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ReactionMap {
+    Table,
+    GuildId, // Primary Key
+    Pattern, // Primary Key, regex matched case-insensitively against message content
+    Emoji,   // Unicode emoji, or custom emoji in <:name:id> form
+}
@@ -0,0 +1,55 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Jobs::Table)
+                    .col(string(Jobs::Name).primary_key())
+                    .col(string(Jobs::Kind).not_null())
+                    .col(string(Jobs::Schedule).not_null().default(""))
+                    .col(text(Jobs::Payload).not_null().default(""))
+                    .col(big_integer(Jobs::NextRunAt).not_null())
+                    .col(integer(Jobs::Attempts).not_null().default(0))
+                    .col(big_integer(Jobs::LastRunAt).not_null().default(0))
+                    .col(text(Jobs::LastError).not_null().default(""))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_jobs_next_run_at")
+                    .table(Jobs::Table)
+                    .col(Jobs::NextRunAt)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Jobs::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Jobs {
+    Table,
+    Name,      // Primary Key
+    Kind,      // Handler running the job
+    Schedule,  // Cron expression of recurring jobs, empty for one-shot jobs
+    Payload,   // Handler specific data
+    NextRunAt, // Unix timestamp
+    Attempts,  // Failed attempts of the current run
+    LastRunAt, // Unix timestamp of the last attempt, 0 if never run
+    LastError, // Error of the last attempt, empty if it succeeded
+}
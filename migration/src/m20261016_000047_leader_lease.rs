@@ -0,0 +1,36 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(LeaderLease::Table)
+                    .col(string(LeaderLease::Name).primary_key())
+                    .col(string(LeaderLease::Holder).not_null())
+                    .col(big_integer(LeaderLease::ExpiresAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LeaderLease::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum LeaderLease {
+    Table,
+    Name,      // Primary Key
+    Holder,    // Id of the instance holding the lease
+    ExpiresAt, // Unix timestamp, the lease can be taken over by another instance once it passed
+}
@@ -0,0 +1,34 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(GreetingSettings::Table)
+                    .col(string(GreetingSettings::GuildId).primary_key())
+                    .col(integer(GreetingSettings::DelaySeconds).not_null())
+                    .col(boolean(GreetingSettings::WaitForPending).not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GreetingSettings::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum GreetingSettings {
+    Table,
+    GuildId,        // Primary Key
+    DelaySeconds,   // Seconds between a member joining and their join notification
+    WaitForPending, // Join notifications wait until the member passed the guild's membership screening
+}
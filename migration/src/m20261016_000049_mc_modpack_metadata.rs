@@ -0,0 +1,68 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only accepts one column per statement
+        let columns = [
+            string(McServer::ModpackName)
+                .not_null()
+                .default("")
+                .to_owned(),
+            string(McServer::ModpackVersion)
+                .not_null()
+                .default("")
+                .to_owned(),
+            string(McServer::ModpackIcon)
+                .not_null()
+                .default("")
+                .to_owned(),
+            big_integer(McServer::ModpackFetchedAt)
+                .not_null()
+                .default(0)
+                .to_owned(),
+        ];
+        for column in columns {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(McServer::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            McServer::ModpackName,
+            McServer::ModpackVersion,
+            McServer::ModpackIcon,
+            McServer::ModpackFetchedAt,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(McServer::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum McServer {
+    Table,
+    ModpackName,    // Name of the modpack the modpack link points to, empty until fetched
+    ModpackVersion, // Latest version of the modpack
+    ModpackIcon,    // URL of the icon of the modpack
+    ModpackFetchedAt, // Unix timestamp of the last attempt to fetch the metadata, 0 to fetch it on next use
+}
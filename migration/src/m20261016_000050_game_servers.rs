@@ -0,0 +1,52 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(GameServer::Table)
+                    .col(string(GameServer::GuildId).not_null())
+                    .col(string(GameServer::Name).not_null())
+                    .col(string(GameServer::Protocol).not_null())
+                    .col(string(GameServer::Address).not_null())
+                    .col(integer(GameServer::Port).not_null().default(0))
+                    .col(string(GameServer::Description).not_null().default(""))
+                    .col(string(GameServer::Thumbnail).not_null().default(""))
+                    .col(string(GameServer::OwnerId).not_null())
+                    .primary_key(
+                        IndexCreateStatement::new()
+                            .col(GameServer::GuildId)
+                            .col(GameServer::Name)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GameServer::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum GameServer {
+    Table,
+    GuildId,  // Primary Key
+    Name,     // Primary Key
+    Protocol, // source, fivem, terraria or minecraft
+    Address,
+    Port,        // 0 for the default port of the protocol
+    Description, // Shown in the status embed, empty for the description reported by the server
+    Thumbnail,
+    OwnerId,
+}
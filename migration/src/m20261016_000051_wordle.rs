@@ -0,0 +1,103 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WordleGames::Table)
+                    .col(string(WordleGames::GuildId).not_null())
+                    .col(string(WordleGames::UserId).not_null())
+                    .col(big_integer(WordleGames::Day).not_null())
+                    .col(string(WordleGames::Guesses).not_null().default(""))
+                    .col(boolean(WordleGames::Solved).not_null().default(false))
+                    .col(big_integer(WordleGames::FinishedAt).not_null().default(0))
+                    .primary_key(
+                        IndexCreateStatement::new()
+                            .col(WordleGames::GuildId)
+                            .col(WordleGames::Day)
+                            .col(WordleGames::UserId)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(WordleStats::Table)
+                    .col(string(WordleStats::GuildId).not_null())
+                    .col(string(WordleStats::UserId).not_null())
+                    .col(integer(WordleStats::Played).not_null().default(0))
+                    .col(integer(WordleStats::Wins).not_null().default(0))
+                    .col(integer(WordleStats::CurrentStreak).not_null().default(0))
+                    .col(integer(WordleStats::MaxStreak).not_null().default(0))
+                    .col(big_integer(WordleStats::LastWinDay).not_null().default(-1))
+                    .primary_key(
+                        IndexCreateStatement::new()
+                            .col(WordleStats::GuildId)
+                            .col(WordleStats::UserId)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(WordleSettings::Table)
+                    .col(string(WordleSettings::GuildId).primary_key())
+                    .col(string(WordleSettings::ChannelId).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WordleSettings::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(WordleStats::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(WordleGames::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum WordleGames {
+    Table,
+    GuildId, // Primary Key
+    Day,     // Primary Key, days since unix epoch
+    UserId,  // Primary Key
+    Guesses, // Comma separated guesses, in order
+    Solved,
+    FinishedAt, // Unix timestamp, 0 while the game is still played
+}
+
+#[derive(DeriveIden)]
+enum WordleStats {
+    Table,
+    GuildId, // Primary Key
+    UserId,  // Primary Key
+    Played,  // Finished games
+    Wins,
+    CurrentStreak, // Days in a row with a win, up to the last win
+    MaxStreak,
+    LastWinDay, // Days since unix epoch, -1 before the first win
+}
+
+#[derive(DeriveIden)]
+enum WordleSettings {
+    Table,
+    GuildId,   // Primary Key
+    ChannelId, // Channel the daily leaderboard is posted in
+}
@@ -0,0 +1,43 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ChannelActivity::Table)
+                    .col(string(ChannelActivity::GuildId).not_null())
+                    .col(big_integer(ChannelActivity::Hour).not_null())
+                    .col(string(ChannelActivity::ChannelId).not_null())
+                    .col(integer(ChannelActivity::Messages).not_null().default(0))
+                    .primary_key(
+                        IndexCreateStatement::new()
+                            .col(ChannelActivity::GuildId)
+                            .col(ChannelActivity::Hour)
+                            .col(ChannelActivity::ChannelId)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ChannelActivity::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ChannelActivity {
+    Table,
+    GuildId,   // Primary Key
+    Hour,      // Primary Key, hours since unix epoch
+    ChannelId, // Primary Key
+    Messages,
+}
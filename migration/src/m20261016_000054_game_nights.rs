@@ -0,0 +1,113 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(GameNights::Table)
+                    .col(pk_auto(GameNights::Id))
+                    .col(string(GameNights::GuildId).not_null())
+                    .col(string(GameNights::ChannelId).not_null())
+                    .col(string(GameNights::MessageId).not_null().default(""))
+                    .col(string(GameNights::HostId).not_null())
+                    .col(string(GameNights::Game).not_null())
+                    .col(big_integer(GameNights::StartsAt).not_null())
+                    .col(string(GameNights::EventId).not_null().default(""))
+                    .col(string(GameNights::VoiceChannelId).not_null().default(""))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_game_nights_guild_id")
+                    .table(GameNights::Table)
+                    .col(GameNights::GuildId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(GameNightRsvps::Table)
+                    .col(integer(GameNightRsvps::GameNightId).not_null())
+                    .col(string(GameNightRsvps::UserId).not_null())
+                    .col(big_integer(GameNightRsvps::RespondedAt).not_null())
+                    .primary_key(
+                        IndexCreateStatement::new()
+                            .col(GameNightRsvps::GameNightId)
+                            .col(GameNightRsvps::UserId)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(GameNightSettings::Table)
+                    .col(string(GameNightSettings::GuildId).primary_key())
+                    .col(string(GameNightSettings::RoleId).not_null().default(""))
+                    .col(string(GameNightSettings::CategoryId).not_null().default(""))
+                    .col(
+                        integer(GameNightSettings::ReminderMinutes)
+                            .not_null()
+                            .default(15),
+                    )
+                    .col(integer(GameNightSettings::LobbyHours).not_null().default(6))
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GameNightSettings::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(GameNightRsvps::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(GameNights::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum GameNights {
+    Table,
+    Id, // Primary Key
+    GuildId,
+    ChannelId, // Channel of the RSVP message
+    MessageId, // RSVP message, empty until it is posted
+    HostId,    // Member who scheduled the game night
+    Game,
+    StartsAt,       // Unix timestamp
+    EventId,        // Discord scheduled event, empty if it couldn't be created
+    VoiceChannelId, // Temporary lobby, empty until the game night starts
+}
+
+#[derive(DeriveIden)]
+enum GameNightRsvps {
+    Table,
+    GameNightId, // Primary Key
+    UserId,      // Primary Key
+    RespondedAt, // Unix timestamp
+}
+
+#[derive(DeriveIden)]
+enum GameNightSettings {
+    Table,
+    GuildId,         // Primary Key
+    RoleId,          // Role pinged by default, empty for none
+    CategoryId,      // Category of the lobbies, empty for none
+    ReminderMinutes, // 0 disables reminders
+    LobbyHours,      // Time until the lobby is deleted
+}
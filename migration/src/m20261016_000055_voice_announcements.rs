@@ -0,0 +1,51 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(VoiceAnnouncements::Table)
+                    .col(string(VoiceAnnouncements::VoiceChannelId).primary_key())
+                    .col(string(VoiceAnnouncements::GuildId).not_null())
+                    .col(string(VoiceAnnouncements::TextChannelId).not_null())
+                    .col(string(VoiceAnnouncements::JoinTemplate).not_null())
+                    .col(string(VoiceAnnouncements::LeaveTemplate).not_null())
+                    .col(integer(VoiceAnnouncements::CoalesceSeconds).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_voice_announcements_guild_id")
+                    .table(VoiceAnnouncements::Table)
+                    .col(VoiceAnnouncements::GuildId)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(VoiceAnnouncements::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum VoiceAnnouncements {
+    Table,
+    VoiceChannelId, // Primary Key
+    GuildId,
+    TextChannelId,   // Channel the announcements are posted in
+    JoinTemplate,    // Empty to not announce joins
+    LeaveTemplate,   // Empty to not announce leaves
+    CoalesceSeconds, // Time changes are gathered before they are posted together
+}
@@ -0,0 +1,42 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(GuildMedia::Table)
+                    .col(string(GuildMedia::GuildId).not_null())
+                    .col(string(GuildMedia::MediaKey).not_null())
+                    .col(string(GuildMedia::Filename).not_null())
+                    .primary_key(
+                        IndexCreateStatement::new()
+                            .col(GuildMedia::GuildId)
+                            .col(GuildMedia::MediaKey)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GuildMedia::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum GuildMedia {
+    Table,
+    GuildId,  // Primary Key
+    MediaKey, // Primary Key, like d6-3 or coin-heads
+    Filename, // User content file replacing the bundled media
+}
@@ -0,0 +1,38 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CoinflipSettings::Table)
+                    .col(string(CoinflipSettings::GuildId).primary_key())
+                    .col(string(CoinflipSettings::HeadsLabel).not_null().default(""))
+                    .col(string(CoinflipSettings::TailsLabel).not_null().default(""))
+                    .col(integer(CoinflipSettings::EdgeOdds).not_null().default(0))
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CoinflipSettings::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum CoinflipSettings {
+    Table,
+    GuildId,    // Primary Key
+    HeadsLabel, // Empty for "heads"
+    TailsLabel, // Empty for "tails"
+    EdgeOdds,   // The coin lands on its edge once in this many flips, 0 for never
+}
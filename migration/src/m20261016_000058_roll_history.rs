@@ -0,0 +1,52 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RollHistory::Table)
+                    .col(pk_auto(RollHistory::Id))
+                    .col(string(RollHistory::GuildId).not_null())
+                    .col(string(RollHistory::UserId).not_null())
+                    .col(integer(RollHistory::Sides).not_null())
+                    .col(integer(RollHistory::Value).not_null())
+                    .col(big_integer(RollHistory::RolledAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_roll_history_guild_id_user_id")
+                    .table(RollHistory::Table)
+                    .col(RollHistory::GuildId)
+                    .col(RollHistory::UserId)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RollHistory::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum RollHistory {
+    Table,
+    Id,      // Primary Key
+    GuildId, // Empty for rolls in direct messages
+    UserId,
+    Sides,    // Sides of the die, e.g. 20 for a D20
+    Value,    // Side the die landed on
+    RolledAt, // Unix timestamp
+}
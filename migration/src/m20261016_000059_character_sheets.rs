@@ -0,0 +1,47 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CharacterSheetFields::Table)
+                    .col(string(CharacterSheetFields::GuildId).not_null())
+                    .col(string(CharacterSheetFields::UserId).not_null())
+                    .col(string(CharacterSheetFields::Field).not_null())
+                    .col(text(CharacterSheetFields::Value).not_null())
+                    .col(big_integer(CharacterSheetFields::UpdatedAt).not_null())
+                    .primary_key(
+                        IndexCreateStatement::new()
+                            .col(CharacterSheetFields::GuildId)
+                            .col(CharacterSheetFields::UserId)
+                            .col(CharacterSheetFields::Field)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CharacterSheetFields::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum CharacterSheetFields {
+    Table,
+    GuildId,
+    UserId,
+    Field, // Lowercase name of the stat, e.g. class or str
+    Value,
+    UpdatedAt, // Unix timestamp
+}
@@ -0,0 +1,53 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only accepts one column per statement
+        let columns = [
+            string(UserContentAsset::ChannelId)
+                .not_null()
+                .default("")
+                .to_owned(),
+            string(UserContentAsset::MessageId)
+                .not_null()
+                .default("")
+                .to_owned(),
+        ];
+        for column in columns {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(UserContentAsset::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [UserContentAsset::ChannelId, UserContentAsset::MessageId] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(UserContentAsset::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserContentAsset {
+    Table,
+    ChannelId, // Channel of the message hosting the file, empty for files hosted before ids were stored
+    MessageId, // Message hosting the file, fetched again to refresh the expiring CDN url
+}
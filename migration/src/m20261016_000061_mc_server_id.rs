@@ -0,0 +1,66 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+const ID_INDEX: &str = "idx_mc_server_id";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(McServer::Table)
+                    .add_column(big_integer(McServer::Id).not_null().default(0))
+                    .to_owned(),
+            )
+            .await?;
+        // The rowid is unique, so existing servers keep distinct ids
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(McServer::Table)
+                    .value(McServer::Id, Expr::cust("rowid"))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name(ID_INDEX)
+                    .table(McServer::Table)
+                    .col(McServer::Id)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name(ID_INDEX)
+                    .table(McServer::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(McServer::Table)
+                    .drop_column(McServer::Id)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum McServer {
+    Table,
+    Id, // Numeric id of the server, short enough for the custom ids of buttons, unlike the name
+}
@@ -0,0 +1,38 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RaidLockdown::Table)
+                    .col(string(RaidLockdown::GuildId).primary_key())
+                    .col(big_integer(RaidLockdown::Until).not_null())
+                    .col(boolean(RaidLockdown::PauseDefaultRoles).not_null())
+                    .col(integer_null(RaidLockdown::PreviousVerificationLevel))
+                    .col(string(RaidLockdown::AlertChannelId).not_null().default(""))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RaidLockdown::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum RaidLockdown {
+    Table,
+    GuildId, // Primary Key
+    Until,   // Unix timestamp at which the lockdown ends
+    PauseDefaultRoles,
+    PreviousVerificationLevel, // Verification level to restore when the lockdown ends, null if it wasn't raised
+    AlertChannelId,            // Empty for no alerts
+}
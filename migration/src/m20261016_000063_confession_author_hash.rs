@@ -0,0 +1,58 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+const AUTHOR_HASH_INDEX: &str = "idx_confessions_author_hash";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Existing confessions can't be hashed without the confession key, so they keep an empty hash until they
+        // expire
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Confessions::Table)
+                    .add_column(string(Confessions::AuthorHash).not_null().default(""))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name(AUTHOR_HASH_INDEX)
+                    .table(Confessions::Table)
+                    .col(Confessions::AuthorHash)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name(AUTHOR_HASH_INDEX)
+                    .table(Confessions::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Confessions::Table)
+                    .drop_column(Confessions::AuthorHash)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Confessions {
+    Table,
+    AuthorHash, // Keyed hash of the author id, to find the confessions of a user without decrypting every author
+}
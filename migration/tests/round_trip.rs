@@ -0,0 +1,98 @@
+//! Applies and rolls back the migrations on an in-memory sqlite database, checking that rollbacks keep the data they
+//! can represent.
+
+use migration::{Migrator, MigratorTrait};
+use sea_orm_migration::sea_orm::{
+    ConnectOptions, ConnectionTrait, Database, DatabaseConnection, DbErr, Statement,
+};
+
+async fn connect() -> Result<DatabaseConnection, DbErr> {
+    // Every connection to an in-memory database opens a new, empty database
+    let options = ConnectOptions::new("sqlite::memory:")
+        .max_connections(1)
+        .to_owned();
+    Database::connect(options).await
+}
+
+/// Runs a query returning the text columns of each row, in order.
+async fn rows(
+    db: &DatabaseConnection,
+    sql: &str,
+    columns: &[&str],
+) -> Result<Vec<Vec<String>>, DbErr> {
+    let mut rows = vec![];
+    for row in db
+        .query_all(Statement::from_string(db.get_database_backend(), sql))
+        .await?
+    {
+        let mut values = vec![];
+        for column in columns {
+            values.push(row.try_get::<String>("", column)?);
+        }
+        rows.push(values);
+    }
+    Ok(rows)
+}
+
+#[async_std::test]
+async fn all_migrations_round_trip() -> Result<(), DbErr> {
+    let db = connect().await?;
+    Migrator::up(&db, None).await?;
+    Migrator::down(&db, None).await?;
+    Migrator::up(&db, None).await?;
+    assert!(Migrator::get_pending_migrations(&db).await?.is_empty());
+    Ok(())
+}
+
+#[async_std::test]
+async fn welcome_channels_survive_round_trip() -> Result<(), DbErr> {
+    let db = connect().await?;
+    Migrator::up(&db, Some(1)).await?;
+    db.execute_unprepared(
+        "INSERT INTO welcome_channel (guild_id, channel_id) VALUES ('1', '10'), ('2', '20')",
+    )
+    .await?;
+
+    Migrator::up(&db, Some(1)).await?;
+    assert_eq!(
+        rows(
+            &db,
+            r#"SELECT guild_id, channel_id, CASE WHEN "join" THEN 'join' ELSE 'leave' END AS kind
+            FROM member_notification_channel ORDER BY guild_id, kind"#,
+            &["guild_id", "channel_id", "kind"],
+        )
+        .await?,
+        [
+            ["1", "10", "join"],
+            ["1", "10", "leave"],
+            ["2", "20", "join"],
+            ["2", "20", "leave"],
+        ]
+    );
+
+    // Guild 1 moves its welcome messages, guild 2 only keeps its goodbye messages
+    db.execute_unprepared(
+        r#"UPDATE member_notification_channel SET channel_id = '11' WHERE guild_id = '1' AND "join""#,
+    )
+    .await?;
+    db.execute_unprepared(
+        r#"DELETE FROM member_notification_channel WHERE guild_id = '2' AND "join""#,
+    )
+    .await?;
+    db.execute_unprepared(
+        r#"INSERT INTO member_notification_channel (guild_id, "join", channel_id) VALUES ('3', false, '30')"#,
+    )
+    .await?;
+
+    Migrator::down(&db, Some(1)).await?;
+    assert_eq!(
+        rows(
+            &db,
+            "SELECT guild_id, channel_id FROM welcome_channel ORDER BY guild_id",
+            &["guild_id", "channel_id"],
+        )
+        .await?,
+        [["1", "11"], ["2", "20"], ["3", "30"]]
+    );
+    Ok(())
+}
@@ -1,8 +1,27 @@
-use std::{collections::HashSet, sync::Arc, time::Duration};
+use std::{
+    collections::HashSet,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
 use anyhow::Context as _;
-use imposterbot::infrastructure::{botdata::Data, environment, environment::env_var_with_context};
-use poise::serenity_prelude::{self as serenity, GatewayIntents, UserId};
+use imposterbot::events::aliases::dispatch_alias;
+use imposterbot::events::auto_slowmode::{self, AutoSlowmodeCache};
+use imposterbot::events::raid_protection::{self, RaidStateMap};
+use imposterbot::infrastructure::{
+    blocklist, bot_lists,
+    botdata::{self, Data},
+    channel_validation,
+    clock::SystemClock,
+    environment,
+    environment::env_var_with_context,
+    game_sessions, global_commands, heartbeat, jobs, leader, localization, log_privacy, mc_monitor,
+    media_servers,
+    modules::{self, BotModule, ModuleRegistry},
+    random::ThreadRandom,
+    scheduler,
+};
+use poise::serenity_prelude::{self as serenity, GatewayIntents, UserId, async_trait};
 use sea_orm::DatabaseConnection;
 use tracing::{error, info, warn};
 
@@ -27,6 +46,8 @@ fn configure_voice(builder: serenity::ClientBuilder) -> serenity::ClientBuilder
     builder
         .register_songbird()
         .type_map_insert::<imposterbot::commands::voice::HttpKey>(reqwest::Client::new())
+        .type_map_insert::<imposterbot::commands::voice::NowPlayingKey>(Default::default())
+        .type_map_insert::<imposterbot::commands::voice::AutoplayKey>(Default::default())
 }
 
 #[cfg(not(feature = "voice"))]
@@ -66,8 +87,48 @@ fn create_poise_framework(pool: DatabaseConnection) -> poise::Framework<Data, im
             },
             initialize_owners: initialize_owners,
             owners: owners,
+            command_check: Some(|ctx| {
+                Box::pin(async move {
+                    Ok(blocklist::blocklist_check(ctx).await?
+                        && global_commands::disabled_command_check(ctx).await?)
+                })
+            }),
             on_error: |error| {
                 Box::pin(async move {
+                    // Blocked users are ignored silently, anyone else is told the command was refused
+                    if let poise::FrameworkError::CommandCheckFailed {
+                        error: None, ctx, ..
+                    } = error
+                    {
+                        if matches!(blocklist::blocklist_check(ctx).await, Ok(false)) {
+                            return;
+                        }
+                        let reply = poise::CreateReply::default()
+                            .content("You can't use this command here.")
+                            .ephemeral(true);
+                        if let Err(e) = ctx.send(reply).await {
+                            warn!("Failed to reply to a refused command: {:?}", e);
+                        }
+                        return;
+                    }
+                    if let poise::FrameworkError::UnknownCommand {
+                        ctx,
+                        msg,
+                        prefix,
+                        msg_content,
+                        framework,
+                        trigger,
+                        ..
+                    } = error
+                    {
+                        match dispatch_alias(framework, ctx, msg, prefix, msg_content, trigger)
+                            .await
+                        {
+                            Ok(true) => return,
+                            Ok(false) => {}
+                            Err(e) => warn!("Alias dispatch produced an error: {:?}", e),
+                        }
+                    }
                     if let Err(e) = poise::builtins::on_error(error).await {
                         error!("{:?}", e);
                     }
@@ -80,11 +141,72 @@ fn create_poise_framework(pool: DatabaseConnection) -> poise::Framework<Data, im
             },
             ..Default::default()
         })
-        .setup(|_ctx, _ready, _framework| {
+        .setup(|ctx, _ready, framework| {
             Box::pin(async move {
+                if let Err(e) = log_privacy::load_privacy_mode(&pool).await {
+                    warn!("Failed to load the log privacy mode: {:?}", e);
+                }
+                if let Err(e) = global_commands::load_disabled_commands(&pool).await {
+                    warn!("Failed to load the globally disabled commands: {:?}", e);
+                }
+                let clock = Arc::new(SystemClock);
+                let random = Arc::new(ThreadRandom);
+                leader::campaign(&pool, clock.as_ref()).await;
+                leader::spawn_leader_election(pool.clone(), clock.clone());
+                mc_monitor::spawn_mc_monitor(ctx.http.clone(), pool.clone(), clock.clone());
+                media_servers::spawn_movie_night_relay(ctx.http.clone(), pool.clone());
+                scheduler::spawn_scheduler(ctx.http.clone(), pool.clone(), clock.clone());
+                jobs::spawn_job_runner(
+                    ctx.http.clone(),
+                    pool.clone(),
+                    clock.clone(),
+                    random.clone(),
+                );
+                channel_validation::spawn_channel_validation(ctx.clone(), pool.clone());
+                heartbeat::spawn_heartbeat(ctx.clone(), framework.shard_manager().clone());
+                bot_lists::spawn_bot_list_stats(ctx.clone());
+                let raid_state: RaidStateMap = Default::default();
+                if let Err(e) =
+                    raid_protection::restore_lockdowns(ctx, &pool, &raid_state, clock.as_ref())
+                        .await
+                {
+                    warn!("Failed to restore raid lockdowns: {:?}", e);
+                }
+                let auto_slowmode: AutoSlowmodeCache = Default::default();
+                auto_slowmode::spawn_auto_slowmode_relaxer(
+                    ctx.http.clone(),
+                    auto_slowmode.clone(),
+                    clock.clone(),
+                );
+                let games = game_sessions::load_game_sessions(&pool)
+                    .await
+                    .unwrap_or_else(|e| {
+                        warn!("Failed to restore game sessions: {:?}", e);
+                        Default::default()
+                    });
                 Ok(Data {
                     db_pool: pool,
+                    http: botdata::http_client(),
                     invoc_time: Default::default(),
+                    raid_state,
+                    latency: Default::default(),
+                    gamestats_cache: Default::default(),
+                    responder_cooldowns: Default::default(),
+                    clock,
+                    random,
+                    recent_invocations: Default::default(),
+                    branding: Default::default(),
+                    game_sessions: Arc::new(RwLock::new(games)),
+                    unfurl: Default::default(),
+                    snipes: Default::default(),
+                    auto_slowmode,
+                    translations: Default::default(),
+                    reaction_maps: Default::default(),
+                    guild_emojis: Default::default(),
+                    pending_greetings: Default::default(),
+                    voice_announcements: Default::default(),
+                    #[cfg(feature = "ai")]
+                    ai_rate_limits: Default::default(),
                 })
             })
         })
@@ -97,25 +219,221 @@ fn create_poise_framework(pool: DatabaseConnection) -> poise::Framework<Data, im
     return framework;
 }
 
+/// Help, configuration and administration of the bot itself.
+struct CoreModule;
+
+impl BotModule for CoreModule {
+    fn name(&self) -> &'static str {
+        "core"
+    }
+
+    fn commands(&self) -> Vec<poise::Command<Data, imposterbot::Error>> {
+        vec![
+            imposterbot::commands::builtins::help(),
+            imposterbot::commands::builtins::register(),
+            imposterbot::commands::ping::ping(),
+            imposterbot::commands::database::db(),
+            imposterbot::commands::log::log(),
+            imposterbot::commands::global_commands::global_commands(),
+            imposterbot::commands::jobs::jobs(),
+            imposterbot::commands::blocklist::blocklist(),
+            imposterbot::commands::alias::alias(),
+            imposterbot::commands::branding::branding(),
+            imposterbot::commands::privacy::privacy(),
+            imposterbot::commands::configure::configure(),
+            imposterbot::commands::audit::audit(),
+        ]
+    }
+}
+
+struct FunModule;
+
+impl BotModule for FunModule {
+    fn name(&self) -> &'static str {
+        "fun"
+    }
+
+    fn commands(&self) -> Vec<poise::Command<Data, imposterbot::Error>> {
+        vec![
+            imposterbot::commands::roll::roll(),
+            imposterbot::commands::roll::roll_again(),
+            imposterbot::commands::roll::rollstats(),
+            imposterbot::commands::coinflip::coinflip(),
+            imposterbot::commands::game::game(),
+            imposterbot::commands::gallery::gallery(),
+            imposterbot::commands::unfurl::unfurl(),
+            imposterbot::commands::coinflip::coinflip_again(),
+            imposterbot::commands::fun::choose(),
+            imposterbot::commands::fun::choose_prefix(),
+            imposterbot::commands::raffle::pick_random_reactor(),
+            imposterbot::commands::movie_night::movie_night(),
+            imposterbot::commands::translate::translate(),
+            imposterbot::commands::snipe::snipe(),
+            imposterbot::commands::snipe::editsnipe(),
+            imposterbot::commands::define::define(),
+            imposterbot::commands::define::define_config(),
+            imposterbot::commands::gamestats::lol::lol(),
+            imposterbot::commands::confess::confess(),
+            imposterbot::commands::confess::confession(),
+            imposterbot::commands::autoresponse::autoresponse(),
+            imposterbot::commands::reaction_map::reactmap(),
+            imposterbot::commands::emojistats::emojistats(),
+            imposterbot::commands::voicestats::voicestats(),
+            imposterbot::commands::activity::activity(),
+            imposterbot::commands::wordle::wordle(),
+            imposterbot::commands::quickpoll::quickpoll(),
+            imposterbot::commands::timestamp::timestamp(),
+            imposterbot::commands::timestamp::timezone(),
+            imposterbot::commands::gamenight::gamenight(),
+            imposterbot::commands::custom_media::custom_media(),
+            imposterbot::commands::character::character(),
+        ]
+    }
+}
+
+struct MinecraftModule;
+
+impl BotModule for MinecraftModule {
+    fn name(&self) -> &'static str {
+        "minecraft"
+    }
+
+    fn commands(&self) -> Vec<poise::Command<Data, imposterbot::Error>> {
+        vec![imposterbot::commands::minecraft::mc()]
+    }
+}
+
+/// Advertising of other game servers, queried with the protocol of their game.
+struct GameServerModule;
+
+impl BotModule for GameServerModule {
+    fn name(&self) -> &'static str {
+        "game_servers"
+    }
+
+    fn commands(&self) -> Vec<poise::Command<Data, imposterbot::Error>> {
+        vec![imposterbot::commands::game_server::server()]
+    }
+}
+
+struct MemberManagementModule;
+
+impl BotModule for MemberManagementModule {
+    fn name(&self) -> &'static str {
+        "member_management"
+    }
+
+    fn commands(&self) -> Vec<poise::Command<Data, imposterbot::Error>> {
+        vec![
+            imposterbot::commands::announce::announce(),
+            imposterbot::commands::embed::embed(),
+            imposterbot::commands::ban_sync::bansync(),
+            imposterbot::commands::member_management::channels::configure_welcome_channel(),
+            imposterbot::commands::member_management::channels::configure_leave_channel(),
+            imposterbot::commands::member_management::channels::configure_asset_storage_channel(),
+            imposterbot::commands::member_management::channels::configure_mod_log_channel(),
+            imposterbot::commands::member_management::roles::add_default_member_role(),
+            imposterbot::commands::member_management::roles::remove_default_member_role(),
+            imposterbot::commands::member_management::roles::default_roles(),
+            imposterbot::commands::member_management::bulk_roles::role(),
+            imposterbot::commands::member_management::temp_roles::temprole(),
+            imposterbot::commands::colorrole::colorrole(),
+            imposterbot::commands::colorrole::colorroles(),
+            imposterbot::commands::member_management::raid_protection::raid_protection(),
+            imposterbot::commands::member_management::screening::screening(),
+            imposterbot::commands::namehistory::namehistory(),
+            imposterbot::commands::member_management::nickname_filter::nickname_filter(),
+            imposterbot::commands::member_management::auto_slowmode::auto_slowmode(),
+            imposterbot::commands::member_management::voice_announcements::voice_announce(),
+            imposterbot::commands::member_management::notifications::test_member_add(),
+            imposterbot::commands::member_management::notifications::test_member_remove(),
+            imposterbot::commands::member_management::notifications::cfg_member_notification(),
+        ]
+    }
+}
+
+/// Outgoing webhooks for member, moderation and minecraft status events.
+struct WebhookModule;
+
+#[async_trait]
+impl BotModule for WebhookModule {
+    fn name(&self) -> &'static str {
+        "webhooks"
+    }
+
+    fn commands(&self) -> Vec<poise::Command<Data, imposterbot::Error>> {
+        vec![imposterbot::commands::webhook::webhook()]
+    }
+
+    async fn on_event(
+        &self,
+        ctx: &serenity::Context,
+        event: &serenity::FullEvent,
+        data: &Data,
+    ) -> Result<(), imposterbot::Error> {
+        imposterbot::events::webhooks::on_event(ctx, event, data).await
+    }
+}
+
+#[cfg(feature = "voice")]
+struct VoiceModule;
+
+#[cfg(feature = "voice")]
+impl BotModule for VoiceModule {
+    fn name(&self) -> &'static str {
+        "voice"
+    }
+
+    fn commands(&self) -> Vec<poise::Command<Data, imposterbot::Error>> {
+        vec![
+            imposterbot::commands::voice::play(),
+            #[cfg(feature = "youtube")]
+            imposterbot::commands::voice_limits::voice_limits(),
+            #[cfg(feature = "youtube")]
+            imposterbot::commands::playlist::playlist(),
+        ]
+    }
+}
+
+#[cfg(feature = "ai")]
+struct AiModule;
+
+#[cfg(feature = "ai")]
+impl BotModule for AiModule {
+    fn name(&self) -> &'static str {
+        "ai"
+    }
+
+    fn commands(&self) -> Vec<poise::Command<Data, imposterbot::Error>> {
+        vec![imposterbot::commands::ai::ai()]
+    }
+}
+
+/// Modules the bot runs with. Register new features here.
+pub fn bot_modules() -> ModuleRegistry {
+    let registry = ModuleRegistry::new()
+        .register(CoreModule)
+        .register(FunModule)
+        .register(MinecraftModule)
+        .register(GameServerModule)
+        .register(MemberManagementModule)
+        .register(WebhookModule);
+    #[cfg(feature = "voice")]
+    let registry = registry.register(VoiceModule);
+    #[cfg(feature = "ai")]
+    let registry = registry.register(AiModule);
+    registry
+}
+
 fn get_enabled_commands() -> Vec<poise::Command<Data, imposterbot::Error>> {
-    let default_commands = vec![
-        imposterbot::commands::builtins::help(),
-        imposterbot::commands::builtins::register(),
-        imposterbot::commands::minecraft::mc(),
-        imposterbot::commands::roll::roll(),
-        imposterbot::commands::coinflip::coinflip(),
-        imposterbot::commands::member_management::channels::configure_welcome_channel(),
-        imposterbot::commands::member_management::channels::configure_leave_channel(),
-        imposterbot::commands::member_management::roles::add_default_member_role(),
-        imposterbot::commands::member_management::roles::remove_default_member_role(),
-        imposterbot::commands::member_management::notifications::test_member_add(),
-        imposterbot::commands::member_management::notifications::test_member_remove(),
-        imposterbot::commands::member_management::notifications::cfg_member_notification(),
-        #[cfg(feature = "voice")]
-        imposterbot::commands::voice::play(),
-    ];
-
-    // Get the list of commands disabled by environment variable
+    let registry = modules::registry();
+    for module in registry.modules() {
+        info!("Loading module: {}", module.name());
+    }
+    let default_commands = registry.commands();
+
+    // Get the list of commands disabled by environment variable. Commands can also be disabled at runtime with
+    // `/global-commands`, which keeps them loaded but unregistered.
     let disable_commands_env = std::env::var("COMMAND_DISABLE_LIST").unwrap_or_default();
     let disabled_commands = disable_commands_env.split(",");
 
@@ -136,8 +454,7 @@ fn get_enabled_commands() -> Vec<poise::Command<Data, imposterbot::Error>> {
         info!("Disabled commands: {:?}", disabled_commands_info);
     }
 
-    // Return the enabled commands
-    default_commands
+    let mut commands: Vec<_> = default_commands
         .into_iter()
         .filter(|cmd| {
             !disabled_commands
@@ -145,7 +462,11 @@ fn get_enabled_commands() -> Vec<poise::Command<Data, imposterbot::Error>> {
                 .into_iter()
                 .any(|disabled| cmd.name.to_uppercase() == disabled.to_uppercase())
         })
-        .collect()
+        .collect();
+    localization::localize_commands(&mut commands);
+
+    // Return the enabled commands
+    commands
 }
 
 enum OwnerParseError {
@@ -0,0 +1,245 @@
+use poise::{
+    CreateReply,
+    serenity_prelude::{self as serenity, Mentionable},
+};
+use tracing::trace;
+
+use crate::{
+    Context, Error,
+    infrastructure::{
+        branding::guild_branding,
+        ids::require_guild_id,
+        util::{DebuggableReply, defer_or_broadcast, resolve_ephemeral},
+    },
+    poise_instrument, record_ctx_fields,
+    services::channel_activity::{ChannelActivityRepo, hour_of},
+};
+
+/// Number of channels listed as the most active.
+const LIST_LENGTH: usize = 10;
+
+/// Squares of the text heatmap, from the quietest to the busiest hours.
+const HEATMAP_LEVELS: [&str; 5] = ["⬛", "🟦", "🟩", "🟨", "🟥"];
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+const CHART_SIZE: (u32, u32) = (960, 280);
+
+#[derive(Debug, poise::ChoiceParameter, Clone, Copy)]
+enum ActivityWindow {
+    #[name = "24 hours"]
+    Day,
+    #[name = "7 days"]
+    Week,
+    #[name = "30 days"]
+    Month,
+    #[name = "90 days"]
+    Quarter,
+}
+
+impl ActivityWindow {
+    fn hours(&self) -> i64 {
+        match self {
+            Self::Day => 24,
+            Self::Week => 24 * 7,
+            Self::Month => 24 * 30,
+            Self::Quarter => 24 * 90,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Day => "24 hours",
+            Self::Week => "7 days",
+            Self::Month => "30 days",
+            Self::Quarter => "90 days",
+        }
+    }
+}
+
+#[derive(Debug, poise::ChoiceParameter, Clone, Copy, PartialEq, Eq)]
+enum ActivityDisplay {
+    #[name = "Embed"]
+    Embed,
+    #[name = "Chart"]
+    Chart,
+}
+
+/// Messages sent during each hour of each day of the week, in UTC, indexed by weekday from sunday then by hour.
+fn weekly_heatmap(hourly: &[(i64, i64)]) -> [[i64; 24]; 7] {
+    let mut heatmap = [[0; 24]; 7];
+    for (hour, messages) in hourly {
+        // The unix epoch was a thursday
+        let weekday = (hour.div_euclid(24) + 4).rem_euclid(7) as usize;
+        heatmap[weekday][hour.rem_euclid(24) as usize] += messages;
+    }
+    heatmap
+}
+
+/// Renders the heatmap as rows of colored squares, one row per day of the week.
+fn text_heatmap(heatmap: &[[i64; 24]; 7]) -> String {
+    let max = heatmap.iter().flatten().copied().max().unwrap_or(0).max(1);
+    heatmap
+        .iter()
+        .zip(WEEKDAYS)
+        .map(|(hours, weekday)| {
+            let squares: String = hours
+                .iter()
+                .map(|messages| {
+                    let level = match *messages {
+                        0 => 0,
+                        x => (1 + (x * 3 / max) as usize).min(HEATMAP_LEVELS.len() - 1),
+                    };
+                    HEATMAP_LEVELS[level]
+                })
+                .collect();
+            format!("`{}` {}", weekday, squares)
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Renders the heatmap as a PNG grid, the busiest hours in the darkest color.
+///
+/// Text needs system fonts that aren't available in the container, so the chart has no labels. The embed it is
+/// attached to describes the axes instead.
+fn render_heatmap_chart(heatmap: &[[i64; 24]; 7]) -> Result<Vec<u8>, Error> {
+    use plotters::prelude::*;
+
+    let (width, height) = CHART_SIZE;
+    let max = heatmap.iter().flatten().copied().max().unwrap_or(0).max(1);
+    let mut buffer = vec![0u8; (width * height * 3) as usize];
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, CHART_SIZE).into_drawing_area();
+        root.fill(&WHITE)?;
+        let mut chart = ChartBuilder::on(&root)
+            .margin(10)
+            .build_cartesian_2d(0..24, 0..7)?;
+        chart.draw_series(heatmap.iter().enumerate().flat_map(|(weekday, hours)| {
+            // Sunday is drawn at the top
+            let y = 6 - weekday as i32;
+            hours.iter().enumerate().map(move |(hour, messages)| {
+                let intensity = *messages as f64 / max as f64;
+                Rectangle::new(
+                    [(hour as i32, y), (hour as i32 + 1, y + 1)],
+                    BLUE.mix(0.05 + intensity * 0.95).filled(),
+                )
+            })
+        }))?;
+        // Lines between the cells, and thicker ones every six hours
+        for hour in 0..=24 {
+            let style = if hour % 6 == 0 {
+                WHITE.stroke_width(3)
+            } else {
+                WHITE.stroke_width(1)
+            };
+            chart.draw_series(LineSeries::new([(hour, 0), (hour, 7)], style))?;
+        }
+        for y in 0..=7 {
+            chart.draw_series(LineSeries::new([(0, y), (24, y)], WHITE.stroke_width(3)))?;
+        }
+        root.present()?;
+    }
+
+    let image = image::RgbImage::from_raw(width, height, buffer)
+        .ok_or::<Error>("Failed to create chart image".into())?;
+    let mut png = std::io::Cursor::new(vec![]);
+    image.write_to(&mut png, image::ImageOutputFormat::Png)?;
+    Ok(png.into_inner())
+}
+
+poise_instrument! {
+    /// Shows the most active channels of this guild and its busiest hours.
+    #[poise::command(
+        slash_command,
+        prefix_command,
+        guild_only,
+        category = "Management"
+    )]
+    pub async fn activity(
+        ctx: Context<'_>,
+        #[description = "Time window (default: 7 days)"] window: Option<ActivityWindow>,
+        #[description = "Show the hours as squares in the embed or as a chart (default: embed)"]
+        #[rename = "display"]
+        view: Option<ActivityDisplay>,
+        #[description = "Visible to you only? (default: true)"] ephemeral: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let ephemeral = resolve_ephemeral(ctx, ephemeral, true).await;
+        let _typing = defer_or_broadcast(ctx, ephemeral).await?;
+
+        let guild_id = require_guild_id(ctx)?;
+        let window = window.unwrap_or(ActivityWindow::Week);
+        let view = view.unwrap_or(ActivityDisplay::Embed);
+        let since_hour = hour_of(ctx.data().clock.unix_timestamp()) - window.hours() + 1;
+        let repo = ChannelActivityRepo::new(&ctx.data().db_pool);
+        let channels = repo.channel_totals(guild_id, since_hour).await?;
+        if channels.is_empty() {
+            return Err(format!("No messages were counted in the last {}.", window.as_str()).into());
+        }
+        let heatmap = weekly_heatmap(&repo.hourly_totals(guild_id, since_hour).await?);
+
+        let total: i64 = channels.iter().map(|(_, messages)| messages).sum();
+        let most_active = channels
+            .iter()
+            .take(LIST_LENGTH)
+            .enumerate()
+            .map(|(i, (channel_id, messages))| {
+                format!(
+                    "**{}.** {} — {} messages ({}%)",
+                    i + 1,
+                    channel_id.mention(),
+                    messages,
+                    messages * 100 / total
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        let branding = guild_branding(ctx).await;
+        let mut embed = branding
+            .embed()
+            .title("Channel Activity")
+            .description(format!(
+                "{} messages in {} channels over the last {}.",
+                total,
+                channels.len(),
+                window.as_str()
+            ))
+            .field("Most active channels", most_active, false);
+        let mut reply = CreateReply::default().ephemeral(ephemeral);
+        match view {
+            ActivityDisplay::Embed => {
+                embed = embed.field(
+                    "Busiest hours",
+                    format!(
+                        "{}\nEach square is an hour from 00:00 to 23:00 UTC, from quiet {} to busy \
+                        {}.",
+                        text_heatmap(&heatmap),
+                        HEATMAP_LEVELS[0],
+                        HEATMAP_LEVELS[HEATMAP_LEVELS.len() - 1]
+                    ),
+                    false,
+                );
+            }
+            ActivityDisplay::Chart => {
+                let chart = render_heatmap_chart(&heatmap)?;
+                let attachment = serenity::CreateAttachment::bytes(chart, "activity.png");
+                embed = embed
+                    .field(
+                        "Busiest hours",
+                        "Rows are the days from Sunday to Saturday, and columns the hours from 00:00 \
+                        to 23:00 UTC, with thicker lines every six hours. Darker cells are busier.",
+                        false,
+                    )
+                    .image(format!("attachment://{}", attachment.filename));
+                reply = reply.attachment(attachment);
+            }
+        }
+
+        let reply = reply.embed(embed);
+        trace!("Sending reply: {:?}", DebuggableReply::new(&reply));
+        ctx.send(reply).await?;
+        Ok(())
+    }
+}
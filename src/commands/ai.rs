@@ -0,0 +1,104 @@
+use std::env::var;
+
+use poise::CreateReply;
+
+use crate::{
+    Context, Error,
+    infrastructure::{
+        branding::guild_branding,
+        environment::{AI_API_KEY, ai_model},
+        ids::require_guild_id,
+    },
+    poise_instrument, record_ctx_fields,
+    services::ai_settings::AiSettingsRepo,
+};
+
+/// Set of commands to configure the conversational replies of the bot.
+#[poise::command(
+    slash_command,
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR",
+    guild_only,
+    category = "Management",
+    subcommands("configure", "status")
+)]
+pub async fn ai(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Lets the bot reply in character with a language model when it is mentioned.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn configure(
+        ctx: Context<'_>,
+        #[description = "Reply with the language model when the bot is mentioned?"] enabled: bool,
+        #[description = "Tokens the guild may use per day (default: 20000)"]
+        #[min = 1]
+        daily_token_budget: Option<u32>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let repo = AiSettingsRepo::new(&ctx.data().db_pool);
+        let now = ctx.data().clock.unix_timestamp();
+        let daily_token_budget = match daily_token_budget {
+            Some(x) => x,
+            None => repo.settings(guild_id, now).await?.daily_token_budget,
+        };
+        repo.configure(guild_id, enabled, daily_token_budget).await?;
+        let content = if enabled {
+            format!(
+                "The bot now replies when it is mentioned, using up to {} tokens per day.",
+                daily_token_budget
+            )
+        } else {
+            "The bot no longer replies with the language model.".to_string()
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
+
+poise_instrument! {
+    /// Shows whether conversational replies are enabled and how much of today's budget is used.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn status(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let now = ctx.data().clock.unix_timestamp();
+        let settings = AiSettingsRepo::new(&ctx.data().db_pool)
+            .settings(guild_id, now)
+            .await?;
+        let endpoint = if var(AI_API_KEY).is_ok() {
+            format!("Model `{}`", ai_model())
+        } else {
+            format!("⚠️ `{}` is not set, so the bot can't reply", AI_API_KEY)
+        };
+        let embed = guild_branding(ctx)
+            .await
+            .embed()
+            .title("Conversational replies")
+            .field("Enabled", if settings.enabled { "Yes" } else { "No" }, true)
+            .field(
+                "Tokens used today",
+                format!("{} / {}", settings.tokens_used, settings.daily_token_budget),
+                true,
+            )
+            .field("Language model", endpoint, false);
+        ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+use poise::CreateReply;
+use sea_orm::{ActiveValue::Set, ColumnTrait, EntityTrait, ModelTrait, QueryFilter, QueryOrder};
+
+use crate::{
+    Context, Error,
+    entities::aliases,
+    events::aliases::{get_alias, normalize_alias_command, normalize_alias_name},
+    infrastructure::{
+        branding::guild_branding,
+        ids::{id_to_string, require_guild_id},
+    },
+    poise_instrument, record_ctx_fields,
+};
+
+/// Set of commands to define shortcuts for prefix commands on this guild.
+#[poise::command(
+    slash_command,
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR",
+    guild_only,
+    category = "Management",
+    subcommands("add", "remove", "list")
+)]
+pub async fn alias(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+#[tracing::instrument(level = tracing::Level::TRACE, skip(ctx))]
+async fn alias_autocomplete<'a>(ctx: Context<'a>, partial: &'a str) -> Vec<String> {
+    let guild_id = match require_guild_id(ctx) {
+        Ok(id) => id,
+        Err(_) => return vec![],
+    };
+    aliases::Entity::find()
+        .filter(aliases::Column::GuildId.eq(id_to_string(guild_id)))
+        .filter(aliases::Column::Alias.starts_with(partial.to_lowercase()))
+        .order_by_asc(aliases::Column::Alias)
+        .all(&ctx.data().db_pool)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|x| x.alias)
+        .take(25)
+        .collect()
+}
+
+poise_instrument! {
+    /// Adds a shortcut for a prefix command, e.g. `d20` for `roll dice:D20`.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn add(
+        ctx: Context<'_>,
+        #[description = "Name of the shortcut"] alias: String,
+        #[description = "Command and arguments the shortcut runs"] command: String,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let options = ctx.framework().options();
+        let alias = normalize_alias_name(options, &alias)?;
+        let command = normalize_alias_command(options, &command)?;
+
+        if get_alias(&ctx.data().db_pool, guild_id, &alias).await?.is_some() {
+            return Err(format!("Alias '{}' already exists.", alias).into());
+        }
+
+        aliases::Entity::insert(aliases::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            alias: Set(alias.clone()),
+            command: Set(command.clone()),
+        })
+        .exec(&ctx.data().db_pool)
+        .await?;
+
+        let prefix = options.prefix_options.prefix.as_deref().unwrap_or_default();
+        ctx.send(
+            CreateReply::default()
+                .content(format!(
+                    "`{}{}` now runs `{}{}`",
+                    prefix, alias, prefix, command
+                ))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Removes a command shortcut.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn remove(
+        ctx: Context<'_>,
+        #[description = "Name of the shortcut"]
+        #[autocomplete = "alias_autocomplete"]
+        alias: String,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let model = get_alias(&ctx.data().db_pool, guild_id, &alias)
+            .await?
+            .ok_or::<Error>(format!("Alias '{}' not found.", alias).into())?;
+        model.delete(&ctx.data().db_pool).await?;
+
+        ctx.send(
+            CreateReply::default()
+                .content(format!("Removed alias '{}'", alias))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Lists the command shortcuts of this guild.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn list(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let aliases = aliases::Entity::find()
+            .filter(aliases::Column::GuildId.eq(id_to_string(guild_id)))
+            .order_by_asc(aliases::Column::Alias)
+            .all(&ctx.data().db_pool)
+            .await?;
+
+        let prefix = ctx
+            .framework()
+            .options()
+            .prefix_options
+            .prefix
+            .as_deref()
+            .unwrap_or_default();
+        let description = if aliases.is_empty() {
+            "No aliases configured".to_string()
+        } else {
+            aliases
+                .iter()
+                .map(|x| format!("`{}{}` → `{}{}`", prefix, x.alias, prefix, x.command))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        let branding = guild_branding(ctx).await;
+        let embed = branding.embed()
+            .title("Aliases")
+            .description(description);
+        ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
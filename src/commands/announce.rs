@@ -0,0 +1,250 @@
+use std::time::Duration;
+
+use poise::{
+    CreateReply,
+    serenity_prelude::{
+        self as serenity, ButtonStyle, ChannelType, CreateActionRow, CreateAllowedMentions,
+        CreateButton, CreateEmbed, CreateInteractionResponse, CreateMessage, GuildChannel, GuildId,
+        Mentionable, Role, RoleId, Timestamp,
+    },
+};
+use sea_orm::{ActiveValue::Set, EntityTrait};
+
+use crate::{
+    Context, Error,
+    entities::scheduled_announcement,
+    infrastructure::{
+        branding::{Branding, guild_branding},
+        ids::{id_to_string, require_guild_id},
+        util::parse_duration,
+    },
+    poise_instrument, record_ctx_fields,
+};
+
+/// Time the author has to confirm an announcement.
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Announcements can't be scheduled further ahead than this.
+const MAX_SCHEDULE_SECONDS: i64 = 60 * 60 * 24 * 365;
+
+/// Embed descriptions are limited to 4096 characters.
+const MAX_MESSAGE_LENGTH: usize = 4096;
+
+/// Builds the embed of an announcement. A literal `\n` in the message starts a new line, since slash command
+/// arguments can't contain line breaks.
+fn announcement_embed(message: &str, branding: &Branding) -> CreateEmbed {
+    branding
+        .embed()
+        .description(message.replace("\\n", "\n"))
+        .timestamp(Timestamp::now())
+}
+
+/// Builds an announcement, pinging only the given role (if any).
+pub fn announcement_message(
+    message: &str,
+    branding: &Branding,
+    guild_id: GuildId,
+    role: Option<RoleId>,
+) -> CreateMessage {
+    let message = CreateMessage::new().embed(announcement_embed(message, branding));
+    match role {
+        // The @everyone role shares its id with the guild
+        Some(role) if role.get() == guild_id.get() => message
+            .content("@everyone")
+            .allowed_mentions(CreateAllowedMentions::new().everyone(true)),
+        Some(role) => message
+            .content(role.mention().to_string())
+            .allowed_mentions(CreateAllowedMentions::new().roles(vec![role])),
+        None => message.allowed_mentions(CreateAllowedMentions::new()),
+    }
+}
+
+/// Checks that the author could post the announcement themselves: they can send messages in the channel, and can ping
+/// the role. The bot would otherwise let anyone with Manage Messages ping @everyone or post where they can't write.
+async fn check_author_permissions(
+    ctx: Context<'_>,
+    channel: &GuildChannel,
+    role: Option<&Role>,
+) -> Result<(), Error> {
+    let member = ctx
+        .author_member()
+        .await
+        .ok_or("Could not look up your permissions")?
+        .into_owned();
+    let permissions = {
+        let guild = ctx
+            .guild()
+            .ok_or("This server is not cached yet, try again in a moment")?;
+        guild.user_permissions_in(channel, &member)
+    };
+    if !permissions.view_channel() || !permissions.send_messages() {
+        return Err(format!("You can't send messages in {}", channel.mention()).into());
+    }
+    if let Some(role) = role
+        && !permissions.mention_everyone()
+    {
+        // The @everyone role shares its id with the guild
+        if role.id.get() == channel.guild_id.get() {
+            return Err("Pinging @everyone needs the Mention Everyone permission".into());
+        }
+        if !role.mentionable {
+            return Err(format!(
+                "{} can't be mentioned by everyone, pinging it needs the Mention Everyone permission",
+                role.mention()
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// Parses a schedule into a unix timestamp. Accepts a delay such as `30m`, `2h` or `1d12h`, or a UTC date and time
+/// in the form `YYYY-MM-DD HH:MM`.
+fn parse_schedule(value: &str, now: i64) -> Result<i64, Error> {
+    let value = value.trim();
+    let send_at = if let Some((date, time)) = value.split_once([' ', 'T']) {
+        Timestamp::parse(&format!("{}T{}:00Z", date.trim(), time.trim()))
+            .map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD HH:MM (UTC)", value))?
+            .unix_timestamp()
+    } else {
+        let seconds = parse_duration(value)
+            .ok_or_else(|| format!("Invalid delay '{}', expected e.g. 30m, 2h or 1d12h", value))?;
+        now.saturating_add(seconds)
+    };
+
+    if send_at <= now {
+        return Err("The scheduled time must be in the future".into());
+    }
+    if send_at - now > MAX_SCHEDULE_SECONDS {
+        return Err("Announcements can be scheduled at most a year ahead".into());
+    }
+    Ok(send_at)
+}
+
+poise_instrument! {
+    /// Posts an announcement embed to a channel, now or at a scheduled time
+    #[poise::command(
+        slash_command,
+        required_permissions = "MANAGE_MESSAGES",
+        default_member_permissions = "MANAGE_MESSAGES",
+        guild_only,
+        category = "Management"
+    )]
+    pub async fn announce(
+        ctx: Context<'_>,
+        #[description = "Channel to post the announcement in"]
+        #[channel_types("Text", "News")]
+        channel: GuildChannel,
+        #[description = "Announcement text, use \\n for a new line"] message: String,
+        #[description = "Role to ping with the announcement"]
+        #[rename = "ping-role"]
+        ping_role: Option<Role>,
+        #[description = "Delay such as 30m, 2h or 1d12h, or a UTC time as YYYY-MM-DD HH:MM (default: now)"]
+        schedule: Option<String>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        if channel.guild_id != guild_id
+            || !matches!(channel.kind, ChannelType::Text | ChannelType::News)
+        {
+            return Err("Announcements can only be posted to text or announcement channels of this server".into());
+        }
+        if message.trim().is_empty() {
+            return Err("The announcement can't be empty".into());
+        }
+        if message.chars().count() > MAX_MESSAGE_LENGTH {
+            return Err(format!("Announcements are limited to {} characters", MAX_MESSAGE_LENGTH).into());
+        }
+        check_author_permissions(ctx, &channel, ping_role.as_ref()).await?;
+        let send_at = match schedule {
+            Some(schedule) => Some(parse_schedule(&schedule, ctx.data().clock.unix_timestamp())?),
+            None => None,
+        };
+        let role = ping_role.map(|x| x.id);
+        let branding = guild_branding(ctx).await;
+
+        let mut summary = format!("Preview of the announcement to {}", channel.mention());
+        if let Some(role) = &role {
+            if role.get() == guild_id.get() {
+                summary.push_str(", pinging @everyone");
+            } else {
+                summary.push_str(&format!(", pinging {}", role.mention()));
+            }
+        }
+        match send_at {
+            Some(send_at) => summary.push_str(&format!(", scheduled for <t:{0}:f> (<t:{0}:R>)", send_at)),
+            None => summary.push_str(", sent immediately"),
+        }
+
+        let confirm_id = format!("{}confirm", ctx.id());
+        let cancel_id = format!("{}cancel", ctx.id());
+        let reply = CreateReply::default()
+            .content(summary)
+            .embed(announcement_embed(&message, &branding))
+            .components(vec![CreateActionRow::Buttons(vec![
+                CreateButton::new(&confirm_id)
+                    .label(if send_at.is_some() { "Schedule" } else { "Send" })
+                    .style(ButtonStyle::Primary),
+                CreateButton::new(&cancel_id)
+                    .label("Cancel")
+                    .style(ButtonStyle::Secondary),
+            ])])
+            .ephemeral(true);
+        let handle = ctx.send(reply).await?;
+
+        let ctx_id = ctx.id().to_string();
+        let interaction = serenity::ComponentInteractionCollector::new(ctx)
+            .author_id(ctx.author().id)
+            .channel_id(ctx.channel_id())
+            .timeout(CONFIRM_TIMEOUT)
+            .filter(move |mci| mci.data.custom_id.starts_with(&ctx_id))
+            .await;
+
+        let content = match interaction {
+            Some(interaction) if interaction.data.custom_id == confirm_id => {
+                interaction
+                    .create_response(ctx, CreateInteractionResponse::Acknowledge)
+                    .await?;
+                match send_at {
+                    Some(send_at) => {
+                        scheduled_announcement::Entity::insert(scheduled_announcement::ActiveModel {
+                            guild_id: Set(id_to_string(guild_id)),
+                            channel_id: Set(id_to_string(channel.id)),
+                            author_id: Set(id_to_string(ctx.author().id)),
+                            message: Set(message),
+                            role_id: Set(role.map(id_to_string).unwrap_or_default()),
+                            send_at: Set(send_at),
+                            ..Default::default()
+                        })
+                        .exec(&ctx.data().db_pool)
+                        .await?;
+                        format!("Scheduled the announcement in {} for <t:{}:f>.", channel.mention(), send_at)
+                    }
+                    None => {
+                        let sent = channel
+                            .id
+                            .send_message(ctx, announcement_message(&message, &branding, guild_id, role))
+                            .await?;
+                        format!("Posted the announcement: {}", sent.link())
+                    }
+                }
+            }
+            Some(interaction) => {
+                interaction
+                    .create_response(ctx, CreateInteractionResponse::Acknowledge)
+                    .await?;
+                "Cancelled, the announcement was not sent.".to_string()
+            }
+            None => "Timed out, the announcement was not sent.".to_string(),
+        };
+        handle
+            .edit(
+                ctx,
+                CreateReply::default()
+                    .content(content)
+                    .components(vec![]),
+            )
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,354 @@
+use poise::{
+    CreateReply,
+    serenity_prelude::{ChannelId, Guild, GuildId, Member, Mentionable, Permissions, RoleId},
+};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+    sea_query::Expr,
+};
+
+use crate::{
+    Context, Error,
+    entities::voice_sessions,
+    infrastructure::{
+        branding::guild_branding,
+        channel_validation::NOTIFICATION_PERMISSIONS,
+        ids::{id_from_string, id_to_string, require_guild_id},
+        mod_log::get_mod_log_channel,
+    },
+    poise_instrument, record_ctx_fields,
+    services::{mc_servers::McServerRepo, notifications::NotificationRepo, roles::RolesRepo},
+};
+
+/// Number of the most used voice channels of a guild checked by the audit.
+const AUDITED_VOICE_CHANNELS: u64 = 5;
+
+/// Maximum length of an embed field value.
+const MAX_FIELD_LENGTH: usize = 1024;
+
+/// Permissions the bot needs to play audio in a voice channel.
+const VOICE_PERMISSIONS: Permissions = Permissions::VIEW_CHANNEL
+    .union(Permissions::CONNECT)
+    .union(Permissions::SPEAK);
+
+/// Permissions the bot needs to rename a minecraft status channel.
+const STATUS_CHANNEL_PERMISSIONS: Permissions =
+    Permissions::VIEW_CHANNEL.union(Permissions::MANAGE_CHANNELS);
+
+/// Permissions the bot needs to react to messages, and to read the reactions used for translations.
+const REACTION_PERMISSIONS: Permissions = Permissions::ADD_REACTIONS
+    .union(Permissions::READ_MESSAGE_HISTORY)
+    .union(Permissions::USE_EXTERNAL_EMOJIS);
+
+/// Features configured on a guild that the bot needs permissions for.
+struct ConfiguredFeatures {
+    welcome_channel: Option<ChannelId>,
+    leave_channel: Option<ChannelId>,
+    mod_log_channel: Option<ChannelId>,
+    default_roles: Vec<RoleId>,
+    voice_channels: Vec<ChannelId>,
+    /// Name of each minecraft server with a status channel, and the channel.
+    status_channels: Vec<(String, ChannelId)>,
+}
+
+/// One line of the audit checklist.
+struct AuditCheck {
+    label: String,
+    /// How to fix the check, None if it passed.
+    hint: Option<String>,
+}
+
+impl AuditCheck {
+    fn pass(label: String) -> Self {
+        Self { label, hint: None }
+    }
+
+    fn fail(label: String, hint: String) -> Self {
+        Self {
+            label,
+            hint: Some(hint),
+        }
+    }
+
+    fn line(&self) -> String {
+        match &self.hint {
+            None => format!("✅ {}", self.label),
+            Some(hint) => format!("❌ {} — {}", self.label, hint),
+        }
+    }
+}
+
+/// A titled group of checks, shown as an embed field.
+struct AuditSection {
+    title: &'static str,
+    checks: Vec<AuditCheck>,
+}
+
+impl AuditSection {
+    /// Lines of the section, cut short to fit in an embed field.
+    fn field_value(&self) -> String {
+        if self.checks.is_empty() {
+            return "Nothing configured".to_string();
+        }
+        let mut value = String::new();
+        for (i, check) in self.checks.iter().enumerate() {
+            let line = check.line();
+            let more = format!("\n…and {} more", self.checks.len() - i);
+            if value.len() + line.len() + 1 + more.len() > MAX_FIELD_LENGTH {
+                value.push_str(&more);
+                break;
+            }
+            if !value.is_empty() {
+                value.push('\n');
+            }
+            value.push_str(&line);
+        }
+        value
+    }
+}
+
+/// Gets the voice channels of a guild with the most voice sessions, most used first.
+async fn most_used_voice_channels(
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+) -> Result<Vec<ChannelId>, Error> {
+    let channels: Vec<String> = voice_sessions::Entity::find()
+        .select_only()
+        .column(voice_sessions::Column::ChannelId)
+        .filter(voice_sessions::Column::GuildId.eq(id_to_string(guild_id)))
+        .group_by(voice_sessions::Column::ChannelId)
+        .order_by_desc(Expr::col(voice_sessions::Column::Id).count())
+        .limit(AUDITED_VOICE_CHANNELS)
+        .into_tuple()
+        .all(db)
+        .await?;
+    Ok(channels
+        .iter()
+        .filter_map(|x| id_from_string(x).ok())
+        .collect())
+}
+
+/// Checks that a channel exists and that the bot has the `required` permissions in it.
+fn check_channel(
+    guild: &Guild,
+    bot: &Member,
+    label: &str,
+    channel_id: ChannelId,
+    required: Permissions,
+) -> AuditCheck {
+    let label = format!("{} ({})", label, channel_id.mention());
+    let Some(channel) = guild.channels.get(&channel_id) else {
+        return AuditCheck::fail(
+            label,
+            "The channel no longer exists, configure another one".to_string(),
+        );
+    };
+    let missing = required - guild.user_permissions_in(channel, bot);
+    if missing.is_empty() {
+        AuditCheck::pass(label)
+    } else {
+        AuditCheck::fail(
+            label,
+            format!(
+                "Grant the bot {} in the channel",
+                missing.get_permission_names().join(", ")
+            ),
+        )
+    }
+}
+
+/// Checks that the bot can give a role to members.
+fn check_role(guild: &Guild, bot: &Member, role_id: RoleId) -> AuditCheck {
+    let label = format!("Default role {}", role_id.mention());
+    let Some(role) = guild.roles.get(&role_id) else {
+        return AuditCheck::fail(
+            label,
+            "The role no longer exists, remove it with `/remove_default_member_role`".to_string(),
+        );
+    };
+    if role.managed {
+        return AuditCheck::fail(
+            label,
+            "The role is managed by an integration and can't be given by the bot".to_string(),
+        );
+    }
+    if !guild
+        .member_permissions(bot)
+        .contains(Permissions::MANAGE_ROLES)
+    {
+        return AuditCheck::fail(label, "Grant the bot Manage Roles".to_string());
+    }
+    let bot_position = guild.member_highest_role(bot).map(|x| x.position);
+    if bot_position.is_none_or(|position| role.position >= position) {
+        return AuditCheck::fail(
+            label,
+            "Move the bot's highest role above this role".to_string(),
+        );
+    }
+    AuditCheck::pass(label)
+}
+
+/// Checks every configured feature against the permissions the bot has on the guild.
+fn audit_guild(guild: &Guild, bot: &Member, features: &ConfiguredFeatures) -> Vec<AuditSection> {
+    let mut channels = vec![];
+    if let Some(channel_id) = features.welcome_channel {
+        channels.push(check_channel(
+            guild,
+            bot,
+            "Welcome channel",
+            channel_id,
+            NOTIFICATION_PERMISSIONS,
+        ));
+    }
+    if let Some(channel_id) = features.leave_channel {
+        channels.push(check_channel(
+            guild,
+            bot,
+            "Leave channel",
+            channel_id,
+            NOTIFICATION_PERMISSIONS,
+        ));
+    }
+    if let Some(channel_id) = features.mod_log_channel {
+        channels.push(check_channel(
+            guild,
+            bot,
+            "Mod-log channel",
+            channel_id,
+            NOTIFICATION_PERMISSIONS,
+        ));
+    }
+    for (name, channel_id) in &features.status_channels {
+        channels.push(check_channel(
+            guild,
+            bot,
+            &format!("Status channel of '{}'", name),
+            *channel_id,
+            STATUS_CHANNEL_PERMISSIONS,
+        ));
+    }
+
+    let roles = features
+        .default_roles
+        .iter()
+        .map(|role_id| check_role(guild, bot, *role_id))
+        .collect();
+
+    let voice = if cfg!(feature = "voice") {
+        features
+            .voice_channels
+            .iter()
+            .map(|channel_id| check_channel(guild, bot, "Voice", *channel_id, VOICE_PERMISSIONS))
+            .collect()
+    } else {
+        vec![]
+    };
+
+    let missing = REACTION_PERMISSIONS - guild.member_permissions(bot);
+    let emojis = vec![if missing.is_empty() {
+        AuditCheck::pass("Reactions and emojis".to_string())
+    } else {
+        AuditCheck::fail(
+            "Reactions and emojis".to_string(),
+            format!(
+                "Grant the bot {} on the guild",
+                missing.get_permission_names().join(", ")
+            ),
+        )
+    }];
+
+    vec![
+        AuditSection {
+            title: "Channels",
+            checks: channels,
+        },
+        AuditSection {
+            title: "Default roles",
+            checks: roles,
+        },
+        AuditSection {
+            title: "Voice channels",
+            checks: voice,
+        },
+        AuditSection {
+            title: "Emojis",
+            checks: emojis,
+        },
+    ]
+}
+
+/// Set of commands to check the setup of the bot on this guild.
+#[poise::command(
+    slash_command,
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR",
+    guild_only,
+    category = "Management",
+    subcommands("permissions")
+)]
+pub async fn audit(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Checks that the bot has the permissions needed by every feature configured on this guild.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn permissions(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        ctx.defer_ephemeral().await?;
+
+        let db = &ctx.data().db_pool;
+        let notifications = NotificationRepo::new(db);
+        let guild_key = id_to_string(guild_id);
+        let features = ConfiguredFeatures {
+            welcome_channel: notifications.channel(guild_id, true).await?,
+            leave_channel: notifications.channel(guild_id, false).await?,
+            mod_log_channel: get_mod_log_channel(db, guild_id).await?,
+            default_roles: RolesRepo::new(db).default_roles(guild_id).await?,
+            voice_channels: most_used_voice_channels(db, guild_id).await?,
+            status_channels: McServerRepo::new(db)
+                .status_channels()
+                .await?
+                .into_iter()
+                .filter(|x| x.guild_id == guild_key)
+                .filter_map(|x| Some((x.name, id_from_string(&x.channel_id).ok()?)))
+                .collect(),
+        };
+
+        let sections = {
+            let guild = ctx
+                .guild()
+                .ok_or("This guild isn't cached yet, try again in a moment")?;
+            let bot = guild
+                .members
+                .get(&ctx.cache().current_user().id)
+                .ok_or("The bot's member isn't cached yet, try again in a moment")?;
+            audit_guild(&guild, bot, &features)
+        };
+
+        let total = sections.iter().map(|x| x.checks.len()).sum::<usize>();
+        let passed = sections
+            .iter()
+            .flat_map(|x| &x.checks)
+            .filter(|x| x.hint.is_none())
+            .count();
+        let mut embed = guild_branding(ctx)
+            .await
+            .embed()
+            .title("Permission audit")
+            .description(format!("{} of {} checks passed", passed, total));
+        for section in &sections {
+            embed = embed.field(section.title, section.field_value(), false);
+        }
+        ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,329 @@
+use migration::OnConflict;
+use poise::{
+    CreateReply,
+    serenity_prelude::{
+        ChannelId, GuildChannel, Mentionable,
+        futures::{self, Stream, StreamExt},
+    },
+};
+use sea_orm::{ActiveValue::Set, ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::{
+    Context, Error,
+    entities::{auto_response_channel, auto_response_toggle},
+    events::responders::{disabled_responders, responders},
+    infrastructure::{
+        branding::guild_branding,
+        ids::{id_from_string, id_to_string, require_guild_id},
+    },
+    poise_instrument, record_ctx_fields,
+};
+
+/// Set of commands to configure the bot's automatic replies to messages.
+#[poise::command(
+    slash_command,
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR",
+    guild_only,
+    category = "Management",
+    subcommands("channels", "responders_command")
+)]
+pub async fn autoresponse(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Set of commands to choose the channels automatic replies are sent in.
+#[poise::command(
+    slash_command,
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR",
+    guild_only,
+    category = "Management",
+    subcommands("allow", "deny", "reset", "list")
+)]
+async fn channels(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Set of commands to turn individual automatic replies on or off.
+#[poise::command(
+    slash_command,
+    rename = "responders",
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR",
+    guild_only,
+    category = "Management",
+    subcommands("enable_responder", "disable_responder", "list_responders")
+)]
+async fn responders_command(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+async fn responder_autocomplete<'a>(
+    _ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Stream<Item = String> + 'a {
+    let partial = partial.to_lowercase();
+    let names: Vec<String> = responders()
+        .iter()
+        .map(|responder| responder.name().to_string())
+        .filter(|name| name.contains(&partial))
+        .collect();
+    futures::stream::iter(names).boxed()
+}
+
+async fn set_responder_enabled(ctx: Context<'_>, name: &str, enabled: bool) -> Result<(), Error> {
+    let guild_id = require_guild_id(ctx)?;
+    let Some(responder) = responders().iter().find(|x| x.name() == name) else {
+        return Err(format!("There is no automatic reply named '{}'", name).into());
+    };
+    auto_response_toggle::Entity::insert(auto_response_toggle::ActiveModel {
+        guild_id: Set(id_to_string(guild_id)),
+        responder: Set(responder.name().to_string()),
+        enabled: Set(enabled),
+    })
+    .on_conflict(
+        OnConflict::columns([
+            auto_response_toggle::Column::GuildId,
+            auto_response_toggle::Column::Responder,
+        ])
+        .update_column(auto_response_toggle::Column::Enabled)
+        .to_owned(),
+    )
+    .exec(&ctx.data().db_pool)
+    .await?;
+    let state = if enabled { "enabled" } else { "disabled" };
+    ctx.send(
+        CreateReply::default()
+            .content(format!(
+                "Automatic reply '{}' is {}",
+                responder.name(),
+                state
+            ))
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+async fn set_channel_rule(
+    ctx: Context<'_>,
+    channel: &GuildChannel,
+    allow: bool,
+) -> Result<(), Error> {
+    let guild_id = require_guild_id(ctx)?;
+    auto_response_channel::Entity::insert(auto_response_channel::ActiveModel {
+        guild_id: Set(id_to_string(guild_id)),
+        channel_id: Set(id_to_string(channel.id)),
+        allow: Set(allow),
+    })
+    .on_conflict(
+        OnConflict::columns([
+            auto_response_channel::Column::GuildId,
+            auto_response_channel::Column::ChannelId,
+        ])
+        .update_column(auto_response_channel::Column::Allow)
+        .to_owned(),
+    )
+    .exec(&ctx.data().db_pool)
+    .await?;
+    Ok(())
+}
+
+poise_instrument! {
+    /// Allows automatic replies in a channel. Once any channel is allowed, other channels are ignored.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn allow(
+        ctx: Context<'_>,
+        #[description = "Channel to allow automatic replies in"] channel: GuildChannel,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        set_channel_rule(ctx, &channel, true).await?;
+        ctx.send(
+            CreateReply::default()
+                .content(format!("Automatic replies are allowed in {}", channel.mention()))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Stops automatic replies in a channel.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn deny(
+        ctx: Context<'_>,
+        #[description = "Channel to stop automatic replies in"] channel: GuildChannel,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        set_channel_rule(ctx, &channel, false).await?;
+        ctx.send(
+            CreateReply::default()
+                .content(format!("Automatic replies are denied in {}", channel.mention()))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Removes the allow or deny rule of a channel.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn reset(
+        ctx: Context<'_>,
+        #[description = "Channel to remove the rule of"] channel: GuildChannel,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let result =
+            auto_response_channel::Entity::delete_by_id((id_to_string(guild_id), id_to_string(channel.id)))
+                .exec(&ctx.data().db_pool)
+                .await?;
+        let content = if result.rows_affected > 0 {
+            format!("Removed the automatic reply rule of {}", channel.mention())
+        } else {
+            format!("{} has no automatic reply rule", channel.mention())
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Lists the channels automatic replies are allowed or denied in.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn list(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let rules = auto_response_channel::Entity::find()
+            .filter(auto_response_channel::Column::GuildId.eq(id_to_string(guild_id)))
+            .all(&ctx.data().db_pool)
+            .await?;
+
+        let mentions = |allow: bool| -> String {
+            let channels = rules
+                .iter()
+                .filter(|rule| rule.allow == allow)
+                .filter_map(|rule| id_from_string::<ChannelId>(&rule.channel_id).ok())
+                .map(|id| id.mention().to_string())
+                .collect::<Vec<_>>();
+            if channels.is_empty() {
+                "None".to_string()
+            } else {
+                channels.join(", ")
+            }
+        };
+        let description = if rules.iter().any(|rule| rule.allow) {
+            "Automatic replies are only sent in allowed channels."
+        } else {
+            "Automatic replies are sent in every channel that is not denied."
+        };
+        let branding = guild_branding(ctx).await;
+        let embed = branding.embed()
+            .title("Automatic Reply Channels")
+            .description(description)
+            .field("Allowed", mentions(true), false)
+            .field("Denied", mentions(false), false);
+        ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Turns an automatic reply back on.
+    #[poise::command(
+        slash_command,
+        rename = "enable",
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn enable_responder(
+        ctx: Context<'_>,
+        #[description = "Automatic reply to turn on"]
+        #[autocomplete = "responder_autocomplete"]
+        name: String,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        set_responder_enabled(ctx, &name, true).await
+    }
+
+    /// Turns an automatic reply off.
+    #[poise::command(
+        slash_command,
+        rename = "disable",
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn disable_responder(
+        ctx: Context<'_>,
+        #[description = "Automatic reply to turn off"]
+        #[autocomplete = "responder_autocomplete"]
+        name: String,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        set_responder_enabled(ctx, &name, false).await
+    }
+
+    /// Lists the automatic replies and whether they are on.
+    #[poise::command(
+        slash_command,
+        rename = "list",
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn list_responders(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let disabled = disabled_responders(&ctx.data().db_pool, guild_id).await?;
+        let description = responders()
+            .iter()
+            .map(|responder| {
+                let state = if disabled.contains(responder.name()) {
+                    "❌"
+                } else {
+                    "✅"
+                };
+                format!(
+                    "{} **{}** – {}",
+                    state,
+                    responder.name(),
+                    responder.description()
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+        let branding = guild_branding(ctx).await;
+        let embed = branding.embed()
+            .title("Automatic Replies")
+            .description(description);
+        ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
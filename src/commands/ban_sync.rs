@@ -0,0 +1,204 @@
+use poise::{
+    CreateReply,
+    serenity_prelude::{GuildChannel, GuildId, Mentionable, UserId},
+};
+
+use crate::{
+    Context, Error,
+    infrastructure::{
+        branding::guild_branding,
+        channel_validation::check_notification_channel,
+        ids::{id_from_string, id_to_string, require_guild_id},
+    },
+    poise_instrument, record_ctx_fields,
+    services::ban_sync::{BanSyncMembership, BanSyncRepo, group_key},
+};
+
+/// Minimum length of the shared secret of a sync group, so groups can't be joined by guessing it.
+const MIN_SECRET_LENGTH: usize = 16;
+
+/// Number of synced bans shown by `/bansync status`.
+const RECENT_BANS: u64 = 10;
+
+/// How bans shared by partner guilds are handled.
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum BanSyncMode {
+    /// Partner bans are applied immediately.
+    #[name = "auto"]
+    Auto,
+    /// Partner bans are proposed in the review channel, and applied once a moderator approves them.
+    #[name = "approve"]
+    Approve,
+}
+
+/// Set of commands to share bans with partner guilds.
+#[poise::command(
+    slash_command,
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR",
+    guild_only,
+    category = "Management",
+    subcommands("join", "leave", "status")
+)]
+pub async fn bansync(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Joins the ban sync group of partner guilds that use the same secret.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn join(
+        ctx: Context<'_>,
+        #[description = "Secret shared with the partner guilds, at least 16 characters"]
+        secret: String,
+        #[description = "auto: apply partner bans immediately, approve: propose them in the review channel"]
+        mode: BanSyncMode,
+        #[description = "Channel partner bans are proposed in, required for the approve mode"]
+        review_channel: Option<GuildChannel>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        if secret.chars().count() < MIN_SECRET_LENGTH {
+            return Err(
+                format!("The secret must be at least {} characters long", MIN_SECRET_LENGTH).into()
+            );
+        }
+        if mode == BanSyncMode::Approve && review_channel.is_none() {
+            return Err("The approve mode needs a review channel".into());
+        }
+        if let Some(channel) = &review_channel {
+            check_notification_channel(ctx.cache(), channel)?;
+        }
+
+        let repo = BanSyncRepo::new(&ctx.data().db_pool);
+        let membership = BanSyncMembership {
+            group_key: group_key(&secret),
+            auto_apply: mode == BanSyncMode::Auto,
+            review_channel: review_channel.map(|x| x.id),
+        };
+        repo.join(guild_id, &membership).await?;
+        let partners = repo.partners(&membership.group_key, guild_id).await?.len();
+        ctx.send(
+            CreateReply::default()
+                .content(format!(
+                    "Joined the ban sync group with {} partner guild(s). \
+                    Bans on this guild are now shared with them.",
+                    partners
+                ))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+poise_instrument! {
+    /// Leaves the ban sync group, so bans are no longer shared with partner guilds.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn leave(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let content = if BanSyncRepo::new(&ctx.data().db_pool).leave(guild_id).await? {
+            "Left the ban sync group."
+        } else {
+            "This guild is not part of a ban sync group."
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
+
+poise_instrument! {
+    /// Shows the ban sync group of this guild and the latest synced bans.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn status(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let repo = BanSyncRepo::new(&ctx.data().db_pool);
+        let Some(membership) = repo.membership(guild_id).await? else {
+            ctx.send(
+                CreateReply::default()
+                    .content("This guild is not part of a ban sync group.")
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        };
+
+        let partners = repo.partners(&membership.group_key, guild_id).await?.len();
+        let mode = if membership.auto_apply {
+            "Partner bans are applied automatically".to_string()
+        } else {
+            format!(
+                "Partner bans are proposed in {}",
+                membership
+                    .review_channel
+                    .map(|x| x.mention().to_string())
+                    .unwrap_or("no channel".to_string())
+            )
+        };
+        let own_id = id_to_string(guild_id);
+        let bans = repo
+            .recent(guild_id, RECENT_BANS)
+            .await?
+            .into_iter()
+            .map(|x| {
+                let user = id_from_string::<UserId>(&x.user_id)
+                    .map(|x| x.mention().to_string())
+                    .unwrap_or(x.user_id.clone());
+                let direction = if x.source_guild_id == own_id {
+                    format!("to {}", describe_guild(ctx, &x.target_guild_id))
+                } else {
+                    format!("from {}", describe_guild(ctx, &x.source_guild_id))
+                };
+                format!(
+                    "#{} {} {}: {} <t:{}:R>",
+                    x.id, user, direction, x.status, x.created_at
+                )
+            })
+            .collect::<Vec<_>>();
+        let bans = if bans.is_empty() {
+            "No bans were synced yet".to_string()
+        } else {
+            bans.join("\n")
+        };
+
+        let embed = guild_branding(ctx)
+            .await
+            .embed()
+            .title("Ban sync")
+            .field("Partner guilds", partners.to_string(), true)
+            .field("Mode", mode, true)
+            .field("Recent synced bans", bans, false);
+        ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
+
+/// Gets the name of a guild by its stored id, falling back to the id if it isn't cached.
+fn describe_guild(ctx: Context<'_>, guild_id: &str) -> String {
+    id_from_string::<GuildId>(guild_id)
+        .ok()
+        .and_then(|x| x.name(ctx.cache()))
+        .unwrap_or(guild_id.to_string())
+}
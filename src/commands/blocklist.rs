@@ -0,0 +1,130 @@
+use poise::{
+    CreateReply,
+    serenity_prelude::{Mentionable, User},
+};
+use sea_orm::{ActiveValue::Set, EntityTrait, QueryOrder};
+
+use crate::{
+    Context, Error,
+    entities::blocked_user,
+    infrastructure::{
+        embed_limits::{MessagePart, split_lines},
+        ids::id_to_string,
+    },
+    poise_instrument, record_ctx_fields,
+};
+
+/// Set of commands to block users from interacting with the bot.
+#[poise::command(
+    slash_command,
+    prefix_command,
+    owners_only,
+    hide_in_help,
+    subcommands("add", "remove", "list")
+)]
+pub async fn blocklist(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Blocks a user from using commands and receiving automatic replies.
+    #[poise::command(slash_command, prefix_command, owners_only, hide_in_help)]
+    async fn add(
+        ctx: Context<'_>,
+        #[description = "User to block"] user: User,
+        #[description = "Reason for the block"]
+        #[rest]
+        reason: Option<String>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        if ctx.framework().options().owners.contains(&user.id) {
+            return Err("Bot owners can't be blocked.".into());
+        }
+
+        let existing = blocked_user::Entity::find_by_id(id_to_string(user.id))
+            .one(&ctx.data().db_pool)
+            .await?;
+        if existing.is_some() {
+            return Err(format!("{} is already blocked.", user.name).into());
+        }
+
+        blocked_user::Entity::insert(blocked_user::ActiveModel {
+            user_id: Set(id_to_string(user.id)),
+            reason: Set(reason.unwrap_or_default()),
+            blocked_at: Set(ctx.data().clock.unix_timestamp()),
+        })
+        .exec(&ctx.data().db_pool)
+        .await?;
+
+        ctx.send(
+            CreateReply::default()
+                .content(format!("Blocked {}", user.mention()))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Unblocks a user.
+    #[poise::command(slash_command, prefix_command, owners_only, hide_in_help)]
+    async fn remove(
+        ctx: Context<'_>,
+        #[description = "User to unblock"] user: User,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let result = blocked_user::Entity::delete_by_id(id_to_string(user.id))
+            .exec(&ctx.data().db_pool)
+            .await?;
+        if result.rows_affected == 0 {
+            return Err(format!("{} is not blocked.", user.name).into());
+        }
+
+        ctx.send(
+            CreateReply::default()
+                .content(format!("Unblocked {}", user.mention()))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Lists the blocked users.
+    #[poise::command(slash_command, prefix_command, owners_only, hide_in_help)]
+    async fn list(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let blocked = blocked_user::Entity::find()
+            .order_by_asc(blocked_user::Column::BlockedAt)
+            .all(&ctx.data().db_pool)
+            .await?;
+
+        if blocked.is_empty() {
+            ctx.send(
+                CreateReply::default()
+                    .content("No users are blocked.")
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let lines: Vec<String> = blocked
+            .iter()
+            .map(|model| {
+                if model.reason.is_empty() {
+                    format!("<@{}> <t:{}:d>", model.user_id, model.blocked_at)
+                } else {
+                    format!(
+                        "<@{}> <t:{}:d> - {}",
+                        model.user_id, model.blocked_at, model.reason
+                    )
+                }
+            })
+            .collect();
+        // Long blocklists are sent over several messages
+        for content in split_lines(&lines, MessagePart::Content.limit()) {
+            ctx.send(CreateReply::default().content(content).ephemeral(true))
+                .await?;
+        }
+        Ok(())
+    }
+}
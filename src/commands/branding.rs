@@ -0,0 +1,152 @@
+use migration::OnConflict;
+use poise::CreateReply;
+use sea_orm::{ActiveValue::Set, EntityTrait};
+
+use crate::{
+    Context, Error,
+    entities::guild_branding,
+    infrastructure::{
+        branding::{guild_branding, invalidate_branding, load_branding},
+        colors::parse_color,
+        ids::{id_to_string, require_guild_id},
+    },
+    poise_instrument, record_ctx_fields,
+};
+
+/// Maximum length of the nickname of a guild member.
+const MAX_NICKNAME_LENGTH: usize = 32;
+
+/// Set of commands to style the bot's embeds and name on this guild.
+#[poise::command(
+    slash_command,
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR",
+    guild_only,
+    category = "Management",
+    subcommands("set", "reset", "show")
+)]
+pub async fn branding(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+async fn send_preview(ctx: Context<'_>, content: &str) -> Result<(), Error> {
+    let branding = guild_branding(ctx).await;
+    let embed = branding
+        .embed()
+        .title("Branding preview")
+        .description("Embeds sent by the bot on this guild look like this.");
+    ctx.send(
+        CreateReply::default()
+            .content(content)
+            .embed(embed)
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+poise_instrument! {
+    /// Sets the accent color, footer and nickname used by the bot on this guild.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn set(
+        ctx: Context<'_>,
+        #[description = "Accent color of embeds, as a hex color like #3E6775"] color: Option<String>,
+        #[description = "Footer text of embeds"]
+        #[max_length = 2048]
+        footer: Option<String>,
+        #[description = "Nickname of the bot on this guild"]
+        #[max_length = 32]
+        nickname: Option<String>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        if color.is_none() && footer.is_none() && nickname.is_none() {
+            return Err("Set at least one of color, footer or nickname".into());
+        }
+
+        let color = color.map(|x| parse_color(&x)).transpose()?;
+        let nickname = nickname.map(|x| x.trim().to_string()).filter(|x| !x.is_empty());
+        if let Some(nickname) = &nickname {
+            if nickname.chars().count() > MAX_NICKNAME_LENGTH {
+                return Err(format!(
+                    "Nicknames are limited to {} characters",
+                    MAX_NICKNAME_LENGTH
+                )
+                .into());
+            }
+            guild_id.edit_nickname(ctx, Some(nickname)).await?;
+        }
+
+        let db = &ctx.data().db_pool;
+        let current = load_branding(db, guild_id).await?;
+        guild_branding::Entity::insert(guild_branding::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            accent_color: Set(Some(color.unwrap_or(current.accent).0 as i64)),
+            footer: Set(footer.or(current.footer)),
+            nickname: Set(nickname.or(current.nickname)),
+        })
+        .on_conflict(
+            OnConflict::column(guild_branding::Column::GuildId)
+                .update_columns([
+                    guild_branding::Column::AccentColor,
+                    guild_branding::Column::Footer,
+                    guild_branding::Column::Nickname,
+                ])
+                .to_owned(),
+        )
+        .exec(db)
+        .await?;
+        invalidate_branding(&ctx.data().branding, guild_id);
+
+        send_preview(ctx, "Branding updated").await
+    }
+
+    /// Restores the default color, removes the footer and resets the bot's nickname on this guild.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn reset(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let db = &ctx.data().db_pool;
+        if load_branding(db, guild_id).await?.nickname.is_some() {
+            guild_id.edit_nickname(ctx, None).await?;
+        }
+        guild_branding::Entity::delete_by_id(id_to_string(guild_id))
+            .exec(db)
+            .await?;
+        invalidate_branding(&ctx.data().branding, guild_id);
+
+        send_preview(ctx, "Branding reset to the defaults").await
+    }
+
+    /// Previews the embeds of the bot on this guild.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn show(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let branding = guild_branding(ctx).await;
+        let content = format!(
+            "Accent color: `#{:06X}`\nFooter: {}\nNickname: {}",
+            branding.accent.0,
+            branding.footer.as_deref().unwrap_or("None"),
+            branding.nickname.as_deref().unwrap_or("None")
+        );
+        send_preview(ctx, &content).await
+    }
+}
@@ -1,6 +1,76 @@
-use poise::samples::HelpConfiguration;
+use std::collections::BTreeMap;
 
-use crate::{Context, Error, poise_instrument, record_ctx_fields};
+use poise::{samples::HelpConfiguration, serenity_prelude::CreateEmbed};
+
+use crate::{
+    Context, Error,
+    infrastructure::{
+        botdata::Data,
+        branding::{Branding, guild_branding},
+        embed_limits::{MAX_EMBED_LENGTH, MessagePart, split_lines},
+        util::Paginator,
+    },
+    poise_instrument, record_ctx_fields,
+};
+
+/// Category shown for commands that don't specify one.
+const DEFAULT_CATEGORY: &str = "General";
+
+/// Hint shown at the bottom of every help page.
+const HELP_HINT: &str = "Use `/help <command>` for more information on a command.";
+
+/// Lists a command and its subcommands as `/name - description` lines.
+fn command_lines(command: &poise::Command<Data, Error>, parent: &str, lines: &mut Vec<String>) {
+    if command.hide_in_help {
+        return;
+    }
+    let name = format!("{}{} ", parent, command.name);
+    if command.subcommands.is_empty() {
+        lines.push(format!(
+            "`/{}` – {}",
+            name.trim_end(),
+            command.description.as_deref().unwrap_or_default()
+        ));
+    }
+    for subcommand in &command.subcommands {
+        command_lines(subcommand, &name, lines);
+    }
+}
+
+/// Builds the help pages of each command category, splitting categories with too many commands for one embed.
+fn help_pages(commands: &[poise::Command<Data, Error>], branding: &Branding) -> Vec<CreateEmbed> {
+    let mut categories: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for command in commands {
+        let category = command.category.as_deref().unwrap_or(DEFAULT_CATEGORY);
+        command_lines(command, "", categories.entry(category).or_default());
+    }
+
+    // Pages also fit the total length of an embed along with their title and the guild's footer
+    let footer = branding.footer.as_deref().map_or(0, |x| x.chars().count());
+    let max_length = MessagePart::Description
+        .limit()
+        .min(MAX_EMBED_LENGTH - footer - MessagePart::Title.limit())
+        - HELP_HINT.len()
+        - 2;
+    let mut pages = vec![];
+    for (category, lines) in categories {
+        let texts = split_lines(&lines, max_length);
+        let count = texts.len();
+        for (i, text) in texts.into_iter().enumerate() {
+            let title = match count {
+                1 => format!("{} Commands", category),
+                _ => format!("{} Commands ({}/{})", category, i + 1, count),
+            };
+            pages.push(
+                branding
+                    .embed()
+                    .title(title)
+                    .description(format!("{}\n\n{}", text, HELP_HINT)),
+            );
+        }
+    }
+    pages
+}
 
 poise_instrument! {
     /// Registers/unregisters commands for this guild or all guilds.
@@ -29,7 +99,14 @@ poise_instrument! {
     )]
     pub async fn help(ctx: Context<'_>, command: Option<String>) -> Result<(), Error> {
         record_ctx_fields!(ctx);
-        poise::builtins::help(ctx, command.as_deref(), HelpConfiguration::default()).await?;
-        Ok(())
+        if command.is_some() {
+            poise::builtins::help(ctx, command.as_deref(), HelpConfiguration::default()).await?;
+            return Ok(());
+        }
+        let branding = guild_branding(ctx).await;
+        Paginator::new(help_pages(&ctx.framework().options().commands, &branding))
+            .ephemeral(true)
+            .send(ctx)
+            .await
     }
 }
@@ -0,0 +1,228 @@
+use poise::{
+    CreateReply,
+    serenity_prelude::{
+        CreateAttachment, User,
+        futures::{self, Stream, StreamExt},
+    },
+};
+use tracing::{info, trace};
+
+use crate::{
+    Context, Error,
+    infrastructure::{
+        branding::guild_branding,
+        character_sheets::{
+            MAX_FIELDS, MAX_VALUE_LENGTH, normalize_field, sheet_embed, sheet_text, template_fields,
+        },
+        ids::require_guild_id,
+        util::{DebuggableReply, defer_or_broadcast, resolve_ephemeral},
+    },
+    poise_instrument, record_ctx_fields,
+    services::character_sheets::CharacterSheetRepo,
+};
+
+#[derive(Debug, poise::ChoiceParameter, Clone, Copy, PartialEq)]
+enum SheetFormat {
+    #[name = "JSON"]
+    Json,
+    #[name = "Markdown"]
+    Markdown,
+}
+
+/// Suggests the fields of the sheet template and of the sheet of the invoking user.
+async fn field_autocomplete<'a>(
+    ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Stream<Item = String> + 'a {
+    let partial = partial.trim().to_lowercase();
+    let mut fields: Vec<String> = template_fields().map(|x| x.to_string()).collect();
+    if let Some(guild_id) = ctx.guild_id()
+        && let Ok(sheet) = CharacterSheetRepo::new(&ctx.data().db_pool)
+            .fields(guild_id, ctx.author().id)
+            .await
+    {
+        for (field, _) in sheet {
+            if !fields.contains(&field) {
+                fields.push(field);
+            }
+        }
+    }
+    let names: Vec<String> = fields
+        .into_iter()
+        .filter(|x| x.contains(&partial))
+        .take(25)
+        .collect();
+    futures::stream::iter(names).boxed()
+}
+
+/// Set of commands to keep a quick reference of your tabletop character on this server.
+#[poise::command(
+    slash_command,
+    guild_only,
+    category = "Fun",
+    subcommands("set", "unset", "show", "export", "clear")
+)]
+pub async fn character(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Sets a field of your character sheet, such as class, level, hp or str
+    #[poise::command(slash_command, guild_only, category = "Fun")]
+    async fn set(
+        ctx: Context<'_>,
+        #[description = "Field to set, such as name, class, level, hp, ac or str"]
+        #[autocomplete = "field_autocomplete"]
+        field: String,
+        #[description = "Value of the field"] value: String,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let field = normalize_field(&field)?;
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(format!("Values can't be empty, use `/character unset {}` instead", field)
+                .into());
+        }
+        if value.chars().count() > MAX_VALUE_LENGTH {
+            return Err(format!("Values are limited to {} characters", MAX_VALUE_LENGTH).into());
+        }
+
+        let repo = CharacterSheetRepo::new(&ctx.data().db_pool);
+        let user_id = ctx.author().id;
+        let sheet = repo.fields(guild_id, user_id).await?;
+        if !sheet.iter().any(|(x, _)| *x == field) && sheet.len() >= MAX_FIELDS {
+            return Err(format!(
+                "Character sheets are limited to {} fields, unset one first",
+                MAX_FIELDS
+            )
+            .into());
+        }
+        let now = ctx.data().clock.unix_timestamp();
+        repo.set(guild_id, user_id, &field, value, now).await?;
+        ctx.send(
+            CreateReply::default()
+                .content(format!("Set **{}** to {}.", field, value))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Removes a field from your character sheet
+    #[poise::command(slash_command, guild_only, category = "Fun")]
+    async fn unset(
+        ctx: Context<'_>,
+        #[description = "Field to remove"]
+        #[autocomplete = "field_autocomplete"]
+        field: String,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let field = normalize_field(&field)?;
+        let removed = CharacterSheetRepo::new(&ctx.data().db_pool)
+            .remove(guild_id, ctx.author().id, &field)
+            .await?;
+        if !removed {
+            return Err(format!("Your character sheet has no {} field", field).into());
+        }
+        ctx.send(
+            CreateReply::default()
+                .content(format!("Removed **{}** from your character sheet.", field))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Shows the character sheet of a member
+    #[poise::command(slash_command, guild_only, category = "Fun")]
+    async fn show(
+        ctx: Context<'_>,
+        #[description = "Member to show the character of (default: you)"] user: Option<User>,
+        #[description = "Visible to you only? (default: false)"] ephemeral: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let ephemeral = resolve_ephemeral(ctx, ephemeral, false).await;
+        let _typing = defer_or_broadcast(ctx, ephemeral).await?;
+
+        let guild_id = require_guild_id(ctx)?;
+        let user = user.as_ref().unwrap_or(ctx.author());
+        let fields = CharacterSheetRepo::new(&ctx.data().db_pool)
+            .fields(guild_id, user.id)
+            .await?;
+        if fields.is_empty() {
+            return Err(format!(
+                "{} has no character sheet on this server, start one with /character set",
+                user.display_name()
+            )
+            .into());
+        }
+
+        let branding = guild_branding(ctx).await;
+        let embed = sheet_embed(branding.embed(), user.display_name(), &fields);
+        let reply = CreateReply::default().embed(embed).ephemeral(ephemeral);
+        trace!("Sending reply: {:?}", DebuggableReply::new(&reply));
+        ctx.send(reply).await?;
+        Ok(())
+    }
+
+    /// Exports your character sheet as a file
+    #[poise::command(slash_command, guild_only, category = "Fun")]
+    async fn export(
+        ctx: Context<'_>,
+        #[description = "File format (default: Markdown)"] format: Option<SheetFormat>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let fields = CharacterSheetRepo::new(&ctx.data().db_pool)
+            .fields(guild_id, ctx.author().id)
+            .await?;
+        if fields.is_empty() {
+            return Err("You have no character sheet on this server to export".into());
+        }
+
+        let file = match format.unwrap_or(SheetFormat::Markdown) {
+            SheetFormat::Json => {
+                let sheet: serde_json::Map<String, serde_json::Value> = fields
+                    .iter()
+                    .map(|(field, value)| (field.clone(), value.clone().into()))
+                    .collect();
+                CreateAttachment::bytes(serde_json::to_vec_pretty(&sheet)?, "character.json")
+            }
+            SheetFormat::Markdown => CreateAttachment::bytes(
+                sheet_text(ctx.author().display_name(), &fields).into_bytes(),
+                "character.md",
+            ),
+        };
+        ctx.send(
+            CreateReply::default()
+                .content(format!("Exported {} field(s).", fields.len()))
+                .attachment(file)
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Deletes your whole character sheet on this server
+    #[poise::command(slash_command, guild_only, category = "Fun")]
+    async fn clear(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let deleted = CharacterSheetRepo::new(&ctx.data().db_pool)
+            .clear(guild_id, ctx.author().id)
+            .await?;
+        if deleted == 0 {
+            return Err("You have no character sheet on this server".into());
+        }
+        info!("{} cleared their character sheet on guild {}", ctx.author().id, guild_id);
+        ctx.send(
+            CreateReply::default()
+                .content(format!("Deleted your character sheet ({} field(s)).", deleted))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -1,20 +1,111 @@
-use poise::{CreateReply, serenity_prelude::CreateEmbed};
-use rand::Rng;
+use poise::{CreateReply, serenity_prelude::Message};
 
 use crate::{
     Context, Error,
     infrastructure::{
-        colors,
-        util::{DebuggableReply, defer_or_broadcast},
+        branding::guild_branding,
+        guild_media::{COIN_EDGE, COIN_HEADS, COIN_TAILS, media_attachment},
+        random::RandomSource,
+        recent_invocations::{Invocation, get_invocation, record_invocation},
+        util::{DebuggableReply, defer_or_broadcast, resolve_ephemeral},
     },
     poise_instrument, record_ctx_fields,
+    services::coinflip::{CoinflipRepo, CoinflipSettings},
 };
 
-fn do_flip(probability: Option<f64>) -> bool {
-    let mut rand = rand::rng();
-    let p = probability.unwrap_or(0.5);
-    let value = rand.random_bool(p);
-    value
+#[derive(Debug, poise::ChoiceParameter, Clone, Copy, PartialEq)]
+pub enum CoinSide {
+    Heads,
+    Tails,
+    Edge,
+}
+
+impl CoinSide {
+    /// Key of the image of the side, see [`crate::infrastructure::guild_media`].
+    pub fn media_key(&self) -> &'static str {
+        match self {
+            CoinSide::Heads => COIN_HEADS,
+            CoinSide::Tails => COIN_TAILS,
+            CoinSide::Edge => COIN_EDGE,
+        }
+    }
+}
+
+fn do_flip(
+    random: &dyn RandomSource,
+    probability: Option<f64>,
+    settings: &CoinflipSettings,
+) -> CoinSide {
+    if settings.edge_odds > 0 && random.random_bool(settings.edge_probability()) {
+        return CoinSide::Edge;
+    }
+    if random.random_bool(probability.unwrap_or(0.5)) {
+        CoinSide::Heads
+    } else {
+        CoinSide::Tails
+    }
+}
+
+fn make_description(
+    side: CoinSide,
+    probability: Option<f64>,
+    settings: &CoinflipSettings,
+) -> String {
+    let (label, p) = match side {
+        CoinSide::Heads => (settings.heads(), probability),
+        CoinSide::Tails => (settings.tails(), probability.map(|p| 1.0 - p)),
+        CoinSide::Edge => {
+            return format!(
+                "It landed on its edge! (1 in {} chance)",
+                settings.edge_odds
+            );
+        }
+    };
+    format!(
+        "It's {} {}",
+        label,
+        if let Some(p) = p {
+            format!("(p={})", p)
+        } else {
+            "".into()
+        }
+    )
+}
+
+/// Flips a coin and sends the result, recording it so it can be flipped again from the message.
+async fn send_flip(
+    ctx: Context<'_>,
+    probability: Option<f64>,
+    ephemeral: bool,
+) -> Result<(), Error> {
+    let db = &ctx.data().db_pool;
+    let settings = match ctx.guild_id() {
+        Some(guild_id) => CoinflipRepo::new(db).settings(guild_id).await?,
+        None => CoinflipSettings::default(),
+    };
+    let side = do_flip(ctx.data().random.as_ref(), probability, &settings);
+    let branding = guild_branding(ctx).await;
+    let mut embed = branding
+        .embed()
+        .title("Coin Flip")
+        .description(make_description(side, probability, &settings));
+    let mut reply = CreateReply::default().ephemeral(ephemeral);
+    // Coin faces aren't bundled, so only guilds that uploaded theirs get an image
+    if let Some(attachment) = media_attachment(db, ctx.guild_id(), side.media_key()).await {
+        embed = embed.thumbnail(format!("attachment://{}", attachment.filename));
+        reply = reply.attachment(attachment);
+    }
+    let reply = reply.embed(embed);
+
+    tracing::trace!("Sending reply: {:?}", DebuggableReply::new(&reply));
+    let handle = ctx.send(reply).await?;
+    let message_id = handle.message().await?.id;
+    record_invocation(
+        &ctx.data().recent_invocations,
+        message_id,
+        Invocation::Coinflip { probability },
+    );
+    Ok(())
 }
 
 poise_instrument! {
@@ -32,7 +123,8 @@ poise_instrument! {
         #[description = "Probability of heads (default: 0.5)"] probability: Option<f64>,
     ) -> Result<(), Error> {
         record_ctx_fields!(ctx);
-        let _typing = defer_or_broadcast(ctx, ephemeral.unwrap_or_default()).await?;
+        let ephemeral = resolve_ephemeral(ctx, ephemeral, false).await;
+        let _typing = defer_or_broadcast(ctx, ephemeral).await?;
 
         if let Some(p) = probability
             && !matches!(p, 0.0..=1.0)
@@ -40,27 +132,94 @@ poise_instrument! {
             return Err("Probability out of range".into());
         }
 
-        let result = do_flip(probability);
-        let reply = CreateReply::default()
-            .embed(
-                CreateEmbed::new()
-                    .title("Coin Flip")
-                    .description(format!(
-                        "It's {} {}",
-                        if result { "heads" } else { "tails" },
-                        if let Some(p) = probability {
-                            format!("(p={})", if result { p } else { 1.0 - p })
-                        } else {
-                            "".into()
-                        }
-                    ))
-                    .color(colors::slate()),
-            )
-            .ephemeral(ephemeral.unwrap_or(false));
-
-        tracing::trace!("Sending reply: {:?}", DebuggableReply::new(&reply));
-        ctx.send(reply).await?;
-        Ok(())
+        send_flip(ctx, probability, ephemeral).await
     }
 
+    /// Flips a coin with the same probability as a coin flip result again
+    #[poise::command(context_menu_command = "Flip again", category = "Fun")]
+    pub async fn coinflip_again(
+        ctx: Context<'_>,
+        message: Message,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let Some(Invocation::Coinflip { probability }) =
+            get_invocation(&ctx.data().recent_invocations, message.id)
+        else {
+            return Err("This message isn't a recent coin flip".into());
+        };
+        let _typing = defer_or_broadcast(ctx, false).await?;
+        send_flip(ctx, probability, false).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::infrastructure::random::SeededRandom;
+
+    use super::*;
+
+    fn flips(
+        random: &dyn RandomSource,
+        probability: Option<f64>,
+        settings: &CoinflipSettings,
+    ) -> [usize; 3] {
+        let mut counts = [0; 3];
+        for _ in 0..1000 {
+            match do_flip(random, probability, settings) {
+                CoinSide::Heads => counts[0] += 1,
+                CoinSide::Tails => counts[1] += 1,
+                CoinSide::Edge => counts[2] += 1,
+            }
+        }
+        counts
+    }
+
+    #[test]
+    fn fair_coin_lands_on_both_sides() {
+        let random = SeededRandom::new(1);
+        let [heads, tails, edge] = flips(&random, None, &CoinflipSettings::default());
+        assert_eq!(edge, 0);
+        assert!((400..600).contains(&heads), "{}", heads);
+        assert_eq!(heads + tails, 1000);
+    }
+
+    #[test]
+    fn probability_and_edge_odds_are_respected() {
+        let random = SeededRandom::new(2);
+        let settings = CoinflipSettings::default();
+        assert_eq!(flips(&random, Some(1.0), &settings), [1000, 0, 0]);
+        assert_eq!(flips(&random, Some(0.0), &settings), [0, 1000, 0]);
+
+        let settings = CoinflipSettings {
+            edge_odds: 10,
+            ..Default::default()
+        };
+        let [_, _, edge] = flips(&random, None, &settings);
+        assert!((50..150).contains(&edge), "{}", edge);
+    }
+
+    #[test]
+    fn description_uses_labels_and_probability() {
+        let settings = CoinflipSettings {
+            heads_label: "cats".to_string(),
+            edge_odds: 6000,
+            ..Default::default()
+        };
+        assert_eq!(
+            make_description(CoinSide::Heads, Some(0.25), &settings),
+            "It's cats (p=0.25)"
+        );
+        assert_eq!(
+            make_description(CoinSide::Tails, Some(0.25), &settings),
+            "It's tails (p=0.75)"
+        );
+        assert_eq!(
+            make_description(CoinSide::Tails, None, &settings),
+            "It's tails "
+        );
+        assert_eq!(
+            make_description(CoinSide::Edge, None, &settings),
+            "It landed on its edge! (1 in 6000 chance)"
+        );
+    }
 }
@@ -0,0 +1,296 @@
+use migration::OnConflict;
+use poise::{
+    CreateReply,
+    serenity_prelude::{
+        EditRole, Mentionable, Permissions, RoleId,
+        futures::{self, Stream, StreamExt},
+    },
+};
+use sea_orm::{ActiveValue::Set, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter};
+use tracing::info;
+
+use crate::{
+    Context, Error,
+    entities::{color_role_settings, color_roles},
+    infrastructure::{
+        branding::guild_branding,
+        color_roles::{get_max_color_roles, guild_color_roles, release_color_role},
+        colors::{PRESETS, parse_color},
+        ids::{id_from_string, id_to_string, require_guild_id},
+    },
+    poise_instrument, record_ctx_fields,
+};
+
+/// Values of `/colorrole` that remove the color role instead.
+const REMOVE_VALUES: [&str; 3] = ["none", "reset", "clear"];
+
+async fn color_autocomplete<'a>(
+    _ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Stream<Item = String> + 'a {
+    let partial = partial.to_lowercase();
+    let names: Vec<String> = PRESETS
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .chain(std::iter::once("none".to_string()))
+        .filter(|name| name.contains(&partial))
+        .collect();
+    futures::stream::iter(names).boxed()
+}
+
+/// The color role of a member, if they wear one.
+async fn member_color_role(ctx: Context<'_>) -> Result<Option<color_roles::Model>, Error> {
+    let guild_id = require_guild_id(ctx)?;
+    Ok(
+        color_roles::Entity::find_by_id((id_to_string(guild_id), id_to_string(ctx.author().id)))
+            .one(&ctx.data().db_pool)
+            .await?,
+    )
+}
+
+/// Finds the role of a color on the guild, or creates it if the guild has room for another color role.
+async fn color_role(
+    ctx: Context<'_>,
+    color: u32,
+    current: Option<&color_roles::Model>,
+) -> Result<RoleId, Error> {
+    let guild_id = require_guild_id(ctx)?;
+    let db = &ctx.data().db_pool;
+    let roles = guild_color_roles(db, guild_id).await?;
+    let exists = |role_id: &RoleId| {
+        ctx.guild()
+            .map(|x| x.roles.contains_key(role_id))
+            .unwrap_or(true)
+    };
+    if let Some((role_id, _)) = roles.iter().find(|(_, x)| *x == color) {
+        if exists(role_id) {
+            return Ok(*role_id);
+        }
+        // The role was deleted by hand, so its wearers lost it already.
+        color_roles::Entity::delete_many()
+            .filter(color_roles::Column::GuildId.eq(id_to_string(guild_id)))
+            .filter(color_roles::Column::RoleId.eq(id_to_string(*role_id)))
+            .exec(db)
+            .await?;
+    }
+
+    // The current role of the member is deleted when they switch if nobody else wears it
+    let mut in_use = roles.len() as i32;
+    if let Some(current) = current {
+        let wearers = color_roles::Entity::find()
+            .filter(color_roles::Column::GuildId.eq(&current.guild_id))
+            .filter(color_roles::Column::RoleId.eq(&current.role_id))
+            .count(db)
+            .await?;
+        if wearers <= 1 {
+            in_use -= 1;
+        }
+    }
+    let max = get_max_color_roles(db, guild_id).await?;
+    if in_use >= max {
+        let colors: Vec<String> = roles
+            .iter()
+            .map(|(_, color)| format!("`#{:06X}`", color))
+            .collect();
+        return Err(format!(
+            "This guild has reached its limit of {} color roles, pick one of the colors in use: {}",
+            max,
+            colors.join(", ")
+        )
+        .into());
+    }
+
+    // Color roles go right below the bot's highest role, so they override the colors of most other roles
+    let position = ctx.guild().and_then(|guild| {
+        let bot = guild.members.get(&ctx.cache().current_user().id)?;
+        guild.member_highest_role(bot).map(|x| x.position)
+    });
+    let mut role = EditRole::new()
+        .name(format!("#{:06X}", color))
+        .colour(color)
+        .permissions(Permissions::empty())
+        .hoist(false)
+        .mentionable(false)
+        .audit_log_reason("Color role requested with /colorrole");
+    if let Some(position) = position {
+        role = role.position(position.saturating_sub(1).max(1));
+    }
+    let role = guild_id.create_role(ctx, role).await?;
+    info!(
+        "Created color role {} for #{:06X} on guild {}",
+        role.id, color, guild_id
+    );
+    Ok(role.id)
+}
+
+poise_instrument! {
+    /// Gives you a role that colors your name. Use "none" to remove it.
+    #[poise::command(slash_command, guild_only, category = "Fun")]
+    pub async fn colorrole(
+        ctx: Context<'_>,
+        #[description = "Hex color like #3E6775, a color name, or none"]
+        #[autocomplete = "color_autocomplete"]
+        color: String,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let db = &ctx.data().db_pool;
+        let current = member_color_role(ctx).await?;
+
+        if REMOVE_VALUES.contains(&color.trim().to_lowercase().as_str()) {
+            if current.is_none() {
+                return Err("You don't have a color role".into());
+            }
+            release_color_role(ctx.http(), db, guild_id, ctx.author().id, true).await?;
+            ctx.send(
+                CreateReply::default()
+                    .content("Removed your color role")
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        if get_max_color_roles(db, guild_id).await? <= 0 {
+            return Err("Color roles are disabled on this guild".into());
+        }
+        let color = parse_color(&color)?.0;
+        if color == 0 {
+            return Err(
+                "#000000 can't be used, since Discord shows it as the default name color".into(),
+            );
+        }
+        if current.as_ref().is_some_and(|x| x.color as u32 == color) {
+            return Err(format!("Your name is already colored #{:06X}", color).into());
+        }
+
+        let role_id = color_role(ctx, color, current.as_ref()).await?;
+        release_color_role(ctx.http(), db, guild_id, ctx.author().id, true).await?;
+        ctx.http()
+            .add_member_role(
+                guild_id,
+                ctx.author().id,
+                role_id,
+                Some("Color role requested with /colorrole"),
+            )
+            .await?;
+        color_roles::Entity::insert(color_roles::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            user_id: Set(id_to_string(ctx.author().id)),
+            role_id: Set(id_to_string(role_id)),
+            color: Set(color as i64),
+        })
+        .on_conflict(
+            OnConflict::columns([color_roles::Column::GuildId, color_roles::Column::UserId])
+                .update_columns([color_roles::Column::RoleId, color_roles::Column::Color])
+                .to_owned(),
+        )
+        .exec(db)
+        .await?;
+
+        let embed = guild_branding(ctx)
+            .await
+            .embed()
+            .color(color)
+            .description(format!("Your name is now colored {}", role_id.mention()));
+        ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
+
+/// Set of commands to manage the color roles members pick with `/colorrole`.
+#[poise::command(
+    slash_command,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    guild_only,
+    category = "Management",
+    subcommands("limit", "list")
+)]
+pub async fn colorroles(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Sets the maximum number of distinct color roles. 0 disables /colorrole.
+    #[poise::command(
+        slash_command,
+        required_permissions = "MANAGE_ROLES",
+        default_member_permissions = "MANAGE_ROLES",
+        guild_only,
+        category = "Management"
+    )]
+    async fn limit(
+        ctx: Context<'_>,
+        #[description = "Maximum number of color roles (default: 25)"]
+        #[min = 0]
+        #[max = 200]
+        max: i32,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        color_role_settings::Entity::insert(color_role_settings::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            max_roles: Set(max),
+        })
+        .on_conflict(
+            OnConflict::column(color_role_settings::Column::GuildId)
+                .update_column(color_role_settings::Column::MaxRoles)
+                .to_owned(),
+        )
+        .exec(&ctx.data().db_pool)
+        .await?;
+        let content = if max == 0 {
+            "Color roles are disabled. Existing color roles are kept until their members remove them.".to_string()
+        } else {
+            format!("Members can pick from up to {} color roles", max)
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Lists the color roles in use and how many members wear them.
+    #[poise::command(
+        slash_command,
+        required_permissions = "MANAGE_ROLES",
+        default_member_permissions = "MANAGE_ROLES",
+        guild_only,
+        category = "Management"
+    )]
+    async fn list(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let db = &ctx.data().db_pool;
+        let rows = color_roles::Entity::find()
+            .filter(color_roles::Column::GuildId.eq(id_to_string(guild_id)))
+            .all(db)
+            .await?;
+        let roles = guild_color_roles(db, guild_id).await?;
+        let max = get_max_color_roles(db, guild_id).await?;
+        let lines: Vec<String> = roles
+            .iter()
+            .map(|(role_id, color)| {
+                let wearers = rows
+                    .iter()
+                    .filter(|x| id_from_string::<RoleId>(&x.role_id).is_ok_and(|x| x == *role_id))
+                    .count();
+                format!("{} `#{:06X}` – {} member(s)", role_id.mention(), color, wearers)
+            })
+            .collect();
+        let description = if lines.is_empty() {
+            "No color roles are in use".to_string()
+        } else {
+            lines.join("\n")
+        };
+        let embed = guild_branding(ctx)
+            .await
+            .embed()
+            .title(format!("Color roles ({}/{})", roles.len(), max))
+            .description(description);
+        ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
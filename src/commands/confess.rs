@@ -0,0 +1,367 @@
+use migration::OnConflict;
+use poise::{
+    CreateReply,
+    serenity_prelude::{
+        self as serenity, ButtonStyle, ChannelId, CreateActionRow, CreateButton, CreateEmbed,
+        CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage, GuildChannel,
+        Mentionable,
+    },
+};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, sea_query::Expr,
+};
+use tracing::info;
+
+use crate::{
+    Context, Error,
+    entities::{confession_settings, confessions},
+    infrastructure::{
+        botdata::Data,
+        branding::{Branding, get_branding, guild_branding},
+        colors,
+        confessions::{
+            STATUS_APPROVED, STATUS_DENIED, STATUS_PENDING, decrypt_author, encrypt_author,
+            hash_author,
+        },
+        ids::{id_from_string, id_to_string, require_guild_id},
+    },
+    poise_instrument, record_ctx_fields,
+};
+
+/// Custom id prefix of the approve and deny buttons of the review queue, followed by `approve:<id>` or `deny:<id>`.
+pub const CONFESSION_REVIEW_ID_PREFIX: &str = "confession_review:";
+
+fn confession_embed(branding: &Branding, id: i32, content: &str) -> CreateEmbed {
+    branding
+        .embed()
+        .title(format!("Confession #{}", id))
+        .description(content)
+}
+
+fn review_components(id: i32) -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("{}approve:{}", CONFESSION_REVIEW_ID_PREFIX, id))
+            .label("Approve")
+            .style(ButtonStyle::Success),
+        CreateButton::new(format!("{}deny:{}", CONFESSION_REVIEW_ID_PREFIX, id))
+            .label("Deny")
+            .style(ButtonStyle::Danger),
+    ])]
+}
+
+async fn get_confession_settings(
+    db: &DatabaseConnection,
+    guild_id: serenity::GuildId,
+) -> Result<Option<confession_settings::Model>, Error> {
+    Ok(
+        confession_settings::Entity::find_by_id(id_to_string(guild_id))
+            .one(db)
+            .await?,
+    )
+}
+
+poise_instrument! {
+    /// Posts a message anonymously in the confession channel of this guild.
+    #[poise::command(slash_command, guild_only, category = "Fun")]
+    pub async fn confess(
+        ctx: Context<'_>,
+        #[description = "What you want to confess"]
+        #[max_length = 2000]
+        text: String,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let db = &ctx.data().db_pool;
+        let settings = get_confession_settings(db, guild_id)
+            .await?
+            .ok_or("Confessions are not set up on this guild")?;
+        let review_channel = settings
+            .review_channel_id
+            .as_deref()
+            .map(id_from_string::<ChannelId>)
+            .transpose()?;
+
+        let id = confessions::Entity::insert(confessions::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            author: Set(encrypt_author(ctx.author().id)?),
+            author_hash: Set(hash_author(ctx.author().id)?),
+            content: Set(text.clone()),
+            status: Set(if review_channel.is_some() { STATUS_PENDING } else { STATUS_APPROVED }.to_string()),
+            created_at: Set(ctx.data().clock.unix_timestamp()),
+            ..Default::default()
+        })
+        .exec(db)
+        .await?
+        .last_insert_id;
+
+        let branding = guild_branding(ctx).await;
+        let content = match review_channel {
+            Some(review_channel) => {
+                let embed = confession_embed(&branding, id, &text).title(format!("Confession #{} awaiting review", id));
+                review_channel
+                    .send_message(ctx, CreateMessage::new().embed(embed).components(review_components(id)))
+                    .await?;
+                format!("Your confession was sent to the moderators for review as #{}.", id)
+            }
+            None => {
+                let channel = id_from_string::<ChannelId>(&settings.channel_id)?;
+                let message = channel
+                    .send_message(ctx, CreateMessage::new().embed(confession_embed(&branding, id, &text)))
+                    .await?;
+                format!("Your confession was posted: {}", message.link())
+            }
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
+
+/// Set of commands to configure anonymous confessions on this guild.
+#[poise::command(
+    slash_command,
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR",
+    guild_only,
+    category = "Management",
+    subcommands("configure", "disable", "reveal")
+)]
+pub async fn confession(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Sets the channel confessions are posted in, and optionally a staff channel to review them in first.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn configure(
+        ctx: Context<'_>,
+        #[description = "Channel confessions are posted in"] channel: GuildChannel,
+        #[description = "Staff channel to approve or deny confessions in before they are posted"]
+        review_channel: Option<GuildChannel>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        confession_settings::Entity::insert(confession_settings::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            channel_id: Set(id_to_string(channel.id)),
+            review_channel_id: Set(review_channel.as_ref().map(|x| id_to_string(x.id))),
+        })
+        .on_conflict(
+            OnConflict::column(confession_settings::Column::GuildId)
+                .update_columns([
+                    confession_settings::Column::ChannelId,
+                    confession_settings::Column::ReviewChannelId,
+                ])
+                .to_owned(),
+        )
+        .exec(&ctx.data().db_pool)
+        .await?;
+
+        let content = match review_channel {
+            Some(review_channel) => format!(
+                "Confessions are reviewed in {} and posted in {}",
+                review_channel.mention(),
+                channel.mention()
+            ),
+            None => format!("Confessions are posted in {} without review", channel.mention()),
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Stops accepting confessions on this guild.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn disable(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        confession_settings::Entity::delete_by_id(id_to_string(guild_id))
+            .exec(&ctx.data().db_pool)
+            .await?;
+        ctx.send(
+            CreateReply::default()
+                .content("Confessions are disabled on this guild")
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Reveals the author of a confession, for handling abuse.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn reveal(
+        ctx: Context<'_>,
+        #[description = "Number of the confession"] number: i32,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let confession = confessions::Entity::find_by_id(number)
+            .filter(confessions::Column::GuildId.eq(id_to_string(guild_id)))
+            .one(&ctx.data().db_pool)
+            .await?
+            .ok_or("The author of this confession is unknown or no longer stored")?;
+        let author = decrypt_author(&confession.author)?;
+        info!(
+            "User {} revealed the author of confession {} on guild {}",
+            ctx.author().id,
+            number,
+            guild_id
+        );
+        ctx.send(
+            CreateReply::default()
+                .content(format!("Confession #{} was written by {}", number, author.mention()))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+async fn respond_ephemeral(
+    ctx: &serenity::Context,
+    interaction: &serenity::ComponentInteraction,
+    content: &str,
+) -> Result<(), Error> {
+    interaction
+        .create_response(
+            ctx,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .ephemeral(true),
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Approves or denies a confession from the buttons of the review queue. `action` is the custom id without
+/// [`CONFESSION_REVIEW_ID_PREFIX`].
+pub async fn handle_review(
+    ctx: &serenity::Context,
+    data: &Data,
+    interaction: &serenity::ComponentInteraction,
+    action: &str,
+) -> Result<(), Error> {
+    let guild_id = interaction
+        .guild_id
+        .ok_or("This function is only available in guilds")?;
+    let (approve, id) = match action.split_once(':') {
+        Some(("approve", id)) => (true, id.parse::<i32>()?),
+        Some(("deny", id)) => (false, id.parse::<i32>()?),
+        _ => return Err(format!("Unknown confession review action '{}'", action).into()),
+    };
+    let can_review = interaction
+        .member
+        .as_ref()
+        .and_then(|x| x.permissions)
+        .is_some_and(|x| x.manage_messages());
+    if !can_review {
+        return respond_ephemeral(
+            ctx,
+            interaction,
+            "You need the Manage Messages permission to review confessions",
+        )
+        .await;
+    }
+
+    let db = &data.db_pool;
+    let confession = match confessions::Entity::find_by_id(id)
+        .filter(confessions::Column::GuildId.eq(id_to_string(guild_id)))
+        .one(db)
+        .await?
+    {
+        Some(x) => x,
+        None => {
+            return respond_ephemeral(ctx, interaction, "This confession is no longer stored")
+                .await;
+        }
+    };
+
+    // Only the first reviewer changes the status, in case two moderators click at the same time
+    let status = if approve {
+        STATUS_APPROVED
+    } else {
+        STATUS_DENIED
+    };
+    let updated = confessions::Entity::update_many()
+        .col_expr(confessions::Column::Status, Expr::value(status))
+        .filter(confessions::Column::Id.eq(id))
+        .filter(confessions::Column::Status.eq(STATUS_PENDING))
+        .exec(db)
+        .await?
+        .rows_affected;
+    if updated == 0 {
+        return respond_ephemeral(ctx, interaction, "This confession was already reviewed").await;
+    }
+
+    let branding = get_branding(db, &data.branding, guild_id).await;
+    let outcome = if approve {
+        let settings = get_confession_settings(db, guild_id)
+            .await?
+            .ok_or("Confessions are not set up on this guild")?;
+        let channel = id_from_string::<ChannelId>(&settings.channel_id)?;
+        let message = match channel
+            .send_message(
+                ctx,
+                CreateMessage::new().embed(confession_embed(&branding, id, &confession.content)),
+            )
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                // Put the confession back in the queue so it can be approved again
+                confessions::Entity::update_many()
+                    .col_expr(confessions::Column::Status, Expr::value(STATUS_PENDING))
+                    .filter(confessions::Column::Id.eq(id))
+                    .exec(db)
+                    .await?;
+                return Err(e.into());
+            }
+        };
+        format!(
+            "Approved by {} and posted: {}",
+            interaction.user.mention(),
+            message.link()
+        )
+    } else {
+        format!("Denied by {}", interaction.user.mention())
+    };
+
+    let embed = confession_embed(&branding, id, &confession.content)
+        .field("Review", outcome, false)
+        .color(if approve {
+            colors::green()
+        } else {
+            colors::red()
+        });
+    interaction
+        .create_response(
+            ctx,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .embed(embed)
+                    .components(vec![]),
+            ),
+        )
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,268 @@
+use poise::{
+    CreateReply,
+    serenity_prelude::futures::{self, Stream, StreamExt},
+};
+
+use crate::{
+    Context, Error,
+    events::snipe::{
+        MAX_RETENTION_MINUTES, SnipeSettings, load_snipe_settings, set_snipe_settings,
+    },
+    infrastructure::{botdata::Data, ids::require_guild_id, mod_log::get_mod_log_channel},
+    poise_instrument, record_ctx_fields,
+    services::{
+        coinflip::CoinflipRepo,
+        command_settings::{CommandSettingsRepo, EphemeralMode},
+        mod_digest::{DigestFrequency, ModDigestRepo},
+    },
+};
+
+/// Set of commands to change how commands behave on this guild.
+#[poise::command(
+    slash_command,
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR",
+    guild_only,
+    category = "Management",
+    subcommands("digest", "ephemeral", "snipe", "coinflip")
+)]
+pub async fn configure(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+const MAX_COIN_LABEL_LENGTH: usize = 32;
+
+/// Lowest odds of a coin landing on its edge, so it stays a surprise.
+const MIN_EDGE_ODDS: u32 = 10;
+
+const MAX_EDGE_ODDS: u32 = 1_000_000;
+
+/// Reads a coin label option: None keeps the current label, '-' restores the default one.
+fn coin_label_option(value: Option<String>, current: String) -> Result<String, Error> {
+    match value.as_deref().map(str::trim) {
+        None => Ok(current),
+        Some("-") => Ok(String::new()),
+        Some("") => Err("Coin labels can't be empty, use '-' to restore the default".into()),
+        Some(x) if x.chars().count() > MAX_COIN_LABEL_LENGTH => Err(format!(
+            "Coin labels are limited to {} characters",
+            MAX_COIN_LABEL_LENGTH
+        )
+        .into()),
+        Some(x) => Ok(x.to_string()),
+    }
+}
+
+/// Gets the qualified names of the commands with an `ephemeral` option, in alphabetical order.
+fn ephemeral_commands(ctx: Context<'_>) -> Vec<String> {
+    fn collect(commands: &[poise::Command<Data, Error>], names: &mut Vec<String>) {
+        for command in commands {
+            if command.parameters.iter().any(|x| x.name == "ephemeral") {
+                names.push(command.qualified_name.clone());
+            }
+            collect(&command.subcommands, names);
+        }
+    }
+
+    let mut names = vec![];
+    collect(&ctx.framework().options().commands, &mut names);
+    names.sort();
+    names
+}
+
+async fn ephemeral_command_autocomplete<'a>(
+    ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Stream<Item = String> + 'a {
+    let partial = partial.to_lowercase();
+    let names: Vec<String> = ephemeral_commands(ctx)
+        .into_iter()
+        .filter(|name| name.contains(&partial))
+        .collect();
+    futures::stream::iter(names).boxed()
+}
+
+poise_instrument! {
+    /// Makes the replies of a command always or never visible to the invoking user only.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn ephemeral(
+        ctx: Context<'_>,
+        #[description = "Command with an ephemeral option, such as `mc status`"]
+        #[autocomplete = "ephemeral_command_autocomplete"]
+        command: String,
+        #[description = "on: always visible to the user only, off: always public, user-choice: the option decides"]
+        mode: EphemeralMode,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let command = command.trim().trim_start_matches('/').to_lowercase();
+        if !ephemeral_commands(ctx).contains(&command) {
+            return Err(format!("`/{}` has no ephemeral option", command).into());
+        }
+
+        CommandSettingsRepo::new(&ctx.data().db_pool)
+            .set_ephemeral_mode(guild_id, &command, mode)
+            .await?;
+        let content = match mode {
+            EphemeralMode::On => {
+                format!("Replies of `/{}` are now only visible to the invoking user.", command)
+            }
+            EphemeralMode::Off => format!("Replies of `/{}` are now visible to everyone.", command),
+            EphemeralMode::UserChoice => format!(
+                "Users now choose whether replies of `/{}` are only visible to them.",
+                command
+            ),
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
+
+poise_instrument! {
+    /// Posts a summary of the moderation activity to the mod-log channel every day or week.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn digest(
+        ctx: Context<'_>,
+        #[description = "How often the moderation digest is posted"] frequency: DigestFrequency,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let db = &ctx.data().db_pool;
+        let now = ctx.data().clock.unix_timestamp();
+        ModDigestRepo::new(db)
+            .set_frequency(guild_id, frequency, now)
+            .await?;
+        let mut content = match frequency {
+            DigestFrequency::Off => "The moderation digest is no longer posted.",
+            DigestFrequency::Daily => "The moderation digest is now posted every day.",
+            DigestFrequency::Weekly => "The moderation digest is now posted every week.",
+        }
+        .to_string();
+        if frequency != DigestFrequency::Off
+            && get_mod_log_channel(db, guild_id).await?.is_none()
+        {
+            content.push_str(
+                "\n⚠️ No mod-log channel is configured, \
+                set one with `/configure_mod_log_channel` to receive it.",
+            );
+        }
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
+
+poise_instrument! {
+    /// Lets members recall recently deleted and edited messages with `/snipe` and `/editsnipe`.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn snipe(
+        ctx: Context<'_>,
+        #[description = "Remember deleted and edited messages for /snipe and /editsnipe?"]
+        enabled: bool,
+        #[description = "Minutes deleted and edited messages are remembered (default: 60)"]
+        #[min = 1]
+        #[max = 1440]
+        retention_minutes: Option<u32>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let db = &ctx.data().db_pool;
+        let retention_minutes = match retention_minutes {
+            Some(x) => x.clamp(1, MAX_RETENTION_MINUTES),
+            None => load_snipe_settings(db, guild_id).await?.retention_minutes,
+        };
+        let settings = SnipeSettings {
+            enabled,
+            retention_minutes,
+        };
+        set_snipe_settings(db, &ctx.data().snipes, guild_id, settings).await?;
+        let content = if enabled {
+            format!(
+                "Deleted and edited messages are now remembered for {} minute(s).",
+                retention_minutes
+            )
+        } else {
+            "Deleted and edited messages are no longer remembered, \
+            and the ones remembered were forgotten."
+                .to_string()
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
+
+poise_instrument! {
+    /// Renames the sides of the coin flipped by `/coinflip`, and lets it land on its edge once in a while.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn coinflip(
+        ctx: Context<'_>,
+        #[description = "Label of heads, such as the name of your server ('-' for the default)"]
+        #[rename = "heads-label"]
+        heads_label: Option<String>,
+        #[description = "Label of tails ('-' for the default)"]
+        #[rename = "tails-label"]
+        tails_label: Option<String>,
+        #[description = "The coin lands on its edge once in this many flips, 0 for never (default: 0)"]
+        #[rename = "edge-odds"]
+        #[min = 0]
+        #[max = 1000000]
+        edge_odds: Option<u32>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        if let Some(x) = edge_odds
+            && x > 0
+            && x < MIN_EDGE_ODDS
+        {
+            return Err(format!(
+                "Landing on the edge should stay rare, use odds of at least {} or 0 for never",
+                MIN_EDGE_ODDS
+            )
+            .into());
+        }
+        let repo = CoinflipRepo::new(&ctx.data().db_pool);
+        let mut settings = repo.settings(guild_id).await?;
+        settings.heads_label = coin_label_option(heads_label, settings.heads_label)?;
+        settings.tails_label = coin_label_option(tails_label, settings.tails_label)?;
+        settings.edge_odds = edge_odds.unwrap_or(settings.edge_odds).min(MAX_EDGE_ODDS);
+        repo.save_settings(guild_id, &settings).await?;
+
+        let mut content = format!(
+            "Coin flips now land on **{}** or **{}**",
+            settings.heads(),
+            settings.tails()
+        );
+        match settings.edge_odds {
+            0 => content.push('.'),
+            x => content.push_str(&format!(", and on their edge once in {} flips.", x)),
+        }
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
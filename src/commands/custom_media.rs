@@ -0,0 +1,191 @@
+use poise::{
+    CreateReply,
+    serenity_prelude::{Attachment, GuildId},
+};
+use tracing::{info, trace};
+
+use crate::{
+    Context, Error,
+    commands::{
+        coinflip::CoinSide,
+        roll::{Dice, dice_number},
+    },
+    infrastructure::{
+        branding::guild_branding,
+        guild_media::{IMAGE_EXTENSIONS, dice_media_key, is_image_filename},
+        ids::require_guild_id,
+        user_content::{create_file_from_attachment_safe, remove_user_content_files},
+        util::DebuggableReply,
+    },
+    poise_instrument, record_ctx_fields,
+    services::{
+        guild_media::GuildMediaRepo, unit_of_work::UnitOfWork, user_content::UserContentRepo,
+    },
+};
+
+/// Replaces a bundled image with an upload of the guild, or goes back to the bundled image without an upload.
+/// Returns whether the guild had replaced the image before.
+async fn replace_media(
+    ctx: Context<'_>,
+    guild_id: GuildId,
+    key: &str,
+    image: Option<Attachment>,
+) -> Result<bool, Error> {
+    if let Some(image) = &image
+        && (!is_image_filename(&image.filename)
+            || image
+                .content_type
+                .as_ref()
+                .is_some_and(|x| !x.starts_with("image/")))
+    {
+        return Err(format!(
+            "Only images can be uploaded ({})",
+            IMAGE_EXTENSIONS.join(", ")
+        )
+        .into());
+    }
+
+    // The new file is removed again if it can't be saved, the old one only once it was replaced
+    let db = &ctx.data().db_pool;
+    let uow = UnitOfWork::begin(db).await?;
+    let result = async {
+        let repo = GuildMediaRepo::new(uow.txn());
+        let previous = match image {
+            Some(image) => {
+                let filename =
+                    create_file_from_attachment_safe(ctx.data(), &guild_id, image, &uow).await?;
+                repo.set(guild_id, key, &filename).await?
+            }
+            None => repo.remove(guild_id, key).await?,
+        };
+        let previous: Vec<String> = previous.into_iter().collect();
+        UserContentRepo::new(uow.txn()).release(&previous).await?;
+        Ok(previous)
+    }
+    .await;
+    let previous = uow.finish(result).await?;
+    let replaced = !previous.is_empty();
+    remove_user_content_files(db.clone(), guild_id, previous).await;
+    Ok(replaced)
+}
+
+/// Set of commands to replace the dice and coin images of /roll and /coinflip with your own.
+#[poise::command(
+    slash_command,
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR",
+    guild_only,
+    category = "Fun",
+    rename = "custom-media",
+    subcommands("dice", "coin", "list")
+)]
+pub async fn custom_media(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Replaces the image of a side of a dice rolled by /roll, or restores the bundled one without an image
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Fun"
+    )]
+    async fn dice(
+        ctx: Context<'_>,
+        #[description = "The type of die"] dice: Dice,
+        #[description = "Side of the die"]
+        #[min = 1]
+        #[max = 20]
+        side: u8,
+        #[description = "Image of the side (default: the bundled image)"]
+        image: Option<Attachment>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        if side < 1 || side > dice_number(&dice) {
+            return Err(format!(
+                "A {:?} only has sides 1 to {}",
+                dice,
+                dice_number(&dice)
+            )
+            .into());
+        }
+        let uploaded = image.is_some();
+        let key = dice_media_key(dice.as_str(), side);
+        let replaced = replace_media(ctx, guild_id, &key, image).await?;
+        info!("{} changed the media {} of guild {}", ctx.author().id, key, guild_id);
+
+        let side = format!("Side {} of the {:?}", side, dice);
+        let content = match (uploaded, replaced) {
+            (true, _) => format!("{} now uses your image.", side),
+            (false, true) => format!("{} uses the bundled image again.", side),
+            (false, false) => format!("{} already uses the bundled image.", side),
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true)).await?;
+        Ok(())
+    }
+
+    /// Replaces the image of a side of the coin flipped by /coinflip, or removes it without an image
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Fun"
+    )]
+    async fn coin(
+        ctx: Context<'_>,
+        #[description = "Side of the coin"] side: CoinSide,
+        #[description = "Image of the side (default: no image)"] image: Option<Attachment>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let uploaded = image.is_some();
+        let key = side.media_key();
+        let replaced = replace_media(ctx, guild_id, key, image).await?;
+        info!("{} changed the media {} of guild {}", ctx.author().id, key, guild_id);
+
+        let side = format!("{:?}", side).to_lowercase();
+        let content = match (uploaded, replaced) {
+            (true, _) => format!("Coin flips landing on {} now show your image.", side),
+            (false, true) => format!("Coin flips landing on {} no longer show an image.", side),
+            (false, false) => format!("Coin flips landing on {} don't show an image.", side),
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true)).await?;
+        Ok(())
+    }
+
+    /// Lists the dice and coin images replaced on this server
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Fun"
+    )]
+    async fn list(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let media = GuildMediaRepo::new(&ctx.data().db_pool).list(guild_id).await?;
+        let description = if media.is_empty() {
+            "No images are replaced, upload one with `/custom-media dice` or `/custom-media coin`."
+                .to_string()
+        } else {
+            media
+                .iter()
+                .map(|(key, _)| format!("`{}`", key))
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+
+        let branding = guild_branding(ctx).await;
+        let reply = CreateReply::default()
+            .embed(branding.embed().title("Custom media").description(description))
+            .ephemeral(true);
+        trace!("Sending reply: {:?}", DebuggableReply::new(&reply));
+        ctx.send(reply).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+use migration::{MigrationStatus, MigratorTrait};
+use poise::CreateReply;
+use tracing::info;
+
+use crate::{
+    Context, Error,
+    infrastructure::{branding::guild_branding, colors, modules::Migrator},
+    poise_instrument, record_ctx_fields,
+};
+
+/// Set of commands to inspect and migrate the bot database.
+#[poise::command(
+    slash_command,
+    prefix_command,
+    owners_only,
+    hide_in_help,
+    subcommands("status", "migrate")
+)]
+pub async fn db(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Shows applied and pending database migrations.
+    #[poise::command(slash_command, prefix_command, owners_only, hide_in_help)]
+    async fn status(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let migrations = Migrator::get_migration_with_status(&ctx.data().db_pool).await?;
+
+        let (applied, pending): (Vec<_>, Vec<_>) = migrations
+            .iter()
+            .partition(|m| m.status() == MigrationStatus::Applied);
+        let version = applied.last().map(|m| m.name()).unwrap_or("None");
+        let list = migrations
+            .iter()
+            .map(|m| match m.status() {
+                MigrationStatus::Applied => format!("✅ `{}`", m.name()),
+                MigrationStatus::Pending => format!("⏳ `{}`", m.name()),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let branding = guild_branding(ctx).await;
+        let embed = branding
+            .embed()
+            .title("Database Status")
+            .color(if pending.is_empty() {
+                colors::green()
+            } else {
+                colors::red()
+            })
+            .field("Schema version", format!("`{}`", version), false)
+            .field("Applied", applied.len().to_string(), true)
+            .field("Pending", pending.len().to_string(), true)
+            .description(list);
+
+        ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Applies all pending database migrations.
+    #[poise::command(slash_command, prefix_command, owners_only, hide_in_help)]
+    async fn migrate(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        ctx.defer_ephemeral().await?;
+        let pending = Migrator::get_pending_migrations(&ctx.data().db_pool).await?;
+        if pending.is_empty() {
+            ctx.send(
+                CreateReply::default()
+                    .content("Database is already up to date")
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        Migrator::up(&ctx.data().db_pool, None).await?;
+        info!("Applied {} pending migrations", pending.len());
+        ctx.send(
+            CreateReply::default()
+                .content(format!(
+                    "Applied {} migrations:\n{}",
+                    pending.len(),
+                    pending
+                        .iter()
+                        .map(|m| format!("- `{}`", m.name()))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                ))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+}
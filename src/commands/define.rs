@@ -0,0 +1,432 @@
+/*!
+
+Looks up the definitions of words and phrases.
+
+Definitions come from a [`DefinitionProvider`]. Providers that aren't safe for work are hidden unless a guild turns
+off its SFW setting with `/define-config sfw`. Results are cached in the database, and expired by the retention
+subsystem.
+
+*/
+
+use migration::OnConflict;
+use poise::serenity_prelude::{CreateEmbed, CreateEmbedAuthor, GuildId, UserId, async_trait};
+use sea_orm::{ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, trace, warn};
+
+use crate::{
+    Context, Error,
+    entities::{define_settings, definition_cache},
+    infrastructure::{
+        branding::{Branding, guild_branding},
+        ids::{id_to_string, require_guild_id},
+        retention::RetentionTask,
+        util::{Paginator, defer_or_broadcast, resolve_ephemeral},
+    },
+    poise_instrument, record_ctx_fields,
+};
+
+/// Maximum number of definitions shown for a term.
+const MAX_DEFINITIONS: usize = 10;
+
+const SECONDS_PER_DAY: i64 = 60 * 60 * 24;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Definition {
+    pub word: String,
+    pub part_of_speech: Option<String>,
+    pub text: String,
+    pub example: Option<String>,
+    pub url: Option<String>,
+    pub author: Option<String>,
+}
+
+#[async_trait]
+pub trait DefinitionProvider: Send + Sync {
+    /// Key of the provider, used in the cache.
+    fn name(&self) -> &'static str;
+
+    /// Name shown next to the definitions.
+    fn display_name(&self) -> &'static str;
+
+    /// Whether the provider may return definitions that aren't safe for work.
+    fn nsfw(&self) -> bool {
+        false
+    }
+
+    /// Looks up the definitions of a term, most relevant first.
+    async fn fetch(&self, http: &reqwest::Client, term: &str) -> Result<Vec<Definition>, Error>;
+}
+
+pub struct DictionaryApi;
+
+#[derive(Debug, Deserialize)]
+struct DictionaryApiEntry {
+    word: String,
+    meanings: Vec<DictionaryApiMeaning>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DictionaryApiMeaning {
+    part_of_speech: Option<String>,
+    definitions: Vec<DictionaryApiDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DictionaryApiDefinition {
+    definition: String,
+    example: Option<String>,
+}
+
+#[async_trait]
+impl DefinitionProvider for DictionaryApi {
+    fn name(&self) -> &'static str {
+        "dictionaryapi"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Free Dictionary"
+    }
+
+    async fn fetch(&self, http: &reqwest::Client, term: &str) -> Result<Vec<Definition>, Error> {
+        let mut url = reqwest::Url::parse("https://api.dictionaryapi.dev/api/v2/entries/en")?;
+        url.path_segments_mut()
+            .map_err(|_| "Dictionary url can't have a path")?
+            .push(term);
+        let response = http.get(url).send().await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(vec![]);
+        }
+        if !response.status().is_success() {
+            warn!("Dictionary provider returned status {}", response.status());
+            return Err("The dictionary is unavailable".into());
+        }
+
+        let entries: Vec<DictionaryApiEntry> = response.json().await?;
+        Ok(entries
+            .into_iter()
+            .flat_map(|entry| {
+                let word = entry.word;
+                entry.meanings.into_iter().flat_map(move |meaning| {
+                    let word = word.clone();
+                    meaning.definitions.into_iter().map(move |x| Definition {
+                        word: word.clone(),
+                        part_of_speech: meaning.part_of_speech.clone(),
+                        text: x.definition,
+                        example: x.example,
+                        url: None,
+                        author: None,
+                    })
+                })
+            })
+            .collect())
+    }
+}
+
+pub struct UrbanDictionary;
+
+#[derive(Debug, Deserialize)]
+struct UrbanDictionaryResponse {
+    list: Vec<UrbanDictionaryDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UrbanDictionaryDefinition {
+    word: String,
+    definition: String,
+    example: Option<String>,
+    permalink: Option<String>,
+    author: Option<String>,
+    #[serde(default)]
+    thumbs_up: i64,
+    #[serde(default)]
+    thumbs_down: i64,
+}
+
+#[async_trait]
+impl DefinitionProvider for UrbanDictionary {
+    fn name(&self) -> &'static str {
+        "urbandictionary"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Urban Dictionary"
+    }
+
+    fn nsfw(&self) -> bool {
+        true
+    }
+
+    async fn fetch(&self, http: &reqwest::Client, term: &str) -> Result<Vec<Definition>, Error> {
+        let response = http
+            .get("https://api.urbandictionary.com/v0/define")
+            .query(&[("term", term)])
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            warn!("Urban Dictionary returned status {}", response.status());
+            return Err("Urban Dictionary is unavailable".into());
+        }
+
+        // Urban Dictionary marks links to other definitions with square brackets
+        let strip_links = |text: String| text.replace(['[', ']'], "");
+        let mut definitions = response.json::<UrbanDictionaryResponse>().await?.list;
+        definitions.sort_by_key(|x| -(x.thumbs_up - x.thumbs_down));
+        Ok(definitions
+            .into_iter()
+            .map(|x| Definition {
+                word: x.word,
+                part_of_speech: None,
+                text: strip_links(x.definition),
+                example: x.example.map(strip_links).filter(|x| !x.trim().is_empty()),
+                url: x.permalink,
+                author: x.author,
+            })
+            .collect())
+    }
+}
+
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum DefinitionSource {
+    #[name = "Dictionary"]
+    Dictionary,
+    #[name = "Urban Dictionary"]
+    Urban,
+}
+
+impl DefinitionSource {
+    fn provider(&self) -> Box<dyn DefinitionProvider> {
+        match self {
+            Self::Dictionary => Box::new(DictionaryApi),
+            Self::Urban => Box::new(UrbanDictionary),
+        }
+    }
+}
+
+/// Gets definitions from the cache, falling back to the provider and caching the result at `now`.
+async fn get_definitions(
+    db: &DatabaseConnection,
+    http: &reqwest::Client,
+    provider: &dyn DefinitionProvider,
+    term: &str,
+    now: i64,
+) -> Result<Vec<Definition>, Error> {
+    let key = (provider.name().to_string(), term.trim().to_lowercase());
+    if let Some(cached) = definition_cache::Entity::find_by_id(key.clone())
+        .one(db)
+        .await?
+    {
+        match serde_json::from_str(&cached.definitions) {
+            Ok(definitions) => {
+                debug!("Found cached definitions for '{}'", term);
+                return Ok(definitions);
+            }
+            Err(e) => warn!("Ignoring unreadable cached definitions: {:?}", e),
+        }
+    }
+
+    let mut definitions = provider.fetch(http, term.trim()).await?;
+    definitions.truncate(MAX_DEFINITIONS);
+    trace!(
+        "{} returned {} definitions",
+        provider.name(),
+        definitions.len()
+    );
+    if !definitions.is_empty() {
+        definition_cache::Entity::insert(definition_cache::ActiveModel {
+            provider: Set(key.0),
+            term: Set(key.1),
+            definitions: Set(serde_json::to_string(&definitions)?),
+            cached_at: Set(now),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                definition_cache::Column::Provider,
+                definition_cache::Column::Term,
+            ])
+            .update_columns([
+                definition_cache::Column::Definitions,
+                definition_cache::Column::CachedAt,
+            ])
+            .to_owned(),
+        )
+        .exec(db)
+        .await?;
+    }
+    Ok(definitions)
+}
+
+/// Whether a guild hides providers that aren't safe for work. Defaults to true.
+async fn is_sfw(db: &DatabaseConnection, guild_id: GuildId) -> Result<bool, Error> {
+    Ok(define_settings::Entity::find_by_id(id_to_string(guild_id))
+        .one(db)
+        .await?
+        .is_none_or(|x| x.sfw))
+}
+
+fn truncate(text: &str, length: usize) -> String {
+    if text.chars().count() > length {
+        format!("{}...", text.chars().take(length - 3).collect::<String>())
+    } else {
+        text.to_string()
+    }
+}
+
+fn definition_embed(
+    provider: &dyn DefinitionProvider,
+    definition: &Definition,
+    branding: &Branding,
+) -> CreateEmbed {
+    let mut embed = branding
+        .embed()
+        .author(CreateEmbedAuthor::new(provider.display_name()))
+        .title(truncate(&definition.word, 256))
+        .description(truncate(&definition.text, 4096));
+    if let Some(url) = &definition.url {
+        embed = embed.url(url);
+    }
+    if let Some(part_of_speech) = &definition.part_of_speech {
+        embed = embed.field("Part of speech", part_of_speech, true);
+    }
+    if let Some(author) = &definition.author {
+        embed = embed.field("Author", author, true);
+    }
+    if let Some(example) = &definition.example {
+        embed = embed.field("Example", truncate(example, 1024), false);
+    }
+    embed
+}
+
+poise_instrument! {
+    /// Looks up the definitions of a word or phrase
+    #[poise::command(slash_command, prefix_command, category = "Fun")]
+    pub async fn define(
+        ctx: Context<'_>,
+        #[description = "Word or phrase to define"] term: String,
+        #[description = "Where to look up the definition (default: Dictionary)"]
+        source: Option<DefinitionSource>,
+        #[description = "Visible to you only? (default: false)"] ephemeral: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        if term.trim().is_empty() {
+            return Err("Enter a word or phrase to define".into());
+        }
+        let provider = source.unwrap_or(DefinitionSource::Dictionary).provider();
+        let db = &ctx.data().db_pool;
+        if provider.nsfw()
+            && let Some(guild_id) = ctx.guild_id()
+            && is_sfw(db, guild_id).await?
+        {
+            return Err(format!(
+                "{} is disabled on this server. Administrators can allow it with `/define-config sfw false`",
+                provider.display_name()
+            )
+            .into());
+        }
+        let ephemeral = resolve_ephemeral(ctx, ephemeral, false).await;
+        let _typing = defer_or_broadcast(ctx, ephemeral).await?;
+
+        let http = reqwest::Client::new();
+        let now = ctx.data().clock.unix_timestamp();
+        let definitions = get_definitions(db, &http, provider.as_ref(), &term, now).await?;
+        if definitions.is_empty() {
+            return Err(format!("No definitions found for '{}'", term.trim()).into());
+        }
+        let branding = guild_branding(ctx).await;
+        let pages = definitions
+            .iter()
+            .map(|x| definition_embed(provider.as_ref(), x, &branding))
+            .collect();
+        Paginator::new(pages)
+            .ephemeral(ephemeral)
+            .send(ctx)
+            .await
+    }
+}
+
+/// Set of commands to configure `/define` on this guild.
+#[poise::command(
+    slash_command,
+    rename = "define-config",
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR",
+    guild_only,
+    category = "Management",
+    subcommands("sfw")
+)]
+pub async fn define_config(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Hides or shows definition sources that aren't safe for work, like Urban Dictionary
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn sfw(
+        ctx: Context<'_>,
+        #[description = "Hide sources that aren't safe for work? (default: true)"] enabled: bool,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        define_settings::Entity::insert(define_settings::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            sfw: Set(enabled),
+        })
+        .on_conflict(
+            OnConflict::column(define_settings::Column::GuildId)
+                .update_column(define_settings::Column::Sfw)
+                .to_owned(),
+        )
+        .exec(&ctx.data().db_pool)
+        .await?;
+
+        let content = if enabled {
+            "Sources that aren't safe for work are now hidden from `/define`."
+        } else {
+            "Sources that aren't safe for work, like Urban Dictionary, can now be used with `/define`."
+        };
+        ctx.send(poise::CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
+
+pub struct DefinitionCacheRetention;
+
+#[async_trait]
+impl RetentionTask for DefinitionCacheRetention {
+    fn name(&self) -> &'static str {
+        "definition_cache"
+    }
+
+    fn default_retention_days(&self) -> Option<u32> {
+        Some(30)
+    }
+
+    async fn expire(
+        &self,
+        db: &DatabaseConnection,
+        now: i64,
+        retention_days: u32,
+    ) -> Result<u64, Error> {
+        let result = definition_cache::Entity::delete_many()
+            .filter(
+                definition_cache::Column::CachedAt
+                    .lt(now - retention_days as i64 * SECONDS_PER_DAY),
+            )
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    async fn forget_user(&self, _db: &DatabaseConnection, _user_id: UserId) -> Result<u64, Error> {
+        // Cached definitions aren't tied to who looked them up
+        Ok(0)
+    }
+}
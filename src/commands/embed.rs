@@ -0,0 +1,337 @@
+use std::time::Duration;
+
+use poise::{
+    CreateReply,
+    serenity_prelude::{
+        CreateAllowedMentions, CreateEmbed, CreateMessage, GuildChannel, GuildId, Mentionable,
+        futures::{self, Stream, StreamExt},
+    },
+};
+
+use crate::{
+    ApplicationContext, Context, Error,
+    infrastructure::{
+        branding::{Branding, guild_branding},
+        channel_validation::check_notification_channel,
+        colors::parse_color,
+        embed_limits::{MessagePart, check_message_lengths},
+        ids::require_guild_id,
+    },
+    poise_instrument, record_ctx_fields,
+    services::saved_embeds::{EmbedDraft, MAX_EMBED_NAME_LENGTH, MAX_SAVED_EMBEDS, SavedEmbedRepo},
+};
+
+/// Time a user has to submit the embed builder modal.
+const BUILDER_MODAL_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+#[derive(Debug, Default, poise::Modal)]
+#[name = "Embed builder"]
+struct EmbedBuilderModal {
+    #[name = "Title"]
+    #[max_length = 256]
+    title: Option<String>,
+    #[name = "Description"]
+    #[paragraph]
+    #[max_length = 4000]
+    description: Option<String>,
+    #[name = "Color (default: the server's branding color)"]
+    #[placeholder = "#3E6775 or orange"]
+    #[max_length = 32]
+    color: Option<String>,
+    #[name = "Image URL"]
+    #[placeholder = "https://..."]
+    #[max_length = 512]
+    image: Option<String>,
+    #[name = "Fields, one per line as: name | value"]
+    #[paragraph]
+    #[max_length = 4000]
+    fields: Option<String>,
+}
+
+impl From<EmbedDraft> for EmbedBuilderModal {
+    fn from(value: EmbedDraft) -> Self {
+        fn optional_string(string: String) -> Option<String> {
+            if string.is_empty() {
+                None
+            } else {
+                Some(string)
+            }
+        }
+
+        Self {
+            title: optional_string(value.title),
+            description: optional_string(value.description),
+            color: optional_string(value.color),
+            image: optional_string(value.image_url),
+            fields: optional_string(value.fields),
+        }
+    }
+}
+
+impl From<EmbedBuilderModal> for EmbedDraft {
+    fn from(value: EmbedBuilderModal) -> Self {
+        fn trimmed(string: Option<String>) -> String {
+            string.as_deref().unwrap_or_default().trim().to_string()
+        }
+
+        Self {
+            title: trimmed(value.title),
+            description: trimmed(value.description),
+            color: trimmed(value.color),
+            image_url: trimmed(value.image),
+            fields: trimmed(value.fields),
+        }
+    }
+}
+
+/// Parses the fields of a draft, one per line as `name | value`.
+fn parse_fields(fields: &str) -> Result<Vec<(String, String)>, Error> {
+    let mut parsed = vec![];
+    for line in fields.lines().map(str::trim).filter(|x| !x.is_empty()) {
+        let Some((name, value)) = line.split_once('|') else {
+            return Err(format!(
+                "The field '{}' is missing a `|` between its name and value",
+                line
+            )
+            .into());
+        };
+        let (name, value) = (name.trim(), value.trim());
+        if name.is_empty() || value.is_empty() {
+            return Err(format!("The field '{}' needs both a name and a value", line).into());
+        }
+        parsed.push((name.to_string(), value.to_string()));
+    }
+    Ok(parsed)
+}
+
+/// Builds the embed of a draft, failing with a description of the first invalid part.
+fn build_embed(draft: &EmbedDraft, branding: &Branding) -> Result<CreateEmbed, Error> {
+    let fields = parse_fields(&draft.fields)?;
+    if draft.title.is_empty()
+        && draft.description.is_empty()
+        && draft.image_url.is_empty()
+        && fields.is_empty()
+    {
+        return Err("The embed needs a title, a description, an image or a field".into());
+    }
+    let mut parts = vec![
+        (MessagePart::Title, draft.title.as_str()),
+        (MessagePart::Description, draft.description.as_str()),
+        (
+            MessagePart::Footer,
+            branding.footer.as_deref().unwrap_or_default(),
+        ),
+    ];
+    for (name, value) in &fields {
+        parts.push((MessagePart::FieldName, name.as_str()));
+        parts.push((MessagePart::FieldValue, value.as_str()));
+    }
+    check_message_lengths(&parts)?;
+
+    let mut embed = branding.embed();
+    if !draft.title.is_empty() {
+        embed = embed.title(&draft.title);
+    }
+    if !draft.description.is_empty() {
+        embed = embed.description(&draft.description);
+    }
+    if !draft.color.is_empty() {
+        embed = embed.color(parse_color(&draft.color)?);
+    }
+    if !draft.image_url.is_empty() {
+        let url = reqwest::Url::parse(&draft.image_url)
+            .map_err(|_| format!("'{}' is not a valid URL", draft.image_url))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err("Image URLs must start with http:// or https://".into());
+        }
+        embed = embed.image(url.as_str());
+    }
+    Ok(embed.fields(fields.into_iter().map(|(name, value)| (name, value, false))))
+}
+
+async fn draft_autocomplete<'a>(
+    ctx: Context<'a>,
+    partial: &'a str,
+) -> impl Stream<Item = String> + 'a {
+    let guild_id = match require_guild_id(ctx) {
+        Ok(id) => id,
+        Err(_) => return futures::stream::empty().boxed(),
+    };
+    let partial = partial.to_lowercase();
+    let names = SavedEmbedRepo::new(&ctx.data().db_pool)
+        .names(guild_id)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter(move |x| x.to_lowercase().contains(&partial));
+    futures::stream::iter(names).boxed()
+}
+
+/// Checks the name a draft is saved as, and that the guild has room for it if it is a new draft.
+async fn check_draft_name(ctx: Context<'_>, guild_id: GuildId, name: &str) -> Result<(), Error> {
+    if name.is_empty() || name.chars().count() > MAX_EMBED_NAME_LENGTH {
+        return Err(format!(
+            "Draft names must be between 1 and {} characters",
+            MAX_EMBED_NAME_LENGTH
+        )
+        .into());
+    }
+    let names = SavedEmbedRepo::new(&ctx.data().db_pool)
+        .names(guild_id)
+        .await?;
+    if !names.iter().any(|x| x == name) && names.len() >= MAX_SAVED_EMBEDS {
+        return Err(format!(
+            "A server can save at most {} embeds, delete one with `/embed delete-draft` first",
+            MAX_SAVED_EMBEDS
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Set of commands to post custom embeds as the bot.
+#[poise::command(
+    slash_command,
+    required_permissions = "MANAGE_MESSAGES",
+    default_member_permissions = "MANAGE_MESSAGES",
+    guild_only,
+    category = "Management",
+    subcommands("create", "drafts", "delete_draft")
+)]
+pub async fn embed(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Builds an embed in a form and posts it to a channel as the bot.
+    #[poise::command(
+        slash_command,
+        required_permissions = "MANAGE_MESSAGES",
+        default_member_permissions = "MANAGE_MESSAGES",
+        guild_only,
+        category = "Management"
+    )]
+    async fn create(
+        ctx: ApplicationContext<'_>,
+        #[description = "Channel to post the embed in"]
+        #[channel_types("Text", "News")]
+        channel: GuildChannel,
+        #[description = "Saved draft to start from"]
+        #[autocomplete = "draft_autocomplete"]
+        draft: Option<String>,
+        #[description = "Save the embed as a draft with this name"]
+        #[rename = "save-as"]
+        save_as: Option<String>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(Context::from(ctx));
+        let guild_id = require_guild_id(ctx.into())?;
+        if channel.guild_id != guild_id {
+            return Err("Embeds can only be posted to channels of this server".into());
+        }
+        check_notification_channel(&ctx.serenity_context().cache, &channel)?;
+        let save_as = save_as.map(|x| x.trim().to_string());
+        if let Some(name) = &save_as {
+            check_draft_name(ctx.into(), guild_id, name).await?;
+        }
+
+        let repo = SavedEmbedRepo::new(&ctx.data().db_pool);
+        let defaults = match &draft {
+            Some(name) => Some(
+                repo.get(guild_id, name)
+                    .await?
+                    .ok_or_else(|| format!("There is no draft named `{}`", name))?
+                    .into(),
+            ),
+            None => None,
+        };
+        let Some(modal) = poise::execute_modal::<_, _, EmbedBuilderModal>(
+            ctx,
+            defaults,
+            Some(BUILDER_MODAL_TIMEOUT),
+        )
+        .await?
+        else {
+            return Ok(());
+        };
+        let ctx = Context::from(ctx);
+
+        let draft = EmbedDraft::from(modal);
+        let embed = build_embed(&draft, &guild_branding(ctx).await)?;
+        let mut content = String::new();
+        if let Some(name) = &save_as {
+            repo.save(guild_id, name, &draft, ctx.data().clock.unix_timestamp())
+                .await?;
+            content.push_str(&format!("Saved the draft `{}`. ", name));
+        }
+        let sent = channel
+            .id
+            .send_message(
+                ctx,
+                CreateMessage::new()
+                    .embed(embed)
+                    .allowed_mentions(CreateAllowedMentions::new()),
+            )
+            .await?;
+        content.push_str(&format!("Posted the embed in {}: {}", channel.mention(), sent.link()));
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Lists the embed drafts saved on this server.
+    #[poise::command(
+        slash_command,
+        required_permissions = "MANAGE_MESSAGES",
+        default_member_permissions = "MANAGE_MESSAGES",
+        guild_only,
+        category = "Management"
+    )]
+    async fn drafts(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let names = SavedEmbedRepo::new(&ctx.data().db_pool).names(guild_id).await?;
+        let description = if names.is_empty() {
+            "No drafts are saved. Save one with `/embed create save-as:<name>`.".to_string()
+        } else {
+            names.iter().map(|x| format!("`{}`", x)).collect::<Vec<String>>().join("\n")
+        };
+        let embed = guild_branding(ctx)
+            .await
+            .embed()
+            .title("Embed drafts")
+            .description(description);
+        ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Deletes a saved embed draft.
+    #[poise::command(
+        slash_command,
+        required_permissions = "MANAGE_MESSAGES",
+        default_member_permissions = "MANAGE_MESSAGES",
+        guild_only,
+        category = "Management",
+        rename = "delete-draft"
+    )]
+    async fn delete_draft(
+        ctx: Context<'_>,
+        #[description = "Name of the draft"]
+        #[autocomplete = "draft_autocomplete"]
+        name: String,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let deleted = SavedEmbedRepo::new(&ctx.data().db_pool)
+            .delete(guild_id, &name)
+            .await?;
+        let content = if deleted {
+            format!("Deleted the draft `{}`", name)
+        } else {
+            format!("There is no draft named `{}`", name)
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
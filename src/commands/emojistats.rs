@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use poise::serenity_prelude::EmojiId;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QuerySelect};
+
+use crate::{
+    Context, Error,
+    entities::emoji_stats,
+    events::emoji_stats::day_of,
+    infrastructure::{
+        branding::guild_branding,
+        ids::{id_from_string, id_to_string, require_guild_id},
+        util::{Paginator, defer_or_broadcast, resolve_ephemeral},
+    },
+    poise_instrument, record_ctx_fields,
+};
+
+/// Number of emojis listed in each of the most and least used sections.
+const LIST_LENGTH: usize = 10;
+
+poise_instrument! {
+    /// Shows the most and least used emojis of this guild.
+    #[poise::command(
+        slash_command,
+        prefix_command,
+        guild_only,
+        category = "Management"
+    )]
+    pub async fn emojistats(
+        ctx: Context<'_>,
+        #[description = "Number of days to include (default: 30)"]
+        #[min = 1]
+        days: Option<u32>,
+        #[description = "Visible to you only? (default: true)"] ephemeral: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let ephemeral = resolve_ephemeral(ctx, ephemeral, true).await;
+        let _typing = defer_or_broadcast(ctx, ephemeral).await?;
+
+        let guild_id = require_guild_id(ctx)?;
+        let days = days.unwrap_or(30).max(1);
+
+        let today = day_of(ctx.data().clock.unix_timestamp());
+        let usage: HashMap<EmojiId, i64> = emoji_stats::Entity::find()
+            .select_only()
+            .column(emoji_stats::Column::EmojiId)
+            .column_as(emoji_stats::Column::Uses.sum(), "uses")
+            .filter(emoji_stats::Column::GuildId.eq(id_to_string(guild_id)))
+            .filter(emoji_stats::Column::Day.gt(today - days as i64))
+            .group_by(emoji_stats::Column::EmojiId)
+            .into_tuple::<(String, i64)>()
+            .all(&ctx.data().db_pool)
+            .await?
+            .into_iter()
+            .filter_map(|(id, uses)| id_from_string::<EmojiId>(&id).ok().map(|id| (id, uses)))
+            .collect();
+
+        // Only emojis that still exist on the guild are reported, including those that were never used.
+        let mut ranked: Vec<(String, i64)> = guild_id
+            .emojis(ctx)
+            .await?
+            .into_iter()
+            .map(|emoji| {
+                let uses = usage.get(&emoji.id).copied().unwrap_or(0);
+                (emoji.to_string(), uses)
+            })
+            .collect();
+        ranked.sort_by_key(|(_, uses)| std::cmp::Reverse(*uses));
+
+        let format_list = |entries: &[(String, i64)]| -> String {
+            entries
+                .iter()
+                .map(|(emoji, uses)| format!("{} — {} uses", emoji, uses))
+                .collect::<Vec<String>>()
+                .join("\n")
+        };
+
+        let branding = guild_branding(ctx).await;
+        let mut embed = branding.embed()
+            .title("Emoji Usage")
+            .description(format!("Custom emoji usage over the last {} days", days));
+        if ranked.is_empty() {
+            embed = embed.field("No emojis", "This guild has no custom emojis.", false);
+        } else {
+            let most_used = &ranked[..ranked.len().min(LIST_LENGTH)];
+            let least_used: Vec<(String, i64)> =
+                ranked.iter().rev().take(LIST_LENGTH).cloned().collect();
+            embed = embed
+                .field("Most used", format_list(most_used), true)
+                .field("Least used", format_list(&least_used), true);
+        }
+
+        // The summary is followed by the full leaderboard.
+        let mut pages = vec![embed];
+        for (page, entries) in ranked.chunks(LIST_LENGTH).enumerate() {
+            let leaderboard = entries
+                .iter()
+                .enumerate()
+                .map(|(i, (emoji, uses))| {
+                    format!("**{}.** {} — {} uses", page * LIST_LENGTH + i + 1, emoji, uses)
+                })
+                .collect::<Vec<String>>()
+                .join("\n");
+            pages.push(
+                branding.embed()
+                    .title("Emoji Leaderboard")
+                    .description(leaderboard),
+            );
+        }
+        Paginator::new(pages).ephemeral(ephemeral).send(ctx).await
+    }
+}
@@ -0,0 +1,280 @@
+use poise::CreateReply;
+
+use crate::{
+    Context, Error,
+    infrastructure::{
+        branding::guild_branding,
+        random::RandomSource,
+        util::{DebuggableReply, defer_or_broadcast, resolve_ephemeral},
+    },
+    lazy_regex, poise_instrument, record_ctx_fields,
+};
+
+/// Maximum number of options accepted by `/choose`.
+const MAX_CHOICE_OPTIONS: usize = 50;
+
+lazy_regex! { COUNT_OPTION_REGEX, r"(?:^|\s)--count(?:(?:=|\s+)(\S+))?" }
+
+#[derive(Debug, Clone, PartialEq)]
+struct ChoiceOption {
+    name: String,
+    weight: f64,
+}
+
+/// Parses `option1 | option2*weight | ...` into options. Options without a weight have a weight of 1.
+fn parse_choice_options(options: &str) -> Result<Vec<ChoiceOption>, Error> {
+    let mut parsed = vec![];
+    for option in options.split('|').map(str::trim).filter(|x| !x.is_empty()) {
+        let (name, weight) = match option.rsplit_once('*') {
+            Some((name, weight)) if weight.trim().parse::<f64>().is_ok() => {
+                (name.trim(), weight.trim().parse::<f64>()?)
+            }
+            _ => (option, 1.0),
+        };
+        if name.is_empty() {
+            return Err(format!("Option '{}' has no name", option).into());
+        }
+        if !weight.is_finite() || weight <= 0.0 {
+            return Err(format!("Weight of option '{}' must be greater than 0", name).into());
+        }
+        parsed.push(ChoiceOption {
+            name: name.to_string(),
+            weight,
+        });
+    }
+
+    if parsed.len() < 2 {
+        return Err("At least two options separated by '|' are required".into());
+    }
+    if parsed.len() > MAX_CHOICE_OPTIONS {
+        return Err(format!("At most {} options are allowed", MAX_CHOICE_OPTIONS).into());
+    }
+    Ok(parsed)
+}
+
+/// Takes the `--count N` option out of the arguments of the prefix command, returning the remaining options and the
+/// count.
+fn parse_prefix_choice_args(args: &str) -> Result<(String, Option<usize>), Error> {
+    let Some(captures) = COUNT_OPTION_REGEX.captures(args) else {
+        return Ok((args.to_string(), None));
+    };
+    let count = captures
+        .get(1)
+        .ok_or("Missing value for `--count`")?
+        .as_str();
+    let count = count
+        .parse::<usize>()
+        .map_err(|_| format!("Invalid value '{}' for `--count`", count))?;
+    let matched = captures.get(0).map_or(0..0, |x| x.range());
+    let options = format!("{} {}", &args[..matched.start], &args[matched.end..]);
+    Ok((options, Some(count)))
+}
+
+/// Picks `count` distinct options at random, each with a chance proportional to its weight.
+fn choose_weighted<'a>(
+    random: &dyn RandomSource,
+    options: &'a [ChoiceOption],
+    count: usize,
+) -> Vec<&'a ChoiceOption> {
+    let mut remaining: Vec<&ChoiceOption> = options.iter().collect();
+    let mut chosen = vec![];
+    while chosen.len() < count && !remaining.is_empty() {
+        let total: f64 = remaining.iter().map(|x| x.weight).sum();
+        let mut target = random.next_f64() * total;
+        // Rounding can leave the target just past the last option
+        let mut index = remaining.len() - 1;
+        for (i, option) in remaining.iter().enumerate() {
+            if target < option.weight {
+                index = i;
+                break;
+            }
+            target -= option.weight;
+        }
+        chosen.push(remaining.remove(index));
+    }
+    chosen
+}
+
+/// Picks options at random and shows them, shared by the slash and prefix variants of `choose`.
+async fn choose_options(
+    ctx: Context<'_>,
+    options: String,
+    count: Option<usize>,
+    ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = resolve_ephemeral(ctx, ephemeral, false).await;
+    let _typing = defer_or_broadcast(ctx, ephemeral).await?;
+
+    let options = parse_choice_options(&options)?;
+    let count = count.unwrap_or(1);
+    if count == 0 || count > options.len() {
+        return Err(format!("Count must be between 1 and {}", options.len()).into());
+    }
+
+    let chosen = choose_weighted(ctx.data().random.as_ref(), &options, count);
+    let description = if let [choice] = chosen.as_slice() {
+        format!("I choose **{}**", choice.name)
+    } else {
+        chosen
+            .iter()
+            .enumerate()
+            .map(|(i, choice)| format!("{}. **{}**", i + 1, choice.name))
+            .collect::<Vec<String>>()
+            .join("\n")
+    };
+
+    let weighted = options.iter().any(|x| x.weight != 1.0);
+    let total_weight: f64 = options.iter().map(|x| x.weight).sum();
+    let option_list = options
+        .iter()
+        .map(|x| {
+            if weighted {
+                format!("{} ({:.0}%)", x.name, x.weight / total_weight * 100.0)
+            } else {
+                x.name.clone()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(", ");
+    // Embed field values are limited to 1024 characters
+    let option_list = if option_list.chars().count() > 1024 {
+        format!("{}...", option_list.chars().take(1021).collect::<String>())
+    } else {
+        option_list
+    };
+
+    let branding = guild_branding(ctx).await;
+    let reply = CreateReply::default()
+        .embed(
+            branding
+                .embed()
+                .title("Choice")
+                .description(description)
+                .field("Options", option_list, false),
+        )
+        .ephemeral(ephemeral);
+
+    tracing::trace!("Sending reply: {:?}", DebuggableReply::new(&reply));
+    ctx.send(reply).await?;
+    Ok(())
+}
+
+poise_instrument! {
+    /// Picks one or more options at random
+    #[poise::command(slash_command, category = "Fun")]
+    pub async fn choose(
+        ctx: Context<'_>,
+        #[description = "Options separated by '|', optionally weighted with 'option*weight'"]
+        options: String,
+        #[description = "Number of options to pick, without repeats (default: 1)"]
+        #[min = 1]
+        count: Option<usize>,
+        #[description = "Visible to you only? (default: false)"] ephemeral: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        choose_options(ctx, options, count, ephemeral).await
+    }
+
+    /// Picks one or more options at random, e.g. `choose pizza | tacos*2 | sushi --count 2`.
+    ///
+    /// Prefix variant of `choose`. The options take the whole message, since they contain spaces, so the count is
+    /// passed as a `--count` option.
+    #[poise::command(
+        prefix_command,
+        category = "Fun",
+        aliases("choose"),
+        hide_in_help,
+        track_edits,
+        track_deletion
+    )]
+    pub async fn choose_prefix(ctx: Context<'_>, #[rest] args: String) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let (options, count) = parse_prefix_choice_args(&args)?;
+        choose_options(ctx, options, count, None).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::infrastructure::random::SeededRandom;
+
+    use super::*;
+
+    fn option(name: &str, weight: f64) -> ChoiceOption {
+        ChoiceOption {
+            name: name.to_string(),
+            weight,
+        }
+    }
+
+    #[test]
+    fn options_are_parsed_with_their_weight() {
+        assert_eq!(
+            parse_choice_options("pizza | tacos*2.5 |  | sushi bar ").unwrap(),
+            vec![
+                option("pizza", 1.0),
+                option("tacos", 2.5),
+                option("sushi bar", 1.0)
+            ]
+        );
+        // Only a number after the last '*' is a weight, so times and ratios are options of their own
+        assert_eq!(
+            parse_choice_options("10:30 | 11:00*3 | a*b | 16:9").unwrap(),
+            vec![
+                option("10:30", 1.0),
+                option("11:00", 3.0),
+                option("a*b", 1.0),
+                option("16:9", 1.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_options_are_refused() {
+        for options in [
+            "",
+            "pizza",
+            "pizza | *2",
+            "pizza | tacos*0",
+            "pizza | tacos*-1",
+            "pizza | tacos*inf",
+        ] {
+            assert!(parse_choice_options(options).is_err(), "{}", options);
+        }
+        let too_many = vec!["x"; MAX_CHOICE_OPTIONS + 1].join("|");
+        assert!(parse_choice_options(&too_many).is_err());
+    }
+
+    #[test]
+    fn prefix_count_is_taken_out_of_the_options() {
+        let parse = |args: &str| {
+            parse_prefix_choice_args(args)
+                .map(|(options, count)| (parse_choice_options(&options).unwrap(), count))
+        };
+        let options = vec![option("pizza", 1.0), option("tacos", 2.0)];
+        assert_eq!(parse("pizza | tacos*2").unwrap(), (options.clone(), None));
+        assert_eq!(
+            parse("pizza | tacos*2 --count 2").unwrap(),
+            (options.clone(), Some(2))
+        );
+        assert_eq!(
+            parse("--count=2 pizza | tacos*2").unwrap(),
+            (options.clone(), Some(2))
+        );
+        assert!(parse("pizza | tacos --count two").is_err());
+        assert!(parse("pizza | tacos --count").is_err());
+    }
+
+    #[test]
+    fn chosen_options_are_distinct() {
+        let options = parse_choice_options("a | b*5 | c").unwrap();
+        let random = SeededRandom::new(1);
+        let mut chosen: Vec<&str> = choose_weighted(&random, &options, 3)
+            .into_iter()
+            .map(|x| x.name.as_str())
+            .collect();
+        chosen.sort();
+        assert_eq!(chosen, vec!["a", "b", "c"]);
+        assert_eq!(choose_weighted(&random, &options, 1).len(), 1);
+    }
+}
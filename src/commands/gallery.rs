@@ -0,0 +1,127 @@
+use migration::OnConflict;
+use poise::{
+    CreateReply,
+    serenity_prelude::{ChannelId, ChannelType, GuildChannel, Mentionable},
+};
+use sea_orm::{ActiveValue::Set, ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::{
+    Context, Error,
+    entities::gallery_channel,
+    infrastructure::ids::{id_from_string, id_to_string, require_guild_id},
+    poise_instrument, record_ctx_fields,
+};
+
+/// Set of commands to open discussion threads under media posts in gallery channels.
+#[poise::command(
+    slash_command,
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR",
+    guild_only,
+    category = "Management",
+    subcommands("enable", "disable", "list")
+)]
+pub async fn gallery(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Opens a thread under every message with attachments or links in a channel.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn enable(
+        ctx: Context<'_>,
+        #[description = "Channel to open threads in"]
+        #[channel_types("Text", "News")]
+        channel: GuildChannel,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        if !matches!(channel.kind, ChannelType::Text | ChannelType::News) {
+            return Err(format!("{} is not a text or announcement channel", channel.mention()).into());
+        }
+        gallery_channel::Entity::insert(gallery_channel::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            channel_id: Set(id_to_string(channel.id)),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                gallery_channel::Column::GuildId,
+                gallery_channel::Column::ChannelId,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .exec_without_returning(&ctx.data().db_pool)
+        .await?;
+        ctx.send(
+            CreateReply::default()
+                .content(format!("Media posts in {} get a discussion thread", channel.mention()))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Stops opening threads under media posts in a channel.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn disable(
+        ctx: Context<'_>,
+        #[description = "Channel to stop opening threads in"] channel: GuildChannel,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let result =
+            gallery_channel::Entity::delete_by_id((id_to_string(guild_id), id_to_string(channel.id)))
+                .exec(&ctx.data().db_pool)
+                .await?;
+        let content = if result.rows_affected > 0 {
+            format!("Media posts in {} no longer get a thread", channel.mention())
+        } else {
+            format!("{} is not a gallery channel", channel.mention())
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Lists the gallery channels of this guild.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn list(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let channels: Vec<String> = gallery_channel::Entity::find()
+            .filter(gallery_channel::Column::GuildId.eq(id_to_string(guild_id)))
+            .all(&ctx.data().db_pool)
+            .await?
+            .into_iter()
+            .filter_map(|x| id_from_string::<ChannelId>(&x.channel_id).ok())
+            .map(|x| x.mention().to_string())
+            .collect();
+        let content = if channels.is_empty() {
+            "There are no gallery channels on this guild".to_string()
+        } else {
+            format!("Gallery channels: {}", channels.join(", "))
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+use poise::{CreateReply, serenity_prelude::Mentionable};
+use tracing::info;
+
+use crate::{
+    Context, Error,
+    events::games::{HANGMAN_WORDS, hangman_board},
+    infrastructure::{
+        game_sessions::{
+            Game, GameSession, delete_game_session, get_game_session, save_game_session,
+        },
+        ids::require_guild_id,
+    },
+    poise_instrument, record_ctx_fields,
+};
+
+/// Channel games played by sending guesses as messages.
+#[poise::command(
+    slash_command,
+    guild_only,
+    category = "Fun",
+    subcommands("numberguess", "hangman", "stop")
+)]
+pub async fn game(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Guess a random number, with hints whether it is higher or lower.
+#[poise::command(
+    slash_command,
+    guild_only,
+    category = "Fun",
+    subcommands("numberguess_start")
+)]
+pub async fn numberguess(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Guess the letters of a word before running out of lives.
+#[poise::command(
+    slash_command,
+    guild_only,
+    category = "Fun",
+    subcommands("hangman_start")
+)]
+pub async fn hangman(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Registers a new game in the channel of the command and announces it.
+async fn start_game(ctx: Context<'_>, game: Game, intro: String) -> Result<(), Error> {
+    let session = GameSession {
+        guild_id: require_guild_id(ctx)?,
+        started_by: ctx.author().id,
+        started_at: ctx.data().clock.unix_timestamp(),
+        game,
+    };
+    {
+        let mut sessions = ctx
+            .data()
+            .game_sessions
+            .write()
+            .map_err(|_| "Game sessions lock is poisoned")?;
+        if let Some(running) = sessions.get(&ctx.channel_id()) {
+            return Err(format!(
+                "A game of {} is already running in this channel, use `/game stop` to end it",
+                running.game.name()
+            )
+            .into());
+        }
+        sessions.insert(ctx.channel_id(), session.clone());
+    }
+    save_game_session(&ctx.data().db_pool, ctx.channel_id(), &session).await?;
+    info!(
+        "Game of {} started in channel {}",
+        session.game.name(),
+        ctx.channel_id()
+    );
+
+    ctx.send(CreateReply::default().content(intro)).await?;
+    Ok(())
+}
+
+poise_instrument! {
+    /// Starts a game where the channel guesses a random number.
+    #[poise::command(slash_command, guild_only, category = "Fun", rename = "start")]
+    async fn numberguess_start(
+        ctx: Context<'_>,
+        #[description = "Highest possible number (default: 100)"]
+        #[min = 2]
+        #[max = 1000000]
+        max: Option<u64>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let max = max.unwrap_or(100);
+        let target = ctx.data().random.random_range(1..=max);
+        let intro = format!(
+            "🔢 {} started a number guessing game! Send a number between 1 and {} in this channel to guess.",
+            ctx.author().mention(),
+            max
+        );
+        start_game(
+            ctx,
+            Game::NumberGuess {
+                target,
+                max,
+                guesses: 0,
+            },
+            intro,
+        )
+        .await
+    }
+
+    /// Starts a game of hangman in this channel.
+    #[poise::command(slash_command, guild_only, category = "Fun", rename = "start")]
+    async fn hangman_start(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let index = ctx
+            .data()
+            .random
+            .random_index(HANGMAN_WORDS.len())
+            .ok_or("There are no hangman words")?;
+        let word = HANGMAN_WORDS[index].to_string();
+        let intro = format!(
+            "🪢 {} started a game of hangman! Send a letter or the whole word in this channel to guess.\n{}",
+            ctx.author().mention(),
+            hangman_board(&word, &[], 0)
+        );
+        start_game(
+            ctx,
+            Game::Hangman {
+                word,
+                guessed: vec![],
+                wrong_guesses: 0,
+            },
+            intro,
+        )
+        .await
+    }
+
+    /// Ends the game running in this channel. Only its starter or moderators can stop it.
+    #[poise::command(slash_command, guild_only, category = "Fun")]
+    async fn stop(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let session = get_game_session(&ctx.data().game_sessions, ctx.channel_id())
+            .ok_or("No game is running in this channel")?;
+        let can_manage = ctx
+            .author_member()
+            .await
+            .and_then(|x| x.permissions)
+            .is_some_and(|x| x.manage_messages());
+        if session.started_by != ctx.author().id && !can_manage {
+            return Err("Only the member who started the game or moderators can stop it".into());
+        }
+
+        if let Ok(mut sessions) = ctx.data().game_sessions.write() {
+            sessions.remove(&ctx.channel_id());
+        }
+        delete_game_session(&ctx.data().db_pool, ctx.channel_id()).await?;
+
+        let reveal = match &session.game {
+            Game::NumberGuess { target, .. } => format!("The number was **{}**.", target),
+            Game::Hangman { word, .. } => format!("The word was **{}**.", word),
+        };
+        ctx.send(CreateReply::default().content(format!(
+            "The game of {} was stopped by {}. {}",
+            session.game.name(),
+            ctx.author().mention(),
+            reveal
+        )))
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,474 @@
+use poise::serenity_prelude::futures::{self, Stream, StreamExt};
+use poise::serenity_prelude::{self as serenity, GuildId, UserId};
+use poise::{ChoiceParameter, CreateReply};
+use sea_orm::ActiveValue::Set;
+use tracing::{debug, info, trace};
+
+use crate::commands::minecraft::{
+    MAX_AUTOCOMPLETE_CHOICES, can_manage_mcserver, highlight_matches, rank_server_names,
+};
+use crate::entities::game_server;
+use crate::infrastructure::botdata::Data;
+use crate::infrastructure::branding::{Branding, get_branding, guild_branding};
+use crate::infrastructure::colors;
+use crate::infrastructure::ids::{id_from_string, id_to_string, require_guild_id};
+use crate::infrastructure::server_ping::{self, GameProtocol};
+use crate::infrastructure::util::{
+    DebuggableReply, Paginator, defer_or_broadcast, resolve_ephemeral,
+};
+use crate::services::game_servers::GameServerRepo;
+use crate::{Context, Error, poise_instrument, record_ctx_fields};
+
+/// Prefix of the custom_id for the "Refresh" button on status embeds. The server name follows the prefix.
+pub const REFRESH_STATUS_ID_PREFIX: &str = "server_status_refresh:";
+
+#[tracing::instrument(level = tracing::Level::TRACE, skip(ctx))]
+async fn server_autocomplete<'a>(
+    ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Stream<Item = serenity::AutocompleteChoice> + 'a {
+    let guild_id = match require_guild_id(ctx) {
+        Ok(id) => id,
+        Err(_) => return futures::stream::empty().boxed(),
+    };
+
+    let names = GameServerRepo::new(&ctx.data().db_pool)
+        .names(guild_id)
+        .await
+        .unwrap_or_default();
+    let result: Vec<serenity::AutocompleteChoice> = rank_server_names(names, partial)
+        .into_iter()
+        .take(MAX_AUTOCOMPLETE_CHOICES)
+        .map(|(name, indices)| {
+            serenity::AutocompleteChoice::new(highlight_matches(&name, &indices), name)
+        })
+        .collect();
+    trace!("Produced autocomplete values: {:?}", result);
+    futures::stream::iter(result).boxed()
+}
+
+/// Set of commands to check status and update registration of advertised game servers.
+///
+/// Supports Source (Steam), FiveM, Terraria (TShock) and minecraft servers. Minecraft servers are better advertised
+/// with `/mc`, which also shows their modpack and charts their player count.
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    subcommands("status", "overview", "add", "update", "remove")
+)]
+pub async fn server(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+struct GameServerResult {
+    name: String,
+    protocol: GameProtocol,
+    address: String,
+    /// Port set for the server, if it doesn't use the default port of its protocol.
+    port: Option<u16>,
+    description: Option<String>,
+    thumbnail: Option<String>,
+    /// The member who added the server.
+    owner: Option<UserId>,
+}
+
+impl TryFrom<game_server::Model> for GameServerResult {
+    type Error = Error;
+
+    fn try_from(value: game_server::Model) -> Result<Self, Self::Error> {
+        let protocol: GameProtocol = value.protocol.parse()?;
+        Ok(Self {
+            name: value.name,
+            protocol,
+            address: value.address,
+            port: u16::try_from(value.port).ok().filter(|x| *x != 0),
+            description: Some(value.description).filter(|x| !x.is_empty()),
+            thumbnail: Some(value.thumbnail).filter(|x| !x.is_empty()),
+            owner: id_from_string(&value.owner_id).ok(),
+        })
+    }
+}
+
+async fn find_server(
+    data: &Data,
+    guild_id: GuildId,
+    name: &str,
+) -> Result<Option<GameServerResult>, Error> {
+    GameServerRepo::new(&data.db_pool)
+        .find(guild_id, name)
+        .await?
+        .map(GameServerResult::try_from)
+        .transpose()
+}
+
+async fn get_server(ctx: Context<'_>, name: &str) -> Result<GameServerResult, Error> {
+    let guild_id = require_guild_id(ctx)?;
+    find_server(ctx.data(), guild_id, name)
+        .await?
+        .ok_or_else(|| format!("Server '{}' does not exist.", name).into())
+}
+
+/// Pings the server and builds the status embed shown by `/server status`.
+async fn build_status_embed(
+    server: GameServerResult,
+    branding: &Branding,
+) -> serenity::CreateEmbed {
+    let port = server.port.unwrap_or(server.protocol.default_port());
+    let status_result = server_ping::pinger(server.protocol)
+        .ping(&server.address, port)
+        .await;
+
+    let mut embed = branding
+        .embed()
+        .title(format!("{} Server Status", server.name));
+    embed = match server.port {
+        Some(port) => embed.field("Address", format!("{}:{}", server.address, port), false),
+        None => embed.field("Address", &server.address, false),
+    };
+    if let Some(thumbnail) = server.thumbnail {
+        embed = embed.thumbnail(thumbnail);
+    }
+
+    match status_result {
+        Ok(status) => {
+            if let Some(description) = server.description.or(status.name) {
+                embed = embed.description(description);
+            }
+            embed = embed.field(
+                "Game",
+                status
+                    .game
+                    .unwrap_or_else(|| server.protocol.name().to_string()),
+                true,
+            );
+            if let Some(map) = status.map {
+                embed = embed.field("Map", map, true);
+            }
+            if let Some(version) = status.version {
+                embed = embed.field("Version", version, true);
+            }
+            embed = embed
+                .color(colors::green())
+                .field("Status", "Online", false)
+                .field(
+                    "Players Online",
+                    format!("{}/{}", status.players, status.max_players),
+                    false,
+                );
+        }
+        Err(e) => {
+            if let Some(description) = server.description {
+                embed = embed.description(description);
+            }
+            embed = embed
+                .color(colors::red())
+                .field("Game", server.protocol.name(), true)
+                .field("Status", "Offline", false);
+            info!("Game server '{}' is offline: {}", server.name, e);
+        }
+    }
+    embed
+}
+
+fn status_components(name: &str) -> Vec<serenity::CreateActionRow> {
+    vec![serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(format!("{}{}", REFRESH_STATUS_ID_PREFIX, name))
+            .label("Refresh")
+            .emoji('🔄')
+            .style(serenity::ButtonStyle::Secondary),
+    ])]
+}
+
+poise_instrument! {
+    /// Gets the status of a game server advertised on this guild.
+    #[poise::command(slash_command, prefix_command, guild_only)]
+    async fn status(
+        ctx: Context<'_>,
+        #[description = "Server Name"]
+        #[autocomplete = "server_autocomplete"]
+        name: String,
+        #[description = "Visible to you only? (default: true)"] ephemeral: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+
+        let ephemeral_resolved = resolve_ephemeral(ctx, ephemeral, true).await;
+        let _typing = defer_or_broadcast(ctx, ephemeral_resolved).await?;
+
+        let server = get_server(ctx, &name).await?;
+        debug!("Found server {:?}", server);
+        let branding = guild_branding(ctx).await;
+        let reply = CreateReply::default()
+            .embed(build_status_embed(server, &branding).await)
+            .components(status_components(&name))
+            .ephemeral(ephemeral_resolved);
+        trace!("Sending reply: {:?}", DebuggableReply::new(&reply));
+        ctx.send(reply).await?;
+        Ok(())
+    }
+}
+
+poise_instrument! {
+    /// Gets the status of every game server advertised on this guild.
+    #[poise::command(slash_command, prefix_command, guild_only)]
+    async fn overview(
+        ctx: Context<'_>,
+        #[description = "Visible to you only? (default: true)"] ephemeral: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+
+        let ephemeral_resolved = resolve_ephemeral(ctx, ephemeral, true).await;
+        let _typing = defer_or_broadcast(ctx, ephemeral_resolved).await?;
+
+        let guild_id = require_guild_id(ctx)?;
+        let servers = GameServerRepo::new(&ctx.data().db_pool)
+            .list(guild_id)
+            .await?
+            .into_iter()
+            .map(GameServerResult::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        if servers.is_empty() {
+            return Err("No game servers are advertised on this guild.".into());
+        }
+
+        let branding = guild_branding(ctx).await;
+        let pages = futures::future::join_all(
+            servers
+                .into_iter()
+                .map(|server| build_status_embed(server, &branding)),
+        )
+        .await;
+        Paginator::new(pages)
+            .ephemeral(ephemeral_resolved)
+            .send(ctx)
+            .await
+    }
+}
+
+/// Handles the "Refresh" button on a status embed by pinging the server again and editing the embed in place.
+#[tracing::instrument(level = tracing::Level::INFO, err(level = tracing::Level::WARN), skip(ctx, data, interaction))]
+pub async fn refresh_status(
+    ctx: &serenity::Context,
+    data: &Data,
+    interaction: &serenity::ComponentInteraction,
+    name: &str,
+) -> Result<(), Error> {
+    let guild_id = interaction
+        .guild_id
+        .ok_or("This function is only available in guilds")?;
+
+    // Pinging may exceed the interaction response window, so acknowledge first and edit afterwards.
+    interaction
+        .create_response(ctx, serenity::CreateInteractionResponse::Acknowledge)
+        .await?;
+
+    let response = match find_server(data, guild_id, name).await? {
+        Some(server) => {
+            let branding = get_branding(&data.db_pool, &data.branding, guild_id).await;
+            serenity::EditInteractionResponse::new()
+                .embed(build_status_embed(server, &branding).await)
+                .components(status_components(name))
+        }
+        None => {
+            info!("Game server '{}' no longer exists.", name);
+            serenity::EditInteractionResponse::new()
+                .embed(
+                    serenity::CreateEmbed::new()
+                        .title(format!("{} Server Status", name))
+                        .description("This server is no longer advertised on this guild.")
+                        .color(colors::red()),
+                )
+                .components(vec![])
+        }
+    };
+    interaction.edit_response(ctx, response).await?;
+    Ok(())
+}
+
+poise_instrument! {
+    /// Adds an advertised game server. Requires the /mc manager role or administrator.
+    #[poise::command(slash_command, prefix_command, guild_only)]
+    async fn add(
+        ctx: Context<'_>,
+        #[description = "Server Name"] name: String,
+        #[description = "Protocol the server is queried with"] protocol: GameProtocol,
+        #[description = "Host name or IP address"] address: String,
+        #[description = "Query port (default: the usual port of the protocol)"] port: Option<u16>,
+        #[description = "Shown instead of the name the server reports"] description: Option<String>,
+        #[description = "Image URL shown in the status"] thumbnail: Option<String>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+
+        if !can_manage_mcserver(ctx, None).await? {
+            return Err("You don't have permission to add game servers.".into());
+        }
+
+        let guild_id = require_guild_id(ctx)?;
+        let repo = GameServerRepo::new(&ctx.data().db_pool);
+        if repo.find(guild_id, &name).await?.is_some() {
+            return Err(format!("Server '{}' already exists.", name).into());
+        }
+
+        repo.insert(game_server::Model {
+            guild_id: id_to_string(guild_id),
+            name: name.clone(),
+            protocol: protocol.as_str().to_string(),
+            address,
+            port: port.unwrap_or(0) as i32,
+            description: description.unwrap_or_default(),
+            thumbnail: thumbnail.unwrap_or_default(),
+            owner_id: id_to_string(ctx.author().id),
+        })
+        .await?;
+
+        ctx.send(
+            CreateReply::default()
+                .content(format!("Successfully added server '{}'", name))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Changes to an advertised game server. The `clear_*` switches reset the field to empty.
+#[derive(Debug, Default)]
+struct GameServerUpdate {
+    protocol: Option<GameProtocol>,
+    address: Option<String>,
+    port: Option<u16>,
+    description: Option<String>,
+    clear_description: Option<bool>,
+    thumbnail: Option<String>,
+    clear_thumbnail: Option<bool>,
+}
+
+impl GameServerUpdate {
+    /// Sets the changed columns of `model`, returning false if nothing changed.
+    fn apply(self, model: &mut game_server::ActiveModel) -> bool {
+        let mut updated = false;
+        if let Some(x) = self.protocol {
+            model.protocol = Set(x.as_str().to_string());
+            updated = true;
+        }
+        if let Some(x) = self.address {
+            model.address = Set(x);
+            updated = true;
+        }
+        if let Some(x) = self.port {
+            model.port = Set(x as i32);
+            updated = true;
+        }
+        if self.clear_description.unwrap_or(false) {
+            model.description = Set(String::new());
+            updated = true;
+        } else if let Some(x) = self.description {
+            model.description = Set(x);
+            updated = true;
+        }
+        if self.clear_thumbnail.unwrap_or(false) {
+            model.thumbnail = Set(String::new());
+            updated = true;
+        } else if let Some(x) = self.thumbnail {
+            model.thumbnail = Set(x);
+            updated = true;
+        }
+        updated
+    }
+}
+
+/// Updates an advertised game server.
+async fn update_server(
+    ctx: Context<'_>,
+    name: String,
+    update: GameServerUpdate,
+) -> Result<(), Error> {
+    let server = get_server(ctx, &name).await?;
+    if !can_manage_mcserver(ctx, server.owner).await? {
+        return Err(format!("You don't have permission to update server '{}'.", name).into());
+    }
+
+    let guild_id = require_guild_id(ctx)?;
+    let mut model = game_server::ActiveModel {
+        guild_id: Set(id_to_string(guild_id)),
+        name: Set(name.clone()),
+        ..Default::default()
+    };
+    if !update.apply(&mut model) {
+        return Err("At least one parameter must be updated.".into());
+    }
+
+    GameServerRepo::new(&ctx.data().db_pool)
+        .update(model)
+        .await?;
+
+    ctx.send(
+        CreateReply::default()
+            .content(format!("Successfully updated server '{}'", name))
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+poise_instrument! {
+    /// Updates an advertised game server. Requires ownership, the /mc manager role or administrator.
+    #[poise::command(slash_command, guild_only)]
+    async fn update(
+        ctx: Context<'_>,
+        #[description = "Server Name"]
+        #[autocomplete = "server_autocomplete"]
+        name: String,
+        #[description = "Protocol the server is queried with"] protocol: Option<GameProtocol>,
+        #[description = "Host name or IP address"] address: Option<String>,
+        #[description = "Query port (0 for the usual port of the protocol)"] port: Option<u16>,
+        #[description = "Shown instead of the name the server reports"] description: Option<String>,
+        clear_description: Option<bool>,
+        #[description = "Image URL shown in the status"] thumbnail: Option<String>,
+        clear_thumbnail: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let update = GameServerUpdate {
+            protocol,
+            address,
+            port,
+            description,
+            clear_description,
+            thumbnail,
+            clear_thumbnail,
+        };
+        update_server(ctx, name, update).await
+    }
+}
+
+poise_instrument! {
+    /// Removes an advertised game server. Requires ownership, the /mc manager role or administrator.
+    #[poise::command(slash_command, prefix_command, guild_only)]
+    async fn remove(
+        ctx: Context<'_>,
+        #[description = "Server Name"]
+        #[autocomplete = "server_autocomplete"]
+        name: String,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+
+        let server = get_server(ctx, &name).await?;
+        if !can_manage_mcserver(ctx, server.owner).await? {
+            return Err(format!("You don't have permission to remove server '{}'.", name).into());
+        }
+
+        let guild_id = require_guild_id(ctx)?;
+        GameServerRepo::new(&ctx.data().db_pool)
+            .remove(guild_id, &name)
+            .await?;
+
+        ctx.send(
+            CreateReply::default()
+                .content(format!("Successfully removed server '{}'", name))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,315 @@
+use poise::{
+    CreateReply,
+    serenity_prelude::{
+        ChannelType, CreateAllowedMentions, CreateMessage, CreateScheduledEvent, GuildChannel,
+        GuildId, Mentionable, Role, RoleId, ScheduledEventType, Timestamp,
+    },
+};
+use tracing::{info, trace, warn};
+
+use crate::{
+    Context, Error,
+    infrastructure::{
+        branding::guild_branding,
+        game_nights::{
+            close_game_night, event_name, rsvp_buttons, rsvp_embed, schedule_game_night_jobs,
+        },
+        ids::require_guild_id,
+        timestamps::{TimestampStyle, parse_datetime},
+        util::DebuggableReply,
+    },
+    poise_instrument, record_ctx_fields,
+    services::{
+        game_nights::{GameNightRepo, GameNightSettings},
+        user_timezones::UserTimezoneRepo,
+    },
+};
+
+/// Game nights a guild may have scheduled at once.
+const MAX_GAME_NIGHTS: usize = 25;
+
+/// How far ahead game nights may be scheduled.
+const MAX_DAYS_AHEAD: i64 = 90;
+
+const MAX_GAME_LENGTH: usize = 80;
+
+/// Pings a role with a message, or nobody. Mirrors the pings of `/announce`.
+fn with_role_ping(
+    message: CreateMessage,
+    guild_id: GuildId,
+    role: Option<RoleId>,
+) -> CreateMessage {
+    match role {
+        // The @everyone role shares its id with the guild
+        Some(role) if role.get() == guild_id.get() => message
+            .content("@everyone")
+            .allowed_mentions(CreateAllowedMentions::new().everyone(true)),
+        Some(role) => message
+            .content(role.mention().to_string())
+            .allowed_mentions(CreateAllowedMentions::new().roles(vec![role])),
+        None => message.allowed_mentions(CreateAllowedMentions::new()),
+    }
+}
+
+/// Set of commands to schedule game nights, with an RSVP message and a voice lobby opened at the start.
+#[poise::command(
+    slash_command,
+    guild_only,
+    category = "Fun",
+    subcommands("schedule", "cancel", "list", "configure")
+)]
+pub async fn gamenight(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Schedules a game night, posting an RSVP message in this channel
+    ///
+    /// The time is read in the timezone saved with `/timezone set`. Members who RSVP are reminded before the start, and
+    /// a voice channel is opened for the game when it starts.
+    #[poise::command(
+        slash_command,
+        required_permissions = "MANAGE_EVENTS",
+        default_member_permissions = "MANAGE_EVENTS",
+        guild_only,
+        category = "Fun"
+    )]
+    async fn schedule(
+        ctx: Context<'_>,
+        #[description = "Start time such as friday at 20:00, tomorrow 9pm or 2026-12-31 21:00"]
+        when: String,
+        #[description = "Game to play, such as Among Us"] game: String,
+        #[description = "Role to ping (default: the configured game night role)"]
+        role: Option<Role>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let db = &ctx.data().db_pool;
+        let now = ctx.data().clock.unix_timestamp();
+        let game = game.trim().to_string();
+        if game.is_empty() {
+            return Err("The game can't be empty".into());
+        }
+        if game.chars().count() > MAX_GAME_LENGTH {
+            return Err(format!("Game names are limited to {} characters", MAX_GAME_LENGTH).into());
+        }
+        let utc_offset = UserTimezoneRepo::new(db).utc_offset(ctx.author().id).await?;
+        let starts_at = parse_datetime(&when, now, utc_offset.unwrap_or(0))?;
+        if starts_at <= now {
+            return Err("Game nights can only be scheduled in the future".into());
+        }
+        if starts_at > now + MAX_DAYS_AHEAD * 24 * 60 * 60 {
+            return Err(format!(
+                "Game nights can be scheduled at most {} days ahead",
+                MAX_DAYS_AHEAD
+            )
+            .into());
+        }
+
+        let repo = GameNightRepo::new(db);
+        if repo.list(guild_id).await?.len() >= MAX_GAME_NIGHTS {
+            return Err(format!(
+                "This server already has {} game nights scheduled, cancel one first",
+                MAX_GAME_NIGHTS
+            )
+            .into());
+        }
+        let settings = repo.settings(guild_id).await?;
+        let role = role.map(|x| x.id).or(settings.role_id);
+        let id = repo
+            .create(guild_id, ctx.channel_id(), ctx.author().id, &game, starts_at)
+            .await?;
+        let mut night = repo.get(id).await?.ok_or("The game night couldn't be saved")?;
+
+        // The game night still works without its scheduled event, e.g. without the permission to
+        // manage events
+        let ends_at = starts_at + settings.lobby_hours.max(1) as i64 * 60 * 60;
+        let event = CreateScheduledEvent::new(
+            ScheduledEventType::External,
+            event_name(&game),
+            Timestamp::from_unix_timestamp(starts_at)?,
+        )
+        .end_time(Timestamp::from_unix_timestamp(ends_at)?)
+        .location("A voice channel opens at the start")
+        .description(format!(
+            "Hosted by {}. Members who RSVP on the server are reminded before the start.",
+            ctx.author().name
+        ));
+        match guild_id.create_scheduled_event(ctx, event).await {
+            Ok(event) => {
+                repo.set_event(id, event.id).await?;
+                night.event_id = Some(event.id);
+            }
+            Err(e) => warn!("Failed to create the scheduled event of game night {}: {:?}", id, e),
+        }
+
+        let branding = guild_branding(ctx).await;
+        let message = with_role_ping(CreateMessage::new(), guild_id, role)
+            .embed(rsvp_embed(&branding, &night, &[]))
+            .components(rsvp_buttons(id));
+        let message = match ctx.channel_id().send_message(ctx, message).await {
+            Ok(x) => x,
+            Err(e) => {
+                close_game_night(ctx.http(), db, &night).await?;
+                return Err(e.into());
+            }
+        };
+        repo.set_message(id, message.id).await?;
+        night.message_id = Some(message.id);
+        schedule_game_night_jobs(db, &night, &settings, now).await?;
+        info!("{} scheduled game night {} of {} at {}", ctx.author().id, id, game, starts_at);
+
+        let mut content = format!(
+            "Game night #{} is scheduled for {}.",
+            id,
+            TimestampStyle::LongDateTime.markup(starts_at)
+        );
+        if night.event_id.is_none() {
+            content.push_str(
+                " The server event couldn't be created, check that I can manage events.",
+            );
+        }
+        ctx.send(CreateReply::default().content(content).ephemeral(true)).await?;
+        Ok(())
+    }
+
+    /// Cancels a game night, deleting its event and its voice channel
+    #[poise::command(
+        slash_command,
+        required_permissions = "MANAGE_EVENTS",
+        default_member_permissions = "MANAGE_EVENTS",
+        guild_only,
+        category = "Fun"
+    )]
+    async fn cancel(
+        ctx: Context<'_>,
+        #[description = "Number of the game night, shown by /gamenight list"] id: i32,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let db = &ctx.data().db_pool;
+        let night = match GameNightRepo::new(db).get(id).await? {
+            Some(x) if x.guild_id == guild_id => x,
+            _ => return Err(format!("There is no game night #{} on this server", id).into()),
+        };
+        close_game_night(ctx.http(), db, &night).await?;
+        info!("{} cancelled game night {}", ctx.author().id, id);
+        ctx.send(
+            CreateReply::default()
+                .content(format!("Game night #{} of {} was cancelled.", id, night.game))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Lists the game nights scheduled on this server
+    #[poise::command(slash_command, guild_only, category = "Fun")]
+    async fn list(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let repo = GameNightRepo::new(&ctx.data().db_pool);
+        let nights = repo.list(guild_id).await?;
+        if nights.is_empty() {
+            return Err(
+                "No game nights are scheduled, schedule one with /gamenight schedule".into(),
+            );
+        }
+        let mut lines = vec![];
+        for night in &nights {
+            let going = repo.attendees(night.id).await?.len();
+            let mut line = format!(
+                "**#{}** {} — {} ({}), {} going",
+                night.id,
+                night.game,
+                TimestampStyle::ShortDateTime.markup(night.starts_at),
+                TimestampStyle::Relative.markup(night.starts_at),
+                going
+            );
+            if let Some(message_id) = night.message_id {
+                let link = message_id.link(night.channel_id, Some(guild_id));
+                line.push_str(&format!(" — [RSVP]({})", link));
+            }
+            lines.push(line);
+        }
+
+        let branding = guild_branding(ctx).await;
+        let reply = CreateReply::default()
+            .embed(branding.embed().title("Game nights").description(lines.join("\n")))
+            .ephemeral(true);
+        trace!("Sending reply: {:?}", DebuggableReply::new(&reply));
+        ctx.send(reply).await?;
+        Ok(())
+    }
+
+    /// Sets the role pinged for game nights, where their voice channels go and when members are reminded
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Fun"
+    )]
+    async fn configure(
+        ctx: Context<'_>,
+        #[description = "Role pinged when a game night is scheduled (default: no role)"]
+        role: Option<Role>,
+        #[description = "Category the voice channels are created in (default: no category)"]
+        #[channel_types("Category")]
+        category: Option<GuildChannel>,
+        #[description = "Minutes before the start to remind members who RSVPed, 0 for never (default: 15)"]
+        #[rename = "reminder-minutes"]
+        #[min = 0]
+        #[max = 1440]
+        reminder_minutes: Option<u32>,
+        #[description = "Hours the voice channel is kept after the start (default: 6)"]
+        #[rename = "lobby-hours"]
+        #[min = 1]
+        #[max = 24]
+        lobby_hours: Option<u32>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        if let Some(category) = &category
+            && (category.guild_id != guild_id || category.kind != ChannelType::Category)
+        {
+            return Err("Voice channels can only be created in a category of this server".into());
+        }
+        let defaults = GameNightSettings::default();
+        let settings = GameNightSettings {
+            role_id: role.map(|x| x.id),
+            category_id: category.map(|x| x.id),
+            reminder_minutes: reminder_minutes.unwrap_or(defaults.reminder_minutes).min(1440),
+            lobby_hours: lobby_hours.unwrap_or(defaults.lobby_hours).clamp(1, 24),
+        };
+        GameNightRepo::new(&ctx.data().db_pool)
+            .save_settings(guild_id, &settings)
+            .await?;
+        info!("Set the game night settings of guild {} to {:?}", guild_id, settings);
+
+        let role = match settings.role_id {
+            Some(role) if role.get() == guild_id.get() => "@everyone".to_string(),
+            Some(role) => role.mention().to_string(),
+            None => "nobody".to_string(),
+        };
+        let category = match settings.category_id {
+            Some(category) => category.mention().to_string(),
+            None => "no category".to_string(),
+        };
+        let reminder = match settings.reminder_minutes {
+            0 => "Members who RSVP aren't reminded".to_string(),
+            x => format!("Members who RSVP are reminded {} minute(s) before the start", x),
+        };
+        ctx.send(
+            CreateReply::default()
+                .content(format!(
+                    "Game nights ping {}. Their voice channels are created in {} and kept {} \
+                    hour(s) after the start. {}.",
+                    role, category, settings.lobby_hours, reminder
+                ))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,298 @@
+use std::time::Duration;
+
+use poise::{ChoiceParameter as _, CreateReply, serenity_prelude::CreateEmbedFooter};
+use reqwest::Url;
+use serde::Deserialize;
+
+use crate::{
+    Context, Error,
+    commands::gamestats::{GameApiError, fetch_json},
+    infrastructure::{
+        branding::guild_branding,
+        environment::RIOT_API_KEY,
+        util::{DebuggableReply, defer_or_broadcast, resolve_ephemeral},
+    },
+    poise_instrument, record_ctx_fields,
+};
+
+/// Number of recent matches shown by `/lol summoner`.
+const RECENT_MATCH_COUNT: usize = 5;
+
+/// Riot IDs rarely change, so accounts are cached longer than the statistics.
+const ACCOUNT_TTL: Duration = Duration::from_secs(60 * 60);
+const STATS_TTL: Duration = Duration::from_secs(60 * 5);
+const MATCH_IDS_TTL: Duration = Duration::from_secs(60 * 2);
+/// Finished matches never change.
+const MATCH_TTL: Duration = Duration::from_secs(60 * 60 * 24);
+
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum LolRegion {
+    #[name = "NA"]
+    Na,
+    #[name = "EUW"]
+    Euw,
+    #[name = "EUNE"]
+    Eune,
+    #[name = "KR"]
+    Kr,
+    #[name = "JP"]
+    Jp,
+    #[name = "BR"]
+    Br,
+    #[name = "LAN"]
+    Lan,
+    #[name = "LAS"]
+    Las,
+    #[name = "OCE"]
+    Oce,
+    #[name = "TR"]
+    Tr,
+    #[name = "RU"]
+    Ru,
+}
+
+impl LolRegion {
+    /// Host of the platform the region's summoners and leagues are stored on.
+    fn platform(&self) -> &'static str {
+        match self {
+            Self::Na => "na1",
+            Self::Euw => "euw1",
+            Self::Eune => "eun1",
+            Self::Kr => "kr",
+            Self::Jp => "jp1",
+            Self::Br => "br1",
+            Self::Lan => "la1",
+            Self::Las => "la2",
+            Self::Oce => "oc1",
+            Self::Tr => "tr1",
+            Self::Ru => "ru",
+        }
+    }
+
+    /// Host of the regional cluster the region's accounts and matches are stored on.
+    fn regional(&self) -> &'static str {
+        match self {
+            Self::Na | Self::Br | Self::Lan | Self::Las => "americas",
+            Self::Euw | Self::Eune | Self::Tr | Self::Ru => "europe",
+            Self::Kr | Self::Jp => "asia",
+            Self::Oce => "sea",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RiotAccount {
+    puuid: String,
+    game_name: Option<String>,
+    tag_line: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Summoner {
+    profile_icon_id: i64,
+    summoner_level: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LeagueEntry {
+    queue_type: String,
+    tier: String,
+    rank: String,
+    league_points: i32,
+    wins: i32,
+    losses: i32,
+}
+
+#[derive(Debug, Deserialize)]
+struct Match {
+    info: MatchInfo,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MatchInfo {
+    game_mode: String,
+    /// Seconds
+    game_duration: i64,
+    /// Unix timestamp in milliseconds
+    game_end_timestamp: Option<i64>,
+    participants: Vec<MatchParticipant>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MatchParticipant {
+    puuid: String,
+    champion_name: String,
+    kills: i32,
+    deaths: i32,
+    assists: i32,
+    win: bool,
+}
+
+/// Builds a Riot API url, escaping the path segments.
+fn riot_url(host: &str, path: &[&str]) -> Result<Url, Error> {
+    let mut url = Url::parse(&format!("https://{}.api.riotgames.com", host))?;
+    url.path_segments_mut()
+        .map_err(|_| "Riot API url can't have a path")?
+        .extend(path);
+    Ok(url)
+}
+
+fn queue_name(queue_type: &str) -> &str {
+    match queue_type {
+        "RANKED_SOLO_5x5" => "Ranked Solo/Duo",
+        "RANKED_FLEX_SR" => "Ranked Flex",
+        "CHERRY" => "Arena",
+        other => other,
+    }
+}
+
+fn format_league_entry(entry: &LeagueEntry) -> String {
+    let games = entry.wins + entry.losses;
+    let winrate = if games > 0 {
+        entry.wins as f64 / games as f64 * 100.0
+    } else {
+        0.0
+    };
+    format!(
+        "{} {} · {} LP\n{}W {}L ({:.0}% winrate)",
+        entry.tier, entry.rank, entry.league_points, entry.wins, entry.losses, winrate
+    )
+}
+
+/// Set of commands to look up League of Legends players
+#[poise::command(slash_command, subcommands("summoner"), category = "Fun")]
+pub async fn lol(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Shows the rank, winrate and recent matches of a League of Legends player
+    #[poise::command(slash_command, category = "Fun")]
+    async fn summoner(
+        ctx: Context<'_>,
+        #[description = "Riot ID of the player, e.g. Faker#KR1"] name: String,
+        #[description = "Region the player plays on (default: NA)"] region: Option<LolRegion>,
+        #[description = "Visible to you only? (default: false)"] ephemeral: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let api_key = std::env::var(RIOT_API_KEY)
+            .map_err(|_| "League of Legends lookups are not configured on this bot")?;
+        let (game_name, tag_line) = name
+            .rsplit_once('#')
+            .map(|(name, tag)| (name.trim(), tag.trim()))
+            .filter(|(name, tag)| !name.is_empty() && !tag.is_empty())
+            .ok_or("Use the Riot ID of the player, in the form Name#TAG")?;
+        let region = region.unwrap_or(LolRegion::Na);
+        let ephemeral = resolve_ephemeral(ctx, ephemeral, false).await;
+        let _typing = defer_or_broadcast(ctx, ephemeral).await?;
+
+        let data = ctx.data();
+        let http = reqwest::Client::new();
+        let headers = [("X-Riot-Token", api_key.as_str())];
+
+        let url = riot_url(
+            region.regional(),
+            &["riot", "account", "v1", "accounts", "by-riot-id", game_name, tag_line],
+        )?;
+        let account: RiotAccount = match fetch_json(data, &http, url.as_str(), &headers, ACCOUNT_TTL).await {
+            Err(e) if matches!(e.downcast_ref::<GameApiError>(), Some(GameApiError::NotFound)) => {
+                return Err(format!("There is no player named {}#{}", game_name, tag_line).into());
+            }
+            x => x?,
+        };
+        let puuid = account.puuid.as_str();
+
+        let url = riot_url(region.platform(), &["lol", "summoner", "v4", "summoners", "by-puuid", puuid])?;
+        let summoner: Summoner = match fetch_json(data, &http, url.as_str(), &headers, STATS_TTL).await {
+            Err(e) if matches!(e.downcast_ref::<GameApiError>(), Some(GameApiError::NotFound)) => {
+                return Err(format!(
+                    "{}#{} has not played League of Legends on {}",
+                    game_name,
+                    tag_line,
+                    region.name()
+                )
+                .into());
+            }
+            x => x?,
+        };
+
+        let url = riot_url(region.platform(), &["lol", "league", "v4", "entries", "by-puuid", puuid])?;
+        let leagues: Vec<LeagueEntry> = fetch_json(data, &http, url.as_str(), &headers, STATS_TTL).await?;
+
+        let mut url = riot_url(region.regional(), &["lol", "match", "v5", "matches", "by-puuid", puuid, "ids"])?;
+        url.query_pairs_mut()
+            .append_pair("count", &RECENT_MATCH_COUNT.to_string());
+        let match_ids: Vec<String> = fetch_json(data, &http, url.as_str(), &headers, MATCH_IDS_TTL).await?;
+
+        let mut matches = vec![];
+        for match_id in match_ids.iter().take(RECENT_MATCH_COUNT) {
+            let url = riot_url(region.regional(), &["lol", "match", "v5", "matches", match_id])?;
+            matches.push(fetch_json::<Match>(data, &http, url.as_str(), &headers, MATCH_TTL).await?);
+        }
+
+        let display_name = format!(
+            "{}#{}",
+            account.game_name.as_deref().unwrap_or(game_name),
+            account.tag_line.as_deref().unwrap_or(tag_line)
+        );
+        let branding = guild_branding(ctx).await;
+        let mut profile = branding.embed()
+            .title(&display_name)
+            .thumbnail(format!(
+                "https://raw.communitydragon.org/latest/game/assets/ux/summonericons/profileicon{}.png",
+                summoner.profile_icon_id
+            ))
+            .field("Level", summoner.summoner_level.to_string(), true)
+            .field("Region", region.name(), true)
+            .footer(CreateEmbedFooter::new("Data from the Riot Games API"));
+        if leagues.is_empty() {
+            profile = profile.field("Rank", "Unranked", false);
+        }
+        for entry in &leagues {
+            profile = profile.field(queue_name(&entry.queue_type), format_league_entry(entry), false);
+        }
+
+        let recent = matches
+            .iter()
+            .filter_map(|x| {
+                let player = x.info.participants.iter().find(|p| p.puuid == account.puuid)?;
+                let ended = x
+                    .info
+                    .game_end_timestamp
+                    .map(|ms| format!(" · <t:{}:R>", ms / 1000))
+                    .unwrap_or_default();
+                Some(format!(
+                    "{} **{}** {}/{}/{} · {} · {}m{}",
+                    if player.win { "✅" } else { "❌" },
+                    player.champion_name,
+                    player.kills,
+                    player.deaths,
+                    player.assists,
+                    x.info.game_mode,
+                    x.info.game_duration / 60,
+                    ended
+                ))
+            })
+            .collect::<Vec<String>>();
+        let recent = branding.embed()
+            .title("Recent matches")
+            .description(if recent.is_empty() {
+                "No recent matches".to_string()
+            } else {
+                recent.join("\n")
+            });
+
+        let reply = CreateReply::default()
+            .embed(profile)
+            .embed(recent)
+            .ephemeral(ephemeral);
+        tracing::trace!("Sending reply: {:?}", DebuggableReply::new(&reply));
+        ctx.send(reply).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+/*!
+
+Lookups of player statistics from game APIs.
+
+The game APIs are rate limited per API key, so responses are kept in a short-lived in-memory cache shared by every
+guild. Repeated lookups of the same player within the cache lifetime don't make any requests.
+
+*/
+
+pub mod lol;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use tracing::{trace, warn};
+
+use crate::{Error, infrastructure::botdata::Data};
+
+#[derive(Debug)]
+pub struct CachedResponse {
+    expires_at: Instant,
+    body: serde_json::Value,
+}
+
+/// Responses of game APIs, by request url.
+pub type ResponseCache = Arc<RwLock<HashMap<String, CachedResponse>>>;
+
+/// Error returned by a game API, described in a way that can be shown to users.
+#[derive(Debug)]
+pub enum GameApiError {
+    NotFound,
+    RateLimited(Option<Duration>),
+    Unauthorized,
+    Status(StatusCode),
+}
+
+impl std::fmt::Display for GameApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "Nothing was found"),
+            Self::RateLimited(Some(retry_after)) => write!(
+                f,
+                "Too many lookups, try again in {} seconds",
+                retry_after.as_secs().max(1)
+            ),
+            Self::RateLimited(None) => write!(f, "Too many lookups, try again later"),
+            Self::Unauthorized => write!(f, "The API key of the bot was rejected"),
+            Self::Status(status) => write!(f, "The game API returned {}", status),
+        }
+    }
+}
+
+impl std::error::Error for GameApiError {}
+
+/// Gets a json response from the cache of the bot, or requests it and caches it for `ttl`.
+///
+/// Only successful responses are cached. `headers` are sent with the request but are not part of the cache key.
+pub async fn fetch_json<T: DeserializeOwned>(
+    data: &Data,
+    http: &reqwest::Client,
+    url: &str,
+    headers: &[(&str, &str)],
+    ttl: Duration,
+) -> Result<T, Error> {
+    let cache = &data.gamestats_cache;
+    let now = data.clock.instant();
+    let cached = cache.read().ok().and_then(|cache| {
+        cache
+            .get(url)
+            .filter(|x| x.expires_at > now)
+            .map(|x| x.body.clone())
+    });
+    if let Some(body) = cached {
+        trace!("Using cached response of {}", url);
+        return Ok(serde_json::from_value(body)?);
+    }
+
+    let mut request = http.get(url);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let response = request.send().await?;
+    match response.status() {
+        status if status.is_success() => {}
+        StatusCode::NOT_FOUND => return Err(GameApiError::NotFound.into()),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            warn!("Game API rejected the API key for {}", url);
+            return Err(GameApiError::Unauthorized.into());
+        }
+        StatusCode::TOO_MANY_REQUESTS => {
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|x| x.to_str().ok())
+                .and_then(|x| x.parse::<u64>().ok())
+                .map(Duration::from_secs);
+            warn!(
+                "Game API is rate limiting requests, retry after {:?}",
+                retry_after
+            );
+            return Err(GameApiError::RateLimited(retry_after).into());
+        }
+        status => return Err(GameApiError::Status(status).into()),
+    }
+
+    let body: serde_json::Value = response.json().await?;
+    if let Ok(mut cache) = cache.write() {
+        let now = data.clock.instant();
+        cache.retain(|_, x| x.expires_at > now);
+        cache.insert(
+            url.to_string(),
+            CachedResponse {
+                expires_at: now + ttl,
+                body: body.clone(),
+            },
+        );
+    }
+    Ok(serde_json::from_value(body)?)
+}
@@ -0,0 +1,131 @@
+use poise::CreateReply;
+use tracing::info;
+
+use crate::{
+    Context, Error,
+    infrastructure::global_commands::{
+        PROTECTED_COMMANDS, disabled_commands, register_enabled_commands, set_command_disabled,
+    },
+    poise_instrument, record_ctx_fields,
+};
+
+/// Maximum number of choices discord shows for an autocompleted parameter.
+const MAX_AUTOCOMPLETE_CHOICES: usize = 25;
+
+#[tracing::instrument(level = tracing::Level::TRACE, skip(ctx))]
+async fn command_autocomplete(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let partial = partial.to_lowercase();
+    ctx.framework()
+        .options()
+        .commands
+        .iter()
+        .map(|x| x.name.clone())
+        .filter(|x| x.to_lowercase().contains(&partial))
+        .take(MAX_AUTOCOMPLETE_CHOICES)
+        .collect()
+}
+
+/// Registers the application commands again after the disabled commands changed, describing the
+/// outcome.
+async fn reregister(ctx: Context<'_>) -> String {
+    match register_enabled_commands(ctx, &ctx.framework().options().commands).await {
+        Ok(count) => format!("Registered {} application commands.", count),
+        Err(e) => format!(
+            "Failed to register application commands ({}), use `/register` to retry.",
+            e
+        ),
+    }
+}
+
+/// Set of commands to disable commands on every guild without restarting the bot.
+#[poise::command(
+    slash_command,
+    prefix_command,
+    owners_only,
+    hide_in_help,
+    rename = "global-commands",
+    subcommands("disable", "enable", "list")
+)]
+pub async fn global_commands(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Disables a command on every guild and removes it from the command list.
+    #[poise::command(slash_command, prefix_command, owners_only, hide_in_help)]
+    async fn disable(
+        ctx: Context<'_>,
+        #[description = "Name of the top level command"]
+        #[autocomplete = "command_autocomplete"]
+        command: String,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let name = command.trim().to_lowercase();
+        let exists = ctx
+            .framework()
+            .options()
+            .commands
+            .iter()
+            .any(|x| x.name.to_lowercase() == name);
+        if !exists {
+            return Err(format!("There is no command named `{}`", name).into());
+        }
+        if PROTECTED_COMMANDS.contains(&name.as_str()) {
+            return Err(format!("`{}` can't be disabled", name).into());
+        }
+        ctx.defer_ephemeral().await?;
+        if !set_command_disabled(&ctx.data().db_pool, &name, true).await? {
+            return Err(format!("`{}` is already disabled", name).into());
+        }
+        info!("Command {} disabled globally by {}", name, ctx.author().id);
+        let registered = reregister(ctx).await;
+        ctx.send(
+            CreateReply::default()
+                .content(format!("Disabled `{}` on every guild. {}", name, registered))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Enables a command disabled with `/global-commands disable`.
+    #[poise::command(slash_command, prefix_command, owners_only, hide_in_help)]
+    async fn enable(
+        ctx: Context<'_>,
+        #[description = "Name of the top level command"]
+        #[autocomplete = "command_autocomplete"]
+        command: String,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let name = command.trim().to_lowercase();
+        ctx.defer_ephemeral().await?;
+        if !set_command_disabled(&ctx.data().db_pool, &name, false).await? {
+            return Err(format!("`{}` is not disabled", name).into());
+        }
+        info!("Command {} enabled globally by {}", name, ctx.author().id);
+        let registered = reregister(ctx).await;
+        ctx.send(
+            CreateReply::default()
+                .content(format!("Enabled `{}` on every guild. {}", name, registered))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Lists the commands disabled on every guild.
+    #[poise::command(slash_command, prefix_command, owners_only, hide_in_help)]
+    async fn list(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let disabled = disabled_commands();
+        let content = if disabled.is_empty() {
+            "No commands are disabled".to_string()
+        } else {
+            let names: Vec<String> = disabled.iter().map(|x| format!("`{}`", x)).collect();
+            format!("Disabled commands: {}", names.join(", "))
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use poise::CreateReply;
+
+use crate::{
+    Context, Error,
+    infrastructure::{
+        branding::guild_branding,
+        jobs::list_jobs,
+        leader::{INSTANCE_ID, is_leader},
+    },
+    poise_instrument, record_ctx_fields,
+};
+
+/// Most jobs listed, since embed descriptions are limited in length.
+const MAX_LISTED_JOBS: usize = 20;
+
+/// Set of commands to observe the background jobs of the bot.
+#[poise::command(
+    slash_command,
+    prefix_command,
+    owners_only,
+    hide_in_help,
+    subcommands("list")
+)]
+pub async fn jobs(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Lists the background jobs, when they run next and how their last run went.
+    #[poise::command(slash_command, prefix_command, owners_only, hide_in_help)]
+    async fn list(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let jobs = list_jobs(&ctx.data().db_pool).await?;
+        let description = if jobs.is_empty() {
+            "No jobs are scheduled.".to_string()
+        } else {
+            let mut lines: Vec<String> = jobs
+                .iter()
+                .take(MAX_LISTED_JOBS)
+                .map(|job| {
+                    let schedule = match job.schedule.as_str() {
+                        "" => "once".to_string(),
+                        x => format!("`{}`", x),
+                    };
+                    let last_run = match job.last_run_at {
+                        0 => "never run".to_string(),
+                        x => format!("last run <t:{}:R>", x),
+                    };
+                    let mut line = format!(
+                        "**{}** ({}, {}) — next run <t:{}:R>, {}",
+                        job.name, job.kind, schedule, job.next_run_at, last_run
+                    );
+                    if !job.last_error.is_empty() {
+                        let error: String = job.last_error.chars().take(200).collect();
+                        line.push_str(&match job.attempts {
+                            0 => format!("\n-# Last run failed: {}", error),
+                            x => format!("\n-# Retrying after {} failed attempts: {}", x, error),
+                        });
+                    }
+                    line
+                })
+                .collect();
+            if jobs.len() > MAX_LISTED_JOBS {
+                lines.push(format!("...and {} more", jobs.len() - MAX_LISTED_JOBS));
+            }
+            lines.join("\n")
+        };
+        let role = if is_leader() {
+            "the leader, running the jobs"
+        } else {
+            "on standby, another instance runs the jobs"
+        };
+        let embed = guild_branding(ctx)
+            .await
+            .embed()
+            .title("Background jobs")
+            .description(format!(
+                "{}\n\n-# This instance (`{}`) is {}",
+                description, *INSTANCE_ID, role
+            ));
+        ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+use poise::CreateReply;
+use tracing::info;
+
+use crate::{
+    Context, Error,
+    infrastructure::{
+        log_filter::{current_log_filter, reset_log_filter, set_log_filter},
+        log_privacy::{privacy_mode_enabled, save_privacy_mode},
+    },
+    poise_instrument, record_ctx_fields,
+};
+
+/// Set of commands to change what the bot logs without restarting it.
+#[poise::command(
+    slash_command,
+    prefix_command,
+    owners_only,
+    hide_in_help,
+    subcommands("level", "reset", "privacy")
+)]
+pub async fn log(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Shows the log filter, or replaces it, e.g. with `warn,imposterbot::events=trace`.
+    #[poise::command(slash_command, prefix_command, owners_only, hide_in_help)]
+    async fn level(
+        ctx: Context<'_>,
+        #[description = "Filter in RUST_LOG syntax (leave empty to show the current filter)"]
+        #[rest]
+        filter: Option<String>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let content = match filter.as_deref().map(str::trim) {
+            Some(filter) if !filter.is_empty() => {
+                set_log_filter(filter)?;
+                info!("Log filter changed to '{}' by {}", filter, ctx.author().id);
+                format!("Log filter set to `{}`", filter)
+            }
+            _ => format!("Log filter is `{}`", current_log_filter()?),
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Restores the log filter the bot was started with.
+    #[poise::command(slash_command, prefix_command, owners_only, hide_in_help)]
+    async fn reset(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let filter = reset_log_filter()?;
+        info!("Log filter reset to '{}' by {}", filter, ctx.author().id);
+        ctx.send(
+            CreateReply::default()
+                .content(format!("Log filter reset to `{}`", filter))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Shows or changes whether user names are hashed and message content is left out of the logs.
+    #[poise::command(slash_command, prefix_command, owners_only, hide_in_help)]
+    async fn privacy(
+        ctx: Context<'_>,
+        #[description = "Redact logs? (leave empty to show the current mode)"] enabled: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        if let Some(enabled) = enabled {
+            save_privacy_mode(&ctx.data().db_pool, enabled).await?;
+            info!("Log privacy mode set to {} by {}", enabled, ctx.author().id);
+        }
+        let content = if privacy_mode_enabled() {
+            "Log privacy mode is **enabled**: user names are hashed and message content is left out"
+        } else {
+            "Log privacy mode is **disabled**"
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
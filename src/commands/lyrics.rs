@@ -0,0 +1,148 @@
+use migration::OnConflict;
+use poise::serenity_prelude::CreateEmbedAuthor;
+use sea_orm::{ActiveValue::Set, DatabaseConnection, EntityTrait};
+use serde::Deserialize;
+use tracing::{debug, trace, warn};
+
+use crate::{
+    Context, Error,
+    commands::voice::{HttpKey, get_now_playing},
+    entities::lyrics_cache,
+    infrastructure::{branding::guild_branding, ids::require_guild_id, util::Paginator},
+    poise_instrument, record_ctx_fields,
+};
+
+const LRCLIB_SEARCH_URL: &str = "https://lrclib.net/api/search";
+
+/// Maximum length of a single page of lyrics. Embed descriptions are limited to 4096 characters.
+const PAGE_LENGTH: usize = 2000;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LrclibTrack {
+    plain_lyrics: Option<String>,
+}
+
+/// Queries lrclib for the plain-text lyrics of a track.
+async fn fetch_lyrics(
+    http: &reqwest::Client,
+    artist: Option<&str>,
+    title: &str,
+) -> Result<Option<String>, Error> {
+    let query: Vec<(&str, &str)> = match artist {
+        Some(artist) => vec![("track_name", title), ("artist_name", artist)],
+        None => vec![("q", title)],
+    };
+    let response = http.get(LRCLIB_SEARCH_URL).query(&query).send().await?;
+    if !response.status().is_success() {
+        warn!("Lyrics provider returned status {}", response.status());
+        return Err("Lyrics provider is unavailable".into());
+    }
+
+    let tracks: Vec<LrclibTrack> = response.json().await?;
+    trace!("Lyrics provider returned {} results", tracks.len());
+    Ok(tracks
+        .into_iter()
+        .filter_map(|track| track.plain_lyrics)
+        .find(|lyrics| !lyrics.trim().is_empty()))
+}
+
+/// Gets lyrics from the cache, falling back to the lyrics provider and caching the result.
+async fn get_lyrics(
+    db: &DatabaseConnection,
+    http: &reqwest::Client,
+    artist: Option<&str>,
+    title: &str,
+) -> Result<Option<String>, Error> {
+    let key = (
+        artist.unwrap_or_default().to_lowercase(),
+        title.to_lowercase(),
+    );
+    if let Some(cached) = lyrics_cache::Entity::find_by_id(key.clone())
+        .one(db)
+        .await?
+    {
+        debug!("Found cached lyrics for '{}'", title);
+        return Ok(Some(cached.lyrics));
+    }
+
+    let lyrics = fetch_lyrics(http, artist, title).await?;
+    if let Some(lyrics) = &lyrics {
+        lyrics_cache::Entity::insert(lyrics_cache::ActiveModel {
+            artist: Set(key.0),
+            title: Set(key.1),
+            lyrics: Set(lyrics.clone()),
+        })
+        // Concurrent lookups of the same track both miss the cache, so the later one replaces the lyrics
+        .on_conflict(
+            OnConflict::columns([lyrics_cache::Column::Artist, lyrics_cache::Column::Title])
+                .update_columns([lyrics_cache::Column::Lyrics])
+                .to_owned(),
+        )
+        .exec(db)
+        .await?;
+    }
+    Ok(lyrics)
+}
+
+/// Splits lyrics into pages on line boundaries.
+fn paginate_lyrics(lyrics: &str) -> Vec<String> {
+    let mut pages = vec![];
+    let mut page = String::new();
+    for line in lyrics.lines() {
+        if !page.is_empty() && page.len() + line.len() + 1 > PAGE_LENGTH {
+            pages.push(std::mem::take(&mut page));
+        }
+        page.push_str(line);
+        page.push('\n');
+    }
+    if !page.is_empty() {
+        pages.push(page);
+    }
+    pages
+}
+
+poise_instrument! {
+    /// Shows the lyrics of the track currently playing in voice
+    #[poise::command(slash_command, guild_only)]
+    pub async fn lyrics(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        ctx.defer().await?;
+        let guild_id = require_guild_id(ctx)?;
+
+        let metadata = get_now_playing(ctx, guild_id)
+            .await
+            .ok_or::<Error>("Nothing is playing right now.".into())?;
+        let artist = metadata.artist.or(metadata.channel);
+        let title = metadata
+            .track
+            .or(metadata.title)
+            .ok_or::<Error>("The current track has no title to search for.".into())?;
+
+        let http_client = {
+            let data = ctx.serenity_context().data.read().await;
+            data.get::<HttpKey>()
+                .cloned()
+                .expect("Guaranteed to exist in the typemap.")
+        };
+
+        let lyrics = get_lyrics(&ctx.data().db_pool, &http_client, artist.as_deref(), &title)
+            .await?
+            .ok_or::<Error>(format!("No lyrics found for '{}'.", title).into())?;
+
+        let branding = guild_branding(ctx).await;
+        let pages = paginate_lyrics(&lyrics)
+            .into_iter()
+            .map(|page| {
+                let embed = branding.embed()
+                    .title(&title)
+                    .description(page);
+                match &artist {
+                    Some(artist) => embed.author(CreateEmbedAuthor::new(artist)),
+                    None => embed,
+                }
+            })
+            .collect();
+        Paginator::new(pages).send(ctx).await
+    }
+}
@@ -0,0 +1,183 @@
+use poise::{
+    CreateReply,
+    serenity_prelude::{ChannelType, EditChannel, GuildChannel, Mentionable},
+};
+
+use crate::{
+    Context, Error,
+    events::auto_slowmode::{
+        AutoSlowmodeSettings, MAX_SLOWMODE_SECONDS, guild_auto_slowmodes, load_auto_slowmode,
+        remove_auto_slowmode, set_auto_slowmode,
+    },
+    infrastructure::{branding::guild_branding, ids::require_guild_id},
+    poise_instrument, record_ctx_fields,
+};
+
+const DEFAULT_MESSAGES_PER_MINUTE: u32 = 30;
+
+const DEFAULT_MIN_SECONDS: u16 = 5;
+
+const DEFAULT_MAX_SECONDS: u16 = 60;
+
+/// Set of commands to adapt the slowmode of channels to their activity.
+#[poise::command(
+    slash_command,
+    required_permissions = "MANAGE_CHANNELS",
+    default_member_permissions = "MANAGE_CHANNELS",
+    guild_only,
+    category = "Management",
+    rename = "auto-slowmode",
+    subcommands("enable", "disable", "list")
+)]
+pub async fn auto_slowmode(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Raises the slowmode of a channel when it gets busy, and relaxes it once it calms down.
+    #[poise::command(
+        slash_command,
+        required_permissions = "MANAGE_CHANNELS",
+        default_member_permissions = "MANAGE_CHANNELS",
+        required_bot_permissions = "MANAGE_CHANNELS",
+        guild_only,
+        category = "Management"
+    )]
+    async fn enable(
+        ctx: Context<'_>,
+        #[description = "Channel to adapt the slowmode of"]
+        #[channel_types("Text", "News")]
+        channel: GuildChannel,
+        #[description = "Messages per minute that raise the slowmode (default: 30, or the current setting)"]
+        #[min = 2]
+        #[max = 1000]
+        threshold: Option<u32>,
+        #[description = "First slowmode step in seconds (default: 5, or the current setting)"]
+        #[min = 1]
+        #[max = 21600]
+        min: Option<u16>,
+        #[description = "Highest slowmode in seconds (default: 60, or the current setting)"]
+        #[min = 1]
+        #[max = 21600]
+        max: Option<u16>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        if channel.guild_id != guild_id
+            || !matches!(channel.kind, ChannelType::Text | ChannelType::News)
+        {
+            return Err("Adaptive slowmode only works on text or announcement channels of this server".into());
+        }
+
+        let db = &ctx.data().db_pool;
+        let current = load_auto_slowmode(db, channel.id).await?;
+        let settings = AutoSlowmodeSettings {
+            messages_per_minute: threshold
+                .or(current.map(|x| x.messages_per_minute))
+                .unwrap_or(DEFAULT_MESSAGES_PER_MINUTE),
+            min_seconds: min
+                .or(current.map(|x| x.min_seconds))
+                .unwrap_or(DEFAULT_MIN_SECONDS)
+                .clamp(1, MAX_SLOWMODE_SECONDS),
+            max_seconds: max
+                .or(current.map(|x| x.max_seconds))
+                .unwrap_or(DEFAULT_MAX_SECONDS)
+                .min(MAX_SLOWMODE_SECONDS),
+        };
+        if settings.max_seconds < settings.min_seconds {
+            return Err("The highest slowmode can't be lower than the first slowmode step".into());
+        }
+        set_auto_slowmode(db, &ctx.data().auto_slowmode, guild_id, channel.id, settings).await?;
+
+        ctx.send(
+            CreateReply::default()
+                .content(format!(
+                    "Adaptive slowmode enabled in {}: from {} messages per minute the slowmode starts at {}s and \
+                     doubles up to {}s, and it is relaxed once the channel gets less than half as many.",
+                    channel.mention(),
+                    settings.messages_per_minute,
+                    settings.min_seconds,
+                    settings.max_seconds
+                ))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Stops adapting the slowmode of a channel, turning off the slowmode the bot set.
+    #[poise::command(
+        slash_command,
+        required_permissions = "MANAGE_CHANNELS",
+        default_member_permissions = "MANAGE_CHANNELS",
+        guild_only,
+        category = "Management"
+    )]
+    async fn disable(
+        ctx: Context<'_>,
+        #[description = "Channel to stop adapting the slowmode of"]
+        #[channel_types("Text", "News")]
+        channel: GuildChannel,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        if channel.guild_id != guild_id {
+            return Err("That channel is not part of this server".into());
+        }
+        let (removed, slowmode) =
+            remove_auto_slowmode(&ctx.data().db_pool, &ctx.data().auto_slowmode, channel.id).await?;
+        if slowmode.is_some() {
+            channel
+                .id
+                .edit(ctx, EditChannel::new().rate_limit_per_user(0))
+                .await?;
+        }
+        let content = if removed {
+            format!("Adaptive slowmode disabled in {}", channel.mention())
+        } else {
+            format!("Adaptive slowmode is not enabled in {}", channel.mention())
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Lists the channels with adaptive slowmode and their settings.
+    #[poise::command(
+        slash_command,
+        required_permissions = "MANAGE_CHANNELS",
+        default_member_permissions = "MANAGE_CHANNELS",
+        guild_only,
+        category = "Management"
+    )]
+    async fn list(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let channels = guild_auto_slowmodes(&ctx.data().db_pool, guild_id).await?;
+        let description = if channels.is_empty() {
+            "No channels have adaptive slowmode. Enable it with `/auto-slowmode enable`.".to_string()
+        } else {
+            channels
+                .iter()
+                .map(|(channel, x)| {
+                    format!(
+                        "{} — from {} messages/min, {}s to {}s",
+                        channel.mention(),
+                        x.messages_per_minute,
+                        x.min_seconds,
+                        x.max_seconds
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join("\n")
+        };
+        let embed = guild_branding(ctx)
+            .await
+            .embed()
+            .title("Adaptive slowmode")
+            .description(description);
+        ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
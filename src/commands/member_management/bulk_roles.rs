@@ -0,0 +1,305 @@
+/*!
+
+Bulk role assignment for all guild members matching a filter.
+
+Members are processed in small chunks with a short pause between chunks, so that role updates for large guilds stay
+well within discord's rate limits while the ephemeral reply is updated with progress. The reply can only be edited
+while the interaction token is valid, so the summary of runs that outlast it is posted in the channel instead.
+
+Only roles below the highest role of both the invoker and the bot can be assigned, so the bot can't be used to hand out
+roles the invoker couldn't assign themselves.
+
+*/
+
+use std::time::Duration;
+
+use poise::{
+    CreateReply,
+    serenity_prelude::{
+        CreateAllowedMentions, CreateMessage, GuildId, Member, Mentionable, RoleId, Timestamp,
+        futures::StreamExt,
+    },
+};
+use tracing::{info, warn};
+
+use crate::{
+    Context, Error, infrastructure::ids::require_guild_id, log_user, poise_instrument,
+    record_ctx_fields,
+};
+
+/// Number of members updated between progress updates.
+pub(crate) const CHUNK_SIZE: usize = 10;
+
+/// Pause between chunks to avoid saturating the role update rate limit bucket.
+pub(crate) const CHUNK_DELAY: Duration = Duration::from_secs(1);
+
+/// Interaction tokens expire after 15 minutes, after which the reply can no longer be edited.
+const INTERACTION_TOKEN_LIFETIME: Duration = Duration::from_secs(14 * 60);
+
+#[derive(Debug, Clone, Copy)]
+enum BulkRoleAction {
+    Add,
+    Remove,
+}
+
+#[derive(Debug, Default)]
+struct MemberFilter {
+    has_role: Option<RoleId>,
+    joined_before: Option<Timestamp>,
+    include_bots: bool,
+}
+
+impl MemberFilter {
+    fn matches(&self, member: &Member) -> bool {
+        if member.user.bot && !self.include_bots {
+            return false;
+        }
+
+        if let Some(role) = self.has_role
+            && !member.roles.contains(&role)
+        {
+            return false;
+        }
+
+        if let Some(before) = self.joined_before {
+            match member.joined_at {
+                Some(joined_at) if joined_at.unix_timestamp() < before.unix_timestamp() => {}
+                _ => return false,
+            }
+        }
+
+        true
+    }
+}
+
+/// Parses a date in the form `YYYY-MM-DD` as midnight UTC.
+fn parse_date(value: &str) -> Result<Timestamp, Error> {
+    Timestamp::parse(&format!("{}T00:00:00Z", value.trim()))
+        .map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD", value).into())
+}
+
+/// Checks that a role can be assigned in bulk: it isn't managed by an integration, and it is below the highest role of
+/// the invoker, unless they own the guild, and of the bot.
+async fn check_assignable(
+    ctx: Context<'_>,
+    guild_id: GuildId,
+    role_id: RoleId,
+) -> Result<(), Error> {
+    if role_id.get() == guild_id.get() {
+        return Err("The @everyone role can't be assigned".into());
+    }
+    let invoker = ctx
+        .author_member()
+        .await
+        .ok_or("Could not look up your roles")?
+        .into_owned();
+    // The cache guard isn't Send, so it must be dropped before awaiting
+    let bot_id = ctx.cache().current_user().id;
+    let bot = guild_id.member(ctx, bot_id).await?;
+
+    let guild = ctx
+        .guild()
+        .ok_or("This server is not cached yet, try again in a moment")?;
+    let Some(role) = guild.roles.get(&role_id) else {
+        return Err("That role no longer exists".into());
+    };
+    if role.managed {
+        return Err(format!(
+            "{} is managed by an integration and can't be assigned",
+            role_id.mention()
+        )
+        .into());
+    }
+    let highest_position = |member: &Member| {
+        guild
+            .member_highest_role(member)
+            .map(|x| x.position)
+            .unwrap_or_default()
+    };
+    if guild.owner_id != invoker.user.id && role.position >= highest_position(&invoker) {
+        return Err(format!("{} is not below your highest role", role_id.mention()).into());
+    }
+    if role.position >= highest_position(&bot) {
+        return Err(format!(
+            "{} is not below the bot's highest role, move the bot's role above it",
+            role_id.mention()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Set of commands for managing roles.
+#[poise::command(
+    slash_command,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    guild_only,
+    category = "Management",
+    subcommands("bulk")
+)]
+pub async fn role(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Applies or strips a role for every member matching a filter.
+#[poise::command(
+    slash_command,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    guild_only,
+    category = "Management",
+    subcommands("add", "remove")
+)]
+async fn bulk(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Adds a role to all members matching the filter.
+    #[poise::command(
+        slash_command,
+        required_permissions = "MANAGE_ROLES",
+        default_member_permissions = "MANAGE_ROLES",
+        required_bot_permissions = "MANAGE_ROLES",
+        guild_only,
+        category = "Management"
+    )]
+    async fn add(
+        ctx: Context<'_>,
+        #[description = "Role to add"] role: RoleId,
+        #[description = "Only members that have this role"] has_role: Option<RoleId>,
+        #[description = "Only members that joined before this date (YYYY-MM-DD)"] joined_before: Option<String>,
+        #[description = "Include bot accounts? (default: false)"] include_bots: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        bulk_role_impl(ctx, BulkRoleAction::Add, role, has_role, joined_before, include_bots).await
+    }
+
+    /// Removes a role from all members matching the filter.
+    #[poise::command(
+        slash_command,
+        required_permissions = "MANAGE_ROLES",
+        default_member_permissions = "MANAGE_ROLES",
+        required_bot_permissions = "MANAGE_ROLES",
+        guild_only,
+        category = "Management"
+    )]
+    async fn remove(
+        ctx: Context<'_>,
+        #[description = "Role to remove"] role: RoleId,
+        #[description = "Only members that have this role"] has_role: Option<RoleId>,
+        #[description = "Only members that joined before this date (YYYY-MM-DD)"] joined_before: Option<String>,
+        #[description = "Include bot accounts? (default: false)"] include_bots: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        bulk_role_impl(ctx, BulkRoleAction::Remove, role, has_role, joined_before, include_bots).await
+    }
+}
+
+async fn bulk_role_impl(
+    ctx: Context<'_>,
+    action: BulkRoleAction,
+    role: RoleId,
+    has_role: Option<RoleId>,
+    joined_before: Option<String>,
+    include_bots: Option<bool>,
+) -> Result<(), Error> {
+    let guild_id = require_guild_id(ctx)?;
+    check_assignable(ctx, guild_id, role).await?;
+    let filter = MemberFilter {
+        has_role,
+        joined_before: joined_before.as_deref().map(parse_date).transpose()?,
+        include_bots: include_bots.unwrap_or(false),
+    };
+
+    let started = ctx.data().clock.instant();
+    let can_edit =
+        || ctx.data().clock.instant().duration_since(started) < INTERACTION_TOKEN_LIFETIME;
+    let handle = ctx
+        .send(
+            CreateReply::default()
+                .content("Collecting members...")
+                .ephemeral(true),
+        )
+        .await?;
+
+    let mut targets: Vec<Member> = vec![];
+    let mut members = guild_id.members_iter(ctx).boxed();
+    while let Some(member) = members.next().await {
+        let member = member?;
+        let has_target_role = member.roles.contains(&role);
+        let needs_update = match action {
+            BulkRoleAction::Add => !has_target_role,
+            BulkRoleAction::Remove => has_target_role,
+        };
+        if needs_update && filter.matches(&member) {
+            targets.push(member);
+        }
+    }
+
+    let total = targets.len();
+    info!(
+        "Bulk role {:?} for role {} matched {} members",
+        action, role, total
+    );
+
+    let mut processed = 0;
+    let mut failed = 0;
+    for chunk in targets.chunks(CHUNK_SIZE) {
+        for member in chunk {
+            let result = match action {
+                BulkRoleAction::Add => member.add_role(ctx, role).await,
+                BulkRoleAction::Remove => member.remove_role(ctx, role).await,
+            };
+            if let Err(e) = result {
+                warn!(
+                    "Failed to update role for member {}: {:?}",
+                    log_user!(&member.user.name),
+                    e
+                );
+                failed += 1;
+            }
+            processed += 1;
+        }
+
+        if can_edit()
+            && let Err(e) = handle
+                .edit(
+                    ctx,
+                    CreateReply::default()
+                        .content(format!("Processed {}/{} members...", processed, total)),
+                )
+                .await
+        {
+            warn!("Failed to report bulk role progress: {:?}", e);
+        }
+        if processed < total {
+            tokio::time::sleep(CHUNK_DELAY).await;
+        }
+    }
+
+    let verb = match action {
+        BulkRoleAction::Add => "Added",
+        BulkRoleAction::Remove => "Removed",
+    };
+    let mut summary = format!("{} <@&{}> for {} members.", verb, role, total - failed);
+    if failed > 0 {
+        summary.push_str(&format!(" Failed to update {} members.", failed));
+    }
+    if can_edit() {
+        handle
+            .edit(ctx, CreateReply::default().content(summary))
+            .await?;
+    } else {
+        ctx.channel_id()
+            .send_message(
+                ctx,
+                CreateMessage::new()
+                    .content(format!("{} {}", ctx.author().mention(), summary))
+                    .allowed_mentions(CreateAllowedMentions::new().users([ctx.author().id])),
+            )
+            .await?;
+    }
+    Ok(())
+}
@@ -1,13 +1,19 @@
 use migration::OnConflict;
 use poise::{CreateReply, serenity_prelude::GuildChannel};
-use sea_orm::{ActiveValue::Set, EntityTrait};
+use sea_orm::{ActiveValue::Set, ColumnTrait, EntityTrait, QueryFilter};
 use tracing::trace;
 
 use crate::{
     Context, Error,
-    entities::member_notification_channel,
-    infrastructure::ids::{id_to_string, require_guild_id},
+    entities::{asset_storage_channel, mod_log_channel, user_content_asset},
+    events::guild_member::notification_files,
+    infrastructure::{
+        asset_storage::{get_asset_urls, upload_assets},
+        channel_validation::check_notification_channel,
+        ids::{id_to_string, require_guild_id},
+    },
     poise_instrument, record_ctx_fields,
+    services::notifications::NotificationRepo,
 };
 
 poise_instrument! {
@@ -23,6 +29,7 @@ poise_instrument! {
     pub async fn configure_welcome_channel(
         ctx: Context<'_>,
         #[description = "Channel to send member joined notifications. If not provided, the bot will not send notifications."]
+        #[channel_types("Text", "News")]
         channel: Option<GuildChannel>,
     ) -> Result<(), Error> {
         record_ctx_fields!(ctx);
@@ -30,21 +37,10 @@ poise_instrument! {
         let guild_id = require_guild_id(ctx)?;
 
         if let Some(channel) = channel {
-            member_notification_channel::Entity::insert(member_notification_channel::ActiveModel {
-                guild_id: Set(id_to_string(guild_id.clone())),
-                join: Set(true),
-                channel_id: Set(id_to_string(channel.id.clone())),
-            })
-            .on_conflict(
-                OnConflict::columns([
-                    member_notification_channel::Column::GuildId,
-                    member_notification_channel::Column::Join,
-                ])
-                .update_columns([member_notification_channel::Column::ChannelId])
-                .to_owned(),
-            )
-            .exec(&ctx.data().db_pool)
-            .await?;
+            check_notification_channel(ctx.cache(), &channel)?;
+            NotificationRepo::new(&ctx.data().db_pool)
+                .set_channel(guild_id, true, channel.id)
+                .await?;
             ctx.send(
                 CreateReply::default()
                     .content("Successfully set welcome channel")
@@ -52,8 +48,8 @@ poise_instrument! {
             )
             .await?;
         } else {
-            member_notification_channel::Entity::delete_by_id((id_to_string(guild_id), true))
-                .exec(&ctx.data().db_pool)
+            NotificationRepo::new(&ctx.data().db_pool)
+                .remove_channel(guild_id, true)
                 .await?;
 
             ctx.send(
@@ -81,6 +77,7 @@ poise_instrument! {
     pub async fn configure_leave_channel(
         ctx: Context<'_>,
         #[description = "Channel to send member left notifications. If not provided, the bot will not send notifications."]
+        #[channel_types("Text", "News")]
         channel: Option<GuildChannel>,
     ) -> Result<(), Error> {
         record_ctx_fields!(ctx);
@@ -88,35 +85,160 @@ poise_instrument! {
         let guild_id = require_guild_id(ctx)?;
 
         if let Some(channel) = channel {
-            member_notification_channel::Entity::insert(member_notification_channel::ActiveModel {
-                guild_id: Set(id_to_string(guild_id.clone())),
-                join: Set(false),
-                channel_id: Set(id_to_string(channel.id.clone())),
+            check_notification_channel(ctx.cache(), &channel)?;
+            NotificationRepo::new(&ctx.data().db_pool)
+                .set_channel(guild_id, false, channel.id)
+                .await?;
+            ctx.send(
+                CreateReply::default()
+                    .content("Successfully set leave channel")
+                    .ephemeral(true),
+            )
+            .await?;
+        } else {
+            NotificationRepo::new(&ctx.data().db_pool)
+                .remove_channel(guild_id, false)
+                .await?;
+
+            ctx.send(
+                CreateReply::default()
+                    .content("Successfully removed leave channel")
+                    .ephemeral(true),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+}
+
+poise_instrument! {
+    /// Configures a private channel to host notification images, so they survive data directory loss.
+    #[poise::command(
+        slash_command,
+        prefix_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    pub async fn configure_asset_storage_channel(
+        ctx: Context<'_>,
+        #[description = "Private channel to upload images to. If not provided, images are sent from local files."]
+        channel: Option<GuildChannel>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+
+        if let Some(channel) = channel {
+            ctx.defer_ephemeral().await?;
+            asset_storage_channel::Entity::insert(asset_storage_channel::ActiveModel {
+                guild_id: Set(id_to_string(guild_id)),
+                channel_id: Set(id_to_string(channel.id)),
+            })
+            .on_conflict(
+                OnConflict::column(asset_storage_channel::Column::GuildId)
+                    .update_column(asset_storage_channel::Column::ChannelId)
+                    .to_owned(),
+            )
+            .exec(&ctx.data().db_pool)
+            .await?;
+
+            // Host any images configured before the channel was set.
+            let hosted = get_asset_urls(
+                &ctx.data().db_pool,
+                guild_id,
+                ctx.data().clock.unix_timestamp(),
+            )
+            .await?;
+            let mut files: Vec<String> = NotificationRepo::new(&ctx.data().db_pool)
+                .guild_variants(guild_id)
+                .await?
+                .iter()
+                .flat_map(notification_files)
+                .filter(|file| !hosted.contains_key(file))
+                .collect();
+            files.sort();
+            files.dedup();
+            let uploaded = upload_assets(ctx.http(), &ctx.data().db_pool, guild_id, &files).await;
+
+            ctx.send(
+                CreateReply::default()
+                    .content(format!(
+                        "Successfully set asset storage channel. Uploaded {} of {} images.",
+                        uploaded,
+                        files.len()
+                    ))
+                    .ephemeral(true),
+            )
+            .await?;
+        } else {
+            asset_storage_channel::Entity::delete_by_id(id_to_string(guild_id))
+                .exec(&ctx.data().db_pool)
+                .await?;
+            user_content_asset::Entity::delete_many()
+                .filter(user_content_asset::Column::GuildId.eq(id_to_string(guild_id)))
+                .exec(&ctx.data().db_pool)
+                .await?;
+
+            ctx.send(
+                CreateReply::default()
+                    .content("Successfully removed asset storage channel. Images are sent from local files again.")
+                    .ephemeral(true),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+}
+
+poise_instrument! {
+    /// Configures a channel for the bot to log moderation actions, such as expired temporary roles, to.
+    #[poise::command(
+        slash_command,
+        prefix_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    pub async fn configure_mod_log_channel(
+        ctx: Context<'_>,
+        #[description = "Channel to log moderation actions to. If not provided, moderation actions are not logged."]
+        #[channel_types("Text", "News")]
+        channel: Option<GuildChannel>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+
+        if let Some(channel) = channel {
+            check_notification_channel(ctx.cache(), &channel)?;
+            mod_log_channel::Entity::insert(mod_log_channel::ActiveModel {
+                guild_id: Set(id_to_string(guild_id)),
+                channel_id: Set(id_to_string(channel.id)),
             })
             .on_conflict(
-                OnConflict::columns([
-                    member_notification_channel::Column::GuildId,
-                    member_notification_channel::Column::Join,
-                ])
-                .update_columns([member_notification_channel::Column::ChannelId])
-                .to_owned(),
+                OnConflict::column(mod_log_channel::Column::GuildId)
+                    .update_column(mod_log_channel::Column::ChannelId)
+                    .to_owned(),
             )
             .exec(&ctx.data().db_pool)
             .await?;
             ctx.send(
                 CreateReply::default()
-                    .content("Successfully set leave channel")
+                    .content("Successfully set mod-log channel")
                     .ephemeral(true),
             )
             .await?;
         } else {
-            member_notification_channel::Entity::delete_by_id((id_to_string(guild_id), false))
+            mod_log_channel::Entity::delete_by_id(id_to_string(guild_id))
                 .exec(&ctx.data().db_pool)
                 .await?;
 
             ctx.send(
                 CreateReply::default()
-                    .content("Successfully removed leave channel")
+                    .content("Successfully removed mod-log channel")
                     .ephemeral(true),
             )
             .await?;
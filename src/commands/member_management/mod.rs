@@ -1,4 +1,12 @@
+pub mod auto_slowmode;
+pub mod bulk_roles;
 pub mod channels;
+pub mod nickname_filter;
+pub mod notification_templates;
 pub mod notifications;
 pub mod notifications_implementation;
+pub mod raid_protection;
 pub mod roles;
+pub mod screening;
+pub mod temp_roles;
+pub mod voice_announcements;
@@ -0,0 +1,155 @@
+use poise::{CreateReply, serenity_prelude::GuildChannel};
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, EntityTrait, IntoActiveModel};
+
+use crate::{
+    Context, Error,
+    entities::nickname_filter,
+    events::nickname_filter::{disallowed_words, get_nickname_filter_config},
+    infrastructure::{
+        branding::guild_branding,
+        colors,
+        ids::{id_to_string, require_guild_id},
+    },
+    poise_instrument, record_ctx_fields,
+};
+
+/// Set of commands to configure automatic nickname normalization for this guild.
+#[poise::command(
+    slash_command,
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR",
+    guild_only,
+    category = "Management",
+    rename = "nickname-filter",
+    subcommands("configure", "status")
+)]
+pub async fn nickname_filter(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Configures how member names are normalized when they join or change their nickname.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn configure(
+        ctx: Context<'_>,
+        #[description = "Enable the nickname filter"] enabled: Option<bool>,
+        #[description = "Strip leading punctuation used to sort to the top of the member list"] dehoist: Option<bool>,
+        #[description = "Comma separated words that are not allowed in names ('-' clears the list)"]
+        disallowed_words: Option<String>,
+        #[description = "Name given to members with a disallowed name. Supports {username} and {id}"]
+        replacement: Option<String>,
+        #[description = "Channel to log renames to"] log_channel: Option<GuildChannel>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+
+        let existing = nickname_filter::Entity::find_by_id(id_to_string(guild_id))
+            .one(&ctx.data().db_pool)
+            .await?;
+        let (mut model, update) = match existing {
+            Some(row) => (row.into_active_model(), true),
+            None => (
+                nickname_filter::ActiveModel {
+                    guild_id: Set(id_to_string(guild_id)),
+                    ..Default::default()
+                },
+                false,
+            ),
+        };
+
+        if let Some(x) = enabled {
+            model.enabled = Set(x);
+        }
+        if let Some(x) = dehoist {
+            model.dehoist = Set(x);
+        }
+        if let Some(x) = disallowed_words {
+            model.disallowed_words = Set(if x.trim() == "-" { String::new() } else { x });
+        }
+        if let Some(x) = replacement {
+            model.replacement = Set(x);
+        }
+        if let Some(x) = log_channel {
+            model.log_channel_id = Set(id_to_string(x.id));
+        }
+
+        if update {
+            model.update(&ctx.data().db_pool).await?;
+        } else {
+            nickname_filter::Entity::insert(model)
+                .exec(&ctx.data().db_pool)
+                .await?;
+        }
+
+        ctx.send(
+            CreateReply::default()
+                .content("Successfully configured nickname filter")
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Shows the nickname filter configuration.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn status(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+
+        let config = match get_nickname_filter_config(&ctx.data().db_pool, &guild_id).await {
+            Some(x) => x,
+            None => {
+                ctx.send(
+                    CreateReply::default()
+                        .content("The nickname filter is not configured on this guild")
+                        .ephemeral(true),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let words = disallowed_words(&config);
+        let words = if words.is_empty() {
+            "None".to_string()
+        } else {
+            words
+                .iter()
+                .map(|x| format!("`{}`", x))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let log_channel = if config.log_channel_id.is_empty() {
+            "None".to_string()
+        } else {
+            format!("<#{}>", config.log_channel_id)
+        };
+
+        let branding = guild_branding(ctx).await;
+        let embed = branding
+            .embed()
+            .title("Nickname Filter")
+            .color(if config.enabled { colors::green() } else { branding.accent })
+            .field("Enabled", config.enabled.to_string(), true)
+            .field("Dehoist", config.dehoist.to_string(), true)
+            .field("Log channel", log_channel, true)
+            .field("Replacement", format!("`{}`", config.replacement), false)
+            .field("Disallowed words", words, false);
+
+        ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+/*!
+
+Built-in starter templates for member join and leave notifications, listed by `/notify-member templates`.
+
+Applying a template replaces the primary variant, which can then be tweaked with the other `/notify-member` commands.
+Templates may use the same placeholders as user configured notifications.
+
+*/
+
+pub struct NotificationTemplate {
+    pub name: &'static str,
+    /// Short explanation shown next to the preview.
+    pub summary: &'static str,
+    /// True for join notifications, false for leave notifications.
+    pub join: bool,
+    pub content: Option<&'static str>,
+    pub title: Option<&'static str>,
+    pub description: Option<&'static str>,
+    pub thumbnail_url: Option<&'static str>,
+    pub author: Option<&'static str>,
+    pub footer: Option<&'static str>,
+}
+
+pub const NOTIFICATION_TEMPLATES: &[NotificationTemplate] = &[
+    NotificationTemplate {
+        name: "Simple welcome",
+        summary: "A short greeting that mentions the new member.",
+        join: true,
+        content: Some("Welcome, {mention}!"),
+        title: None,
+        description: Some("**{name}** has joined the server."),
+        thumbnail_url: Some("{user_avatar}"),
+        author: None,
+        footer: Some("Member #{member_count}"),
+    },
+    NotificationTemplate {
+        name: "Cozy welcome",
+        summary: "A friendly welcome with pointers for getting started.",
+        join: true,
+        content: Some("Hey {mention}, make yourself at home!"),
+        title: Some("Welcome aboard, {name}!"),
+        description: Some(
+            "We're glad you're here.\n\n• Say hi to everyone\n• Read the rules before posting\n• Grab your roles and have fun",
+        ),
+        thumbnail_url: Some("{user_avatar}"),
+        author: Some("A new crewmate has arrived"),
+        footer: Some("{online_member_count} of {member_count} members are online"),
+    },
+    NotificationTemplate {
+        name: "Emergency meeting",
+        summary: "An Among Us themed announcement.",
+        join: true,
+        content: Some("{mention}"),
+        title: Some("EMERGENCY MEETING"),
+        description: Some("**{name}** just landed on the ship.\nThey seem trustworthy... for now."),
+        thumbnail_url: Some("{user_avatar}"),
+        author: None,
+        footer: Some("Crewmates aboard: {member_count}"),
+    },
+    NotificationTemplate {
+        name: "Simple goodbye",
+        summary: "A short note that a member left.",
+        join: false,
+        content: None,
+        title: None,
+        description: Some("**{name}** has left the server."),
+        thumbnail_url: Some("{user_avatar}"),
+        author: None,
+        footer: Some("{member_count} members remain"),
+    },
+    NotificationTemplate {
+        name: "Fond farewell",
+        summary: "A warmer goodbye.",
+        join: false,
+        content: None,
+        title: Some("Farewell, {name}"),
+        description: Some("Thanks for spending time with us. The door is always open!"),
+        thumbnail_url: Some("{user_avatar}"),
+        author: None,
+        footer: None,
+    },
+    NotificationTemplate {
+        name: "Ejected",
+        summary: "An Among Us themed goodbye.",
+        join: false,
+        content: None,
+        title: None,
+        description: Some("**{name}** was ejected.\n\n. 　　。　　　　•　 　ﾟ　　。"),
+        thumbnail_url: Some("{user_avatar}"),
+        author: None,
+        footer: Some("{member_count} crewmates remain"),
+    },
+];
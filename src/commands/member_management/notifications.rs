@@ -8,23 +8,30 @@ Declarations ONLY, no real implementations.
 
 use poise::{
     CreateReply,
-    serenity_prelude::{self as serenity, CreateEmbed},
+    serenity_prelude::{self as serenity},
 };
 
 use crate::{
-    Context, Error,
+    ApplicationContext, Context, Error,
     commands::member_management::notifications_implementation::{
-        MemberEventConfigurer, NotificationType,
+        BotMemberChoice, MemberEventConfigurer, NotificationType, TestTarget,
+        configure_bot_members, configure_greeting_delay, configure_rejoin, configure_staff_mode,
+        dry_run_member_notification, member_notification_doctor, show_member_notifications,
+        show_notification_templates, test_send_member_notification,
     },
     events::guild_member::{guild_member_add, guild_member_remove},
-    infrastructure::{colors, ids::require_guild_id},
+    infrastructure::{branding::guild_branding, ids::require_guild_id},
     poise_instrument, record_ctx_fields,
 };
 
 static HELP_DESCRIPTION: &'static str = r#"
 This command configures the join and leave messages for this guild.
 
-This command can be used to make incremental updates to a notification format, or to fully replace the format with the specified format (`/notify-member join full` or `/notify-member leave full` commands)."#;
+This command can be used to make incremental updates to a notification format, or to fully replace the format with the specified format (`/notify-member join full` or `/notify-member leave full` commands).
+
+Additional variants can be added with `add-variant`, in which case one variant is picked at random for each notification. All other commands modify the primary variant (`0`).
+
+Members who were on the guild before get the return notification (`/notify-member return ...`) when they rejoin, or the join notification if no return notification is configured."#;
 
 static HELP_IMAGES: &'static str = r#"
 There are 3 places where an image can appear in the message:
@@ -41,12 +48,13 @@ static HELP_PLACEHOLDERS: &'static str = r#"
 When sending the message, Imposterbot will replace the following items with their values:
 
 - `{name}` -> username of the user
-- `{mention}` -> @mention's the user: Available only for `/notify-member join` commands.
+- `{mention}` -> @mention's the user: Available only for `/notify-member join` and `/notify-member return` commands.
 - `{user_avatar}` -> url of user's avatar: If placed in a _url field (`thumbnail_url`, `author_icon_url`, or `footer_icon_url`), it will be rendered as an image.
 - `{member_count}` -> current member count of the guild
 - `{online_member_count}` -> current number of online members in the guild
+- `{previous_join_date}` -> date the user previously joined the guild: Available only for `/notify-member return` commands.
 
-Note: discord does not allow entering line breaks in command parameters, but you can get around this with `\n`.
+Note: discord does not allow entering line breaks in command parameters, but you can get around this with `\n`, or use `/notify-member join edit` to write multi-line text in an editor.
 "#;
 
 static HELP_EXAMPLES: &'static str = r#"
@@ -67,6 +75,11 @@ Update the plain-text content of the join notification:
 "#;
 
 static HELP_LIST: &'static str = r#"
+- `/notify-member show`
+- `/notify-member templates`
+- `/notify-member doctor`
+- `/notify-member bots`
+
 - `/notify-member join full`
 - `/notify-member join title`
 - `/notify-member join content`
@@ -77,6 +90,10 @@ static HELP_LIST: &'static str = r#"
 - `/notify-member join image`
 - `/notify-member join author-icon`
 - `/notify-member join footer-icon`
+- `/notify-member join add-variant`
+- `/notify-member join remove-variant`
+- `/notify-member join variants`
+- `/notify-member join edit`
 
 - `/notify-member leave full`
 - `/notify-member leave title`
@@ -88,6 +105,10 @@ static HELP_LIST: &'static str = r#"
 - `/notify-member leave image`
 - `/notify-member leave author-icon`
 - `/notify-member leave footer-icon`
+- `/notify-member leave add-variant`
+- `/notify-member leave remove-variant`
+- `/notify-member leave variants`
+- `/notify-member leave edit`
 "#;
 
 #[poise::command(
@@ -97,7 +118,19 @@ static HELP_LIST: &'static str = r#"
     guild_only,
     category = "Management",
     rename = "notify-member",
-    subcommands("CfgMemberJoin::group", "CfgMemberLeave::group", "help")
+    subcommands(
+        "CfgMemberJoin::group",
+        "CfgMemberLeave::group",
+        "CfgMemberReturn::group",
+        "show",
+        "templates",
+        "doctor",
+        "bots",
+        "staff_mode",
+        "rejoin",
+        "delay",
+        "help"
+    )
 )]
 pub async fn cfg_member_notification(_ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
@@ -116,8 +149,9 @@ poise_instrument! {
     ctx.send(
         CreateReply::default()
             .embed(
-                CreateEmbed::default()
-                    .color(colors::slate())
+                guild_branding(ctx)
+                    .await
+                    .embed()
                     .title("Help for /notify-member")
                     .description(HELP_DESCRIPTION)
                     .field("**Images**", HELP_IMAGES, false)
@@ -131,6 +165,125 @@ poise_instrument! {
     Ok(())
 }
 }
+poise_instrument! {
+    /// Previews the join and leave notifications together and flags misconfiguration
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn show(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        show_member_notifications(ctx).await
+    }
+
+    /// Browses built-in notification templates and applies one as a starting point
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn templates(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        show_notification_templates(ctx).await
+    }
+
+    /// Finds missing files and unreachable urls in the notifications and offers to clear them
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn doctor(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        member_notification_doctor(ctx).await
+    }
+
+    /// Configures notifications and default roles for bot accounts
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn bots(
+        ctx: Context<'_>,
+        #[description = "How bot accounts are handled"] mode: BotMemberChoice,
+        #[description = "Channel for notifications of bots, when using a separate channel"]
+        #[channel_types("Text", "News")]
+        channel: Option<serenity::GuildChannel>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        configure_bot_members(ctx, mode, channel).await
+    }
+
+    /// Shows the moderator and reason of kicks and bans in leave notifications, for staff channels
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management",
+        rename = "staff-mode"
+    )]
+    async fn staff_mode(
+        ctx: Context<'_>,
+        #[description = "Show kicks and bans in leave notifications"] enabled: bool,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        configure_staff_mode(ctx, enabled).await
+    }
+
+    /// Skips or replaces the join notification of members who rejoin shortly after their previous join
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn rejoin(
+        ctx: Context<'_>,
+        #[description = "Hours after a join in which a rejoin isn't welcomed again, 0 to disable"]
+        #[min = 0]
+        #[max = 168]
+        window_hours: u32,
+        #[description = "Send the return message to members who rejoin in the window (default: false)"]
+        welcome_back: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        configure_rejoin(ctx, window_hours, welcome_back.unwrap_or(false)).await
+    }
+
+    /// Delays join notifications, so members can see the channel they are welcomed in
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn delay(
+        ctx: Context<'_>,
+        #[description = "Seconds between a member joining and their join notification, 0 to send it right away"]
+        #[min = 0]
+        #[max = 3600]
+        seconds: u32,
+        #[description = "Also wait until the member passed membership screening (default: false)"]
+        wait_for_screening: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        configure_greeting_delay(ctx, seconds, wait_for_screening.unwrap_or(false)).await
+    }
+}
+
 /// Subcommands of cfg_member_notification for join events
 ///
 /// Contains poise declarations, but implementations are defined in the MemberEventConfigurer trait
@@ -159,6 +312,10 @@ impl CfgMemberJoin {
             "CfgMemberJoin::author_icon",
             "CfgMemberJoin::footer",
             "CfgMemberJoin::footer_icon",
+            "CfgMemberJoin::add_variant",
+            "CfgMemberJoin::remove_variant",
+            "CfgMemberJoin::list_variants",
+            "CfgMemberJoin::edit",
         )
     )]
     async fn group(_ctx: Context<'_>) -> Result<(), Error> {
@@ -368,6 +525,99 @@ impl CfgMemberJoin {
             record_ctx_fields!(ctx);
             CfgMemberJoin::footer_icon_impl(ctx, footer_icon_file, footer_icon_url).await
         }
+
+        /// Adds another join notification variant. One variant is picked at random for each notification.
+        #[poise::command(
+            slash_command,
+            required_permissions = "ADMINISTRATOR",
+            default_member_permissions = "ADMINISTRATOR",
+            guild_only,
+            rename = "add-variant",
+            category = "Management"
+        )]
+        async fn add_variant(
+            ctx: Context<'_>,
+            #[description = "Plain-text content of the notification message"] content: Option<String>,
+            #[description = "Embed title text"] title: Option<String>,
+            #[description = "Embed description text"] description: Option<String>,
+            #[description = "Embed thumbnail file upload"] thumbnail_file: Option<serenity::Attachment>,
+            #[description = "Embed thumbnail web url"] thumbnail_url: Option<String>,
+            #[description = "Embed image file upload"] image_file: Option<serenity::Attachment>,
+            #[description = "Embed image web url"] image_url: Option<String>,
+            #[description = "Embed author text"] author: Option<String>,
+            #[description = "Embed author icon file upload"] author_icon_file: Option<
+                serenity::Attachment,
+            >,
+            #[description = "Embed author icon web url"] author_icon_url: Option<String>,
+            #[description = "Embed footer text"] footer: Option<String>,
+            #[description = "Embed footer icon file upload"] footer_icon_file: Option<
+                serenity::Attachment,
+            >,
+            #[description = "Embed footer icon web url"] footer_icon_url: Option<String>,
+        ) -> Result<(), Error> {
+            record_ctx_fields!(ctx);
+            CfgMemberJoin::add_variant_impl(
+                ctx,
+                content,
+                title,
+                description,
+                thumbnail_file,
+                thumbnail_url,
+                image_file,
+                image_url,
+                author,
+                author_icon_file,
+                author_icon_url,
+                footer,
+                footer_icon_file,
+                footer_icon_url,
+            )
+            .await
+        }
+
+        /// Removes a join notification variant
+        #[poise::command(
+            slash_command,
+            required_permissions = "ADMINISTRATOR",
+            default_member_permissions = "ADMINISTRATOR",
+            guild_only,
+            rename = "remove-variant",
+            category = "Management"
+        )]
+        async fn remove_variant(
+            ctx: Context<'_>,
+            #[description = "Index of the variant (see the variants command)"] variant: u32,
+        ) -> Result<(), Error> {
+            record_ctx_fields!(ctx);
+            CfgMemberJoin::remove_variant_impl(ctx, variant).await
+        }
+
+        /// Lists the join notification variants
+        #[poise::command(
+            slash_command,
+            required_permissions = "ADMINISTRATOR",
+            default_member_permissions = "ADMINISTRATOR",
+            guild_only,
+            rename = "variants",
+            category = "Management"
+        )]
+        async fn list_variants(ctx: Context<'_>) -> Result<(), Error> {
+            record_ctx_fields!(ctx);
+            CfgMemberJoin::list_variants_impl(ctx).await
+        }
+
+        /// Opens a multi-line editor for the join notification text
+        #[poise::command(
+            slash_command,
+            required_permissions = "ADMINISTRATOR",
+            default_member_permissions = "ADMINISTRATOR",
+            guild_only,
+            category = "Management"
+        )]
+        async fn edit(ctx: ApplicationContext<'_>) -> Result<(), Error> {
+            record_ctx_fields!(Context::from(ctx));
+            CfgMemberJoin::edit_impl(ctx).await
+        }
     }
 }
 
@@ -399,6 +649,10 @@ impl CfgMemberLeave {
             "CfgMemberLeave::author_icon",
             "CfgMemberLeave::footer",
             "CfgMemberLeave::footer_icon",
+            "CfgMemberLeave::add_variant",
+            "CfgMemberLeave::remove_variant",
+            "CfgMemberLeave::list_variants",
+            "CfgMemberLeave::edit",
         )
     )]
     async fn group(_ctx: Context<'_>) -> Result<(), Error> {
@@ -613,6 +867,436 @@ impl CfgMemberLeave {
             record_ctx_fields!(ctx);
             CfgMemberLeave::footer_icon_impl(ctx, footer_icon_file, footer_icon_url).await
         }
+
+        /// Adds another leave notification variant. One variant is picked at random for each notification.
+        #[poise::command(
+            slash_command,
+            required_permissions = "ADMINISTRATOR",
+            default_member_permissions = "ADMINISTRATOR",
+            guild_only,
+            rename = "add-variant",
+            category = "Management"
+        )]
+        async fn add_variant(
+            ctx: Context<'_>,
+            #[description = "Plain-text content of the notification message"] content: Option<String>,
+            #[description = "Embed title text"] title: Option<String>,
+            #[description = "Embed description text"] description: Option<String>,
+            #[description = "Embed thumbnail file upload"] thumbnail_file: Option<serenity::Attachment>,
+            #[description = "Embed thumbnail web url"] thumbnail_url: Option<String>,
+            #[description = "Embed image file upload"] image_file: Option<serenity::Attachment>,
+            #[description = "Embed image web url"] image_url: Option<String>,
+            #[description = "Embed author text"] author: Option<String>,
+            #[description = "Embed author icon file upload"] author_icon_file: Option<
+                serenity::Attachment,
+            >,
+            #[description = "Embed author icon web url"] author_icon_url: Option<String>,
+            #[description = "Embed footer text"] footer: Option<String>,
+            #[description = "Embed footer icon file upload"] footer_icon_file: Option<
+                serenity::Attachment,
+            >,
+            #[description = "Embed footer icon web url"] footer_icon_url: Option<String>,
+        ) -> Result<(), Error> {
+            record_ctx_fields!(ctx);
+            CfgMemberLeave::add_variant_impl(
+                ctx,
+                content,
+                title,
+                description,
+                thumbnail_file,
+                thumbnail_url,
+                image_file,
+                image_url,
+                author,
+                author_icon_file,
+                author_icon_url,
+                footer,
+                footer_icon_file,
+                footer_icon_url,
+            )
+            .await
+        }
+
+        /// Removes a leave notification variant
+        #[poise::command(
+            slash_command,
+            required_permissions = "ADMINISTRATOR",
+            default_member_permissions = "ADMINISTRATOR",
+            guild_only,
+            rename = "remove-variant",
+            category = "Management"
+        )]
+        async fn remove_variant(
+            ctx: Context<'_>,
+            #[description = "Index of the variant (see the variants command)"] variant: u32,
+        ) -> Result<(), Error> {
+            record_ctx_fields!(ctx);
+            CfgMemberLeave::remove_variant_impl(ctx, variant).await
+        }
+
+        /// Lists the leave notification variants
+        #[poise::command(
+            slash_command,
+            required_permissions = "ADMINISTRATOR",
+            default_member_permissions = "ADMINISTRATOR",
+            guild_only,
+            rename = "variants",
+            category = "Management"
+        )]
+        async fn list_variants(ctx: Context<'_>) -> Result<(), Error> {
+            record_ctx_fields!(ctx);
+            CfgMemberLeave::list_variants_impl(ctx).await
+        }
+
+        /// Opens a multi-line editor for the leave notification text
+        #[poise::command(
+            slash_command,
+            required_permissions = "ADMINISTRATOR",
+            default_member_permissions = "ADMINISTRATOR",
+            guild_only,
+            category = "Management"
+        )]
+        async fn edit(ctx: ApplicationContext<'_>) -> Result<(), Error> {
+            record_ctx_fields!(Context::from(ctx));
+            CfgMemberLeave::edit_impl(ctx).await
+        }
+    }
+}
+
+/// Subcommands of cfg_member_notification for members who rejoin the guild
+///
+/// Contains poise declarations, but implementations are defined in the MemberEventConfigurer trait
+struct CfgMemberReturn;
+
+impl MemberEventConfigurer for CfgMemberReturn {
+    const NOTIFICATION_TYPE: NotificationType = NotificationType::Return;
+}
+
+impl CfgMemberReturn {
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management",
+        rename = "return",
+        subcommands(
+            "CfgMemberReturn::full",
+            "CfgMemberReturn::content",
+            "CfgMemberReturn::title",
+            "CfgMemberReturn::description",
+            "CfgMemberReturn::thumbnail",
+            "CfgMemberReturn::image",
+            "CfgMemberReturn::author",
+            "CfgMemberReturn::author_icon",
+            "CfgMemberReturn::footer",
+            "CfgMemberReturn::footer_icon",
+            "CfgMemberReturn::add_variant",
+            "CfgMemberReturn::remove_variant",
+            "CfgMemberReturn::list_variants",
+            "CfgMemberReturn::edit",
+        )
+    )]
+    async fn group(_ctx: Context<'_>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    poise_instrument! {
+        /// Provides all configuration options for when members who were here before rejoin this guild.
+        #[poise::command(
+            slash_command,
+            required_permissions = "ADMINISTRATOR",
+            default_member_permissions = "ADMINISTRATOR",
+            guild_only,
+            rename = "full",
+            category = "Management"
+        )]
+        async fn full(
+            ctx: Context<'_>,
+            #[description = "Plain-text content of the notification message"] content: Option<String>,
+            #[description = "Embed title text"] title: Option<String>,
+            #[description = "Embed description text"] description: Option<String>,
+            #[description = "Embed thumbnail file upload"] thumbnail_file: Option<serenity::Attachment>,
+            #[description = "Embed thumbnail web url"] thumbnail_url: Option<String>,
+            #[description = "Embed image file upload"] image_file: Option<serenity::Attachment>,
+            #[description = "Embed image web url"] image_url: Option<String>,
+            #[description = "Embed author text"] author: Option<String>,
+            #[description = "Embed author icon file upload"] author_icon_file: Option<
+                serenity::Attachment,
+            >,
+            #[description = "Embed author icon web url"] author_icon_url: Option<String>,
+            #[description = "Embed footer text"] footer: Option<String>,
+            #[description = "Embed footer icon file upload"] footer_icon_file: Option<
+                serenity::Attachment,
+            >,
+            #[description = "Embed footer icon web url"] footer_icon_url: Option<String>,
+        ) -> Result<(), Error> {
+            record_ctx_fields!(ctx);
+            CfgMemberReturn::full_impl(
+                ctx,
+                content,
+                title,
+                description,
+                thumbnail_file,
+                thumbnail_url,
+                image_file,
+                image_url,
+                author,
+                author_icon_file,
+                author_icon_url,
+                footer,
+                footer_icon_file,
+                footer_icon_url,
+            )
+            .await
+        }
+
+        // Configures the return notification content
+        #[poise::command(
+            slash_command,
+            required_permissions = "ADMINISTRATOR",
+            default_member_permissions = "ADMINISTRATOR",
+            guild_only,
+            category = "Management"
+        )]
+        async fn content(
+            ctx: Context<'_>,
+            #[description = "Plain-text content of the notification message"] content: Option<String>, // param matches func name
+        ) -> Result<(), Error> {
+            record_ctx_fields!(ctx);
+            CfgMemberReturn::content_impl(ctx, content).await
+        }
+
+        /// Configures the return notification embed title
+        #[poise::command(
+            slash_command,
+            required_permissions = "ADMINISTRATOR",
+            default_member_permissions = "ADMINISTRATOR",
+            guild_only,
+            category = "Management"
+        )]
+        async fn title(
+            ctx: Context<'_>,
+            #[description = "Embed title text"] title: Option<String>,
+        ) -> Result<(), Error> {
+            record_ctx_fields!(ctx);
+            CfgMemberReturn::title_impl(ctx, title).await
+        }
+
+        /// Configures the return notification embed description
+        #[poise::command(
+            slash_command,
+            required_permissions = "ADMINISTRATOR",
+            default_member_permissions = "ADMINISTRATOR",
+            guild_only,
+            category = "Management"
+        )]
+        async fn description(
+            ctx: Context<'_>,
+            #[description = "Embed description text"] description: Option<String>,
+        ) -> Result<(), Error> {
+            record_ctx_fields!(ctx);
+            CfgMemberReturn::description_impl(ctx, description).await
+        }
+
+        /// Configures the return notification embed thumbnail
+        #[poise::command(
+            slash_command,
+            required_permissions = "ADMINISTRATOR",
+            default_member_permissions = "ADMINISTRATOR",
+            guild_only,
+            category = "Management"
+        )]
+        async fn thumbnail(
+            ctx: Context<'_>,
+            #[description = "Embed thumbnail file upload"] thumbnail_file: Option<serenity::Attachment>,
+            #[description = "Embed thumbnail web url"] thumbnail_url: Option<String>,
+        ) -> Result<(), Error> {
+            record_ctx_fields!(ctx);
+            CfgMemberReturn::thumbnail_impl(ctx, thumbnail_file, thumbnail_url).await
+        }
+
+        /// Configures the return notification embed image
+        #[poise::command(
+            slash_command,
+            required_permissions = "ADMINISTRATOR",
+            default_member_permissions = "ADMINISTRATOR",
+            guild_only,
+            category = "Management"
+        )]
+        async fn image(
+            ctx: Context<'_>,
+            #[description = "Embed image file upload"] image_file: Option<serenity::Attachment>,
+            #[description = "Embed image web url"] image_url: Option<String>,
+        ) -> Result<(), Error> {
+            record_ctx_fields!(ctx);
+            CfgMemberReturn::image_impl(ctx, image_file, image_url).await
+        }
+
+        /// Configures the return notification embed author
+        #[poise::command(
+            slash_command,
+            required_permissions = "ADMINISTRATOR",
+            default_member_permissions = "ADMINISTRATOR",
+            guild_only,
+            category = "Management"
+        )]
+        async fn author(
+            ctx: Context<'_>,
+            #[description = "Embed author text"] author: Option<String>,
+        ) -> Result<(), Error> {
+            record_ctx_fields!(ctx);
+            CfgMemberReturn::author_impl(ctx, author).await
+        }
+
+        /// Configures the return notification embed author icon
+        #[poise::command(
+            slash_command,
+            required_permissions = "ADMINISTRATOR",
+            default_member_permissions = "ADMINISTRATOR",
+            guild_only,
+            rename = "author-icon",
+            category = "Management"
+        )]
+        async fn author_icon(
+            ctx: Context<'_>,
+            #[description = "Embed author icon file upload"] author_icon_file: Option<
+                serenity::Attachment,
+            >,
+            #[description = "Embed author icon web url"] author_icon_url: Option<String>,
+        ) -> Result<(), Error> {
+            record_ctx_fields!(ctx);
+            CfgMemberReturn::author_icon_impl(ctx, author_icon_file, author_icon_url).await
+        }
+
+        /// Configures the return notification embed footer
+        #[poise::command(
+            slash_command,
+            required_permissions = "ADMINISTRATOR",
+            default_member_permissions = "ADMINISTRATOR",
+            guild_only,
+            category = "Management"
+        )]
+        async fn footer(
+            ctx: Context<'_>,
+            #[description = "Embed footer text"] footer: Option<String>,
+        ) -> Result<(), Error> {
+            record_ctx_fields!(ctx);
+            CfgMemberReturn::footer_impl(ctx, footer).await
+        }
+
+        /// Configures the return notification embed footer icon
+        #[poise::command(
+            slash_command,
+            required_permissions = "ADMINISTRATOR",
+            default_member_permissions = "ADMINISTRATOR",
+            guild_only,
+            rename = "footer-icon",
+            category = "Management"
+        )]
+        async fn footer_icon(
+            ctx: Context<'_>,
+            #[description = "Embed footer icon file upload"] footer_icon_file: Option<
+                serenity::Attachment,
+            >,
+            #[description = "Embed footer icon web url"] footer_icon_url: Option<String>,
+        ) -> Result<(), Error> {
+            record_ctx_fields!(ctx);
+            CfgMemberReturn::footer_icon_impl(ctx, footer_icon_file, footer_icon_url).await
+        }
+
+        /// Adds another return notification variant. One variant is picked at random for each notification.
+        #[poise::command(
+            slash_command,
+            required_permissions = "ADMINISTRATOR",
+            default_member_permissions = "ADMINISTRATOR",
+            guild_only,
+            rename = "add-variant",
+            category = "Management"
+        )]
+        async fn add_variant(
+            ctx: Context<'_>,
+            #[description = "Plain-text content of the notification message"] content: Option<String>,
+            #[description = "Embed title text"] title: Option<String>,
+            #[description = "Embed description text"] description: Option<String>,
+            #[description = "Embed thumbnail file upload"] thumbnail_file: Option<serenity::Attachment>,
+            #[description = "Embed thumbnail web url"] thumbnail_url: Option<String>,
+            #[description = "Embed image file upload"] image_file: Option<serenity::Attachment>,
+            #[description = "Embed image web url"] image_url: Option<String>,
+            #[description = "Embed author text"] author: Option<String>,
+            #[description = "Embed author icon file upload"] author_icon_file: Option<
+                serenity::Attachment,
+            >,
+            #[description = "Embed author icon web url"] author_icon_url: Option<String>,
+            #[description = "Embed footer text"] footer: Option<String>,
+            #[description = "Embed footer icon file upload"] footer_icon_file: Option<
+                serenity::Attachment,
+            >,
+            #[description = "Embed footer icon web url"] footer_icon_url: Option<String>,
+        ) -> Result<(), Error> {
+            record_ctx_fields!(ctx);
+            CfgMemberReturn::add_variant_impl(
+                ctx,
+                content,
+                title,
+                description,
+                thumbnail_file,
+                thumbnail_url,
+                image_file,
+                image_url,
+                author,
+                author_icon_file,
+                author_icon_url,
+                footer,
+                footer_icon_file,
+                footer_icon_url,
+            )
+            .await
+        }
+
+        /// Removes a return notification variant
+        #[poise::command(
+            slash_command,
+            required_permissions = "ADMINISTRATOR",
+            default_member_permissions = "ADMINISTRATOR",
+            guild_only,
+            rename = "remove-variant",
+            category = "Management"
+        )]
+        async fn remove_variant(
+            ctx: Context<'_>,
+            #[description = "Index of the variant (see the variants command)"] variant: u32,
+        ) -> Result<(), Error> {
+            record_ctx_fields!(ctx);
+            CfgMemberReturn::remove_variant_impl(ctx, variant).await
+        }
+
+        /// Lists the return notification variants
+        #[poise::command(
+            slash_command,
+            required_permissions = "ADMINISTRATOR",
+            default_member_permissions = "ADMINISTRATOR",
+            guild_only,
+            rename = "variants",
+            category = "Management"
+        )]
+        async fn list_variants(ctx: Context<'_>) -> Result<(), Error> {
+            record_ctx_fields!(ctx);
+            CfgMemberReturn::list_variants_impl(ctx).await
+        }
+
+        /// Opens a multi-line editor for the return notification text
+        #[poise::command(
+            slash_command,
+            required_permissions = "ADMINISTRATOR",
+            default_member_permissions = "ADMINISTRATOR",
+            guild_only,
+            category = "Management"
+        )]
+        async fn edit(ctx: ApplicationContext<'_>) -> Result<(), Error> {
+            record_ctx_fields!(Context::from(ctx));
+            CfgMemberReturn::edit_impl(ctx).await
+        }
     }
 }
 
@@ -626,8 +1310,21 @@ poise_instrument! {
         hide_in_help,
         category = "Management"
     )]
-    pub async fn test_member_add(ctx: Context<'_>) -> Result<(), Error> {
+    pub async fn test_member_add(
+        ctx: Context<'_>,
+        #[description = "simulate: run the join handling, reply: preview here, channel: send as a test"]
+        target: Option<TestTarget>,
+    ) -> Result<(), Error> {
             record_ctx_fields!(ctx);
+        match target.unwrap_or_default() {
+            TestTarget::Simulate => {}
+            TestTarget::Reply => {
+                return dry_run_member_notification(ctx, NotificationType::Join).await;
+            }
+            TestTarget::Channel => {
+                return test_send_member_notification(ctx, NotificationType::Join).await;
+            }
+        }
         ctx.defer_ephemeral().await?;
         let member = match ctx.author_member().await {
             Some(member) => member,
@@ -652,8 +1349,21 @@ poise_instrument! {
         hide_in_help,
         category = "Management"
     )]
-    pub async fn test_member_remove(ctx: Context<'_>) -> Result<(), Error> {
+    pub async fn test_member_remove(
+        ctx: Context<'_>,
+        #[description = "simulate: run the leave handling, reply: preview here, channel: send as a test"]
+        target: Option<TestTarget>,
+    ) -> Result<(), Error> {
             record_ctx_fields!(ctx);
+        match target.unwrap_or_default() {
+            TestTarget::Simulate => {}
+            TestTarget::Reply => {
+                return dry_run_member_notification(ctx, NotificationType::Leave).await;
+            }
+            TestTarget::Channel => {
+                return test_send_member_notification(ctx, NotificationType::Leave).await;
+            }
+        }
         ctx.defer_ephemeral().await?;
         let guild_id = require_guild_id(ctx)?;
         guild_member_remove(ctx.serenity_context(), ctx.data(), &guild_id, ctx.author()).await?;
@@ -1,32 +1,62 @@
 /*!
 
-Implements the member join / member leave notifications feature, abstracted away from the poise declarations, allowing for code de-duplication.
+Implements the member join / member leave / member return notifications feature, abstracted away from the poise declarations, allowing for code de-duplication.
 
-Without this layer of abstraction, every single function was duplicated twice (once for join and once for leave), with few options to reduce complexity.
+Without this layer of abstraction, every single function was duplicated three times (once for join, leave and return), with few options to reduce complexity.
 
 */
 
-use std::{path::Path, pin::Pin};
+use std::{collections::HashMap, pin::Pin, time::Duration};
 
+use once_cell::sync::Lazy;
 use poise::{
     CreateReply,
-    serenity_prelude::{self as serenity, Attachment, GuildId},
+    serenity_prelude::{
+        self as serenity, ButtonStyle, ChannelId, ComponentInteractionCollector,
+        ComponentInteractionDataKind, CreateActionRow, CreateAllowedMentions, CreateAttachment,
+        CreateButton, CreateEmbed, CreateInteractionResponse, CreateInteractionResponseMessage,
+        CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, EmojiId, GuildChannel,
+        GuildId, Member, Mentionable, PartialGuild,
+    },
 };
+use regex::Regex;
+use reqwest::{StatusCode, header};
 use sea_orm::{
-    ActiveModelTrait,
     ActiveValue::{NotSet, Set, Unchanged},
-    EntityTrait, IntoActiveModel,
+    DatabaseConnection, IntoActiveModel,
 };
-use tokio::io::AsyncWriteExt;
-use tracing::{Level, error, trace, warn};
-use uuid::Uuid;
+use tracing::{Level, error};
 
 use crate::{
-    Context, Error,
+    ApplicationContext, Context, Error,
+    commands::member_management::notification_templates::{
+        NOTIFICATION_TEMPLATES, NotificationTemplate,
+    },
     entities::{self, member_notification_message},
+    events::guild_member::{
+        MemberNotificationFile, MemberNotificationMessageDetails, get_member_notification_variant,
+        member_format_args, notification_files, returning_member_format_args, user_format_args,
+    },
+    events::raid_protection::lockdown_remaining,
     infrastructure::{
-        environment::get_guild_user_content_directory,
-        ids::{id_to_string, require_guild_id},
+        asset_storage::{get_asset_urls, remove_assets, upload_assets},
+        botdata::Data,
+        branding::guild_branding,
+        channel_validation::check_notification_channel,
+        embed_limits::{MessagePart, check_message_lengths},
+        ids::{id_from_string, id_to_string, require_guild_id},
+        user_content::{
+            create_file_from_attachment_safe, remove_user_content_files, user_content_path,
+        },
+    },
+    services::{
+        notifications::{
+            BotMemberMode, GreetingSettings, NotificationRepo, PRIMARY_VARIANT,
+            RETURN_PRIMARY_VARIANT, RejoinSettings,
+        },
+        roles::RolesRepo,
+        unit_of_work::UnitOfWork,
+        user_content::UserContentRepo,
     },
 };
 
@@ -34,6 +64,103 @@ use crate::{
 pub enum NotificationType {
     Join,
     Leave,
+    /// Join notification of members who joined the guild before.
+    Return,
+}
+
+impl NotificationType {
+    fn is_join(&self) -> bool {
+        match self {
+            Self::Join | Self::Return => true,
+            Self::Leave => false,
+        }
+    }
+
+    /// Gets the type of a stored notification variant.
+    fn of_variant(join: bool, variant: i32) -> Self {
+        if !join {
+            Self::Leave
+        } else if variant <= RETURN_PRIMARY_VARIANT {
+            Self::Return
+        } else {
+            Self::Join
+        }
+    }
+
+    /// Name of the `/notify-member` subcommand group configuring this type.
+    fn command_name(&self) -> &'static str {
+        match self {
+            Self::Join => "join",
+            Self::Leave => "leave",
+            Self::Return => "return",
+        }
+    }
+
+    /// Stored variant edited by the single-message configuration commands.
+    fn primary_variant(&self) -> i32 {
+        match self {
+            Self::Join | Self::Leave => PRIMARY_VARIANT,
+            Self::Return => RETURN_PRIMARY_VARIANT,
+        }
+    }
+
+    /// Converts a stored variant to the number shown to users, which counts up from 0 for every type.
+    fn shown_variant(&self, variant: i32) -> i32 {
+        match self {
+            Self::Join | Self::Leave => variant,
+            Self::Return => RETURN_PRIMARY_VARIANT - variant,
+        }
+    }
+
+    /// Converts a variant number shown to users to the stored variant.
+    fn stored_variant(&self, shown: i32) -> i32 {
+        // The conversion is its own inverse
+        self.shown_variant(shown)
+    }
+
+    /// Gets the variants of this type on a guild, starting with the primary variant.
+    async fn variants(
+        &self,
+        db: &DatabaseConnection,
+        guild_id: GuildId,
+    ) -> Result<Vec<member_notification_message::Model>, Error> {
+        let repo = NotificationRepo::new(db);
+        match self {
+            Self::Join | Self::Leave => repo.variants(guild_id, self.is_join()).await,
+            Self::Return => repo.return_variants(guild_id).await,
+        }
+    }
+
+    /// Returns the stored variant for a new variant of this type.
+    async fn next_variant(&self, db: &DatabaseConnection, guild_id: GuildId) -> Result<i32, Error> {
+        let repo = NotificationRepo::new(db);
+        match self {
+            Self::Join | Self::Leave => repo.next_variant(guild_id, self.is_join()).await,
+            Self::Return => repo.next_return_variant(guild_id).await,
+        }
+    }
+
+    /// Gets the placeholder values used to preview a notification of this type for the invoking user.
+    ///
+    /// Return notifications use the time the member joined the guild as their previous join.
+    fn preview_format_args(
+        &self,
+        ctx: Context<'_>,
+        member: Option<&Member>,
+        guild: Option<PartialGuild>,
+    ) -> HashMap<String, String> {
+        match (self, member) {
+            (Self::Join, Some(member)) => member_format_args(member, guild),
+            (Self::Return, Some(member)) => {
+                let previous_join = member
+                    .joined_at
+                    .map(|x| x.unix_timestamp())
+                    .unwrap_or_else(|| ctx.data().clock.unix_timestamp());
+                returning_member_format_args(member, guild, previous_join)
+            }
+            _ => user_format_args(ctx.author(), guild),
+        }
+    }
 }
 
 #[derive(Default, Debug, Clone)]
@@ -85,14 +212,19 @@ impl EmbedAttachment {
 
     async fn get_url_and_create_attachment(
         self,
+        data: &Data,
         guild_id: &GuildId,
+        uow: &UnitOfWork,
         files_added: &mut Vec<String>,
     ) -> Result<String, crate::Error> {
         match self {
             EmbedAttachment::URL(u) => Ok(u),
             EmbedAttachment::File(f) => {
-                match create_file_from_attachment_safe(&guild_id, f, files_added).await {
-                    Ok(filename) => Ok(filename),
+                match create_file_from_attachment_safe(data, &guild_id, f, uow).await {
+                    Ok(filename) => {
+                        files_added.push(filename.clone());
+                        Ok(filename)
+                    }
                     Err(e) => {
                         return Err(e);
                     }
@@ -180,110 +312,1307 @@ impl NotificationManagementRequest {
             .into();
         self
     }
+
+    /// Texts set by the request, which may contain custom emojis.
+    fn texts(&self) -> impl Iterator<Item = &str> {
+        [
+            &self.content,
+            &self.title,
+            &self.description,
+            &self.author,
+            &self.footer,
+        ]
+        .into_iter()
+        .filter_map(|x| match x {
+            OptionalClearable::Some(x) => Some(x.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Urls of the images set by the request. Uploaded files are not included.
+    fn urls(&self) -> impl Iterator<Item = &str> {
+        [
+            &self.thumbnail,
+            &self.image,
+            &self.author_icon,
+            &self.footer_icon,
+        ]
+        .into_iter()
+        .filter_map(|x| match x {
+            OptionalClearable::Some(EmbedAttachment::URL(x)) => Some(x.as_str()),
+            _ => None,
+        })
+    }
 }
 
-/// Creates a file on disk for an attachment submitted via discord API, then returns the name of the newly created file.
-///
-/// This method is 'safe', as in it ensures that any files created (including previous files which can be input with [`files_added`]) are cleaned up if an error occurs.
-///
-/// Since a discord attachment only contains a url to the content hosted on the discord CDN, this function will perform an HTTP request to download the content and write it to disk.
-async fn create_file_from_attachment_safe(
+/// Time a user has to submit the notification editor modal.
+const EDIT_MODAL_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// Multi-line editor for the text fields of a notification, which slash command options can't provide.
+#[derive(Debug, Default, poise::Modal)]
+#[name = "Edit notification message"]
+struct NotificationTextModal {
+    #[name = "Content"]
+    #[paragraph]
+    #[max_length = 2000]
+    content: Option<String>,
+    #[name = "Embed description"]
+    #[paragraph]
+    #[max_length = 4000]
+    description: Option<String>,
+    #[name = "Embed author"]
+    #[max_length = 256]
+    author: Option<String>,
+    #[name = "Embed footer"]
+    #[paragraph]
+    #[max_length = 2048]
+    footer: Option<String>,
+}
+
+/// Opens the notification editor modal prefilled with the primary variant, then saves the submitted text.
+#[tracing::instrument(level = Level::TRACE, err(level = Level::WARN), skip(ctx))]
+async fn edit_member_notification_text(
+    ctx: ApplicationContext<'_>,
+    r#type: NotificationType,
+) -> Result<(), Error> {
+    fn optional_string(string: String) -> Option<String> {
+        if string.is_empty() {
+            None
+        } else {
+            Some(string)
+        }
+    }
+
+    let guild_id = require_guild_id(ctx.into())?;
+    let defaults = NotificationRepo::new(&ctx.data().db_pool)
+        .variant(guild_id, r#type.is_join(), r#type.primary_variant())
+        .await?
+        .map(|model| NotificationTextModal {
+            content: optional_string(model.content),
+            description: optional_string(model.description),
+            author: optional_string(model.author),
+            footer: optional_string(model.footer),
+        });
+
+    let modal = match poise::execute_modal(ctx, defaults, Some(EDIT_MODAL_TIMEOUT)).await? {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+
+    let variant = r#type.primary_variant();
+    configure_member_notifications_impl(
+        ctx.into(),
+        r#type,
+        variant,
+        NotificationManagementRequest::default()
+            .content(modal.content)
+            .description(modal.description)
+            .author(modal.author)
+            .footer(modal.footer),
+    )
+    .await
+}
+
+/// Deletes a notification variant along with any files uploaded for it.
+#[tracing::instrument(level = Level::TRACE, err(level = Level::WARN), skip(ctx))]
+async fn remove_member_notification_variant(
+    ctx: Context<'_>,
+    r#type: NotificationType,
+    variant: i32,
+) -> Result<(), Error> {
+    let guild_id = require_guild_id(ctx)?;
+    let existing = NotificationRepo::new(&ctx.data().db_pool)
+        .variant(guild_id, r#type.is_join(), r#type.stored_variant(variant))
+        .await?;
+
+    let model = match existing {
+        Some(x) => x,
+        None => return Err(format!("Variant {} does not exist.", variant).into()),
+    };
+
+    let files = notification_files(&model);
+
+    let uow = UnitOfWork::begin(&ctx.data().db_pool).await?;
+    let result = async {
+        NotificationRepo::new(uow.txn())
+            .delete_variant(guild_id, model.join, model.variant)
+            .await?;
+        UserContentRepo::new(uow.txn()).release(&files).await?;
+        remove_assets(uow.txn(), guild_id, &files).await
+    }
+    .await;
+    uow.finish(result).await?;
+    remove_user_content_files(ctx.data().db_pool.clone(), guild_id, files).await;
+
+    ctx.send(
+        CreateReply::default()
+            .content(format!("Successfully removed variant {}", variant))
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Lists the configured notification variants with a short preview of each.
+#[tracing::instrument(level = Level::TRACE, err(level = Level::WARN), skip(ctx))]
+async fn list_member_notification_variants(
+    ctx: Context<'_>,
+    r#type: NotificationType,
+) -> Result<(), Error> {
+    fn preview(model: &member_notification_message::Model) -> String {
+        let text = [&model.content, &model.title, &model.description]
+            .into_iter()
+            .find(|x| !x.is_empty())
+            .map(|x| x.replace('\n', " "))
+            .unwrap_or("(no text)".into());
+        if text.chars().count() > 60 {
+            format!("{}...", text.chars().take(60).collect::<String>())
+        } else {
+            text
+        }
+    }
+
+    let guild_id = require_guild_id(ctx)?;
+    let variants = r#type.variants(&ctx.data().db_pool, guild_id).await?;
+
+    let content = if variants.is_empty() {
+        "No notification variants are configured.".to_string()
+    } else {
+        variants
+            .iter()
+            .map(|model| {
+                format!(
+                    "`{}` {}",
+                    r#type.shown_variant(model.variant),
+                    preview(model)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    };
+
+    ctx.send(CreateReply::default().content(content).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Mentions the channel a notification is sent to, adding any problem with it to `problems`.
+async fn notification_channel_summary(
+    ctx: Context<'_>,
+    guild_channels: &HashMap<ChannelId, GuildChannel>,
     guild_id: &GuildId,
-    attachment: Attachment,
-    files_added: &mut Vec<String>,
-) -> Result<String, crate::Error> {
-    #[derive(Debug)]
-    enum CreateAttachmentFileError {
-        DiscordApiError,
-        FlushError(String, crate::Error),
-        WriteError(String, crate::Error),
-        CreateFileError(crate::Error),
-    }
-
-    async fn try_create_file(
-        guild_id: &GuildId,
-        attachment: Attachment,
-    ) -> Result<String, CreateAttachmentFileError> {
-        trace!("Creating file for attachment: {:?}", &attachment);
-        let path = get_guild_user_content_directory(*guild_id);
-        trace!("Ensuring user directory exists: {}", &path.display());
-        tokio::fs::create_dir_all(&path)
-            .await
-            .map_err(|x| CreateAttachmentFileError::CreateFileError(x.into()))?;
-        let guid = Uuid::new_v4();
-        let ext = Path::new(&attachment.filename).extension();
-        let random_filename = if let Some(x) = ext {
-            format!("{}.{}", guid, x.display())
+    is_join: bool,
+    problems: &mut Vec<String>,
+) -> String {
+    let channel = NotificationRepo::new(&ctx.data().db_pool)
+        .channel(*guild_id, is_join)
+        .await
+        .unwrap_or_else(|e| {
+            error!("Failed to get member notification channel: {}", e);
+            None
+        });
+    match channel {
+        Some(channel) if guild_channels.contains_key(&channel) => {
+            if let Err(problem) = check_notification_channel(ctx.cache(), &guild_channels[&channel])
+            {
+                problems.push(problem);
+            }
+            channel.mention().to_string()
+        }
+        Some(channel) => {
+            problems.push(format!("Channel `{}` no longer exists", channel));
+            "(deleted)".to_string()
+        }
+        None => {
+            problems.push("No channel is configured, so nothing is sent".to_string());
+            "(none)".to_string()
+        }
+    }
+}
+
+/// Gets the files a notification variant uses that are neither hosted in the asset storage channel nor on disk.
+async fn missing_notification_files(
+    assets: &HashMap<String, String>,
+    guild_id: GuildId,
+    model: &member_notification_message::Model,
+) -> Vec<String> {
+    let mut missing = vec![];
+    for file in notification_files(model) {
+        let exists = assets.contains_key(&file)
+            || tokio::fs::try_exists(user_content_path(guild_id, &file))
+                .await
+                .unwrap_or(false);
+        if !exists {
+            missing.push(file);
+        }
+    }
+    missing
+}
+
+/// Gets the guild with its member count, used to fill in the previews of notifications.
+// TODO: this request is quite large and slow. Figure out how to more quickly retrieve the guild member count.
+async fn preview_guild(ctx: Context<'_>, guild_id: GuildId) -> Option<PartialGuild> {
+    guild_id.to_partial_guild_with_counts(ctx).await.ok()
+}
+
+/// Shows the join and leave notifications side by side, flagging any misconfiguration.
+///
+/// The reply lists the channel and problems of each notification, followed by a preview of the lowest variant of each
+/// as two embeds (join first, then leave).
+#[tracing::instrument(level = Level::TRACE, err(level = Level::WARN), skip(ctx))]
+pub async fn show_member_notifications(ctx: Context<'_>) -> Result<(), Error> {
+    let branding = guild_branding(ctx).await;
+    fn truncate(text: &str) -> String {
+        let text = text.replace('\n', " ");
+        if text.chars().count() > 100 {
+            format!("{}...", text.chars().take(100).collect::<String>())
         } else {
-            guid.to_string()
-        };
-        trace!("Downloading file attachment");
-        let mut response = reqwest::get(attachment.url)
-            .await
-            .map_err(|_| CreateAttachmentFileError::DiscordApiError)?;
-        if !response.status().is_success() {
-            warn!("Discord returned non-success api response");
-            return Err(CreateAttachmentFileError::DiscordApiError);
-        }
-        trace!("Response: {:?}", response);
-        trace!(
-            "Creating file: {} at path {}",
-            &path.display(),
-            &random_filename
-        );
-        let mut file = tokio::fs::File::create_new(&path.join(&random_filename))
-            .await
-            .map_err(|x| CreateAttachmentFileError::CreateFileError(x.into()))?;
-        while let Some(chunk) = response
-            .chunk()
-            .await
-            .map_err(|x| CreateAttachmentFileError::WriteError(random_filename.clone(), x.into()))?
-        {
-            file.write_all(&chunk).await.map_err(|x| {
-                CreateAttachmentFileError::WriteError(random_filename.clone(), x.into())
-            })?;
-        }
-        file.flush().await.map_err(|x| {
-            CreateAttachmentFileError::FlushError(random_filename.clone(), x.into())
-        })?;
-        Ok(random_filename)
-    }
-
-    match try_create_file(guild_id, attachment).await {
-        Ok(file_name) => {
-            files_added.push(file_name.clone());
-            Ok(file_name)
-        }
-        Err(error) => {
-            warn!("Failed to create file: {:?}", error);
-            let remove_file = match &error {
-                CreateAttachmentFileError::DiscordApiError => None,
-                CreateAttachmentFileError::FlushError(f, _) => Some(f.clone()),
-                CreateAttachmentFileError::WriteError(f, _) => Some(f.clone()),
-                CreateAttachmentFileError::CreateFileError(_) => None,
-            };
+            text
+        }
+    }
+
+    let guild_id = require_guild_id(ctx)?;
+    ctx.defer_ephemeral().await?;
+
+    let db = &ctx.data().db_pool;
+    let guild_channels = guild_id.channels(ctx).await?;
+    let assets = get_asset_urls(db, guild_id, ctx.data().clock.unix_timestamp()).await?;
+    let guild = preview_guild(ctx, guild_id).await;
+    let member = ctx.author_member().await.map(|x| x.into_owned());
+
+    let mut summary: Vec<String> = vec![];
+    let mut embeds: Vec<CreateEmbed> = vec![];
+    let mut attachments: Vec<CreateAttachment> = vec![];
+    for (r#type, position) in [
+        (NotificationType::Join, "first"),
+        (NotificationType::Leave, "second"),
+        (NotificationType::Return, "third"),
+    ] {
+        let is_join = r#type.is_join();
+        let mut problems: Vec<String> = vec![];
+
+        let channel =
+            notification_channel_summary(ctx, &guild_channels, &guild_id, is_join, &mut problems)
+                .await;
 
-            if let Some(f) = remove_file {
-                files_added.push(f);
+        let variants = r#type.variants(db, guild_id).await?;
+        // Returning members get the join notification if no return notification is configured
+        if variants.is_empty() && !matches!(r#type, NotificationType::Return) {
+            problems.push("No message is configured".to_string());
+        }
+        for model in &variants {
+            for file in missing_notification_files(&assets, guild_id, model).await {
+                problems.push(format!(
+                    "Variant {} uses a file that no longer exists (`{}`)",
+                    r#type.shown_variant(model.variant),
+                    file
+                ));
             }
-            for file in files_added {
-                match tokio::fs::remove_file(file).await {
-                    Err(e) => {
-                        error!("Newly created file cannot be removed: {}", e);
+        }
+
+        let format = match variants.first() {
+            Some(model) => {
+                get_member_notification_variant(ctx.data(), &guild_id, is_join, model.variant).await
+            }
+            None => None,
+        };
+        let details = format.map(|format| {
+            MemberNotificationMessageDetails::from_fmt_args(
+                r#type.preview_format_args(ctx, member.as_ref(), guild.clone()),
+                format,
+            )
+        });
+
+        let mut lines = vec![format!(
+            "**{:?} notification** ({} preview) → {} · {} variant(s)",
+            r#type,
+            position,
+            channel,
+            variants.len()
+        )];
+        if let Some(content) = details.as_ref().and_then(|x| x.content.as_ref()) {
+            lines.push(format!("Content: {}", truncate(content)));
+        }
+        if problems.is_empty() {
+            lines.push("✅ No problems found".to_string());
+        } else {
+            lines.extend(problems.iter().map(|x| format!("⚠️ {}", x)));
+        }
+        summary.push(lines.join("\n"));
+
+        let preview = match &details {
+            Some(details) => details.to_embed(&guild_id).await,
+            None => None,
+        };
+        match preview {
+            Some((embed, files)) => {
+                embeds.push(embed);
+                for file in files {
+                    if !attachments.iter().any(|x| x.filename == file.filename) {
+                        attachments.push(file);
                     }
-                    _ => {}
                 }
             }
+            None => embeds.push(branding.embed().description(if details.is_some() {
+                format!("The {:?} notification has no embed", r#type)
+            } else {
+                format!("The {:?} notification is not configured", r#type)
+            })),
+        }
+    }
+
+    let mut reply = CreateReply::default()
+        .content(summary.join("\n\n"))
+        .ephemeral(true);
+    for embed in embeds {
+        reply = reply.embed(embed);
+    }
+    for attachment in attachments {
+        reply = reply.attachment(attachment);
+    }
+    ctx.send(reply).await?;
+    Ok(())
+}
+
+/// Renders a notification for the invoking member into an ephemeral reply, without sending it to the configured
+/// channel or changing the member.
+///
+/// Picks a variant at random like a real event would, and reports the roles that would be added, the placeholders
+/// that resolved and any problem that would prevent the notification from being sent as configured.
+#[tracing::instrument(level = Level::TRACE, err(level = Level::WARN), skip(ctx))]
+pub async fn dry_run_member_notification(
+    ctx: Context<'_>,
+    r#type: NotificationType,
+) -> Result<(), Error> {
+    let branding = guild_branding(ctx).await;
+    fn list_or_none(items: Vec<String>) -> String {
+        if items.is_empty() {
+            "(none)".to_string()
+        } else {
+            items.join(", ")
+        }
+    }
+
+    let guild_id = require_guild_id(ctx)?;
+    ctx.defer_ephemeral().await?;
+
+    let is_join = r#type.is_join();
+    let db = &ctx.data().db_pool;
+    let guild_channels = guild_id.channels(ctx).await?;
+    let assets = get_asset_urls(db, guild_id, ctx.data().clock.unix_timestamp()).await?;
+    let guild = preview_guild(ctx, guild_id).await;
+    let member = ctx.author_member().await.map(|x| x.into_owned());
+
+    let mut problems: Vec<String> = vec![];
+    let channel =
+        notification_channel_summary(ctx, &guild_channels, &guild_id, is_join, &mut problems).await;
 
-            Err(match error {
-                CreateAttachmentFileError::DiscordApiError => None,
-                CreateAttachmentFileError::FlushError(_, e) => Some(e),
-                CreateAttachmentFileError::WriteError(_, e) => Some(e),
-                CreateAttachmentFileError::CreateFileError(e) => Some(e),
+    let variants = r#type.variants(db, guild_id).await?;
+    let chosen = ctx
+        .data()
+        .random
+        .random_index(variants.len())
+        .map(|index| &variants[index]);
+    let variant = match chosen {
+        Some(model) => {
+            for file in missing_notification_files(&assets, guild_id, model).await {
+                problems.push(format!("File `{}` no longer exists", file));
             }
-            .unwrap_or(format!("Failed to save attachment.").into()))
+            format!(
+                "{} (of {})",
+                r#type.shown_variant(model.variant),
+                variants.len()
+            )
+        }
+        None => {
+            problems.push("No message is configured".to_string());
+            "(none)".to_string()
+        }
+    };
+    let format = match chosen {
+        Some(model) => {
+            get_member_notification_variant(ctx.data(), &guild_id, is_join, model.variant).await
         }
+        None => None,
+    };
+
+    let args = r#type.preview_format_args(ctx, member.as_ref(), guild.clone());
+    let (resolved, unresolved): (Vec<String>, Vec<String>) = format
+        .as_ref()
+        .map(|x| x.placeholders())
+        .unwrap_or_default()
+        .into_iter()
+        .partition(|name| args.contains_key(name));
+    for name in &unresolved {
+        problems.push(format!(
+            "Placeholder `{{{}}}` has no value, so the fields using it are left out",
+            name
+        ));
     }
+
+    let mut report = branding
+        .embed()
+        .title(format!("Dry run: {:?} notification", r#type))
+        .description("Nothing was sent to the channel and the member was not changed.")
+        .field("Channel", channel, true)
+        .field("Variant", variant, true)
+        .field(
+            "Placeholders",
+            list_or_none(resolved.iter().map(|x| format!("`{{{}}}`", x)).collect()),
+            false,
+        );
+    if is_join {
+        let roles = RolesRepo::new(db)
+            .default_roles(guild_id)
+            .await?
+            .into_iter()
+            .map(|x| x.mention().to_string())
+            .collect::<Vec<String>>();
+        let mut roles = list_or_none(roles);
+        if lockdown_remaining(&ctx.data().raid_state, &guild_id).is_some() {
+            roles.push_str("\n(paused by raid lockdown)");
+        }
+        report = report.field("Roles to add", roles, false);
+    }
+    let problems = if problems.is_empty() {
+        "✅ No problems found".to_string()
+    } else {
+        problems
+            .iter()
+            .map(|x| format!("⚠️ {}", x))
+            .collect::<Vec<String>>()
+            .join("\n")
+    };
+    report = report.field("Problems", problems, false);
+
+    let details =
+        format.map(|format| MemberNotificationMessageDetails::from_fmt_args(args, format));
+    let reply = match &details {
+        Some(details) => details.to_reply(&guild_id).await,
+        None => CreateReply::default(),
+    };
+    ctx.send(
+        reply
+            .embed(report)
+            .allowed_mentions(CreateAllowedMentions::new())
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Where the test commands send the notification of a simulated member.
+#[derive(Debug, Clone, Copy, PartialEq, Default, poise::ChoiceParameter)]
+pub enum TestTarget {
+    /// Runs the full join or leave handling for the invoking member, including roles.
+    #[default]
+    #[name = "simulate"]
+    Simulate,
+    /// Only previews the notification in the reply, without touching the channel or member.
+    #[name = "reply"]
+    Reply,
+    /// Sends the notification to the configured channel, marked as a test, without touching the member.
+    #[name = "channel"]
+    Channel,
+}
+
+/// Sends a notification rendered for the invoking member to the configured channel, marked as a test so members
+/// aren't confused by it. Fails if the channel is missing or the bot can't post to it.
+pub async fn test_send_member_notification(
+    ctx: Context<'_>,
+    r#type: NotificationType,
+) -> Result<(), Error> {
+    let guild_id = require_guild_id(ctx)?;
+    ctx.defer_ephemeral().await?;
+
+    let is_join = r#type.is_join();
+    let db = &ctx.data().db_pool;
+    let channel_id = NotificationRepo::new(db)
+        .channel(guild_id, is_join)
+        .await?
+        .ok_or("No notification channel is configured")?;
+    let channel = channel_id
+        .to_channel(ctx)
+        .await?
+        .guild()
+        .ok_or("The notification channel is not a guild channel")?;
+    check_notification_channel(ctx.cache(), &channel)?;
+
+    let variants = r#type.variants(db, guild_id).await?;
+    let model = ctx
+        .data()
+        .random
+        .random_index(variants.len())
+        .map(|index| &variants[index])
+        .ok_or("No message is configured")?;
+    let format = get_member_notification_variant(ctx.data(), &guild_id, is_join, model.variant)
+        .await
+        .ok_or("The notification message could not be loaded")?;
+    let guild = preview_guild(ctx, guild_id).await;
+    let member = ctx.author_member().await.map(|x| x.into_owned());
+    let args = r#type.preview_format_args(ctx, member.as_ref(), guild);
+
+    let mut details = MemberNotificationMessageDetails::from_fmt_args(args, format);
+    let marker = format!(
+        "🧪 **Test {:?} notification** sent by {}, nobody joined or left.",
+        r#type,
+        ctx.author().mention()
+    );
+    details.content = Some(match details.content {
+        Some(content) => format!("{}\n{}", marker, content),
+        None => marker,
+    });
+    let message = channel
+        .send_message(
+            ctx,
+            details
+                .to_message(&guild_id)
+                .await
+                .allowed_mentions(CreateAllowedMentions::new()),
+        )
+        .await?;
+    ctx.send(
+        CreateReply::default()
+            .content(format!(
+                "Sent variant {} to {}: {}",
+                r#type.shown_variant(model.variant),
+                channel.mention(),
+                message.link()
+            ))
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum BotMemberChoice {
+    #[name = "Same as members"]
+    Notify,
+    #[name = "No notifications or roles"]
+    Suppress,
+    #[name = "Separate channel"]
+    Route,
+}
+
+/// Configures how join and leave notifications and default roles are handled for bot accounts.
+#[tracing::instrument(level = Level::TRACE, err(level = Level::WARN), skip(ctx))]
+pub async fn configure_bot_members(
+    ctx: Context<'_>,
+    mode: BotMemberChoice,
+    channel: Option<GuildChannel>,
+) -> Result<(), Error> {
+    let guild_id = require_guild_id(ctx)?;
+    let (mode, content) = match (mode, channel) {
+        (BotMemberChoice::Notify, _) => (
+            BotMemberMode::Notify,
+            "Bots now get the same notifications and roles as other members.".to_string(),
+        ),
+        (BotMemberChoice::Suppress, _) => (
+            BotMemberMode::Suppress,
+            "Bots no longer get join and leave notifications or default roles.".to_string(),
+        ),
+        (BotMemberChoice::Route, Some(channel)) => {
+            check_notification_channel(ctx.cache(), &channel)?;
+            (
+                BotMemberMode::Route(channel.id),
+                format!(
+                    "Join and leave notifications of bots are now sent to {}.",
+                    channel.mention()
+                ),
+            )
+        }
+        (BotMemberChoice::Route, None) => {
+            return Err("Choose the channel to send notifications of bots to".into());
+        }
+    };
+
+    NotificationRepo::new(&ctx.data().db_pool)
+        .set_bot_member_mode(guild_id, &mode)
+        .await?;
+
+    ctx.send(CreateReply::default().content(content).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Configures whether leave notifications show the moderator and reason when a member was kicked or banned.
+#[tracing::instrument(level = Level::TRACE, err(level = Level::WARN), skip(ctx))]
+pub async fn configure_staff_mode(ctx: Context<'_>, enabled: bool) -> Result<(), Error> {
+    let guild_id = require_guild_id(ctx)?;
+    NotificationRepo::new(&ctx.data().db_pool)
+        .set_staff_mode(guild_id, enabled)
+        .await?;
+
+    let content = if !enabled {
+        "Leave notifications no longer show kicks and bans.".to_string()
+    } else {
+        let can_view_audit_log = ctx.guild().is_some_and(|guild| {
+            guild
+                .members
+                .get(&ctx.cache().current_user().id)
+                .is_some_and(|bot| guild.member_permissions(bot).view_audit_log())
+        });
+        let mut content = "Leave notifications now show the moderator and reason when a member was kicked or banned. Only enable this if the leave channel is visible to staff only.".to_string();
+        if !can_view_audit_log {
+            content.push_str(
+                "\n⚠️ The bot needs the View Audit Log permission to see kicks and bans.",
+            );
+        }
+        content
+    };
+    ctx.send(CreateReply::default().content(content).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Configures how members who rejoin within `window_hours` of their previous join are welcomed.
+#[tracing::instrument(level = Level::TRACE, err(level = Level::WARN), skip(ctx))]
+pub async fn configure_rejoin(
+    ctx: Context<'_>,
+    window_hours: u32,
+    welcome_back: bool,
+) -> Result<(), Error> {
+    let guild_id = require_guild_id(ctx)?;
+    let repo = NotificationRepo::new(&ctx.data().db_pool);
+    repo.set_rejoin_settings(
+        guild_id,
+        RejoinSettings {
+            window_hours: window_hours as i32,
+            welcome_back,
+        },
+    )
+    .await?;
+
+    let content = if window_hours == 0 {
+        "Members who rejoin are welcomed like new members.".to_string()
+    } else {
+        let mut content = if welcome_back {
+            format!(
+                "Members who rejoin within {} hour(s) only get the return notification.",
+                window_hours
+            )
+        } else {
+            format!(
+                "Members who rejoin within {} hour(s) are not welcomed again.",
+                window_hours
+            )
+        };
+        if welcome_back && repo.return_variants(guild_id).await?.is_empty() {
+            content.push_str(
+                "\n⚠️ No return notification is configured, so nothing is sent. Set one with `/notify-member return full`.",
+            );
+        }
+        content
+    };
+    ctx.send(CreateReply::default().content(content).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Configures how long join notifications wait after a member joined, and whether they wait for membership screening.
+#[tracing::instrument(level = Level::TRACE, err(level = Level::WARN), skip(ctx))]
+pub async fn configure_greeting_delay(
+    ctx: Context<'_>,
+    delay_seconds: u32,
+    wait_for_screening: bool,
+) -> Result<(), Error> {
+    let guild_id = require_guild_id(ctx)?;
+    NotificationRepo::new(&ctx.data().db_pool)
+        .set_greeting_settings(
+            guild_id,
+            GreetingSettings {
+                delay_seconds: delay_seconds as i32,
+                wait_for_pending: wait_for_screening,
+            },
+        )
+        .await?;
+
+    let mut content = match delay_seconds {
+        0 => "Join notifications are sent as soon as members join".to_string(),
+        x => format!(
+            "Join notifications are sent {} second(s) after members join",
+            x
+        ),
+    };
+    if wait_for_screening {
+        content.push_str(", once they passed membership screening.");
+        let screening_enabled = ctx.guild().is_some_and(|guild| {
+            guild
+                .features
+                .iter()
+                .any(|x| x == "MEMBER_VERIFICATION_GATE_ENABLED")
+        });
+        if !screening_enabled {
+            content.push_str(
+                "\n⚠️ Membership screening is not enabled on this server, so members never wait for it.",
+            );
+        }
+    } else {
+        content.push('.');
+    }
+    ctx.send(CreateReply::default().content(content).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Time a user has to decide whether to clear the broken references found by the doctor.
+const DOCTOR_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Time a url referenced by a notification has to respond when checked by the doctor.
+const DOCTOR_URL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Image of a notification variant that can reference a file or a url.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NotificationImage {
+    Thumbnail,
+    Image,
+    AuthorIcon,
+    FooterIcon,
+}
+
+impl NotificationImage {
+    const ALL: [Self; 4] = [
+        Self::Thumbnail,
+        Self::Image,
+        Self::AuthorIcon,
+        Self::FooterIcon,
+    ];
+
+    /// Name of the command that replaces this image.
+    fn command_name(&self) -> &'static str {
+        match self {
+            Self::Thumbnail => "thumbnail",
+            Self::Image => "image",
+            Self::AuthorIcon => "author-icon",
+            Self::FooterIcon => "footer-icon",
+        }
+    }
+
+    /// Gets whether the image is an uploaded file, and its filename or url.
+    fn get<'a>(&self, model: &'a member_notification_message::Model) -> (bool, &'a str) {
+        match self {
+            Self::Thumbnail => (model.thumbnail_is_file, &model.thumbnail_url),
+            Self::Image => (model.image_is_file, &model.image_url),
+            Self::AuthorIcon => (model.author_icon_is_file, &model.author_icon_url),
+            Self::FooterIcon => (model.footer_icon_is_file, &model.footer_icon_url),
+        }
+    }
+
+    /// Removes the image from a variant.
+    fn clear(&self, model: &mut member_notification_message::ActiveModel) {
+        match self {
+            Self::Thumbnail => {
+                model.thumbnail_is_file = Set(false);
+                model.thumbnail_url = Set(String::new());
+            }
+            Self::Image => {
+                model.image_is_file = Set(false);
+                model.image_url = Set(String::new());
+            }
+            Self::AuthorIcon => {
+                model.author_icon_is_file = Set(false);
+                model.author_icon_url = Set(String::new());
+            }
+            Self::FooterIcon => {
+                model.footer_icon_is_file = Set(false);
+                model.footer_icon_url = Set(String::new());
+            }
+        }
+    }
+}
+
+/// Image of a notification variant that would fail to render.
+struct BrokenReference {
+    join: bool,
+    variant: i32,
+    image: NotificationImage,
+    problem: String,
+}
+
+/// Checks that a url referenced by a notification can be downloaded, describing the problem if it can't.
+async fn check_notification_url(http: &reqwest::Client, url: &str) -> Result<(), String> {
+    match http.get(url).send().await {
+        Ok(response) if response.status().is_success() => Ok(()),
+        Ok(response) => Err(format!("url <{}> returned {}", url, response.status())),
+        Err(e) if e.is_timeout() => Err(format!("url <{}> timed out", url)),
+        Err(_) => Err(format!("url <{}> could not be reached", url)),
+    }
+}
+
+/// Custom emojis in a message, such as `<:name:id>` or `<a:name:id>`.
+static CUSTOM_EMOJI_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"<a?:(\w+):([1-9]\d*)>").expect("Custom emoji pattern is a valid regex")
+});
+
+/// Checks that a url set on a notification points to an image, with a HEAD request rather than downloading it.
+/// Servers that don't support HEAD requests are checked like the doctor does.
+async fn check_notification_image_url(http: &reqwest::Client, url: &str) -> Result<(), String> {
+    let response = match http.head(url).send().await {
+        Ok(response)
+            if matches!(
+                response.status(),
+                StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+            ) =>
+        {
+            return check_notification_url(http, url).await;
+        }
+        Ok(response) => response,
+        Err(e) if e.is_timeout() => return Err(format!("url <{}> timed out", url)),
+        Err(_) => return Err(format!("url <{}> could not be reached", url)),
+    };
+    if !response.status().is_success() {
+        return Err(format!("url <{}> returned {}", url, response.status()));
+    }
+    match response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|x| x.to_str().ok())
+    {
+        Some(content_type) if !content_type.starts_with("image/") => {
+            Err(format!("url <{}> is not an image ({})", url, content_type))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Checks that the bot can use a custom emoji in the notifications of a guild, describing the problem if it can't.
+/// Emojis are checked against the cache, and are assumed to be fine if the guild isn't cached yet.
+fn check_notification_emoji(
+    cache: &serenity::Cache,
+    guild_id: GuildId,
+    name: &str,
+    emoji_id: EmojiId,
+) -> Result<(), String> {
+    let bot_id = cache.current_user().id;
+    let Some(guild) = cache.guild(guild_id) else {
+        return Ok(());
+    };
+    let Some(member) = guild.members.get(&bot_id) else {
+        return Ok(());
+    };
+    if let Some(emoji) = guild.emojis.get(&emoji_id) {
+        if !emoji.available {
+            return Err(format!(
+                "emoji :{}: is unavailable, the server may have lost the boosts it needs",
+                name
+            ));
+        }
+        if !emoji.roles.is_empty() && !emoji.roles.iter().any(|x| member.roles.contains(x)) {
+            return Err(format!(
+                "emoji :{}: is restricted to roles the bot doesn't have",
+                name
+            ));
+        }
+        return Ok(());
+    }
+    let can_use_external = guild.member_permissions(member).use_external_emojis();
+    drop(guild);
+
+    let known = cache.guilds().into_iter().any(|id| {
+        cache
+            .guild(id)
+            .is_some_and(|x| x.emojis.contains_key(&emoji_id))
+    });
+    if !known {
+        Err(format!(
+            "emoji :{}: is from a server the bot is not in, so it will show as text",
+            name
+        ))
+    } else if !can_use_external {
+        Err(format!(
+            "emoji :{}: is from another server, and the bot is missing the Use External Emojis permission",
+            name
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Looks for content of a notification management request that would not render: image urls that don't respond and
+/// custom emojis the bot can't use. Requests are saved anyway, since a url may only be down for a while.
+async fn notification_request_warnings(
+    ctx: Context<'_>,
+    guild_id: GuildId,
+    request: &NotificationManagementRequest,
+) -> Result<Vec<String>, Error> {
+    let mut warnings = vec![];
+    let mut checked_emojis = vec![];
+    for captures in request
+        .texts()
+        .flat_map(|x| CUSTOM_EMOJI_PATTERN.captures_iter(x))
+    {
+        let Ok(emoji_id) = id_from_string::<EmojiId>(&captures[2]) else {
+            continue;
+        };
+        if checked_emojis.contains(&emoji_id) {
+            continue;
+        }
+        checked_emojis.push(emoji_id);
+        if let Err(problem) =
+            check_notification_emoji(ctx.cache(), guild_id, &captures[1], emoji_id)
+        {
+            warnings.push(problem);
+        }
+    }
+
+    // Urls with placeholders are only known once a notification is sent
+    let urls: Vec<&str> = request
+        .urls()
+        .filter(|x| !x.is_empty() && !x.contains('{'))
+        .collect();
+    if !urls.is_empty() {
+        let http = reqwest::Client::builder()
+            .timeout(DOCTOR_URL_TIMEOUT)
+            .build()?;
+        let results = serenity::futures::future::join_all(
+            urls.iter()
+                .map(|url| check_notification_image_url(&http, url)),
+        )
+        .await;
+        warnings.extend(results.into_iter().filter_map(Result::err));
+    }
+    Ok(warnings)
+}
+
+/// Scans every notification variant of the guild for files that no longer exist and urls that don't respond, and
+/// offers to clear the broken references.
+///
+/// Broken references would otherwise only be noticed through the errors logged when a notification is rendered.
+#[tracing::instrument(level = Level::TRACE, err(level = Level::WARN), skip(ctx))]
+pub async fn member_notification_doctor(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = require_guild_id(ctx)?;
+    ctx.defer_ephemeral().await?;
+
+    let db = &ctx.data().db_pool;
+    let assets = get_asset_urls(db, guild_id, ctx.data().clock.unix_timestamp()).await?;
+    let http = reqwest::Client::builder()
+        .timeout(DOCTOR_URL_TIMEOUT)
+        .build()?;
+
+    let repo = NotificationRepo::new(db);
+    let models = repo.guild_variants(guild_id).await?;
+
+    let missing_files: Vec<Vec<String>> = serenity::futures::future::join_all(
+        models
+            .iter()
+            .map(|model| missing_notification_files(&assets, guild_id, model)),
+    )
+    .await;
+    let mut checks = vec![];
+    for (model, missing_files) in models.iter().zip(&missing_files) {
+        for image in NotificationImage::ALL {
+            let (is_file, url) = image.get(model);
+            let http = &http;
+            checks.push(async move {
+                let problem = if url.is_empty() {
+                    None
+                } else if is_file {
+                    missing_files
+                        .iter()
+                        .any(|x| x == url)
+                        .then(|| format!("file `{}` no longer exists", url))
+                } else if url.contains('{') {
+                    None // Placeholders are only resolved when a notification is sent
+                } else {
+                    check_notification_url(http, url).await.err()
+                };
+                problem.map(|problem| BrokenReference {
+                    join: model.join,
+                    variant: model.variant,
+                    image,
+                    problem,
+                })
+            });
+        }
+    }
+    let broken: Vec<BrokenReference> = serenity::futures::future::join_all(checks)
+        .await
+        .into_iter()
+        .flatten()
+        .collect();
+
+    if broken.is_empty() {
+        ctx.send(
+            CreateReply::default()
+                .content(format!(
+                    "✅ Checked {} notification variant(s), every file and url is reachable.",
+                    models.len()
+                ))
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let mut lines = vec![format!(
+        "Found {} broken reference(s) in {} notification variant(s):",
+        broken.len(),
+        models.len()
+    )];
+    for x in &broken {
+        let r#type = NotificationType::of_variant(x.join, x.variant);
+        let kind = r#type.command_name();
+        let fix = if x.variant == r#type.primary_variant() {
+            format!(
+                "replace with `/notify-member {} {}`",
+                kind,
+                x.image.command_name()
+            )
+        } else {
+            format!("re-create with `/notify-member {} add-variant`", kind)
+        };
+        lines.push(format!(
+            "⚠️ {} variant {} {}: {} ({})",
+            kind,
+            r#type.shown_variant(x.variant),
+            x.image.command_name(),
+            x.problem,
+            fix
+        ));
+    }
+    lines.push(String::new());
+    lines.push(
+        "Clear the broken references so the notifications are sent without them?".to_string(),
+    );
+    let mut content = lines.join("\n");
+    if content.chars().count() > 2000 {
+        content = format!("{}...", content.chars().take(1997).collect::<String>());
+    }
+
+    let clear_id = format!("{}clear", ctx.id());
+    let keep_id = format!("{}keep", ctx.id());
+    let handle = ctx
+        .send(
+            CreateReply::default()
+                .content(content)
+                .components(vec![CreateActionRow::Buttons(vec![
+                    CreateButton::new(&clear_id)
+                        .label("Clear broken references")
+                        .style(ButtonStyle::Danger),
+                    CreateButton::new(&keep_id)
+                        .label("Keep")
+                        .style(ButtonStyle::Secondary),
+                ])])
+                .ephemeral(true),
+        )
+        .await?;
+
+    let ctx_id = ctx.id().to_string();
+    let interaction = ComponentInteractionCollector::new(ctx)
+        .author_id(ctx.author().id)
+        .channel_id(ctx.channel_id())
+        .timeout(DOCTOR_TIMEOUT)
+        .filter(move |mci| mci.data.custom_id.starts_with(&ctx_id))
+        .await;
+
+    let result = match interaction {
+        Some(interaction) if interaction.data.custom_id == clear_id => {
+            interaction
+                .create_response(ctx, CreateInteractionResponse::Acknowledge)
+                .await?;
+            let mut cleared = 0;
+            for model in models {
+                let images: Vec<NotificationImage> = broken
+                    .iter()
+                    .filter(|x| x.join == model.join && x.variant == model.variant)
+                    .map(|x| x.image)
+                    .collect();
+                if images.is_empty() {
+                    continue;
+                }
+                let mut active = model.into_active_model();
+                for image in &images {
+                    image.clear(&mut active);
+                }
+                repo.update_variant(active).await?;
+                cleared += images.len();
+            }
+            format!("Cleared {} broken reference(s).", cleared)
+        }
+        Some(interaction) => {
+            interaction
+                .create_response(ctx, CreateInteractionResponse::Acknowledge)
+                .await?;
+            "Kept the broken references.".to_string()
+        }
+        None => "Timed out, kept the broken references.".to_string(),
+    };
+    handle
+        .edit(
+            ctx,
+            CreateReply::default().content(result).components(vec![]),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Time a user has to browse the template gallery before its components are removed.
+const TEMPLATE_GALLERY_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Renders a page of the template gallery: a description of the template, its preview and the gallery components.
+async fn template_gallery_page(
+    ctx: Context<'_>,
+    guild_id: &GuildId,
+    guild: Option<PartialGuild>,
+    member: Option<&Member>,
+    id: u64,
+    index: usize,
+) -> (String, Option<CreateEmbed>, Vec<CreateActionRow>) {
+    let template = &NOTIFICATION_TEMPLATES[index];
+    let kind = if template.join { "join" } else { "leave" };
+    let format = MemberNotificationMessageDetails::format(
+        template.content.map(String::from),
+        true,
+        template.title.map(String::from),
+        template.description.map(String::from),
+        template.thumbnail_url.map(|url| MemberNotificationFile {
+            attachment: false,
+            url: url.to_string(),
+        }),
+        None,
+        template.author.map(String::from),
+        None,
+        template.footer.map(String::from),
+        None,
+    );
+    let details = match member {
+        Some(member) if template.join => {
+            MemberNotificationMessageDetails::for_member(member, guild, format)
+        }
+        _ => MemberNotificationMessageDetails::for_user(ctx.author(), guild, format),
+    };
+
+    let mut content = format!(
+        "**{}** ({} template {}/{})\n{}",
+        template.name,
+        kind,
+        index + 1,
+        NOTIFICATION_TEMPLATES.len(),
+        template.summary
+    );
+    if let Some(x) = &details.content {
+        content = format!("{}\n\n>>> {}", content, x);
+    }
+
+    let options = NOTIFICATION_TEMPLATES
+        .iter()
+        .enumerate()
+        .map(|(i, x)| {
+            CreateSelectMenuOption::new(
+                format!("{} ({})", x.name, if x.join { "join" } else { "leave" }),
+                i.to_string(),
+            )
+            .default_selection(i == index)
+        })
+        .collect();
+    let components = vec![
+        CreateActionRow::SelectMenu(CreateSelectMenu::new(
+            format!("{}select", id),
+            CreateSelectMenuKind::String { options },
+        )),
+        CreateActionRow::Buttons(vec![
+            CreateButton::new(format!("{}apply", id))
+                .label(format!("Apply as {} notification", kind))
+                .style(ButtonStyle::Primary),
+        ]),
+    ];
+
+    let embed = details.to_embed(guild_id).await.map(|(embed, _)| embed);
+    (content, embed, components)
+}
+
+/// Builds the request that replaces the primary variant with a template.
+fn template_request(template: &NotificationTemplate) -> NotificationManagementRequest {
+    NotificationManagementRequest::default()
+        .content(template.content.map(String::from))
+        .title(template.title.map(String::from))
+        .description(template.description.map(String::from))
+        .thumbnail(None, template.thumbnail_url.map(String::from))
+        .image(None, None)
+        .author(template.author.map(String::from))
+        .author_icon(None, None)
+        .footer(template.footer.map(String::from))
+        .footer_icon(None, None)
+}
+
+/// Shows the built-in notification templates with a preview of each, and applies the template the user picks.
+#[tracing::instrument(level = Level::TRACE, err(level = Level::WARN), skip(ctx))]
+pub async fn show_notification_templates(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = require_guild_id(ctx)?;
+    ctx.defer_ephemeral().await?;
+
+    let guild = preview_guild(ctx, guild_id).await;
+    let member = ctx.author_member().await.map(|x| x.into_owned());
+    let id = ctx.id();
+    let mut index = 0;
+
+    let (content, embed, components) =
+        template_gallery_page(ctx, &guild_id, guild.clone(), member.as_ref(), id, index).await;
+    let mut reply = CreateReply::default()
+        .content(content)
+        .components(components)
+        .ephemeral(true);
+    if let Some(embed) = embed {
+        reply = reply.embed(embed);
+    }
+    let handle = ctx.send(reply).await?;
+
+    while let Some(press) = ComponentInteractionCollector::new(ctx)
+        .filter(move |press| press.data.custom_id.starts_with(&id.to_string()))
+        .timeout(TEMPLATE_GALLERY_TIMEOUT)
+        .await
+    {
+        if press.data.custom_id.ends_with("apply") {
+            let template = &NOTIFICATION_TEMPLATES[index];
+            press
+                .create_response(
+                    ctx,
+                    CreateInteractionResponse::UpdateMessage(
+                        CreateInteractionResponseMessage::new()
+                            .content(format!("Applying template **{}**...", template.name))
+                            .components(vec![]),
+                    ),
+                )
+                .await?;
+            let r#type = if template.join {
+                NotificationType::Join
+            } else {
+                NotificationType::Leave
+            };
+            return configure_member_notifications_impl(
+                ctx,
+                r#type,
+                PRIMARY_VARIANT,
+                template_request(template),
+            )
+            .await;
+        }
+
+        if let ComponentInteractionDataKind::StringSelect { values } = &press.data.kind {
+            index = values
+                .first()
+                .and_then(|x| x.parse::<usize>().ok())
+                .filter(|x| *x < NOTIFICATION_TEMPLATES.len())
+                .unwrap_or(index);
+        }
+        let (content, embed, components) =
+            template_gallery_page(ctx, &guild_id, guild.clone(), member.as_ref(), id, index).await;
+        let mut response = CreateInteractionResponseMessage::new()
+            .content(content)
+            .components(components);
+        if let Some(embed) = embed {
+            response = response.embed(embed);
+        }
+        press
+            .create_response(ctx, CreateInteractionResponse::UpdateMessage(response))
+            .await?;
+    }
+
+    handle
+        .edit(ctx, CreateReply::default().components(vec![]))
+        .await?;
+    Ok(())
 }
 
 /// Fully implements a notification management request, including database access, http requests for new attachments, and deletion of old attachments.
@@ -291,6 +1620,7 @@ async fn create_file_from_attachment_safe(
 async fn configure_member_notifications_impl(
     ctx: Context<'_>,
     r#type: NotificationType,
+    variant: i32,
     request: NotificationManagementRequest,
 ) -> Result<(), Error> {
     fn active_model_file_attachment(
@@ -312,19 +1642,21 @@ async fn configure_member_notifications_impl(
         }
     }
 
+    fn active_model_text(value: &sea_orm::ActiveValue<String>) -> &str {
+        match value {
+            Set(value) | Unchanged(value) => value,
+            NotSet => "",
+        }
+    }
+
     ctx.defer_ephemeral().await?;
 
     let guild_id = require_guild_id(ctx)?;
-    let is_join = match r#type {
-        NotificationType::Join => true,
-        NotificationType::Leave => false,
-    };
-    let existing = entities::member_notification_message::Entity::find_by_id((
-        id_to_string(guild_id),
-        is_join,
-    ))
-    .one(&ctx.data().db_pool)
-    .await?;
+    let warnings = notification_request_warnings(ctx, guild_id, &request).await?;
+    let is_join = r#type.is_join();
+    let existing = NotificationRepo::new(&ctx.data().db_pool)
+        .variant(guild_id, is_join, variant)
+        .await?;
 
     let mut files_to_delete: Vec<String> = vec![];
     let mut files_added: Vec<String> = vec![];
@@ -334,157 +1666,142 @@ async fn configure_member_notifications_impl(
             entities::member_notification_message::ActiveModel {
                 guild_id: Set(id_to_string(guild_id.clone())),
                 join: Set(is_join),
+                variant: Set(variant),
                 ..Default::default()
             },
             false,
         ),
     };
 
-    if let Option::<String>::Some(x) = request.content.into() {
-        model.content = Set(x.replace("\\n", "\n"));
-    }
-
-    if let Option::<String>::Some(x) = request.title.into() {
-        model.title = Set(x.replace("\\n", "\n"));
-    }
+    // New files are removed again if the change can't be saved, old files only once it was saved
+    let uow = UnitOfWork::begin(&ctx.data().db_pool).await?;
+    let result = async {
+        if let Option::<String>::Some(x) = request.content.into() {
+            model.content = Set(x.replace("\\n", "\n"));
+        }
 
-    if let Option::<String>::Some(x) = request.description.into() {
-        model.description = Set(x.replace("\\n", "\n"));
-    }
+        if let Option::<String>::Some(x) = request.title.into() {
+            model.title = Set(x.replace("\\n", "\n"));
+        }
 
-    if let Option::<EmbedAttachment>::Some(x) = request.thumbnail.into() {
-        if let Some(old_file) =
-            active_model_file_attachment(model.thumbnail_is_file, model.thumbnail_url)
-        {
-            files_to_delete.push(old_file);
+        if let Option::<String>::Some(x) = request.description.into() {
+            model.description = Set(x.replace("\\n", "\n"));
         }
 
-        model.thumbnail_is_file = Set(x.is_file());
-        model.thumbnail_url = Set(x
-            .get_url_and_create_attachment(&guild_id, &mut files_added)
-            .await?);
-    }
+        if let Option::<EmbedAttachment>::Some(x) = request.thumbnail.into() {
+            if let Some(old_file) =
+                active_model_file_attachment(model.thumbnail_is_file, model.thumbnail_url)
+            {
+                files_to_delete.push(old_file);
+            }
 
-    if let Option::<EmbedAttachment>::Some(x) = request.image.into() {
-        if let Some(old_file) = active_model_file_attachment(model.image_is_file, model.image_url) {
-            files_to_delete.push(old_file);
+            model.thumbnail_is_file = Set(x.is_file());
+            model.thumbnail_url = Set(x
+                .get_url_and_create_attachment(ctx.data(), &guild_id, &uow, &mut files_added)
+                .await?);
         }
 
-        model.image_is_file = Set(x.is_file());
-        model.image_url = Set(x
-            .get_url_and_create_attachment(&guild_id, &mut files_added)
-            .await?)
-    }
-
-    if let Option::<String>::Some(x) = request.author.into() {
-        model.author = Set(x.replace("\\n", "\n"));
-    }
+        if let Option::<EmbedAttachment>::Some(x) = request.image.into() {
+            if let Some(old_file) =
+                active_model_file_attachment(model.image_is_file, model.image_url)
+            {
+                files_to_delete.push(old_file);
+            }
 
-    if let Option::<EmbedAttachment>::Some(x) = request.author_icon.into() {
-        if let Some(old_file) =
-            active_model_file_attachment(model.author_icon_is_file, model.author_icon_url)
-        {
-            files_to_delete.push(old_file);
+            model.image_is_file = Set(x.is_file());
+            model.image_url = Set(x
+                .get_url_and_create_attachment(ctx.data(), &guild_id, &uow, &mut files_added)
+                .await?)
         }
 
-        model.author_icon_is_file = Set(x.is_file());
-        model.author_icon_url = Set(x
-            .get_url_and_create_attachment(&guild_id, &mut files_added)
-            .await?)
-    }
+        if let Option::<String>::Some(x) = request.author.into() {
+            model.author = Set(x.replace("\\n", "\n"));
+        }
 
-    if let Option::<String>::Some(x) = request.footer.into() {
-        model.footer = Set(x.replace("\\n", "\n"));
-    }
+        if let Option::<EmbedAttachment>::Some(x) = request.author_icon.into() {
+            if let Some(old_file) =
+                active_model_file_attachment(model.author_icon_is_file, model.author_icon_url)
+            {
+                files_to_delete.push(old_file);
+            }
 
-    if let Option::<EmbedAttachment>::Some(x) = request.footer_icon.into() {
-        if let Some(old_file) =
-            active_model_file_attachment(model.footer_icon_is_file, model.footer_icon_url)
-        {
-            files_to_delete.push(old_file);
+            model.author_icon_is_file = Set(x.is_file());
+            model.author_icon_url = Set(x
+                .get_url_and_create_attachment(ctx.data(), &guild_id, &uow, &mut files_added)
+                .await?)
         }
 
-        model.footer_icon_is_file = Set(x.is_file());
-        model.footer_icon_url = Set(x
-            .get_url_and_create_attachment(&guild_id, &mut files_added)
-            .await?)
-    }
+        if let Option::<String>::Some(x) = request.footer.into() {
+            model.footer = Set(x.replace("\\n", "\n"));
+        }
 
-    if update {
-        model.update(&ctx.data().db_pool).await?;
-    } else {
-        member_notification_message::Entity::insert(model)
-            .exec(&ctx.data().db_pool)
-            .await?;
-    }
+        if let Option::<EmbedAttachment>::Some(x) = request.footer_icon.into() {
+            if let Some(old_file) =
+                active_model_file_attachment(model.footer_icon_is_file, model.footer_icon_url)
+            {
+                files_to_delete.push(old_file);
+            }
 
-    // Delete old files from disk
-    if !files_to_delete.is_empty() {
-        let path = get_guild_user_content_directory(guild_id);
-        let mut errors: Vec<std::io::Error> = vec![];
-        for file in files_to_delete {
-            match tokio::fs::remove_file(path.join(file)).await {
-                Ok(_) => {}
-                Err(e) => {
-                    errors.push(e);
-                }
-            };
+            model.footer_icon_is_file = Set(x.is_file());
+            model.footer_icon_url = Set(x
+                .get_url_and_create_attachment(ctx.data(), &guild_id, &uow, &mut files_added)
+                .await?)
         }
 
-        if !errors.is_empty() {
-            let err_str = errors
-                .iter()
-                .map(|e| e.to_string())
-                .collect::<Vec<String>>()
-                .join(", ");
-            warn!(
-                "Encountered errors attempting to remove user content files: {}",
-                err_str
-            );
+        check_message_lengths(&[
+            (MessagePart::Content, active_model_text(&model.content)),
+            (MessagePart::Title, active_model_text(&model.title)),
+            (
+                MessagePart::Description,
+                active_model_text(&model.description),
+            ),
+            (MessagePart::Author, active_model_text(&model.author)),
+            (MessagePart::Footer, active_model_text(&model.footer)),
+        ])?;
+
+        let repo = NotificationRepo::new(uow.txn());
+        if update {
+            repo.update_variant(model).await?;
+        } else {
+            repo.insert_variant(model).await?;
         }
+        UserContentRepo::new(uow.txn())
+            .release(&files_to_delete)
+            .await?;
+        remove_assets(uow.txn(), guild_id, &files_to_delete).await
     }
+    .await;
+    uow.finish(result).await?;
 
-    let notification_details = crate::events::guild_member::get_member_notification_details(
-        &ctx.data().db_pool,
+    // Host new files on discord, and delete old files from disk once nothing references them
+    upload_assets(ctx.http(), &ctx.data().db_pool, guild_id, &files_added).await;
+    remove_user_content_files(ctx.data().db_pool.clone(), guild_id, files_to_delete).await;
+
+    let notification_details = crate::events::guild_member::get_member_notification_variant(
+        ctx.data(),
         &guild_id,
         is_join,
+        variant,
     )
     .await;
 
+    let shown_variant = r#type.shown_variant(variant);
+    let warnings: String = warnings.iter().map(|x| format!("\n⚠️ The {}", x)).collect();
     match notification_details {
         Some(format) => {
             ctx.send(
                 CreateReply::default()
-                    .content("Successfully configured member notification message. Below is a sample of the new format:")
+                    .content(format!("Successfully configured member notification message (variant {}). Below is a sample of the new format:{}", shown_variant, warnings))
                     .ephemeral(true),
             )
             .await?;
 
-            let guild = guild_id.to_partial_guild_with_counts(ctx).await; // TODO: this request is quite large and slow. Figure out how to more quickly retrieve the guild member count.
-            let notification_details = if !is_join {
-                crate::events::guild_member::MemberNotificationMessageDetails::for_user(
-                    ctx.author(),
-                    guild.ok(),
-                    format,
-                )
-            } else {
-                match ctx.author_member().await {
-                    Some(member) => {
-                        crate::events::guild_member::MemberNotificationMessageDetails::for_member(
-                            &member,
-                            guild.ok(),
-                            format,
-                        )
-                    }
-                    None => {
-                        crate::events::guild_member::MemberNotificationMessageDetails::for_user(
-                            ctx.author(),
-                            guild.ok(),
-                            format,
-                        )
-                    }
-                }
-            };
+            let guild = preview_guild(ctx, guild_id).await;
+            let member = ctx.author_member().await.map(|x| x.into_owned());
+            let notification_details = MemberNotificationMessageDetails::from_fmt_args(
+                r#type.preview_format_args(ctx, member.as_ref(), guild),
+                format,
+            );
 
             let reply = notification_details
                 .to_reply(&guild_id)
@@ -495,7 +1812,10 @@ async fn configure_member_notifications_impl(
         None => {
             ctx.send(
                 CreateReply::default()
-                    .content("Successfully configured member notification message")
+                    .content(format!(
+                        "Successfully configured member notification message (variant {}){}",
+                        shown_variant, warnings
+                    ))
                     .ephemeral(true),
             )
             .await?;
@@ -515,6 +1835,7 @@ macro_rules! member_cmd_impl {
                 configure_member_notifications_impl(
                     ctx,
                     Self::NOTIFICATION_TYPE,
+                    Self::NOTIFICATION_TYPE.primary_variant(),
                     NotificationManagementRequest::default().$builder_method($param_name),
                 )
                 .await
@@ -532,6 +1853,7 @@ macro_rules! member_cmd_impl {
                 configure_member_notifications_impl(
                     ctx,
                     Self::NOTIFICATION_TYPE,
+                    Self::NOTIFICATION_TYPE.primary_variant(),
                     NotificationManagementRequest::default()
                         .$builder_method($param_name_1, $param_name_2),
                 )
@@ -565,6 +1887,7 @@ pub trait MemberEventConfigurer {
             configure_member_notifications_impl(
                 ctx,
                 Self::NOTIFICATION_TYPE,
+                Self::NOTIFICATION_TYPE.primary_variant(),
                 NotificationManagementRequest::default()
                     .content(content)
                     .title(title)
@@ -580,6 +1903,70 @@ pub trait MemberEventConfigurer {
         })
     }
 
+    /// Same as [`Self::full_impl`], but creates a new variant instead of modifying the primary variant.
+    fn add_variant_impl<'a>(
+        ctx: Context<'a>,
+        content: Option<String>,
+        title: Option<String>,
+        description: Option<String>,
+        thumbnail_file: Option<serenity::Attachment>,
+        thumbnail_url: Option<String>,
+        image_file: Option<serenity::Attachment>,
+        image_url: Option<String>,
+        author: Option<String>,
+        author_icon_file: Option<serenity::Attachment>,
+        author_icon_url: Option<String>,
+        footer: Option<String>,
+        footer_icon_file: Option<serenity::Attachment>,
+        footer_icon_url: Option<String>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>> {
+        Box::pin(async move {
+            let guild_id = require_guild_id(ctx)?;
+            let variant = Self::NOTIFICATION_TYPE
+                .next_variant(&ctx.data().db_pool, guild_id)
+                .await?;
+            configure_member_notifications_impl(
+                ctx,
+                Self::NOTIFICATION_TYPE,
+                variant,
+                NotificationManagementRequest::default()
+                    .content(content)
+                    .title(title)
+                    .description(description)
+                    .thumbnail(thumbnail_file, thumbnail_url)
+                    .image(image_file, image_url)
+                    .author(author)
+                    .author_icon(author_icon_file, author_icon_url)
+                    .footer(footer)
+                    .footer_icon(footer_icon_file, footer_icon_url),
+            )
+            .await
+        })
+    }
+
+    fn remove_variant_impl<'a>(
+        ctx: Context<'a>,
+        variant: u32,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>> {
+        Box::pin(async move {
+            remove_member_notification_variant(ctx, Self::NOTIFICATION_TYPE, variant as i32).await
+        })
+    }
+
+    fn edit_impl<'a>(
+        ctx: ApplicationContext<'a>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>> {
+        Box::pin(async move { edit_member_notification_text(ctx, Self::NOTIFICATION_TYPE).await })
+    }
+
+    fn list_variants_impl<'a>(
+        ctx: Context<'a>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>> {
+        Box::pin(
+            async move { list_member_notification_variants(ctx, Self::NOTIFICATION_TYPE).await },
+        )
+    }
+
     member_cmd_impl!(content_impl, content, content);
     member_cmd_impl!(title_impl, description, title);
     member_cmd_impl!(description_impl, description, description);
@@ -0,0 +1,250 @@
+use poise::{CreateReply, serenity_prelude::GuildChannel};
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, EntityTrait, IntoActiveModel};
+
+use crate::{
+    Context, Error,
+    entities::raid_protection,
+    events::raid_protection::{
+        self as raid_detection, get_raid_protection_config, lockdown_remaining,
+    },
+    infrastructure::{
+        branding::guild_branding,
+        colors,
+        ids::{id_to_string, require_guild_id},
+    },
+    poise_instrument, record_ctx_fields,
+};
+
+/// Set of commands to configure raid detection and lockdown for this guild.
+#[poise::command(
+    slash_command,
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR",
+    guild_only,
+    category = "Management",
+    rename = "raid-protection",
+    subcommands("configure", "configure_lockdown", "status", "end_lockdown")
+)]
+pub async fn raid_protection(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Creates the raid protection configuration of a guild if needed, applies changes to it and saves it.
+async fn update_config(
+    ctx: Context<'_>,
+    apply: impl FnOnce(&mut raid_protection::ActiveModel),
+) -> Result<(), Error> {
+    let guild_id = require_guild_id(ctx)?;
+
+    let existing = raid_protection::Entity::find_by_id(id_to_string(guild_id))
+        .one(&ctx.data().db_pool)
+        .await?;
+    let (mut model, update) = match existing {
+        Some(row) => (row.into_active_model(), true),
+        None => (
+            raid_protection::ActiveModel {
+                guild_id: Set(id_to_string(guild_id)),
+                ..Default::default()
+            },
+            false,
+        ),
+    };
+
+    apply(&mut model);
+
+    if update {
+        model.update(&ctx.data().db_pool).await?;
+    } else {
+        raid_protection::Entity::insert(model)
+            .exec(&ctx.data().db_pool)
+            .await?;
+    }
+
+    ctx.send(
+        CreateReply::default()
+            .content("Successfully configured raid protection")
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+poise_instrument! {
+    /// Configures raid detection thresholds.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn configure(
+        ctx: Context<'_>,
+        #[description = "Enable raid detection"] enabled: Option<bool>,
+        #[description = "Joins within the window that trigger a lockdown (0 disables)"]
+        #[min = 0]
+        join_threshold: Option<u32>,
+        #[description = "Length of the detection window in seconds"]
+        #[min = 1]
+        window_seconds: Option<u32>,
+        #[description = "Accounts younger than this many hours are considered new"]
+        #[min = 0]
+        account_age_hours: Option<u32>,
+        #[description = "New accounts within the window that trigger a lockdown (0 disables)"]
+        #[min = 0]
+        new_account_threshold: Option<u32>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        update_config(ctx, |model| {
+            if let Some(x) = enabled {
+                model.enabled = Set(x);
+            }
+            if let Some(x) = join_threshold {
+                model.join_threshold = Set(x as i32);
+            }
+            if let Some(x) = window_seconds {
+                model.window_seconds = Set(x as i32);
+            }
+            if let Some(x) = account_age_hours {
+                model.account_age_hours = Set(x as i32);
+            }
+            if let Some(x) = new_account_threshold {
+                model.new_account_threshold = Set(x as i32);
+            }
+        })
+        .await
+    }
+
+    /// Configures how the guild is locked down when a raid is detected.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management",
+        rename = "configure-lockdown"
+    )]
+    async fn configure_lockdown(
+        ctx: Context<'_>,
+        #[description = "Raise the guild verification level during a lockdown"] raise_verification: Option<bool>,
+        #[description = "Pause default role assignment during a lockdown"] pause_default_roles: Option<bool>,
+        #[description = "Channel to send raid alerts to"] alert_channel: Option<GuildChannel>,
+        #[description = "Length of a lockdown in minutes"]
+        #[min = 1]
+        lockdown_minutes: Option<u32>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        update_config(ctx, |model| {
+            if let Some(x) = raise_verification {
+                model.raise_verification = Set(x);
+            }
+            if let Some(x) = pause_default_roles {
+                model.pause_default_roles = Set(x);
+            }
+            if let Some(x) = alert_channel {
+                model.alert_channel_id = Set(id_to_string(x.id));
+            }
+            if let Some(x) = lockdown_minutes {
+                model.lockdown_minutes = Set(x as i32);
+            }
+        })
+        .await
+    }
+
+    /// Shows the raid protection configuration and lockdown status.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn status(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+
+        let config = match get_raid_protection_config(&ctx.data().db_pool, &guild_id).await {
+            Some(x) => x,
+            None => {
+                ctx.send(
+                    CreateReply::default()
+                        .content("Raid protection is not configured on this guild")
+                        .ephemeral(true),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let lockdown = match lockdown_remaining(&ctx.data().raid_state, &guild_id) {
+            Some(remaining) => format!("Active ({} minutes remaining)", remaining.as_secs() / 60 + 1),
+            None => "Inactive".into(),
+        };
+        let alert_channel = if config.alert_channel_id.is_empty() {
+            "None".to_string()
+        } else {
+            format!("<#{}>", config.alert_channel_id)
+        };
+
+        let branding = guild_branding(ctx).await;
+        let embed = branding
+            .embed()
+            .title("Raid Protection")
+            .color(if config.enabled { colors::green() } else { branding.accent })
+            .field("Enabled", config.enabled.to_string(), true)
+            .field("Lockdown", lockdown, true)
+            .field(
+                "Join threshold",
+                format!("{} joins / {}s", config.join_threshold, config.window_seconds),
+                false,
+            )
+            .field(
+                "New account threshold",
+                format!(
+                    "{} accounts younger than {}h",
+                    config.new_account_threshold, config.account_age_hours
+                ),
+                false,
+            )
+            .field("Raise verification", config.raise_verification.to_string(), true)
+            .field("Pause default roles", config.pause_default_roles.to_string(), true)
+            .field("Lockdown length", format!("{} minutes", config.lockdown_minutes), true)
+            .field("Alert channel", alert_channel, true);
+
+        ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Ends an active raid lockdown early.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management",
+        rename = "end-lockdown"
+    )]
+    async fn end_lockdown(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let ended = raid_detection::end_lockdown(
+            ctx.serenity_context(),
+            &ctx.data().db_pool,
+            &ctx.data().raid_state,
+            guild_id,
+        )
+        .await?;
+        ctx.send(
+            CreateReply::default()
+                .content(if ended {
+                    "Lockdown ended"
+                } else {
+                    "This guild is not locked down"
+                })
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -1,19 +1,21 @@
 use poise::{
     CreateReply,
     serenity_prelude::{
-        RoleId,
+        CreateEmbed, Member, RoleId,
         futures::{self, Stream, StreamExt},
     },
 };
-use sea_orm::{ActiveValue::Set, EntityTrait};
-use tracing::debug;
+use tracing::{debug, info, warn};
 
 use crate::{
     Context, Error,
-    entities::welcome_roles,
-    events::guild_member::get_member_roles_on_join,
-    infrastructure::ids::{id_to_string, require_guild_id},
-    poise_instrument, record_ctx_fields,
+    commands::member_management::bulk_roles::{CHUNK_DELAY, CHUNK_SIZE},
+    infrastructure::{
+        branding::guild_branding,
+        ids::{id_to_string, require_guild_id},
+    },
+    log_user, poise_instrument, record_ctx_fields,
+    services::roles::RolesRepo,
 };
 
 async fn default_role_autocomplete<'a>(
@@ -30,7 +32,8 @@ async fn default_role_autocomplete<'a>(
         Err(_) => return futures::stream::empty().boxed(),
     };
 
-    let roles = get_member_roles_on_join(&ctx.data().db_pool, &guild_id)
+    let roles = RolesRepo::new(&ctx.data().db_pool)
+        .default_roles(guild_id)
         .await
         .unwrap_or_default()
         .into_iter()
@@ -39,6 +42,20 @@ async fn default_role_autocomplete<'a>(
     futures::stream::iter(roles).boxed()
 }
 
+/// Set of commands for the roles applied to new members.
+#[poise::command(
+    slash_command,
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR",
+    guild_only,
+    category = "Management",
+    rename = "default-roles",
+    subcommands("sync")
+)]
+pub async fn default_roles(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
 poise_instrument! {
     /// Adds a role that will be applied to all new members when they join.
     #[poise::command(
@@ -53,12 +70,9 @@ poise_instrument! {
         record_ctx_fields!(ctx);
         let guild_id = require_guild_id(ctx)?;
 
-        welcome_roles::Entity::insert(welcome_roles::ActiveModel {
-            guild_id: Set(id_to_string(guild_id.clone())),
-            role_id: Set(id_to_string(role.clone())),
-        })
-        .exec(&ctx.data().db_pool)
-        .await?;
+        RolesRepo::new(&ctx.data().db_pool)
+            .add_default_role(guild_id, role)
+            .await?;
 
         ctx.send(
             CreateReply::default()
@@ -95,8 +109,8 @@ poise_instrument! {
 
         match role_id {
             Some(role_id) => {
-                welcome_roles::Entity::delete_by_id((id_to_string(guild_id), id_to_string(role_id)))
-                    .exec(&ctx.data().db_pool)
+                RolesRepo::new(&ctx.data().db_pool)
+                    .remove_default_role(guild_id, role_id)
                     .await?;
 
                 ctx.send(
@@ -118,4 +132,111 @@ poise_instrument! {
 
         Ok(())
     }
+
+    /// Gives the default roles to existing members that are missing them.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        required_bot_permissions = "MANAGE_ROLES",
+        guild_only,
+        category = "Management"
+    )]
+    async fn sync(
+        ctx: Context<'_>,
+        #[description = "Include bot accounts? (default: false)"] include_bots: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let include_bots = include_bots.unwrap_or(false);
+
+        // Roles deleted since they were configured can't be applied
+        let guild_roles = guild_id.roles(ctx).await?;
+        let roles: Vec<RoleId> = RolesRepo::new(&ctx.data().db_pool)
+            .default_roles(guild_id)
+            .await?
+            .into_iter()
+            .filter(|x| guild_roles.contains_key(x))
+            .collect();
+        if roles.is_empty() {
+            return Err(
+                "No default roles are configured. Add one with `/add_default_member_role`.".into(),
+            );
+        }
+
+        let branding = guild_branding(ctx).await;
+        let progress_embed = |description: String| -> CreateEmbed {
+            branding.embed().title("Syncing default roles").description(description)
+        };
+        let handle = ctx
+            .send(
+                CreateReply::default()
+                    .embed(progress_embed("Collecting members...".to_string()))
+                    .ephemeral(true),
+            )
+            .await?;
+
+        let mut targets: Vec<(Member, Vec<RoleId>)> = vec![];
+        let mut members = guild_id.members_iter(ctx).boxed();
+        while let Some(member) = members.next().await {
+            let member = member?;
+            if member.user.bot && !include_bots {
+                continue;
+            }
+            let missing: Vec<RoleId> = roles
+                .iter()
+                .filter(|x| !member.roles.contains(x))
+                .cloned()
+                .collect();
+            if !missing.is_empty() {
+                targets.push((member, missing));
+            }
+        }
+
+        let total = targets.len();
+        info!("Default role sync on guild {} matched {} members", guild_id, total);
+
+        let mut processed = 0;
+        let mut failed = 0;
+        for chunk in targets.chunks(CHUNK_SIZE) {
+            for (member, missing) in chunk {
+                if let Err(e) = member.add_roles(ctx, missing).await {
+                    warn!(
+                        "Failed to add default roles to member {}: {:?}",
+                        log_user!(&member.user.name),
+                        e
+                    );
+                    failed += 1;
+                }
+                processed += 1;
+            }
+
+            handle
+                .edit(
+                    ctx,
+                    CreateReply::default().embed(progress_embed(format!(
+                        "Processed {}/{} members...",
+                        processed, total
+                    ))),
+                )
+                .await?;
+            if processed < total {
+                tokio::time::sleep(CHUNK_DELAY).await;
+            }
+        }
+
+        let roles: Vec<String> = roles.iter().map(|x| format!("<@&{}>", x)).collect();
+        let mut summary = if total == 0 {
+            "Every member already has the default roles.".to_string()
+        } else {
+            format!("Gave {} to {} members.", roles.join(", "), total - failed)
+        };
+        if failed > 0 {
+            summary.push_str(&format!(" Failed to update {} members.", failed));
+        }
+        handle
+            .edit(ctx, CreateReply::default().embed(progress_embed(summary)))
+            .await?;
+        Ok(())
+    }
 }
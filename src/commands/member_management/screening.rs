@@ -0,0 +1,182 @@
+use poise::{
+    CreateReply,
+    serenity_prelude::{GuildChannel, Mentionable},
+};
+
+use crate::{
+    Context, Error,
+    infrastructure::{branding::guild_branding, ids::require_guild_id},
+    poise_instrument, record_ctx_fields,
+    services::screening::{MAX_QUESTION_LENGTH, MAX_QUESTIONS, ScreeningRepo},
+};
+
+/// Set of commands to ask new members questions before they get the default roles.
+#[poise::command(
+    slash_command,
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR",
+    guild_only,
+    category = "Management",
+    subcommands("enable", "disable", "add_question", "remove_question", "show")
+)]
+pub async fn screening(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Screens new members, posting their answers to a staff channel to approve or kick them in.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn enable(
+        ctx: Context<'_>,
+        #[description = "Staff channel answers are posted to"] review_channel: GuildChannel,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let repo = ScreeningRepo::new(&ctx.data().db_pool);
+        repo.enable(guild_id, review_channel.id).await?;
+        let mut content = format!(
+            "New members are screened, and their answers are posted in {}. Default roles are given once a member \
+             is approved.",
+            review_channel.mention()
+        );
+        if repo.questions(guild_id).await?.is_empty() {
+            content.push_str("\nAdd questions with `/screening add-question` to start screening.");
+        }
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Stops screening new members, giving them the default roles when they join again.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn disable(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let disabled = ScreeningRepo::new(&ctx.data().db_pool)
+            .disable(guild_id)
+            .await?;
+        let content = if disabled {
+            "New members are no longer screened"
+        } else {
+            "Screening is not enabled on this guild"
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Adds a question new members are asked.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management",
+        rename = "add-question"
+    )]
+    async fn add_question(
+        ctx: Context<'_>,
+        #[description = "Question to ask"]
+        #[max_length = 45]
+        question: String,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let question = question.trim();
+        if question.is_empty() || question.chars().count() > MAX_QUESTION_LENGTH {
+            return Err(format!("Questions must be 1 to {} characters long", MAX_QUESTION_LENGTH).into());
+        }
+        let repo = ScreeningRepo::new(&ctx.data().db_pool);
+        if repo.questions(guild_id).await?.len() >= MAX_QUESTIONS {
+            return Err(format!("New members can be asked at most {} questions", MAX_QUESTIONS).into());
+        }
+        repo.add_question(guild_id, question).await?;
+        ctx.send(
+            CreateReply::default()
+                .content(format!("New members are asked: {}", question))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Removes a question new members are asked.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management",
+        rename = "remove-question"
+    )]
+    async fn remove_question(
+        ctx: Context<'_>,
+        #[description = "Number of the question, as shown by /screening show"]
+        #[min = 1]
+        number: usize,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let content = match ScreeningRepo::new(&ctx.data().db_pool)
+            .remove_question(guild_id, number)
+            .await?
+        {
+            Some(question) => format!("Removed question: {}", question),
+            None => format!("There is no question #{}", number),
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Shows the screening settings and questions of this guild.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn show(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let repo = ScreeningRepo::new(&ctx.data().db_pool);
+        let status = match repo.review_channel(guild_id).await? {
+            Some(channel) => format!("Answers are posted in {}", channel.mention()),
+            None => "Screening is disabled".to_string(),
+        };
+        let questions: Vec<String> = repo
+            .questions(guild_id)
+            .await?
+            .iter()
+            .enumerate()
+            .map(|(i, question)| format!("**{}.** {}", i + 1, question))
+            .collect();
+        let questions = if questions.is_empty() {
+            "No questions. Add one with `/screening add-question`.".to_string()
+        } else {
+            questions.join("\n")
+        };
+        let embed = guild_branding(ctx)
+            .await
+            .embed()
+            .title("Member screening")
+            .description(status)
+            .field("Questions", questions, false);
+        ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
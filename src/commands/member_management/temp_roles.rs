@@ -0,0 +1,281 @@
+/*!
+
+Temporary role assignment, for event roles and temporary punishments.
+
+The expiry of every temporary role is stored in the `temp_roles` table, and the
+[scheduler](crate::infrastructure::scheduler) removes roles once they expire. Assignments and removals are logged to
+the guild's mod-log channel.
+
+*/
+
+use migration::OnConflict;
+use poise::{
+    CreateReply,
+    serenity_prelude::{Member, Mentionable, Role, RoleId, User, UserId},
+};
+use sea_orm::{ActiveValue::Set, ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+use tracing::info;
+
+use crate::{
+    Context, Error,
+    entities::temp_roles,
+    infrastructure::{
+        branding::guild_branding,
+        ids::{id_from_string, id_to_string, require_guild_id},
+        mod_log::send_mod_log,
+        util::parse_duration,
+    },
+    poise_instrument, record_ctx_fields,
+};
+
+/// Temporary roles can't last longer than this.
+const MAX_DURATION_SECONDS: i64 = 60 * 60 * 24 * 365;
+
+/// Number of temporary roles shown by `/temprole list`.
+const MAX_LISTED: usize = 40;
+
+/// Set of commands to assign roles that are removed automatically after a while.
+#[poise::command(
+    slash_command,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    guild_only,
+    category = "Management",
+    subcommands("add", "remove", "list")
+)]
+pub async fn temprole(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+fn check_assignable(ctx: Context<'_>, role: &Role) -> Result<(), Error> {
+    if role.id.get() == require_guild_id(ctx)?.get() {
+        return Err("The @everyone role can't be assigned".into());
+    }
+    if role.managed {
+        return Err(format!(
+            "{} is managed by an integration and can't be assigned",
+            role.mention()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+poise_instrument! {
+    /// Gives a member a role that is removed automatically once the duration has passed.
+    #[poise::command(
+        slash_command,
+        required_permissions = "MANAGE_ROLES",
+        default_member_permissions = "MANAGE_ROLES",
+        required_bot_permissions = "MANAGE_ROLES",
+        guild_only,
+        category = "Management"
+    )]
+    async fn add(
+        ctx: Context<'_>,
+        #[description = "Member to give the role to"] user: Member,
+        #[description = "Role to give"] role: Role,
+        #[description = "How long the member keeps the role, such as 30m, 2h or 7d"] duration: String,
+        #[description = "Reason, shown in the mod-log"] reason: Option<String>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        check_assignable(ctx, &role)?;
+        let seconds = parse_duration(&duration)
+            .ok_or_else(|| format!("Invalid duration '{}', expected e.g. 30m, 2h or 7d", duration))?;
+        if seconds > MAX_DURATION_SECONDS {
+            return Err("Temporary roles can last at most a year".into());
+        }
+        let expires_at = ctx.data().clock.unix_timestamp() + seconds;
+
+        let existing = temp_roles::Entity::find_by_id((
+            id_to_string(guild_id),
+            id_to_string(user.user.id),
+            id_to_string(role.id),
+        ))
+        .one(&ctx.data().db_pool)
+        .await?;
+        if existing.is_none() && user.roles.contains(&role.id) {
+            return Err(format!(
+                "{} already has {} permanently",
+                user.mention(),
+                role.mention()
+            )
+            .into());
+        }
+
+        let audit_reason = format!("Temporary role assigned by {}", ctx.author().name);
+        if !user.roles.contains(&role.id) {
+            ctx.http()
+                .add_member_role(guild_id, user.user.id, role.id, Some(&audit_reason))
+                .await?;
+        }
+        temp_roles::Entity::insert(temp_roles::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            user_id: Set(id_to_string(user.user.id)),
+            role_id: Set(id_to_string(role.id)),
+            assigned_by: Set(id_to_string(ctx.author().id)),
+            reason: Set(reason.clone()),
+            expires_at: Set(expires_at),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                temp_roles::Column::GuildId,
+                temp_roles::Column::UserId,
+                temp_roles::Column::RoleId,
+            ])
+            .update_columns([
+                temp_roles::Column::AssignedBy,
+                temp_roles::Column::Reason,
+                temp_roles::Column::ExpiresAt,
+            ])
+            .to_owned(),
+        )
+        .exec(&ctx.data().db_pool)
+        .await?;
+        info!(
+            "Temporary role {} given to member {} on guild {} until {}",
+            role.id, user.user.id, guild_id, expires_at
+        );
+
+        let verb = if existing.is_some() { "Extended" } else { "Gave" };
+        let summary = format!(
+            "{} {} to {} until <t:{}:f> (<t:{}:R>)",
+            verb,
+            role.mention(),
+            user.mention(),
+            expires_at,
+            expires_at
+        );
+        let mut embed = guild_branding(ctx)
+            .await
+            .embed()
+            .title("Temporary role assigned")
+            .description(&summary)
+            .field("Moderator", ctx.author().mention().to_string(), true);
+        if let Some(reason) = &reason {
+            embed = embed.field("Reason", reason, true);
+        }
+        send_mod_log(ctx.http(), &ctx.data().db_pool, guild_id, embed).await;
+
+        ctx.send(CreateReply::default().content(summary).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Removes a temporary role before it expires.
+    #[poise::command(
+        slash_command,
+        required_permissions = "MANAGE_ROLES",
+        default_member_permissions = "MANAGE_ROLES",
+        required_bot_permissions = "MANAGE_ROLES",
+        guild_only,
+        category = "Management"
+    )]
+    async fn remove(
+        ctx: Context<'_>,
+        #[description = "Member to remove the role from"] user: User,
+        #[description = "Temporary role to remove"] role: Role,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let id = (
+            id_to_string(guild_id),
+            id_to_string(user.id),
+            id_to_string(role.id),
+        );
+        if temp_roles::Entity::find_by_id(id.clone())
+            .one(&ctx.data().db_pool)
+            .await?
+            .is_none()
+        {
+            return Err(format!(
+                "{} doesn't have {} as a temporary role",
+                user.mention(),
+                role.mention()
+            )
+            .into());
+        }
+
+        let audit_reason = format!("Temporary role removed by {}", ctx.author().name);
+        ctx.http()
+            .remove_member_role(guild_id, user.id, role.id, Some(&audit_reason))
+            .await?;
+        temp_roles::Entity::delete_by_id(id)
+            .exec(&ctx.data().db_pool)
+            .await?;
+
+        let summary = format!("Removed {} from {}", role.mention(), user.mention());
+        let embed = guild_branding(ctx)
+            .await
+            .embed()
+            .title("Temporary role removed early")
+            .description(&summary)
+            .field("Moderator", ctx.author().mention().to_string(), true);
+        send_mod_log(ctx.http(), &ctx.data().db_pool, guild_id, embed).await;
+
+        ctx.send(CreateReply::default().content(summary).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Lists the temporary roles of this guild, soonest to expire first.
+    #[poise::command(
+        slash_command,
+        required_permissions = "MANAGE_ROLES",
+        default_member_permissions = "MANAGE_ROLES",
+        guild_only,
+        category = "Management"
+    )]
+    async fn list(
+        ctx: Context<'_>,
+        #[description = "Only show the temporary roles of this member"] user: Option<User>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let mut query = temp_roles::Entity::find()
+            .filter(temp_roles::Column::GuildId.eq(id_to_string(guild_id)));
+        if let Some(user) = &user {
+            query = query.filter(temp_roles::Column::UserId.eq(id_to_string(user.id)));
+        }
+        let temp_roles = query
+            .order_by_asc(temp_roles::Column::ExpiresAt)
+            .all(&ctx.data().db_pool)
+            .await?;
+        if temp_roles.is_empty() {
+            ctx.send(
+                CreateReply::default()
+                    .content("There are no temporary roles")
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let mut lines: Vec<String> = temp_roles
+            .iter()
+            .take(MAX_LISTED)
+            .filter_map(|x| {
+                let user_id = id_from_string::<UserId>(&x.user_id).ok()?;
+                let role_id = id_from_string::<RoleId>(&x.role_id).ok()?;
+                Some(format!(
+                    "{} – {} – expires <t:{}:R>",
+                    user_id.mention(),
+                    role_id.mention(),
+                    x.expires_at
+                ))
+            })
+            .collect();
+        if temp_roles.len() > MAX_LISTED {
+            lines.push(format!("…and {} more", temp_roles.len() - MAX_LISTED));
+        }
+        let embed = guild_branding(ctx)
+            .await
+            .embed()
+            .title("Temporary roles")
+            .description(lines.join("\n"));
+        ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
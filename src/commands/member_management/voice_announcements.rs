@@ -0,0 +1,234 @@
+use poise::{
+    CreateReply,
+    serenity_prelude::{ChannelType, GuildChannel, Mentionable},
+};
+
+use crate::{
+    Context, Error,
+    events::voice_announcements::{
+        DEFAULT_COALESCE_SECONDS, DEFAULT_JOIN_TEMPLATE, DEFAULT_LEAVE_TEMPLATE,
+        MAX_COALESCE_SECONDS, VoiceAnnouncementSettings, guild_voice_announcements,
+        load_voice_announcement, remove_voice_announcement, set_voice_announcement,
+        validate_template,
+    },
+    infrastructure::{branding::guild_branding, ids::require_guild_id},
+    poise_instrument, record_ctx_fields,
+};
+
+/// Voice channels a guild may announce the members of.
+const MAX_ANNOUNCED_CHANNELS: usize = 8;
+
+/// Reads a template option: None keeps the current template, '-' turns the announcement off.
+fn template_option(
+    value: Option<String>,
+    current: Option<&str>,
+    default: &str,
+) -> Result<String, Error> {
+    let template = match value.as_deref().map(str::trim) {
+        Some("-") => return Ok(String::new()),
+        Some(x) => x.to_string(),
+        None => return Ok(current.unwrap_or(default).to_string()),
+    };
+    validate_template(&template)?;
+    Ok(template)
+}
+
+/// Set of commands to announce in a text channel when members join or leave a voice channel.
+#[poise::command(
+    slash_command,
+    required_permissions = "MANAGE_CHANNELS",
+    default_member_permissions = "MANAGE_CHANNELS",
+    guild_only,
+    category = "Management",
+    rename = "voice-announce",
+    subcommands("enable", "disable", "list")
+)]
+pub async fn voice_announce(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Announces members joining and leaving a voice channel, gathering changes a few seconds apart.
+    ///
+    /// Templates may use `{name}`, `{mention}` and `{channel}`. Mentions are shown without pinging anyone.
+    #[poise::command(
+        slash_command,
+        required_permissions = "MANAGE_CHANNELS",
+        default_member_permissions = "MANAGE_CHANNELS",
+        guild_only,
+        category = "Management"
+    )]
+    async fn enable(
+        ctx: Context<'_>,
+        #[description = "Voice channel to announce the members of"]
+        #[channel_types("Voice", "Stage")]
+        channel: GuildChannel,
+        #[description = "Channel to post the announcements in"]
+        #[channel_types("Text", "News", "Voice")]
+        #[rename = "text-channel"]
+        text_channel: GuildChannel,
+        #[description = "Message when someone joins, with {name}, {mention} or {channel} ('-' for none)"]
+        #[rename = "join-message"]
+        join_message: Option<String>,
+        #[description = "Message when someone leaves, with {name}, {mention} or {channel} ('-' for none)"]
+        #[rename = "leave-message"]
+        leave_message: Option<String>,
+        #[description = "Seconds changes are gathered before they are posted together (default: 10)"]
+        #[min = 0]
+        #[max = 300]
+        coalesce: Option<u32>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        if channel.guild_id != guild_id
+            || !matches!(channel.kind, ChannelType::Voice | ChannelType::Stage)
+        {
+            return Err(
+                "Announcements can only track voice or stage channels of this server".into(),
+            );
+        }
+        if text_channel.guild_id != guild_id
+            || !matches!(
+                text_channel.kind,
+                ChannelType::Text | ChannelType::News | ChannelType::Voice
+            )
+        {
+            return Err("Announcements can only be posted to text, announcement or voice \
+                channels of this server"
+                .into());
+        }
+
+        let db = &ctx.data().db_pool;
+        let current = load_voice_announcement(db, channel.id).await?;
+        if current.is_none()
+            && guild_voice_announcements(db, guild_id).await?.len() >= MAX_ANNOUNCED_CHANNELS
+        {
+            return Err(format!(
+                "At most {} voice channels can be announced, disable one first",
+                MAX_ANNOUNCED_CHANNELS
+            )
+            .into());
+        }
+        let settings = VoiceAnnouncementSettings {
+            text_channel_id: text_channel.id,
+            join_template: template_option(
+                join_message,
+                current.as_ref().map(|x| x.join_template.as_str()),
+                DEFAULT_JOIN_TEMPLATE,
+            )?,
+            leave_template: template_option(
+                leave_message,
+                current.as_ref().map(|x| x.leave_template.as_str()),
+                DEFAULT_LEAVE_TEMPLATE,
+            )?,
+            coalesce_seconds: coalesce
+                .or(current.as_ref().map(|x| x.coalesce_seconds))
+                .unwrap_or(DEFAULT_COALESCE_SECONDS)
+                .min(MAX_COALESCE_SECONDS),
+        };
+        if settings.join_template.is_empty() && settings.leave_template.is_empty() {
+            return Err(
+                "Announce joins, leaves or both, or use `/voice-announce disable` instead".into(),
+            );
+        }
+        set_voice_announcement(
+            db,
+            &ctx.data().voice_announcements,
+            guild_id,
+            channel.id,
+            settings.clone(),
+        )
+        .await?;
+        ctx.send(
+            CreateReply::default()
+                .content(format!(
+                    "Members joining and leaving {} are now announced in {}, gathered over {}s.",
+                    channel.mention(),
+                    text_channel.mention(),
+                    settings.coalesce_seconds
+                ))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Stops announcing members joining and leaving a voice channel.
+    #[poise::command(
+        slash_command,
+        required_permissions = "MANAGE_CHANNELS",
+        default_member_permissions = "MANAGE_CHANNELS",
+        guild_only,
+        category = "Management"
+    )]
+    async fn disable(
+        ctx: Context<'_>,
+        #[description = "Voice channel to stop announcing the members of"]
+        #[channel_types("Voice", "Stage")]
+        channel: GuildChannel,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        if channel.guild_id != guild_id {
+            return Err("That channel is not part of this server".into());
+        }
+        let removed = remove_voice_announcement(
+            &ctx.data().db_pool,
+            &ctx.data().voice_announcements,
+            channel.id,
+        )
+        .await?;
+        let content = if removed {
+            format!("Members of {} are no longer announced", channel.mention())
+        } else {
+            format!("Members of {} are not announced", channel.mention())
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Lists the voice channels whose members are announced, and where.
+    #[poise::command(
+        slash_command,
+        required_permissions = "MANAGE_CHANNELS",
+        default_member_permissions = "MANAGE_CHANNELS",
+        guild_only,
+        category = "Management"
+    )]
+    async fn list(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let channels = guild_voice_announcements(&ctx.data().db_pool, guild_id).await?;
+        let description = if channels.is_empty() {
+            "No voice channels are announced. Start with `/voice-announce enable`.".to_string()
+        } else {
+            channels
+                .iter()
+                .map(|(channel, x)| {
+                    let template = |x: &str| match x {
+                        "" => "*none*".to_string(),
+                        x => format!("`{}`", x),
+                    };
+                    format!(
+                        "{} → {}, gathered over {}s\nJoin: {}\nLeave: {}",
+                        channel.mention(),
+                        x.text_channel_id.mention(),
+                        x.coalesce_seconds,
+                        template(&x.join_template),
+                        template(&x.leave_template)
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join("\n\n")
+        };
+        let embed = guild_branding(ctx)
+            .await
+            .embed()
+            .title("Voice announcements")
+            .description(description);
+        ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
@@ -1,15 +1,32 @@
+use std::collections::HashSet;
+use std::path::Path;
+
 use async_minecraft_ping::{ConnectionConfig, ServerError, StatusResponse};
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
 use poise::CreateReply;
 use poise::serenity_prelude::futures::{self, Stream, StreamExt};
 use poise::serenity_prelude::{self as serenity};
+use poise::serenity_prelude::{GuildId, Mentionable, RoleId, UserId};
 use sea_orm::ActiveValue::Set;
-use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
 use tracing::{debug, info, trace};
 
-use crate::entities::mc_server;
+use crate::entities::{mc_server, mc_server_samples};
+use crate::infrastructure::botdata::Data;
+use crate::infrastructure::branding::{Branding, get_branding, guild_branding};
 use crate::infrastructure::colors;
-use crate::infrastructure::ids::{id_to_string, require_guild_id};
-use crate::infrastructure::util::{DebuggableReply, defer_or_broadcast};
+use crate::infrastructure::ids::{id_from_string, id_to_string, require_guild_id};
+use crate::infrastructure::mc_format;
+use crate::infrastructure::mc_monitor::{self, SAMPLE_INTERVAL};
+use crate::infrastructure::modpacks::{self, ModpackMetadata};
+use crate::infrastructure::prefix_args::PrefixArgs;
+use crate::infrastructure::util::{
+    DebuggableReply, Paginator, defer_or_broadcast, resolve_ephemeral,
+};
+use crate::services::mc_servers::McServerRepo;
+use crate::services::unit_of_work::UnitOfWork;
 use crate::{Context, Error, poise_instrument, record_ctx_fields};
 
 #[tracing::instrument(level = 1, ret, err, skip(config))]
@@ -21,28 +38,75 @@ async fn ping_mc_server(
     Ok(response.status)
 }
 
+/// Maximum number of choices discord shows for an autocompleted parameter.
+pub(crate) const MAX_AUTOCOMPLETE_CHOICES: usize = 25;
+
+/// Maximum length of the label of an autocomplete choice.
+const MAX_CHOICE_LABEL_LENGTH: usize = 100;
+
+/// Ranks server names by how well they fuzzy match `partial`, ignoring case, along with the indices of the matched
+/// characters. Names that don't match are left out, and every name matches an empty `partial` in alphabetical order.
+pub(crate) fn rank_server_names(names: Vec<String>, partial: &str) -> Vec<(String, Vec<usize>)> {
+    let matcher = SkimMatcherV2::default().ignore_case();
+    let mut ranked: Vec<(i64, String, Vec<usize>)> = names
+        .into_iter()
+        .filter_map(|name| {
+            let (score, indices) = matcher.fuzzy_indices(&name, partial)?;
+            Some((score, name, indices))
+        })
+        .collect();
+    // Stable, so names with the same score stay in alphabetical order
+    ranked.sort_by_key(|(score, _, _)| std::cmp::Reverse(*score));
+    ranked
+        .into_iter()
+        .map(|(_, name, indices)| (name, indices))
+        .collect()
+}
+
+/// Wraps the runs of matched characters of a name in brackets, e.g. `[surv]ival`.
+pub(crate) fn highlight_matches(name: &str, indices: &[usize]) -> String {
+    let mut label = String::new();
+    let mut in_match = false;
+    for (i, c) in name.chars().enumerate() {
+        let matched = indices.contains(&i);
+        if matched != in_match {
+            label.push(if matched { '[' } else { ']' });
+            in_match = matched;
+        }
+        label.push(c);
+    }
+    if in_match {
+        label.push(']');
+    }
+    if label.chars().count() > MAX_CHOICE_LABEL_LENGTH {
+        name.to_string()
+    } else {
+        label
+    }
+}
+
 #[tracing::instrument(level = tracing::Level::TRACE, skip(ctx))]
 async fn mcserver_autocomplete<'a>(
     ctx: Context<'_>,
     partial: &'a str,
-) -> impl Stream<Item = String> + 'a {
+) -> impl Stream<Item = serenity::AutocompleteChoice> + 'a {
     // Get guild id
     let guild_id = match require_guild_id(ctx) {
         Ok(id) => id,
         Err(_) => return futures::stream::empty().boxed(),
     };
 
-    let result: Vec<String> = mc_server::Entity::find()
-        .select_only()
-        .column(mc_server::Column::Name)
-        .filter(mc_server::Column::GuildId.eq(id_to_string(guild_id)))
-        .filter(mc_server::Column::Name.starts_with(partial))
-        .order_by_asc(mc_server::Column::Name)
-        .limit(10)
-        .into_tuple()
-        .all(&ctx.data().db_pool)
+    let names = McServerRepo::new(&ctx.data().db_pool)
+        .names(guild_id)
         .await
         .unwrap_or_default();
+    let result: Vec<serenity::AutocompleteChoice> = rank_server_names(names, partial)
+        .into_iter()
+        .take(MAX_AUTOCOMPLETE_CHOICES)
+        .map(|(name, indices)| {
+            serenity::AutocompleteChoice::new(highlight_matches(&name, &indices), name)
+        })
+        .collect();
     trace!("Produced autocomplete values: {:?}", result);
     futures::stream::iter(result).boxed()
 }
@@ -54,12 +118,153 @@ async fn mcserver_autocomplete<'a>(
     track_edits,
     track_deletion,
     guild_only,
-    subcommands("status", "remove", "add", "update")
+    subcommands(
+        "status",
+        "overview",
+        "stats",
+        "remove",
+        "add",
+        // Prefix commands are looked up by name or alias in this order, so `!mc update` reaches the prefix
+        // variant first. Slash commands are looked up by name only and reach `update`.
+        "update_prefix",
+        "update",
+        "import",
+        "export",
+        "manager_role",
+        "status_channel"
+    )
 )]
 pub async fn mc(_ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
+/// Prefix of the custom_id for the "Refresh" button on status embeds. The id of the server follows the prefix, since
+/// names can be too long for the 100 characters of a custom_id.
+pub const REFRESH_STATUS_ID_PREFIX: &str = "mc_status_refresh:";
+
+async fn ping_address(address: &str, port: Option<u16>) -> Result<StatusResponse, ServerError> {
+    let mut connection = ConnectionConfig::build(address).with_srv_lookup();
+    if let Some(port) = port {
+        connection = connection.with_port(port);
+    }
+    ping_mc_server(connection).await
+}
+
+/// Pings an advertised server, used by the monitoring task to sample player counts.
+pub(crate) async fn ping_registered_server(
+    server: mc_server::Model,
+) -> Result<StatusResponse, ServerError> {
+    let server_info = McServerResult::from(server);
+    ping_address(&server_info.address, server_info.port).await
+}
+
+/// Pings the server and builds the status embed shown by `/mc status`. With `with_favicon`, servers without a custom
+/// thumbnail get their icon as the thumbnail, which must be sent along the embed as an attachment.
+async fn build_status_embed(
+    name: &str,
+    server_info: McServerResult,
+    branding: &Branding,
+    with_favicon: bool,
+) -> (serenity::CreateEmbed, Option<serenity::CreateAttachment>) {
+    let status_result = ping_address(&server_info.address, server_info.port).await;
+
+    let mut embed = branding.embed().title(format!("{} Server Status", name));
+    if let Some(port) = server_info.port {
+        embed = embed.field(
+            "Address",
+            format!("{}:{}", &server_info.address, port),
+            false,
+        );
+    } else {
+        embed = embed.field("Address", &server_info.address, false);
+    }
+
+    if let Some(version) = server_info.version {
+        embed = embed.field("Version", version, false);
+    }
+
+    if let Some(modpack) = server_info.modpack {
+        match server_info.modpack_metadata {
+            Some(metadata) => {
+                let mut value = format!("[{}]({})", metadata.name, modpack);
+                if !metadata.version.is_empty() {
+                    value.push_str(&format!("\nLatest version: `{}`", metadata.version));
+                }
+                if let Ok(Some(link)) = modpacks::parse_link(&modpack) {
+                    value.push_str(&format!("\n-# On {}", link.source.as_str()));
+                }
+                embed = embed.field("Modpack", value, false);
+                if !metadata.icon_url.is_empty() {
+                    embed = embed.author(
+                        serenity::CreateEmbedAuthor::new(metadata.name)
+                            .url(modpack)
+                            .icon_url(metadata.icon_url),
+                    );
+                }
+            }
+            None => embed = embed.field("Modpack", modpack, false),
+        }
+    }
+
+    if let Some(instructions) = server_info.instructions {
+        embed = embed.field("Instructions", instructions, false);
+    }
+
+    let has_thumbnail = server_info.thumbnail.is_some();
+    if let Some(thumbnail) = server_info.thumbnail {
+        embed = embed.thumbnail(thumbnail);
+    }
+
+    let mut favicon = None;
+    if let Ok(ref status) = status_result {
+        let description = if let Some(s) = server_info.custom_description {
+            s
+        } else {
+            mc_format::formatted_to_ansi(match status.description {
+                async_minecraft_ping::ServerDescription::Plain(ref text) => text,
+                async_minecraft_ping::ServerDescription::Object { ref text } => text,
+            })
+        };
+        if with_favicon && !has_thumbnail {
+            favicon = status
+                .favicon
+                .as_deref()
+                .and_then(mc_format::decode_favicon)
+                .map(|data| serenity::CreateAttachment::bytes(data, mc_format::FAVICON_FILENAME));
+            if favicon.is_some() {
+                embed = embed.thumbnail(format!("attachment://{}", mc_format::FAVICON_FILENAME));
+            }
+        }
+        embed = embed
+            .color(colors::green())
+            .description(description)
+            .field("Status", "Online", false)
+            .field(
+                "Players Online",
+                format!("{}/{}", status.players.online, status.players.max),
+                false,
+            );
+    } else {
+        if let Some(description) = server_info.custom_description {
+            embed = embed.description(description);
+        }
+
+        embed = embed.color(colors::red()).field("Status", "Offline", false);
+        info!("Minecraft serer '{}' is offline.", name);
+    }
+
+    (embed, favicon)
+}
+
+fn status_components(id: i64) -> Vec<serenity::CreateActionRow> {
+    vec![serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(format!("{}{}", REFRESH_STATUS_ID_PREFIX, id))
+            .label("Refresh")
+            .emoji('🔄')
+            .style(serenity::ButtonStyle::Secondary),
+    ])]
+}
+
 poise_instrument! {
     /// Gets the status of a minecraft server advertised on this guild.
     #[poise::command(slash_command, prefix_command, track_edits, track_deletion, guild_only)]
@@ -72,96 +277,296 @@ poise_instrument! {
     ) -> Result<(), Error> {
         record_ctx_fields!(ctx);
 
-        let ephemeral_resolved = ephemeral.unwrap_or(true);
+        let ephemeral_resolved = resolve_ephemeral(ctx, ephemeral, true).await;
         let _typing = defer_or_broadcast(ctx, ephemeral_resolved).await?;
 
-        let optional_server_info = get_mcserver(ctx, &name).await?;
+        let guild_id = require_guild_id(ctx)?;
+        let optional_server_info = find_mcserver_for_status(ctx.data(), guild_id, &name).await?;
         debug!("Found server info {:?}", optional_server_info);
 
         if let Some(server_info) = optional_server_info {
-            let mut connection = ConnectionConfig::build(&server_info.address).with_srv_lookup();
-            if let Some(port) = server_info.port {
-                connection = connection.with_port(port);
-            }
-            let status_result = ping_mc_server(connection).await;
-
-            let mut embed = serenity::CreateEmbed::new().title(format!("{} Server Status", &name));
-            if let Some(port) = server_info.port {
-                embed = embed.field(
-                    "Address",
-                    format!("{}:{}", &server_info.address, port),
-                    false,
-                );
-            } else {
-                embed = embed.field("Address", &server_info.address, false);
+            let branding = guild_branding(ctx).await;
+            let components = status_components(server_info.id);
+            let (embed, favicon) = build_status_embed(&name, server_info, &branding, true).await;
+            let mut reply = CreateReply::default()
+                .embed(embed)
+                .components(components)
+                .ephemeral(ephemeral_resolved);
+            if let Some(favicon) = favicon {
+                reply = reply.attachment(favicon);
             }
+            trace!("Sending reply: {:?}", DebuggableReply::new(&reply));
+            ctx.send(reply).await?;
+            Ok(())
+        } else {
+            info!("Minecraft server '{}' not found.", name);
+            return Err(format!("Minecraft server '{}' not found.", name).into());
+        }
+    }
+}
 
-            if let Some(version) = server_info.version {
-                embed = embed.field("Version", version, false);
-            }
+poise_instrument! {
+    /// Gets the status of every minecraft server advertised on this guild.
+    #[poise::command(slash_command, prefix_command, guild_only)]
+    async fn overview(
+        ctx: Context<'_>,
+        #[description = "Visible to you only? (default: true)"] ephemeral: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
 
-            if let Some(modpack) = server_info.modpack {
-                embed = embed.field("Modpack", modpack, false);
-            }
+        let ephemeral_resolved = resolve_ephemeral(ctx, ephemeral, true).await;
+        let _typing = defer_or_broadcast(ctx, ephemeral_resolved).await?;
+
+        let guild_id = require_guild_id(ctx)?;
+        let servers = McServerRepo::new(&ctx.data().db_pool).list(guild_id).await?;
+        if servers.is_empty() {
+            return Err("No minecraft servers are advertised on this guild.".into());
+        }
 
-            if let Some(instructions) = server_info.instructions {
-                embed = embed.field("Instructions", instructions, false);
+        let branding = guild_branding(ctx).await;
+        let now = ctx.data().clock.unix_timestamp();
+        let pages = futures::future::join_all(servers.into_iter().map(|server| {
+            let branding = &branding;
+            async move {
+                let server = modpacks::refresh_metadata(&ctx.data().db_pool, server, now).await;
+                let name = server.name.clone();
+                // Pages are swapped by editing the message, which can't carry the attachment of each page
+                build_status_embed(&name, server.into(), branding, false).await.0
             }
+        }))
+        .await;
+        Paginator::new(pages)
+            .ephemeral(ephemeral_resolved)
+            .send(ctx)
+            .await
+    }
+}
+
+#[derive(Debug, poise::ChoiceParameter, Clone, Copy)]
+enum StatsRange {
+    #[name = "24 hours"]
+    Day,
+    #[name = "7 days"]
+    Week,
+}
+
+impl StatsRange {
+    fn seconds(&self) -> i64 {
+        match self {
+            StatsRange::Day => 60 * 60 * 24,
+            StatsRange::Week => 60 * 60 * 24 * 7,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            StatsRange::Day => "24 hours",
+            StatsRange::Week => "7 days",
+        }
+    }
+}
+
+const CHART_SIZE: (u32, u32) = (800, 300);
+
+/// Renders the player count of a server as a PNG line chart, shading the periods where the server was offline.
+///
+/// Text needs system fonts that aren't available in the container, so the chart has no labels. The embed it is
+/// attached to describes the axes instead.
+fn render_player_chart(
+    samples: &[mc_server_samples::Model],
+    since: i64,
+    until: i64,
+    y_step: i32,
+    y_max: i32,
+) -> Result<Vec<u8>, Error> {
+    use plotters::prelude::*;
 
-            if let Some(thumbnail) = server_info.thumbnail {
-                embed = embed.thumbnail(thumbnail);
+    let (width, height) = CHART_SIZE;
+    let mut buffer = vec![0u8; (width * height * 3) as usize];
+    let interval = SAMPLE_INTERVAL.as_secs() as i64;
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, CHART_SIZE).into_drawing_area();
+        root.fill(&WHITE)?;
+        let mut chart = ChartBuilder::on(&root)
+            .margin(10)
+            .build_cartesian_2d(since..until, 0..y_max)?;
+
+        for y in (0..=y_max).step_by(y_step as usize) {
+            chart.draw_series(LineSeries::new([(since, y), (until, y)], BLACK.mix(0.15)))?;
+        }
+
+        chart.draw_series(samples.iter().filter(|x| !x.online).map(|x| {
+            Rectangle::new(
+                [(x.sampled_at, 0), (x.sampled_at + interval, y_max)],
+                RED.mix(0.25).filled(),
+            )
+        }))?;
+
+        // Consecutive online samples form one line, so gaps in the data aren't drawn over.
+        let mut segment: Vec<(i64, i32)> = vec![];
+        for sample in samples {
+            let connected = segment
+                .last()
+                .is_some_and(|(t, _)| sample.sampled_at - t <= interval * 2);
+            if !sample.online || !connected {
+                if segment.len() > 1 {
+                    chart.draw_series(LineSeries::new(segment.drain(..), BLUE.stroke_width(2)))?;
+                }
+                segment.clear();
+            }
+            if sample.online {
+                segment.push((sample.sampled_at, sample.players_online));
             }
+        }
+        if segment.len() > 1 {
+            chart.draw_series(LineSeries::new(segment, BLUE.stroke_width(2)))?;
+        }
+        root.present()?;
+    }
 
-            if let Ok(ref status) = status_result {
-                let description = if let Some(s) = server_info.custom_description {
-                    s
-                } else {
-                    match status.description {
-                        async_minecraft_ping::ServerDescription::Plain(ref text) => text,
-                        async_minecraft_ping::ServerDescription::Object { ref text } => text,
-                    }
-                    .clone()
-                };
-                embed = embed
-                    .color(colors::green())
-                    .description(description)
-                    .field("Status", "Online", false)
-                    .field(
-                        "Players Online",
-                        format!("{}/{}", status.players.online, status.players.max),
-                        false,
-                    );
+    let image = image::RgbImage::from_raw(width, height, buffer)
+        .ok_or::<Error>("Failed to create chart image".into())?;
+    let mut png = std::io::Cursor::new(vec![]);
+    image.write_to(&mut png, image::ImageOutputFormat::Png)?;
+    Ok(png.into_inner())
+}
+
+poise_instrument! {
+    /// Shows the player count history and uptime of a minecraft server advertised on this guild.
+    #[poise::command(slash_command, prefix_command, guild_only)]
+    async fn stats(
+        ctx: Context<'_>,
+        #[description = "Server Name"]
+        #[autocomplete = "mcserver_autocomplete"]
+        name: String,
+        #[description = "Time range (default: 24 hours)"] range: Option<StatsRange>,
+        #[description = "Visible to you only? (default: true)"] ephemeral: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+
+        let ephemeral_resolved = resolve_ephemeral(ctx, ephemeral, true).await;
+        let _typing = defer_or_broadcast(ctx, ephemeral_resolved).await?;
+
+        if get_mcserver(ctx, &name).await?.is_none() {
+            return Err(format!("Minecraft server '{}' not found.", name).into());
+        }
+
+        let range = range.unwrap_or(StatsRange::Day);
+        let until = ctx.data().clock.unix_timestamp();
+        let since = until - range.seconds();
+        let guild_id = require_guild_id(ctx)?;
+        let samples = mc_monitor::get_samples(&ctx.data().db_pool, guild_id, &name, since).await?;
+        if samples.is_empty() {
+            return Err(format!("No samples have been recorded for server '{}' yet.", name).into());
+        }
+
+        let online = samples.iter().filter(|x| x.online).count();
+        let uptime = online as f64 / samples.len() as f64 * 100.0;
+        let peak = samples.iter().map(|x| x.players_online).max().unwrap_or(0);
+        let y_step = (peak / 4).max(1);
+        let y_max = (peak / y_step + 1) * y_step;
+        let chart = render_player_chart(&samples, since, until, y_step, y_max)?;
+        let attachment = serenity::CreateAttachment::bytes(chart, "player_count.png");
+
+        let branding = guild_branding(ctx).await;
+        let embed = branding
+            .embed()
+            .title(format!("{} Server Stats", name))
+            .description(format!(
+                "Players online over the last {}. Gridlines are {} players apart, up to {} players. \
+                Red areas mark downtime.",
+                range.as_str(),
+                y_step,
+                y_max
+            ))
+            .color(if uptime >= 99.0 {
+                colors::green()
             } else {
-                if let Some(description) = server_info.custom_description {
-                    embed = embed.description(description);
-                }
+                branding.accent
+            })
+            .field("Uptime", format!("{:.1}%", uptime), true)
+            .field("Peak players", peak.to_string(), true)
+            .image(format!("attachment://{}", attachment.filename));
 
-                embed = embed.color(colors::red()).field("Status", "Offline", false);
-                info!("Minecraft serer '{}' is offline.", name);
-            }
+        let reply = CreateReply::default()
+            .embed(embed)
+            .attachment(attachment)
+            .ephemeral(ephemeral_resolved);
+        trace!("Sending reply: {:?}", DebuggableReply::new(&reply));
+        ctx.send(reply).await?;
+        Ok(())
+    }
+}
 
-            let reply = CreateReply::default()
+/// Handles the "Refresh" button on a status embed by re-pinging the server and editing the embed in place.
+#[tracing::instrument(level = tracing::Level::INFO, err(level = tracing::Level::WARN), skip(ctx, data, interaction))]
+pub async fn refresh_status(
+    ctx: &serenity::Context,
+    data: &Data,
+    interaction: &serenity::ComponentInteraction,
+    server_id: &str,
+) -> Result<(), Error> {
+    let guild_id = interaction
+        .guild_id
+        .ok_or("This function is only available in guilds")?;
+
+    // Pinging may exceed the interaction response window, so acknowledge first and edit afterwards.
+    interaction
+        .create_response(ctx, serenity::CreateInteractionResponse::Acknowledge)
+        .await?;
+
+    // Buttons sent before servers had ids carry the name of the server instead
+    let name = match server_id.parse::<i64>() {
+        Ok(id) => McServerRepo::new(&data.db_pool)
+            .find_by_server_id(guild_id, id)
+            .await?
+            .map(|x| x.name),
+        Err(_) => Some(server_id.to_string()),
+    };
+    let server_info = match &name {
+        Some(name) => find_mcserver_for_status(data, guild_id, name).await?,
+        None => None,
+    };
+    let name = name.unwrap_or_else(|| "Minecraft".to_string());
+
+    match server_info {
+        Some(server_info) => {
+            let branding = get_branding(&data.db_pool, &data.branding, guild_id).await;
+            let components = status_components(server_info.id);
+            let (embed, favicon) = build_status_embed(&name, server_info, &branding, true).await;
+            // Replaces the icon of the previous status, which the server may have changed or dropped since
+            let mut response = serenity::EditInteractionResponse::new()
                 .embed(embed)
-                .ephemeral(ephemeral_resolved);
-            trace!("Sending reply: {:?}", DebuggableReply::new(&reply));
-            ctx.send(reply).await?;
-            Ok(())
-        } else {
-            info!("Minecraft server '{}' not found.", name);
-            return Err(format!("Minecraft server '{}' not found.", name).into());
+                .components(components)
+                .clear_attachments();
+            if let Some(favicon) = favicon {
+                response = response.new_attachment(favicon);
+            }
+            interaction.edit_response(ctx, response).await?;
+        }
+        None => {
+            info!("Minecraft server '{}' no longer exists.", name);
+            interaction
+                .edit_response(
+                    ctx,
+                    serenity::EditInteractionResponse::new()
+                        .embed(
+                            serenity::CreateEmbed::new()
+                                .title(format!("{} Server Status", name))
+                                .description("This server is no longer advertised on this guild.")
+                                .color(colors::red()),
+                        )
+                        .components(vec![]),
+                )
+                .await?;
         }
     }
+    Ok(())
 }
 
 poise_instrument! {
-    /// Removes an advertised minecraft server.
-    #[poise::command(
-        slash_command,
-        prefix_command,
-        required_permissions = "ADMINISTRATOR",
-        default_member_permissions = "ADMINISTRATOR",
-        guild_only
-    )]
+    /// Removes an advertised minecraft server. Requires ownership, the manager role or administrator.
+    #[poise::command(slash_command, prefix_command, guild_only)]
     async fn remove(
         ctx: Context<'_>,
         #[autocomplete = "mcserver_autocomplete"]
@@ -170,15 +575,20 @@ poise_instrument! {
     ) -> Result<(), Error> {
         record_ctx_fields!(ctx);
         let srv_match = get_mcserver(ctx, &name).await?;
-        if let Some(_) = srv_match {
-            return Err(format!("Server '{}' already exists.", name).into());
+        let server_info = match srv_match {
+            Some(x) => x,
+            None => return Err(format!("Server '{}' does not exist.", name).into()),
+        };
+        if !can_manage_mcserver(ctx, server_info.owner).await? {
+            return Err(format!("You don't have permission to remove server '{}'.", name).into());
         }
 
         // Remove server from list
         let guild_id = require_guild_id(ctx)?;
-        mc_server::Entity::delete_by_id((id_to_string(guild_id), name.clone()))
-            .exec(&ctx.data().db_pool)
-            .await?;
+        let repo = McServerRepo::new(&ctx.data().db_pool);
+        repo.remove(guild_id, &name).await?;
+        repo.set_status_channel(guild_id, &name, None).await?;
+        mc_monitor::remove_samples(&ctx.data().db_pool, guild_id, &name).await?;
 
         ctx.send(
             CreateReply::default()
@@ -193,6 +603,7 @@ poise_instrument! {
 
 #[derive(Debug, Clone)]
 struct McServerResult {
+    pub id: i64,
     pub address: String,
     pub port: Option<u16>,
     pub version: Option<String>,
@@ -200,70 +611,128 @@ struct McServerResult {
     pub custom_description: Option<String>,
     pub instructions: Option<String>,
     pub thumbnail: Option<String>,
+    /// The member who added the server. None for servers added before ownership was recorded.
+    pub owner: Option<UserId>,
+    /// Cached metadata of the modpack, when the modpack is a link to a Modrinth or CurseForge project.
+    pub modpack_metadata: Option<ModpackMetadata>,
+}
+
+impl From<mc_server::Model> for McServerResult {
+    fn from(value: mc_server::Model) -> Self {
+        let modpack_metadata = ModpackMetadata::from_server(&value);
+        let port = if value.port > 0 && value.port < u16::MAX as i32 {
+            Some(value.port as u16)
+        } else {
+            None
+        };
+        let version = if !value.version.is_empty() {
+            Some(value.version)
+        } else {
+            None
+        };
+        let modpack = if !value.modpack.is_empty() {
+            Some(value.modpack)
+        } else {
+            None
+        };
+        let custom_description = if !value.custom_description.is_empty() {
+            Some(value.custom_description)
+        } else {
+            None
+        };
+        let instructions = if !value.instructions.is_empty() {
+            Some(value.instructions)
+        } else {
+            None
+        };
+        let thumbnail = if !value.thumbnail.is_empty() {
+            Some(value.thumbnail)
+        } else {
+            None
+        };
+        let owner = id_from_string(&value.owner_id).ok();
+        McServerResult {
+            id: value.id,
+            address: value.address,
+            port: port,
+            version: version,
+            modpack: modpack,
+            custom_description: custom_description,
+            instructions: instructions,
+            thumbnail: thumbnail,
+            owner,
+            modpack_metadata,
+        }
+    }
 }
 
 async fn get_mcserver(ctx: Context<'_>, name: &String) -> Result<Option<McServerResult>, Error> {
     let guild_id = require_guild_id(ctx)?;
+    find_mcserver(&ctx.data().db_pool, guild_id, name).await
+}
 
-    let found = mc_server::Entity::find_by_id((id_to_string(guild_id), name.clone()))
-        .one(&ctx.data().db_pool)
-        .await?;
+async fn find_mcserver(
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+    name: &str,
+) -> Result<Option<McServerResult>, Error> {
+    let found = McServerRepo::new(db).find(guild_id, name).await?;
+    Ok(found.map(McServerResult::from))
+}
 
-    match found {
-        Some(value) => {
-            let port = if value.port > 0 && value.port < u16::MAX as i32 {
-                Some(value.port as u16)
-            } else {
-                None
-            };
-            let version = if !value.version.is_empty() {
-                Some(value.version)
-            } else {
-                None
-            };
-            let modpack = if !value.modpack.is_empty() {
-                Some(value.modpack)
-            } else {
-                None
-            };
-            let custom_description = if !value.custom_description.is_empty() {
-                Some(value.custom_description)
-            } else {
-                None
-            };
-            let instructions = if !value.instructions.is_empty() {
-                Some(value.instructions)
-            } else {
-                None
-            };
-            let thumbnail = if !value.thumbnail.is_empty() {
-                Some(value.thumbnail)
-            } else {
-                None
-            };
-            Ok(Some(McServerResult {
-                address: value.address,
-                port: port,
-                version: version,
-                modpack: modpack,
-                custom_description: custom_description,
-                instructions: instructions,
-                thumbnail: thumbnail,
-            }))
-        }
-        _ => Ok(None),
+/// Finds a server to show its status, refreshing the cached metadata of its modpack if it is stale.
+async fn find_mcserver_for_status(
+    data: &Data,
+    guild_id: GuildId,
+    name: &str,
+) -> Result<Option<McServerResult>, Error> {
+    let Some(server) = McServerRepo::new(&data.db_pool)
+        .find(guild_id, name)
+        .await?
+    else {
+        return Ok(None);
+    };
+    let now = data.clock.unix_timestamp();
+    let server = modpacks::refresh_metadata(&data.db_pool, server, now).await;
+    Ok(Some(server.into()))
+}
+
+/// Checks whether the author may manage minecraft servers on this guild.
+///
+/// Administrators and members with the server manager role may manage every server, while other members may only
+/// manage the servers they own. Pass None as the owner to check whether the author may add new servers.
+pub(crate) async fn can_manage_mcserver(
+    ctx: Context<'_>,
+    owner: Option<UserId>,
+) -> Result<bool, Error> {
+    if owner.is_some_and(|owner| owner == ctx.author().id) {
+        return Ok(true);
+    }
+
+    let guild_id = require_guild_id(ctx)?;
+    let member = ctx
+        .author_member()
+        .await
+        .ok_or::<Error>("Failed to get guild member".into())?;
+    let is_admin = match member.permissions {
+        Some(permissions) => permissions.administrator(),
+        None => ctx
+            .guild()
+            .is_some_and(|guild| guild.member_permissions(&member).administrator()),
+    };
+    if is_admin {
+        return Ok(true);
     }
+
+    Ok(McServerRepo::new(&ctx.data().db_pool)
+        .manager_role(guild_id)
+        .await?
+        .is_some_and(|role| member.roles.contains(&role)))
 }
 
 poise_instrument! {
-    /// Adds an advertised minecraft server.
-    #[poise::command(
-        slash_command,
-        prefix_command,
-        required_permissions = "ADMINISTRATOR",
-        default_member_permissions = "ADMINISTRATOR",
-        guild_only
-    )]
+    /// Adds an advertised minecraft server. Requires the server manager role or administrator.
+    #[poise::command(slash_command, prefix_command, guild_only)]
     async fn add(
         ctx: Context<'_>,
         name: String,
@@ -277,6 +746,10 @@ poise_instrument! {
     ) -> Result<(), Error> {
         record_ctx_fields!(ctx);
 
+        if !can_manage_mcserver(ctx, None).await? {
+            return Err("You don't have permission to add minecraft servers.".into());
+        }
+
         let srv_match = get_mcserver(ctx, &name).await?;
         if let Some(_) = srv_match {
             return Err(format!("Server '{}' already exists.", name).into());
@@ -287,23 +760,36 @@ poise_instrument! {
         let port_or_zero = port.unwrap_or(0);
         let version_or_empty = version.unwrap_or("".into());
         let modpack_or_empty = modpack.unwrap_or("".into());
+        let modpack_metadata = modpacks::validate_modpack(&modpack_or_empty).await?;
+        // Without metadata, it is fetched again when the status of the server is shown
+        let modpack_fetched_at = match modpack_metadata {
+            Some(_) => ctx.data().clock.unix_timestamp(),
+            None => 0,
+        };
+        let modpack_metadata = modpack_metadata.unwrap_or_default();
         let custom_description_or_empty = custom_description.unwrap_or("".into());
         let instructions_or_empty = instructions.unwrap_or("".into());
         let thumbnail_or_empty = thumbnail.unwrap_or("".into());
 
-        mc_server::Entity::insert(mc_server::ActiveModel {
-            guild_id: Set(id_to_string(guild_id)),
-            name: Set(name.clone()),
-            address: Set(address),
-            port: Set(port_or_zero as i32),
-            version: Set(version_or_empty),
-            modpack: Set(modpack_or_empty),
-            custom_description: Set(custom_description_or_empty),
-            instructions: Set(instructions_or_empty),
-            thumbnail: Set(thumbnail_or_empty),
-        })
-        .exec(&ctx.data().db_pool)
-        .await?;
+        McServerRepo::new(&ctx.data().db_pool)
+            .insert(mc_server::Model {
+                guild_id: id_to_string(guild_id),
+                name: name.clone(),
+                address,
+                port: port_or_zero as i32,
+                version: version_or_empty,
+                modpack: modpack_or_empty,
+                custom_description: custom_description_or_empty,
+                instructions: instructions_or_empty,
+                thumbnail: thumbnail_or_empty,
+                owner_id: id_to_string(ctx.author().id),
+                modpack_name: modpack_metadata.name,
+                modpack_version: modpack_metadata.version,
+                modpack_icon: modpack_metadata.icon_url,
+                modpack_fetched_at,
+                id: 0,
+            })
+            .await?;
 
         ctx.send(
             CreateReply::default()
@@ -316,15 +802,424 @@ poise_instrument! {
     }
 }
 
+/// Largest file `/mc import` accepts.
+const MAX_IMPORT_SIZE: u32 = 256 * 1024;
+
+/// Most servers `/mc import` accepts in one file.
+const MAX_IMPORT_ROWS: usize = 100;
+
+/// Longest import report sent as a message. Longer reports are attached as a file.
+const MAX_REPORT_LENGTH: usize = 1900;
+
+/// A minecraft server as read by `/mc import` and written by `/mc export`. Only the name and address are required.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct McServerRecord {
+    name: String,
+    address: String,
+    #[serde(default)]
+    port: Option<u16>,
+    #[serde(default)]
+    version: String,
+    #[serde(default)]
+    modpack: String,
+    #[serde(default)]
+    custom_description: String,
+    #[serde(default)]
+    instructions: String,
+    #[serde(default)]
+    thumbnail: String,
+}
+
+impl McServerRecord {
+    /// Checks the required fields of an imported server, trimming its name and address.
+    fn validated(mut self) -> Result<Self, String> {
+        self.name = self.name.trim().to_string();
+        self.address = self.address.trim().to_string();
+        if self.name.is_empty() {
+            return Err("the name is missing".to_string());
+        }
+        if self.name.chars().count() > 100 {
+            return Err("the name is longer than 100 characters".to_string());
+        }
+        if self.address.is_empty() {
+            return Err("the address is missing".to_string());
+        }
+        Ok(self)
+    }
+
+    fn into_model(self, guild_id: GuildId, owner: UserId) -> mc_server::Model {
+        mc_server::Model {
+            guild_id: id_to_string(guild_id),
+            name: self.name,
+            address: self.address,
+            port: self.port.unwrap_or(0) as i32,
+            version: self.version,
+            modpack: self.modpack,
+            custom_description: self.custom_description,
+            instructions: self.instructions,
+            thumbnail: self.thumbnail,
+            owner_id: id_to_string(owner),
+            // Fetched when the status of the server is first shown
+            modpack_name: String::new(),
+            modpack_version: String::new(),
+            modpack_icon: String::new(),
+            modpack_fetched_at: 0,
+            // Assigned when the server is added
+            id: 0,
+        }
+    }
+}
+
+impl From<mc_server::Model> for McServerRecord {
+    fn from(value: mc_server::Model) -> Self {
+        Self {
+            name: value.name,
+            address: value.address,
+            port: u16::try_from(value.port).ok().filter(|x| *x != 0),
+            version: value.version,
+            modpack: value.modpack,
+            custom_description: value.custom_description,
+            instructions: value.instructions,
+            thumbnail: value.thumbnail,
+        }
+    }
+}
+
+/// File formats of `/mc import` and `/mc export`.
+#[derive(Debug, poise::ChoiceParameter, Clone, Copy)]
+enum McDataFormat {
+    #[name = "JSON"]
+    Json,
+    #[name = "CSV"]
+    Csv,
+}
+
+impl McDataFormat {
+    fn from_filename(filename: &str) -> Option<Self> {
+        let extension = Path::new(filename).extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+        }
+    }
+
+    /// Reads the servers of a file. Each row that can't be read is an error of its own, so the other rows can
+    /// still be imported.
+    fn parse(&self, data: &[u8]) -> Result<Vec<Result<McServerRecord, String>>, Error> {
+        match self {
+            Self::Json => {
+                let values: Vec<serde_json::Value> = serde_json::from_slice(data)?;
+                Ok(values
+                    .into_iter()
+                    .map(|x| serde_json::from_value(x).map_err(|e| e.to_string()))
+                    .collect())
+            }
+            Self::Csv => Ok(csv::ReaderBuilder::new()
+                .trim(csv::Trim::All)
+                .from_reader(data)
+                .deserialize()
+                .map(|x| x.map_err(|e| e.to_string()))
+                .collect()),
+        }
+    }
+
+    fn write(&self, records: &[McServerRecord]) -> Result<Vec<u8>, Error> {
+        match self {
+            Self::Json => Ok(serde_json::to_vec_pretty(records)?),
+            Self::Csv => {
+                let mut writer = csv::Writer::from_writer(vec![]);
+                for record in records {
+                    writer.serialize(record)?;
+                }
+                Ok(writer.into_inner()?)
+            }
+        }
+    }
+}
+
 poise_instrument! {
-    /// Updates an advertised minecraft server.
-    #[poise::command(
-        slash_command,
-        //prefix_command, // bug in proc-macro causes prefix commands with many Option<T> parameters to have exponential compilation times
-        required_permissions = "ADMINISTRATOR",
-        default_member_permissions = "ADMINISTRATOR",
-        guild_only
-    )]
+    /// Adds the servers of a JSON or CSV file in the format of /mc export.
+    #[poise::command(slash_command, prefix_command, guild_only)]
+    async fn import(
+        ctx: Context<'_>,
+        #[description = "JSON or CSV file describing the servers"] file: serenity::Attachment,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+
+        if !can_manage_mcserver(ctx, None).await? {
+            return Err("You don't have permission to add minecraft servers.".into());
+        }
+        let format = McDataFormat::from_filename(&file.filename)
+            .ok_or("The file must be a .json or .csv file.")?;
+        if file.size > MAX_IMPORT_SIZE {
+            return Err(format!("The file can be at most {} KB.", MAX_IMPORT_SIZE / 1024).into());
+        }
+        ctx.defer_ephemeral().await?;
+
+        let rows = format.parse(&file.download().await?)?;
+        if rows.len() > MAX_IMPORT_ROWS {
+            return Err(format!("At most {} servers can be imported at once.", MAX_IMPORT_ROWS).into());
+        }
+
+        let guild_id = require_guild_id(ctx)?;
+        let db = &ctx.data().db_pool;
+        let existing: HashSet<String> = McServerRepo::new(db)
+            .list(guild_id)
+            .await?
+            .into_iter()
+            .map(|x| x.name)
+            .collect();
+        let total = rows.len();
+        let mut names = HashSet::new();
+        let mut report: Vec<String> = vec![];
+        let mut added = 0;
+
+        // Either every valid row is added or, if saving one fails, none
+        let uow = UnitOfWork::begin(db).await?;
+        let result = async {
+            let repo = McServerRepo::new(uow.txn());
+            for (index, row) in rows.into_iter().enumerate() {
+                let row_number = index + 1;
+                let record = match row.and_then(McServerRecord::validated) {
+                    Ok(x) => x,
+                    Err(e) => {
+                        report.push(format!("❌ Row {}: {}", row_number, e));
+                        continue;
+                    }
+                };
+                if existing.contains(&record.name) || !names.insert(record.name.clone()) {
+                    report.push(format!(
+                        "❌ Row {} ({}): a server with this name already exists",
+                        row_number, record.name
+                    ));
+                    continue;
+                }
+                let name = record.name.clone();
+                repo.insert(record.into_model(guild_id, ctx.author().id)).await?;
+                report.push(format!("✅ Row {} ({}): added", row_number, name));
+                added += 1;
+            }
+            Ok(())
+        }
+        .await;
+        uow.finish(result).await?;
+        info!("Imported {} of {} minecraft servers on guild {}", added, total, guild_id);
+
+        let summary = format!("Imported {} of {} servers.", added, total);
+        let report = report.join("\n");
+        let reply = if summary.len() + report.len() < MAX_REPORT_LENGTH {
+            CreateReply::default().content(format!("{}\n{}", summary, report))
+        } else {
+            CreateReply::default()
+                .content(summary)
+                .attachment(serenity::CreateAttachment::bytes(report, "import_report.txt"))
+        };
+        ctx.send(reply.ephemeral(true)).await?;
+        Ok(())
+    }
+
+    /// Exports the advertised servers as a file that /mc import accepts.
+    #[poise::command(slash_command, prefix_command, guild_only)]
+    async fn export(
+        ctx: Context<'_>,
+        #[description = "File format (default: JSON)"] format: Option<McDataFormat>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+
+        if !can_manage_mcserver(ctx, None).await? {
+            return Err("You don't have permission to export minecraft servers.".into());
+        }
+        let guild_id = require_guild_id(ctx)?;
+        let servers = McServerRepo::new(&ctx.data().db_pool).list(guild_id).await?;
+        if servers.is_empty() {
+            return Err("No minecraft servers are advertised on this guild.".into());
+        }
+
+        let format = format.unwrap_or(McDataFormat::Json);
+        let count = servers.len();
+        let records: Vec<McServerRecord> = servers.into_iter().map(McServerRecord::from).collect();
+        let file = serenity::CreateAttachment::bytes(
+            format.write(&records)?,
+            format!("mc_servers.{}", format.extension()),
+        );
+        ctx.send(
+            CreateReply::default()
+                .content(format!("Exported {} server(s).", count))
+                .attachment(file)
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Changes to an advertised minecraft server. The `clear_*` switches reset the field to empty.
+#[derive(Debug, Default, PartialEq)]
+struct McServerUpdate {
+    address: Option<String>,
+    port: Option<u16>,
+    version: Option<String>,
+    clear_version: Option<bool>,
+    modpack: Option<String>,
+    clear_modpack: Option<bool>,
+    custom_description: Option<String>,
+    clear_custom_description: Option<bool>,
+    instructions: Option<String>,
+    clear_instructions: Option<bool>,
+    thumbnail: Option<String>,
+    clear_thumbnail: Option<bool>,
+}
+
+impl McServerUpdate {
+    fn is_empty(&self) -> bool {
+        self.address.is_none()
+            && self.port.is_none()
+            && self.version.is_none()
+            && self.clear_version.is_none()
+            && self.modpack.is_none()
+            && self.clear_modpack.is_none()
+            && self.custom_description.is_none()
+            && self.clear_custom_description.is_none()
+            && self.instructions.is_none()
+            && self.clear_instructions.is_none()
+            && self.thumbnail.is_none()
+            && self.clear_thumbnail.is_none()
+    }
+
+    /// Parses the arguments of the prefix command into the name of the server and its changes.
+    fn parse_prefix(input: &str) -> Result<(String, Self), Error> {
+        let mut args = PrefixArgs::parse(input)?;
+        let update = Self::from_prefix_args(&mut args)?;
+        let name = args.require("name")?;
+        args.finish()?;
+        Ok((name, update))
+    }
+
+    /// Parses the arguments of the prefix command, e.g. `survival --version "1.21" --clear-modpack`.
+    fn from_prefix_args(args: &mut PrefixArgs) -> Result<Self, Error> {
+        // Switches are taken first so that they don't consume the following argument as their value
+        let clear_version = args.switch("clear_version")?;
+        let clear_modpack = args.switch("clear_modpack")?;
+        let clear_custom_description = args.switch("clear_custom_description")?;
+        let clear_instructions = args.switch("clear_instructions")?;
+        let clear_thumbnail = args.switch("clear_thumbnail")?;
+        Ok(McServerUpdate {
+            address: args.named("address")?,
+            port: args.named("port")?,
+            version: args.named("version")?,
+            clear_version,
+            modpack: args.named("modpack")?,
+            clear_modpack,
+            custom_description: args.named("custom_description")?,
+            clear_custom_description,
+            instructions: args.named("instructions")?,
+            clear_instructions,
+            thumbnail: args.named("thumbnail")?,
+            clear_thumbnail,
+        })
+    }
+}
+
+/// Updates an advertised minecraft server, shared by the slash and prefix variants of `mc update`.
+async fn update_server(
+    ctx: Context<'_>,
+    name: String,
+    update: McServerUpdate,
+) -> Result<(), Error> {
+    fn apply_clear<T>(value: Option<T>, clear: Option<bool>) -> Option<T>
+    where
+        T: Default,
+    {
+        if clear.unwrap_or(false) {
+            Some(Default::default())
+        } else {
+            value
+        }
+    }
+
+    let srv_match = get_mcserver(ctx, &name).await?;
+
+    // Return early if server does not exist
+    let server_info = match srv_match {
+        Some(x) => x,
+        None => return Err(format!("Server '{}' does not exist.", name).into()),
+    };
+    if !can_manage_mcserver(ctx, server_info.owner).await? {
+        return Err(format!("You don't have permission to update server '{}'.", name).into());
+    }
+
+    if update.is_empty() {
+        return Err("At least one parameter must be updated.".into());
+    }
+
+    let port_value = update.port.filter(|x| *x > 0);
+
+    let version = apply_clear(update.version, update.clear_version);
+    let modpack = apply_clear(update.modpack, update.clear_modpack);
+    let custom_description =
+        apply_clear(update.custom_description, update.clear_custom_description);
+    let instructions = apply_clear(update.instructions, update.clear_instructions);
+    let thumbnail = apply_clear(update.thumbnail, update.clear_thumbnail);
+
+    let guild_id = require_guild_id(ctx)?;
+    let mut model = mc_server::ActiveModel {
+        guild_id: Set(id_to_string(guild_id)),
+        name: Set(name.clone()),
+        ..Default::default()
+    };
+
+    if let Some(x) = update.address {
+        model.address = Set(x);
+    }
+
+    if let Some(x) = port_value {
+        model.port = Set(x.into());
+    }
+
+    if let Some(x) = version {
+        model.version = Set(x);
+    }
+
+    if let Some(x) = modpack {
+        let metadata = modpacks::validate_modpack(&x).await?;
+        modpacks::set_metadata(&mut model, metadata, ctx.data().clock.unix_timestamp());
+        model.modpack = Set(x);
+    }
+
+    if let Some(x) = custom_description {
+        model.custom_description = Set(x);
+    }
+
+    if let Some(x) = instructions {
+        model.instructions = Set(x);
+    }
+
+    if let Some(x) = thumbnail {
+        model.thumbnail = Set(x);
+    }
+
+    McServerRepo::new(&ctx.data().db_pool).update(model).await?;
+
+    ctx.send(
+        CreateReply::default()
+            .content(format!("Successfully updated server '{}'", name))
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+poise_instrument! {
+    /// Updates an advertised minecraft server. Requires ownership, the manager role or administrator.
+    #[poise::command(slash_command, guild_only)]
     async fn update(
         ctx: Context<'_>,
         #[autocomplete = "mcserver_autocomplete"] name: String,
@@ -342,103 +1237,209 @@ poise_instrument! {
         clear_thumbnail: Option<bool>,
     ) -> Result<(), Error> {
         record_ctx_fields!(ctx);
-
-        fn apply_clear<T>(value: Option<T>, clear: Option<bool>) -> Option<T>
-        where
-            T: Default,
-        {
-            if clear.unwrap_or(false) {
-                Some(Default::default())
-            } else {
-                value
-            }
-        }
-
-        let srv_match = get_mcserver(ctx, &name).await?;
-
-        // Return early if server does not exist
-        if let None = srv_match {
-            return Err(format!("Server '{}' does not exist.", name).into());
-        }
-
-        if address.is_none()
-            && port.is_none()
-            && version.is_none()
-            && clear_version.is_none()
-            && modpack.is_none()
-            && clear_modpack.is_none()
-            && custom_description.is_none()
-            && clear_custom_description.is_none()
-            && instructions.is_none()
-            && clear_instructions.is_none()
-            && thumbnail.is_none()
-            && clear_thumbnail.is_none()
-        {
-            return Err("At least one parameter must be updated.".into());
-        }
-
-        let port_value = match port {
-            Some(x) => {
-                if x > 0 {
-                    Some(x)
-                } else {
-                    None
-                }
-            }
-            _ => None,
+        let update = McServerUpdate {
+            address,
+            port,
+            version,
+            clear_version,
+            modpack,
+            clear_modpack,
+            custom_description,
+            clear_custom_description,
+            instructions,
+            clear_instructions,
+            thumbnail,
+            clear_thumbnail,
         };
+        update_server(ctx, name, update).await
+    }
 
-        let version = apply_clear(version, clear_version);
-        let modpack = apply_clear(modpack, clear_modpack);
-        let custom_description = apply_clear(custom_description, clear_custom_description);
-        let instructions = apply_clear(instructions, clear_instructions);
-        let thumbnail = apply_clear(thumbnail, clear_thumbnail);
+    /// Updates an advertised minecraft server, e.g. `mc update survival --version "1.21" --clear-modpack`.
+    ///
+    /// Prefix variant of `mc update`. Its many optional parameters are passed as `--name value` options, since
+    /// positional prefix arguments can't skip parameters.
+    #[poise::command(prefix_command, guild_only, aliases("update"), hide_in_help)]
+    async fn update_prefix(
+        ctx: Context<'_>,
+        #[rest] args: Option<String>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let (name, update) = McServerUpdate::parse_prefix(args.as_deref().unwrap_or_default())?;
+        update_server(ctx, name, update).await
+    }
+}
 
+poise_instrument! {
+    /// Sets the role whose members may add, update and remove any advertised minecraft server.
+    #[poise::command(
+        slash_command,
+        prefix_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        rename = "manager-role"
+    )]
+    async fn manager_role(
+        ctx: Context<'_>,
+        #[description = "Server manager role (leave empty to clear)"] role: Option<RoleId>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
         let guild_id = require_guild_id(ctx)?;
-        let mut model = mc_server::ActiveModel {
-            guild_id: Set(id_to_string(guild_id)),
-            name: Set(name.clone()),
-            ..Default::default()
-        };
 
-        if let Some(x) = address {
-            model.address = Set(x);
-        }
+        McServerRepo::new(&ctx.data().db_pool)
+            .set_manager_role(guild_id, role)
+            .await?;
 
-        if let Some(x) = port_value {
-            model.port = Set(x.into());
-        }
+        let content = match role {
+            Some(role) => format!("Members with {} may now manage minecraft servers", role.mention()),
+            None => "Cleared the minecraft server manager role".to_string(),
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
 
-        if let Some(x) = version {
-            model.version = Set(x);
+poise_instrument! {
+    /// Mirrors the status of a server into the name of a voice channel, locked so nobody can join it
+    #[poise::command(slash_command, guild_only, rename = "status-channel")]
+    async fn status_channel(
+        ctx: Context<'_>,
+        #[autocomplete = "mcserver_autocomplete"]
+        #[description = "Server Name"]
+        name: String,
+        #[description = "Voice channel to rename (leave empty to stop mirroring the status)"]
+        #[channel_types("Voice")]
+        channel: Option<serenity::GuildChannel>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let server_info = match get_mcserver(ctx, &name).await? {
+            Some(x) => x,
+            None => return Err(format!("Server '{}' does not exist.", name).into()),
+        };
+        if !can_manage_mcserver(ctx, server_info.owner).await? {
+            return Err(format!("You don't have permission to update server '{}'.", name).into());
         }
 
-        if let Some(x) = modpack {
-            model.modpack = Set(x);
+        let guild_id = require_guild_id(ctx)?;
+        if let Some(channel) = &channel {
+            // The everyone role shares its id with the guild
+            channel
+                .id
+                .create_permission(
+                    ctx,
+                    serenity::PermissionOverwrite {
+                        allow: serenity::Permissions::empty(),
+                        deny: serenity::Permissions::CONNECT,
+                        kind: serenity::PermissionOverwriteType::Role(RoleId::new(guild_id.get())),
+                    },
+                )
+                .await
+                .map_err(|_| {
+                    format!(
+                        "Failed to lock {}, the bot needs the Manage Channels and Manage Permissions permissions there",
+                        channel.mention()
+                    )
+                })?;
         }
+        McServerRepo::new(&ctx.data().db_pool)
+            .set_status_channel(guild_id, &name, channel.as_ref().map(|x| x.id))
+            .await?;
 
-        if let Some(x) = custom_description {
-            model.custom_description = Set(x);
-        }
+        let content = match channel {
+            Some(channel) => format!(
+                "{} will show the status of '{}' from the next check, within {} minutes",
+                channel.mention(),
+                name,
+                SAMPLE_INTERVAL.as_secs() / 60
+            ),
+            None => format!("Stopped mirroring the status of '{}'", name),
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
 
-        if let Some(x) = instructions {
-            model.instructions = Set(x);
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        if let Some(x) = thumbnail {
-            model.thumbnail = Set(x);
-        }
+    fn error_of<T: std::fmt::Debug>(result: Result<T, Error>) -> String {
+        result.unwrap_err().to_string()
+    }
 
-        mc_server::Entity::update(model)
-            .exec(&ctx.data().db_pool)
-            .await?;
+    #[test]
+    fn flags_and_quoted_values_are_parsed() -> Result<(), Error> {
+        let (name, update) = McServerUpdate::parse_prefix(
+            r#"survival --version "1.21 Tricky Trials" --port=25566 --clear-modpack"#,
+        )?;
+        assert_eq!(name, "survival");
+        assert_eq!(
+            update,
+            McServerUpdate {
+                version: Some("1.21 Tricky Trials".to_string()),
+                port: Some(25566),
+                clear_modpack: Some(true),
+                ..Default::default()
+            }
+        );
 
-        ctx.send(
-            CreateReply::default()
-                .content(format!("Successfully updated server '{}'", name))
-                .ephemeral(true),
-        )
-        .await?;
+        // The name may come after the flags, and quoted text starting with dashes is a value
+        let (name, update) = McServerUpdate::parse_prefix(
+            r#"--custom-description "--not a flag" --clear-thumbnail=false 'modded server'"#,
+        )?;
+        assert_eq!(name, "modded server");
+        assert_eq!(
+            update,
+            McServerUpdate {
+                custom_description: Some("--not a flag".to_string()),
+                clear_thumbnail: Some(false),
+                ..Default::default()
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn quotes_are_escaped() -> Result<(), Error> {
+        let (_, update) = McServerUpdate::parse_prefix(
+            r#"survival --custom-description "The \"best\" server" --instructions 'Say "hi" \o/'"#,
+        )?;
+        assert_eq!(
+            update.custom_description.as_deref(),
+            Some(r#"The "best" server"#)
+        );
+        // Backslashes only escape inside double quotes
+        assert_eq!(update.instructions.as_deref(), Some(r#"Say "hi" \o/"#));
         Ok(())
     }
+
+    #[test]
+    fn invalid_arguments_are_refused() {
+        assert_eq!(
+            error_of(McServerUpdate::parse_prefix("survival --versoin 1.21")),
+            "Unknown option `--versoin`"
+        );
+        assert_eq!(
+            error_of(McServerUpdate::parse_prefix("survival creative --port 1")),
+            "Unexpected argument 'creative'"
+        );
+        assert_eq!(
+            error_of(McServerUpdate::parse_prefix("survival --port")),
+            "Missing value for `--port`"
+        );
+        assert_eq!(
+            error_of(McServerUpdate::parse_prefix("survival --port high")),
+            "Invalid value 'high' for `port`"
+        );
+        assert_eq!(
+            error_of(McServerUpdate::parse_prefix("--version 1.21")),
+            "Missing argument `name`"
+        );
+        assert_eq!(
+            error_of(McServerUpdate::parse_prefix(r#"survival --version "1.21"#)),
+            "Missing closing quote \""
+        );
+    }
 }
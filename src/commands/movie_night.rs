@@ -0,0 +1,129 @@
+use poise::{
+    ChoiceParameter, CreateReply,
+    serenity_prelude::{GuildChannel, Mentionable},
+};
+
+use crate::{
+    Context, Error,
+    infrastructure::{
+        branding::guild_branding,
+        channel_validation::check_notification_channel,
+        ids::require_guild_id,
+        media_servers::{MediaServerKind, describe_now_playing, media_server},
+    },
+    poise_instrument, record_ctx_fields,
+    services::movie_night::MovieNightRepo,
+};
+
+/// Set of commands to show what is playing on a Jellyfin or Plex server, for watch parties.
+#[poise::command(
+    slash_command,
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR",
+    guild_only,
+    category = "Management",
+    rename = "movie-night",
+    subcommands("enable", "disable", "status")
+)]
+pub async fn movie_night(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Posts an embed to a channel that is kept up to date with what is playing on a media server.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn enable(
+        ctx: Context<'_>,
+        #[description = "Channel to show what is playing in"]
+        #[channel_types("Text", "News")]
+        channel: GuildChannel,
+        #[description = "Media server to follow"] server: MediaServerKind,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        if channel.guild_id != guild_id {
+            return Err("That channel is not part of this server".into());
+        }
+        check_notification_channel(&ctx.serenity_context().cache, &channel)?;
+        if media_server(server).is_none() {
+            return Err(format!("{} is not configured by the bot's operator", server.name()).into());
+        }
+        MovieNightRepo::new(&ctx.data().db_pool)
+            .enable(guild_id, channel.id, server)
+            .await?;
+        ctx.send(
+            CreateReply::default()
+                .content(format!(
+                    "What is playing on {} will be shown in {} shortly.",
+                    server.name(),
+                    channel.mention()
+                ))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Stops showing what is playing on the media server.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn disable(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let disabled = MovieNightRepo::new(&ctx.data().db_pool)
+            .disable(guild_id)
+            .await?;
+        let content = if disabled {
+            "Movie night disabled, the last message is no longer updated."
+        } else {
+            "Movie night is not enabled on this server."
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Shows the movie night configuration and what is playing right now.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn status(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let Some(movie_night) = MovieNightRepo::new(&ctx.data().db_pool).get(guild_id).await? else {
+            return Err("Movie night is not enabled on this server. Enable it with `/movie-night enable`.".into());
+        };
+        let now_playing = match media_server(movie_night.server) {
+            Some(server) => match server.now_playing().await {
+                Ok(sessions) => describe_now_playing(&sessions),
+                Err(e) => format!("Failed to reach the server: {}", e),
+            },
+            None => "The server is no longer configured by the bot's operator.".to_string(),
+        };
+        let embed = guild_branding(ctx)
+            .await
+            .embed()
+            .title("Movie night")
+            .field("Server", movie_night.server.name(), true)
+            .field("Channel", movie_night.channel_id.mention().to_string(), true)
+            .field("Now playing", now_playing, false);
+        ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
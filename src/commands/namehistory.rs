@@ -0,0 +1,77 @@
+use poise::{
+    CreateReply,
+    serenity_prelude::{CreateEmbedFooter, Mentionable, User},
+};
+
+use crate::{
+    Context, Error,
+    infrastructure::{
+        branding::guild_branding, ids::require_guild_id, retention::get_retention_days,
+    },
+    poise_instrument, record_ctx_fields,
+    services::name_history::{NameHistoryRepo, NameHistoryRetention},
+};
+
+/// Number of changes shown by `/namehistory`.
+const MAX_CHANGES: u64 = 20;
+
+fn display_name(name: &str) -> String {
+    if name.is_empty() {
+        "*none*".to_string()
+    } else {
+        format!("`{}`", name.replace('`', "'"))
+    }
+}
+
+poise_instrument! {
+    /// Shows the past usernames, display names and nicknames of a member on this guild
+    #[poise::command(
+        slash_command,
+        required_permissions = "MANAGE_NICKNAMES",
+        default_member_permissions = "MANAGE_NICKNAMES",
+        guild_only,
+        category = "Management"
+    )]
+    pub async fn namehistory(
+        ctx: Context<'_>,
+        #[description = "Member to show the names of"] user: User,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let db = &ctx.data().db_pool;
+        let changes = NameHistoryRepo::new(db)
+            .history(guild_id, user.id, MAX_CHANGES)
+            .await?;
+        let description = if changes.is_empty() {
+            format!("No name changes of {} were seen.", user.mention())
+        } else {
+            changes
+                .iter()
+                .map(|x| {
+                    format!(
+                        "<t:{}:d> **{}** {} → {}",
+                        x.changed_at,
+                        x.kind.label(),
+                        display_name(&x.old_name),
+                        display_name(&x.new_name)
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join("\n")
+        };
+        let retention = match get_retention_days(db, &NameHistoryRetention).await? {
+            Some(days) => format!("Name changes are kept for {} days", days),
+            None => "Name changes are kept forever".to_string(),
+        };
+        let embed = guild_branding(ctx)
+            .await
+            .embed()
+            .title(format!("Name history of {}", user.name))
+            .thumbnail(user.face())
+            .description(description)
+            .footer(CreateEmbedFooter::new(retention));
+        ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
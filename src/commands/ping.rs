@@ -0,0 +1,79 @@
+use std::time::{Duration, Instant};
+
+use poise::CreateReply;
+
+use crate::{
+    Context, Error,
+    infrastructure::{
+        branding::guild_branding,
+        latency::{LatencyKind, latency_summary, ping_database, record_latency},
+    },
+    poise_instrument, record_ctx_fields,
+};
+
+fn format_latency(latency: Duration) -> String {
+    format!("{} ms", latency.as_millis())
+}
+
+poise_instrument! {
+    /// Shows the bot's gateway, REST API and database latency.
+    #[poise::command(slash_command, prefix_command)]
+    pub async fn ping(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+
+        let handle = ctx
+            .send(CreateReply::default().content("Pinging...").ephemeral(true))
+            .await?;
+
+        // Editing the reply measures a full REST round trip
+        let start = Instant::now();
+        handle
+            .edit(ctx, CreateReply::default().content("Pinging... (database)"))
+            .await?;
+        let rest = start.elapsed();
+        let database = ping_database(&ctx.data().db_pool).await?;
+        let gateway = ctx.ping().await;
+
+        let samples = &ctx.data().latency;
+        record_latency(samples, LatencyKind::Rest, rest);
+        record_latency(samples, LatencyKind::Database, database);
+
+        let gateway = if gateway.is_zero() {
+            "Not measured yet".to_string()
+        } else {
+            format_latency(gateway)
+        };
+        let recent = [
+            ("Gateway", LatencyKind::Gateway),
+            ("REST", LatencyKind::Rest),
+            ("Database", LatencyKind::Database),
+        ]
+        .into_iter()
+        .filter_map(|(name, kind)| {
+            latency_summary(samples, kind).map(|x| {
+                format!(
+                    "{}: {} average, {} max ({} samples)",
+                    name,
+                    format_latency(x.average),
+                    format_latency(x.max),
+                    x.count
+                )
+            })
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+        let branding = guild_branding(ctx).await;
+        let embed = branding.embed()
+            .title("Pong!")
+            .field("Gateway", gateway, true)
+            .field("REST", format_latency(rest), true)
+            .field("Database", format_latency(database), true)
+            .field("Recent", recent, false);
+
+        handle
+            .edit(ctx, CreateReply::default().content("").embed(embed))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,305 @@
+use std::time::Duration;
+
+use poise::{
+    CreateReply,
+    serenity_prelude::{
+        ChannelId,
+        futures::{self, Stream, StreamExt},
+    },
+};
+use songbird::input::AuxMetadata;
+
+use crate::{
+    Context, Error,
+    commands::voice::{get_now_playing, queue_tracks, resolve_track, youtube_search_autocomplete},
+    entities::user_playlists,
+    infrastructure::{branding::guild_branding, ids::require_guild_id, util::Paginator},
+    poise_instrument, record_ctx_fields,
+    services::{
+        playlists::{MAX_PLAYLIST_NAME_LENGTH, MAX_PLAYLIST_TRACKS, MAX_PLAYLISTS, PlaylistRepo},
+        unit_of_work::UnitOfWork,
+    },
+};
+
+/// Number of tracks shown on each page of a playlist.
+const PLAYLIST_PAGE_SIZE: usize = 10;
+
+/// Set of commands to build playlists and play them in voice.
+#[poise::command(
+    slash_command,
+    category = "Fun",
+    subcommands("create", "add", "play", "list", "delete")
+)]
+pub async fn playlist(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+async fn playlist_autocomplete<'a>(
+    ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Stream<Item = String> + 'a {
+    let partial = partial.to_lowercase();
+    let names: Vec<String> = PlaylistRepo::new(&ctx.data().db_pool)
+        .playlists(ctx.author().id)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|x| x.name)
+        .filter(|name| name.to_lowercase().contains(&partial))
+        .collect();
+    futures::stream::iter(names).boxed()
+}
+
+/// Gets a playlist of the invoking user by name.
+async fn find_playlist(ctx: Context<'_>, name: &str) -> Result<user_playlists::Model, Error> {
+    PlaylistRepo::new(&ctx.data().db_pool)
+        .find(ctx.author().id, name.trim())
+        .await?
+        .ok_or(format!("You don't have a playlist named '{}'", name.trim()).into())
+}
+
+poise_instrument! {
+    /// Creates an empty playlist.
+    #[poise::command(slash_command, category = "Fun")]
+    async fn create(
+        ctx: Context<'_>,
+        #[description = "Name of the playlist"] name: String,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_PLAYLIST_NAME_LENGTH {
+            return Err(format!(
+                "Playlist names must be 1 to {} characters long",
+                MAX_PLAYLIST_NAME_LENGTH
+            )
+            .into());
+        }
+        let repo = PlaylistRepo::new(&ctx.data().db_pool);
+        let playlists = repo.playlists(ctx.author().id).await?;
+        if playlists.iter().any(|x| x.name == name) {
+            return Err(format!("You already have a playlist named '{}'", name).into());
+        }
+        if playlists.len() >= MAX_PLAYLISTS {
+            return Err(format!("You can have at most {} playlists", MAX_PLAYLISTS).into());
+        }
+        repo.create(ctx.author().id, name, ctx.data().clock.unix_timestamp())
+            .await?;
+        ctx.send(
+            CreateReply::default()
+                .content(format!(
+                    "Created playlist '{}'. Add tracks with `/playlist add`.",
+                    name
+                ))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+poise_instrument! {
+    /// Adds a youtube video, or the track playing now, to a playlist.
+    #[poise::command(slash_command, category = "Fun")]
+    async fn add(
+        ctx: Context<'_>,
+        #[description = "Playlist to add to"]
+        #[autocomplete = "playlist_autocomplete"]
+        playlist: String,
+        #[description = "Video or search (default: the track playing now)"]
+        #[autocomplete = "youtube_search_autocomplete"]
+        track: Option<String>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let playlist = find_playlist(ctx, &playlist).await?;
+        let repo = PlaylistRepo::new(&ctx.data().db_pool);
+        if repo.track_count(playlist.id).await? >= MAX_PLAYLIST_TRACKS {
+            return Err(
+                format!("Playlists can have at most {} tracks", MAX_PLAYLIST_TRACKS).into()
+            );
+        }
+        let meta = match track {
+            Some(track) => {
+                ctx.defer_ephemeral().await?;
+                resolve_track(ctx, &track).await?
+            }
+            None => {
+                let guild_id = require_guild_id(ctx)?;
+                get_now_playing(ctx, guild_id)
+                    .await
+                    .ok_or("Nothing is playing right now, so pick a track to add")?
+            }
+        };
+        let url = meta
+            .source_url
+            .clone()
+            .ok_or("The track playing now can't be added to a playlist")?;
+        let title = meta.track.or(meta.title).unwrap_or(url.clone());
+        repo.add_track(
+            playlist.id,
+            &title,
+            &url,
+            meta.duration.map(|x| x.as_secs()),
+        )
+        .await?;
+        ctx.send(
+            CreateReply::default()
+                .content(format!("Added '{}' to playlist '{}'.", title, playlist.name))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+poise_instrument! {
+    /// Plays every track of a playlist in voice, one after the other.
+    #[poise::command(
+        slash_command,
+        guild_only,
+        required_permissions = "USE_SOUNDBOARD",
+        category = "Fun"
+    )]
+    async fn play(
+        ctx: Context<'_>,
+        #[description = "Playlist to play"]
+        #[autocomplete = "playlist_autocomplete"]
+        playlist: String,
+        channel: Option<ChannelId>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let playlist = find_playlist(ctx, &playlist).await?;
+        let tracks = PlaylistRepo::new(&ctx.data().db_pool)
+            .tracks(playlist.id)
+            .await?;
+        if tracks.is_empty() {
+            return Err(format!("Playlist '{}' has no tracks yet", playlist.name).into());
+        }
+        ctx.defer().await?;
+        let tracks = tracks
+            .into_iter()
+            .map(|x| AuxMetadata {
+                title: Some(x.title),
+                source_url: Some(x.url),
+                duration: x.duration_seconds.map(|x| Duration::from_secs(x.max(0) as u64)),
+                ..Default::default()
+            })
+            .collect();
+        let result = queue_tracks(ctx, channel, tracks).await?;
+        let mut content = if result.queued > 0 {
+            format!(
+                "Playing {} track(s) of playlist '{}'.",
+                result.queued, playlist.name
+            )
+        } else {
+            format!("No track of playlist '{}' can be played.", playlist.name)
+        };
+        if !result.skipped.is_empty() {
+            content.push_str("\nSkipped:");
+            for skipped in result.skipped {
+                content.push_str(&format!("\n- {}", skipped));
+            }
+        }
+        ctx.send(CreateReply::default().content(content).reply(true))
+            .await?;
+        Ok(())
+    }
+}
+
+poise_instrument! {
+    /// Lists your playlists, or the tracks of one of them.
+    #[poise::command(slash_command, category = "Fun")]
+    async fn list(
+        ctx: Context<'_>,
+        #[description = "Playlist to show the tracks of"]
+        #[autocomplete = "playlist_autocomplete"]
+        playlist: Option<String>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let repo = PlaylistRepo::new(&ctx.data().db_pool);
+        let branding = guild_branding(ctx).await;
+        let Some(playlist) = playlist else {
+            let playlists = repo.playlists(ctx.author().id).await?;
+            if playlists.is_empty() {
+                ctx.send(
+                    CreateReply::default()
+                        .content("You have no playlists yet. Create one with `/playlist create`.")
+                        .ephemeral(true),
+                )
+                .await?;
+                return Ok(());
+            }
+            let mut lines = vec![];
+            for playlist in playlists {
+                let count = repo.track_count(playlist.id).await?;
+                lines.push(format!("**{}** - {} track(s)", playlist.name, count));
+            }
+            let embed = branding.embed().title("Your playlists").description(lines.join("\n"));
+            ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+                .await?;
+            return Ok(());
+        };
+
+        let playlist = find_playlist(ctx, &playlist).await?;
+        let tracks = repo.tracks(playlist.id).await?;
+        if tracks.is_empty() {
+            ctx.send(
+                CreateReply::default()
+                    .content(format!("Playlist '{}' has no tracks yet.", playlist.name))
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+        let pages = tracks
+            .chunks(PLAYLIST_PAGE_SIZE)
+            .enumerate()
+            .map(|(page, tracks)| {
+                let lines = tracks
+                    .iter()
+                    .enumerate()
+                    .map(|(i, track)| {
+                        format!(
+                            "`{}` [{}]({})",
+                            page * PLAYLIST_PAGE_SIZE + i + 1,
+                            track.title,
+                            track.url
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                branding.embed().title(&playlist.name).description(lines)
+            })
+            .collect();
+        Paginator::new(pages).ephemeral(true).send(ctx).await
+    }
+}
+
+poise_instrument! {
+    /// Deletes one of your playlists.
+    #[poise::command(slash_command, category = "Fun")]
+    async fn delete(
+        ctx: Context<'_>,
+        #[description = "Playlist to delete"]
+        #[autocomplete = "playlist_autocomplete"]
+        playlist: String,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let playlist = find_playlist(ctx, &playlist).await?;
+        // The tracks and the playlist are deleted together
+        let uow = UnitOfWork::begin(&ctx.data().db_pool).await?;
+        match PlaylistRepo::new(uow.txn()).delete(playlist.id).await {
+            Ok(_) => uow.commit().await?,
+            Err(e) => {
+                uow.rollback().await;
+                return Err(e);
+            }
+        }
+        ctx.send(
+            CreateReply::default()
+                .content(format!("Deleted playlist '{}'.", playlist.name))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+use std::time::Duration;
+
+use migration::OnConflict;
+use poise::{
+    CreateReply,
+    serenity_prelude::{
+        self as serenity, ButtonStyle, CreateActionRow, CreateButton, CreateInteractionResponse,
+    },
+};
+use sea_orm::{ActiveValue::Set, EntityTrait};
+
+use crate::{
+    Context, Error,
+    entities::retention_policy,
+    infrastructure::{
+        branding::guild_branding,
+        retention::{forget_user, get_retention_days, retention_tasks, run_retention},
+    },
+    poise_instrument, record_ctx_fields,
+};
+
+/// Time a user has to confirm a data purge.
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Set of commands to manage the data the bot stores.
+#[poise::command(slash_command, subcommands("forget_me", "retention"))]
+pub async fn privacy(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Set of commands to configure how long data is kept.
+#[poise::command(
+    slash_command,
+    owners_only,
+    hide_in_help,
+    subcommands("show", "set", "run")
+)]
+async fn retention(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+#[tracing::instrument(level = tracing::Level::TRACE, skip(_ctx))]
+async fn retention_subsystem_autocomplete<'a>(
+    _ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Iterator<Item = &'static str> + 'a {
+    retention_tasks()
+        .into_iter()
+        .map(|task| task.name())
+        .filter(move |name| name.starts_with(partial))
+}
+
+poise_instrument! {
+    /// Deletes all data the bot has stored about you.
+    #[poise::command(slash_command, rename = "forget-me")]
+    async fn forget_me(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let confirm_id = format!("{}confirm", ctx.id());
+        let cancel_id = format!("{}cancel", ctx.id());
+        let reply = CreateReply::default()
+            .content("This permanently deletes all data stored about you. Are you sure?")
+            .components(vec![CreateActionRow::Buttons(vec![
+                CreateButton::new(&confirm_id)
+                    .label("Delete my data")
+                    .style(ButtonStyle::Danger),
+                CreateButton::new(&cancel_id)
+                    .label("Cancel")
+                    .style(ButtonStyle::Secondary),
+            ])])
+            .ephemeral(true);
+        let handle = ctx.send(reply).await?;
+
+        let ctx_id = ctx.id().to_string();
+        let interaction = serenity::ComponentInteractionCollector::new(ctx)
+            .author_id(ctx.author().id)
+            .channel_id(ctx.channel_id())
+            .timeout(CONFIRM_TIMEOUT)
+            .filter(move |mci| mci.data.custom_id.starts_with(&ctx_id))
+            .await;
+
+        let content = match interaction {
+            Some(interaction) if interaction.data.custom_id == confirm_id => {
+                interaction
+                    .create_response(ctx, CreateInteractionResponse::Acknowledge)
+                    .await?;
+                match forget_user(&ctx.data().db_pool, ctx.author().id).await {
+                    Ok(deleted) => format!("Deleted {} records stored about you.", deleted),
+                    Err(e) => e.to_string(),
+                }
+            }
+            Some(interaction) => {
+                interaction
+                    .create_response(ctx, CreateInteractionResponse::Acknowledge)
+                    .await?;
+                "Cancelled, no data was deleted.".to_string()
+            }
+            None => "Timed out, no data was deleted.".to_string(),
+        };
+        handle
+            .edit(ctx, CreateReply::default().content(content).components(vec![]))
+            .await?;
+        Ok(())
+    }
+
+    /// Shows the retention period of each subsystem.
+    #[poise::command(slash_command, owners_only, hide_in_help)]
+    async fn show(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let branding = guild_branding(ctx).await;
+        let mut embed = branding.embed()
+            .title("Data Retention");
+        for task in retention_tasks() {
+            let retention = match get_retention_days(&ctx.data().db_pool, task.as_ref()).await? {
+                Some(days) => format!("{} days", days),
+                None => "Forever".to_string(),
+            };
+            embed = embed.field(task.name(), retention, true);
+        }
+        ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Sets how long a subsystem keeps its data.
+    #[poise::command(slash_command, owners_only, hide_in_help)]
+    async fn set(
+        ctx: Context<'_>,
+        #[description = "Subsystem"]
+        #[autocomplete = "retention_subsystem_autocomplete"]
+        subsystem: String,
+        #[description = "Days to keep data for (0 keeps data forever)"]
+        #[min = 0]
+        days: u32,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        if !retention_tasks().iter().any(|task| task.name() == subsystem) {
+            return Err(format!("Unknown subsystem '{}'.", subsystem).into());
+        }
+
+        retention_policy::Entity::insert(retention_policy::ActiveModel {
+            subsystem: Set(subsystem.clone()),
+            retention_days: Set(days as i32),
+        })
+        .on_conflict(
+            OnConflict::column(retention_policy::Column::Subsystem)
+                .update_column(retention_policy::Column::RetentionDays)
+                .to_owned(),
+        )
+        .exec(&ctx.data().db_pool)
+        .await?;
+
+        let content = if days == 0 {
+            format!("{} data is now kept forever.", subsystem)
+        } else {
+            format!("{} data is now kept for {} days.", subsystem, days)
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Expires old data now instead of waiting for the next scheduled run.
+    #[poise::command(slash_command, owners_only, hide_in_help)]
+    async fn run(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        ctx.defer_ephemeral().await?;
+        let now = ctx.data().clock.unix_timestamp();
+        let deleted = run_retention(&ctx.data().db_pool, now).await?;
+        ctx.send(
+            CreateReply::default()
+                .content(format!("Expired {} records.", deleted))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+}
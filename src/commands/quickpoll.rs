@@ -0,0 +1,239 @@
+use poise::{
+    CreateReply,
+    serenity_prelude::{ArgumentConvert, CreateEmbedFooter, Message, ReactionType},
+};
+use tracing::{debug, trace, warn};
+
+use crate::{
+    Context, Error,
+    infrastructure::{branding::guild_branding, ids::require_guild_id, util::DebuggableReply},
+    poise_instrument, record_ctx_fields,
+};
+
+/// Reactions of polls with options, in the order of the options.
+const OPTION_EMOJIS: [&str; 10] = ["1️⃣", "2️⃣", "3️⃣", "4️⃣", "5️⃣", "6️⃣", "7️⃣", "8️⃣", "9️⃣", "🔟"];
+
+/// Reactions of polls without options, answered with yes or no.
+const YES_NO_EMOJIS: [&str; 2] = ["👍", "👎"];
+
+/// Embed titles are limited to 256 characters.
+const MAX_QUESTION_LENGTH: usize = 256;
+
+const MAX_OPTION_LENGTH: usize = 100;
+
+/// Number of characters of the bars in the results.
+const BAR_WIDTH: usize = 20;
+
+/// Parses `option1 | option2 | ...` into the options of a poll, each with its reaction. Polls without options are
+/// answered with yes or no.
+fn parse_poll_options(options: Option<&str>) -> Result<Vec<(&'static str, String)>, Error> {
+    let Some(options) = options else {
+        return Ok(YES_NO_EMOJIS
+            .into_iter()
+            .zip(["Yes".to_string(), "No".to_string()])
+            .collect());
+    };
+    let options: Vec<&str> = options
+        .split('|')
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .collect();
+    if options.len() < 2 {
+        return Err("At least two options separated by '|' are required".into());
+    }
+    if options.len() > OPTION_EMOJIS.len() {
+        return Err(format!("At most {} options are allowed", OPTION_EMOJIS.len()).into());
+    }
+    if let Some(option) = options
+        .iter()
+        .find(|x| x.chars().count() > MAX_OPTION_LENGTH)
+    {
+        return Err(format!(
+            "Option '{}...' is longer than {} characters",
+            option.chars().take(20).collect::<String>(),
+            MAX_OPTION_LENGTH
+        )
+        .into());
+    }
+    Ok(OPTION_EMOJIS
+        .into_iter()
+        .zip(options.into_iter().map(str::to_string))
+        .collect())
+}
+
+/// Reads the options of a poll back from the description of its embed, where each line is an option after its
+/// reaction.
+fn poll_options(description: &str) -> Vec<(&'static str, String)> {
+    description
+        .lines()
+        .filter_map(|line| {
+            let (emoji, option) = line.split_once(' ')?;
+            let emoji = OPTION_EMOJIS
+                .into_iter()
+                .chain(YES_NO_EMOJIS)
+                .find(|x| *x == emoji)?;
+            Some((emoji, option.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Votes for an option, without the reaction the bot seeded the poll with.
+fn count_votes(message: &Message, emoji: &str) -> u64 {
+    message
+        .reactions
+        .iter()
+        .find(|x| matches!(&x.reaction_type, ReactionType::Unicode(name) if name == emoji))
+        .map(|x| x.count.saturating_sub(u64::from(x.me)))
+        .unwrap_or(0)
+}
+
+/// Bar of `BAR_WIDTH` characters, filled in proportion to a share of the votes.
+fn vote_bar(votes: u64, total: u64) -> String {
+    let filled = (votes * BAR_WIDTH as u64 + total / 2)
+        .checked_div(total)
+        .unwrap_or(0) as usize;
+    format!("{}{}", "█".repeat(filled), "░".repeat(BAR_WIDTH - filled))
+}
+
+/// Set of commands to run quick polls answered with reactions.
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    category = "Fun",
+    subcommands("create", "results")
+)]
+pub async fn quickpoll(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Posts a poll that members answer by reacting, with up to 10 options or yes and no
+    #[poise::command(slash_command, prefix_command, guild_only, category = "Fun")]
+    async fn create(
+        ctx: Context<'_>,
+        #[description = "Question of the poll"] question: String,
+        #[description = "Options separated by '|', up to 10 (default: yes or no)"]
+        options: Option<String>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let question = question.trim();
+        if question.is_empty() {
+            return Err("The question can't be empty".into());
+        }
+        if question.chars().count() > MAX_QUESTION_LENGTH {
+            return Err(format!("Questions are limited to {} characters", MAX_QUESTION_LENGTH).into());
+        }
+        let options = parse_poll_options(options.as_deref())?;
+
+        let branding = guild_branding(ctx).await;
+        let description = options
+            .iter()
+            .map(|(emoji, option)| format!("{} {}", emoji, option))
+            .collect::<Vec<String>>()
+            .join("\n");
+        let reply = CreateReply::default().embed(
+            branding
+                .embed()
+                .title(format!("📊 {}", question))
+                .description(description)
+                .footer(CreateEmbedFooter::new(format!(
+                    "Poll by {} · React to vote",
+                    ctx.author().name
+                ))),
+        );
+        trace!("Sending reply: {:?}", DebuggableReply::new(&reply));
+        let message = ctx.send(reply).await?.into_message().await?;
+
+        // Seeding the reactions can fail if the bot can't react in the channel, which leaves members to add them
+        for (emoji, _) in &options {
+            if let Err(e) = message.react(ctx, ReactionType::Unicode(emoji.to_string())).await {
+                warn!("Failed to seed the reactions of poll {}: {}", message.id, e);
+                break;
+            }
+        }
+        debug!("Posted poll {} with {} options", message.id, options.len());
+        Ok(())
+    }
+
+    /// Tallies the votes of a poll posted with /quickpoll create
+    #[poise::command(slash_command, prefix_command, guild_only, category = "Fun")]
+    async fn results(
+        ctx: Context<'_>,
+        #[description = "Link to the message of the poll"]
+        #[rename = "message-link"]
+        message_link: String,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let message = Message::convert(
+            ctx.serenity_context(),
+            Some(guild_id),
+            Some(ctx.channel_id()),
+            message_link.trim(),
+        )
+        .await
+        .map_err(|_| "That isn't a link to a message the bot can see")?;
+        let in_guild = message
+            .channel_id
+            .to_channel(ctx)
+            .await?
+            .guild()
+            .is_some_and(|x| x.guild_id == guild_id);
+        if !in_guild {
+            return Err("The poll must be posted on this server".into());
+        }
+
+        let poll = message
+            .embeds
+            .first()
+            .filter(|_| message.author.id == ctx.serenity_context().cache.current_user().id);
+        let (question, options) = match poll {
+            Some(embed) => (
+                embed.title.clone().unwrap_or_default(),
+                poll_options(embed.description.as_deref().unwrap_or_default()),
+            ),
+            None => (String::new(), vec![]),
+        };
+        if options.is_empty() {
+            return Err("That message isn't a poll posted with /quickpoll create".into());
+        }
+
+        let votes: Vec<u64> = options
+            .iter()
+            .map(|(emoji, _)| count_votes(&message, emoji))
+            .collect();
+        let total: u64 = votes.iter().sum();
+        let lines = options
+            .iter()
+            .zip(&votes)
+            .map(|((emoji, option), votes)| {
+                let percent = (votes * 100).checked_div(total).unwrap_or(0);
+                format!(
+                    "{} **{}**\n`{}` {}% ({} {})",
+                    emoji,
+                    option,
+                    vote_bar(*votes, total),
+                    percent,
+                    votes,
+                    if *votes == 1 { "vote" } else { "votes" }
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        let branding = guild_branding(ctx).await;
+        let reply = CreateReply::default().embed(
+            branding
+                .embed()
+                .title(question)
+                .description(lines)
+                .field("Total votes", total.to_string(), true)
+                .field("Poll", message.link(), true)
+                .footer(CreateEmbedFooter::new("Results of the poll so far")),
+        );
+        trace!("Sending reply: {:?}", DebuggableReply::new(&reply));
+        ctx.send(reply).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,168 @@
+use std::{collections::HashSet, time::Duration};
+
+use poise::{
+    CreateReply,
+    serenity_prelude::{Mentionable, Message, ReactionType, User, UserId},
+};
+
+use crate::{
+    ApplicationContext, Context, Error,
+    infrastructure::{branding::guild_branding, random::RandomSource, util::DebuggableReply},
+    poise_instrument, record_ctx_fields,
+};
+
+/// Maximum number of winners picked at once.
+const MAX_WINNERS: usize = 25;
+
+/// Users requested per page of reactors, the most the API returns.
+const REACTORS_PAGE_SIZE: u8 = 100;
+
+/// Maximum number of reactors fetched per emoji, to bound the requests made for huge messages.
+const MAX_REACTORS_PER_EMOJI: usize = 2000;
+
+/// Time a user has to submit the options modal.
+const OPTIONS_MODAL_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Default, poise::Modal)]
+#[name = "Pick random reactors"]
+struct PickReactorsModal {
+    #[name = "Number of winners (default: 1)"]
+    #[placeholder = "1"]
+    #[max_length = 2]
+    count: Option<String>,
+    #[name = "Only count this emoji (default: any)"]
+    #[placeholder = "🎉"]
+    #[max_length = 100]
+    emoji: Option<String>,
+}
+
+/// Whether a reaction is the emoji typed by a user, either as the emoji itself or as a custom emoji's name or id.
+fn matches_emoji(reaction: &ReactionType, filter: &str) -> bool {
+    match reaction {
+        ReactionType::Unicode(emoji) => emoji == filter,
+        ReactionType::Custom { id, name, .. } => {
+            filter.contains(&id.to_string())
+                || name
+                    .as_deref()
+                    .is_some_and(|name| filter.trim_matches(':') == name)
+        }
+        _ => false,
+    }
+}
+
+/// Gets the users who reacted to a message with the matching emojis, without bots and duplicates.
+async fn fetch_reactors(
+    ctx: Context<'_>,
+    message: &Message,
+    emoji: Option<&str>,
+) -> Result<Vec<User>, Error> {
+    let mut seen = HashSet::new();
+    let mut reactors = vec![];
+    let reactions = message
+        .reactions
+        .iter()
+        .filter(|x| emoji.is_none_or(|emoji| matches_emoji(&x.reaction_type, emoji)));
+    for reaction in reactions {
+        let mut after: Option<UserId> = None;
+        let mut fetched = 0;
+        loop {
+            let page = message
+                .reaction_users(
+                    ctx,
+                    reaction.reaction_type.clone(),
+                    Some(REACTORS_PAGE_SIZE),
+                    after,
+                )
+                .await?;
+            fetched += page.len();
+            after = page.last().map(|x| x.id);
+            let last_page = page.len() < REACTORS_PAGE_SIZE as usize;
+            for user in page {
+                if !user.bot && seen.insert(user.id) {
+                    reactors.push(user);
+                }
+            }
+            if last_page || fetched >= MAX_REACTORS_PER_EMOJI {
+                break;
+            }
+        }
+    }
+    Ok(reactors)
+}
+
+/// Picks `count` distinct users at random.
+fn pick_winners(random: &dyn RandomSource, mut users: Vec<User>, count: usize) -> Vec<User> {
+    let mut winners = vec![];
+    while winners.len() < count {
+        let Some(index) = random.random_index(users.len()) else {
+            break;
+        };
+        winners.push(users.swap_remove(index));
+    }
+    winners
+}
+
+poise_instrument! {
+    /// Picks random users who reacted to a message, for quick giveaways
+    #[poise::command(context_menu_command = "Pick random reactor", guild_only, category = "Fun")]
+    pub async fn pick_random_reactor(
+        ctx: ApplicationContext<'_>,
+        message: Message,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(Context::from(ctx));
+        let Some(options) =
+            poise::execute_modal::<_, _, PickReactorsModal>(ctx, None, Some(OPTIONS_MODAL_TIMEOUT))
+                .await?
+        else {
+            return Ok(());
+        };
+        let ctx = Context::from(ctx);
+
+        let count = match options.count.as_deref().map(str::trim) {
+            Some(x) if !x.is_empty() => x
+                .parse::<usize>()
+                .ok()
+                .filter(|x| (1..=MAX_WINNERS).contains(x))
+                .ok_or(format!("The number of winners must be between 1 and {}", MAX_WINNERS))?,
+            _ => 1,
+        };
+        let emoji = options
+            .emoji
+            .as_deref()
+            .map(str::trim)
+            .filter(|x| !x.is_empty());
+
+        let reactors = fetch_reactors(ctx, &message, emoji).await?;
+        if reactors.is_empty() {
+            return Err(match emoji {
+                Some(emoji) => format!("Nobody reacted to this message with {}", emoji).into(),
+                None => "Nobody reacted to this message".into(),
+            });
+        }
+        let total = reactors.len();
+        let winners = pick_winners(ctx.data().random.as_ref(), reactors, count);
+
+        let description = winners
+            .iter()
+            .enumerate()
+            .map(|(i, user)| format!("{}. {}", i + 1, user.mention()))
+            .collect::<Vec<String>>()
+            .join("\n");
+        let entries = match emoji {
+            Some(emoji) => format!("Picked from {} users who reacted with {} to {}", total, emoji, message.link()),
+            None => format!("Picked from {} users who reacted to {}", total, message.link()),
+        };
+        let branding = guild_branding(ctx).await;
+        let reply = CreateReply::default().embed(
+            branding
+                .embed()
+                .title(if winners.len() == 1 { "🎉 Winner" } else { "🎉 Winners" })
+                .description(description)
+                .field("Entries", entries, false),
+        );
+
+        tracing::trace!("Sending reply: {:?}", DebuggableReply::new(&reply));
+        ctx.send(reply).await?;
+        Ok(())
+    }
+}
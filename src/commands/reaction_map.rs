@@ -0,0 +1,173 @@
+use poise::{
+    CreateReply,
+    serenity_prelude::futures::{self, Stream, StreamExt},
+};
+
+use crate::{
+    Context, Error,
+    events::reaction_map::{
+        MAX_PATTERN_LENGTH, MAX_REACTION_MAPPINGS, compile_pattern, guild_reaction_mappings,
+        remove_reaction_mapping, resolve_emoji, set_reaction_mapping,
+    },
+    infrastructure::{branding::guild_branding, ids::require_guild_id},
+    poise_instrument, record_ctx_fields,
+};
+
+/// Set of commands to react to messages containing keywords with an emoji.
+#[poise::command(
+    slash_command,
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR",
+    guild_only,
+    category = "Management",
+    subcommands("add", "remove", "list")
+)]
+pub async fn reactmap(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+async fn pattern_autocomplete<'a>(
+    ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Stream<Item = String> + 'a {
+    let partial = partial.to_lowercase();
+    let patterns: Vec<String> = match ctx.guild_id() {
+        Some(guild_id) => {
+            guild_reaction_mappings(&ctx.data().db_pool, &ctx.data().reaction_maps, guild_id)
+                .await
+                .iter()
+                .map(|x| x.pattern.clone())
+                .filter(|x| x.to_lowercase().contains(&partial))
+                .collect()
+        }
+        None => vec![],
+    };
+    futures::stream::iter(patterns).boxed()
+}
+
+poise_instrument! {
+    /// Reacts with an emoji to messages matching a pattern, where automatic replies are allowed.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn add(
+        ctx: Context<'_>,
+        #[description = "Word or regex matched anywhere in messages, ignoring case"]
+        #[max_length = 100]
+        pattern: String,
+        #[description = "Emoji to react with, either unicode or an emoji of this server"]
+        emoji: String,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let pattern = pattern.trim().to_string();
+        if pattern.is_empty() || pattern.chars().count() > MAX_PATTERN_LENGTH {
+            return Err(
+                format!("Patterns must be between 1 and {} characters", MAX_PATTERN_LENGTH).into(),
+            );
+        }
+        if let Err(e) = compile_pattern(&pattern) {
+            return Err(format!("`{}` is not a valid pattern: {}", pattern, e).into());
+        }
+        let Some(emoji) =
+            resolve_emoji(ctx.serenity_context(), &ctx.data().guild_emojis, guild_id, &emoji).await?
+        else {
+            return Err(
+                format!("'{}' is not an emoji, or an emoji name of this server", emoji).into(),
+            );
+        };
+
+        let db = &ctx.data().db_pool;
+        let cache = &ctx.data().reaction_maps;
+        let mappings = guild_reaction_mappings(db, cache, guild_id).await;
+        if mappings.len() >= MAX_REACTION_MAPPINGS
+            && !mappings.iter().any(|x| x.pattern == pattern)
+        {
+            return Err(format!(
+                "This server already has {} reactions, remove one with `/reactmap remove` first",
+                MAX_REACTION_MAPPINGS
+            )
+            .into());
+        }
+        set_reaction_mapping(db, cache, guild_id, &pattern, &emoji).await?;
+        ctx.send(
+            CreateReply::default()
+                .content(format!(
+                    "Messages matching `{}` will be reacted to with {}",
+                    pattern, emoji
+                ))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Stops reacting to messages matching a pattern.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn remove(
+        ctx: Context<'_>,
+        #[description = "Pattern to stop reacting to"]
+        #[autocomplete = "pattern_autocomplete"]
+        pattern: String,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let removed = remove_reaction_mapping(
+            &ctx.data().db_pool,
+            &ctx.data().reaction_maps,
+            guild_id,
+            &pattern,
+        )
+        .await?;
+        let content = if removed {
+            format!("Messages matching `{}` are no longer reacted to", pattern)
+        } else {
+            format!("There is no reaction for the pattern `{}`", pattern)
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Lists the patterns reacted to and their emojis.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn list(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let mappings =
+            guild_reaction_mappings(&ctx.data().db_pool, &ctx.data().reaction_maps, guild_id).await;
+        let description = if mappings.is_empty() {
+            "No messages are reacted to. Add a reaction with `/reactmap add`.".to_string()
+        } else {
+            mappings
+                .iter()
+                .map(|x| format!("`{}` → {}", x.pattern, x.emoji))
+                .collect::<Vec<String>>()
+                .join("\n")
+        };
+        let embed = guild_branding(ctx)
+            .await
+            .embed()
+            .title("Reaction map")
+            .description(description);
+        ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
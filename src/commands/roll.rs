@@ -1,21 +1,24 @@
 use poise::{
     CreateReply,
-    serenity_prelude::{Colour, CreateAttachment, CreateEmbed, CreateEmbedAuthor},
+    serenity_prelude::{Colour, CreateAttachment, CreateEmbedAuthor, Mentionable, Message, User},
 };
-use rand::Rng;
-use tracing::trace;
+use tracing::{trace, warn};
 
 use crate::{
     Context, Error,
     infrastructure::{
-        environment::get_media_directory,
-        util::{DebuggableReply, defer_or_broadcast},
+        branding::guild_branding,
+        guild_media::{dice_media_key, media_attachment},
+        random::RandomSource,
+        recent_invocations::{Invocation, get_invocation, record_invocation},
+        util::{DebuggableReply, defer_or_broadcast, resolve_ephemeral},
     },
     poise_instrument, record_ctx_fields,
+    services::roll_history::{DieStats, RollHistoryRepo},
 };
 
-#[derive(Debug, poise::ChoiceParameter, Clone, Copy)]
-enum Dice {
+#[derive(Debug, poise::ChoiceParameter, Clone, Copy, PartialEq)]
+pub enum Dice {
     D4 = 4,
     D6 = 6,
     D8 = 8,
@@ -25,7 +28,7 @@ enum Dice {
 }
 
 impl Dice {
-    fn as_str(&self) -> &'static str {
+    pub fn as_str(&self) -> &'static str {
         match self {
             Dice::D4 => "d4",
             Dice::D6 => "d6",
@@ -37,27 +40,26 @@ impl Dice {
     }
 }
 
-fn dice_number(dice: &Dice) -> u8 {
+pub fn dice_number(dice: &Dice) -> u8 {
     *dice as u8
 }
 
-fn roll_dice(dice: &Dice) -> u8 {
-    let mut rng = rand::rng();
-    let value = rng.random_range(1..=dice_number(dice)) as u8;
+fn roll_dice(random: &dyn RandomSource, dice: &Dice) -> u8 {
+    let value = random.random_range(1..=dice_number(dice) as u64) as u8;
     trace!(value = value, "Generated");
     value
 }
 
+/// Gets the image of a side of a dice, as uploaded by the guild or else bundled with the bot.
 async fn get_dice_attachment(
+    ctx: Context<'_>,
     dice: &Dice,
     side: u8,
-) -> Result<CreateAttachment, poise::serenity_prelude::Error> {
-    let path = get_media_directory().join(dice.as_str()).join(format!(
-        "{}-{}.png",
-        dice.as_str(),
-        side.to_string(),
-    ));
-    CreateAttachment::path(path).await
+) -> Result<CreateAttachment, Error> {
+    let key = dice_media_key(dice.as_str(), side);
+    media_attachment(&ctx.data().db_pool, ctx.guild_id(), &key)
+        .await
+        .ok_or_else(|| format!("The image of side {} of the {:?} is missing", side, dice).into())
 }
 
 fn make_color(dice: &Dice, side: u8) -> Colour {
@@ -75,6 +77,74 @@ fn make_description(side: u8) -> String {
     format!("It rolled {}", side)
 }
 
+/// Rolls a dice and sends the result, recording it so it can be rolled again from the message.
+async fn send_roll(ctx: Context<'_>, dice: Dice, ephemeral: bool) -> Result<(), Error> {
+    let side = roll_dice(ctx.data().random.as_ref(), &dice);
+    let attachment = get_dice_attachment(ctx, &dice, side).await?;
+
+    let mut author = CreateEmbedAuthor::new(format!(
+        "{} rolls 1{:?}",
+        ctx.author()
+            .member
+            .as_ref()
+            .and_then(|m| m.nick.clone())
+            .unwrap_or(ctx.author().display_name().to_string()),
+        dice
+    ));
+    let avatar_url = ctx.author().avatar_url();
+    if let Some(s) = avatar_url {
+        author = author.icon_url(s);
+    }
+
+    let branding = guild_branding(ctx).await;
+    let embed = branding
+        .embed()
+        .thumbnail(format!("attachment://{}", attachment.filename))
+        .author(author)
+        .color(make_color(&dice, side))
+        .description(make_description(side));
+
+    let reply = CreateReply::default()
+        .embed(embed)
+        .attachment(attachment)
+        .ephemeral(ephemeral);
+    trace!("Sending reply: {:?}", DebuggableReply::new(&reply));
+    let handle = ctx.send(reply).await?;
+    let message_id = handle.message().await?.id;
+    record_invocation(
+        &ctx.data().recent_invocations,
+        message_id,
+        Invocation::Roll { dice },
+    );
+    // The roll was already shown, so it isn't undone if it can't be counted in the stats
+    if let Err(e) = RollHistoryRepo::new(&ctx.data().db_pool)
+        .record(
+            ctx.guild_id(),
+            ctx.author().id,
+            dice_number(&dice) as u32,
+            side as u32,
+            ctx.data().clock.unix_timestamp(),
+        )
+        .await
+    {
+        warn!("Failed to record the roll of {}: {:?}", ctx.author().id, e);
+    }
+    Ok(())
+}
+
+/// Describes the rolls of one type of die, for a field of `/rollstats`.
+fn describe_die_stats(stats: &DieStats) -> String {
+    format!(
+        "{} roll(s), average **{:.2}** (fair die: {})\nRolled {} {} time(s) and 1 {} time(s)",
+        stats.rolls,
+        stats.average(),
+        stats.expected_average(),
+        stats.sides,
+        stats.maxes,
+        stats.ones
+    )
+}
+
 poise_instrument! {
     // TODO: add modifier and quantity optional parameters
     /// Rolls a dice
@@ -92,37 +162,114 @@ poise_instrument! {
         #[description = "Visible to you only? (default: false)"] ephemeral: Option<bool>,
     ) -> Result<(), Error> {
         record_ctx_fields!(ctx);
-        let _typing = defer_or_broadcast(ctx, ephemeral.unwrap_or_default()).await?;
-
-        let side = roll_dice(&dice);
-        let attachment = get_dice_attachment(&dice, side).await?;
-
-        let mut author = CreateEmbedAuthor::new(format!(
-            "{} rolls 1{:?}",
-            ctx.author()
-                .member
-                .as_ref()
-                .and_then(|m| m.nick.clone())
-                .unwrap_or(ctx.author().display_name().to_string()),
-            dice
-        ));
-        let avatar_url = ctx.author().avatar_url();
-        if let Some(s) = avatar_url {
-            author = author.icon_url(s);
-        }
+        let ephemeral = resolve_ephemeral(ctx, ephemeral, false).await;
+        let _typing = defer_or_broadcast(ctx, ephemeral).await?;
+
+        send_roll(ctx, dice, ephemeral).await
+    }
+
+    /// Rolls the same dice as a roll result again
+    #[poise::command(context_menu_command = "Roll again", category = "Fun")]
+    pub async fn roll_again(
+        ctx: Context<'_>,
+        message: Message,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let Some(Invocation::Roll { dice }) =
+            get_invocation(&ctx.data().recent_invocations, message.id)
+        else {
+            return Err("This message isn't a recent roll".into());
+        };
+        let _typing = defer_or_broadcast(ctx, false).await?;
+        send_roll(ctx, dice, false).await
+    }
+
+    /// Shows how many dice a member rolled, their natural 20s and 1s and their average per die
+    #[poise::command(
+        slash_command,
+        prefix_command,
+        category = "Fun"
+    )]
+    pub async fn rollstats(
+        ctx: Context<'_>,
+        #[description = "Member to show the stats of (default: you)"] user: Option<User>,
+        #[description = "Visible to you only? (default: false)"] ephemeral: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let ephemeral = resolve_ephemeral(ctx, ephemeral, false).await;
+        let _typing = defer_or_broadcast(ctx, ephemeral).await?;
 
-        let embed = CreateEmbed::new()
-            .thumbnail(format!("attachment://{}", attachment.filename))
-            .author(author)
-            .color(make_color(&dice, side))
-            .description(make_description(side));
+        let user = user.as_ref().unwrap_or(ctx.author());
+        let stats = RollHistoryRepo::new(&ctx.data().db_pool)
+            .stats(ctx.guild_id(), user.id)
+            .await?;
+        if stats.is_empty() {
+            return Err(format!("{} hasn't rolled any dice here yet", user.display_name()).into());
+        }
 
-        let reply = CreateReply::default()
-            .embed(embed)
-            .attachment(attachment)
-            .ephemeral(ephemeral.unwrap_or_default());
+        let rolls: i64 = stats.iter().map(|x| x.rolls).sum();
+        let mut description = format!("{} rolled **{}** dice", user.mention(), rolls);
+        if let Some(d20) = stats.iter().find(|x| x.sides == dice_number(&Dice::D20) as u32) {
+            description.push_str(&format!(
+                "\nOn the D20: **{}** natural 20(s) and **{}** natural 1(s)",
+                d20.maxes, d20.ones
+            ));
+        }
+        let branding = guild_branding(ctx).await;
+        let mut embed = branding.embed().title("Roll Stats").description(description);
+        for x in &stats {
+            embed = embed.field(format!("D{}", x.sides), describe_die_stats(x), true);
+        }
+        let reply = CreateReply::default().embed(embed).ephemeral(ephemeral);
         trace!("Sending reply: {:?}", DebuggableReply::new(&reply));
         ctx.send(reply).await?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::infrastructure::random::SeededRandom;
+
+    use super::*;
+
+    #[test]
+    fn rolls_cover_every_side() {
+        let random = SeededRandom::new(3);
+        for dice in [
+            Dice::D4,
+            Dice::D6,
+            Dice::D8,
+            Dice::D10,
+            Dice::D12,
+            Dice::D20,
+        ] {
+            let mut seen = vec![false; dice_number(&dice) as usize];
+            for _ in 0..1000 {
+                let side = roll_dice(&random, &dice);
+                assert!((1..=dice_number(&dice)).contains(&side), "{}", side);
+                seen[side as usize - 1] = true;
+            }
+            assert!(seen.iter().all(|x| *x), "{:?}", dice);
+        }
+    }
+
+    #[test]
+    fn same_seed_rolls_the_same() {
+        let (first, second) = (SeededRandom::new(4), SeededRandom::new(4));
+        for _ in 0..20 {
+            assert_eq!(
+                roll_dice(&first, &Dice::D20),
+                roll_dice(&second, &Dice::D20)
+            );
+        }
+    }
+
+    #[test]
+    fn colors_go_from_red_to_green() {
+        assert_eq!(make_color(&Dice::D20, 1), Colour::from_rgb(255, 0, 0));
+        assert_eq!(make_color(&Dice::D20, 20), Colour::from_rgb(0, 255, 0));
+        assert_eq!(make_description(1), "Critical **FAIL**");
+        assert_eq!(make_description(12), "It rolled 12");
+    }
+}
@@ -0,0 +1,110 @@
+use poise::{
+    CreateReply,
+    serenity_prelude::{CreateEmbedAuthor, Timestamp},
+};
+
+use crate::{
+    Context, Error,
+    events::snipe::{SNIPES_PER_CHANNEL, Snipe, get_snipe},
+    infrastructure::{branding::guild_branding, ids::require_guild_id, util::resolve_ephemeral},
+    poise_instrument, record_ctx_fields,
+};
+
+/// Maximum length of the before and after content of an edit, so both fit in an embed description.
+const MAX_EDIT_CONTENT_LENGTH: usize = 1900;
+
+fn truncate(content: &str, max_length: usize) -> String {
+    if content.chars().count() > max_length {
+        format!(
+            "{}...",
+            content.chars().take(max_length - 3).collect::<String>()
+        )
+    } else {
+        content.to_string()
+    }
+}
+
+async fn send_snipe(
+    ctx: Context<'_>,
+    snipe: Option<Snipe>,
+    ephemeral: bool,
+    edited: bool,
+) -> Result<(), Error> {
+    let Some(snipe) = snipe else {
+        let content = if edited {
+            "There is no recently edited message to snipe in this channel"
+        } else {
+            "There is no recently deleted message to snipe in this channel"
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        return Ok(());
+    };
+
+    let author = match snipe.author.to_user(ctx).await {
+        Ok(user) => CreateEmbedAuthor::new(user.display_name()).icon_url(user.face()),
+        Err(_) => CreateEmbedAuthor::new(snipe.author.to_string()),
+    };
+    let description = match &snipe.edited_content {
+        Some(after) => format!(
+            "**Before**\n{}\n**After**\n{}",
+            truncate(&snipe.content, MAX_EDIT_CONTENT_LENGTH),
+            truncate(after, MAX_EDIT_CONTENT_LENGTH)
+        ),
+        None => snipe.content.clone(),
+    };
+    let action = if edited { "Edited" } else { "Deleted" };
+    let mut embed = guild_branding(ctx)
+        .await
+        .embed()
+        .author(author)
+        .description(description)
+        .field("Sent", format!("<t:{}:R>", snipe.sent_at), true)
+        .field(action, format!("<t:{}:R>", snipe.sniped_at), true);
+    if let Ok(timestamp) = Timestamp::from_unix_timestamp(snipe.sniped_at) {
+        embed = embed.timestamp(timestamp);
+    }
+    ctx.send(CreateReply::default().embed(embed).ephemeral(ephemeral))
+        .await?;
+    Ok(())
+}
+
+poise_instrument! {
+    /// Shows a recently deleted message of this channel
+    #[poise::command(slash_command, prefix_command, guild_only, category = "Fun")]
+    pub async fn snipe(
+        ctx: Context<'_>,
+        #[description = "1 for the latest deleted message, 2 for the one before, ... (default: 1)"]
+        #[min = 1]
+        #[max = 5]
+        index: Option<usize>,
+        #[description = "Visible to you only? (default: false)"] ephemeral: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let ephemeral = resolve_ephemeral(ctx, ephemeral, false).await;
+        let index = index.unwrap_or(1).clamp(1, SNIPES_PER_CHANNEL) - 1;
+        let snipe = get_snipe(ctx.data(), guild_id, ctx.channel_id(), false, index).await;
+        send_snipe(ctx, snipe, ephemeral, false).await
+    }
+}
+
+poise_instrument! {
+    /// Shows a recently edited message of this channel, before and after the edit
+    #[poise::command(slash_command, prefix_command, guild_only, category = "Fun")]
+    pub async fn editsnipe(
+        ctx: Context<'_>,
+        #[description = "1 for the latest edited message, 2 for the one before, ... (default: 1)"]
+        #[min = 1]
+        #[max = 5]
+        index: Option<usize>,
+        #[description = "Visible to you only? (default: false)"] ephemeral: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let ephemeral = resolve_ephemeral(ctx, ephemeral, false).await;
+        let index = index.unwrap_or(1).clamp(1, SNIPES_PER_CHANNEL) - 1;
+        let snipe = get_snipe(ctx.data(), guild_id, ctx.channel_id(), true, index).await;
+        send_snipe(ctx, snipe, ephemeral, true).await
+    }
+}
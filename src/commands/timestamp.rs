@@ -0,0 +1,134 @@
+use poise::{ChoiceParameter, CreateReply, serenity_prelude::CreateEmbedFooter};
+use tracing::{info, trace};
+
+use crate::{
+    Context, Error,
+    infrastructure::{
+        branding::guild_branding,
+        timestamps::{TimestampStyle, format_utc_offset, parse_datetime, parse_utc_offset},
+        util::{DebuggableReply, resolve_ephemeral},
+    },
+    poise_instrument, record_ctx_fields,
+    services::user_timezones::UserTimezoneRepo,
+};
+
+poise_instrument! {
+    /// Converts a time into a Discord timestamp that everyone sees in their own timezone
+    ///
+    /// The time is read in the timezone saved with `/timezone set`, or in UTC without one. Accepts times like `18:30`,
+    /// `tomorrow 6pm`, `friday at 20:00`, `2026-12-31 23:00` or `in 2h`.
+    #[poise::command(slash_command, prefix_command, category = "Fun")]
+    pub async fn timestamp(
+        ctx: Context<'_>,
+        #[description = "Time such as 18:30, tomorrow 6pm, friday at 20:00, 2026-12-31 23:00 or in 2h"]
+        datetime: String,
+        #[description = "Display style (default: all of them)"] format: Option<TimestampStyle>,
+        #[description = "Visible to you only? (default: true)"] ephemeral: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let ephemeral = resolve_ephemeral(ctx, ephemeral, true).await;
+        let utc_offset = UserTimezoneRepo::new(&ctx.data().db_pool)
+            .utc_offset(ctx.author().id)
+            .await?;
+        let timestamp = parse_datetime(
+            &datetime,
+            ctx.data().clock.unix_timestamp(),
+            utc_offset.unwrap_or(0),
+        )?;
+
+        let branding = guild_branding(ctx).await;
+        let mut embed = branding
+            .embed()
+            .title("Timestamp")
+            .description(TimestampStyle::LongDateTime.markup(timestamp));
+        let styles = match format {
+            Some(style) => vec![style],
+            None => TimestampStyle::ALL.to_vec(),
+        };
+        for style in styles {
+            let markup = style.markup(timestamp);
+            embed = embed.field(style.name(), format!("{}\n`{}`", markup, markup), true);
+        }
+        let footer = match utc_offset {
+            Some(offset) => format!("Read in your timezone, {}", format_utc_offset(offset)),
+            None => "Read in UTC. Save your timezone with /timezone set".to_string(),
+        };
+        let reply = CreateReply::default()
+            .embed(embed.footer(CreateEmbedFooter::new(footer)))
+            .ephemeral(ephemeral);
+        trace!("Sending reply: {:?}", DebuggableReply::new(&reply));
+        ctx.send(reply).await?;
+        Ok(())
+    }
+}
+
+/// Set of commands to save the timezone `/timestamp` reads your times in.
+#[poise::command(
+    slash_command,
+    prefix_command,
+    category = "Fun",
+    subcommands("set", "show", "clear")
+)]
+pub async fn timezone(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Saves your timezone as an offset from UTC, like UTC+2 or -05:00
+    ///
+    /// Offsets don't follow daylight saving time, so update yours when your clocks change.
+    #[poise::command(slash_command, prefix_command, category = "Fun")]
+    async fn set(
+        ctx: Context<'_>,
+        #[description = "Offset from UTC, such as UTC+2, -05:00 or +05:30"] offset: String,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let offset = parse_utc_offset(&offset)?;
+        UserTimezoneRepo::new(&ctx.data().db_pool)
+            .set_utc_offset(ctx.author().id, Some(offset))
+            .await?;
+        info!("{} set their timezone to {}", ctx.author().id, format_utc_offset(offset));
+        ctx.send(
+            CreateReply::default()
+                .content(format!(
+                    "Your timezone is now {}. Times given to `/timestamp` are read in it.",
+                    format_utc_offset(offset)
+                ))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Shows the timezone your times are read in
+    #[poise::command(slash_command, prefix_command, category = "Fun")]
+    async fn show(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let offset = UserTimezoneRepo::new(&ctx.data().db_pool)
+            .utc_offset(ctx.author().id)
+            .await?;
+        let content = match offset {
+            Some(offset) => format!("Your timezone is {}.", format_utc_offset(offset)),
+            None => "You haven't saved a timezone, so your times are read in UTC.".to_string(),
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Forgets your timezone, reading your times in UTC again
+    #[poise::command(slash_command, prefix_command, category = "Fun")]
+    async fn clear(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        UserTimezoneRepo::new(&ctx.data().db_pool)
+            .set_utc_offset(ctx.author().id, None)
+            .await?;
+        ctx.send(
+            CreateReply::default()
+                .content("Your timezone was forgotten, your times are read in UTC again.")
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+}
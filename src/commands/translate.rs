@@ -0,0 +1,65 @@
+use poise::{ChoiceParameter as _, CreateReply, serenity_prelude::CreateEmbed};
+
+use crate::{
+    Context, Error,
+    infrastructure::{
+        branding::{Branding, guild_branding},
+        translation::{Language, Translation, describe_language, translation_provider},
+        util::{defer_or_broadcast, resolve_ephemeral},
+    },
+    poise_instrument, record_ctx_fields,
+};
+
+/// Maximum length of the text sent to the provider.
+pub const MAX_TRANSLATION_LENGTH: usize = 2000;
+
+/// Describes a translation, with its source and target languages.
+pub fn translation_embed(
+    branding: &Branding,
+    translation: &Translation,
+    target: Language,
+    provider: &str,
+) -> CreateEmbed {
+    let source = translation
+        .source
+        .as_deref()
+        .map(describe_language)
+        .unwrap_or("Unknown".to_string());
+    branding
+        .embed()
+        .description(&translation.text)
+        .field("From", source, true)
+        .field("To", target.name(), true)
+        .field("Translated by", provider, true)
+}
+
+poise_instrument! {
+    /// Translates text to another language, detecting its language
+    #[poise::command(slash_command, category = "Fun")]
+    pub async fn translate(
+        ctx: Context<'_>,
+        #[description = "Text to translate"] text: String,
+        #[description = "Language to translate to (default: English)"] target: Option<Language>,
+        #[description = "Visible to you only? (default: false)"] ephemeral: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let provider =
+            translation_provider().ok_or("Translations are not configured on this bot")?;
+        if text.chars().count() > MAX_TRANSLATION_LENGTH {
+            return Err(
+                format!("Text can be at most {} characters long", MAX_TRANSLATION_LENGTH).into()
+            );
+        }
+        let target = target.unwrap_or_default();
+        let ephemeral = resolve_ephemeral(ctx, ephemeral, false).await;
+        let _typing = defer_or_broadcast(ctx, ephemeral).await?;
+
+        let translation = provider.translate(&text, target).await?;
+        let branding = guild_branding(ctx).await;
+        let embed = translation_embed(&branding, &translation, target, provider.name())
+            .title("Translation");
+        ctx.send(CreateReply::default().embed(embed).ephemeral(ephemeral))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use migration::OnConflict;
+use poise::{
+    CreateReply,
+    serenity_prelude::futures::{self, Stream, StreamExt},
+};
+use sea_orm::{ActiveValue::Set, ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::{
+    Context, Error,
+    entities::unfurl_domain,
+    events::unfurl::normalize_domain,
+    infrastructure::ids::{id_to_string, require_guild_id},
+    poise_instrument, record_ctx_fields,
+};
+
+/// Set of commands to choose the domains whose links get a preview embed.
+#[poise::command(
+    slash_command,
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR",
+    guild_only,
+    category = "Management",
+    subcommands("add", "remove", "list")
+)]
+pub async fn unfurl(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+async fn domain_autocomplete<'a>(
+    ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Stream<Item = String> + 'a {
+    let partial = partial.to_lowercase();
+    let domains: Vec<String> = match ctx.guild_id() {
+        Some(guild_id) => unfurl_domain::Entity::find()
+            .filter(unfurl_domain::Column::GuildId.eq(id_to_string(guild_id)))
+            .all(&ctx.data().db_pool)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(|x| x.domain)
+            .filter(|x| x.contains(&partial))
+            .collect(),
+        None => vec![],
+    };
+    futures::stream::iter(domains).boxed()
+}
+
+poise_instrument! {
+    /// Posts a preview of links to a domain and its subdomains.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn add(
+        ctx: Context<'_>,
+        #[description = "Domain to preview links of, such as example.com"] domain: String,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let domain = normalize_domain(&domain)
+            .ok_or_else(|| format!("'{}' is not a domain like example.com", domain))?;
+        unfurl_domain::Entity::insert(unfurl_domain::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            domain: Set(domain.clone()),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                unfurl_domain::Column::GuildId,
+                unfurl_domain::Column::Domain,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .exec_without_returning(&ctx.data().db_pool)
+        .await?;
+        ctx.send(
+            CreateReply::default()
+                .content(format!("Links to `{}` get a preview", domain))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Stops posting previews of links to a domain.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn remove(
+        ctx: Context<'_>,
+        #[description = "Domain to stop previewing links of"]
+        #[autocomplete = "domain_autocomplete"]
+        domain: String,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let domain = normalize_domain(&domain).unwrap_or(domain);
+        let result = unfurl_domain::Entity::delete_by_id((id_to_string(guild_id), domain.clone()))
+            .exec(&ctx.data().db_pool)
+            .await?;
+        let content = if result.rows_affected > 0 {
+            format!("Links to `{}` no longer get a preview", domain)
+        } else {
+            format!("`{}` is not a previewed domain", domain)
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Lists the domains whose links get a preview on this guild.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn list(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let domains: Vec<String> = unfurl_domain::Entity::find()
+            .filter(unfurl_domain::Column::GuildId.eq(id_to_string(guild_id)))
+            .all(&ctx.data().db_pool)
+            .await?
+            .into_iter()
+            .map(|x| format!("`{}`", x.domain))
+            .collect();
+        let content = if domains.is_empty() {
+            "No domains get link previews on this guild".to_string()
+        } else {
+            format!("Link previews are posted for: {}", domains.join(", "))
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
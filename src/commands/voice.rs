@@ -1,9 +1,17 @@
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use crate::{
     Context, Error,
-    infrastructure::{environment::get_media_directory, ids::require_guild_id},
+    commands::lyrics::lyrics,
+    infrastructure::{
+        branding::guild_branding,
+        ids::require_guild_id,
+        media_library::{find_media_file, media_files, media_folder},
+        play_history::{get_play_history, record_play},
+        util::Paginator,
+    },
     poise_instrument, record_ctx_fields,
 };
 use poise::CreateReply;
@@ -11,18 +19,28 @@ use poise::serenity_prelude::ChannelId;
 use poise::serenity_prelude::GuildId;
 use poise::serenity_prelude::async_trait;
 use poise::serenity_prelude::prelude::TypeMapKey;
+use poise::serenity_prelude::{
+    self as serenity, ButtonStyle, ComponentInteraction, CreateActionRow, CreateButton,
+    CreateInteractionResponse, CreateInteractionResponseMessage,
+};
 use songbird::error::JoinError;
 use songbird::events::{Event, EventContext, EventHandler as VoiceEventHandler, TrackEvent};
-use songbird::tracks::TrackHandle;
+use songbird::input::AuxMetadata;
+use songbird::tracks::{PlayMode, TrackHandle};
 use tracing::error;
 use tracing::trace;
 use tracing::warn;
 
+/// Maximum number of choices discord shows for an autocompleted parameter.
+const MAX_AUTOCOMPLETE_CHOICES: usize = 25;
+
 /// Set of commands to play/stop playing audio in voice channel
 #[cfg(feature = "youtube")]
 #[poise::command(
     slash_command,
-    subcommands("mariah", "stop", "youtube"),
+    subcommands(
+        "mariah", "media", "stop", "youtube", "lyrics", "history", "replay", "autoplay"
+    ),
     required_permissions = "USE_SOUNDBOARD",
     default_member_permissions = "USE_SOUNDBOARD"
 )]
@@ -34,7 +52,7 @@ pub async fn play(_ctx: Context<'_>) -> Result<(), Error> {
 #[cfg(not(feature = "youtube"))]
 #[poise::command(
     slash_command,
-    subcommands("mariah", "stop"),
+    subcommands("mariah", "media", "stop", "lyrics", "history"),
     required_permissions = "USE_SOUNDBOARD",
     default_member_permissions = "USE_SOUNDBOARD"
 )]
@@ -42,87 +60,161 @@ pub async fn play(_ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
-poise_instrument! {
-    /// Plays mariah carey christmas music in voice
-    #[poise::command(slash_command, guild_only)]
-    pub async fn mariah(ctx: Context<'_>, channel: Option<ChannelId>) -> Result<(), Error> {
-        record_ctx_fields!(ctx);
-        let file = get_media_directory().join("opus").join("mariah.opus");
-        let guild_id = require_guild_id(ctx)?;
-        let channel_id = match channel {
-            Some(x) => Ok(x),
-            None => {
-                let voice_state = guild_id
-                    .get_user_voice_state(&ctx.serenity_context().http, ctx.author().id)
-                    .await?;
+/// Joins the voice channel of a command and plays a local audio file in it, replying with `label` as what's playing.
+async fn play_local_file(
+    ctx: Context<'_>,
+    channel: Option<ChannelId>,
+    file: PathBuf,
+    metadata: AuxMetadata,
+    label: &str,
+) -> Result<(), Error> {
+    let guild_id = require_guild_id(ctx)?;
+    let channel_id = match channel {
+        Some(x) => Ok(x),
+        None => {
+            let voice_state = guild_id
+                .get_user_voice_state(&ctx.serenity_context().http, ctx.author().id)
+                .await?;
 
-                voice_state
-                    .channel_id
-                    .ok_or::<Error>("You must specify a channel or be in a voice channel.".into())
-            }
-        }?;
+            voice_state
+                .channel_id
+                .ok_or::<Error>("You must specify a channel or be in a voice channel.".into())
+        }
+    }?;
 
-        let voice_manager = songbird::get(ctx.serenity_context())
-            .await
-            .expect("Songbird Voice Client registered at startup")
-            .clone();
+    let voice_manager = songbird::get(ctx.serenity_context())
+        .await
+        .expect("Songbird Voice Client registered at startup")
+        .clone();
 
-        match voice_manager.join(guild_id, channel_id).await {
-            Ok(_) => match play_from_file(ctx, file).await {
-                Ok(track) => {
-                    track.add_event(
-                        Event::Track(TrackEvent::End),
-                        TrackEndNotifier {
-                            guild_id,
-                            manager: voice_manager.clone(),
-                        },
-                    )?;
-                    ctx.send(
-                        CreateReply::default()
-                            .content("Playing mariah carey!")
-                            .reply(true),
-                    )
-                    .await?;
-                }
-                Err(play_err) => {
-                    warn!(
-                        guild_id = guild_id.get(),
-                        channel_id = channel_id.get(),
-                        "Voice manager had an error attempting to play mariah carey: {:?}",
-                        play_err
-                    );
-                    ctx.send(
-                        CreateReply::default()
-                            .content("Cannot play mariah carey... :(")
-                            .ephemeral(true)
-                            .reply(true),
-                    )
-                    .await?;
-                }
-            },
-            Err(join_err) => {
+    match voice_manager.join(guild_id, channel_id).await {
+        Ok(_) => match play_from_file(ctx, file).await {
+            Ok(track) => {
+                let title = match (&metadata.artist, &metadata.track) {
+                    (Some(artist), Some(track)) => format!("{} - {}", artist, track),
+                    (None, Some(track)) => track.clone(),
+                    _ => label.to_string(),
+                };
+                record_play(
+                    &ctx.data().db_pool,
+                    ctx.data().clock.as_ref(),
+                    guild_id,
+                    ctx.author().id,
+                    &title,
+                    "",
+                )
+                .await;
+                let now_playing = now_playing_map(ctx.serenity_context()).await;
+                set_now_playing(
+                    &now_playing,
+                    guild_id,
+                    Some(NowPlaying {
+                        metadata,
+                        track: track.clone(),
+                    }),
+                );
+                track.add_event(
+                    Event::Track(TrackEvent::End),
+                    TrackEndNotifier {
+                        guild_id,
+                        manager: voice_manager.clone(),
+                        now_playing,
+                        #[cfg(feature = "youtube")]
+                        autoplay: None,
+                    },
+                )?;
+                ctx.send(
+                    CreateReply::default()
+                        .content(format!("Playing {}!", label))
+                        .components(playback_components())
+                        .reply(true),
+                )
+                .await?;
+            }
+            Err(play_err) => {
                 warn!(
                     guild_id = guild_id.get(),
                     channel_id = channel_id.get(),
-                    "Voice manager had an error while joining channel: {:?}",
-                    join_err
+                    "Voice manager had an error attempting to play {}: {:?}",
+                    label,
+                    play_err
                 );
                 ctx.send(
                     CreateReply::default()
-                        .content("Cannot join channel...")
+                        .content(format!("Cannot play {}... :(", label))
                         .ephemeral(true)
                         .reply(true),
                 )
                 .await?;
             }
+        },
+        Err(join_err) => {
+            warn!(
+                guild_id = guild_id.get(),
+                channel_id = channel_id.get(),
+                "Voice manager had an error while joining channel: {:?}",
+                join_err
+            );
+            ctx.send(
+                CreateReply::default()
+                    .content("Cannot join channel...")
+                    .ephemeral(true)
+                    .reply(true),
+            )
+            .await?;
         }
-        Ok(())
+    }
+    Ok(())
+}
+
+#[tracing::instrument(level = tracing::Level::TRACE, skip(_ctx))]
+async fn media_autocomplete(_ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let partial = partial.to_lowercase();
+    media_files()
+        .into_iter()
+        .map(|x| x.name)
+        .filter(|x| x.to_lowercase().contains(&partial))
+        .take(MAX_AUTOCOMPLETE_CHOICES)
+        .collect()
+}
+
+poise_instrument! {
+    /// Plays mariah carey christmas music in voice
+    #[poise::command(slash_command, guild_only)]
+    pub async fn mariah(ctx: Context<'_>, channel: Option<ChannelId>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let file = media_folder().join("mariah.opus");
+        let metadata = AuxMetadata {
+            track: Some("All I Want for Christmas Is You".into()),
+            artist: Some("Mariah Carey".into()),
+            ..Default::default()
+        };
+        play_local_file(ctx, channel, file, metadata, "mariah carey").await
+    }
+
+    /// Plays an audio file from the media folder of the bot in voice
+    #[poise::command(slash_command, guild_only)]
+    pub async fn media(
+        ctx: Context<'_>,
+        #[description = "Name of the audio file"]
+        #[autocomplete = "media_autocomplete"]
+        name: String,
+        channel: Option<ChannelId>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let file = find_media_file(&name)
+            .ok_or_else(|| format!("There is no audio file named '{}'", name))?;
+        let metadata = AuxMetadata {
+            track: Some(file.name.clone()),
+            ..Default::default()
+        };
+        play_local_file(ctx, channel, file.path, metadata, &file.name).await
     }
 }
 
 #[cfg(feature = "youtube")]
 #[tracing::instrument(level = tracing::Level::TRACE, skip(ctx))]
-async fn youtube_search_autocomplete<'a>(
+pub async fn youtube_search_autocomplete<'a>(
     ctx: Context<'a>,
     partial: &'a str,
 ) -> impl poise::serenity_prelude::futures::Stream<Item = String> + 'a {
@@ -161,77 +253,141 @@ poise_instrument! {
         channel: Option<ChannelId>,
     ) -> Result<(), Error> {
         record_ctx_fields!(ctx);
+        play_youtube_in_channel(ctx, video, channel).await
+    }
+}
 
-        ctx.defer().await?;
-        let guild_id = require_guild_id(ctx)?;
-        let channel_id = match channel {
-            Some(x) => Ok(x),
-            None => {
-                let voice_state = guild_id
-                    .get_user_voice_state(&ctx.serenity_context().http, ctx.author().id)
-                    .await?;
+/// Joins a voice channel and plays a youtube video or search result, recording it in the play history.
+#[cfg(feature = "youtube")]
+async fn play_youtube_in_channel(
+    ctx: Context<'_>,
+    video: String,
+    channel: Option<ChannelId>,
+) -> Result<(), Error> {
+    use crate::commands::voice_limits::PlaybackLimitError;
 
-                voice_state
-                    .channel_id
-                    .ok_or::<Error>("You must specify a channel or be in a voice channel.".into())
-            }
-        }?;
+    ctx.defer().await?;
+    let guild_id = require_guild_id(ctx)?;
+    let channel_id = match channel {
+        Some(x) => Ok(x),
+        None => {
+            let voice_state = guild_id
+                .get_user_voice_state(&ctx.serenity_context().http, ctx.author().id)
+                .await?;
 
-        let voice_manager = songbird::get(ctx.serenity_context())
-            .await
-            .expect("Songbird Voice Client registered at startup")
-            .clone();
+            voice_state
+                .channel_id
+                .ok_or::<Error>("You must specify a channel or be in a voice channel.".into())
+        }
+    }?;
 
-        match voice_manager.join(guild_id, channel_id).await {
-            Ok(_) => match play_from_youtube(ctx, video.into()).await {
-                Ok((meta, track)) => {
-                    track.add_event(
-                        Event::Track(TrackEvent::End),
-                        TrackEndNotifier {
-                            guild_id,
-                            manager: voice_manager.clone(),
-                        },
-                    )?;
-                    let reply = match meta {
-                        Some(meta) => CreateReply::default().embed(get_track_embed(meta)),
-                        None => CreateReply::default().content("Playing from youtube"),
-                    };
-                    ctx.send(reply.reply(true)).await?;
-                }
-                Err(play_err) => {
-                    warn!(
-                        guild_id = guild_id.get(),
-                        channel_id = channel_id.get(),
-                        "Voice manager had an error attempting to play video: {:?}",
-                        play_err
-                    );
-                    ctx.send(
-                        CreateReply::default()
-                            .content("Cannot play video... :(")
-                            .ephemeral(true)
-                            .reply(true),
-                    )
+    let voice_manager = songbird::get(ctx.serenity_context())
+        .await
+        .expect("Songbird Voice Client registered at startup")
+        .clone();
+
+    match voice_manager.join(guild_id, channel_id).await {
+        Ok(_) => match play_from_youtube(ctx, video.clone()).await {
+            Ok((meta, track)) => {
+                let title = meta
+                    .as_ref()
+                    .and_then(|x| x.track.clone().or(x.title.clone()))
+                    .unwrap_or(video.clone());
+                let url = meta
+                    .as_ref()
+                    .and_then(|x| x.source_url.clone())
+                    .unwrap_or(video);
+                record_play(
+                    &ctx.data().db_pool,
+                    ctx.data().clock.as_ref(),
+                    guild_id,
+                    ctx.author().id,
+                    &title,
+                    &url,
+                )
+                .await;
+                let now_playing = now_playing_map(ctx.serenity_context()).await;
+                set_now_playing(
+                    &now_playing,
+                    guild_id,
+                    Some(NowPlaying {
+                        metadata: meta.clone().unwrap_or_default(),
+                        track: track.clone(),
+                    }),
+                );
+                track.add_event(
+                    Event::Track(TrackEvent::End),
+                    TrackEndNotifier {
+                        guild_id,
+                        manager: voice_manager.clone(),
+                        now_playing,
+                        autoplay: Some(AutoplayContext {
+                            guilds: autoplay_guilds(ctx.serenity_context()).await,
+                            http: ctx.serenity_context().http.clone(),
+                            http_client: http_client(ctx.serenity_context()).await,
+                            db: ctx.data().db_pool.clone(),
+                            clock: ctx.data().clock.clone(),
+                            text_channel: ctx.channel_id(),
+                            requested_by: ctx.author().id,
+                        }),
+                    },
+                )?;
+                let branding = guild_branding(ctx).await;
+                let reply = match meta {
+                    Some(meta) => CreateReply::default().embed(get_track_embed(meta, &branding)),
+                    None => CreateReply::default().content("Playing from youtube"),
+                };
+                ctx.send(reply.components(playback_components()).reply(true))
                     .await?;
+            }
+            Err(play_err) if play_err.is::<PlaybackLimitError>() => {
+                trace!("Track rejected by voice limits: {}", play_err);
+                // Leave again if the bot only joined to play the rejected track.
+                let playing = get_now_playing(ctx, guild_id).await.is_some();
+                if !playing && let Err(e) = voice_manager.remove(guild_id).await {
+                    warn!("Failed to leave voice channel: {:?}", e);
                 }
-            },
-            Err(join_err) => {
+                ctx.send(
+                    CreateReply::default()
+                        .content(play_err.to_string())
+                        .ephemeral(true)
+                        .reply(true),
+                )
+                .await?;
+            }
+            Err(play_err) => {
                 warn!(
                     guild_id = guild_id.get(),
                     channel_id = channel_id.get(),
-                    "Voice manager had an error while joining channel: {:?}",
-                    join_err
+                    "Voice manager had an error attempting to play video: {:?}",
+                    play_err
                 );
                 ctx.send(
                     CreateReply::default()
-                        .content("Cannot join channel...")
+                        .content("Cannot play video... :(")
                         .ephemeral(true)
                         .reply(true),
                 )
                 .await?;
             }
+        },
+        Err(join_err) => {
+            warn!(
+                guild_id = guild_id.get(),
+                channel_id = channel_id.get(),
+                "Voice manager had an error while joining channel: {:?}",
+                join_err
+            );
+            ctx.send(
+                CreateReply::default()
+                    .content("Cannot join channel...")
+                    .ephemeral(true)
+                    .reply(true),
+            )
+            .await?;
         }
-        Ok(())
     }
+    Ok(())
 }
 
 poise_instrument! {
@@ -244,6 +400,8 @@ poise_instrument! {
             .expect("Songbird Voice Client registered at startup")
             .clone();
         let guild_id = require_guild_id(ctx)?;
+        let now_playing = now_playing_map(ctx.serenity_context()).await;
+        set_now_playing(&now_playing, guild_id, None);
         match voice_manager.remove(guild_id).await {
             Ok(_) => Ok::<(), Error>(()),
             Err(join_error) => match join_error {
@@ -268,23 +426,195 @@ poise_instrument! {
     }
 }
 
+/// Number of tracks shown on each page of the play history.
+const HISTORY_PAGE_SIZE: usize = 10;
+
+/// Maximum number of tracks shown in the play history.
+const HISTORY_LIMIT: u64 = 100;
+
+poise_instrument! {
+    /// Lists the tracks recently played on this guild.
+    #[poise::command(slash_command, guild_only)]
+    pub async fn history(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let history = get_play_history(&ctx.data().db_pool, guild_id, HISTORY_LIMIT).await?;
+        if history.is_empty() {
+            ctx.send(
+                CreateReply::default()
+                    .content("Nothing has been played on this guild yet.")
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let branding = guild_branding(ctx).await;
+        let pages = history
+            .chunks(HISTORY_PAGE_SIZE)
+            .enumerate()
+            .map(|(page, entries)| {
+                let lines = entries
+                    .iter()
+                    .enumerate()
+                    .map(|(i, entry)| {
+                        let title = if entry.url.starts_with("http") {
+                            format!("[{}]({})", entry.title, entry.url)
+                        } else {
+                            entry.title.clone()
+                        };
+                        format!(
+                            "`{}` {} - <@{}> <t:{}:R>",
+                            page * HISTORY_PAGE_SIZE + i + 1,
+                            title,
+                            entry.user_id,
+                            entry.played_at
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                branding.embed().title("Play History").description(lines)
+            })
+            .collect();
+        Paginator::new(pages).ephemeral(true).send(ctx).await
+    }
+}
+
+poise_instrument! {
+    /// Plays a track from the play history again.
+    #[cfg(feature = "youtube")]
+    #[poise::command(slash_command, guild_only)]
+    pub async fn replay(
+        ctx: Context<'_>,
+        #[description = "Position in /play history (1 is the most recent track)"]
+        #[min = 1]
+        index: u32,
+        channel: Option<ChannelId>,
+    ) -> Result<(), Error> {
+        use crate::infrastructure::play_history::get_play_history_entry;
+
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let entry = get_play_history_entry(&ctx.data().db_pool, guild_id, index as u64)
+            .await?
+            .ok_or::<Error>(format!("There is no track at position {} of the play history.", index).into())?;
+        if entry.url.is_empty() {
+            return Err(format!("'{}' can't be replayed.", entry.title).into());
+        }
+        play_youtube_in_channel(ctx, entry.url, channel).await
+    }
+}
+
+#[cfg(feature = "youtube")]
+#[derive(Debug, poise::ChoiceParameter)]
+pub enum AutoplayMode {
+    #[name = "on"]
+    On,
+    #[name = "off"]
+    Off,
+}
+
+poise_instrument! {
+    /// Queues a related video when playback ends, instead of leaving the voice channel.
+    #[cfg(feature = "youtube")]
+    #[poise::command(slash_command, guild_only)]
+    pub async fn autoplay(
+        ctx: Context<'_>,
+        #[description = "Turn autoplay on or off"] mode: AutoplayMode,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let guilds = autoplay_guilds(ctx.serenity_context()).await;
+        let content = match guilds.write() {
+            Ok(mut guilds) => match mode {
+                AutoplayMode::On => {
+                    guilds.insert(guild_id);
+                    "Autoplay is on, a related video is played when the queue runs out."
+                }
+                AutoplayMode::Off => {
+                    guilds.remove(&guild_id);
+                    "Autoplay is off, I leave the voice channel when the queue runs out."
+                }
+            },
+            Err(_) => return Err("Autoplay state is poisoned".into()),
+        };
+        ctx.send(CreateReply::default().content(content).reply(true))
+            .await?;
+        Ok(())
+    }
+}
+
+/// Number of recently played tracks that autoplay avoids repeating.
+#[cfg(feature = "youtube")]
+const AUTOPLAY_HISTORY_LIMIT: u64 = 50;
+
+/// Extracts the id of a youtube video from its url.
+#[cfg(feature = "youtube")]
+fn youtube_video_id(url: &str) -> Option<String> {
+    let url = reqwest::Url::parse(url).ok()?;
+    let id = match url.host_str()? {
+        "youtu.be" => url.path_segments()?.next()?.to_string(),
+        host if host.ends_with("youtube.com") => match url.path_segments()?.collect::<Vec<_>>()[..]
+        {
+            ["shorts", id] | ["live", id] => id.to_string(),
+            _ => url
+                .query_pairs()
+                .find(|(key, _)| key == "v")
+                .map(|(_, value)| value.into_owned())?,
+        },
+        _ => return None,
+    };
+    Some(id).filter(|x| !x.is_empty())
+}
+
+/// Gets the urls of videos related to a youtube video, most related first.
+///
+/// Uses the youtube "mix" playlist of the video, which yt-dlp can list without downloading anything.
+#[cfg(feature = "youtube")]
+async fn fetch_related_videos(url: &str) -> Result<Vec<String>, Error> {
+    let id = youtube_video_id(url).ok_or("Only youtube videos have related videos")?;
+    let mix = format!("https://www.youtube.com/watch?v={0}&list=RD{0}", id);
+    let output = tokio::process::Command::new("yt-dlp")
+        .args([
+            "--flat-playlist",
+            "--no-warnings",
+            "--playlist-items",
+            "2:25",
+            "--print",
+            "url",
+            &mix,
+        ])
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(format!(
+            "yt-dlp exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|x| x.starts_with("http"))
+        .map(String::from)
+        .collect())
+}
+
 #[cfg(feature = "youtube")]
 async fn play_from_youtube(
     ctx: Context<'_>,
     url: String,
 ) -> Result<(Option<songbird::input::AuxMetadata>, TrackHandle), Error> {
+    use crate::commands::voice_limits::{check_voice_limits, get_voice_limits};
     use songbird::input::Compose;
     use songbird::input::YoutubeDl;
 
     let guild_id = require_guild_id(ctx)?;
     let do_search = !url.starts_with("http");
 
-    let http_client = {
-        let data = ctx.serenity_context().data.read().await;
-        data.get::<HttpKey>()
-            .cloned()
-            .expect("Guaranteed to exist in the typemap.")
-    };
+    let http_client = http_client(ctx.serenity_context()).await;
 
     let manager = songbird::get(ctx.serenity_context())
         .await
@@ -292,35 +622,41 @@ async fn play_from_youtube(
         .clone();
 
     if let Some(handler_lock) = manager.get(guild_id) {
-        let mut handler = handler_lock.lock().await;
-        handler.add_global_event(TrackEvent::Error.into(), TrackErrorNotifier);
-
         let mut meta_src = if do_search {
             YoutubeDl::new_search(http_client.clone(), url.clone())
         } else {
             YoutubeDl::new(http_client.clone(), url.clone())
         };
         let play_src = if do_search {
-            YoutubeDl::new_search(http_client, url)
+            YoutubeDl::new_search(http_client, url.clone())
         } else {
-            YoutubeDl::new(http_client, url)
+            YoutubeDl::new(http_client, url.clone())
         };
 
-        let res = tokio::join!(async { meta_src.aux_metadata().await.ok() }, async {
-            handler.play_only_input(play_src.into())
-        });
-        Ok(res)
+        // Limits are checked against the metadata before the track is handed to songbird.
+        let meta = meta_src.aux_metadata().await.ok();
+        if let Some(limits) = get_voice_limits(&ctx.data().db_pool, &guild_id).await {
+            check_voice_limits(&limits, &url, meta.as_ref())?;
+        }
+
+        let mut handler = handler_lock.lock().await;
+        handler.add_global_event(TrackEvent::Error.into(), TrackErrorNotifier);
+        Ok((meta, handler.play_only_input(play_src.into())))
     } else {
         Err("Not in voice channel".into())
     }
 }
 
 #[cfg(feature = "youtube")]
-fn get_track_embed(metadata: songbird::input::AuxMetadata) -> poise::serenity_prelude::CreateEmbed {
-    use poise::serenity_prelude::{CreateEmbed, CreateEmbedAuthor};
+fn get_track_embed(
+    metadata: songbird::input::AuxMetadata,
+    branding: &crate::infrastructure::branding::Branding,
+) -> poise::serenity_prelude::CreateEmbed {
+    use poise::serenity_prelude::CreateEmbedAuthor;
 
-    let mut embd =
-        CreateEmbed::default().title(metadata.track.or(metadata.title).unwrap_or_default());
+    let mut embd = branding
+        .embed()
+        .title(metadata.track.or(metadata.title).unwrap_or_default());
     if let Some(x) = metadata.thumbnail {
         embd = embd.thumbnail(x);
     }
@@ -336,6 +672,131 @@ fn get_track_embed(metadata: songbird::input::AuxMetadata) -> poise::serenity_pr
     embd
 }
 
+/// Looks up a youtube video, or the first result of a search, without playing it.
+#[cfg(feature = "youtube")]
+pub async fn resolve_track(ctx: Context<'_>, query: &str) -> Result<AuxMetadata, Error> {
+    use songbird::input::{Compose, YoutubeDl};
+
+    let http_client = http_client(ctx.serenity_context()).await;
+    let mut source = if query.starts_with("http") {
+        YoutubeDl::new(http_client, query.to_string())
+    } else {
+        YoutubeDl::new_search(http_client, query.to_string())
+    };
+    let meta = source.aux_metadata().await?;
+    if meta.source_url.is_none() {
+        return Err(format!("No video found for '{}'", query).into());
+    }
+    Ok(meta)
+}
+
+/// Tracks queued by [`queue_tracks`], and the tracks it skipped with the reason they were skipped.
+#[cfg(feature = "youtube")]
+pub struct QueuedTracks {
+    pub queued: usize,
+    pub skipped: Vec<String>,
+}
+
+/// Joins a voice channel and queues tracks to play one after the other, replacing what was playing.
+///
+/// Each track is recorded in the play history when it starts. Tracks rejected by the voice limits are skipped, using
+/// the duration in their metadata rather than looking every track up again.
+#[cfg(feature = "youtube")]
+pub async fn queue_tracks(
+    ctx: Context<'_>,
+    channel: Option<ChannelId>,
+    tracks: Vec<AuxMetadata>,
+) -> Result<QueuedTracks, Error> {
+    use crate::commands::voice_limits::{check_voice_limits, get_voice_limits};
+    use songbird::input::YoutubeDl;
+
+    let guild_id = require_guild_id(ctx)?;
+    let channel_id = match channel {
+        Some(x) => Ok(x),
+        None => {
+            let voice_state = guild_id
+                .get_user_voice_state(&ctx.serenity_context().http, ctx.author().id)
+                .await?;
+
+            voice_state
+                .channel_id
+                .ok_or::<Error>("You must specify a channel or be in a voice channel.".into())
+        }
+    }?;
+
+    let limits = get_voice_limits(&ctx.data().db_pool, &guild_id).await;
+    let mut skipped = vec![];
+    let tracks: Vec<(AuxMetadata, String)> = tracks
+        .into_iter()
+        .filter_map(|meta| {
+            let url = meta.source_url.clone()?;
+            if let Some(limits) = &limits
+                && let Err(e) = check_voice_limits(limits, &url, Some(&meta))
+            {
+                skipped.push(format!("{}: {}", meta.title.as_deref().unwrap_or(&url), e));
+                return None;
+            }
+            Some((meta, url))
+        })
+        .collect();
+    if tracks.is_empty() {
+        return Ok(QueuedTracks { queued: 0, skipped });
+    }
+
+    let voice_manager = songbird::get(ctx.serenity_context())
+        .await
+        .expect("Songbird Voice Client registered at startup")
+        .clone();
+    let handler_lock = voice_manager.join(guild_id, channel_id).await?;
+    let http_client = http_client(ctx.serenity_context()).await;
+    let now_playing = now_playing_map(ctx.serenity_context()).await;
+    let autoplay = AutoplayContext {
+        guilds: autoplay_guilds(ctx.serenity_context()).await,
+        http: ctx.serenity_context().http.clone(),
+        http_client: http_client.clone(),
+        db: ctx.data().db_pool.clone(),
+        clock: ctx.data().clock.clone(),
+        text_channel: ctx.channel_id(),
+        requested_by: ctx.author().id,
+    };
+
+    let mut handler = handler_lock.lock().await;
+    handler.queue().stop();
+    handler.stop();
+    set_now_playing(&now_playing, guild_id, None);
+    handler.add_global_event(TrackEvent::Error.into(), TrackErrorNotifier);
+    let queued = tracks.len();
+    for (meta, url) in tracks {
+        let track = handler
+            .enqueue_input(YoutubeDl::new(http_client.clone(), url).into())
+            .await;
+        let start = TrackStartNotifier {
+            guild_id,
+            now_playing: now_playing.clone(),
+            metadata: meta,
+            db: ctx.data().db_pool.clone(),
+            clock: ctx.data().clock.clone(),
+            requested_by: ctx.author().id,
+        };
+        // The first track starts right away, the others are resumed by the queue when their turn comes
+        if handler.queue().len() == 1 {
+            start.started(&track).await;
+        } else {
+            track.add_event(Event::Track(TrackEvent::Play), start)?;
+        }
+        track.add_event(
+            Event::Track(TrackEvent::End),
+            TrackEndNotifier {
+                guild_id,
+                manager: voice_manager.clone(),
+                now_playing: now_playing.clone(),
+                autoplay: Some(autoplay.clone()),
+            },
+        )?;
+    }
+    Ok(QueuedTracks { queued, skipped })
+}
+
 async fn play_from_file(ctx: Context<'_>, file: PathBuf) -> Result<TrackHandle, Error> {
     let guild_id = require_guild_id(ctx)?;
 
@@ -379,9 +840,262 @@ impl TypeMapKey for HttpKey {
     type Value = reqwest::Client;
 }
 
+#[cfg(feature = "youtube")]
+async fn http_client(ctx: &serenity::Context) -> reqwest::Client {
+    let data = ctx.data.read().await;
+    data.get::<HttpKey>()
+        .cloned()
+        .expect("Guaranteed to exist in the typemap.")
+}
+
+pub type AutoplayGuilds = Arc<RwLock<HashSet<GuildId>>>;
+
+/// Guilds that have autoplay turned on.
+pub struct AutoplayKey;
+
+impl TypeMapKey for AutoplayKey {
+    type Value = AutoplayGuilds;
+}
+
+#[cfg(feature = "youtube")]
+async fn autoplay_guilds(ctx: &serenity::Context) -> AutoplayGuilds {
+    let data = ctx.data.read().await;
+    data.get::<AutoplayKey>()
+        .cloned()
+        .expect("Guaranteed to exist in the typemap.")
+}
+
+/// The track currently playing on a guild.
+#[derive(Clone)]
+pub struct NowPlaying {
+    pub metadata: AuxMetadata,
+    pub track: TrackHandle,
+}
+
+pub type NowPlayingMap = Arc<RwLock<HashMap<GuildId, NowPlaying>>>;
+
+/// The track currently playing on each guild.
+pub struct NowPlayingKey;
+
+impl TypeMapKey for NowPlayingKey {
+    type Value = NowPlayingMap;
+}
+
+async fn now_playing_map(ctx: &serenity::Context) -> NowPlayingMap {
+    let data = ctx.data.read().await;
+    data.get::<NowPlayingKey>()
+        .cloned()
+        .expect("Guaranteed to exist in the typemap.")
+}
+
+fn set_now_playing(
+    now_playing: &RwLock<HashMap<GuildId, NowPlaying>>,
+    guild_id: GuildId,
+    playing: Option<NowPlaying>,
+) {
+    match now_playing.write() {
+        Ok(mut map) => {
+            match playing {
+                Some(x) => map.insert(guild_id, x),
+                None => map.remove(&guild_id),
+            };
+        }
+        Err(e) => error!("Now playing state is poisoned: {:?}", e),
+    }
+}
+
+/// Gets the metadata of the track currently playing on a guild, if known.
+pub async fn get_now_playing(ctx: Context<'_>, guild_id: GuildId) -> Option<AuxMetadata> {
+    let now_playing = now_playing_map(ctx.serenity_context()).await;
+    let map = now_playing.read().ok()?;
+    map.get(&guild_id).map(|x| x.metadata.clone())
+}
+
+/// Everything needed to queue a related video from a track end event.
+#[cfg(feature = "youtube")]
+#[derive(Clone)]
+struct AutoplayContext {
+    guilds: AutoplayGuilds,
+    http: Arc<serenity::Http>,
+    http_client: reqwest::Client,
+    db: sea_orm::DatabaseConnection,
+    clock: crate::infrastructure::clock::SharedClock,
+    /// Channel the related videos are announced in.
+    text_channel: ChannelId,
+    /// User who requested the original track, credited in the play history.
+    requested_by: serenity::UserId,
+}
+
+/// Updates the now playing track when a queued track starts, and records it in the play history.
+#[cfg(feature = "youtube")]
+struct TrackStartNotifier {
+    guild_id: GuildId,
+    now_playing: NowPlayingMap,
+    metadata: AuxMetadata,
+    db: sea_orm::DatabaseConnection,
+    clock: crate::infrastructure::clock::SharedClock,
+    requested_by: serenity::UserId,
+}
+
+#[cfg(feature = "youtube")]
+impl TrackStartNotifier {
+    async fn started(&self, track: &TrackHandle) {
+        let url = self.metadata.source_url.clone().unwrap_or_default();
+        let title = self
+            .metadata
+            .track
+            .clone()
+            .or(self.metadata.title.clone())
+            .unwrap_or(url.clone());
+        record_play(
+            &self.db,
+            self.clock.as_ref(),
+            self.guild_id,
+            self.requested_by,
+            &title,
+            &url,
+        )
+        .await;
+        set_now_playing(
+            &self.now_playing,
+            self.guild_id,
+            Some(NowPlaying {
+                metadata: self.metadata.clone(),
+                track: track.clone(),
+            }),
+        );
+    }
+}
+
+#[cfg(feature = "youtube")]
+#[async_trait]
+impl VoiceEventHandler for TrackStartNotifier {
+    async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+        if let EventContext::Track(track_list) = ctx
+            && let Some((_state, handle)) = track_list.first()
+        {
+            self.started(handle).await;
+        }
+        // Play also fires when a paused track is resumed, which isn't a new start
+        Some(Event::Cancel)
+    }
+}
+
 struct TrackEndNotifier {
     guild_id: GuildId,
     manager: Arc<songbird::Songbird>,
+    now_playing: NowPlayingMap,
+    /// Set for tracks that can be followed by a related video.
+    #[cfg(feature = "youtube")]
+    autoplay: Option<AutoplayContext>,
+}
+
+impl TrackEndNotifier {
+    /// Plays a video related to the track that just ended, if autoplay is on for the guild.
+    ///
+    /// Returns true if a video is now playing. Recently played videos and videos rejected by the voice limits are
+    /// skipped.
+    #[cfg(feature = "youtube")]
+    async fn autoplay_next(&self) -> Result<bool, Error> {
+        use crate::commands::voice_limits::{check_voice_limits, get_voice_limits};
+        use songbird::input::{Compose, YoutubeDl};
+
+        let Some(autoplay) = &self.autoplay else {
+            return Ok(false);
+        };
+        let enabled = autoplay
+            .guilds
+            .read()
+            .is_ok_and(|x| x.contains(&self.guild_id));
+        let ended_url = self
+            .now_playing
+            .read()
+            .ok()
+            .and_then(|x| x.get(&self.guild_id)?.metadata.source_url.clone());
+        let Some(ended_url) = ended_url.filter(|_| enabled) else {
+            return Ok(false);
+        };
+
+        let played: HashSet<String> =
+            get_play_history(&autoplay.db, self.guild_id, AUTOPLAY_HISTORY_LIMIT)
+                .await?
+                .iter()
+                .filter_map(|x| youtube_video_id(&x.url))
+                .collect();
+        let limits = get_voice_limits(&autoplay.db, &self.guild_id).await;
+        for url in fetch_related_videos(&ended_url).await? {
+            if youtube_video_id(&url).is_some_and(|id| played.contains(&id)) {
+                continue;
+            }
+            let meta = YoutubeDl::new(autoplay.http_client.clone(), url.clone())
+                .aux_metadata()
+                .await
+                .ok();
+            if let Some(limits) = &limits
+                && let Err(e) = check_voice_limits(limits, &url, meta.as_ref())
+            {
+                trace!("Autoplay skipped {} rejected by voice limits: {}", url, e);
+                continue;
+            }
+
+            let Some(handler_lock) = self.manager.get(self.guild_id) else {
+                return Ok(false);
+            };
+            let track = handler_lock
+                .lock()
+                .await
+                .play_only_input(YoutubeDl::new(autoplay.http_client.clone(), url.clone()).into());
+            let meta = meta.unwrap_or_default();
+            let title = meta
+                .track
+                .clone()
+                .or(meta.title.clone())
+                .unwrap_or(url.clone());
+            record_play(
+                &autoplay.db,
+                autoplay.clock.as_ref(),
+                self.guild_id,
+                autoplay.requested_by,
+                &title,
+                meta.source_url.as_deref().unwrap_or(&url),
+            )
+            .await;
+            set_now_playing(
+                &self.now_playing,
+                self.guild_id,
+                Some(NowPlaying {
+                    metadata: meta.clone(),
+                    track: track.clone(),
+                }),
+            );
+            track.add_event(
+                Event::Track(TrackEvent::End),
+                TrackEndNotifier {
+                    guild_id: self.guild_id,
+                    manager: self.manager.clone(),
+                    now_playing: self.now_playing.clone(),
+                    autoplay: Some(autoplay.clone()),
+                },
+            )?;
+            let branding =
+                crate::infrastructure::branding::load_branding(&autoplay.db, self.guild_id)
+                    .await
+                    .unwrap_or_default();
+            let message = serenity::CreateMessage::new()
+                .content("Autoplaying a related video")
+                .embed(get_track_embed(meta, &branding))
+                .components(playback_components());
+            if let Err(e) = autoplay
+                .text_channel
+                .send_message(&autoplay.http, message)
+                .await
+            {
+                warn!("Failed to announce autoplayed track: {:?}", e);
+            }
+            return Ok(true);
+        }
+        Ok(false)
+    }
 }
 
 #[async_trait]
@@ -392,12 +1106,18 @@ impl VoiceEventHandler for TrackEndNotifier {
             // This fires when the track finishes naturally
             if let Some((_state, _handle)) = track_list.first() {
                 if let Some(handler_lock) = self.manager.get(self.guild_id) {
-                    let handler = handler_lock.lock().await;
+                    let queue_empty = handler_lock.lock().await.queue().is_empty();
 
                     // Only leave if nothing else is playing
-                    if handler.queue().is_empty() {
+                    if queue_empty {
+                        #[cfg(feature = "youtube")]
+                        match self.autoplay_next().await {
+                            Ok(true) => return None,
+                            Ok(false) => {}
+                            Err(e) => warn!("Failed to autoplay a related video: {:?}", e),
+                        }
                         trace!("Queue is empty.. leaving voice channel.");
-                        drop(handler); // lock must be released before calling remove...
+                        set_now_playing(&self.now_playing, self.guild_id, None);
                         match self.manager.remove(self.guild_id).await {
                             Err(err) => {
                                 error!("Failed to leave voice channel: {:?}", err)
@@ -412,3 +1132,135 @@ impl VoiceEventHandler for TrackEndNotifier {
         None
     }
 }
+
+/// Prefix of the custom_id for the playback buttons on now-playing messages. The action follows the prefix.
+pub const PLAYBACK_CONTROL_ID_PREFIX: &str = "voice_control:";
+
+fn playback_components() -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("{}pause", PLAYBACK_CONTROL_ID_PREFIX))
+            .label("Pause/Resume")
+            .emoji('⏯')
+            .style(ButtonStyle::Secondary),
+        CreateButton::new(format!("{}skip", PLAYBACK_CONTROL_ID_PREFIX))
+            .label("Skip")
+            .emoji('⏭')
+            .style(ButtonStyle::Secondary),
+        CreateButton::new(format!("{}stop", PLAYBACK_CONTROL_ID_PREFIX))
+            .label("Stop")
+            .emoji('⏹')
+            .style(ButtonStyle::Danger),
+    ])]
+}
+
+async fn respond_ephemeral(
+    ctx: &serenity::Context,
+    interaction: &ComponentInteraction,
+    content: &str,
+) -> Result<(), Error> {
+    interaction
+        .create_response(
+            ctx,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .ephemeral(true),
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Handles the pause/resume, skip and stop buttons on a now-playing message.
+///
+/// Only members in the same voice channel as the bot may control playback.
+#[tracing::instrument(level = tracing::Level::INFO, err(level = tracing::Level::WARN), skip(ctx, interaction))]
+pub async fn handle_playback_control(
+    ctx: &serenity::Context,
+    interaction: &ComponentInteraction,
+    action: &str,
+) -> Result<(), Error> {
+    let guild_id = interaction
+        .guild_id
+        .ok_or("This function is only available in guilds")?;
+
+    let voice_manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice Client registered at startup")
+        .clone();
+    let call = match voice_manager.get(guild_id) {
+        Some(x) => x,
+        None => {
+            return respond_ephemeral(ctx, interaction, "I am not in any voice channel...").await;
+        }
+    };
+    let call_channel = call.lock().await.current_channel();
+
+    let user_channel = guild_id
+        .get_user_voice_state(&ctx.http, interaction.user.id)
+        .await
+        .ok()
+        .and_then(|state| state.channel_id);
+    if call_channel.is_none() || user_channel.map(songbird::id::ChannelId::from) != call_channel {
+        return respond_ephemeral(
+            ctx,
+            interaction,
+            "You must be in my voice channel to control playback.",
+        )
+        .await;
+    }
+
+    let now_playing = now_playing_map(ctx).await;
+    let current = now_playing
+        .read()
+        .ok()
+        .and_then(|map| map.get(&guild_id).map(|x| x.track.clone()));
+
+    match action {
+        "pause" => {
+            let track = match current {
+                Some(x) => x,
+                None => {
+                    return respond_ephemeral(ctx, interaction, "Nothing is playing right now.")
+                        .await;
+                }
+            };
+            let content = if track.get_info().await?.playing == PlayMode::Pause {
+                track.play()?;
+                "Resumed playback"
+            } else {
+                track.pause()?;
+                "Paused playback"
+            };
+            respond_ephemeral(ctx, interaction, content).await
+        }
+        "skip" => {
+            let handler = call.lock().await;
+            if !handler.queue().is_empty() {
+                handler.queue().skip()?;
+            } else if let Some(track) = current {
+                // Stopping the only track ends playback, which also leaves the voice channel.
+                track.stop()?;
+            }
+            drop(handler);
+            respond_ephemeral(ctx, interaction, "Skipped").await
+        }
+        "stop" => {
+            set_now_playing(&now_playing, guild_id, None);
+            voice_manager.remove(guild_id).await?;
+            interaction
+                .create_response(
+                    ctx,
+                    CreateInteractionResponse::UpdateMessage(
+                        CreateInteractionResponseMessage::new().components(vec![]),
+                    ),
+                )
+                .await?;
+            Ok(())
+        }
+        _ => {
+            trace!("Ignoring unknown playback action: {}", action);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use std::time::Duration;
+
+use poise::{CreateReply, serenity_prelude::GuildId};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, DatabaseConnection, EntityTrait, IntoActiveModel,
+};
+use songbird::input::AuxMetadata;
+use tracing::error;
+
+use crate::{
+    Context, Error,
+    entities::voice_limits,
+    infrastructure::{
+        branding::guild_branding,
+        ids::{id_to_string, require_guild_id},
+    },
+    poise_instrument, record_ctx_fields,
+};
+
+/// A track was rejected by the guild's playback limits. The message is shown to the user.
+#[derive(Debug)]
+pub struct PlaybackLimitError(pub String);
+
+impl std::fmt::Display for PlaybackLimitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for PlaybackLimitError {}
+
+pub async fn get_voice_limits(
+    db: &DatabaseConnection,
+    guild_id: &GuildId,
+) -> Option<voice_limits::Model> {
+    match voice_limits::Entity::find_by_id(id_to_string(*guild_id))
+        .one(db)
+        .await
+    {
+        Ok(model) => model,
+        Err(e) => {
+            error!("Failed to get voice limits: {}", e);
+            None
+        }
+    }
+}
+
+/// Splits the comma separated blocked domains of a configuration.
+fn blocked_domains(limits: &voice_limits::Model) -> Vec<String> {
+    limits
+        .blocked_domains
+        .split(',')
+        .map(|domain| domain.trim().trim_start_matches("www.").to_lowercase())
+        .filter(|domain| !domain.is_empty())
+        .collect()
+}
+
+fn is_blocked_url(url: &str, domains: &[String]) -> bool {
+    let host = match reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.to_lowercase()))
+    {
+        Some(x) => x,
+        None => return false,
+    };
+    domains
+        .iter()
+        .any(|domain| host == *domain || host.ends_with(&format!(".{}", domain)))
+}
+
+/// Checks a track against the guild's playback limits before it is played.
+///
+/// `requested` is the url or search the user requested. Tracks without a reported duration are treated as livestreams.
+pub fn check_voice_limits(
+    limits: &voice_limits::Model,
+    requested: &str,
+    metadata: Option<&AuxMetadata>,
+) -> Result<(), PlaybackLimitError> {
+    let domains = blocked_domains(limits);
+    let source_url = metadata.and_then(|x| x.source_url.as_deref());
+    if [Some(requested), source_url]
+        .into_iter()
+        .flatten()
+        .any(|url| is_blocked_url(url, &domains))
+    {
+        return Err(PlaybackLimitError(
+            "Tracks from this site are blocked on this guild.".into(),
+        ));
+    }
+
+    let max_duration = Duration::from_secs(limits.max_duration_seconds.max(0) as u64);
+    if max_duration.is_zero() && limits.allow_livestreams {
+        return Ok(());
+    }
+    let metadata = metadata.ok_or(PlaybackLimitError(
+        "Couldn't check the length of this track.".into(),
+    ))?;
+    match metadata.duration {
+        None if !limits.allow_livestreams => Err(PlaybackLimitError(
+            "Livestreams are not allowed on this guild.".into(),
+        )),
+        Some(duration) if !max_duration.is_zero() && duration > max_duration => {
+            Err(PlaybackLimitError(format!(
+                "Tracks longer than {} minutes are not allowed on this guild.",
+                max_duration.as_secs().div_ceil(60)
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Set of commands to limit what can be played in voice channels on this guild.
+#[poise::command(
+    slash_command,
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR",
+    guild_only,
+    category = "Management",
+    rename = "voice-limits",
+    subcommands("configure", "status")
+)]
+pub async fn voice_limits(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Configures the maximum track length, blocked sites, and whether livestreams may be played.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn configure(
+        ctx: Context<'_>,
+        #[description = "Maximum track length in minutes (0 is unlimited)"]
+        #[min = 0]
+        max_minutes: Option<u32>,
+        #[description = "Comma separated domains that can't be played from ('-' clears the list)"]
+        blocked_domains: Option<String>,
+        #[description = "Allow livestreams to be played"] allow_livestreams: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+
+        let existing = voice_limits::Entity::find_by_id(id_to_string(guild_id))
+            .one(&ctx.data().db_pool)
+            .await?;
+        let (mut model, update) = match existing {
+            Some(row) => (row.into_active_model(), true),
+            None => (
+                voice_limits::ActiveModel {
+                    guild_id: Set(id_to_string(guild_id)),
+                    ..Default::default()
+                },
+                false,
+            ),
+        };
+
+        if let Some(x) = max_minutes {
+            model.max_duration_seconds = Set(x.saturating_mul(60).min(i32::MAX as u32) as i32);
+        }
+        if let Some(x) = blocked_domains {
+            model.blocked_domains = Set(if x.trim() == "-" { String::new() } else { x });
+        }
+        if let Some(x) = allow_livestreams {
+            model.allow_livestreams = Set(x);
+        }
+
+        if update {
+            model.update(&ctx.data().db_pool).await?;
+        } else {
+            voice_limits::Entity::insert(model)
+                .exec(&ctx.data().db_pool)
+                .await?;
+        }
+
+        ctx.send(
+            CreateReply::default()
+                .content("Successfully configured voice limits")
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Shows the voice playback limits of this guild.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn status(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+
+        let limits = match get_voice_limits(&ctx.data().db_pool, &guild_id).await {
+            Some(x) => x,
+            None => {
+                ctx.send(
+                    CreateReply::default()
+                        .content("No voice limits are configured on this guild")
+                        .ephemeral(true),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let max_duration = if limits.max_duration_seconds > 0 {
+            format!("{} minutes", limits.max_duration_seconds / 60)
+        } else {
+            "Unlimited".to_string()
+        };
+        let domains = blocked_domains(&limits);
+        let domains = if domains.is_empty() {
+            "None".to_string()
+        } else {
+            domains
+                .iter()
+                .map(|x| format!("`{}`", x))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        let branding = guild_branding(ctx).await;
+        let embed = branding.embed()
+            .title("Voice Limits")
+            .field("Maximum length", max_duration, true)
+            .field("Livestreams", limits.allow_livestreams.to_string(), true)
+            .field("Blocked domains", domains, false);
+
+        ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+use poise::{
+    CreateReply,
+    serenity_prelude::{ChannelId, Mentionable, User, UserId},
+};
+use sea_orm::{
+    ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+    sea_query::{Alias, Expr, Func, SimpleExpr},
+};
+
+use crate::{
+    Context, Error,
+    entities::voice_sessions,
+    infrastructure::{
+        branding::guild_branding,
+        ids::{id_from_string, id_to_string, require_guild_id},
+        util::{defer_or_broadcast, resolve_ephemeral},
+    },
+    poise_instrument, record_ctx_fields,
+};
+
+/// Number of entries listed in the favorite channels and leaderboard sections.
+const LIST_LENGTH: u64 = 10;
+
+/// Total seconds of the selected sessions, counting open sessions up to now.
+fn total_seconds(now: i64) -> SimpleExpr {
+    Func::sum(
+        SimpleExpr::from(Func::coalesce([
+            Expr::col(voice_sessions::Column::LeftAt).into(),
+            Expr::val(now).into(),
+        ]))
+        .sub(Expr::col(voice_sessions::Column::JoinedAt)),
+    )
+    .into()
+}
+
+fn format_voice_time(seconds: i64) -> String {
+    let hours = seconds / 3600;
+    let minutes = seconds % 3600 / 60;
+    if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+fn format_list(entries: Vec<String>) -> String {
+    if entries.is_empty() {
+        "None".to_string()
+    } else {
+        entries.join("\n")
+    }
+}
+
+poise_instrument! {
+    /// Shows the time spent in voice channels, favorite channels and the guild leaderboard.
+    #[poise::command(
+        slash_command,
+        prefix_command,
+        guild_only,
+        category = "Management"
+    )]
+    pub async fn voicestats(
+        ctx: Context<'_>,
+        #[description = "Member to show the stats of (default: you)"] user: Option<User>,
+        #[description = "Visible to you only? (default: true)"] ephemeral: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let ephemeral = resolve_ephemeral(ctx, ephemeral, true).await;
+        let _typing = defer_or_broadcast(ctx, ephemeral).await?;
+
+        let guild_id = require_guild_id(ctx)?;
+        let user = user.as_ref().unwrap_or(ctx.author());
+        let db = &ctx.data().db_pool;
+        let now = ctx.data().clock.unix_timestamp();
+
+        let channels = voice_sessions::Entity::find()
+            .select_only()
+            .column(voice_sessions::Column::ChannelId)
+            .column_as(total_seconds(now), "seconds")
+            .filter(voice_sessions::Column::GuildId.eq(id_to_string(guild_id)))
+            .filter(voice_sessions::Column::UserId.eq(id_to_string(user.id)))
+            .group_by(voice_sessions::Column::ChannelId)
+            .order_by_desc(Expr::col(Alias::new("seconds")))
+            .into_tuple::<(String, i64)>()
+            .all(db)
+            .await?;
+        let total: i64 = channels.iter().map(|(_, seconds)| seconds).sum();
+        let favorite_channels = channels
+            .iter()
+            .take(LIST_LENGTH as usize)
+            .filter_map(|(id, seconds)| {
+                id_from_string::<ChannelId>(id)
+                    .ok()
+                    .map(|id| format!("{} — {}", id.mention(), format_voice_time(*seconds)))
+            })
+            .collect();
+
+        let leaderboard = voice_sessions::Entity::find()
+            .select_only()
+            .column(voice_sessions::Column::UserId)
+            .column_as(total_seconds(now), "seconds")
+            .filter(voice_sessions::Column::GuildId.eq(id_to_string(guild_id)))
+            .group_by(voice_sessions::Column::UserId)
+            .order_by_desc(Expr::col(Alias::new("seconds")))
+            .limit(LIST_LENGTH)
+            .into_tuple::<(String, i64)>()
+            .all(db)
+            .await?
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, (id, seconds))| {
+                id_from_string::<UserId>(&id).ok().map(|id| {
+                    format!("**{}.** {} — {}", i + 1, id.mention(), format_voice_time(seconds))
+                })
+            })
+            .collect();
+
+        let branding = guild_branding(ctx).await;
+        let embed = branding.embed()
+            .title("Voice Activity")
+            .description(format!(
+                "{} has spent **{}** in voice channels",
+                user.mention(),
+                format_voice_time(total)
+            ))
+            .field("Favorite channels", format_list(favorite_channels), true)
+            .field("Leaderboard", format_list(leaderboard), true);
+        ctx.send(CreateReply::default().embed(embed).ephemeral(ephemeral))
+            .await?;
+        Ok(())
+    }
+}
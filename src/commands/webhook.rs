@@ -0,0 +1,204 @@
+use poise::CreateReply;
+
+use crate::{
+    Context, Error,
+    infrastructure::{
+        branding::guild_branding,
+        ids::require_guild_id,
+        public_http::check_public_url,
+        webhooks::{EVENT_HEADER, SIGNATURE_HEADER},
+    },
+    poise_instrument, record_ctx_fields,
+    services::webhooks::{MAX_WEBHOOKS, WebhookEvent, WebhookRepo},
+};
+
+/// Number of deliveries shown by `/webhook deliveries`.
+const RECENT_DELIVERIES: u64 = 15;
+
+/// Maximum number of characters of a delivery error shown by `/webhook deliveries`.
+const MAX_ERROR_LENGTH: usize = 100;
+
+/// Set of commands to send guild events to outgoing webhooks.
+#[poise::command(
+    slash_command,
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR",
+    guild_only,
+    category = "Management",
+    subcommands("add", "remove", "list", "deliveries")
+)]
+pub async fn webhook(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Registers a URL that guild events are POSTed to as signed JSON.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn add(
+        ctx: Context<'_>,
+        #[description = "http or https URL to POST events to"] url: String,
+        #[description = "Send member joins and leaves? (default: true)"] member_events: Option<bool>,
+        #[description = "Send bans, unbans, nickname filter renames and raid lockdowns? (default: true)"]
+        mod_actions: Option<bool>,
+        #[description = "Send minecraft servers going online or offline? (default: true)"]
+        mc_status: Option<bool>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let url = url.trim();
+        let parsed = reqwest::Url::parse(url).map_err(|_| format!("'{}' is not a valid URL", url))?;
+        check_public_url(&parsed)?;
+
+        let mut events = vec![];
+        if member_events.unwrap_or(true) {
+            events.extend([WebhookEvent::MemberJoin, WebhookEvent::MemberLeave]);
+        }
+        if mod_actions.unwrap_or(true) {
+            events.push(WebhookEvent::ModAction);
+        }
+        if mc_status.unwrap_or(true) {
+            events.push(WebhookEvent::McStatus);
+        }
+        if events.is_empty() {
+            return Err("Pick at least one kind of event to send".into());
+        }
+
+        let repo = WebhookRepo::new(&ctx.data().db_pool);
+        if repo.list(guild_id).await?.len() >= MAX_WEBHOOKS {
+            return Err(format!("A guild can have at most {} webhooks", MAX_WEBHOOKS).into());
+        }
+        let secret = uuid::Uuid::new_v4().simple().to_string();
+        let id = repo
+            .add(guild_id, url, &secret, &events, ctx.data().clock.unix_timestamp())
+            .await?;
+
+        let events: Vec<&str> = events.iter().map(|x| x.as_str()).collect();
+        ctx.send(
+            CreateReply::default()
+                .content(format!(
+                    "Registered webhook #{} for {}.\nPayloads are signed with the secret `{}`: the `{}` header holds \
+                     `sha256=` followed by the HMAC-SHA256 of the body, and the `{}` header holds the event name. \
+                     Keep the secret somewhere safe, it is not shown again.",
+                    id,
+                    events.join(", "),
+                    secret,
+                    SIGNATURE_HEADER,
+                    EVENT_HEADER
+                ))
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Stops sending events to a webhook.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn remove(
+        ctx: Context<'_>,
+        #[description = "Number of the webhook, as shown by /webhook list"] id: i32,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let removed = WebhookRepo::new(&ctx.data().db_pool)
+            .remove(guild_id, id)
+            .await?;
+        let content = if removed {
+            format!("Removed webhook #{}", id)
+        } else {
+            format!("There is no webhook #{} on this guild", id)
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Lists the webhooks of this guild and the events they receive.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn list(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let webhooks = WebhookRepo::new(&ctx.data().db_pool).list(guild_id).await?;
+        let lines: Vec<String> = webhooks
+            .iter()
+            .map(|webhook| {
+                let events: Vec<&str> = webhook.events.iter().map(|x| x.as_str()).collect();
+                format!("**#{}** `{}` — {}", webhook.id, webhook.url, events.join(", "))
+            })
+            .collect();
+        let description = if lines.is_empty() {
+            "No webhooks are registered. Add one with `/webhook add`.".to_string()
+        } else {
+            lines.join("\n")
+        };
+        let embed = guild_branding(ctx)
+            .await
+            .embed()
+            .title("Webhooks")
+            .description(description);
+        ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Shows the latest deliveries to the webhooks of this guild.
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Management"
+    )]
+    async fn deliveries(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let deliveries = WebhookRepo::new(&ctx.data().db_pool)
+            .recent_deliveries(guild_id, RECENT_DELIVERIES)
+            .await?;
+        let lines: Vec<String> = deliveries
+            .iter()
+            .map(|x| {
+                let outcome = if x.success {
+                    "✅".to_string()
+                } else {
+                    let error: String = x.error.chars().take(MAX_ERROR_LENGTH).collect();
+                    format!("❌ {}", error)
+                };
+                format!(
+                    "<t:{}:R> **#{}** `{}` ({} attempt(s)) {}",
+                    x.delivered_at, x.webhook_id, x.event, x.attempts, outcome
+                )
+            })
+            .collect();
+        let description = if lines.is_empty() {
+            "Nothing was delivered yet.".to_string()
+        } else {
+            lines.join("\n")
+        };
+        let embed = guild_branding(ctx)
+            .await
+            .embed()
+            .title("Webhook deliveries")
+            .description(description);
+        ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+}
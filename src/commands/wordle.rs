@@ -0,0 +1,208 @@
+use poise::CreateReply;
+use poise::serenity_prelude::{ChannelType, CreateEmbedFooter, GuildChannel, Mentionable, User};
+use tracing::{debug, info, trace};
+
+use crate::infrastructure::branding::guild_branding;
+use crate::infrastructure::ids::require_guild_id;
+use crate::infrastructure::util::DebuggableReply;
+use crate::infrastructure::wordle::{
+    MAX_GUESSES, board, daily_word, day_of, result_grid, score_label, validate_guess,
+};
+use crate::services::unit_of_work::UnitOfWork;
+use crate::services::wordle::WordleRepo;
+use crate::{Context, Error, poise_instrument, record_ctx_fields};
+
+/// Daily word game: find the word of the day in six guesses.
+///
+/// Every server has its own word, which changes at midnight UTC. Guesses are only visible to you, and the grid of a
+/// finished game can be shared with `/wordle share` without giving the word away.
+#[poise::command(
+    slash_command,
+    guild_only,
+    category = "Fun",
+    subcommands("guess", "share", "stats", "channel")
+)]
+pub async fn wordle(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+poise_instrument! {
+    /// Guesses the word of the day
+    #[poise::command(slash_command, guild_only, category = "Fun")]
+    async fn guess(
+        ctx: Context<'_>,
+        #[description = "A word of five letters"] word: String,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let user_id = ctx.author().id;
+        let now = ctx.data().clock.unix_timestamp();
+        let day = day_of(now);
+        let answer = daily_word(guild_id, day);
+        let db = &ctx.data().db_pool;
+
+        let mut game = WordleRepo::new(db).game(guild_id, user_id, day).await?;
+        if game.is_finished() {
+            return Err("You already played today's wordle, come back tomorrow!".into());
+        }
+        let word = validate_guess(&word)?;
+        if game.guesses.contains(&word) {
+            return Err(format!("You already guessed '{}' today.", word).into());
+        }
+        game.guesses.push(word.clone());
+        game.solved = word == answer;
+        if game.solved || game.guesses.len() >= MAX_GUESSES {
+            game.finished_at = now;
+        }
+
+        // The game and its result are saved together, so finished games are always counted
+        let uow = UnitOfWork::begin(db).await?;
+        let result = async {
+            let repo = WordleRepo::new(uow.txn());
+            repo.save_game(guild_id, user_id, day, &game).await?;
+            if game.is_finished() {
+                return Ok(Some(repo.record_result(guild_id, user_id, day, game.solved).await?));
+            }
+            Ok(None)
+        }
+        .await;
+        let stats = uow.finish(result).await?;
+        debug!("Guess {} of {} on guild {}: {}", game.guesses.len(), user_id, guild_id, word);
+
+        let branding = guild_branding(ctx).await;
+        let mut embed = branding
+            .embed()
+            .title(format!("Wordle {}/{}", game.guesses.len(), MAX_GUESSES))
+            .description(board(&game, answer));
+        embed = match stats {
+            Some(stats) if game.solved => {
+                info!(
+                    "{} solved the wordle of guild {} in {} guesses",
+                    user_id,
+                    guild_id,
+                    game.guesses.len()
+                );
+                embed.field(
+                    "You found it!",
+                    format!(
+                        "Score {}, streak of {} days. Share your grid with `/wordle share`.",
+                        score_label(&game),
+                        stats.current_streak
+                    ),
+                    false,
+                )
+            }
+            Some(_) => embed.field(
+                "Out of guesses",
+                format!("The word was **{}**. Try again tomorrow!", answer.to_uppercase()),
+                false,
+            ),
+            None => embed.footer(CreateEmbedFooter::new(format!(
+                "{} guesses left",
+                MAX_GUESSES - game.guesses.len()
+            ))),
+        };
+        let reply = CreateReply::default().embed(embed).ephemeral(true);
+        trace!("Sending reply: {:?}", DebuggableReply::new(&reply));
+        ctx.send(reply).await?;
+        Ok(())
+    }
+
+    /// Shares the grid of your wordle of today, without the letters
+    #[poise::command(slash_command, guild_only, category = "Fun")]
+    async fn share(ctx: Context<'_>) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let day = day_of(ctx.data().clock.unix_timestamp());
+        let game = WordleRepo::new(&ctx.data().db_pool)
+            .game(guild_id, ctx.author().id, day)
+            .await?;
+        if !game.is_finished() {
+            return Err("Finish today's wordle with `/wordle guess` before sharing it.".into());
+        }
+
+        let branding = guild_branding(ctx).await;
+        let embed = branding
+            .embed()
+            .title(format!("Wordle {}", score_label(&game)))
+            .description(format!(
+                "{}\n\n{}",
+                ctx.author().mention(),
+                result_grid(&game, daily_word(guild_id, day))
+            ));
+        let reply = CreateReply::default().embed(embed);
+        trace!("Sending reply: {:?}", DebuggableReply::new(&reply));
+        ctx.send(reply).await?;
+        Ok(())
+    }
+
+    /// Shows the wordle stats and streak of a member
+    #[poise::command(slash_command, guild_only, category = "Fun")]
+    async fn stats(
+        ctx: Context<'_>,
+        #[description = "Member to show the stats of (default: you)"] user: Option<User>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        let user = user.as_ref().unwrap_or(ctx.author());
+        let day = day_of(ctx.data().clock.unix_timestamp());
+        let stats = WordleRepo::new(&ctx.data().db_pool)
+            .stats(guild_id, user.id)
+            .await?;
+        let win_rate = (stats.wins * 100).checked_div(stats.played).unwrap_or(0);
+
+        let branding = guild_branding(ctx).await;
+        let embed = branding
+            .embed()
+            .title(format!("Wordle stats of {}", user.name))
+            .field("Played", stats.played.to_string(), true)
+            .field("Wins", format!("{} ({}%)", stats.wins, win_rate), true)
+            .field("Current streak", stats.streak_on(day).to_string(), true)
+            .field("Best streak", stats.max_streak.to_string(), true);
+        let reply = CreateReply::default().embed(embed).ephemeral(true);
+        trace!("Sending reply: {:?}", DebuggableReply::new(&reply));
+        ctx.send(reply).await?;
+        Ok(())
+    }
+
+    /// Sets the channel the results of each day are posted in, or stops posting them
+    #[poise::command(
+        slash_command,
+        required_permissions = "ADMINISTRATOR",
+        default_member_permissions = "ADMINISTRATOR",
+        guild_only,
+        category = "Fun"
+    )]
+    async fn channel(
+        ctx: Context<'_>,
+        #[description = "Channel to post the daily leaderboard in (default: stop posting it)"]
+        #[channel_types("Text", "News")]
+        channel: Option<GuildChannel>,
+    ) -> Result<(), Error> {
+        record_ctx_fields!(ctx);
+        let guild_id = require_guild_id(ctx)?;
+        if let Some(channel) = &channel
+            && (channel.guild_id != guild_id
+                || !matches!(channel.kind, ChannelType::Text | ChannelType::News))
+        {
+            return Err(
+                "The leaderboard can only be posted to text or announcement channels of this server".into(),
+            );
+        }
+        let channel_id = channel.as_ref().map(|x| x.id);
+        WordleRepo::new(&ctx.data().db_pool)
+            .set_leaderboard_channel(guild_id, channel_id)
+            .await?;
+        info!("Set the wordle leaderboard channel of guild {} to {:?}", guild_id, channel_id);
+
+        let content = match channel {
+            Some(channel) => format!(
+                "The wordle leaderboard will be posted in {} every day.",
+                channel.mention()
+            ),
+            None => "The wordle leaderboard will no longer be posted.".to_string(),
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true)).await?;
+        Ok(())
+    }
+}
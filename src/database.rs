@@ -1,8 +1,11 @@
 use anyhow::{Context, Result};
-use imposterbot::infrastructure::environment::{self, env_var_with_context, get_data_directory};
-use migration::{Migrator, MigratorTrait};
+use imposterbot::infrastructure::{
+    environment::{self, auto_migrate_enabled, env_var_with_context, get_data_directory},
+    modules::Migrator,
+};
+use migration::MigratorTrait;
 use sea_orm::{ConnectOptions, Database, DatabaseConnection};
-use tracing::info;
+use tracing::{info, warn};
 
 pub async fn init_database() -> Result<DatabaseConnection> {
     ensure_data_dir_created()?;
@@ -25,6 +28,19 @@ async fn create_db_pool() -> Result<DatabaseConnection> {
 }
 
 async fn init_db(db: &DatabaseConnection) -> Result<()> {
+    if !auto_migrate_enabled() {
+        let pending = Migrator::get_pending_migrations(db)
+            .await
+            .context("Failed to get pending migrations")?;
+        if !pending.is_empty() {
+            warn!(
+                "{} database migrations are pending. Run `/db migrate` to apply them.",
+                pending.len()
+            );
+        }
+        return Ok(());
+    }
+
     let res = Migrator::up(db, None)
         .await
         .context("Failed to migrate database to latest");
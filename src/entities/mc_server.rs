@@ -22,6 +22,16 @@ pub struct Model {
     pub instructions: String,
     #[sea_orm(column_type = "Text")]
     pub thumbnail: String,
+    pub owner_id: String,
+    #[sea_orm(column_type = "Text")]
+    pub modpack_name: String,
+    #[sea_orm(column_type = "Text")]
+    pub modpack_version: String,
+    #[sea_orm(column_type = "Text")]
+    pub modpack_icon: String,
+    pub modpack_fetched_at: i64,
+    #[sea_orm(unique)]
+    pub id: i64,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -9,6 +9,8 @@ pub struct Model {
     pub guild_id: String,
     #[sea_orm(primary_key, auto_increment = false)]
     pub join: bool,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub variant: i32,
     #[sea_orm(column_type = "Text")]
     pub content: String,
     #[sea_orm(column_type = "Text")]
@@ -2,7 +2,79 @@
 
 pub mod prelude;
 
+pub mod ai_settings;
+pub mod aliases;
+pub mod asset_storage_channel;
+pub mod auto_response_channel;
+pub mod auto_response_toggle;
+pub mod auto_slowmode;
+pub mod ban_sync;
+pub mod ban_sync_member;
+pub mod blocked_user;
+pub mod bot_member_settings;
+pub mod bot_settings;
+pub mod channel_activity;
+pub mod character_sheet_fields;
+pub mod coinflip_settings;
+pub mod color_role_settings;
+pub mod color_roles;
+pub mod confession_settings;
+pub mod confessions;
+pub mod define_settings;
+pub mod definition_cache;
+pub mod emoji_stats;
+pub mod ephemeral_settings;
+pub mod gallery_channel;
+pub mod game_night_rsvps;
+pub mod game_night_settings;
+pub mod game_nights;
+pub mod game_server;
+pub mod game_sessions;
+pub mod greeting_settings;
+pub mod guild_branding;
+pub mod guild_media;
+pub mod guild_webhooks;
+pub mod jobs;
+pub mod known_members;
+pub mod leader_lease;
+pub mod leave_notification_settings;
+pub mod lyrics_cache;
 pub mod mc_server;
+pub mod mc_server_samples;
+pub mod mc_settings;
+pub mod mc_status_channel;
 pub mod member_notification_channel;
 pub mod member_notification_message;
+pub mod mod_activity;
+pub mod mod_digest_settings;
+pub mod mod_log_channel;
+pub mod movie_night;
+pub mod name_history;
+pub mod nickname_filter;
+pub mod play_history;
+pub mod playlist_tracks;
+pub mod raid_lockdown;
+pub mod raid_protection;
+pub mod reaction_map;
+pub mod rejoin_settings;
+pub mod retention_policy;
+pub mod roll_history;
+pub mod saved_embeds;
+pub mod scheduled_announcement;
+pub mod screening_questions;
+pub mod screening_settings;
+pub mod snipe_settings;
+pub mod temp_roles;
+pub mod unfurl_domain;
+pub mod user_content_asset;
+pub mod user_content_blobs;
+pub mod user_playlists;
+pub mod user_timezones;
+pub mod voice_announcements;
+pub mod voice_limits;
+pub mod voice_sessions;
+pub mod webhook_deliveries;
 pub mod welcome_roles;
+pub mod wordle_games;
+pub mod wordle_settings;
+pub mod wordle_stats;
@@ -1,6 +1,78 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.19
 
+pub use super::ai_settings::Entity as AiSettings;
+pub use super::aliases::Entity as Aliases;
+pub use super::asset_storage_channel::Entity as AssetStorageChannel;
+pub use super::auto_response_channel::Entity as AutoResponseChannel;
+pub use super::auto_response_toggle::Entity as AutoResponseToggle;
+pub use super::auto_slowmode::Entity as AutoSlowmode;
+pub use super::ban_sync::Entity as BanSync;
+pub use super::ban_sync_member::Entity as BanSyncMember;
+pub use super::blocked_user::Entity as BlockedUser;
+pub use super::bot_member_settings::Entity as BotMemberSettings;
+pub use super::bot_settings::Entity as BotSettings;
+pub use super::channel_activity::Entity as ChannelActivity;
+pub use super::character_sheet_fields::Entity as CharacterSheetFields;
+pub use super::coinflip_settings::Entity as CoinflipSettings;
+pub use super::color_role_settings::Entity as ColorRoleSettings;
+pub use super::color_roles::Entity as ColorRoles;
+pub use super::confession_settings::Entity as ConfessionSettings;
+pub use super::confessions::Entity as Confessions;
+pub use super::define_settings::Entity as DefineSettings;
+pub use super::definition_cache::Entity as DefinitionCache;
+pub use super::emoji_stats::Entity as EmojiStats;
+pub use super::ephemeral_settings::Entity as EphemeralSettings;
+pub use super::gallery_channel::Entity as GalleryChannel;
+pub use super::game_night_rsvps::Entity as GameNightRsvps;
+pub use super::game_night_settings::Entity as GameNightSettings;
+pub use super::game_nights::Entity as GameNights;
+pub use super::game_server::Entity as GameServer;
+pub use super::game_sessions::Entity as GameSessions;
+pub use super::greeting_settings::Entity as GreetingSettings;
+pub use super::guild_branding::Entity as GuildBranding;
+pub use super::guild_media::Entity as GuildMedia;
+pub use super::guild_webhooks::Entity as GuildWebhooks;
+pub use super::jobs::Entity as Jobs;
+pub use super::known_members::Entity as KnownMembers;
+pub use super::leader_lease::Entity as LeaderLease;
+pub use super::leave_notification_settings::Entity as LeaveNotificationSettings;
+pub use super::lyrics_cache::Entity as LyricsCache;
 pub use super::mc_server::Entity as McServer;
+pub use super::mc_server_samples::Entity as McServerSamples;
+pub use super::mc_settings::Entity as McSettings;
+pub use super::mc_status_channel::Entity as McStatusChannel;
 pub use super::member_notification_channel::Entity as MemberNotificationChannel;
 pub use super::member_notification_message::Entity as MemberNotificationMessage;
+pub use super::mod_activity::Entity as ModActivity;
+pub use super::mod_digest_settings::Entity as ModDigestSettings;
+pub use super::mod_log_channel::Entity as ModLogChannel;
+pub use super::movie_night::Entity as MovieNight;
+pub use super::name_history::Entity as NameHistory;
+pub use super::nickname_filter::Entity as NicknameFilter;
+pub use super::play_history::Entity as PlayHistory;
+pub use super::playlist_tracks::Entity as PlaylistTracks;
+pub use super::raid_lockdown::Entity as RaidLockdown;
+pub use super::raid_protection::Entity as RaidProtection;
+pub use super::reaction_map::Entity as ReactionMap;
+pub use super::rejoin_settings::Entity as RejoinSettings;
+pub use super::retention_policy::Entity as RetentionPolicy;
+pub use super::roll_history::Entity as RollHistory;
+pub use super::saved_embeds::Entity as SavedEmbeds;
+pub use super::scheduled_announcement::Entity as ScheduledAnnouncement;
+pub use super::screening_questions::Entity as ScreeningQuestions;
+pub use super::screening_settings::Entity as ScreeningSettings;
+pub use super::snipe_settings::Entity as SnipeSettings;
+pub use super::temp_roles::Entity as TempRoles;
+pub use super::unfurl_domain::Entity as UnfurlDomain;
+pub use super::user_content_asset::Entity as UserContentAsset;
+pub use super::user_content_blobs::Entity as UserContentBlobs;
+pub use super::user_playlists::Entity as UserPlaylists;
+pub use super::user_timezones::Entity as UserTimezones;
+pub use super::voice_announcements::Entity as VoiceAnnouncements;
+pub use super::voice_limits::Entity as VoiceLimits;
+pub use super::voice_sessions::Entity as VoiceSessions;
+pub use super::webhook_deliveries::Entity as WebhookDeliveries;
 pub use super::welcome_roles::Entity as WelcomeRoles;
+pub use super::wordle_games::Entity as WordleGames;
+pub use super::wordle_settings::Entity as WordleSettings;
+pub use super::wordle_stats::Entity as WordleStats;
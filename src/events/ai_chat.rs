@@ -0,0 +1,301 @@
+/*!
+
+Conversational replies of the `ai` feature: when the bot is mentioned or called by its name on a guild that enabled
+them with `/ai configure`, the message and the few messages before it are sent to a language model, and the bot
+replies in character.
+
+The model is reached through an OpenAI compatible chat completions endpoint, configured with the `AI_API_URL`,
+`AI_API_KEY` and `AI_MODEL` environment variables. Replies are limited by a per-user cooldown, a per-guild rate limit
+and the guild's daily token budget. Messages that don't get a reply fall through to the other responders.
+
+*/
+
+use std::{
+    collections::{HashMap, VecDeque},
+    env::var,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+use poise::serenity_prelude::{
+    Context, CreateAllowedMentions, CreateMessage, GetMessages, GuildId, Message, UserId,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use crate::{
+    Error,
+    events::responders::IMPOSTERBOT_REGEX,
+    infrastructure::{
+        botdata::Data,
+        clock::Clock,
+        environment::{AI_API_KEY, ai_api_url, ai_model},
+    },
+    services::ai_settings::AiSettingsRepo,
+};
+
+const SYSTEM_PROMPT: &str = "You are Imposterbot, a Discord bot who is a crewmate in Among Us, and maybe the \
+    impostor. You are playful and a little suspicious of everyone, but never admit to being the impostor. Reply \
+    casually in one to three short sentences, without markdown headings. The conversation is from a Discord channel, \
+    and user messages are prefixed with the name of their author.";
+
+/// Number of messages before the mention sent to the model, so it can follow the conversation.
+const CONTEXT_MESSAGES: u8 = 10;
+
+/// Context messages are cut to this many characters, to keep the token usage predictable.
+const MAX_CONTEXT_MESSAGE_LENGTH: usize = 500;
+
+const MAX_REPLY_TOKENS: u32 = 200;
+
+const MAX_REPLY_LENGTH: usize = 2000;
+
+/// Minimum time between two replies to the same user.
+const USER_COOLDOWN: Duration = Duration::from_secs(10);
+
+/// Maximum number of replies on a guild within [`GUILD_RATE_WINDOW`].
+const GUILD_RATE_LIMIT: usize = 6;
+
+const GUILD_RATE_WINDOW: Duration = Duration::from_secs(60);
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+static HTTP: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+});
+
+#[derive(Debug, Default)]
+pub struct AiRateLimitState {
+    last_reply_to_user: HashMap<UserId, Instant>,
+    guild_replies: HashMap<GuildId, VecDeque<Instant>>,
+}
+
+pub type AiRateLimits = Arc<RwLock<AiRateLimitState>>;
+
+/// Records a reply to a user on a guild, unless the user is on cooldown or the guild reached its rate limit.
+fn try_start_reply(
+    limits: &AiRateLimits,
+    clock: &dyn Clock,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> bool {
+    let mut limits = match limits.write() {
+        Ok(x) => x,
+        Err(_) => {
+            warn!("AI rate limits lock is poisoned");
+            return false;
+        }
+    };
+    let now = clock.instant();
+    if limits
+        .last_reply_to_user
+        .get(&user_id)
+        .is_some_and(|last| now.saturating_duration_since(*last) < USER_COOLDOWN)
+    {
+        return false;
+    }
+    let replies = limits.guild_replies.entry(guild_id).or_default();
+    while replies
+        .front()
+        .is_some_and(|x| now.saturating_duration_since(*x) >= GUILD_RATE_WINDOW)
+    {
+        replies.pop_front();
+    }
+    if replies.len() >= GUILD_RATE_LIMIT {
+        return false;
+    }
+    replies.push_back(now);
+    limits.last_reply_to_user.insert(user_id, now);
+    true
+}
+
+#[derive(Debug, Serialize)]
+struct ChatMessage {
+    role: &'static str,
+    content: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatRequest {
+    model: String,
+    messages: Vec<ChatMessage>,
+    max_tokens: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+    usage: Option<ChatUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoice {
+    message: ChatResponseMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponseMessage {
+    content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatUsage {
+    total_tokens: u32,
+}
+
+fn truncate(content: &str, max_length: usize) -> String {
+    if content.chars().count() > max_length {
+        format!(
+            "{}...",
+            content.chars().take(max_length - 3).collect::<String>()
+        )
+    } else {
+        content.to_string()
+    }
+}
+
+/// Describes a message for the model, as the bot's own words or prefixed with the name of its author.
+fn chat_message(ctx: &Context, message: &Message, bot_id: UserId) -> ChatMessage {
+    let content = truncate(
+        &message.content_safe(&ctx.cache),
+        MAX_CONTEXT_MESSAGE_LENGTH,
+    );
+    if message.author.id == bot_id {
+        ChatMessage {
+            role: "assistant",
+            content,
+        }
+    } else {
+        ChatMessage {
+            role: "user",
+            content: format!("{}: {}", message.author.display_name(), content),
+        }
+    }
+}
+
+/// Sends a conversation to the model, returning its reply and the number of tokens used.
+async fn complete(api_key: &str, messages: Vec<ChatMessage>) -> Result<(String, u32), Error> {
+    let request = ChatRequest {
+        model: ai_model(),
+        messages,
+        max_tokens: MAX_REPLY_TOKENS,
+    };
+    let response: ChatResponse = HTTP
+        .post(ai_api_url())
+        .bearer_auth(api_key)
+        .json(&request)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let reply = response
+        .choices
+        .into_iter()
+        .find_map(|x| x.message.content)
+        .map(|x| x.trim().to_string())
+        .filter(|x| !x.is_empty())
+        .ok_or("The language model returned an empty reply")?;
+    // Roughly four characters per token, for endpoints that don't report their usage
+    let tokens = response.usage.map(|x| x.total_tokens).unwrap_or_else(|| {
+        let characters: usize = request.messages.iter().map(|x| x.content.len()).sum();
+        ((characters + reply.len()) / 4) as u32
+    });
+    Ok((reply, tokens))
+}
+
+/// Replies to a message that mentions the bot, returning whether it was replied to.
+pub async fn on_message(ctx: &Context, data: &Data, message: &Message) -> Result<bool, Error> {
+    let Some(guild_id) = message.guild_id else {
+        return Ok(false);
+    };
+    let bot_id = ctx.cache.current_user().id;
+    let mentioned =
+        message.mentions_user_id(bot_id) || IMPOSTERBOT_REGEX.is_match(&message.content);
+    if !mentioned {
+        return Ok(false);
+    }
+
+    let repo = AiSettingsRepo::new(&data.db_pool);
+    let now = data.clock.unix_timestamp();
+    let settings = repo.settings(guild_id, now).await?;
+    if !settings.enabled {
+        return Ok(false);
+    }
+    if settings.budget_exhausted() {
+        debug!("Daily token budget of guild {} is exhausted", guild_id);
+        return Ok(false);
+    }
+    let Ok(api_key) = var(AI_API_KEY) else {
+        warn!(
+            "Conversational replies are enabled, but {} is not set",
+            AI_API_KEY
+        );
+        return Ok(false);
+    };
+    if !try_start_reply(
+        &data.ai_rate_limits,
+        data.clock.as_ref(),
+        guild_id,
+        message.author.id,
+    ) {
+        debug!(
+            "Conversational reply to user {} is rate limited",
+            message.author.id
+        );
+        return Ok(false);
+    }
+
+    let _typing = message.channel_id.start_typing(&ctx.http);
+    let mut history = message
+        .channel_id
+        .messages(
+            &ctx.http,
+            GetMessages::new()
+                .before(message.id)
+                .limit(CONTEXT_MESSAGES),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            warn!("Failed to get the messages before a mention: {:?}", e);
+            vec![]
+        });
+    // Messages are returned newest first
+    history.reverse();
+    let guild_name = guild_id.name(&ctx.cache).unwrap_or_default();
+    let mut messages = vec![ChatMessage {
+        role: "system",
+        content: format!(
+            "{} The Discord server is called '{}'.",
+            SYSTEM_PROMPT, guild_name
+        ),
+    }];
+    messages.extend(
+        history
+            .iter()
+            .filter(|x| !x.content.is_empty())
+            .map(|x| chat_message(ctx, x, bot_id)),
+    );
+    messages.push(chat_message(ctx, message, bot_id));
+
+    let (reply, tokens) = complete(&api_key, messages).await?;
+    repo.add_usage(guild_id, tokens, now).await?;
+    info!(
+        "Replied to user {} on guild {} with the language model, using {} tokens",
+        message.author.id, guild_id, tokens
+    );
+    message
+        .channel_id
+        .send_message(
+            &ctx.http,
+            CreateMessage::new()
+                .content(truncate(&reply, MAX_REPLY_LENGTH))
+                .reference_message(message)
+                .allowed_mentions(CreateAllowedMentions::new()),
+        )
+        .await?;
+    Ok(true)
+}
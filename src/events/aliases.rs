@@ -0,0 +1,157 @@
+/*
+    Resolves guild-defined command aliases for prefix commands.
+
+    Poise reports prefix messages that don't name a known command as `FrameworkError::UnknownCommand`. The framework
+    error handler passes those messages here, and if the first word is an alias defined on the guild, the message is
+    rewritten to the aliased command and dispatched again.
+*/
+
+use poise::serenity_prelude::{Context, GuildId, Message};
+use sea_orm::{DatabaseConnection, EntityTrait};
+use tracing::{debug, warn};
+
+use crate::{
+    Error,
+    entities::aliases,
+    infrastructure::{botdata::Data, ids::id_to_string},
+    log_content,
+};
+
+/// Maximum length of an alias name, matching discord's limit for command names.
+pub const MAX_ALIAS_LENGTH: usize = 32;
+
+pub async fn get_alias(
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+    alias: &str,
+) -> Result<Option<aliases::Model>, Error> {
+    Ok(
+        aliases::Entity::find_by_id((id_to_string(guild_id), alias.to_lowercase()))
+            .one(db)
+            .await?,
+    )
+}
+
+/// Validates the name of a new alias, returning it normalized to lowercase without the command prefix.
+pub fn normalize_alias_name(
+    options: &poise::FrameworkOptions<Data, Error>,
+    alias: &str,
+) -> Result<String, Error> {
+    let mut alias = alias.trim();
+    if let Some(prefix) = &options.prefix_options.prefix {
+        alias = alias.strip_prefix(prefix.as_str()).unwrap_or(alias);
+    }
+    let alias = alias.to_lowercase();
+
+    if alias.is_empty() || alias.len() > MAX_ALIAS_LENGTH || alias.contains(char::is_whitespace) {
+        return Err(format!(
+            "Aliases must be a single word of at most {} characters.",
+            MAX_ALIAS_LENGTH
+        )
+        .into());
+    }
+    if poise::find_command(&options.commands, &alias, true, &mut vec![]).is_some() {
+        return Err(format!("'{}' is already the name of a command.", alias).into());
+    }
+    Ok(alias)
+}
+
+/// Validates the command invocation an alias expands to, returning it normalized for prefix dispatch.
+///
+/// Slash-style `name:value` arguments are reduced to their values, so `roll dice:D20` becomes `roll D20`.
+pub fn normalize_alias_command(
+    options: &poise::FrameworkOptions<Data, Error>,
+    command: &str,
+) -> Result<String, Error> {
+    let command = command.trim().trim_start_matches('/');
+    let (target, _, args) = poise::find_command(
+        &options.commands,
+        command,
+        options.prefix_options.case_insensitive_commands,
+        &mut vec![],
+    )
+    .ok_or(format!("Unknown command '{}'.", command))?;
+    if target.prefix_action.is_none() {
+        return Err(format!("'{}' can't be used as a prefix command.", target.name).into());
+    }
+
+    // `args` is the remainder of `command` after the (sub)command names.
+    let invocation = command[..command.len() - args.len()].trim_end();
+    let args = args
+        .split_whitespace()
+        .map(|arg| match arg.split_once(':') {
+            Some((name, value)) if target.parameters.iter().any(|p| p.name == name) => value,
+            _ => arg,
+        });
+    Ok(std::iter::once(invocation)
+        .chain(args)
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+/// Dispatches a prefix message whose command name is an alias on the guild.
+///
+/// Returns false if the message does not start with an alias.
+pub async fn dispatch_alias(
+    framework: poise::FrameworkContext<'_, Data, Error>,
+    ctx: &Context,
+    msg: &Message,
+    prefix: &str,
+    msg_content: &str,
+    trigger: poise::MessageDispatchTrigger,
+) -> Result<bool, Error> {
+    let guild_id = match msg.guild_id {
+        Some(x) => x,
+        None => return Ok(false),
+    };
+    let (name, args) = match msg_content.split_once(char::is_whitespace) {
+        Some((name, args)) => (name, args.trim_start()),
+        None => (msg_content, ""),
+    };
+    let alias = match get_alias(&framework.user_data.db_pool, guild_id, name).await? {
+        Some(x) => x,
+        None => return Ok(false),
+    };
+
+    // Only dispatch to real commands, so that aliases can't expand to each other.
+    if poise::find_command(
+        &framework.options.commands,
+        &alias.command,
+        framework.options.prefix_options.case_insensitive_commands,
+        &mut vec![],
+    )
+    .is_none()
+    {
+        warn!(
+            "Alias '{}' refers to unknown command '{}'",
+            alias.alias, alias.command
+        );
+        return Ok(false);
+    }
+
+    let mut aliased = msg.clone();
+    aliased.content = format!("{}{} {}", prefix, alias.command, args)
+        .trim_end()
+        .to_string();
+    debug!(
+        "Expanded alias '{}' to '{}'",
+        name,
+        log_content!(&aliased.content)
+    );
+
+    let invocation_data = tokio::sync::Mutex::new(Box::new(()) as _);
+    let mut parent_commands = vec![];
+    if let Err(e) = poise::dispatch_message(
+        framework,
+        ctx,
+        &aliased,
+        trigger,
+        &invocation_data,
+        &mut parent_commands,
+    )
+    .await
+    {
+        e.handle(framework.options).await;
+    }
+    Ok(true)
+}
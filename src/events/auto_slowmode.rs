@@ -0,0 +1,376 @@
+/*!
+
+Adapts the slowmode of channels to their activity, configured per channel with `/auto-slowmode`.
+
+The messages of each enabled channel are counted over a sliding window of a minute. When a channel gets busier than its
+threshold the slowmode is raised, starting at the channel's minimum and doubling up to its maximum, and once the
+channel calmed down to less than half its threshold the slowmode is halved again, until it is turned off. The slowmode
+is changed at most once per cooldown so members have time to notice, and a background task relaxes channels that went
+quiet, since those don't receive messages to trigger the check.
+
+*/
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use migration::OnConflict;
+use poise::serenity_prelude::{Cache, ChannelId, Context, EditChannel, GuildId, Http, Message};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    Error,
+    entities::auto_slowmode,
+    infrastructure::{
+        botdata::Data,
+        clock::{Clock, SharedClock},
+        ids::{id_from_string, id_to_string},
+    },
+};
+
+/// Window messages are counted over.
+const ACTIVITY_WINDOW: Duration = Duration::from_secs(60);
+
+/// Minimum time between two changes of the slowmode of a channel.
+const ADJUST_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Interval between checks for quiet channels whose slowmode can be relaxed.
+const RELAX_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Longest slowmode discord allows, 6 hours.
+pub const MAX_SLOWMODE_SECONDS: u16 = 21600;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutoSlowmodeSettings {
+    /// Slowmode is raised when the channel gets at least this many messages per minute.
+    pub messages_per_minute: u32,
+    /// First slowmode step. Relaxing below it turns the slowmode off.
+    pub min_seconds: u16,
+    /// Slowmode is never raised above this.
+    pub max_seconds: u16,
+}
+
+impl AutoSlowmodeSettings {
+    fn from_model(model: &auto_slowmode::Model) -> Self {
+        let min_seconds = model.min_seconds.clamp(1, MAX_SLOWMODE_SECONDS as i32) as u16;
+        Self {
+            messages_per_minute: model.messages_per_minute.max(1) as u32,
+            min_seconds,
+            max_seconds: model
+                .max_seconds
+                .clamp(min_seconds as i32, MAX_SLOWMODE_SECONDS as i32)
+                as u16,
+        }
+    }
+
+    /// Gets the next slowmode of a busy channel.
+    fn raised(&self, current: u16) -> u16 {
+        if current < self.min_seconds {
+            self.min_seconds
+        } else {
+            current.saturating_mul(2).min(self.max_seconds)
+        }
+    }
+
+    /// Gets the next slowmode of a calm channel.
+    fn relaxed(&self, current: u16) -> u16 {
+        let halved = current / 2;
+        if halved < self.min_seconds { 0 } else { halved }
+    }
+
+    fn is_calm(&self, messages: usize) -> bool {
+        (messages as u32) * 2 < self.messages_per_minute
+    }
+}
+
+#[derive(Debug)]
+struct ChannelActivity {
+    settings: AutoSlowmodeSettings,
+    messages: VecDeque<Instant>,
+    /// Slowmode of the channel in seconds, as last set or seen by the bot.
+    slowmode: u16,
+    last_change: Option<Instant>,
+}
+
+impl ChannelActivity {
+    fn expire(&mut self, now: Instant) {
+        while let Some(time) = self.messages.front()
+            && now.duration_since(*time) > ACTIVITY_WINDOW
+        {
+            self.messages.pop_front();
+        }
+    }
+
+    fn can_change(&self, now: Instant) -> bool {
+        self.last_change
+            .is_none_or(|x| now.duration_since(x) >= ADJUST_COOLDOWN)
+    }
+
+    /// Records a change of the slowmode, returning the new slowmode if it differs from the current one.
+    fn change(&mut self, slowmode: u16, now: Instant) -> Option<u16> {
+        if slowmode == self.slowmode {
+            return None;
+        }
+        self.slowmode = slowmode;
+        self.last_change = Some(now);
+        Some(slowmode)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct AutoSlowmodeState {
+    /// Settings of the channels seen so far, None for channels without adaptive slowmode.
+    settings: HashMap<ChannelId, Option<AutoSlowmodeSettings>>,
+    channels: HashMap<ChannelId, ChannelActivity>,
+}
+
+pub type AutoSlowmodeCache = Arc<RwLock<AutoSlowmodeState>>;
+
+/// Loads the adaptive slowmode settings of a channel from the database, bypassing the cache.
+pub async fn load_auto_slowmode(
+    db: &DatabaseConnection,
+    channel_id: ChannelId,
+) -> Result<Option<AutoSlowmodeSettings>, Error> {
+    Ok(auto_slowmode::Entity::find_by_id(id_to_string(channel_id))
+        .one(db)
+        .await?
+        .map(|x| AutoSlowmodeSettings::from_model(&x)))
+}
+
+/// Gets the channels of a guild with adaptive slowmode, and their settings.
+pub async fn guild_auto_slowmodes(
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+) -> Result<Vec<(ChannelId, AutoSlowmodeSettings)>, Error> {
+    auto_slowmode::Entity::find()
+        .filter(auto_slowmode::Column::GuildId.eq(id_to_string(guild_id)))
+        .order_by_asc(auto_slowmode::Column::ChannelId)
+        .all(db)
+        .await?
+        .iter()
+        .map(|x| {
+            Ok((
+                id_from_string(&x.channel_id)?,
+                AutoSlowmodeSettings::from_model(x),
+            ))
+        })
+        .collect()
+}
+
+/// Gets the adaptive slowmode settings of a channel, caching them. Falls back to no adaptive slowmode if they can't be
+/// loaded.
+async fn get_auto_slowmode(
+    db: &DatabaseConnection,
+    cache: &AutoSlowmodeCache,
+    channel_id: ChannelId,
+) -> Option<AutoSlowmodeSettings> {
+    if let Some(settings) = cache
+        .read()
+        .ok()
+        .and_then(|x| x.settings.get(&channel_id).copied())
+    {
+        return settings;
+    }
+    match load_auto_slowmode(db, channel_id).await {
+        Ok(settings) => {
+            if let Ok(mut cache) = cache.write() {
+                cache.settings.insert(channel_id, settings);
+            }
+            settings
+        }
+        Err(e) => {
+            warn!(
+                "Failed to load adaptive slowmode settings of channel {}: {:?}",
+                channel_id, e
+            );
+            None
+        }
+    }
+}
+
+/// Enables adaptive slowmode on a channel, or changes its settings.
+pub async fn set_auto_slowmode(
+    db: &DatabaseConnection,
+    cache: &AutoSlowmodeCache,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    settings: AutoSlowmodeSettings,
+) -> Result<(), Error> {
+    auto_slowmode::Entity::insert(auto_slowmode::ActiveModel {
+        channel_id: Set(id_to_string(channel_id)),
+        guild_id: Set(id_to_string(guild_id)),
+        messages_per_minute: Set(settings.messages_per_minute as i32),
+        min_seconds: Set(settings.min_seconds as i32),
+        max_seconds: Set(settings.max_seconds as i32),
+    })
+    .on_conflict(
+        OnConflict::column(auto_slowmode::Column::ChannelId)
+            .update_columns([
+                auto_slowmode::Column::MessagesPerMinute,
+                auto_slowmode::Column::MinSeconds,
+                auto_slowmode::Column::MaxSeconds,
+            ])
+            .to_owned(),
+    )
+    .exec(db)
+    .await?;
+    if let Ok(mut cache) = cache.write() {
+        cache.settings.insert(channel_id, Some(settings));
+        if let Some(channel) = cache.channels.get_mut(&channel_id) {
+            channel.settings = settings;
+        }
+    }
+    Ok(())
+}
+
+/// Disables adaptive slowmode on a channel. Returns whether it was enabled, and the slowmode the bot had set, if any.
+pub async fn remove_auto_slowmode(
+    db: &DatabaseConnection,
+    cache: &AutoSlowmodeCache,
+    channel_id: ChannelId,
+) -> Result<(bool, Option<u16>), Error> {
+    let result = auto_slowmode::Entity::delete_by_id(id_to_string(channel_id))
+        .exec(db)
+        .await?;
+    let mut slowmode = None;
+    if let Ok(mut cache) = cache.write() {
+        cache.settings.insert(channel_id, None);
+        slowmode = cache
+            .channels
+            .remove(&channel_id)
+            .filter(|x| x.last_change.is_some() && x.slowmode > 0)
+            .map(|x| x.slowmode);
+    }
+    Ok((result.rows_affected > 0, slowmode))
+}
+
+/// Applies a slowmode decided by [`on_message`] or [`relax_quiet_channels`].
+async fn apply_slowmode(http: &Http, channel_id: ChannelId, seconds: u16) {
+    match channel_id
+        .edit(http, EditChannel::new().rate_limit_per_user(seconds))
+        .await
+    {
+        Ok(_) => info!(
+            "Adaptive slowmode of channel {} set to {}s",
+            channel_id, seconds
+        ),
+        Err(e) => warn!(
+            "Failed to set the adaptive slowmode of channel {}: {:?}",
+            channel_id, e
+        ),
+    }
+}
+
+/// Gets the slowmode of a channel from the cache, 0 if it isn't cached.
+fn cached_slowmode(cache: &Cache, message: &Message) -> u16 {
+    message
+        .guild(cache)
+        .and_then(|guild| {
+            guild
+                .channels
+                .get(&message.channel_id)
+                .and_then(|channel| channel.rate_limit_per_user)
+        })
+        .unwrap_or_default()
+}
+
+/// Counts a message, raising the slowmode of the channel if it got too busy, or relaxing it if it calmed down.
+pub async fn on_message(ctx: &Context, data: &Data, message: &Message) -> Result<(), Error> {
+    if message.guild_id.is_none() || message.author.bot {
+        return Ok(());
+    }
+    let Some(settings) =
+        get_auto_slowmode(&data.db_pool, &data.auto_slowmode, message.channel_id).await
+    else {
+        return Ok(());
+    };
+
+    let now = data.clock.instant();
+    let change = {
+        let mut cache = data
+            .auto_slowmode
+            .write()
+            .map_err(|_| "Adaptive slowmode state lock is poisoned")?;
+        let channel = cache
+            .channels
+            .entry(message.channel_id)
+            .or_insert_with(|| ChannelActivity {
+                settings,
+                messages: VecDeque::new(),
+                slowmode: cached_slowmode(&ctx.cache, message),
+                last_change: None,
+            });
+        channel.expire(now);
+        channel.messages.push_back(now);
+
+        let messages = channel.messages.len();
+        if !channel.can_change(now) {
+            None
+        } else if messages as u32 >= channel.settings.messages_per_minute {
+            let raised = channel.settings.raised(channel.slowmode);
+            channel.change(raised, now)
+        } else if channel.settings.is_calm(messages) {
+            let relaxed = channel.settings.relaxed(channel.slowmode);
+            channel.change(relaxed, now)
+        } else {
+            None
+        }
+    };
+
+    if let Some(seconds) = change {
+        apply_slowmode(&ctx.http, message.channel_id, seconds).await;
+    }
+    Ok(())
+}
+
+/// Relaxes the slowmode of channels that calmed down without receiving messages.
+async fn relax_quiet_channels(
+    http: &Http,
+    cache: &AutoSlowmodeCache,
+    clock: &dyn Clock,
+) -> Result<(), Error> {
+    let now = clock.instant();
+    let changes: Vec<(ChannelId, u16)> = {
+        let mut cache = cache
+            .write()
+            .map_err(|_| "Adaptive slowmode state lock is poisoned")?;
+        cache
+            .channels
+            .iter_mut()
+            .filter_map(|(channel_id, channel)| {
+                channel.expire(now);
+                if channel.slowmode == 0
+                    || !channel.can_change(now)
+                    || !channel.settings.is_calm(channel.messages.len())
+                {
+                    return None;
+                }
+                let relaxed = channel.settings.relaxed(channel.slowmode);
+                channel.change(relaxed, now).map(|x| (*channel_id, x))
+            })
+            .collect()
+    };
+
+    for (channel_id, seconds) in changes {
+        apply_slowmode(http, channel_id, seconds).await;
+    }
+    Ok(())
+}
+
+/// Relaxes the slowmode of quiet channels on a schedule for the lifetime of the bot.
+pub fn spawn_auto_slowmode_relaxer(http: Arc<Http>, cache: AutoSlowmodeCache, clock: SharedClock) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(RELAX_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = relax_quiet_channels(&http, &cache, clock.as_ref()).await {
+                error!("Failed to relax adaptive slowmodes: {:?}", e);
+            }
+        }
+    });
+}
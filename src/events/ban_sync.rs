@@ -0,0 +1,353 @@
+/*
+    Shares bans with the partner guilds of the ban sync group a guild joined with `/bansync join`.
+
+    A ban on a guild is mirrored right away on partners that auto-apply bans, and proposed in the review channel of
+    the other partners, where a moderator approves or rejects it with a button. Every synced ban is recorded in the
+    ban_sync table and posted to the mod-log channels of both guilds.
+
+    Bans made by the sync itself are already recorded for the guild, so they aren't shared again.
+*/
+
+use poise::serenity_prelude::{
+    self as serenity, ButtonStyle, Context, CreateActionRow, CreateButton, CreateEmbed,
+    CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage, GuildId, Http,
+    Mentionable, User, UserId, async_trait,
+};
+use sea_orm::{ActiveValue::Set, DatabaseConnection};
+use tracing::{info, warn};
+
+use crate::{
+    Error,
+    entities::ban_sync,
+    infrastructure::{
+        audit_log::find_removal_action,
+        botdata::Data,
+        branding::{Branding, get_branding},
+        colors,
+        ids::{id_from_string, id_to_string},
+        mod_log::send_mod_log,
+        retention::RetentionTask,
+    },
+    services::ban_sync::{
+        BanSyncRepo, STATUS_APPLIED, STATUS_FAILED, STATUS_PENDING, STATUS_REJECTED,
+    },
+};
+
+/// Custom id prefix of the approve and reject buttons of ban proposals, followed by `approve:<id>` or `reject:<id>`.
+pub const BAN_SYNC_ID_PREFIX: &str = "ban_sync:";
+
+/// Maximum length of an audit log reason.
+const MAX_REASON_LENGTH: usize = 512;
+
+const SECONDS_PER_DAY: i64 = 60 * 60 * 24;
+
+fn guild_name(ctx: &Context, guild_id: GuildId) -> String {
+    guild_id
+        .name(&ctx.cache)
+        .unwrap_or_else(|| guild_id.to_string())
+}
+
+fn review_components(id: i32) -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("{}approve:{}", BAN_SYNC_ID_PREFIX, id))
+            .label("Ban")
+            .style(ButtonStyle::Danger),
+        CreateButton::new(format!("{}reject:{}", BAN_SYNC_ID_PREFIX, id))
+            .label("Reject")
+            .style(ButtonStyle::Secondary),
+    ])]
+}
+
+/// Describes a synced ban, as shown in review channels and mod-logs.
+fn ban_embed(branding: &Branding, entry: &ban_sync::Model, source_name: &str) -> CreateEmbed {
+    let user = id_from_string::<UserId>(&entry.user_id)
+        .map(|x| format!("{} ({})", x.mention(), x))
+        .unwrap_or_else(|_| entry.user_id.clone());
+    let moderator = entry
+        .moderator_id
+        .as_deref()
+        .and_then(|x| id_from_string::<UserId>(x).ok())
+        .map(|x| x.mention().to_string())
+        .unwrap_or("Unknown".to_string());
+    branding
+        .embed()
+        .title(format!("Synced ban #{}", entry.id))
+        .field("User", user, true)
+        .field("Banned on", source_name, true)
+        .field("Moderator", moderator, true)
+        .field(
+            "Reason",
+            entry.reason.as_deref().unwrap_or("No reason given"),
+            false,
+        )
+}
+
+/// Bans the user of a synced ban on its target guild.
+async fn apply_ban(http: &Http, entry: &ban_sync::Model, source_name: &str) -> Result<(), Error> {
+    let guild_id = id_from_string::<GuildId>(&entry.target_guild_id)?;
+    let user_id = id_from_string::<UserId>(&entry.user_id)?;
+    let reason: String = format!(
+        "Ban synced from {}: {}",
+        source_name,
+        entry.reason.as_deref().unwrap_or("No reason given")
+    )
+    .chars()
+    .take(MAX_REASON_LENGTH)
+    .collect();
+    guild_id.ban_with_reason(http, user_id, 0, &reason).await?;
+    Ok(())
+}
+
+pub async fn on_ban_add(
+    ctx: &Context,
+    data: &Data,
+    guild_id: &GuildId,
+    user: &User,
+) -> Result<(), Error> {
+    let db = &data.db_pool;
+    let repo = BanSyncRepo::new(db);
+    let Some(membership) = repo.membership(*guild_id).await? else {
+        return Ok(());
+    };
+    if repo.is_synced(*guild_id, user.id).await? {
+        // The ban came from a partner guild, so the partners already know about it
+        return Ok(());
+    }
+    let partners = repo.partners(&membership.group_key, *guild_id).await?;
+    if partners.is_empty() {
+        return Ok(());
+    }
+
+    let removal = find_removal_action(&ctx.http, *guild_id, user.id)
+        .await
+        .unwrap_or_else(|e| {
+            warn!("Failed to read the audit log of guild {}: {}", guild_id, e);
+            None
+        });
+    let source_name = guild_name(ctx, *guild_id);
+    let now = data.clock.unix_timestamp();
+    let mut shared = 0;
+    for (partner_id, partner) in partners {
+        if repo.is_synced(partner_id, user.id).await? {
+            continue;
+        }
+        let id = repo
+            .insert(ban_sync::ActiveModel {
+                source_guild_id: Set(id_to_string(*guild_id)),
+                target_guild_id: Set(id_to_string(partner_id)),
+                user_id: Set(id_to_string(user.id)),
+                moderator_id: Set(removal.as_ref().map(|x| id_to_string(x.moderator))),
+                reason: Set(removal.as_ref().and_then(|x| x.reason.clone())),
+                status: Set(STATUS_PENDING.to_string()),
+                created_at: Set(now),
+                ..Default::default()
+            })
+            .await?;
+        let entry = repo.find(id).await?.ok_or("Synced ban was not stored")?;
+        let branding = get_branding(db, &data.branding, partner_id).await;
+        shared += 1;
+
+        if partner.auto_apply {
+            let (status, outcome, color) = match apply_ban(&ctx.http, &entry, &source_name).await {
+                Ok(()) => (
+                    STATUS_APPLIED,
+                    "Banned automatically".to_string(),
+                    colors::green(),
+                ),
+                Err(e) => {
+                    warn!(
+                        "Failed to sync ban #{} to guild {}: {:?}",
+                        id, partner_id, e
+                    );
+                    (
+                        STATUS_FAILED,
+                        format!("Failed to ban: {}", e),
+                        colors::red(),
+                    )
+                }
+            };
+            repo.resolve(id, STATUS_PENDING, status, None, now).await?;
+            info!(
+                "Synced ban of user {} from guild {} to guild {}: {}",
+                user.id, guild_id, partner_id, status
+            );
+            let embed = ban_embed(&branding, &entry, &source_name)
+                .field("Outcome", outcome, false)
+                .color(color);
+            send_mod_log(&ctx.http, db, partner_id, embed).await;
+            continue;
+        }
+
+        let sent = match partner.review_channel {
+            Some(channel) => channel
+                .send_message(
+                    &ctx.http,
+                    CreateMessage::new()
+                        .embed(ban_embed(&branding, &entry, &source_name))
+                        .components(review_components(id)),
+                )
+                .await
+                .map_err(Error::from),
+            None => Err("No review channel is configured".into()),
+        };
+        if let Err(e) = sent {
+            warn!(
+                "Failed to propose ban #{} to guild {}: {:?}",
+                id, partner_id, e
+            );
+            repo.resolve(id, STATUS_PENDING, STATUS_FAILED, None, now)
+                .await?;
+            continue;
+        }
+        info!(
+            "Proposed ban of user {} from guild {} to guild {}",
+            user.id, guild_id, partner_id
+        );
+    }
+
+    if shared > 0 {
+        let embed = get_branding(db, &data.branding, *guild_id)
+            .await
+            .embed()
+            .title("Ban shared with partner guilds")
+            .field("User", format!("{} ({})", user.mention(), user.id), true)
+            .field("Partner guilds", shared.to_string(), true);
+        send_mod_log(&ctx.http, db, *guild_id, embed).await;
+    }
+    Ok(())
+}
+
+async fn respond_ephemeral(
+    ctx: &Context,
+    interaction: &serenity::ComponentInteraction,
+    content: &str,
+) -> Result<(), Error> {
+    interaction
+        .create_response(
+            ctx,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .ephemeral(true),
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Approves or rejects a ban proposed by a partner guild.
+pub async fn handle_review(
+    ctx: &Context,
+    data: &Data,
+    interaction: &serenity::ComponentInteraction,
+    action: &str,
+) -> Result<(), Error> {
+    let guild_id = interaction
+        .guild_id
+        .ok_or("This function is only available in guilds")?;
+    let (approve, id) = match action.split_once(':') {
+        Some(("approve", id)) => (true, id.parse::<i32>()?),
+        Some(("reject", id)) => (false, id.parse::<i32>()?),
+        _ => return Err(format!("Unknown ban sync action '{}'", action).into()),
+    };
+    let can_review = interaction
+        .member
+        .as_ref()
+        .and_then(|x| x.permissions)
+        .is_some_and(|x| x.ban_members());
+    if !can_review {
+        return respond_ephemeral(
+            ctx,
+            interaction,
+            "You need the Ban Members permission to review synced bans",
+        )
+        .await;
+    }
+
+    let db = &data.db_pool;
+    let repo = BanSyncRepo::new(db);
+    let entry = match repo.find(id).await? {
+        Some(x) if x.target_guild_id == id_to_string(guild_id) => x,
+        _ => {
+            return respond_ephemeral(ctx, interaction, "This ban is no longer stored").await;
+        }
+    };
+
+    // Only the first reviewer changes the status, in case two moderators click at the same time
+    let status = if approve {
+        STATUS_APPLIED
+    } else {
+        STATUS_REJECTED
+    };
+    let now = data.clock.unix_timestamp();
+    if !repo
+        .resolve(id, STATUS_PENDING, status, Some(interaction.user.id), now)
+        .await?
+    {
+        return respond_ephemeral(ctx, interaction, "This ban was already reviewed").await;
+    }
+
+    let source_name = id_from_string::<GuildId>(&entry.source_guild_id)
+        .map(|x| guild_name(ctx, x))
+        .unwrap_or_else(|_| entry.source_guild_id.clone());
+    let outcome = if approve {
+        if let Err(e) = apply_ban(&ctx.http, &entry, &source_name).await {
+            // Put the ban back in the queue so it can be approved again
+            repo.resolve(id, STATUS_APPLIED, STATUS_PENDING, None, now)
+                .await?;
+            return Err(e);
+        }
+        format!("Banned by {}", interaction.user.mention())
+    } else {
+        format!("Rejected by {}", interaction.user.mention())
+    };
+    info!(
+        "Synced ban #{} on guild {} {} by {}",
+        id, guild_id, status, interaction.user.id
+    );
+
+    let branding = get_branding(db, &data.branding, guild_id).await;
+    let embed = ban_embed(&branding, &entry, &source_name)
+        .field("Review", outcome, false)
+        .color(if approve {
+            colors::green()
+        } else {
+            colors::red()
+        });
+    send_mod_log(&ctx.http, db, guild_id, embed.clone()).await;
+    interaction
+        .create_response(
+            ctx,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .embed(embed)
+                    .components(vec![]),
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+pub struct BanSyncRetention;
+
+#[async_trait]
+impl RetentionTask for BanSyncRetention {
+    fn name(&self) -> &'static str {
+        "ban_sync"
+    }
+
+    async fn expire(
+        &self,
+        db: &DatabaseConnection,
+        now: i64,
+        retention_days: u32,
+    ) -> Result<u64, Error> {
+        BanSyncRepo::new(db)
+            .remove_before(now - retention_days as i64 * SECONDS_PER_DAY)
+            .await
+    }
+
+    async fn forget_user(&self, db: &DatabaseConnection, user_id: UserId) -> Result<u64, Error> {
+        BanSyncRepo::new(db).forget_user(user_id).await
+    }
+}
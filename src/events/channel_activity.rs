@@ -0,0 +1,32 @@
+/*!
+
+Counts the messages sent in each channel for `/activity`, per hour. Messages of bots, webhooks and direct messages
+aren't counted.
+
+*/
+
+use poise::serenity_prelude::Message;
+use tracing::trace;
+
+use crate::{
+    Error,
+    infrastructure::botdata::Data,
+    services::channel_activity::{ChannelActivityRepo, hour_of},
+};
+
+pub async fn on_message(data: &Data, message: &Message) -> Result<(), Error> {
+    let Some(guild_id) = message.guild_id else {
+        return Ok(());
+    };
+    if message.author.bot || message.webhook_id.is_some() {
+        return Ok(());
+    }
+    let hour = hour_of(data.clock.unix_timestamp());
+    trace!(
+        "Counting a message in channel {} during hour {}",
+        message.channel_id, hour
+    );
+    ChannelActivityRepo::new(&data.db_pool)
+        .record_message(guild_id, message.channel_id, hour)
+        .await
+}
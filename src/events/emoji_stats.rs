@@ -0,0 +1,142 @@
+/*
+    Records custom emoji usage from messages and reactions for the /emojistats command.
+
+    Usage is bucketed per day so that statistics can be reported over arbitrary time windows.
+*/
+
+use std::collections::HashMap;
+
+use migration::{Expr, OnConflict};
+use poise::serenity_prelude::{
+    Context, EmojiId, GuildId, Message, Reaction, ReactionType, UserId, async_trait,
+};
+use sea_orm::{ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use tracing::trace;
+
+use crate::{
+    Error,
+    entities::emoji_stats,
+    infrastructure::{botdata::Data, ids::id_to_string, retention::RetentionTask},
+    lazy_regex,
+};
+
+lazy_regex! { CUSTOM_EMOJI_REGEX, r"<a?:\w+:(\d+)>"}
+
+const SECONDS_PER_DAY: i64 = 60 * 60 * 24;
+
+/// Day of a unix timestamp, as the number of days since the unix epoch.
+pub fn day_of(timestamp: i64) -> i64 {
+    timestamp.div_euclid(SECONDS_PER_DAY)
+}
+
+async fn record_emoji_uses(
+    db: &DatabaseConnection,
+    day: i64,
+    guild_id: GuildId,
+    emoji_id: EmojiId,
+    uses: i32,
+) -> Result<(), Error> {
+    emoji_stats::Entity::insert(emoji_stats::ActiveModel {
+        guild_id: Set(id_to_string(guild_id)),
+        emoji_id: Set(id_to_string(emoji_id)),
+        day: Set(day),
+        uses: Set(uses),
+    })
+    .on_conflict(
+        OnConflict::columns([
+            emoji_stats::Column::GuildId,
+            emoji_stats::Column::EmojiId,
+            emoji_stats::Column::Day,
+        ])
+        .value(
+            emoji_stats::Column::Uses,
+            Expr::col(emoji_stats::Column::Uses).add(uses),
+        )
+        .to_owned(),
+    )
+    .exec(db)
+    .await?;
+    Ok(())
+}
+
+/// Records every custom emoji used in the content of a guild message.
+pub async fn track_message_emojis(data: &Data, message: &Message) -> Result<(), Error> {
+    let guild_id = match message.guild_id {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+    if message.author.bot {
+        return Ok(());
+    }
+
+    let mut counts = HashMap::<EmojiId, i32>::new();
+    for capture in CUSTOM_EMOJI_REGEX.captures_iter(&message.content) {
+        if let Ok(id) = capture[1].parse::<u64>() {
+            *counts.entry(EmojiId::new(id)).or_default() += 1;
+        }
+    }
+
+    let day = day_of(data.clock.unix_timestamp());
+    for (emoji_id, uses) in counts {
+        trace!("Recording {} uses of emoji {}", uses, emoji_id);
+        record_emoji_uses(&data.db_pool, day, guild_id, emoji_id, uses).await?;
+    }
+    Ok(())
+}
+
+/// Records a custom emoji reaction added to a guild message.
+pub async fn track_reaction_emoji(
+    ctx: &Context,
+    data: &Data,
+    reaction: &Reaction,
+) -> Result<(), Error> {
+    let guild_id = match reaction.guild_id {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+    let emoji_id = match reaction.emoji {
+        ReactionType::Custom { id, .. } => id,
+        _ => return Ok(()),
+    };
+    if reaction.user_id == Some(ctx.cache.current_user().id)
+        || reaction.member.as_ref().is_some_and(|m| m.user.bot)
+    {
+        return Ok(());
+    }
+
+    trace!("Recording reaction use of emoji {}", emoji_id);
+    let day = day_of(data.clock.unix_timestamp());
+    record_emoji_uses(&data.db_pool, day, guild_id, emoji_id, 1).await
+}
+
+/// Expires daily emoji usage rows. Emoji statistics are not associated with users.
+pub struct EmojiStatsRetention;
+
+#[async_trait]
+impl RetentionTask for EmojiStatsRetention {
+    fn name(&self) -> &'static str {
+        "emoji_stats"
+    }
+
+    fn default_retention_days(&self) -> Option<u32> {
+        Some(365)
+    }
+
+    async fn expire(
+        &self,
+        db: &DatabaseConnection,
+        now: i64,
+        retention_days: u32,
+    ) -> Result<u64, Error> {
+        let result = emoji_stats::Entity::delete_many()
+            .filter(emoji_stats::Column::Day.lt(day_of(now) - retention_days as i64))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    async fn forget_user(&self, _db: &DatabaseConnection, _user_id: UserId) -> Result<u64, Error> {
+        // Emoji uses are counted per guild, not per member
+        Ok(0)
+    }
+}
@@ -0,0 +1,106 @@
+/*!
+
+Opens a discussion thread under every media post in the gallery channels configured with `/gallery`.
+
+A message is a media post if it has attachments or contains a link. Threads are named after the first attachment's
+file name, or else the title of the first linked page, falling back to the link's host name.
+
+*/
+
+use std::path::Path;
+
+use poise::serenity_prelude::{Context, CreateThread, Message};
+use sea_orm::EntityTrait;
+use tracing::{debug, trace};
+
+use crate::{
+    Error,
+    entities::gallery_channel,
+    infrastructure::{
+        botdata::Data,
+        ids::id_to_string,
+        web_page::{fetch_html, find_links, page_title},
+    },
+};
+
+/// Discord limits thread names to 100 characters.
+const MAX_THREAD_NAME_LENGTH: usize = 100;
+
+/// Collapses whitespace and shortens a name to the thread name limit. Returns None for blank names.
+fn thread_name(name: &str) -> Option<String> {
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    if name.is_empty() {
+        return None;
+    }
+    if name.chars().count() <= MAX_THREAD_NAME_LENGTH {
+        return Some(name);
+    }
+    let mut short: String = name.chars().take(MAX_THREAD_NAME_LENGTH - 1).collect();
+    short.push('…');
+    Some(short)
+}
+
+/// Picks the name of the thread of a media post, or None if the message is not a media post.
+async fn media_thread_name(message: &Message) -> Option<String> {
+    if let Some(attachment) = message.attachments.first() {
+        let path = Path::new(&attachment.filename);
+        let stem = path
+            .file_stem()
+            .map(|x| x.to_string_lossy().replace('_', " "))
+            .unwrap_or_else(|| attachment.filename.clone());
+        return thread_name(&stem).or_else(|| Some("Attachment".to_string()));
+    }
+
+    let url = find_links(&message.content).next()?.as_str();
+    match fetch_html(url)
+        .await
+        .map(|x| x.as_deref().and_then(page_title))
+    {
+        Ok(Some(title)) => {
+            if let Some(name) = thread_name(&title) {
+                return Some(name);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => debug!("Failed to fetch the title of {}: {:?}", url, e),
+    }
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|x| x.host_str().and_then(thread_name))
+        .or_else(|| Some("Link".to_string()))
+}
+
+/// Opens a thread under a media post in a gallery channel.
+pub async fn on_message(ctx: &Context, data: &Data, message: &Message) -> Result<(), Error> {
+    let Some(guild_id) = message.guild_id else {
+        return Ok(());
+    };
+    if message.author.bot
+        || (message.attachments.is_empty() && find_links(&message.content).next().is_none())
+    {
+        return Ok(());
+    }
+    let is_gallery = gallery_channel::Entity::find_by_id((
+        id_to_string(guild_id),
+        id_to_string(message.channel_id),
+    ))
+    .one(&data.db_pool)
+    .await?
+    .is_some();
+    if !is_gallery {
+        return Ok(());
+    }
+
+    let Some(name) = media_thread_name(message).await else {
+        return Ok(());
+    };
+    trace!(
+        "Opening thread '{}' for message {} in gallery channel {}",
+        name, message.id, message.channel_id
+    );
+    message
+        .channel_id
+        .create_thread_from_message(ctx, message.id, CreateThread::new(name))
+        .await?;
+    Ok(())
+}
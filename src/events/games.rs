@@ -0,0 +1,243 @@
+/*!
+
+Processes guesses for the channel games started with `/game`.
+
+Messages in a channel with a running game are checked before the auto-responders. Messages that aren't guesses, such
+as regular chat in a hangman channel, are left to the rest of the message pipeline.
+
+*/
+
+use poise::serenity_prelude::{Context, Mentionable, Message};
+use tracing::{info, warn};
+
+use crate::{
+    Error,
+    infrastructure::{
+        botdata::Data,
+        game_sessions::{Game, delete_game_session, save_game_session},
+    },
+};
+
+/// Wrong guesses allowed before a hangman game is lost.
+pub const HANGMAN_LIVES: u32 = 6;
+
+/// Words picked from for hangman games.
+pub const HANGMAN_WORDS: &[&str] = &[
+    "imposter",
+    "crewmate",
+    "electrical",
+    "reactor",
+    "navigation",
+    "cafeteria",
+    "admin",
+    "medbay",
+    "storage",
+    "communications",
+    "oxygen",
+    "security",
+    "emergency",
+    "sabotage",
+    "vent",
+    "task",
+    "asteroids",
+    "wiring",
+    "specimen",
+    "laboratory",
+];
+
+/// Result of a guess.
+pub struct GuessOutcome {
+    pub reply: String,
+    pub finished: bool,
+}
+
+impl GuessOutcome {
+    fn next(reply: String) -> Self {
+        GuessOutcome {
+            reply,
+            finished: false,
+        }
+    }
+
+    fn end(reply: String) -> Self {
+        GuessOutcome {
+            reply,
+            finished: true,
+        }
+    }
+}
+
+/// Shows the revealed letters of a hangman word, the wrong guesses and the remaining lives.
+pub fn hangman_board(word: &str, guessed: &[char], wrong_guesses: u32) -> String {
+    let letters: Vec<String> = word
+        .chars()
+        .map(|x| {
+            if guessed.contains(&x) {
+                x.to_string()
+            } else {
+                "\\_".to_string()
+            }
+        })
+        .collect();
+    let misses: Vec<String> = guessed
+        .iter()
+        .filter(|x| !word.contains(**x))
+        .map(|x| x.to_string())
+        .collect();
+    format!(
+        "{}\nWrong letters: {}\nLives: {}/{}",
+        letters.join(" "),
+        if misses.is_empty() {
+            "none".to_string()
+        } else {
+            misses.join(", ")
+        },
+        HANGMAN_LIVES.saturating_sub(wrong_guesses),
+        HANGMAN_LIVES
+    )
+}
+
+fn guess_number(
+    target: u64,
+    max: u64,
+    guesses: &mut u32,
+    content: &str,
+    player: &str,
+) -> Option<GuessOutcome> {
+    let guess = content.parse::<u64>().ok()?;
+    if guess == 0 || guess > max {
+        return Some(GuessOutcome::next(format!(
+            "Guess a number between 1 and {}.",
+            max
+        )));
+    }
+    *guesses += 1;
+    Some(if guess < target {
+        GuessOutcome::next(format!("Higher than {}!", guess))
+    } else if guess > target {
+        GuessOutcome::next(format!("Lower than {}!", guess))
+    } else {
+        GuessOutcome::end(format!(
+            "🎉 {} guessed the number **{}** after {} guesses!",
+            player, target, guesses
+        ))
+    })
+}
+
+fn guess_hangman(
+    word: &str,
+    guessed: &mut Vec<char>,
+    wrong_guesses: &mut u32,
+    content: &str,
+    player: &str,
+) -> Option<GuessOutcome> {
+    let content = content.to_lowercase();
+    let mut chars = content.chars();
+    let is_letter = matches!((chars.next(), chars.next()), (Some(x), None) if x.is_alphabetic());
+
+    if is_letter {
+        let letter = content.chars().next()?;
+        if guessed.contains(&letter) {
+            return Some(GuessOutcome::next(format!(
+                "`{}` was already guessed.",
+                letter
+            )));
+        }
+        guessed.push(letter);
+        if word.contains(letter) {
+            if word.chars().all(|x| guessed.contains(&x)) {
+                return Some(GuessOutcome::end(format!(
+                    "🎉 {} completed the word **{}**!",
+                    player, word
+                )));
+            }
+            return Some(GuessOutcome::next(hangman_board(
+                word,
+                guessed,
+                *wrong_guesses,
+            )));
+        }
+    } else if content.chars().count() == word.chars().count()
+        && content.chars().all(char::is_alphabetic)
+    {
+        // Chat messages usually contain spaces, so only single words of the right length count as guesses
+        if content == word {
+            return Some(GuessOutcome::end(format!(
+                "🎉 {} guessed the word **{}**!",
+                player, word
+            )));
+        }
+    } else {
+        return None;
+    }
+
+    *wrong_guesses += 1;
+    if *wrong_guesses >= HANGMAN_LIVES {
+        return Some(GuessOutcome::end(format!(
+            "💀 Out of lives! The word was **{}**.",
+            word
+        )));
+    }
+    Some(GuessOutcome::next(hangman_board(
+        word,
+        guessed,
+        *wrong_guesses,
+    )))
+}
+
+/// Applies a message to a game. Returns None if the message is not a guess.
+pub fn apply_guess(game: &mut Game, content: &str, player: &str) -> Option<GuessOutcome> {
+    let content = content.trim();
+    match game {
+        Game::NumberGuess {
+            target,
+            max,
+            guesses,
+        } => guess_number(*target, *max, guesses, content, player),
+        Game::Hangman {
+            word,
+            guessed,
+            wrong_guesses,
+        } => guess_hangman(word, guessed, wrong_guesses, content, player),
+    }
+}
+
+/// Processes a message as a guess for the game running in its channel.
+///
+/// Returns true if the message was a guess, in which case it shouldn't trigger other responses.
+pub async fn on_message(ctx: &Context, data: &Data, message: &Message) -> Result<bool, Error> {
+    let player = message.author.mention().to_string();
+    let (outcome, session) = {
+        let mut sessions = match data.game_sessions.write() {
+            Ok(x) => x,
+            Err(_) => {
+                warn!("Game sessions lock is poisoned");
+                return Ok(false);
+            }
+        };
+        let Some(session) = sessions.get_mut(&message.channel_id) else {
+            return Ok(false);
+        };
+        let Some(outcome) = apply_guess(&mut session.game, &message.content, &player) else {
+            return Ok(false);
+        };
+        let session = session.clone();
+        if outcome.finished {
+            sessions.remove(&message.channel_id);
+        }
+        (outcome, session)
+    };
+
+    if outcome.finished {
+        info!(
+            "Game of {} ended in channel {}",
+            session.game.name(),
+            message.channel_id
+        );
+        delete_game_session(&data.db_pool, message.channel_id).await?;
+    } else {
+        save_game_session(&data.db_pool, message.channel_id, &session).await?;
+    }
+    message.channel_id.say(ctx, outcome.reply).await?;
+    Ok(true)
+}
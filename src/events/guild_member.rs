@@ -1,37 +1,81 @@
 /*
     Handles saying hello and goodbye when members join and leave the guild.
 
-    Adds specified role(s) to new members.
+    Adds specified role(s) to new members, unless paused by a raid lockdown.
+
+    Releases the color role of members that leave, so unused color roles are deleted.
+
+    Remembers the members that joined before, so returning members get the return notification, and members who rejoin
+    shortly after leaving aren't welcomed again.
+
+    Delays the join notification when the guild asks for it, optionally until the member passed membership screening,
+    so members can see the channel they are welcomed in. Members who leave before are not welcomed.
 */
 
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
 use poise::{
     CreateReply,
     serenity_prelude::{
         ChannelId, Context, CreateAttachment, CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter,
-        CreateMessage, GuildId, Member, Mentionable, PartialGuild, RoleId, User, futures::future,
+        CreateMessage, GuildId, GuildMemberUpdateEvent, Member, Mentionable, PartialGuild, User,
+        UserId, async_trait, futures::future,
     },
 };
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use sea_orm::DatabaseConnection;
 use strfmt::strfmt;
-use tracing::{Level, error, trace};
+use tracing::{Level, debug, error, info, trace, warn};
 
 use crate::{
     Error, entities,
+    events::{raid_protection, screening},
     infrastructure::{
+        asset_storage,
+        audit_log::{RemovalAction, find_removal_action},
         botdata::Data,
-        environment::get_data_directory,
-        ids::{id_from_string, id_to_string},
+        branding::get_branding,
+        color_roles::release_color_role,
+        embed_limits::MessagePart,
+        mod_digest::record_activity,
+        retention::RetentionTask,
+        user_content::user_content_path,
+    },
+    log_content, record_member_fields,
+    services::{
+        mod_digest::ModActivity,
+        notifications::{BotMemberMode, GreetingSettings, NotificationRepo},
+        roles::RolesRepo,
     },
-    record_member_fields,
 };
 
+const SECONDS_PER_HOUR: i64 = 60 * 60;
+
+/// Longest time a join notification waits for the member to pass membership screening. Members who take longer are not
+/// welcomed.
+const MAX_PENDING_WAIT: Duration = Duration::from_secs(60 * 60 * 24);
+
 enum MemberEvent<'a> {
-    Join(&'a Member),
+    Join(&'a Member, Greeting),
     Leave(&'a GuildId, &'a User),
 }
 
+/// A join notification waiting for its delay to pass, or for the member to pass membership screening.
+#[derive(Debug, Clone)]
+pub struct PendingGreeting {
+    member: Member,
+    greeting: Greeting,
+    /// Unix timestamp at which the delay is over.
+    send_at: i64,
+    wait_for_pending: bool,
+}
+
+/// Join notifications that weren't sent yet, by guild and member.
+pub type PendingGreetings = Arc<RwLock<HashMap<(GuildId, UserId), PendingGreeting>>>;
+
 #[derive(Default, Clone, Debug)]
 pub struct MemberNotificationMessageDetails {
     pub content: Option<String>,
@@ -98,22 +142,20 @@ impl MemberNotificationMessageDetails {
         guild: Option<PartialGuild>,
         format: MemberNotificationMessageDetails,
     ) -> Self {
-        let mut fmtargs = HashMap::<String, String>::new();
-        fmtargs.insert("name".into(), member.user.name.clone());
-        fmtargs.insert("mention".into(), member.mention().to_string());
-        if let Some(avatar) = member.avatar_url().or(member.user.avatar_url()) {
-            fmtargs.insert("user_avatar".into(), avatar);
-        }
-        if let Some(guild) = guild {
-            if let Some(member_count) = guild.approximate_member_count {
-                fmtargs.insert("member_count".into(), member_count.to_string());
-            }
-            if let Some(presence_count) = guild.approximate_presence_count {
-                fmtargs.insert("online_member_count".into(), presence_count.to_string());
-            }
-        }
+        Self::from_fmt_args(member_format_args(member, guild), format)
+    }
 
-        Self::from_fmt_args(fmtargs, format)
+    /// Same as [`Self::for_member`], with the placeholders of return notifications.
+    pub fn for_returning_member(
+        member: &Member,
+        guild: Option<PartialGuild>,
+        previous_join: i64,
+        format: MemberNotificationMessageDetails,
+    ) -> Self {
+        Self::from_fmt_args(
+            returning_member_format_args(member, guild, previous_join),
+            format,
+        )
     }
 
     pub fn for_user(
@@ -121,32 +163,69 @@ impl MemberNotificationMessageDetails {
         guild: Option<PartialGuild>,
         format: MemberNotificationMessageDetails,
     ) -> Self {
-        let mut fmtargs = HashMap::<String, String>::new();
-        fmtargs.insert("name".into(), user.name.clone());
-        fmtargs.insert("rules".into(), "(Not yet implemented)".into());
-        if let Some(avatar) = user.avatar_url() {
-            fmtargs.insert("user_avatar".into(), avatar);
-        }
+        Self::from_fmt_args(user_format_args(user, guild), format)
+    }
 
-        if let Some(guild) = guild {
-            if let Some(member_count) = guild.approximate_member_count {
-                fmtargs.insert("member_count".into(), member_count.to_string());
-            }
-            if let Some(presence_count) = guild.approximate_presence_count {
-                fmtargs.insert("online_member_count".into(), presence_count.to_string());
-            }
+    /// Gets the names of the placeholders used by this format, sorted and without duplicates.
+    ///
+    /// Uploaded files are not formatted, so placeholders in their names are ignored.
+    pub fn placeholders(&self) -> Vec<String> {
+        let mut fields: Vec<&String> = self.content.iter().collect();
+        if let Some(embed) = &self.embed {
+            fields.extend(
+                [
+                    &embed.title,
+                    &embed.description,
+                    &embed.author,
+                    &embed.footer,
+                ]
+                .into_iter()
+                .flatten(),
+            );
+            fields.extend(
+                [
+                    &embed.thumbnail,
+                    &embed.image,
+                    &embed.author_icon_url,
+                    &embed.footer_icon_url,
+                ]
+                .into_iter()
+                .flatten()
+                .filter(|file| !file.attachment)
+                .map(|file| &file.url),
+            );
         }
 
-        Self::from_fmt_args(fmtargs, format)
+        let mut placeholders: Vec<String> = fields
+            .into_iter()
+            .flat_map(|field| {
+                field
+                    .split('{')
+                    .skip(1)
+                    .filter_map(|x| x.split_once('}'))
+                    // Drop format specs, e.g. `{name:>10}`
+                    .map(|(name, _)| name.split(':').next().unwrap_or_default().to_string())
+                    .collect::<Vec<String>>()
+            })
+            .filter(|name| !name.is_empty())
+            .collect();
+        placeholders.sort();
+        placeholders.dedup();
+        placeholders
     }
 
-    fn from_fmt_args(
+    pub fn from_fmt_args(
         fmtargs: HashMap<String, String>,
         format: MemberNotificationMessageDetails,
     ) -> Self {
-        fn get_string(fmt: Option<String>, args: &HashMap<String, String>) -> Option<String> {
+        // Placeholders can push a text past its limit, which was only checked before they were filled in
+        fn get_string(
+            fmt: Option<String>,
+            part: MessagePart,
+            args: &HashMap<String, String>,
+        ) -> Option<String> {
             if let Some(content_fmt) = fmt {
-                strfmt(&*content_fmt, &args).ok()
+                strfmt(&*content_fmt, &args).ok().map(|x| part.truncate(x))
             } else {
                 None
             }
@@ -181,16 +260,16 @@ impl MemberNotificationMessageDetails {
             }
         }
 
-        let content = get_string(format.content, &fmtargs);
+        let content = get_string(format.content, MessagePart::Content, &fmtargs);
         let embed: Option<MemberNotificationEmbedDetails> = if let Some(embd_fmt) = format.embed {
             Some(MemberNotificationEmbedDetails {
-                title: get_string(embd_fmt.title, &fmtargs),
-                description: get_string(embd_fmt.description, &fmtargs),
+                title: get_string(embd_fmt.title, MessagePart::Title, &fmtargs),
+                description: get_string(embd_fmt.description, MessagePart::Description, &fmtargs),
                 thumbnail: get_attachment(embd_fmt.thumbnail, &fmtargs),
                 image: get_attachment(embd_fmt.image, &fmtargs),
-                author: get_string(embd_fmt.author, &fmtargs),
+                author: get_string(embd_fmt.author, MessagePart::Author, &fmtargs),
                 author_icon_url: get_attachment(embd_fmt.author_icon_url, &fmtargs),
-                footer: get_string(embd_fmt.footer, &fmtargs),
+                footer: get_string(embd_fmt.footer, MessagePart::Footer, &fmtargs),
                 footer_icon_url: get_attachment(embd_fmt.footer_icon_url, &fmtargs),
             })
         } else {
@@ -220,13 +299,8 @@ impl MemberNotificationMessageDetails {
 
             if let Some(thumbnail_file) = &embed_details.thumbnail {
                 if thumbnail_file.attachment {
-                    match CreateAttachment::path(
-                        get_data_directory()
-                            .join("user_content")
-                            .join(id_to_string(guild_id.clone()))
-                            .join(&thumbnail_file.url),
-                    )
-                    .await
+                    match CreateAttachment::path(user_content_path(*guild_id, &thumbnail_file.url))
+                        .await
                     {
                         Ok(attachment) => {
                             embed = embed
@@ -247,13 +321,8 @@ impl MemberNotificationMessageDetails {
 
             if let Some(image_file) = &embed_details.image {
                 if image_file.attachment {
-                    match CreateAttachment::path(
-                        get_data_directory()
-                            .join("user_content")
-                            .join(id_to_string(guild_id.clone()))
-                            .join(&image_file.url),
-                    )
-                    .await
+                    match CreateAttachment::path(user_content_path(*guild_id, &image_file.url))
+                        .await
                     {
                         Ok(attachment) => {
                             embed = embed
@@ -276,13 +345,8 @@ impl MemberNotificationMessageDetails {
                 let mut author = CreateEmbedAuthor::new(x);
                 if let Some(icon_file) = &embed_details.author_icon_url {
                     if icon_file.attachment {
-                        match CreateAttachment::path(
-                            get_data_directory()
-                                .join("user_content")
-                                .join(id_to_string(guild_id.clone()))
-                                .join(&icon_file.url),
-                        )
-                        .await
+                        match CreateAttachment::path(user_content_path(*guild_id, &icon_file.url))
+                            .await
                         {
                             Ok(attachment) => {
                                 author = author.icon_url(format!(
@@ -311,13 +375,8 @@ impl MemberNotificationMessageDetails {
 
                 if let Some(icon_file) = &embed_details.footer_icon_url {
                     if icon_file.attachment {
-                        match CreateAttachment::path(
-                            get_data_directory()
-                                .join("user_content")
-                                .join(id_to_string(guild_id.clone()))
-                                .join(&icon_file.url),
-                        )
-                        .await
+                        match CreateAttachment::path(user_content_path(*guild_id, &icon_file.url))
+                            .await
                         {
                             Ok(attachment) => {
                                 footer = footer.icon_url(format!(
@@ -380,11 +439,77 @@ impl MemberNotificationMessageDetails {
     }
 }
 
-pub async fn get_member_notification_details(
-    db: &DatabaseConnection,
-    guild_id: &GuildId,
-    join: bool,
-) -> Option<MemberNotificationMessageDetails> {
+/// Adds the placeholder values shared by join and leave notifications.
+fn insert_guild_format_args(fmtargs: &mut HashMap<String, String>, guild: Option<PartialGuild>) {
+    if let Some(guild) = guild {
+        if let Some(member_count) = guild.approximate_member_count {
+            fmtargs.insert("member_count".into(), member_count.to_string());
+        }
+        if let Some(presence_count) = guild.approximate_presence_count {
+            fmtargs.insert("online_member_count".into(), presence_count.to_string());
+        }
+    }
+}
+
+/// Gets the placeholder values of a join notification.
+pub fn member_format_args(member: &Member, guild: Option<PartialGuild>) -> HashMap<String, String> {
+    let mut fmtargs = HashMap::<String, String>::new();
+    fmtargs.insert("name".into(), member.user.name.clone());
+    fmtargs.insert("mention".into(), member.mention().to_string());
+    if let Some(avatar) = member.avatar_url().or(member.user.avatar_url()) {
+        fmtargs.insert("user_avatar".into(), avatar);
+    }
+    insert_guild_format_args(&mut fmtargs, guild);
+    fmtargs
+}
+
+/// Gets the placeholder values of a return notification, for a member that previously joined at `previous_join`.
+pub fn returning_member_format_args(
+    member: &Member,
+    guild: Option<PartialGuild>,
+    previous_join: i64,
+) -> HashMap<String, String> {
+    let mut fmtargs = member_format_args(member, guild);
+    fmtargs.insert(
+        "previous_join_date".into(),
+        format!("<t:{}:D>", previous_join),
+    );
+    fmtargs
+}
+
+/// Gets the placeholder values of a leave notification.
+pub fn user_format_args(user: &User, guild: Option<PartialGuild>) -> HashMap<String, String> {
+    let mut fmtargs = HashMap::<String, String>::new();
+    fmtargs.insert("name".into(), user.name.clone());
+    fmtargs.insert("rules".into(), "(Not yet implemented)".into());
+    if let Some(avatar) = user.avatar_url() {
+        fmtargs.insert("user_avatar".into(), avatar);
+    }
+    insert_guild_format_args(&mut fmtargs, guild);
+    fmtargs
+}
+
+/// Gets the filenames of the user content files a notification variant uses.
+pub fn notification_files(model: &entities::member_notification_message::Model) -> Vec<String> {
+    [
+        (model.thumbnail_is_file, &model.thumbnail_url),
+        (model.image_is_file, &model.image_url),
+        (model.author_icon_is_file, &model.author_icon_url),
+        (model.footer_icon_is_file, &model.footer_icon_url),
+    ]
+    .into_iter()
+    .filter(|(is_file, url)| *is_file && !url.is_empty())
+    .map(|(_, url)| url.clone())
+    .collect()
+}
+
+/// Converts a stored notification variant into its format.
+///
+/// Files that have been re-uploaded to the asset storage channel are sent by their CDN url instead.
+fn notification_details_from_model(
+    model: entities::member_notification_message::Model,
+    assets: &HashMap<String, String>,
+) -> MemberNotificationMessageDetails {
     fn optional_string(string: String) -> Option<String> {
         if string.is_empty() {
             None
@@ -393,39 +518,66 @@ pub async fn get_member_notification_details(
         }
     }
 
-    fn optional_attachment(file: bool, url: String) -> Option<MemberNotificationFile> {
+    let optional_attachment = |file: bool, url: String| -> Option<MemberNotificationFile> {
         if url.is_empty() {
             None
+        } else if let Some(hosted) = assets.get(&url).filter(|_| file) {
+            Some(MemberNotificationFile {
+                attachment: false,
+                url: hosted.clone(),
+            })
         } else {
             Some(MemberNotificationFile {
                 attachment: file,
                 url: url,
             })
         }
-    }
+    };
 
-    match entities::member_notification_message::Entity::find_by_id((id_to_string(*guild_id), join))
-        .one(db)
+    MemberNotificationMessageDetails::format(
+        optional_string(model.content),
+        !model.description.is_empty()
+            || !model.author.is_empty()
+            || !model.footer.is_empty()
+            || !model.thumbnail_url.is_empty()
+            || !model.image_url.is_empty(),
+        optional_string(model.title),
+        optional_string(model.description),
+        optional_attachment(model.thumbnail_is_file, model.thumbnail_url),
+        optional_attachment(model.image_is_file, model.image_url),
+        optional_string(model.author),
+        optional_attachment(model.author_icon_is_file, model.author_icon_url),
+        optional_string(model.footer),
+        optional_attachment(model.footer_icon_is_file, model.footer_icon_url),
+    )
+}
+
+/// Gets the hosted user content files of a guild, treating errors as if no files are hosted.
+async fn get_asset_urls_or_default(data: &Data, guild_id: &GuildId) -> HashMap<String, String> {
+    asset_storage::get_asset_urls(&data.db_pool, *guild_id, data.clock.unix_timestamp())
+        .await
+        .unwrap_or_else(|err| {
+            error!("An error occurred while fetching hosted assets: {}", err);
+            HashMap::new()
+        })
+}
+
+/// Gets the format of a specific member notification variant.
+pub async fn get_member_notification_variant(
+    data: &Data,
+    guild_id: &GuildId,
+    join: bool,
+    variant: i32,
+) -> Option<MemberNotificationMessageDetails> {
+    match NotificationRepo::new(&data.db_pool)
+        .variant(*guild_id, join, variant)
         .await
     {
-        Ok(model) => model.map(|model| {
-            MemberNotificationMessageDetails::format(
-                optional_string(model.content),
-                !model.description.is_empty()
-                    || !model.author.is_empty()
-                    || !model.footer.is_empty()
-                    || !model.thumbnail_url.is_empty()
-                    || !model.image_url.is_empty(),
-                optional_string(model.title),
-                optional_string(model.description),
-                optional_attachment(model.thumbnail_is_file, model.thumbnail_url),
-                optional_attachment(model.image_is_file, model.image_url),
-                optional_string(model.author),
-                optional_attachment(model.author_icon_is_file, model.author_icon_url),
-                optional_string(model.footer),
-                optional_attachment(model.footer_icon_is_file, model.footer_icon_url),
-            )
-        }),
+        Ok(Some(model)) => {
+            let assets = get_asset_urls_or_default(data, guild_id).await;
+            Some(notification_details_from_model(model, &assets))
+        }
+        Ok(None) => None,
         Err(err) => {
             error!(
                 "An error occurred while fetching member notification message: {}",
@@ -436,63 +588,173 @@ pub async fn get_member_notification_details(
     }
 }
 
+/// Gets the format of a randomly selected member notification variant.
+pub async fn get_member_notification_details(
+    data: &Data,
+    guild_id: &GuildId,
+    join: bool,
+) -> Option<MemberNotificationMessageDetails> {
+    choose_notification_details(
+        data,
+        guild_id,
+        NotificationRepo::new(&data.db_pool)
+            .variants(*guild_id, join)
+            .await,
+    )
+    .await
+}
+
+/// Gets the format of a randomly selected return notification variant.
+async fn get_return_notification_details(
+    data: &Data,
+    guild_id: &GuildId,
+) -> Option<MemberNotificationMessageDetails> {
+    choose_notification_details(
+        data,
+        guild_id,
+        NotificationRepo::new(&data.db_pool)
+            .return_variants(*guild_id)
+            .await,
+    )
+    .await
+}
+
+async fn choose_notification_details(
+    data: &Data,
+    guild_id: &GuildId,
+    variants: Result<Vec<entities::member_notification_message::Model>, Error>,
+) -> Option<MemberNotificationMessageDetails> {
+    match variants {
+        Ok(mut models) => {
+            let model = models.swap_remove(data.random.random_index(models.len())?);
+            let assets = get_asset_urls_or_default(data, guild_id).await;
+            Some(notification_details_from_model(model, &assets))
+        }
+        Err(err) => {
+            error!(
+                "An error occurred while fetching member notification message: {}",
+                err
+            );
+            None
+        }
+    }
+}
+
+/// Gets the channel a member notification is sent to, treating errors as if none is configured.
 async fn get_member_notification_channel(
     db: &DatabaseConnection,
     guild_id: &GuildId,
     join: bool,
 ) -> Option<ChannelId> {
-    let query_result =
-        entities::member_notification_channel::Entity::find_by_id((id_to_string(*guild_id), join))
-            .one(db)
-            .await;
-
-    match query_result {
-        Ok(model) => model
-            .map(
-                |model| match id_from_string::<ChannelId>(model.channel_id.as_str()) {
-                    Ok(id) => Some(id),
-                    Err(error) => {
-                        error!(
-                            "Error occurred while parsing member notification channel: {}. Value: {}",
-                            error,
-                            model.channel_id
-                        );
-                        None
-                    }
-                },
-            )
-            .flatten(),
-        Err(error) => {
+    NotificationRepo::new(db)
+        .channel(*guild_id, join)
+        .await
+        .unwrap_or_else(|error| {
             error!(
                 "Error occurred while getting member notification channel: {}",
                 error
             );
             None
-        }
-    }
+        })
 }
 
-pub async fn get_member_roles_on_join(
-    db: &DatabaseConnection,
+/// Gets how bot accounts are handled on a guild, treating errors as the default.
+async fn get_bot_member_mode(db: &DatabaseConnection, guild_id: &GuildId) -> BotMemberMode {
+    NotificationRepo::new(db)
+        .bot_member_mode(*guild_id)
+        .await
+        .unwrap_or_else(|error| {
+            error!(
+                "Error occurred while getting bot member settings: {}",
+                error
+            );
+            BotMemberMode::Notify
+        })
+}
+
+/// Finds the kick or ban behind a member leaving, if staff mode is enabled for the guild's leave notifications.
+async fn get_removal_action(
+    ctx: &Context,
+    data: &Data,
     guild_id: &GuildId,
-) -> Option<Vec<RoleId>> {
-    let query_result = entities::welcome_roles::Entity::find()
-        .filter(entities::welcome_roles::Column::GuildId.eq(id_to_string(*guild_id)))
-        .one(db)
-        .await;
-
-    match query_result {
-        Ok(result) => Some(
-            result
-                .iter()
-                .map(|role| id_from_string::<RoleId>(role.role_id.as_str()))
-                .filter(|result| result.is_ok())
-                .map(|result| result.expect("Failed results should have been filtered out"))
-                .collect(),
-        ),
+    user: &User,
+) -> Option<RemovalAction> {
+    match NotificationRepo::new(&data.db_pool)
+        .staff_mode(*guild_id)
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => return None,
         Err(e) => {
-            error!("Failed to get member roles on join: {}", e);
+            error!(
+                "Error occurred while getting leave notification settings: {}",
+                e
+            );
+            return None;
+        }
+    }
+    find_removal_action(&ctx.http, *guild_id, user.id)
+        .await
+        .unwrap_or_else(|e| {
+            warn!("Failed to read the audit log of guild {}: {}", guild_id, e);
             None
+        })
+}
+
+/// Describes the kick or ban behind a member leaving, for staff reading the leave notifications.
+async fn removal_embed(data: &Data, guild_id: &GuildId, removal: RemovalAction) -> CreateEmbed {
+    get_branding(&data.db_pool, &data.branding, *guild_id)
+        .await
+        .embed()
+        .field("Action", removal.kind.describe(), true)
+        .field("Moderator", removal.moderator.mention().to_string(), true)
+        .field(
+            "Reason",
+            removal.reason.unwrap_or("No reason given".to_string()),
+            false,
+        )
+}
+
+/// How a joining member is welcomed, depending on whether and when they joined before.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Greeting {
+    Welcome,
+    Suppress,
+    /// The member joined before, at `previous_join`. Members who rejoined within the rejoin window only get the return
+    /// notification, while other members get the join notification if no return notification is configured.
+    Return {
+        previous_join: i64,
+        within_window: bool,
+    },
+}
+
+/// Records a member joining and decides how they are welcomed, treating errors as a first join.
+async fn get_greeting(data: &Data, member: &Member) -> Greeting {
+    let repo = NotificationRepo::new(&data.db_pool);
+    let now = data.clock.unix_timestamp();
+    let previous_join = match repo.record_join(member.guild_id, member.user.id, now).await {
+        Ok(Some(x)) => x,
+        Ok(None) => return Greeting::Welcome,
+        Err(e) => {
+            error!("Failed to record member join: {}", e);
+            return Greeting::Welcome;
+        }
+    };
+    let settings = repo
+        .rejoin_settings(member.guild_id)
+        .await
+        .unwrap_or_else(|e| {
+            error!("Error occurred while getting rejoin settings: {}", e);
+            Default::default()
+        });
+
+    let within_window = now - previous_join < settings.window_hours as i64 * SECONDS_PER_HOUR;
+    if within_window && !settings.welcome_back {
+        Greeting::Suppress
+    } else {
+        Greeting::Return {
+            previous_join,
+            within_window,
         }
     }
 }
@@ -503,21 +765,57 @@ async fn notify_member_event(
     event: MemberEvent<'_>,
 ) -> Result<(), Error> {
     let guild_id = match event {
-        MemberEvent::Join(member) => &member.guild_id,
+        MemberEvent::Join(member, _) => &member.guild_id,
         MemberEvent::Leave(guild_id, _) => guild_id,
     };
     let join = match event {
-        MemberEvent::Join(_) => true,
+        MemberEvent::Join(_, _) => true,
         MemberEvent::Leave(_, _) => false,
     };
+    let is_bot = match event {
+        MemberEvent::Join(member, _) => member.user.bot,
+        MemberEvent::Leave(_, user) => user.bot,
+    };
+    let bot_channel = if is_bot {
+        match get_bot_member_mode(&data.db_pool, guild_id).await {
+            BotMemberMode::Suppress => {
+                trace!("Notifications are suppressed for bot accounts");
+                return Ok(());
+            }
+            BotMemberMode::Route(channel) => Some(channel),
+            BotMemberMode::Notify => None,
+        }
+    } else {
+        None
+    };
+    let greeting = match event {
+        MemberEvent::Join(_, greeting) => greeting,
+        MemberEvent::Leave(_, _) => Greeting::Welcome,
+    };
+    if greeting == Greeting::Suppress {
+        trace!("Member rejoined recently, so they are not welcomed again");
+        return Ok(());
+    }
+    let format = async {
+        match greeting {
+            Greeting::Return { within_window, .. } => {
+                match get_return_notification_details(data, guild_id).await {
+                    Some(x) => Some(x),
+                    None if within_window => None,
+                    None => get_member_notification_details(data, guild_id, join).await,
+                }
+            }
+            _ => get_member_notification_details(data, guild_id, join).await,
+        }
+    };
     let (channel, format, guild) = future::join3(
         get_member_notification_channel(&data.db_pool, guild_id, join),
-        get_member_notification_details(&data.db_pool, guild_id, join),
+        format,
         guild_id.to_partial_guild_with_counts(ctx), // TODO: this request is quite large and slow. Figure out how to more quickly retrieve the guild member count.
     )
     .await;
 
-    let channel = match channel {
+    let channel = match bot_channel.or(channel) {
         Some(x) => x,
         None => return Ok(()), // Notification channel not confiugred on this guild.
     };
@@ -528,33 +826,172 @@ async fn notify_member_event(
     };
 
     let content = match event {
-        MemberEvent::Join(member) => {
-            MemberNotificationMessageDetails::for_member(member, guild.ok(), format)
-        }
+        MemberEvent::Join(member, _) => match greeting {
+            Greeting::Return { previous_join, .. } => {
+                MemberNotificationMessageDetails::for_returning_member(
+                    member,
+                    guild.ok(),
+                    previous_join,
+                    format,
+                )
+            }
+            _ => MemberNotificationMessageDetails::for_member(member, guild.ok(), format),
+        },
         MemberEvent::Leave(_, user) => {
             MemberNotificationMessageDetails::for_user(user, guild.ok(), format)
         }
     };
 
-    trace!("Member event content: {:?}", content);
-    let reply = content.to_message(&guild_id).await;
+    trace!(
+        "Member event content: {}",
+        log_content!(&format!("{:?}", content))
+    );
+    let mut reply = content.to_message(&guild_id).await;
+    if let MemberEvent::Leave(_, user) = event
+        && let Some(removal) = get_removal_action(ctx, data, guild_id, user).await
+    {
+        reply = reply.add_embed(removal_embed(data, guild_id, removal).await);
+    }
     channel.send_message(ctx, reply).await?;
     Ok(())
 }
 
+/// Sends the join notification of a pending greeting, once its delay is over and the member passed screening.
+async fn send_pending_greeting(
+    ctx: &Context,
+    data: &Data,
+    key: (GuildId, UserId),
+) -> Result<(), Error> {
+    let greeting = {
+        let mut pending = data
+            .pending_greetings
+            .write()
+            .map_err(|_| "Pending greetings lock is poisoned")?;
+        match pending.get(&key) {
+            Some(x) if !x.wait_for_pending && x.send_at <= data.clock.unix_timestamp() => {
+                pending.remove(&key)
+            }
+            _ => None,
+        }
+    };
+    match greeting {
+        Some(x) => notify_member_event(ctx, data, MemberEvent::Join(&x.member, x.greeting)).await,
+        None => Ok(()),
+    }
+}
+
+/// Sends the join notification of a new member, right away or once the delay configured for the guild is over and
+/// the member passed membership screening.
+async fn greet_member(ctx: &Context, data: &Data, member: &Member) -> Result<(), Error> {
+    let greeting = get_greeting(data, member).await;
+    let settings = NotificationRepo::new(&data.db_pool)
+        .greeting_settings(member.guild_id)
+        .await
+        .unwrap_or_else(|e| {
+            error!("Error occurred while getting greeting settings: {}", e);
+            GreetingSettings::default()
+        });
+    let delay = settings.delay_seconds.max(0) as u64;
+    let wait_for_pending = settings.wait_for_pending && member.pending;
+    if delay == 0 && !wait_for_pending {
+        return notify_member_event(ctx, data, MemberEvent::Join(member, greeting)).await;
+    }
+
+    let key = (member.guild_id, member.user.id);
+    let send_at = data.clock.unix_timestamp() + delay as i64;
+    data.pending_greetings
+        .write()
+        .map_err(|_| "Pending greetings lock is poisoned")?
+        .insert(
+            key,
+            PendingGreeting {
+                member: member.clone(),
+                greeting,
+                send_at,
+                wait_for_pending,
+            },
+        );
+    debug!(
+        "Join notification delayed by {}s{}",
+        delay,
+        if wait_for_pending {
+            ", until the member passed screening"
+        } else {
+            ""
+        }
+    );
+    let ctx = ctx.clone();
+    let data = data.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(delay)).await;
+        if let Err(e) = send_pending_greeting(&ctx, &data, key).await {
+            error!("Failed to welcome new member: {}", e);
+        }
+        // Members still in screening are sent their notification by on_member_update, until they are given up on
+        let is_current = |pending: &HashMap<(GuildId, UserId), PendingGreeting>| {
+            pending.get(&key).is_some_and(|x| x.send_at == send_at)
+        };
+        if !data.pending_greetings.read().is_ok_and(|x| is_current(&x)) {
+            return;
+        }
+        tokio::time::sleep(MAX_PENDING_WAIT).await;
+        if let Ok(mut pending) = data.pending_greetings.write()
+            && is_current(&pending)
+        {
+            pending.remove(&key);
+            debug!("Member didn't pass screening in time, so they are not welcomed");
+        }
+    });
+    Ok(())
+}
+
+/// Sends the join notification that waited for a member to pass membership screening.
+pub async fn on_member_update(
+    ctx: &Context,
+    data: &Data,
+    event: &GuildMemberUpdateEvent,
+) -> Result<(), Error> {
+    if event.pending {
+        return Ok(());
+    }
+    let key = (event.guild_id, event.user.id);
+    {
+        let mut pending = data
+            .pending_greetings
+            .write()
+            .map_err(|_| "Pending greetings lock is poisoned")?;
+        match pending.get_mut(&key) {
+            Some(x) if x.wait_for_pending => {
+                x.wait_for_pending = false;
+                x.member.pending = false;
+            }
+            _ => return Ok(()),
+        }
+    }
+    debug!("Member passed screening");
+    send_pending_greeting(ctx, data, key).await
+}
+
+/// Gives a member the default roles of their guild.
 #[tracing::instrument(level = Level::DEBUG, err(level = Level::WARN), skip_all)]
-async fn add_initial_member_roles(
+pub async fn add_initial_member_roles(
     ctx: &Context,
     data: &Data,
     new_member: &Member,
 ) -> Result<(), Error> {
-    match get_member_roles_on_join(&data.db_pool, &new_member.guild_id).await {
-        Some(roles) => match new_member.add_roles(ctx, &roles).await {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e.into()),
-        },
-        None => Ok(()),
+    if new_member.user.bot
+        && get_bot_member_mode(&data.db_pool, &new_member.guild_id).await == BotMemberMode::Suppress
+    {
+        trace!("Default roles are suppressed for bot accounts");
+        return Ok(());
+    }
+    let roles = RolesRepo::new(&data.db_pool)
+        .default_roles(new_member.guild_id)
+        .await?;
+    if !roles.is_empty() {
+        new_member.add_roles(ctx, &roles).await?;
     }
+    Ok(())
 }
 
 #[tracing::instrument(level = tracing::Level::INFO, err(level = tracing::Level::WARN), skip_all, fields(user = tracing::field::Empty, guild_id = tracing::field::Empty))]
@@ -564,10 +1001,37 @@ pub async fn guild_member_add(
     new_member: &Member,
 ) -> Result<(), Error> {
     record_member_fields!(new_member);
-    if let Err(e) = notify_member_event(ctx, data, MemberEvent::Join(new_member)).await {
+    record_activity(
+        &data.db_pool,
+        data.clock.as_ref(),
+        new_member.guild_id,
+        ModActivity::Join,
+    )
+    .await;
+    let roles_paused = match raid_protection::on_member_join(ctx, data, new_member).await {
+        Ok(paused) => paused,
+        Err(e) => {
+            error!("Failed to run raid detection for new member: {}", e);
+            false
+        }
+    };
+    if let Err(e) = greet_member(ctx, data, new_member).await {
         error!("Failed to welcome new member: {}", e)
     }
-    if let Err(e) = add_initial_member_roles(ctx, data, new_member).await {
+    if roles_paused {
+        info!("Default role assignment is paused by raid lockdown");
+        return Ok(());
+    }
+    let screened = match screening::on_member_join(ctx, data, new_member).await {
+        Ok(screened) => screened,
+        Err(e) => {
+            error!("Failed to start screening new member: {}", e);
+            false
+        }
+    };
+    if screened {
+        debug!("Default roles are given once the member is approved by screening");
+    } else if let Err(e) = add_initial_member_roles(ctx, data, new_member).await {
         error!("Failed to add roles to new member: {}", e)
     }
     Ok(())
@@ -581,8 +1045,47 @@ pub async fn guild_member_remove(
     user: &User,
 ) -> Result<(), Error> {
     record_member_fields!(user, guild_id);
+    record_activity(
+        &data.db_pool,
+        data.clock.as_ref(),
+        *guild_id,
+        ModActivity::Leave,
+    )
+    .await;
+    if let Ok(mut pending) = data.pending_greetings.write()
+        && pending.remove(&(*guild_id, user.id)).is_some()
+    {
+        debug!("Member left before their join notification was sent");
+    }
     if let Err(e) = notify_member_event(ctx, data, MemberEvent::Leave(guild_id, user)).await {
         error!("Failed to welcome member leave: {}", e)
     }
+    release_color_role(&ctx.http, &data.db_pool, *guild_id, user.id, false).await?;
     Ok(())
 }
+
+/// Forgets the members seen joining before. Members are kept by default, since forgotten members are welcomed like new
+/// members when they return.
+pub struct KnownMemberRetention;
+
+#[async_trait]
+impl RetentionTask for KnownMemberRetention {
+    fn name(&self) -> &'static str {
+        "known_members"
+    }
+
+    async fn expire(
+        &self,
+        db: &DatabaseConnection,
+        now: i64,
+        retention_days: u32,
+    ) -> Result<u64, Error> {
+        NotificationRepo::new(db)
+            .remove_known_members_before(now - retention_days as i64 * 24 * SECONDS_PER_HOUR)
+            .await
+    }
+
+    async fn forget_user(&self, db: &DatabaseConnection, user_id: UserId) -> Result<u64, Error> {
+        NotificationRepo::new(db).remove_user_joins(user_id).await
+    }
+}
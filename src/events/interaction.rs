@@ -0,0 +1,54 @@
+/*
+    Routes message component interactions (buttons, select menus) to the feature that created them.
+
+    Components are identified by a custom_id prefix owned by each feature. Components without a known prefix
+    are ignored, since poise collectors (e.g. the register buttons) handle their own interactions.
+*/
+
+use poise::serenity_prelude::{ComponentInteraction, Context};
+use tracing::trace;
+
+use crate::{
+    Error,
+    commands::{confess, game_server, minecraft},
+    events::{ban_sync, screening},
+    infrastructure::{blocklist, botdata::Data, game_nights},
+};
+
+pub async fn on_component_interaction(
+    ctx: &Context,
+    data: &Data,
+    interaction: &ComponentInteraction,
+) -> Result<(), Error> {
+    if blocklist::is_blocked(&data.db_pool, interaction.user.id).await {
+        trace!("Ignoring component interaction from blocked user");
+        return Ok(());
+    }
+    let custom_id = interaction.data.custom_id.as_str();
+    if let Some(server_id) = custom_id.strip_prefix(minecraft::REFRESH_STATUS_ID_PREFIX) {
+        return minecraft::refresh_status(ctx, data, interaction, server_id).await;
+    }
+    if let Some(name) = custom_id.strip_prefix(game_server::REFRESH_STATUS_ID_PREFIX) {
+        return game_server::refresh_status(ctx, data, interaction, name).await;
+    }
+    if let Some(action) = custom_id.strip_prefix(confess::CONFESSION_REVIEW_ID_PREFIX) {
+        return confess::handle_review(ctx, data, interaction, action).await;
+    }
+    if let Some(action) = custom_id.strip_prefix(ban_sync::BAN_SYNC_ID_PREFIX) {
+        return ban_sync::handle_review(ctx, data, interaction, action).await;
+    }
+    if let Some(action) = custom_id.strip_prefix(screening::SCREENING_ID_PREFIX) {
+        return screening::handle_interaction(ctx, data, interaction, action).await;
+    }
+    if let Some(action) = custom_id.strip_prefix(game_nights::GAME_NIGHT_ID_PREFIX) {
+        return game_nights::handle_interaction(ctx, data, interaction, action).await;
+    }
+    #[cfg(feature = "voice")]
+    if let Some(action) = custom_id.strip_prefix(crate::commands::voice::PLAYBACK_CONTROL_ID_PREFIX)
+    {
+        return crate::commands::voice::handle_playback_control(ctx, interaction, action).await;
+    }
+
+    trace!("Ignoring component interaction: {}", custom_id);
+    Ok(())
+}
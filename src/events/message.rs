@@ -1,56 +1,21 @@
 use crate::{
     Error,
-    infrastructure::{botdata::Data, ids, util::send_message_from_reply},
-    lazy_regex,
+    entities::auto_response_channel,
+    events::{
+        games, reaction_map,
+        responders::{
+            Responder, disabled_responders, respond, responders, roll_probability,
+            try_start_cooldown,
+        },
+        unfurl,
+    },
+    infrastructure::{blocklist, botdata::Data, ids::id_to_string},
+    log_user,
 };
-use poise::{
-    CreateReply,
-    serenity_prelude::{Context, Emoji, GuildId, Http, Message, ReactionType},
-};
-use rand::seq::IndexedRandom;
+use poise::serenity_prelude::{Channel, ChannelId, Context, GuildId, Message};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
 use tracing::{info, warn};
 
-lazy_regex! { BODY_REGEX, r"\bbody+\b"}
-lazy_regex! { RED_SUS_REGEX, r"\bred sus\b"}
-lazy_regex! { BLUE_SUS_REGEX, r"\bblue sus\b"}
-lazy_regex! { NAV_REGEX, r"\bnav\b"}
-lazy_regex! { BLITZCRANK_REGEX, r"\bblitzcrank\b"}
-lazy_regex! { MEETING_REGEX, r"\bmeeting\b"}
-lazy_regex! { IMPOSTERBOT_REGEX, r"\bimposterbot\b"}
-lazy_regex! { SAD_REGEX, r"\bi(('*m)|( am)) sad\b"}
-lazy_regex! { OWO_REGEX, r"\bowo\b"}
-lazy_regex! { VENTED_REGEX, r"\bvented\b"}
-lazy_regex! { SUSPICIOUS_REGEX, r"\bsuspicious\b"}
-lazy_regex! { WHO_YOU_GONNA_CALL_REGEX, r"\bwho you gonna call\b"}
-lazy_regex! { PAIN_REGEX, r"\bpain\b"}
-
-async fn get_emote_by_name(
-    ctx: impl AsRef<Http>,
-    guild: Option<GuildId>,
-    emote_name: &str,
-) -> Option<Emoji> {
-    if let Some(gid) = guild {
-        return match gid.emojis(ctx).await {
-            Ok(emojis) => match emojis.iter().find(|emoji| {
-                emoji
-                    .name
-                    .to_lowercase()
-                    .contains(emote_name.to_lowercase().as_str())
-            }) {
-                Some(emoji) => Some(emoji.clone()),
-                _ => None,
-            },
-            _ => None,
-        };
-    }
-
-    None
-}
-
-fn rand_message(messages: &[&str]) -> String {
-    messages.choose(&mut rand::rng()).unwrap_or(&"").to_string()
-}
-
 fn matches_prefix(framework: poise::FrameworkContext<'_, Data, Error>, content: &String) -> bool {
     if let Some(p) = &framework.options.prefix_options.prefix
         && content.starts_with(p)
@@ -61,42 +26,120 @@ fn matches_prefix(framework: poise::FrameworkContext<'_, Data, Error>, content:
     return false;
 }
 
-async fn send_reaction(
-    message: &Message,
-    ctx: &Context,
-    emote_name: &str,
-    guild_id: Option<GuildId>,
-    on_guild_string: &String,
-) -> Result<(), Error> {
-    let emote_option = get_emote_by_name(ctx, guild_id, emote_name).await;
-    if let Some(emote) = emote_option {
-        let reaction = ReactionType::Custom {
-            animated: emote.animated,
-            id: emote.id,
-            name: Some(emote.name),
-        };
-        message.react(ctx, reaction).await?;
-    } else {
-        warn!("Emoji 'pain' was not found {}", on_guild_string);
+/// Gets the channel a thread was created in, or the channel itself if it isn't a thread.
+async fn thread_parent(ctx: &Context, guild_id: GuildId, channel_id: ChannelId) -> ChannelId {
+    let cached = ctx.cache.guild(guild_id).and_then(|guild| {
+        if guild.channels.contains_key(&channel_id) {
+            return Some(channel_id);
+        }
+        guild
+            .threads
+            .iter()
+            .find(|x| x.id == channel_id)
+            .map(|x| x.parent_id.unwrap_or(channel_id))
+    });
+    if let Some(channel_id) = cached {
+        return channel_id;
+    }
+    match channel_id.to_channel(ctx).await {
+        Ok(Channel::Guild(channel)) if channel.thread_metadata.is_some() => {
+            channel.parent_id.unwrap_or(channel_id)
+        }
+        _ => channel_id,
     }
+}
 
-    Ok(())
+/// Checks whether auto-responses may fire in a guild channel.
+///
+/// Denied channels never respond. If the guild allows any channel, only allowed channels respond. Threads follow the
+/// rules of the channel they were created in.
+pub async fn auto_responses_enabled(
+    ctx: &Context,
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+) -> Result<bool, Error> {
+    let channel_id = thread_parent(ctx, guild_id, channel_id).await;
+    let rules = auto_response_channel::Entity::find()
+        .filter(auto_response_channel::Column::GuildId.eq(id_to_string(guild_id)))
+        .all(db)
+        .await?;
+    let channel_id = id_to_string(channel_id);
+    Ok(
+        match rules.iter().find(|rule| rule.channel_id == channel_id) {
+            Some(rule) => rule.allow,
+            None => !rules.iter().any(|rule| rule.allow),
+        },
+    )
 }
 
 pub async fn on_message(
     ctx: &Context,
     framework: poise::FrameworkContext<'_, Data, Error>,
-    _data: &Data,
+    data: &Data,
     message: &Message,
 ) -> Result<(), Error> {
-    if message.author.bot || matches_prefix(framework, &message.content) {
+    if message.author.bot
+        || matches_prefix(framework, &message.content)
+        || blocklist::is_blocked(&data.db_pool, message.author.id).await
+    {
+        return Ok(());
+    }
+
+    if games::on_message(ctx, data, message).await? {
+        return Ok(());
+    }
+
+    if let Err(e) = unfurl::on_message(ctx, data, message).await {
+        warn!("Link preview handler produced an error: {:?}", e);
+    }
+
+    if let Err(e) = reaction_map::on_message(ctx, data, message).await {
+        warn!("Reaction map handler produced an error: {:?}", e);
+    }
+
+    #[cfg(feature = "ai")]
+    match crate::events::ai_chat::on_message(ctx, data, message).await {
+        Ok(true) => return Ok(()),
+        Ok(false) => {}
+        Err(e) => warn!("Conversational reply handler produced an error: {:?}", e),
+    }
+
+    let matched: Vec<&dyn Responder> = responders()
+        .iter()
+        .map(|responder| responder.as_ref())
+        .filter(|responder| responder.matches(message))
+        .collect();
+    if matched.is_empty() {
         return Ok(());
     }
 
-    // Gathering metadata about message...
     let guild_id = message.guild_id;
-    let guild_name = guild_id.and_then(|id| id.name(&ctx.cache));
+    let disabled = match guild_id {
+        Some(guild_id) => {
+            if !auto_responses_enabled(ctx, &data.db_pool, guild_id, message.channel_id).await? {
+                return Ok(());
+            }
+            disabled_responders(&data.db_pool, guild_id).await?
+        }
+        None => Default::default(),
+    };
+
+    let Some(responder) = matched.into_iter().find(|responder| {
+        !disabled.contains(responder.name())
+            && roll_probability(data.random.as_ref(), *responder)
+            && try_start_cooldown(
+                &data.responder_cooldowns,
+                data.clock.as_ref(),
+                *responder,
+                message.channel_id,
+            )
+    }) else {
+        return Ok(());
+    };
 
+    // Gathering metadata about message...
+    let guild_name = guild_id.and_then(|id| id.name(&ctx.cache));
     let username = &message.author.name;
     let display_name = if let Some(gid) = guild_id {
         message.author.nick_in(ctx, gid).await
@@ -111,119 +154,19 @@ pub async fn on_message(
     } else {
         "".into()
     };
+    info!(
+        "User '{}' triggered responder '{}' {}",
+        log_user!(&display_name),
+        responder.name(),
+        on_guild_string
+    );
 
-    let content_lower = message.content.to_lowercase();
-    if BODY_REGEX.is_match(&message.content) {
-        info!("User '{}' said 'body' {}", display_name, on_guild_string);
-        let reply = CreateReply::default().content("where");
-        send_message_from_reply(&message.channel_id, ctx, reply).await?;
-    } else if RED_SUS_REGEX.is_match(&message.content) {
-        info!("User '{}' said 'red sus' {}", display_name, on_guild_string);
-        let reply = CreateReply::default().content("I agree, vote red.");
-        send_message_from_reply(&message.channel_id, ctx, reply).await?;
-    } else if BLUE_SUS_REGEX.is_match(&message.content) {
-        info!(
-            "User '{}' said 'blue sus' {}",
-            display_name, on_guild_string
-        );
-        let reply =
-            CreateReply::default().content("I think blue is safe, I saw them do a med scan.");
-        send_message_from_reply(&message.channel_id, ctx, reply).await?;
-    } else if NAV_REGEX.is_match(&message.content) {
-        info!("User '{}' said 'nav' {}", display_name, on_guild_string);
-        let reply = CreateReply::default().content("I was just in nav, didn't see anyone.");
-        send_message_from_reply(&message.channel_id, ctx, reply).await?;
-    } else if BLITZCRANK_REGEX.is_match(&message.content) {
-        info!(
-            "User '{}' said 'blitzcrank' {}",
-            display_name, on_guild_string
-        );
-        message
-            .react(ctx, ReactionType::Unicode("👍".to_string()))
-            .await?;
-    } else if MEETING_REGEX.is_match(&message.content) {
-        info!("User '{}' said 'meeting' {}", display_name, on_guild_string);
-        send_reaction(message, ctx, "deny", guild_id, &on_guild_string).await?;
-        let reply = CreateReply::default().content("**Loud meeting button noise**");
-        send_message_from_reply(&message.channel_id, ctx, reply).await?;
-    } else if IMPOSTERBOT_REGEX.is_match(&message.content) {
-        info!(
-            "User '{}' said 'imposterbot' {}",
-            display_name, on_guild_string
-        );
-        let responses = [
-            "Not me, vote cyan.",
-            "I was in admin.",
-            "Didn't see orange at O2..",
-            "It wasn't me, vote lime.",
-        ];
-        let reply = CreateReply::default().content(rand_message(&responses));
-        send_message_from_reply(&message.channel_id, ctx, reply).await?;
-    } else if SAD_REGEX.is_match(&message.content) {
-        info!(
-            "User '{}' said they are sad {}",
-            display_name, on_guild_string
-        );
-        let responses = ["Don't be sad 😢", "Cheer up!"]; // Simplified emoji
-        let reply = CreateReply::default().content(rand_message(&responses));
-        send_message_from_reply(&message.channel_id, ctx, reply).await?;
-    } else if OWO_REGEX.is_match(&content_lower) {
-        info!("User '{}' said 'owo' {}", display_name, on_guild_string);
-        let reply = CreateReply::default().content("OwO?");
-        send_message_from_reply(&message.channel_id, ctx, reply).await?;
-    } else if VENTED_REGEX.is_match(&message.content) {
-        info!("User '{}' said 'vented' {}", display_name, on_guild_string);
-        let responses = [
-            "Was it green? I thought I saw them vent.",
-            "I was in storage.. no where near any vents.",
-        ];
-        let reply = CreateReply::default().content(rand_message(&responses));
-        send_message_from_reply(&message.channel_id, ctx, reply).await?;
-        let emote_option = get_emote_by_name(ctx, guild_id, "deny").await;
-        if let Some(emote) = emote_option {
-            let reaction = ReactionType::Custom {
-                animated: emote.animated,
-                id: emote.id,
-                name: Some(emote.name),
-            };
-            message.react(ctx, reaction).await?;
-        }
-    } else if SUSPICIOUS_REGEX.is_match(&message.content) {
-        info!(
-            "User '{}' said 'suspicious' {}",
-            display_name, on_guild_string
-        );
-        let reply = CreateReply::default().content("Very sus.");
-        send_message_from_reply(&message.channel_id, ctx, reply).await?;
-        let reply = CreateReply::default().content("👀");
-        send_message_from_reply(&message.channel_id, ctx, reply).await?;
-    } else if WHO_YOU_GONNA_CALL_REGEX.is_match(&message.content) {
-        info!("User '{}' said 'pain' {}", display_name, on_guild_string);
-        let reply = CreateReply::default().content("ghost busters!");
-        send_message_from_reply(&message.channel_id, ctx, reply).await?;
-    } else if PAIN_REGEX.is_match(&message.content) {
-        info!("User '{}' said 'pain' {}", display_name, on_guild_string);
-        let emote_option = get_emote_by_name(ctx, guild_id, "pain").await;
-        if let Some(emote) = emote_option {
-            let reaction = ReactionType::Custom {
-                animated: emote.animated,
-                id: emote.id,
-                name: Some(emote.name),
-            };
-            message.react(ctx, reaction).await?;
-        } else {
-            warn!("Emoji 'pain' was not found {}", on_guild_string);
-        }
-    } else if message.content == "<:doggoban:802308677737381948>"
-        && [ids::KHAZAARI_ID, ids::CRESSY_ID].contains(&message.author.id)
-    {
-        info!(
-            "User '{}' sent doggoban emoji {}",
-            display_name, on_guild_string
-        );
-        let reply = CreateReply::default().content("Banning **MoustachioMario#2067**");
-        send_message_from_reply(&message.channel_id, ctx, reply).await?;
-    }
-
-    Ok(())
+    respond(
+        ctx,
+        data.random.as_ref(),
+        &data.guild_emojis,
+        message,
+        responder,
+    )
+    .await
 }
@@ -0,0 +1,54 @@
+/*
+    Records the username, display name and nickname changes of members into the name_history table, so moderators
+    can review the past identities of a member with `/namehistory`.
+
+    Changes are detected from guild member updates against the cached member, so changes made while the bot was
+    offline, or to members not cached yet, are missed. Username changes are recorded on every guild the member shares
+    with the bot, so each guild only sees what happened while the member was there.
+*/
+
+use poise::serenity_prelude::{GuildMemberUpdateEvent, Member};
+
+use crate::{
+    Error,
+    infrastructure::botdata::Data,
+    services::name_history::{NameChange, NameHistoryRepo, NameKind},
+};
+
+pub async fn on_member_update(
+    data: &Data,
+    old: Option<&Member>,
+    event: &GuildMemberUpdateEvent,
+) -> Result<(), Error> {
+    let Some(old) = old else {
+        return Ok(());
+    };
+    let changed_at = data.clock.unix_timestamp();
+    let changes = [
+        (
+            NameKind::Username,
+            Some(&old.user.name),
+            Some(&event.user.name),
+        ),
+        (
+            NameKind::GlobalName,
+            old.user.global_name.as_ref(),
+            event.user.global_name.as_ref(),
+        ),
+        (NameKind::Nickname, old.nick.as_ref(), event.nick.as_ref()),
+    ];
+    let repo = NameHistoryRepo::new(&data.db_pool);
+    for (kind, old_name, new_name) in changes {
+        if old_name == new_name {
+            continue;
+        }
+        let change = NameChange {
+            kind,
+            old_name: old_name.cloned().unwrap_or_default(),
+            new_name: new_name.cloned().unwrap_or_default(),
+            changed_at,
+        };
+        repo.record(event.guild_id, event.user.id, &change).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,212 @@
+/*
+    Normalizes member display names when they join or change their nickname.
+
+    Names starting with hoisting characters (punctuation used to sort to the top of the member list) have those
+    characters stripped, and names containing a disallowed word are replaced using the guild's replacement pattern.
+    Every rename is logged to the guild's mod-log channel, if one is configured.
+*/
+
+use poise::serenity_prelude::{
+    ChannelId, Context, CreateMessage, EditMember, GuildId, GuildMemberUpdateEvent, Member,
+    Mentionable, User,
+};
+use sea_orm::{DatabaseConnection, EntityTrait};
+use tracing::{error, info, warn};
+
+use crate::{
+    Error,
+    entities::nickname_filter,
+    events::webhooks::dispatch_mod_action,
+    infrastructure::{
+        botdata::Data,
+        branding::get_branding,
+        ids::{id_from_string, id_to_string},
+        mod_digest::record_activity,
+    },
+    services::mod_digest::ModActivity,
+};
+
+/// Maximum length of a nickname allowed by discord.
+const MAX_NICKNAME_LENGTH: usize = 32;
+
+/// Name used when the replacement pattern itself produces an unusable name.
+const FALLBACK_NICKNAME: &str = "Member";
+
+pub async fn get_nickname_filter_config(
+    db: &DatabaseConnection,
+    guild_id: &GuildId,
+) -> Option<nickname_filter::Model> {
+    match nickname_filter::Entity::find_by_id(id_to_string(*guild_id))
+        .one(db)
+        .await
+    {
+        Ok(model) => model,
+        Err(e) => {
+            error!("Failed to get nickname filter configuration: {}", e);
+            None
+        }
+    }
+}
+
+/// Splits the comma separated disallowed words of a configuration.
+pub fn disallowed_words(config: &nickname_filter::Model) -> Vec<String> {
+    config
+        .disallowed_words
+        .split(',')
+        .map(|word| word.trim().to_lowercase())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+fn is_hoisting_char(c: char) -> bool {
+    c.is_ascii_punctuation() || c.is_whitespace()
+}
+
+fn contains_disallowed_word(name: &str, words: &[String]) -> bool {
+    let name = name.to_lowercase();
+    words.iter().any(|word| name.contains(word.as_str()))
+}
+
+fn truncate_nickname(name: &str) -> String {
+    name.chars().take(MAX_NICKNAME_LENGTH).collect()
+}
+
+/// Computes the normalized form of a display name, or None if the name is acceptable.
+///
+/// Returns the new name and the reason it was changed.
+pub fn normalize_nickname(
+    config: &nickname_filter::Model,
+    user: &User,
+    name: &str,
+) -> Option<(String, &'static str)> {
+    let words = disallowed_words(config);
+    let replacement = || {
+        let replaced = truncate_nickname(
+            config
+                .replacement
+                .replace("{username}", &user.name)
+                .replace("{id}", &user.id.to_string())
+                .trim(),
+        );
+        if replaced.is_empty()
+            || replaced.starts_with(is_hoisting_char)
+            || contains_disallowed_word(&replaced, &words)
+        {
+            FALLBACK_NICKNAME.to_string()
+        } else {
+            replaced
+        }
+    };
+
+    if contains_disallowed_word(name, &words) {
+        return Some((replacement(), "disallowed word"));
+    }
+    if config.dehoist && name.starts_with(is_hoisting_char) {
+        let stripped = truncate_nickname(name.trim_start_matches(is_hoisting_char));
+        let stripped = if stripped.is_empty() {
+            replacement()
+        } else {
+            stripped
+        };
+        return Some((stripped, "hoisting"));
+    }
+    None
+}
+
+/// Renames a member if their display name violates the guild's nickname filter.
+async fn apply_nickname_filter(
+    ctx: &Context,
+    data: &Data,
+    guild_id: GuildId,
+    user: &User,
+    nick: Option<&str>,
+) -> Result<(), Error> {
+    let config = match get_nickname_filter_config(&data.db_pool, &guild_id).await {
+        Some(config) if config.enabled => config,
+        _ => return Ok(()),
+    };
+    if user.bot {
+        return Ok(());
+    }
+
+    let name = nick
+        .or(user.global_name.as_deref())
+        .unwrap_or(user.name.as_str());
+    let (new_name, reason) = match normalize_nickname(&config, user, name) {
+        Some(x) if x.0 != name => x,
+        _ => return Ok(()),
+    };
+
+    if let Err(e) = guild_id
+        .edit_member(
+            ctx,
+            user.id,
+            EditMember::new()
+                .nickname(&new_name)
+                .audit_log_reason("Nickname filter"),
+        )
+        .await
+    {
+        warn!("Failed to rename member {}: {:?}", user.id, e);
+        return Ok(());
+    }
+    info!(
+        "Renamed member {} on guild {} ({})",
+        user.id, guild_id, reason
+    );
+    record_activity(
+        &data.db_pool,
+        data.clock.as_ref(),
+        guild_id,
+        ModActivity::NicknameFiltered,
+    )
+    .await;
+    dispatch_mod_action(data, guild_id, "nickname_filtered", Some(user));
+
+    if let Ok(channel) = id_from_string::<ChannelId>(&config.log_channel_id) {
+        let branding = get_branding(&data.db_pool, &data.branding, guild_id).await;
+        let embed = branding
+            .embed()
+            .title("Nickname changed")
+            .description(format!(
+                "{}: `{}` → `{}`\nReason: {}",
+                user.mention(),
+                name,
+                new_name,
+                reason
+            ));
+        if let Err(e) = channel
+            .send_message(ctx, CreateMessage::new().embed(embed))
+            .await
+        {
+            warn!("Failed to send nickname filter log: {:?}", e);
+        }
+    }
+    Ok(())
+}
+
+pub async fn on_member_join(ctx: &Context, data: &Data, member: &Member) -> Result<(), Error> {
+    apply_nickname_filter(
+        ctx,
+        data,
+        member.guild_id,
+        &member.user,
+        member.nick.as_deref(),
+    )
+    .await
+}
+
+pub async fn on_member_update(
+    ctx: &Context,
+    data: &Data,
+    event: &GuildMemberUpdateEvent,
+) -> Result<(), Error> {
+    apply_nickname_filter(
+        ctx,
+        data,
+        event.guild_id,
+        &event.user,
+        event.nick.as_deref(),
+    )
+    .await
+}
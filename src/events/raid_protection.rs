@@ -0,0 +1,419 @@
+/*
+    Detects raids by monitoring member join velocity and the age of joining accounts.
+
+    When a raid is detected the guild enters a lockdown, which (depending on guild configuration) raises the guild
+    verification level, pauses default role assignment, and alerts a moderator channel. The lockdown ends
+    automatically after a configured duration, or manually with `/raid-protection end-lockdown`.
+
+    Active lockdowns are stored in the database, so a restart neither leaves the verification level raised forever
+    nor lets members through before the lockdown was meant to end.
+*/
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use migration::OnConflict;
+use poise::serenity_prelude::{
+    ChannelId, Context, CreateEmbed, CreateMessage, EditGuild, GuildId, Member, User,
+    VerificationLevel,
+};
+use sea_orm::{ActiveValue::Set, DatabaseConnection, EntityTrait};
+use tracing::{error, info, warn};
+
+use crate::{
+    Error,
+    entities::{raid_lockdown, raid_protection},
+    events::webhooks::dispatch_mod_action,
+    infrastructure::{
+        botdata::Data,
+        clock::Clock,
+        colors,
+        ids::{id_from_string, id_to_string},
+        mod_digest::record_activity,
+    },
+    services::mod_digest::ModActivity,
+};
+
+pub type RaidStateMap = Arc<RwLock<HashMap<GuildId, RaidState>>>;
+
+/// In-memory join tracking for a single guild.
+#[derive(Debug, Default)]
+pub struct RaidState {
+    /// Joins within the detection window, and whether each joining account was considered new.
+    recent_joins: VecDeque<(Instant, bool)>,
+    lockdown: Option<Lockdown>,
+}
+
+#[derive(Debug, Clone)]
+struct Lockdown {
+    until: Instant,
+    pause_default_roles: bool,
+    /// Verification level before the lockdown raised it, restored when the lockdown ends.
+    previous_verification_level: Option<VerificationLevel>,
+    alert_channel: Option<ChannelId>,
+}
+
+pub async fn get_raid_protection_config(
+    db: &DatabaseConnection,
+    guild_id: &GuildId,
+) -> Option<raid_protection::Model> {
+    match raid_protection::Entity::find_by_id(id_to_string(*guild_id))
+        .one(db)
+        .await
+    {
+        Ok(model) => model,
+        Err(e) => {
+            error!("Failed to get raid protection configuration: {}", e);
+            None
+        }
+    }
+}
+
+fn account_age_hours(clock: &dyn Clock, user: &User) -> i64 {
+    (clock.unix_timestamp() - user.id.created_at().unix_timestamp()) / 3600
+}
+
+/// Remaining lockdown time for a guild, if it is currently locked down.
+pub fn lockdown_remaining(raid_state: &RaidStateMap, guild_id: &GuildId) -> Option<Duration> {
+    let state = raid_state.read().ok()?;
+    let lockdown = state.get(guild_id)?.lockdown.as_ref()?;
+    lockdown.until.checked_duration_since(Instant::now())
+}
+
+/// Records a member join and starts a lockdown if the configured thresholds are exceeded.
+///
+/// Returns true if default role assignment is paused for this member.
+pub async fn on_member_join(ctx: &Context, data: &Data, member: &Member) -> Result<bool, Error> {
+    let guild_id = member.guild_id;
+    let config = match get_raid_protection_config(&data.db_pool, &guild_id).await {
+        Some(config) if config.enabled => config,
+        _ => return Ok(false),
+    };
+
+    let now = Instant::now();
+    let window = Duration::from_secs(config.window_seconds.max(1) as u64);
+    let duration = Duration::from_secs(config.lockdown_minutes.max(1) as u64 * 60);
+    let is_new_account =
+        account_age_hours(data.clock.as_ref(), &member.user) < config.account_age_hours as i64;
+
+    let (lockdown, joins, new_accounts) = {
+        let mut states = data
+            .raid_state
+            .write()
+            .map_err(|_| "Raid protection state lock is poisoned")?;
+        let state = states.entry(guild_id).or_default();
+
+        while let Some((time, _)) = state.recent_joins.front()
+            && now.duration_since(*time) > window
+        {
+            state.recent_joins.pop_front();
+        }
+        state.recent_joins.push_back((now, is_new_account));
+
+        if let Some(lockdown) = &state.lockdown
+            && lockdown.until > now
+        {
+            return Ok(lockdown.pause_default_roles);
+        }
+
+        let joins = state.recent_joins.len();
+        let new_accounts = state.recent_joins.iter().filter(|(_, new)| *new).count();
+        let velocity_exceeded =
+            config.join_threshold > 0 && joins >= config.join_threshold as usize;
+        let new_accounts_exceeded = config.new_account_threshold > 0
+            && new_accounts >= config.new_account_threshold as usize;
+        if !velocity_exceeded && !new_accounts_exceeded {
+            return Ok(false);
+        }
+
+        // Reserve the lockdown before releasing the lock, so joins detected while it starts don't start another one.
+        // A lockdown that ran out but wasn't ended yet already raised the verification level, so its previous level
+        // is kept to be restored later.
+        let lockdown = Lockdown {
+            until: now + duration,
+            pause_default_roles: config.pause_default_roles,
+            previous_verification_level: state
+                .lockdown
+                .take()
+                .and_then(|x| x.previous_verification_level),
+            alert_channel: id_from_string::<ChannelId>(&config.alert_channel_id).ok(),
+        };
+        state.lockdown = Some(lockdown.clone());
+        (lockdown, joins, new_accounts)
+    };
+
+    warn!(
+        joins = joins,
+        new_accounts = new_accounts,
+        "Raid detected on guild {}",
+        guild_id
+    );
+    start_lockdown(ctx, data, guild_id, &config, lockdown, joins, new_accounts).await?;
+    Ok(config.pause_default_roles)
+}
+
+/// Applies a lockdown reserved by [`on_member_join`]: raises the verification level, saves the lockdown and alerts
+/// moderators.
+async fn start_lockdown(
+    ctx: &Context,
+    data: &Data,
+    guild_id: GuildId,
+    config: &raid_protection::Model,
+    mut lockdown: Lockdown,
+    joins: usize,
+    new_accounts: usize,
+) -> Result<(), Error> {
+    let duration = lockdown.until.saturating_duration_since(Instant::now());
+    let alert_channel = lockdown.alert_channel;
+
+    let mut raised_verification_level = None;
+    if config.raise_verification && lockdown.previous_verification_level.is_none() {
+        match guild_id.to_partial_guild(ctx).await {
+            Ok(guild) if guild.verification_level < VerificationLevel::High => {
+                match guild_id
+                    .edit(
+                        ctx,
+                        EditGuild::new().verification_level(VerificationLevel::High),
+                    )
+                    .await
+                {
+                    Ok(_) => raised_verification_level = Some(guild.verification_level),
+                    Err(e) => warn!("Failed to raise verification level: {:?}", e),
+                }
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to get guild verification level: {:?}", e),
+        }
+    }
+
+    if raised_verification_level.is_some() {
+        lockdown.previous_verification_level = raised_verification_level;
+    }
+
+    let still_active = {
+        let mut states = data
+            .raid_state
+            .write()
+            .map_err(|_| "Raid protection state lock is poisoned")?;
+        match states
+            .get_mut(&guild_id)
+            .and_then(|state| state.lockdown.as_mut())
+        {
+            Some(active) if active.until == lockdown.until => {
+                active.previous_verification_level = lockdown.previous_verification_level;
+                true
+            }
+            _ => false,
+        }
+    };
+    if !still_active {
+        // The lockdown was ended while it was starting, so the verification level is put back right away
+        if let Some(level) = raised_verification_level {
+            guild_id
+                .edit(ctx, EditGuild::new().verification_level(level))
+                .await?;
+        }
+        return Ok(());
+    }
+
+    let until = data.clock.unix_timestamp() + duration.as_secs() as i64;
+    if let Err(e) = save_lockdown(&data.db_pool, guild_id, &lockdown, until).await {
+        warn!("Failed to save raid lockdown: {:?}", e);
+    }
+    record_activity(
+        &data.db_pool,
+        data.clock.as_ref(),
+        guild_id,
+        ModActivity::RaidLockdown,
+    )
+    .await;
+    dispatch_mod_action(data, guild_id, "raid_lockdown", None);
+
+    if let Some(channel) = alert_channel {
+        let mut actions = vec![];
+        if lockdown.previous_verification_level.is_some() {
+            actions.push("- Verification level raised to High");
+        }
+        if config.pause_default_roles {
+            actions.push("- Default role assignment paused");
+        }
+        let embed = CreateEmbed::new()
+            .title("Possible raid detected")
+            .description(format!(
+                "{} members joined in the last {} seconds ({} new accounts).\n\nLockdown active for {} minutes.\n{}",
+                joins,
+                config.window_seconds,
+                new_accounts,
+                config.lockdown_minutes,
+                actions.join("\n")
+            ))
+            .color(colors::red());
+        if let Err(e) = channel
+            .send_message(ctx, CreateMessage::new().embed(embed))
+            .await
+        {
+            warn!("Failed to send raid alert: {:?}", e);
+        }
+    }
+
+    spawn_lockdown_timer(
+        ctx.clone(),
+        data.db_pool.clone(),
+        data.raid_state.clone(),
+        guild_id,
+        duration,
+    );
+    Ok(())
+}
+
+async fn save_lockdown(
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+    lockdown: &Lockdown,
+    until: i64,
+) -> Result<(), Error> {
+    raid_lockdown::Entity::insert(raid_lockdown::ActiveModel {
+        guild_id: Set(id_to_string(guild_id)),
+        until: Set(until),
+        pause_default_roles: Set(lockdown.pause_default_roles),
+        previous_verification_level: Set(lockdown
+            .previous_verification_level
+            .map(|x| u8::from(x) as i32)),
+        alert_channel_id: Set(lockdown.alert_channel.map(id_to_string).unwrap_or_default()),
+    })
+    .on_conflict(
+        OnConflict::column(raid_lockdown::Column::GuildId)
+            .update_columns([
+                raid_lockdown::Column::Until,
+                raid_lockdown::Column::PauseDefaultRoles,
+                raid_lockdown::Column::PreviousVerificationLevel,
+                raid_lockdown::Column::AlertChannelId,
+            ])
+            .to_owned(),
+    )
+    .exec(db)
+    .await?;
+    Ok(())
+}
+
+/// Ends the lockdown of a guild once its duration has passed.
+fn spawn_lockdown_timer(
+    ctx: Context,
+    db: DatabaseConnection,
+    raid_state: RaidStateMap,
+    guild_id: GuildId,
+    duration: Duration,
+) {
+    tokio::spawn(async move {
+        tokio::time::sleep(duration).await;
+        // A manual end followed by a new lockdown replaces `until`, in which case this timer is stale.
+        let expired = raid_state
+            .read()
+            .ok()
+            .and_then(|states| {
+                states
+                    .get(&guild_id)
+                    .and_then(|s| s.lockdown.as_ref().map(|l| l.until <= Instant::now()))
+            })
+            .unwrap_or(false);
+        if expired && let Err(e) = end_lockdown(&ctx, &db, &raid_state, guild_id).await {
+            error!("Failed to end raid lockdown: {:?}", e);
+        }
+    });
+}
+
+/// Restores the lockdowns that were active when the bot stopped. Lockdowns that ended in the meantime are ended right
+/// away, restoring the verification level of their guild.
+pub async fn restore_lockdowns(
+    ctx: &Context,
+    db: &DatabaseConnection,
+    raid_state: &RaidStateMap,
+    clock: &dyn Clock,
+) -> Result<(), Error> {
+    let now = clock.unix_timestamp();
+    for model in raid_lockdown::Entity::find().all(db).await? {
+        let Ok(guild_id) = id_from_string::<GuildId>(&model.guild_id) else {
+            warn!("Raid lockdown has an invalid guild id '{}'", model.guild_id);
+            continue;
+        };
+        let duration = Duration::from_secs((model.until - now).max(0) as u64);
+        let lockdown = Lockdown {
+            until: Instant::now() + duration,
+            pause_default_roles: model.pause_default_roles,
+            previous_verification_level: model
+                .previous_verification_level
+                .map(|x| VerificationLevel::from(x as u8)),
+            alert_channel: id_from_string(&model.alert_channel_id).ok(),
+        };
+        raid_state
+            .write()
+            .map_err(|_| "Raid protection state lock is poisoned")?
+            .entry(guild_id)
+            .or_default()
+            .lockdown = Some(lockdown);
+        info!(
+            "Restored raid lockdown of guild {}, ending in {:?}",
+            guild_id, duration
+        );
+        spawn_lockdown_timer(
+            ctx.clone(),
+            db.clone(),
+            raid_state.clone(),
+            guild_id,
+            duration,
+        );
+    }
+    Ok(())
+}
+
+/// Ends an active lockdown, restoring the previous verification level.
+///
+/// Returns false if the guild was not locked down.
+pub async fn end_lockdown(
+    ctx: &Context,
+    db: &DatabaseConnection,
+    raid_state: &RaidStateMap,
+    guild_id: GuildId,
+) -> Result<bool, Error> {
+    let lockdown = {
+        let mut states = raid_state
+            .write()
+            .map_err(|_| "Raid protection state lock is poisoned")?;
+        match states.get_mut(&guild_id) {
+            Some(state) => {
+                state.recent_joins.clear();
+                state.lockdown.take()
+            }
+            None => None,
+        }
+    };
+
+    let lockdown = match lockdown {
+        Some(x) => x,
+        None => return Ok(false),
+    };
+    raid_lockdown::Entity::delete_by_id(id_to_string(guild_id))
+        .exec(db)
+        .await?;
+
+    if let Some(level) = lockdown.previous_verification_level {
+        guild_id
+            .edit(ctx, EditGuild::new().verification_level(level))
+            .await?;
+    }
+
+    info!("Raid lockdown ended on guild {}", guild_id);
+    if let Some(channel) = lockdown.alert_channel {
+        let embed = CreateEmbed::new()
+            .title("Raid lockdown ended")
+            .description("Verification level and default role assignment have been restored.")
+            .color(colors::green());
+        channel
+            .send_message(ctx, CreateMessage::new().embed(embed))
+            .await?;
+    }
+    Ok(true)
+}
@@ -0,0 +1,306 @@
+/*!
+
+Reactions to messages matching keywords, configured per guild with `/reactmap`.
+
+Each guild maps patterns to emojis: a message matching a pattern is reacted to with its emoji, in the channels where
+automatic replies are allowed (see `/autoresponse channels`). The mappings of each guild are cached once loaded, and
+refreshed when they are changed.
+
+The custom emojis of each guild are cached too, so reacting with a guild emoji by name (here, or with
+[`ResponseAction::ReactWithGuildEmoji`](super::responders::ResponseAction::ReactWithGuildEmoji)) doesn't fetch the
+whole emoji list for every message. The cache is filled with the emojis sent when a guild becomes available, replaced
+when the guild's emojis are updated and emptied when the bot leaves the guild; guilds missing from it are fetched once.
+
+*/
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use migration::OnConflict;
+use poise::serenity_prelude::{
+    Context, Emoji, EmojiId, Guild, GuildId, Message, ReactionType, UnavailableGuild,
+};
+use regex::{Regex, RegexBuilder};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+};
+use tracing::warn;
+
+use crate::{
+    Error,
+    entities::reaction_map,
+    events::message::auto_responses_enabled,
+    infrastructure::{botdata::Data, ids::id_to_string},
+};
+
+pub const MAX_REACTION_MAPPINGS: usize = 25;
+
+pub const MAX_PATTERN_LENGTH: usize = 100;
+
+/// Most reactions added to a single message, however many patterns it matches.
+const MAX_REACTIONS_PER_MESSAGE: usize = 5;
+
+/// Size limit of a compiled pattern, so admins can't configure a pattern that is expensive to match.
+const PATTERN_SIZE_LIMIT: usize = 256 * 1024;
+
+/// Longest unicode emoji accepted, in bytes. Emojis made of several code points (flags, skin tones, families...) take
+/// up to about 30 bytes.
+const MAX_UNICODE_EMOJI_LENGTH: usize = 32;
+
+/// A pattern of a guild and the emoji messages matching it are reacted to with.
+#[derive(Debug, Clone)]
+pub struct ReactionMapping {
+    pub pattern: String,
+    /// Unicode emoji, or custom emoji in `<:name:id>` form.
+    pub emoji: String,
+    regex: Regex,
+}
+
+/// Reaction mappings of each guild, once loaded.
+pub type ReactionMapCache = Arc<RwLock<HashMap<GuildId, Arc<Vec<ReactionMapping>>>>>;
+
+/// Custom emojis of each guild, once fetched.
+pub type GuildEmojiCache = Arc<RwLock<HashMap<GuildId, Arc<Vec<Emoji>>>>>;
+
+/// Compiles a pattern, matched case-insensitively anywhere in the message content.
+pub fn compile_pattern(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .size_limit(PATTERN_SIZE_LIMIT)
+        .build()
+}
+
+/// Gets the custom emojis of a guild, fetching them only if they aren't cached.
+pub async fn guild_emojis(
+    ctx: &Context,
+    cache: &GuildEmojiCache,
+    guild_id: GuildId,
+) -> Result<Arc<Vec<Emoji>>, Error> {
+    if let Some(emojis) = cache.read().ok().and_then(|x| x.get(&guild_id).cloned()) {
+        return Ok(emojis);
+    }
+    let emojis = Arc::new(guild_id.emojis(ctx).await?);
+    if let Ok(mut cache) = cache.write() {
+        cache.insert(guild_id, emojis.clone());
+    }
+    Ok(emojis)
+}
+
+/// Finds the guild emoji with a name, or else the first one whose name contains it, ignoring case.
+pub fn find_guild_emoji<'a>(emojis: &'a [Emoji], name: &str) -> Option<&'a Emoji> {
+    let name = name.to_lowercase();
+    emojis
+        .iter()
+        .find(|emoji| emoji.name.to_lowercase() == name)
+        .or_else(|| {
+            emojis
+                .iter()
+                .find(|emoji| emoji.name.to_lowercase().contains(&name))
+        })
+}
+
+fn cache_emojis(
+    cache: &GuildEmojiCache,
+    guild_id: GuildId,
+    emojis: &HashMap<EmojiId, Emoji>,
+) -> Result<(), Error> {
+    let mut emojis: Vec<Emoji> = emojis.values().cloned().collect();
+    emojis.sort_by_key(|x| x.id);
+    cache
+        .write()
+        .map_err(|_| "Guild emoji cache lock is poisoned")?
+        .insert(guild_id, Arc::new(emojis));
+    Ok(())
+}
+
+/// Caches the emojis of a guild when it becomes available.
+pub fn on_guild_available(data: &Data, guild: &Guild) -> Result<(), Error> {
+    cache_emojis(&data.guild_emojis, guild.id, &guild.emojis)
+}
+
+/// Replaces the cached emojis of a guild after they were updated.
+pub fn on_emojis_update(
+    data: &Data,
+    guild_id: GuildId,
+    current_state: &HashMap<EmojiId, Emoji>,
+) -> Result<(), Error> {
+    cache_emojis(&data.guild_emojis, guild_id, current_state)
+}
+
+/// Forgets the emojis of a guild the bot left. Guilds that are only unavailable keep their emojis.
+pub fn on_guild_delete(data: &Data, guild: &UnavailableGuild) -> Result<(), Error> {
+    if guild.unavailable {
+        return Ok(());
+    }
+    data.guild_emojis
+        .write()
+        .map_err(|_| "Guild emoji cache lock is poisoned")?
+        .remove(&guild.id);
+    Ok(())
+}
+
+/// Resolves the emoji given to `/reactmap add` to the form it is stored in: a custom emoji of the guild, given as
+/// `<:name:id>`, `:name:` or by name, or a unicode emoji. Returns None if it is neither.
+pub async fn resolve_emoji(
+    ctx: &Context,
+    cache: &GuildEmojiCache,
+    guild_id: GuildId,
+    emoji: &str,
+) -> Result<Option<String>, Error> {
+    let emoji = emoji.trim();
+    if let Ok(ReactionType::Custom { id, .. }) = ReactionType::try_from(emoji) {
+        let emojis = guild_emojis(ctx, cache, guild_id).await?;
+        return Ok(emojis.iter().find(|x| x.id == id).map(|x| x.to_string()));
+    }
+    if emoji.is_empty() || emoji.chars().any(char::is_whitespace) {
+        return Ok(None);
+    }
+    if emoji.chars().any(|x| x.is_ascii_alphanumeric() || x == '_') {
+        let emojis = guild_emojis(ctx, cache, guild_id).await?;
+        return Ok(find_guild_emoji(&emojis, emoji.trim_matches(':')).map(|x| x.to_string()));
+    }
+    Ok((emoji.len() <= MAX_UNICODE_EMOJI_LENGTH).then(|| emoji.to_string()))
+}
+
+/// Loads the reaction mappings of a guild from the database, bypassing the cache. Invalid patterns are skipped.
+pub async fn load_reaction_mappings(
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+) -> Result<Vec<ReactionMapping>, Error> {
+    Ok(reaction_map::Entity::find()
+        .filter(reaction_map::Column::GuildId.eq(id_to_string(guild_id)))
+        .order_by_asc(reaction_map::Column::Pattern)
+        .all(db)
+        .await?
+        .into_iter()
+        .filter_map(|x| match compile_pattern(&x.pattern) {
+            Ok(regex) => Some(ReactionMapping {
+                pattern: x.pattern,
+                emoji: x.emoji,
+                regex,
+            }),
+            Err(e) => {
+                warn!(
+                    "Skipping invalid reaction pattern '{}' of guild {}: {:?}",
+                    x.pattern, guild_id, e
+                );
+                None
+            }
+        })
+        .collect())
+}
+
+/// Gets the reaction mappings of a guild, caching them. Falls back to no mappings if they can't be loaded.
+pub async fn guild_reaction_mappings(
+    db: &DatabaseConnection,
+    cache: &ReactionMapCache,
+    guild_id: GuildId,
+) -> Arc<Vec<ReactionMapping>> {
+    if let Some(mappings) = cache.read().ok().and_then(|x| x.get(&guild_id).cloned()) {
+        return mappings;
+    }
+    match load_reaction_mappings(db, guild_id).await {
+        Ok(mappings) => {
+            let mappings = Arc::new(mappings);
+            if let Ok(mut cache) = cache.write() {
+                cache.insert(guild_id, mappings.clone());
+            }
+            mappings
+        }
+        Err(e) => {
+            warn!(
+                "Failed to load reaction mappings of guild {}: {:?}",
+                guild_id, e
+            );
+            Default::default()
+        }
+    }
+}
+
+fn invalidate(cache: &ReactionMapCache, guild_id: GuildId) -> Result<(), Error> {
+    cache
+        .write()
+        .map_err(|_| "Reaction map cache lock is poisoned")?
+        .remove(&guild_id);
+    Ok(())
+}
+
+/// Maps a pattern to an emoji, replacing the emoji of the pattern if it is already mapped.
+pub async fn set_reaction_mapping(
+    db: &DatabaseConnection,
+    cache: &ReactionMapCache,
+    guild_id: GuildId,
+    pattern: &str,
+    emoji: &str,
+) -> Result<(), Error> {
+    reaction_map::Entity::insert(reaction_map::ActiveModel {
+        guild_id: Set(id_to_string(guild_id)),
+        pattern: Set(pattern.to_string()),
+        emoji: Set(emoji.to_string()),
+    })
+    .on_conflict(
+        OnConflict::columns([reaction_map::Column::GuildId, reaction_map::Column::Pattern])
+            .update_column(reaction_map::Column::Emoji)
+            .to_owned(),
+    )
+    .exec(db)
+    .await?;
+    invalidate(cache, guild_id)
+}
+
+/// Removes the mapping of a pattern. Returns false if the pattern wasn't mapped.
+pub async fn remove_reaction_mapping(
+    db: &DatabaseConnection,
+    cache: &ReactionMapCache,
+    guild_id: GuildId,
+    pattern: &str,
+) -> Result<bool, Error> {
+    let result = reaction_map::Entity::delete_many()
+        .filter(reaction_map::Column::GuildId.eq(id_to_string(guild_id)))
+        .filter(reaction_map::Column::Pattern.eq(pattern))
+        .exec(db)
+        .await?;
+    invalidate(cache, guild_id)?;
+    Ok(result.rows_affected > 0)
+}
+
+/// Reacts to a message with the emojis of the patterns it matches.
+pub async fn on_message(ctx: &Context, data: &Data, message: &Message) -> Result<(), Error> {
+    let Some(guild_id) = message.guild_id else {
+        return Ok(());
+    };
+    let mappings = guild_reaction_mappings(&data.db_pool, &data.reaction_maps, guild_id).await;
+    let mut emojis: Vec<&str> = vec![];
+    for mapping in mappings.iter() {
+        if emojis.len() >= MAX_REACTIONS_PER_MESSAGE {
+            break;
+        }
+        if !emojis.contains(&mapping.emoji.as_str()) && mapping.regex.is_match(&message.content) {
+            emojis.push(&mapping.emoji);
+        }
+    }
+    if emojis.is_empty()
+        || !auto_responses_enabled(ctx, &data.db_pool, guild_id, message.channel_id).await?
+    {
+        return Ok(());
+    }
+
+    for emoji in emojis {
+        let Ok(reaction) = ReactionType::try_from(emoji) else {
+            warn!(
+                "Invalid emoji '{}' in reaction map of guild {}",
+                emoji, guild_id
+            );
+            continue;
+        };
+        if let Err(e) = message.react(ctx, reaction).await {
+            warn!(
+                "Failed to react with '{}' on guild {}: {:?}",
+                emoji, guild_id, e
+            );
+        }
+    }
+    Ok(())
+}
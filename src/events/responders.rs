@@ -0,0 +1,464 @@
+/*!
+
+Automatic replies to messages.
+
+Every responder is registered in [`responders`] and evaluated in order by [`on_message`](super::message::on_message).
+The first responder that matches a message, is enabled on the guild, is not on cooldown in the channel and wins its
+probability roll performs its response actions; the remaining responders are skipped.
+
+Responders are enabled by default and can be toggled per guild with `/autoresponse responders`.
+
+*/
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+use poise::{
+    CreateReply,
+    serenity_prelude::{ChannelId, Context, GuildId, Message, ReactionType, UserId},
+};
+use regex::Regex;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use tracing::warn;
+
+use crate::{
+    Error,
+    entities::auto_response_toggle,
+    events::reaction_map::{GuildEmojiCache, find_guild_emoji, guild_emojis},
+    infrastructure::{
+        clock::Clock,
+        ids::{self, id_to_string},
+        random::RandomSource,
+        util::send_message_from_reply,
+    },
+};
+
+/// Matches the bot being called by its name.
+const IMPOSTERBOT_PATTERN: &str = r"\bimposterbot\b";
+
+/// Matches messages that call the bot by its name, like the `imposterbot` responder.
+pub static IMPOSTERBOT_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(IMPOSTERBOT_PATTERN).expect("Imposterbot pattern is a valid regex"));
+
+/// Time each responder last fired in a channel, used to enforce cooldowns.
+pub type ResponderCooldowns = Arc<RwLock<HashMap<(ChannelId, String), Instant>>>;
+
+/// Something a responder does in response to a message.
+#[derive(Debug, Clone)]
+pub enum ResponseAction {
+    /// Sends one of the messages in the channel, picked at random.
+    Reply(Vec<String>),
+    /// Reacts to the message with a unicode emoji.
+    React(String),
+    /// Reacts to the message with the guild emoji with the given name, or else the first one whose name contains it.
+    ReactWithGuildEmoji(String),
+}
+
+pub trait Responder: Send + Sync {
+    /// Unique name, used to toggle the responder per guild.
+    fn name(&self) -> &str;
+
+    /// Short explanation of what triggers the responder, shown by `/autoresponse responders list`.
+    fn description(&self) -> &str;
+
+    /// Whether the message triggers the responder.
+    fn matches(&self, message: &Message) -> bool;
+
+    /// Chance between 0 and 1 that the responder fires when it matches.
+    fn probability(&self) -> f64 {
+        1.0
+    }
+
+    /// Minimum time between two responses of the responder in the same channel.
+    fn cooldown(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    /// Actions performed when the responder fires, in order.
+    fn actions(&self) -> &[ResponseAction];
+}
+
+/// A responder triggered by a regex match anywhere in the message content.
+pub struct PatternResponder {
+    pub name: String,
+    pub description: String,
+    pub pattern: Regex,
+    /// Only messages sent by these users trigger the responder. Empty allows everyone.
+    pub authors: Vec<UserId>,
+    pub probability: f64,
+    pub cooldown: Duration,
+    pub actions: Vec<ResponseAction>,
+}
+
+impl PatternResponder {
+    fn new(name: &str, description: &str, pattern: &str, actions: Vec<ResponseAction>) -> Self {
+        PatternResponder {
+            name: name.to_string(),
+            description: description.to_string(),
+            pattern: Regex::new(pattern).expect("Responder pattern is a valid regex"),
+            authors: vec![],
+            probability: 1.0,
+            cooldown: Duration::ZERO,
+            actions,
+        }
+    }
+}
+
+impl Responder for PatternResponder {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn matches(&self, message: &Message) -> bool {
+        (self.authors.is_empty() || self.authors.contains(&message.author.id))
+            && self.pattern.is_match(&message.content)
+    }
+
+    fn probability(&self) -> f64 {
+        self.probability
+    }
+
+    fn cooldown(&self) -> Duration {
+        self.cooldown
+    }
+
+    fn actions(&self) -> &[ResponseAction] {
+        &self.actions
+    }
+}
+
+fn reply(messages: &[&str]) -> ResponseAction {
+    ResponseAction::Reply(messages.iter().map(|x| x.to_string()).collect())
+}
+
+static BUILTIN_RESPONDERS: Lazy<Vec<Box<dyn Responder>>> = Lazy::new(|| {
+    let mut doggoban = PatternResponder::new(
+        "doggoban",
+        "The doggoban emoji, sent by its owners",
+        r"^<:doggoban:802308677737381948>$",
+        vec![reply(&["Banning **MoustachioMario#2067**"])],
+    );
+    doggoban.authors = vec![ids::KHAZAARI_ID, ids::CRESSY_ID];
+
+    let responders = vec![
+        PatternResponder::new("body", "\"body\"", r"\bbody+\b", vec![reply(&["where"])]),
+        PatternResponder::new(
+            "red-sus",
+            "\"red sus\"",
+            r"\bred sus\b",
+            vec![reply(&["I agree, vote red."])],
+        ),
+        PatternResponder::new(
+            "blue-sus",
+            "\"blue sus\"",
+            r"\bblue sus\b",
+            vec![reply(&["I think blue is safe, I saw them do a med scan."])],
+        ),
+        PatternResponder::new(
+            "nav",
+            "\"nav\"",
+            r"\bnav\b",
+            vec![reply(&["I was just in nav, didn't see anyone."])],
+        ),
+        PatternResponder::new(
+            "blitzcrank",
+            "\"blitzcrank\"",
+            r"\bblitzcrank\b",
+            vec![ResponseAction::React("👍".to_string())],
+        ),
+        PatternResponder::new(
+            "meeting",
+            "\"meeting\"",
+            r"\bmeeting\b",
+            vec![
+                ResponseAction::ReactWithGuildEmoji("deny".to_string()),
+                reply(&["**Loud meeting button noise**"]),
+            ],
+        ),
+        PatternResponder::new(
+            "imposterbot",
+            "\"imposterbot\"",
+            IMPOSTERBOT_PATTERN,
+            vec![reply(&[
+                "Not me, vote cyan.",
+                "I was in admin.",
+                "Didn't see orange at O2..",
+                "It wasn't me, vote lime.",
+            ])],
+        ),
+        PatternResponder::new(
+            "sad",
+            "\"I'm sad\" or \"I am sad\"",
+            r"\bi(('*m)|( am)) sad\b",
+            vec![reply(&["Don't be sad 😢", "Cheer up!"])],
+        ),
+        PatternResponder::new(
+            "owo",
+            "\"owo\", in any case",
+            r"(?i)\bowo\b",
+            vec![reply(&["OwO?"])],
+        ),
+        PatternResponder::new(
+            "vented",
+            "\"vented\"",
+            r"\bvented\b",
+            vec![
+                reply(&[
+                    "Was it green? I thought I saw them vent.",
+                    "I was in storage.. no where near any vents.",
+                ]),
+                ResponseAction::ReactWithGuildEmoji("deny".to_string()),
+            ],
+        ),
+        PatternResponder::new(
+            "suspicious",
+            "\"suspicious\"",
+            r"\bsuspicious\b",
+            vec![reply(&["Very sus."]), reply(&["👀"])],
+        ),
+        PatternResponder::new(
+            "who-you-gonna-call",
+            "\"who you gonna call\"",
+            r"\bwho you gonna call\b",
+            vec![reply(&["ghost busters!"])],
+        ),
+        PatternResponder::new(
+            "pain",
+            "\"pain\"",
+            r"\bpain\b",
+            vec![ResponseAction::ReactWithGuildEmoji("pain".to_string())],
+        ),
+        doggoban,
+    ];
+    responders
+        .into_iter()
+        .map(|x| Box::new(x) as Box<dyn Responder>)
+        .collect()
+});
+
+/// All registered responders, in evaluation order.
+pub fn responders() -> &'static [Box<dyn Responder>] {
+    &BUILTIN_RESPONDERS
+}
+
+/// Names of the responders disabled on a guild.
+pub async fn disabled_responders(
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+) -> Result<HashSet<String>, Error> {
+    Ok(auto_response_toggle::Entity::find()
+        .filter(auto_response_toggle::Column::GuildId.eq(id_to_string(guild_id)))
+        .filter(auto_response_toggle::Column::Enabled.eq(false))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|x| x.responder)
+        .collect())
+}
+
+/// Checks the cooldown of a responder in a channel, and starts a new cooldown if it isn't on cooldown.
+///
+/// Returns false if the responder is on cooldown.
+pub fn try_start_cooldown(
+    cooldowns: &ResponderCooldowns,
+    clock: &dyn Clock,
+    responder: &dyn Responder,
+    channel_id: ChannelId,
+) -> bool {
+    let cooldown = responder.cooldown();
+    if cooldown.is_zero() {
+        return true;
+    }
+    let mut cooldowns = match cooldowns.write() {
+        Ok(x) => x,
+        Err(_) => {
+            warn!("Responder cooldowns lock is poisoned");
+            return true;
+        }
+    };
+    let key = (channel_id, responder.name().to_string());
+    let now = clock.instant();
+    if cooldowns
+        .get(&key)
+        .is_some_and(|last| now.saturating_duration_since(*last) < cooldown)
+    {
+        return false;
+    }
+    cooldowns.insert(key, now);
+    true
+}
+
+/// Rolls the probability of a responder firing.
+pub fn roll_probability(random: &dyn RandomSource, responder: &dyn Responder) -> bool {
+    let probability = responder.probability();
+    probability >= 1.0 || random.random_bool(probability)
+}
+
+async fn react_with_guild_emoji(
+    ctx: &Context,
+    emojis: &GuildEmojiCache,
+    message: &Message,
+    emoji_name: &str,
+) -> Result<(), Error> {
+    let Some(guild_id) = message.guild_id else {
+        return Ok(());
+    };
+    let guild_emojis = guild_emojis(ctx, emojis, guild_id).await?;
+    match find_guild_emoji(&guild_emojis, emoji_name) {
+        Some(emoji) => {
+            let reaction = ReactionType::Custom {
+                animated: emoji.animated,
+                id: emoji.id,
+                name: Some(emoji.name.clone()),
+            };
+            message.react(ctx, reaction).await?;
+        }
+        None => warn!("Emoji '{}' was not found on guild {}", emoji_name, guild_id),
+    }
+    Ok(())
+}
+
+/// Performs the response actions of a responder.
+pub async fn respond(
+    ctx: &Context,
+    random: &dyn RandomSource,
+    emojis: &GuildEmojiCache,
+    message: &Message,
+    responder: &dyn Responder,
+) -> Result<(), Error> {
+    for action in responder.actions() {
+        match action {
+            ResponseAction::Reply(messages) => {
+                if let Some(content) = random.random_index(messages.len()).map(|i| &messages[i]) {
+                    let reply = CreateReply::default().content(content);
+                    send_message_from_reply(&message.channel_id, ctx, reply).await?;
+                }
+            }
+            ResponseAction::React(emoji) => {
+                message
+                    .react(ctx, ReactionType::Unicode(emoji.clone()))
+                    .await?;
+            }
+            ResponseAction::ReactWithGuildEmoji(name) => {
+                react_with_guild_emoji(ctx, emojis, message, name).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::infrastructure::{clock::ManualClock, random::SeededRandom};
+
+    use super::*;
+
+    fn message(content: &str, author: UserId) -> Message {
+        let mut message = Message::default();
+        message.content = content.to_string();
+        message.author.id = author;
+        message
+    }
+
+    fn first_match(content: &str, author: UserId) -> Option<&'static str> {
+        let message = message(content, author);
+        responders()
+            .iter()
+            .find(|x| x.matches(&message))
+            .map(|x| x.name())
+    }
+
+    #[test]
+    fn every_responder_matches_its_trigger() {
+        let anyone = UserId::new(1);
+        for (name, content) in [
+            ("body", "found a bodyyy in electrical"),
+            ("red-sus", "red sus tbh"),
+            ("blue-sus", "isn't blue sus?"),
+            ("nav", "who was in nav"),
+            ("blitzcrank", "blitzcrank hook"),
+            ("meeting", "call a meeting"),
+            ("imposterbot", "imposterbot where were you"),
+            ("sad", "i'm sad"),
+            ("owo", "OwO what's this"),
+            ("vented", "pink vented"),
+            ("suspicious", "that's suspicious"),
+            ("who-you-gonna-call", "who you gonna call"),
+            ("pain", "pain"),
+        ] {
+            assert_eq!(first_match(content, anyone), Some(name), "{}", content);
+        }
+        assert_eq!(first_match("i am sad", anyone), Some("sad"));
+        assert_eq!(first_match("nobody navigates here", anyone), None);
+        assert_eq!(first_match("Imposterbot", anyone), None);
+    }
+
+    #[test]
+    fn doggoban_only_matches_its_owners() {
+        let content = "<:doggoban:802308677737381948>";
+        assert_eq!(first_match(content, ids::KHAZAARI_ID), Some("doggoban"));
+        assert_eq!(first_match(content, ids::CRESSY_ID), Some("doggoban"));
+        assert_eq!(first_match(content, UserId::new(1)), None);
+        assert_eq!(
+            first_match(&format!("hi {}", content), ids::CRESSY_ID),
+            None
+        );
+    }
+
+    #[test]
+    fn builtin_responders_always_fire() {
+        let random = SeededRandom::new(0);
+        let clock = ManualClock::new(0);
+        let cooldowns = ResponderCooldowns::default();
+        for responder in responders() {
+            for _ in 0..10 {
+                assert!(roll_probability(&random, responder.as_ref()));
+                assert!(try_start_cooldown(
+                    &cooldowns,
+                    &clock,
+                    responder.as_ref(),
+                    ChannelId::new(1)
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn probability_is_rolled() {
+        let random = SeededRandom::new(42);
+        let mut responder = PatternResponder::new("test", "test", "test", vec![]);
+        responder.probability = 0.0;
+        assert!((0..100).all(|_| !roll_probability(&random, &responder)));
+        responder.probability = 0.5;
+        let fired = (0..1000)
+            .filter(|_| roll_probability(&random, &responder))
+            .count();
+        assert!((400..600).contains(&fired), "{}", fired);
+    }
+
+    #[test]
+    fn cooldown_is_per_channel() {
+        let clock = ManualClock::new(0);
+        let cooldowns = ResponderCooldowns::default();
+        let mut responder = PatternResponder::new("test", "test", "test", vec![]);
+        responder.cooldown = Duration::from_secs(30);
+        let (first, second) = (ChannelId::new(1), ChannelId::new(2));
+
+        assert!(try_start_cooldown(&cooldowns, &clock, &responder, first));
+        assert!(!try_start_cooldown(&cooldowns, &clock, &responder, first));
+        assert!(try_start_cooldown(&cooldowns, &clock, &responder, second));
+
+        clock.advance(Duration::from_secs(29));
+        assert!(!try_start_cooldown(&cooldowns, &clock, &responder, first));
+        clock.advance(Duration::from_secs(1));
+        assert!(try_start_cooldown(&cooldowns, &clock, &responder, first));
+    }
+}
@@ -0,0 +1,335 @@
+/*
+    Screens new members before they get the default roles of a guild.
+
+    When screening is enabled with `/screening enable`, new members are sent a DM with a button that opens a modal
+    with the questions of the guild. Their answers are posted to the staff review channel with Approve and Kick
+    buttons, and approving the member gives them the default roles they would otherwise have received on join.
+    Members that can't receive DMs are posted to the review channel without answers.
+*/
+
+use std::time::Duration;
+
+use poise::serenity_prelude::{
+    self as serenity, ActionRowComponent, ButtonStyle, Context, CreateActionRow, CreateButton,
+    CreateEmbed, CreateInputText, CreateInteractionResponse, CreateInteractionResponseMessage,
+    CreateMessage, CreateModal, GuildId, InputTextStyle, Member, Mentionable,
+    ModalInteractionCollector, UserId,
+};
+use tracing::{info, warn};
+
+use crate::{
+    Error,
+    events::guild_member::add_initial_member_roles,
+    infrastructure::{botdata::Data, branding::get_branding, colors, mod_log::send_mod_log},
+    services::screening::ScreeningRepo,
+};
+
+/// Custom id prefix of the screening buttons, followed by `start:<guild id>`, `approve:<user id>` or
+/// `kick:<user id>`.
+pub const SCREENING_ID_PREFIX: &str = "screening:";
+
+/// Time a member has to submit their answers after opening the modal.
+const ANSWERS_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// Maximum length of an answer, so every answer fits in an embed field.
+const MAX_ANSWER_LENGTH: u16 = 1000;
+
+/// Audit log reason of kicks from the review channel.
+const KICK_REASON: &str = "Rejected by member screening";
+
+fn guild_name(ctx: &Context, guild_id: GuildId) -> String {
+    guild_id
+        .name(&ctx.cache)
+        .unwrap_or_else(|| guild_id.to_string())
+}
+
+fn review_components(user_id: UserId) -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("{}approve:{}", SCREENING_ID_PREFIX, user_id))
+            .label("Approve")
+            .style(ButtonStyle::Success),
+        CreateButton::new(format!("{}kick:{}", SCREENING_ID_PREFIX, user_id))
+            .label("Kick")
+            .style(ButtonStyle::Danger),
+    ])]
+}
+
+/// Starts screening a new member, returning whether the guild screens members, in which case the default roles are
+/// given on approval instead of on join.
+pub async fn on_member_join(ctx: &Context, data: &Data, member: &Member) -> Result<bool, Error> {
+    if member.user.bot {
+        return Ok(false);
+    }
+    let guild_id = member.guild_id;
+    let Some(screening) = ScreeningRepo::new(&data.db_pool)
+        .screening(guild_id)
+        .await?
+    else {
+        return Ok(false);
+    };
+
+    let branding = get_branding(&data.db_pool, &data.branding, guild_id).await;
+    let embed = branding
+        .embed()
+        .title(format!("Welcome to {}!", guild_name(ctx, guild_id)))
+        .description(
+            "Before you get access, please answer a few questions for the staff of the server.",
+        );
+    let button = CreateButton::new(format!("{}start:{}", SCREENING_ID_PREFIX, guild_id))
+        .label("Answer questions")
+        .style(ButtonStyle::Primary);
+    let dm = member
+        .user
+        .direct_message(
+            ctx,
+            CreateMessage::new()
+                .embed(embed)
+                .components(vec![CreateActionRow::Buttons(vec![button])]),
+        )
+        .await;
+    if let Err(e) = dm {
+        info!("Failed to send screening questions to new member: {:?}", e);
+        let embed = branding.embed().title("Screening").description(format!(
+            "{} joined but can't receive DMs, so they could not answer the screening questions.",
+            member.mention()
+        ));
+        screening
+            .review_channel
+            .send_message(
+                ctx,
+                CreateMessage::new()
+                    .embed(embed)
+                    .components(review_components(member.user.id)),
+            )
+            .await?;
+    }
+    Ok(true)
+}
+
+/// Handles the screening buttons, whose custom id starts with [`SCREENING_ID_PREFIX`].
+pub async fn handle_interaction(
+    ctx: &Context,
+    data: &Data,
+    interaction: &serenity::ComponentInteraction,
+    action: &str,
+) -> Result<(), Error> {
+    match action.split_once(':') {
+        Some(("start", guild_id)) => start(ctx, data, interaction, guild_id.parse()?).await,
+        Some(("approve", user_id)) => review(ctx, data, interaction, user_id.parse()?, true).await,
+        Some(("kick", user_id)) => review(ctx, data, interaction, user_id.parse()?, false).await,
+        _ => Err(format!("Unknown screening action '{}'", action).into()),
+    }
+}
+
+/// Asks the questions of a guild in a modal, and posts the answers to its review channel.
+async fn start(
+    ctx: &Context,
+    data: &Data,
+    interaction: &serenity::ComponentInteraction,
+    guild_id: GuildId,
+) -> Result<(), Error> {
+    let Some(screening) = ScreeningRepo::new(&data.db_pool)
+        .screening(guild_id)
+        .await?
+    else {
+        return respond_ephemeral(
+            ctx,
+            interaction,
+            "This server no longer screens new members",
+        )
+        .await;
+    };
+
+    let modal_id = format!("{}answers:{}", SCREENING_ID_PREFIX, interaction.id);
+    let inputs = screening
+        .questions
+        .iter()
+        .enumerate()
+        .map(|(i, question)| {
+            CreateActionRow::InputText(
+                CreateInputText::new(InputTextStyle::Paragraph, question, i.to_string())
+                    .required(true)
+                    .max_length(MAX_ANSWER_LENGTH),
+            )
+        })
+        .collect();
+    interaction
+        .create_response(
+            ctx,
+            CreateInteractionResponse::Modal(
+                CreateModal::new(&modal_id, "Screening questions").components(inputs),
+            ),
+        )
+        .await?;
+
+    let Some(submission) = ModalInteractionCollector::new(ctx)
+        .author_id(interaction.user.id)
+        .custom_ids(vec![modal_id])
+        .timeout(ANSWERS_TIMEOUT)
+        .await
+    else {
+        return Ok(());
+    };
+    let answers: Vec<String> = submission
+        .data
+        .components
+        .iter()
+        .flat_map(|row| &row.components)
+        .filter_map(|component| match component {
+            ActionRowComponent::InputText(x) => Some(x.value.clone().unwrap_or_default()),
+            _ => None,
+        })
+        .collect();
+
+    let branding = get_branding(&data.db_pool, &data.branding, guild_id).await;
+    let mut embed = branding
+        .embed()
+        .title("Screening answers")
+        .description(format!(
+            "{} ({}) answered the screening questions.",
+            interaction.user.mention(),
+            interaction.user.name
+        ))
+        .thumbnail(interaction.user.face());
+    for (question, answer) in screening.questions.iter().zip(answers) {
+        embed = embed.field(question, answer, false);
+    }
+    screening
+        .review_channel
+        .send_message(
+            ctx,
+            CreateMessage::new()
+                .embed(embed)
+                .components(review_components(interaction.user.id)),
+        )
+        .await?;
+
+    submission
+        .create_response(
+            ctx,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(format!(
+                        "Thanks! Your answers were sent to the staff of {}.",
+                        guild_name(ctx, guild_id)
+                    ))
+                    .components(vec![]),
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Approves a screened member, giving them the default roles, or kicks them.
+async fn review(
+    ctx: &Context,
+    data: &Data,
+    interaction: &serenity::ComponentInteraction,
+    user_id: UserId,
+    approve: bool,
+) -> Result<(), Error> {
+    let guild_id = interaction
+        .guild_id
+        .ok_or("This function is only available in guilds")?;
+    let permissions = interaction.member.as_ref().and_then(|x| x.permissions);
+    let (allowed, missing) = if approve {
+        (
+            permissions.is_some_and(|x| x.manage_roles()),
+            "Manage Roles",
+        )
+    } else {
+        (
+            permissions.is_some_and(|x| x.kick_members()),
+            "Kick Members",
+        )
+    };
+    if !allowed {
+        return respond_ephemeral(
+            ctx,
+            interaction,
+            &format!("You need the {} permission to review new members", missing),
+        )
+        .await;
+    }
+
+    let member = match guild_id.member(ctx, user_id).await {
+        Ok(x) => x,
+        Err(_) => {
+            return respond_ephemeral(ctx, interaction, "This member already left the server")
+                .await;
+        }
+    };
+    let outcome = if approve {
+        add_initial_member_roles(ctx, data, &member).await?;
+        format!("Approved by {}", interaction.user.mention())
+    } else {
+        member.kick_with_reason(ctx, KICK_REASON).await?;
+        info!(
+            "Kicked member {} of guild {} after screening",
+            user_id, guild_id
+        );
+        let branding = get_branding(&data.db_pool, &data.branding, guild_id).await;
+        send_mod_log(
+            &ctx.http,
+            &data.db_pool,
+            guild_id,
+            branding
+                .embed()
+                .title("Member kicked by screening")
+                .description(format!(
+                    "{} was kicked by {}",
+                    member.mention(),
+                    interaction.user.mention()
+                ))
+                .color(colors::red()),
+        )
+        .await;
+        format!("Kicked by {}", interaction.user.mention())
+    };
+
+    let embed = interaction
+        .message
+        .embeds
+        .first()
+        .cloned()
+        .map(CreateEmbed::from)
+        .unwrap_or_default()
+        .field("Review", outcome, false)
+        .color(if approve {
+            colors::green()
+        } else {
+            colors::red()
+        });
+    if let Err(e) = interaction
+        .create_response(
+            ctx,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .embed(embed)
+                    .components(vec![]),
+            ),
+        )
+        .await
+    {
+        warn!("Failed to update screening review message: {:?}", e);
+    }
+    Ok(())
+}
+
+async fn respond_ephemeral(
+    ctx: &Context,
+    interaction: &serenity::ComponentInteraction,
+    content: &str,
+) -> Result<(), Error> {
+    interaction
+        .create_response(
+            ctx,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .ephemeral(true),
+            ),
+        )
+        .await?;
+    Ok(())
+}
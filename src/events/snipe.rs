@@ -0,0 +1,334 @@
+/*!
+
+Remembers the last few deleted and edited messages of each channel for `/snipe` and `/editsnipe`.
+
+Deleted messages can't be fetched from Discord, so the latest messages of each channel are tracked in memory, and
+moved to the channel's snipes when they are deleted or edited. Nothing is written to the database: messages are
+forgotten after the guild's retention limit, or when the bot restarts. Guilds can opt out with `/configure snipe`,
+in which case their messages aren't tracked at all. Messages of bots and bulk deletions are ignored.
+
+*/
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, RwLock},
+};
+
+use migration::OnConflict;
+use poise::serenity_prelude::{ChannelId, GuildId, Message, MessageId, MessageUpdateEvent, UserId};
+use sea_orm::{ActiveValue::Set, DatabaseConnection, EntityTrait};
+use tracing::warn;
+
+use crate::{
+    Error,
+    entities::snipe_settings,
+    infrastructure::{botdata::Data, ids::id_to_string},
+};
+
+/// Number of latest messages tracked per channel, so they can be sniped once deleted or edited.
+const TRACKED_MESSAGES_PER_CHANNEL: usize = 100;
+
+/// Number of deleted and edited messages kept per channel.
+pub const SNIPES_PER_CHANNEL: usize = 5;
+
+pub const DEFAULT_RETENTION_MINUTES: u32 = 60;
+
+pub const MAX_RETENTION_MINUTES: u32 = 60 * 24;
+
+const SECONDS_PER_MINUTE: i64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SnipeSettings {
+    pub enabled: bool,
+    pub retention_minutes: u32,
+}
+
+impl Default for SnipeSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            retention_minutes: DEFAULT_RETENTION_MINUTES,
+        }
+    }
+}
+
+impl SnipeSettings {
+    fn from_model(model: snipe_settings::Model) -> Self {
+        Self {
+            enabled: model.enabled,
+            retention_minutes: model
+                .retention_minutes
+                .clamp(1, MAX_RETENTION_MINUTES as i32) as u32,
+        }
+    }
+
+    /// Gets the oldest unix timestamp of messages that may still be remembered.
+    fn cutoff(&self, now: i64) -> i64 {
+        now - self.retention_minutes as i64 * SECONDS_PER_MINUTE
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TrackedMessage {
+    id: MessageId,
+    author: UserId,
+    content: String,
+    sent_at: i64,
+}
+
+/// A deleted or edited message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snipe {
+    pub author: UserId,
+    pub content: String,
+    /// Content after the edit, or None if the message was deleted.
+    pub edited_content: Option<String>,
+    pub sent_at: i64,
+    /// Unix timestamp of the deletion or edit.
+    pub sniped_at: i64,
+}
+
+#[derive(Debug)]
+struct ChannelMessages {
+    guild_id: GuildId,
+    tracked: VecDeque<TrackedMessage>,
+    deleted: VecDeque<Snipe>,
+    edited: VecDeque<Snipe>,
+}
+
+impl ChannelMessages {
+    fn new(guild_id: GuildId) -> Self {
+        Self {
+            guild_id,
+            tracked: VecDeque::new(),
+            deleted: VecDeque::new(),
+            edited: VecDeque::new(),
+        }
+    }
+
+    /// Forgets messages sent, deleted or edited before a unix timestamp.
+    fn expire(&mut self, cutoff: i64) {
+        self.tracked.retain(|x| x.sent_at >= cutoff);
+        self.deleted.retain(|x| x.sniped_at >= cutoff);
+        self.edited.retain(|x| x.sniped_at >= cutoff);
+    }
+
+    fn take_tracked(&mut self, id: MessageId) -> Option<TrackedMessage> {
+        let index = self.tracked.iter().position(|x| x.id == id)?;
+        self.tracked.remove(index)
+    }
+}
+
+fn push_capped<T>(queue: &mut VecDeque<T>, item: T, capacity: usize) {
+    if queue.len() >= capacity {
+        queue.pop_front();
+    }
+    queue.push_back(item);
+}
+
+#[derive(Debug, Default)]
+pub struct SnipeState {
+    settings: HashMap<GuildId, SnipeSettings>,
+    channels: HashMap<ChannelId, ChannelMessages>,
+}
+
+pub type SnipeCache = Arc<RwLock<SnipeState>>;
+
+/// Loads the snipe settings of a guild from the database, bypassing the cache.
+pub async fn load_snipe_settings(
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+) -> Result<SnipeSettings, Error> {
+    Ok(snipe_settings::Entity::find_by_id(id_to_string(guild_id))
+        .one(db)
+        .await?
+        .map(SnipeSettings::from_model)
+        .unwrap_or_default())
+}
+
+/// Gets the snipe settings of a guild, caching them. Falls back to disabled sniping if they can't be loaded.
+pub async fn get_snipe_settings(
+    db: &DatabaseConnection,
+    cache: &SnipeCache,
+    guild_id: GuildId,
+) -> SnipeSettings {
+    if let Some(settings) = cache
+        .read()
+        .ok()
+        .and_then(|x| x.settings.get(&guild_id).copied())
+    {
+        return settings;
+    }
+    match load_snipe_settings(db, guild_id).await {
+        Ok(settings) => {
+            if let Ok(mut cache) = cache.write() {
+                cache.settings.insert(guild_id, settings);
+            }
+            settings
+        }
+        Err(e) => {
+            warn!(
+                "Failed to load snipe settings of guild {}: {:?}",
+                guild_id, e
+            );
+            SnipeSettings {
+                enabled: false,
+                ..Default::default()
+            }
+        }
+    }
+}
+
+/// Stores the snipe settings of a guild. Disabling sniping forgets the messages already remembered for the guild.
+pub async fn set_snipe_settings(
+    db: &DatabaseConnection,
+    cache: &SnipeCache,
+    guild_id: GuildId,
+    settings: SnipeSettings,
+) -> Result<(), Error> {
+    snipe_settings::Entity::insert(snipe_settings::ActiveModel {
+        guild_id: Set(id_to_string(guild_id)),
+        enabled: Set(settings.enabled),
+        retention_minutes: Set(settings.retention_minutes as i32),
+    })
+    .on_conflict(
+        OnConflict::column(snipe_settings::Column::GuildId)
+            .update_columns([
+                snipe_settings::Column::Enabled,
+                snipe_settings::Column::RetentionMinutes,
+            ])
+            .to_owned(),
+    )
+    .exec(db)
+    .await?;
+    if let Ok(mut cache) = cache.write() {
+        cache.settings.insert(guild_id, settings);
+        if !settings.enabled {
+            cache.channels.retain(|_, x| x.guild_id != guild_id);
+        }
+    }
+    Ok(())
+}
+
+pub async fn on_message(data: &Data, message: &Message) -> Result<(), Error> {
+    let Some(guild_id) = message.guild_id else {
+        return Ok(());
+    };
+    if message.author.bot || message.content.is_empty() {
+        return Ok(());
+    }
+    let settings = get_snipe_settings(&data.db_pool, &data.snipes, guild_id).await;
+    if !settings.enabled {
+        return Ok(());
+    }
+
+    let now = data.clock.unix_timestamp();
+    let mut cache = data
+        .snipes
+        .write()
+        .map_err(|_| "Snipe cache lock is poisoned")?;
+    let channel = cache
+        .channels
+        .entry(message.channel_id)
+        .or_insert_with(|| ChannelMessages::new(guild_id));
+    channel.expire(settings.cutoff(now));
+    push_capped(
+        &mut channel.tracked,
+        TrackedMessage {
+            id: message.id,
+            author: message.author.id,
+            content: message.content.clone(),
+            sent_at: now,
+        },
+        TRACKED_MESSAGES_PER_CHANNEL,
+    );
+    Ok(())
+}
+
+pub fn on_message_delete(
+    data: &Data,
+    channel_id: ChannelId,
+    message_id: MessageId,
+) -> Result<(), Error> {
+    let now = data.clock.unix_timestamp();
+    let mut cache = data
+        .snipes
+        .write()
+        .map_err(|_| "Snipe cache lock is poisoned")?;
+    let Some(channel) = cache.channels.get_mut(&channel_id) else {
+        return Ok(());
+    };
+    let Some(message) = channel.take_tracked(message_id) else {
+        return Ok(());
+    };
+    push_capped(
+        &mut channel.deleted,
+        Snipe {
+            author: message.author,
+            content: message.content,
+            edited_content: None,
+            sent_at: message.sent_at,
+            sniped_at: now,
+        },
+        SNIPES_PER_CHANNEL,
+    );
+    Ok(())
+}
+
+pub fn on_message_update(data: &Data, event: &MessageUpdateEvent) -> Result<(), Error> {
+    let Some(content) = &event.content else {
+        return Ok(());
+    };
+    let now = data.clock.unix_timestamp();
+    let mut cache = data
+        .snipes
+        .write()
+        .map_err(|_| "Snipe cache lock is poisoned")?;
+    let Some(channel) = cache.channels.get_mut(&event.channel_id) else {
+        return Ok(());
+    };
+    let Some(message) = channel.tracked.iter_mut().find(|x| x.id == event.id) else {
+        return Ok(());
+    };
+    if message.content == *content {
+        // Embeds were added to the message, but its content didn't change
+        return Ok(());
+    }
+    let snipe = Snipe {
+        author: message.author,
+        content: std::mem::replace(&mut message.content, content.clone()),
+        edited_content: Some(content.clone()),
+        sent_at: message.sent_at,
+        sniped_at: now,
+    };
+    push_capped(&mut channel.edited, snipe, SNIPES_PER_CHANNEL);
+    Ok(())
+}
+
+/// Gets a remembered message of a channel, where 0 is the most recently deleted or edited one.
+pub async fn get_snipe(
+    data: &Data,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    edited: bool,
+    index: usize,
+) -> Option<Snipe> {
+    let settings = get_snipe_settings(&data.db_pool, &data.snipes, guild_id).await;
+    if !settings.enabled {
+        return None;
+    }
+    let cutoff = settings.cutoff(data.clock.unix_timestamp());
+    let cache = data.snipes.read().ok()?;
+    let channel = cache.channels.get(&channel_id)?;
+    let snipes = if edited {
+        &channel.edited
+    } else {
+        &channel.deleted
+    };
+    snipes
+        .iter()
+        .rev()
+        .filter(|x| x.sniped_at >= cutoff)
+        .nth(index)
+        .cloned()
+}
@@ -0,0 +1,115 @@
+/*!
+
+Translates a message when a member reacts to it with the flag of a country, such as 🇫🇷 for French.
+
+The translation is posted as a reply to the message. Each message is translated to each language once, so members
+adding the same flag don't repeat it; only the most recent translations are remembered, in memory.
+
+*/
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, RwLock},
+};
+
+use poise::serenity_prelude::{
+    Context, CreateAllowedMentions, CreateEmbedAuthor, CreateMessage, MessageId, Reaction,
+    ReactionType,
+};
+use tracing::{info, warn};
+
+use crate::{
+    Error,
+    commands::translate::{MAX_TRANSLATION_LENGTH, translation_embed},
+    infrastructure::{
+        botdata::Data,
+        branding::get_branding,
+        translation::{Language, translation_provider},
+    },
+};
+
+/// Number of translated messages remembered.
+const RECENT_TRANSLATION_CAPACITY: usize = 200;
+
+pub type RecentTranslations = Arc<RwLock<VecDeque<(MessageId, Language)>>>;
+
+/// Records the translation of a message to a language, returning false if it was already translated.
+fn try_record_translation(
+    recent: &RecentTranslations,
+    message_id: MessageId,
+    language: Language,
+) -> bool {
+    let mut recent = match recent.write() {
+        Ok(x) => x,
+        Err(_) => {
+            warn!("Recent translations lock is poisoned");
+            return false;
+        }
+    };
+    if recent.contains(&(message_id, language)) {
+        return false;
+    }
+    if recent.len() >= RECENT_TRANSLATION_CAPACITY {
+        recent.pop_front();
+    }
+    recent.push_back((message_id, language));
+    true
+}
+
+pub async fn on_reaction_add(ctx: &Context, data: &Data, reaction: &Reaction) -> Result<(), Error> {
+    let Some(guild_id) = reaction.guild_id else {
+        return Ok(());
+    };
+    let ReactionType::Unicode(emoji) = &reaction.emoji else {
+        return Ok(());
+    };
+    let Some(target) = Language::from_flag(emoji) else {
+        return Ok(());
+    };
+    if reaction.user_id == Some(ctx.cache.current_user().id)
+        || reaction.member.as_ref().is_some_and(|m| m.user.bot)
+    {
+        return Ok(());
+    }
+    let Some(provider) = translation_provider() else {
+        return Ok(());
+    };
+
+    let message = reaction.message(&ctx.http).await?;
+    if message.content.is_empty()
+        || message.content.chars().count() > MAX_TRANSLATION_LENGTH
+        || !try_record_translation(&data.translations, message.id, target)
+    {
+        return Ok(());
+    }
+
+    let translation = match provider.translate(&message.content, target).await {
+        Ok(x) => x,
+        Err(e) => {
+            // Let the next reaction try again
+            if let Ok(mut recent) = data.translations.write() {
+                recent.retain(|x| *x != (message.id, target));
+            }
+            return Err(e);
+        }
+    };
+    let branding = get_branding(&data.db_pool, &data.branding, guild_id).await;
+    let embed = translation_embed(&branding, &translation, target, provider.name()).author(
+        CreateEmbedAuthor::new(message.author.display_name()).icon_url(message.author.face()),
+    );
+    message
+        .channel_id
+        .send_message(
+            &ctx.http,
+            CreateMessage::new()
+                .embed(embed)
+                .reference_message(&message)
+                .allowed_mentions(CreateAllowedMentions::new()),
+        )
+        .await?;
+    info!(
+        "Translated message {} to {:?} on guild {}",
+        message.id, target, guild_id
+    );
+    Ok(())
+}
@@ -0,0 +1,326 @@
+/*!
+
+Posts compact previews of links to the domains a guild whitelisted with `/unfurl`.
+
+Guilds opt in by adding domains; subdomains of a whitelisted domain match too. Previews are built from the OpenGraph
+metadata of the linked page and kept in a small in-memory LRU cache shared by every guild, so a link posted again
+doesn't fetch the page again. Each domain is fetched at most once per [`DOMAIN_FETCH_INTERVAL`], and links that
+would exceed it are skipped rather than delayed.
+
+Links wrapped in `<...>`, which Discord doesn't embed either, are never previewed.
+
+*/
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use poise::serenity_prelude::{
+    Context, CreateAllowedMentions, CreateEmbed, CreateEmbedAuthor, CreateMessage, Message,
+    MessageFlags,
+};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use tracing::{debug, trace, warn};
+
+use crate::{
+    Error,
+    entities::unfurl_domain,
+    infrastructure::{
+        botdata::Data,
+        branding::{Branding, get_branding},
+        ids::id_to_string,
+        web_page::{fetch_html, find_links, open_graph, page_title},
+    },
+};
+
+/// Number of previews kept in the cache.
+const CACHE_CAPACITY: usize = 256;
+
+/// How long a cached preview is used before the page is fetched again.
+const CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Minimum time between two fetches of pages of the same domain.
+const DOMAIN_FETCH_INTERVAL: Duration = Duration::from_secs(10);
+
+const MAX_PREVIEWS_PER_MESSAGE: usize = 3;
+
+const MAX_DESCRIPTION_LENGTH: usize = 300;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkPreview {
+    pub url: String,
+    pub site_name: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+}
+
+#[derive(Debug)]
+struct CachedPreview {
+    /// None if the page has no metadata to preview.
+    preview: Option<LinkPreview>,
+    fetched_at: Instant,
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct UnfurlState {
+    previews: HashMap<String, CachedPreview>,
+    /// Increases with every cache use, to find the least recently used preview.
+    uses: u64,
+    last_fetch: HashMap<String, Instant>,
+}
+
+pub type UnfurlCache = Arc<RwLock<UnfurlState>>;
+
+impl UnfurlState {
+    /// Gets a fresh cached preview of a url. The outer None means the url must be fetched.
+    fn get(&mut self, url: &str, now: Instant) -> Option<Option<LinkPreview>> {
+        self.uses += 1;
+        let uses = self.uses;
+        let cached = self
+            .previews
+            .get_mut(url)
+            .filter(|x| now.saturating_duration_since(x.fetched_at) < CACHE_TTL)?;
+        cached.last_used = uses;
+        Some(cached.preview.clone())
+    }
+
+    /// Caches a preview, evicting the least recently used one when the cache is full.
+    fn insert(&mut self, url: String, preview: Option<LinkPreview>, now: Instant) {
+        if self.previews.len() >= CACHE_CAPACITY
+            && !self.previews.contains_key(&url)
+            && let Some(oldest) = self
+                .previews
+                .iter()
+                .min_by_key(|(_, x)| x.last_used)
+                .map(|(url, _)| url.clone())
+        {
+            self.previews.remove(&oldest);
+        }
+        self.uses += 1;
+        self.previews.insert(
+            url,
+            CachedPreview {
+                preview,
+                fetched_at: now,
+                last_used: self.uses,
+            },
+        );
+    }
+
+    /// Whether a page of the domain may be fetched now, and if so records the fetch.
+    fn try_start_fetch(&mut self, domain: &str, now: Instant) -> bool {
+        self.last_fetch
+            .retain(|_, last| now.saturating_duration_since(*last) < DOMAIN_FETCH_INTERVAL);
+        if self.last_fetch.contains_key(domain) {
+            return false;
+        }
+        self.last_fetch.insert(domain.to_string(), now);
+        true
+    }
+}
+
+/// Normalizes a domain or url given by a user to a bare lowercase domain, such as `example.com`.
+pub fn normalize_domain(input: &str) -> Option<String> {
+    let input = input.trim().to_lowercase();
+    let host = match input.split_once("://") {
+        Some((_, rest)) => rest,
+        None => &input,
+    };
+    let host = host.split(['/', '?', '#']).next()?;
+    let host = host.split(':').next()?;
+    let host = host.strip_prefix("www.").unwrap_or(host);
+    let valid = host.contains('.')
+        && !host.starts_with(['.', '-'])
+        && !host.ends_with(['.', '-'])
+        && !host.contains("..")
+        && host
+            .chars()
+            .all(|x| x.is_ascii_alphanumeric() || x == '.' || x == '-');
+    valid.then(|| host.to_string())
+}
+
+/// The whitelisted domain a host belongs to, if any.
+fn matching_domain<'a>(host: &str, domains: &'a [String]) -> Option<&'a str> {
+    let host = host.to_lowercase();
+    domains
+        .iter()
+        .find(|domain| {
+            host == **domain
+                || host
+                    .strip_suffix(domain.as_str())
+                    .is_some_and(|x| x.ends_with('.'))
+        })
+        .map(|x| x.as_str())
+}
+
+/// Links in a message that may be previewed: not wrapped in `<...>`, without trailing punctuation, and unique.
+fn preview_candidates(content: &str) -> Vec<String> {
+    let mut links: Vec<String> = vec![];
+    for link in find_links(content) {
+        if content[..link.start()].ends_with('<') {
+            continue;
+        }
+        let url = link
+            .as_str()
+            .trim_end_matches(['.', ',', ')', '!', '?', ';', ':', '"', '\''])
+            .to_string();
+        if !links.contains(&url) {
+            links.push(url);
+        }
+    }
+    links
+}
+
+fn truncate(text: &str, length: usize) -> String {
+    if text.chars().count() > length {
+        format!("{}...", text.chars().take(length - 3).collect::<String>())
+    } else {
+        text.to_string()
+    }
+}
+
+/// Fetches a page and reads its preview metadata. Returns None for pages without a title or description.
+async fn fetch_preview(url: &str) -> Result<Option<LinkPreview>, Error> {
+    let Some(html) = fetch_html(url).await? else {
+        return Ok(None);
+    };
+    let mut properties = open_graph(&html);
+    let mut take = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| properties.remove(*name))
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty())
+    };
+    let title = take(&["og:title", "twitter:title"]).or_else(|| page_title(&html));
+    let description = take(&["og:description", "twitter:description"]);
+    let site_name = take(&["og:site_name"]);
+    let image = take(&["og:image", "twitter:image"]).filter(|x| x.starts_with("http"));
+    if title.is_none() && description.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(LinkPreview {
+        url: url.to_string(),
+        site_name,
+        title,
+        description,
+        image,
+    }))
+}
+
+/// Gets the preview of a link from the cache, or fetches it if its domain isn't rate limited.
+async fn get_preview(data: &Data, url: &str, domain: &str) -> Option<LinkPreview> {
+    {
+        let mut state = match data.unfurl.write() {
+            Ok(x) => x,
+            Err(_) => {
+                warn!("Unfurl cache lock is poisoned");
+                return None;
+            }
+        };
+        let now = data.clock.instant();
+        if let Some(preview) = state.get(url, now) {
+            trace!("Using cached preview of {}", url);
+            return preview;
+        }
+        if !state.try_start_fetch(domain, now) {
+            debug!("Skipping preview of {}, {} is rate limited", url, domain);
+            return None;
+        }
+    }
+
+    let preview = fetch_preview(url)
+        .await
+        .inspect_err(|e| debug!("Failed to fetch preview of {}: {:?}", url, e))
+        .ok()
+        .flatten();
+    if let Ok(mut state) = data.unfurl.write() {
+        state.insert(url.to_string(), preview.clone(), data.clock.instant());
+    }
+    preview
+}
+
+fn preview_embed(branding: &Branding, preview: &LinkPreview) -> CreateEmbed {
+    let mut embed = branding.embed().url(&preview.url);
+    if let Some(site_name) = &preview.site_name {
+        embed = embed.author(CreateEmbedAuthor::new(truncate(site_name, 256)));
+    }
+    embed = embed.title(truncate(
+        preview.title.as_deref().unwrap_or(&preview.url),
+        256,
+    ));
+    if let Some(description) = &preview.description {
+        embed = embed.description(truncate(description, MAX_DESCRIPTION_LENGTH));
+    }
+    if let Some(image) = &preview.image {
+        embed = embed.thumbnail(image);
+    }
+    embed
+}
+
+/// Replies to a message with previews of its links to whitelisted domains.
+pub async fn on_message(ctx: &Context, data: &Data, message: &Message) -> Result<(), Error> {
+    let Some(guild_id) = message.guild_id else {
+        return Ok(());
+    };
+    if message
+        .flags
+        .is_some_and(|x| x.contains(MessageFlags::SUPPRESS_EMBEDS))
+    {
+        return Ok(());
+    }
+    let links = preview_candidates(&message.content);
+    if links.is_empty() {
+        return Ok(());
+    }
+    let domains: Vec<String> = unfurl_domain::Entity::find()
+        .filter(unfurl_domain::Column::GuildId.eq(id_to_string(guild_id)))
+        .all(&data.db_pool)
+        .await?
+        .into_iter()
+        .map(|x| x.domain)
+        .collect();
+    if domains.is_empty() {
+        return Ok(());
+    }
+
+    let mut previews = vec![];
+    for url in links {
+        if previews.len() >= MAX_PREVIEWS_PER_MESSAGE {
+            break;
+        }
+        let Some(domain) = reqwest::Url::parse(&url).ok().and_then(|x| {
+            x.host_str()
+                .and_then(|host| matching_domain(host, &domains))
+        }) else {
+            continue;
+        };
+        if let Some(preview) = get_preview(data, &url, domain).await {
+            previews.push(preview);
+        }
+    }
+    if previews.is_empty() {
+        return Ok(());
+    }
+
+    let branding = get_branding(&data.db_pool, &data.branding, guild_id).await;
+    let embeds = previews
+        .iter()
+        .map(|x| preview_embed(&branding, x))
+        .collect();
+    message
+        .channel_id
+        .send_message(
+            ctx,
+            CreateMessage::new()
+                .embeds(embeds)
+                .reference_message(message)
+                .allowed_mentions(CreateAllowedMentions::new()),
+        )
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,183 @@
+/*!
+
+Records the time members spend in voice channels for the `/voicestats` command.
+
+A session is opened when a member joins a voice channel and closed when they leave or move to another channel.
+Sessions that were open while the bot was offline have an unknown end. When the guild becomes available again, the
+sessions of members still in the same channel are kept open, the others are closed at that time, and sessions are
+opened for the members that joined voice in the meantime.
+
+*/
+
+use std::collections::{HashMap, HashSet};
+
+use poise::serenity_prelude::{
+    ChannelId, Context, Guild, GuildId, UserId, VoiceState, async_trait,
+};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, sea_query::Expr,
+};
+use tracing::trace;
+
+use crate::{
+    Error,
+    entities::voice_sessions,
+    infrastructure::{botdata::Data, ids::id_to_string, retention::RetentionTask},
+};
+
+const SECONDS_PER_DAY: i64 = 60 * 60 * 24;
+
+pub(crate) fn is_bot(ctx: &Context, state: &VoiceState) -> bool {
+    state
+        .member
+        .as_ref()
+        .map(|member| member.user.bot)
+        .or_else(|| ctx.cache.user(state.user_id).map(|user| user.bot))
+        .unwrap_or(false)
+}
+
+async fn open_session(
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+    user_id: UserId,
+    channel_id: ChannelId,
+    now: i64,
+) -> Result<(), Error> {
+    voice_sessions::Entity::insert(voice_sessions::ActiveModel {
+        guild_id: Set(id_to_string(guild_id)),
+        user_id: Set(id_to_string(user_id)),
+        channel_id: Set(id_to_string(channel_id)),
+        joined_at: Set(now),
+        left_at: Set(None),
+        ..Default::default()
+    })
+    .exec(db)
+    .await?;
+    Ok(())
+}
+
+/// Opens and closes voice sessions when a member joins, leaves or moves between voice channels.
+pub async fn on_voice_state_update(
+    ctx: &Context,
+    data: &Data,
+    new: &VoiceState,
+) -> Result<(), Error> {
+    let Some(guild_id) = new.guild_id else {
+        return Ok(());
+    };
+    if is_bot(ctx, new) {
+        return Ok(());
+    }
+
+    let db = &data.db_pool;
+    let open = voice_sessions::Entity::find()
+        .filter(voice_sessions::Column::GuildId.eq(id_to_string(guild_id)))
+        .filter(voice_sessions::Column::UserId.eq(id_to_string(new.user_id)))
+        .filter(voice_sessions::Column::LeftAt.is_null())
+        .all(db)
+        .await?;
+
+    // Mute, deafen and stream updates don't change the channel
+    let new_channel = new.channel_id.map(id_to_string);
+    if open.len() == 1 && Some(&open[0].channel_id) == new_channel.as_ref() {
+        return Ok(());
+    }
+
+    let now = data.clock.unix_timestamp();
+    if !open.is_empty() {
+        voice_sessions::Entity::update_many()
+            .col_expr(voice_sessions::Column::LeftAt, Expr::value(now))
+            .filter(voice_sessions::Column::Id.is_in(open.iter().map(|x| x.id)))
+            .exec(db)
+            .await?;
+    }
+    if let Some(channel_id) = new.channel_id {
+        trace!(
+            "User {} joined voice channel {} on guild {}",
+            new.user_id, channel_id, guild_id
+        );
+        open_session(db, guild_id, new.user_id, channel_id, now).await?;
+    }
+    Ok(())
+}
+
+/// Reconciles the sessions of a guild that were left open while the bot was offline with the members currently in
+/// voice: sessions of members still in the same channel stay open, the others are closed now, and sessions are opened
+/// for the members in voice without one.
+pub async fn on_guild_available(ctx: &Context, data: &Data, guild: &Guild) -> Result<(), Error> {
+    let db = &data.db_pool;
+    let open = voice_sessions::Entity::find()
+        .filter(voice_sessions::Column::GuildId.eq(id_to_string(guild.id)))
+        .filter(voice_sessions::Column::LeftAt.is_null())
+        .all(db)
+        .await?;
+
+    let current: HashMap<String, String> = guild
+        .voice_states
+        .values()
+        .filter(|state| !is_bot(ctx, state))
+        .filter_map(|state| {
+            let channel_id = state.channel_id?;
+            Some((id_to_string(state.user_id), id_to_string(channel_id)))
+        })
+        .collect();
+    let (kept, closed): (Vec<_>, Vec<_>) = open
+        .into_iter()
+        .partition(|x| current.get(&x.user_id) == Some(&x.channel_id));
+
+    let now = data.clock.unix_timestamp();
+    if !closed.is_empty() {
+        voice_sessions::Entity::update_many()
+            .col_expr(voice_sessions::Column::LeftAt, Expr::value(now))
+            .filter(voice_sessions::Column::Id.is_in(closed.iter().map(|x| x.id)))
+            .exec(db)
+            .await?;
+    }
+
+    let kept: HashSet<&str> = kept.iter().map(|x| x.user_id.as_str()).collect();
+    for state in guild.voice_states.values() {
+        if let Some(channel_id) = state.channel_id
+            && !is_bot(ctx, state)
+            && !kept.contains(id_to_string(state.user_id).as_str())
+        {
+            open_session(db, guild.id, state.user_id, channel_id, now).await?;
+        }
+    }
+    Ok(())
+}
+
+pub struct VoiceSessionRetention;
+
+#[async_trait]
+impl RetentionTask for VoiceSessionRetention {
+    fn name(&self) -> &'static str {
+        "voice_sessions"
+    }
+
+    fn default_retention_days(&self) -> Option<u32> {
+        Some(365)
+    }
+
+    async fn expire(
+        &self,
+        db: &DatabaseConnection,
+        now: i64,
+        retention_days: u32,
+    ) -> Result<u64, Error> {
+        let result = voice_sessions::Entity::delete_many()
+            .filter(
+                voice_sessions::Column::LeftAt.lt(now - retention_days as i64 * SECONDS_PER_DAY),
+            )
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    async fn forget_user(&self, db: &DatabaseConnection, user_id: UserId) -> Result<u64, Error> {
+        let result = voice_sessions::Entity::delete_many()
+            .filter(voice_sessions::Column::UserId.eq(id_to_string(user_id)))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
@@ -0,0 +1,387 @@
+/*!
+
+Announces in a text channel when members join or leave a voice channel, configured per voice channel with
+`/voice-announce`.
+
+Each tracked voice channel is linked to a text channel and has a template for joins and one for leaves, where
+`{name}`, `{mention}` and `{channel}` are replaced by the member's name, their mention and the voice channel. Mentions
+never ping. To keep busy channels from flooding the text channel, changes are gathered for a few seconds after the
+first one and posted together, and members who leave and come back during that time (or the other way around) are
+left out, since nothing changed for the others.
+
+Leaves are detected from the voice state the cache held before the update, so members who were in voice before the
+bot started are only announced once they joined again.
+
+*/
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use migration::OnConflict;
+use poise::serenity_prelude::{
+    ChannelId, Context, CreateAllowedMentions, CreateMessage, GuildId, Http, Mentionable, UserId,
+    VoiceState,
+};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+};
+use strfmt::strfmt;
+use tracing::{debug, warn};
+
+use crate::{
+    Error,
+    entities::voice_announcements,
+    events::voice_activity::is_bot,
+    infrastructure::{
+        botdata::Data,
+        ids::{id_from_string, id_to_string},
+    },
+};
+
+pub const DEFAULT_JOIN_TEMPLATE: &str = "🔊 **{name}** joined {channel}";
+
+pub const DEFAULT_LEAVE_TEMPLATE: &str = "🔇 **{name}** left {channel}";
+
+pub const DEFAULT_COALESCE_SECONDS: u32 = 10;
+
+pub const MAX_COALESCE_SECONDS: u32 = 300;
+
+pub const MAX_TEMPLATE_LENGTH: usize = 150;
+
+/// Most changes listed in a single announcement.
+const MAX_ANNOUNCED_CHANGES: usize = 15;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VoiceAnnouncementSettings {
+    pub text_channel_id: ChannelId,
+    /// Empty to not announce joins.
+    pub join_template: String,
+    /// Empty to not announce leaves.
+    pub leave_template: String,
+    /// Time changes are gathered before they are posted together.
+    pub coalesce_seconds: u32,
+}
+
+impl VoiceAnnouncementSettings {
+    fn from_model(model: &voice_announcements::Model) -> Result<Self, Error> {
+        Ok(Self {
+            text_channel_id: id_from_string(&model.text_channel_id)?,
+            join_template: model.join_template.clone(),
+            leave_template: model.leave_template.clone(),
+            coalesce_seconds: (model.coalesce_seconds.max(0) as u32).min(MAX_COALESCE_SECONDS),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VoiceChange {
+    Joined,
+    Left,
+}
+
+/// Changes of a voice channel waiting to be announced.
+#[derive(Debug)]
+struct PendingAnnouncement {
+    text_channel_id: ChannelId,
+    changes: Vec<(UserId, VoiceChange, String)>,
+}
+
+#[derive(Debug, Default)]
+pub struct VoiceAnnouncementState {
+    /// Settings of the voice channels seen so far, None for channels that aren't tracked.
+    settings: HashMap<ChannelId, Option<VoiceAnnouncementSettings>>,
+    pending: HashMap<ChannelId, PendingAnnouncement>,
+}
+
+pub type VoiceAnnouncementCache = Arc<RwLock<VoiceAnnouncementState>>;
+
+/// Fills a template with the name and mention of a member and the mention of a voice channel.
+fn render_template(
+    template: &str,
+    name: &str,
+    user_id: UserId,
+    channel_id: ChannelId,
+) -> Result<String, strfmt::FmtError> {
+    let args = HashMap::from([
+        ("name".to_string(), name.to_string()),
+        ("mention".to_string(), user_id.mention().to_string()),
+        ("channel".to_string(), channel_id.mention().to_string()),
+    ]);
+    strfmt(template, &args)
+}
+
+/// Checks that a template only uses the known placeholders and fits in an announcement.
+pub fn validate_template(template: &str) -> Result<(), String> {
+    if template.chars().count() > MAX_TEMPLATE_LENGTH {
+        return Err(format!(
+            "Templates are limited to {} characters",
+            MAX_TEMPLATE_LENGTH
+        ));
+    }
+    render_template(template, "name", UserId::new(1), ChannelId::new(1))
+        .map(|_| ())
+        .map_err(|e| {
+            format!(
+                "Invalid template ({}). Use {{name}}, {{mention}} and {{channel}} as placeholders",
+                e
+            )
+        })
+}
+
+/// Loads the announcement settings of a voice channel from the database, bypassing the cache.
+pub async fn load_voice_announcement(
+    db: &DatabaseConnection,
+    channel_id: ChannelId,
+) -> Result<Option<VoiceAnnouncementSettings>, Error> {
+    voice_announcements::Entity::find_by_id(id_to_string(channel_id))
+        .one(db)
+        .await?
+        .map(|x| VoiceAnnouncementSettings::from_model(&x))
+        .transpose()
+}
+
+/// Gets the voice channels of a guild with announcements, and their settings.
+pub async fn guild_voice_announcements(
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+) -> Result<Vec<(ChannelId, VoiceAnnouncementSettings)>, Error> {
+    voice_announcements::Entity::find()
+        .filter(voice_announcements::Column::GuildId.eq(id_to_string(guild_id)))
+        .order_by_asc(voice_announcements::Column::VoiceChannelId)
+        .all(db)
+        .await?
+        .iter()
+        .map(|x| {
+            Ok((
+                id_from_string(&x.voice_channel_id)?,
+                VoiceAnnouncementSettings::from_model(x)?,
+            ))
+        })
+        .collect()
+}
+
+/// Gets the announcement settings of a voice channel, caching them. Falls back to no announcements if they can't be
+/// loaded.
+async fn get_voice_announcement(
+    db: &DatabaseConnection,
+    cache: &VoiceAnnouncementCache,
+    channel_id: ChannelId,
+) -> Option<VoiceAnnouncementSettings> {
+    if let Some(settings) = cache
+        .read()
+        .ok()
+        .and_then(|x| x.settings.get(&channel_id).cloned())
+    {
+        return settings;
+    }
+    match load_voice_announcement(db, channel_id).await {
+        Ok(settings) => {
+            if let Ok(mut cache) = cache.write() {
+                cache.settings.insert(channel_id, settings.clone());
+            }
+            settings
+        }
+        Err(e) => {
+            warn!(
+                "Failed to load voice announcement settings of channel {}: {:?}",
+                channel_id, e
+            );
+            None
+        }
+    }
+}
+
+/// Starts announcing the members joining and leaving a voice channel, or changes its settings.
+pub async fn set_voice_announcement(
+    db: &DatabaseConnection,
+    cache: &VoiceAnnouncementCache,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    settings: VoiceAnnouncementSettings,
+) -> Result<(), Error> {
+    voice_announcements::Entity::insert(voice_announcements::ActiveModel {
+        voice_channel_id: Set(id_to_string(channel_id)),
+        guild_id: Set(id_to_string(guild_id)),
+        text_channel_id: Set(id_to_string(settings.text_channel_id)),
+        join_template: Set(settings.join_template.clone()),
+        leave_template: Set(settings.leave_template.clone()),
+        coalesce_seconds: Set(settings.coalesce_seconds as i32),
+    })
+    .on_conflict(
+        OnConflict::column(voice_announcements::Column::VoiceChannelId)
+            .update_columns([
+                voice_announcements::Column::TextChannelId,
+                voice_announcements::Column::JoinTemplate,
+                voice_announcements::Column::LeaveTemplate,
+                voice_announcements::Column::CoalesceSeconds,
+            ])
+            .to_owned(),
+    )
+    .exec(db)
+    .await?;
+    if let Ok(mut cache) = cache.write() {
+        cache.settings.insert(channel_id, Some(settings));
+    }
+    Ok(())
+}
+
+/// Stops announcing the members joining and leaving a voice channel. Returns whether it was announced.
+pub async fn remove_voice_announcement(
+    db: &DatabaseConnection,
+    cache: &VoiceAnnouncementCache,
+    channel_id: ChannelId,
+) -> Result<bool, Error> {
+    let result = voice_announcements::Entity::delete_by_id(id_to_string(channel_id))
+        .exec(db)
+        .await?;
+    if let Ok(mut cache) = cache.write() {
+        cache.settings.insert(channel_id, None);
+        cache.pending.remove(&channel_id);
+    }
+    Ok(result.rows_affected > 0)
+}
+
+/// Posts the changes gathered for a voice channel once its coalescing delay is over.
+async fn flush_announcement(
+    http: Arc<Http>,
+    cache: VoiceAnnouncementCache,
+    channel_id: ChannelId,
+    delay: Duration,
+) {
+    tokio::time::sleep(delay).await;
+    let Some(pending) = cache
+        .write()
+        .ok()
+        .and_then(|mut x| x.pending.remove(&channel_id))
+    else {
+        return;
+    };
+    if pending.changes.is_empty() {
+        debug!("Nothing changed in voice channel {} after all", channel_id);
+        return;
+    }
+    let mut lines: Vec<String> = pending
+        .changes
+        .iter()
+        .take(MAX_ANNOUNCED_CHANGES)
+        .map(|(_, _, line)| line.clone())
+        .collect();
+    if pending.changes.len() > MAX_ANNOUNCED_CHANGES {
+        lines.push(format!(
+            "...and {} more",
+            pending.changes.len() - MAX_ANNOUNCED_CHANGES
+        ));
+    }
+    let message = CreateMessage::new()
+        .content(lines.join("\n"))
+        .allowed_mentions(CreateAllowedMentions::new());
+    if let Err(e) = pending.text_channel_id.send_message(&http, message).await {
+        warn!(
+            "Failed to announce the changes of voice channel {}: {:?}",
+            channel_id, e
+        );
+    }
+}
+
+/// Queues the announcement of a member joining or leaving a tracked voice channel.
+async fn queue_change(
+    ctx: &Context,
+    data: &Data,
+    channel_id: ChannelId,
+    user_id: UserId,
+    name: &str,
+    change: VoiceChange,
+) -> Result<(), Error> {
+    let Some(settings) =
+        get_voice_announcement(&data.db_pool, &data.voice_announcements, channel_id).await
+    else {
+        return Ok(());
+    };
+    let template = match change {
+        VoiceChange::Joined => &settings.join_template,
+        VoiceChange::Left => &settings.leave_template,
+    };
+    if template.is_empty() {
+        return Ok(());
+    }
+    let line = render_template(template, name, user_id, channel_id)?;
+
+    let Ok(mut cache) = data.voice_announcements.write() else {
+        return Ok(());
+    };
+    if let Some(pending) = cache.pending.get_mut(&channel_id) {
+        // Leaving and coming back (or hopping in and out) cancels out
+        match pending
+            .changes
+            .iter()
+            .position(|(user, previous, _)| *user == user_id && *previous != change)
+        {
+            Some(position) => {
+                pending.changes.remove(position);
+            }
+            None => pending.changes.push((user_id, change, line)),
+        }
+        return Ok(());
+    }
+    cache.pending.insert(
+        channel_id,
+        PendingAnnouncement {
+            text_channel_id: settings.text_channel_id,
+            changes: vec![(user_id, change, line)],
+        },
+    );
+    drop(cache);
+    tokio::spawn(flush_announcement(
+        ctx.http.clone(),
+        data.voice_announcements.clone(),
+        channel_id,
+        Duration::from_secs(settings.coalesce_seconds as u64),
+    ));
+    Ok(())
+}
+
+/// Announces members joining, leaving or moving between tracked voice channels.
+pub async fn on_voice_state_update(
+    ctx: &Context,
+    data: &Data,
+    old: Option<&VoiceState>,
+    new: &VoiceState,
+) -> Result<(), Error> {
+    if new.guild_id.is_none() {
+        return Ok(());
+    }
+    // Mute, deafen and stream updates don't change the channel
+    let old_channel = old.and_then(|x| x.channel_id);
+    if old_channel == new.channel_id || is_bot(ctx, new) {
+        return Ok(());
+    }
+
+    let name = new
+        .member
+        .as_ref()
+        .map(|x| x.display_name().to_string())
+        .or_else(|| {
+            ctx.cache
+                .user(new.user_id)
+                .map(|x| x.display_name().to_string())
+        })
+        .unwrap_or_else(|| new.user_id.to_string());
+    if let Some(channel_id) = old_channel {
+        queue_change(ctx, data, channel_id, new.user_id, &name, VoiceChange::Left).await?;
+    }
+    if let Some(channel_id) = new.channel_id {
+        queue_change(
+            ctx,
+            data,
+            channel_id,
+            new.user_id,
+            &name,
+            VoiceChange::Joined,
+        )
+        .await?;
+    }
+    Ok(())
+}
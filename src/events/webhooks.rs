@@ -0,0 +1,67 @@
+/*!
+
+Forwards the member and moderation events of the gateway to the outgoing webhooks of each guild. Moderation actions
+taken by the bot itself, like nickname filter renames and raid lockdowns, are dispatched where they happen.
+
+*/
+
+use poise::serenity_prelude::{Context, FullEvent, GuildId, User};
+use serde_json::json;
+
+use crate::{
+    Error,
+    infrastructure::{botdata::Data, webhooks::dispatch},
+    services::webhooks::WebhookEvent,
+};
+
+fn user_json(user: &User) -> serde_json::Value {
+    json!({
+        "id": user.id.to_string(),
+        "name": user.name,
+        "global_name": user.global_name,
+        "bot": user.bot,
+    })
+}
+
+/// Dispatches a moderation action of a guild, e.g. `ban`, with the member it targets if any.
+pub fn dispatch_mod_action(data: &Data, guild_id: GuildId, action: &str, user: Option<&User>) {
+    dispatch(
+        &data.db_pool,
+        &data.clock,
+        guild_id,
+        WebhookEvent::ModAction,
+        json!({
+            "action": action,
+            "user": user.map(user_json),
+        }),
+    );
+}
+
+pub async fn on_event(_ctx: &Context, event: &FullEvent, data: &Data) -> Result<(), Error> {
+    match event {
+        FullEvent::GuildMemberAddition { new_member } => dispatch(
+            &data.db_pool,
+            &data.clock,
+            new_member.guild_id,
+            WebhookEvent::MemberJoin,
+            json!({ "user": user_json(&new_member.user) }),
+        ),
+        FullEvent::GuildMemberRemoval { guild_id, user, .. } => dispatch(
+            &data.db_pool,
+            &data.clock,
+            *guild_id,
+            WebhookEvent::MemberLeave,
+            json!({ "user": user_json(user) }),
+        ),
+        FullEvent::GuildBanAddition {
+            guild_id,
+            banned_user,
+        } => dispatch_mod_action(data, *guild_id, "ban", Some(banned_user)),
+        FullEvent::GuildBanRemoval {
+            guild_id,
+            unbanned_user,
+        } => dispatch_mod_action(data, *guild_id, "unban", Some(unbanned_user)),
+        _ => {}
+    }
+    Ok(())
+}
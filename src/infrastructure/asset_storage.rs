@@ -0,0 +1,249 @@
+/*!
+
+Hosts user content files on discord, so that configured images survive the loss of the data directory.
+
+When a guild configures an asset storage channel, every user content file is re-uploaded to that channel and the
+resulting CDN url is stored along with the message hosting it. Discord signs CDN urls with an expiry, so the
+[`AssetRefreshJob`] fetches the hosting messages again before their urls expire. Notifications use the CDN url when an
+unexpired one exists, falling back to the local file otherwise.
+
+*/
+
+use std::collections::HashMap;
+
+use migration::OnConflict;
+use poise::serenity_prelude::{
+    ChannelId, CreateAttachment, CreateMessage, GuildId, Http, MessageId, async_trait,
+};
+use reqwest::Url;
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+};
+use tracing::{info, trace, warn};
+
+use crate::{
+    Error,
+    entities::{asset_storage_channel, user_content_asset},
+    infrastructure::{
+        ids::{id_from_string, id_to_string},
+        jobs::{JobContext, JobHandler},
+        user_content::user_content_path,
+    },
+};
+
+/// Cron schedule of the url refreshes: hourly.
+const REFRESH_SCHEDULE: &str = "17 * * * *";
+
+/// Urls expiring within this many seconds are refreshed, so they stay valid until the next refresh.
+const REFRESH_MARGIN_SECONDS: i64 = 3 * 60 * 60;
+
+/// Unix timestamp at which a CDN url stops working, from its hexadecimal `ex` parameter. None if it doesn't expire.
+fn url_expiry(url: &str) -> Option<i64> {
+    Url::parse(url)
+        .ok()?
+        .query_pairs()
+        .find(|(key, _)| key == "ex")
+        .and_then(|(_, value)| i64::from_str_radix(&value, 16).ok())
+}
+
+/// Whether a CDN url still works at the given unix timestamp.
+fn is_url_valid(url: &str, now: i64) -> bool {
+    url_expiry(url).is_none_or(|expiry| expiry > now)
+}
+
+pub async fn get_asset_storage_channel(
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+) -> Result<Option<ChannelId>, Error> {
+    Ok(
+        asset_storage_channel::Entity::find_by_id(id_to_string(guild_id))
+            .one(db)
+            .await?
+            .and_then(|model| id_from_string(&model.channel_id).ok()),
+    )
+}
+
+/// Re-uploads a user content file to the asset storage channel and stores its CDN url.
+///
+/// Returns None if the guild has no asset storage channel.
+pub async fn upload_asset(
+    http: &Http,
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+    filename: &str,
+) -> Result<Option<String>, Error> {
+    let channel = match get_asset_storage_channel(db, guild_id).await? {
+        Some(x) => x,
+        None => return Ok(None),
+    };
+
+    let attachment = CreateAttachment::path(user_content_path(guild_id, filename)).await?;
+    let message = channel
+        .send_message(http, CreateMessage::new().add_file(attachment))
+        .await?;
+    let url = message
+        .attachments
+        .first()
+        .map(|x| x.url.clone())
+        .ok_or("Uploaded asset message has no attachment")?;
+    trace!("Uploaded asset {} to {}", filename, url);
+
+    user_content_asset::Entity::insert(user_content_asset::ActiveModel {
+        guild_id: Set(id_to_string(guild_id)),
+        filename: Set(filename.to_string()),
+        url: Set(url.clone()),
+        channel_id: Set(id_to_string(channel)),
+        message_id: Set(id_to_string(message.id)),
+    })
+    .on_conflict(
+        OnConflict::columns([
+            user_content_asset::Column::GuildId,
+            user_content_asset::Column::Filename,
+        ])
+        .update_columns([
+            user_content_asset::Column::Url,
+            user_content_asset::Column::ChannelId,
+            user_content_asset::Column::MessageId,
+        ])
+        .to_owned(),
+    )
+    .exec(db)
+    .await?;
+    Ok(Some(url))
+}
+
+/// Re-uploads several user content files, logging (but otherwise ignoring) any failures.
+///
+/// Returns the number of files uploaded.
+pub async fn upload_assets(
+    http: &Http,
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+    filenames: &[String],
+) -> usize {
+    let mut uploaded = 0;
+    for filename in filenames {
+        match upload_asset(http, db, guild_id, filename).await {
+            Ok(Some(_)) => uploaded += 1,
+            Ok(None) => break,
+            Err(e) => warn!("Failed to upload asset {}: {:?}", filename, e),
+        }
+    }
+    uploaded
+}
+
+/// Gets the CDN urls of all hosted user content files of a guild, keyed by filename.
+///
+/// Urls that expired by `now` (a unix timestamp) are left out, so that the local files are used instead.
+pub async fn get_asset_urls(
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+    now: i64,
+) -> Result<HashMap<String, String>, Error> {
+    Ok(user_content_asset::Entity::find()
+        .filter(user_content_asset::Column::GuildId.eq(id_to_string(guild_id)))
+        .all(db)
+        .await?
+        .into_iter()
+        .filter(|model| is_url_valid(&model.url, now))
+        .map(|model| (model.filename, model.url))
+        .collect())
+}
+
+/// Gets a fresh CDN url for a hosted file, by fetching the message hosting it again. Files hosted before the message
+/// ids were stored are uploaded again instead.
+async fn refresh_asset(
+    http: &Http,
+    db: &DatabaseConnection,
+    model: user_content_asset::Model,
+) -> Result<(), Error> {
+    let guild_id: GuildId = id_from_string(&model.guild_id)?;
+    if model.message_id.is_empty() {
+        upload_asset(http, db, guild_id, &model.filename).await?;
+        return Ok(());
+    }
+
+    let channel_id: ChannelId = id_from_string(&model.channel_id)?;
+    let message_id: MessageId = id_from_string(&model.message_id)?;
+    let url = channel_id
+        .message(http, message_id)
+        .await?
+        .attachments
+        .first()
+        .map(|x| x.url.clone())
+        .ok_or("Asset message has no attachment")?;
+    trace!("Refreshed asset {} to {}", model.filename, url);
+
+    let mut active: user_content_asset::ActiveModel = model.into();
+    active.url = Set(url);
+    user_content_asset::Entity::update(active).exec(db).await?;
+    Ok(())
+}
+
+/// Forgets the CDN urls of user content files, e.g. because the files were removed.
+pub async fn remove_assets(
+    db: &impl ConnectionTrait,
+    guild_id: GuildId,
+    filenames: &[String],
+) -> Result<(), Error> {
+    if filenames.is_empty() {
+        return Ok(());
+    }
+    user_content_asset::Entity::delete_many()
+        .filter(user_content_asset::Column::GuildId.eq(id_to_string(guild_id)))
+        .filter(user_content_asset::Column::Filename.is_in(filenames.iter().cloned()))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Refreshes the CDN urls of hosted files before they expire.
+pub struct AssetRefreshJob;
+
+#[async_trait]
+impl JobHandler for AssetRefreshJob {
+    fn kind(&self) -> &'static str {
+        "asset_refresh"
+    }
+
+    fn schedule(&self) -> Option<&'static str> {
+        Some(REFRESH_SCHEDULE)
+    }
+
+    async fn run(&self, ctx: &JobContext, _payload: &str) -> Result<(), Error> {
+        let deadline = ctx.clock.unix_timestamp() + REFRESH_MARGIN_SECONDS;
+        let mut refreshed = 0;
+        for model in user_content_asset::Entity::find().all(&ctx.db).await? {
+            if is_url_valid(&model.url, deadline) {
+                continue;
+            }
+            let (guild_id, filename) = (model.guild_id.clone(), model.filename.clone());
+            match refresh_asset(&ctx.http, &ctx.db, model).await {
+                Ok(()) => refreshed += 1,
+                // The local file is used until the next attempt
+                Err(e) => warn!(
+                    "Failed to refresh asset {} of guild {}: {:?}",
+                    filename, guild_id, e
+                ),
+            }
+        }
+        if refreshed > 0 {
+            info!("Refreshed {} asset urls", refreshed);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_urls_expire() {
+        let url = "https://cdn.discordapp.com/attachments/1/2/welcome.png?ex=6720f3c4&is=671fa244&hm=abc&";
+        assert_eq!(url_expiry(url), Some(0x6720f3c4));
+        assert!(is_url_valid(url, 0x6720f3c3));
+        assert!(!is_url_valid(url, 0x6720f3c4));
+        assert!(is_url_valid("https://example.com/welcome.png", i64::MAX));
+    }
+}
@@ -0,0 +1,82 @@
+/*!
+
+Looks up the moderation action behind a gateway event in the guild's audit log.
+
+Gateway events don't say why they happened, so a member leaving, being kicked and being banned all arrive as the same
+member removal. The audit log does record kicks and bans, with the moderator and reason, but its entries can be
+written shortly after the gateway event, so lookups wait a moment before querying it.
+
+Reading the audit log requires the View Audit Log permission.
+
+*/
+
+use std::time::Duration;
+
+use poise::serenity_prelude::{
+    GuildId, Http, Timestamp, UserId,
+    audit_log::{Action, MemberAction},
+};
+
+use crate::Error;
+
+/// Time to wait for the audit log entry of an action to be written.
+const AUDIT_LOG_DELAY: Duration = Duration::from_secs(2);
+
+/// Audit log entries older than this are not considered to belong to the event.
+const AUDIT_LOG_WINDOW_SECONDS: i64 = 30;
+
+/// Number of recent audit log entries searched for the action.
+const AUDIT_LOG_LIMIT: u8 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RemovalKind {
+    Kick,
+    Ban,
+}
+
+impl RemovalKind {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Self::Kick => "Kicked",
+            Self::Ban => "Banned",
+        }
+    }
+}
+
+/// A kick or ban found in the audit log.
+#[derive(Debug, Clone)]
+pub struct RemovalAction {
+    pub kind: RemovalKind,
+    pub moderator: UserId,
+    pub reason: Option<String>,
+}
+
+/// Finds the kick or ban that removed a member from a guild just now, if they didn't leave on their own.
+pub async fn find_removal_action(
+    http: &Http,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Result<Option<RemovalAction>, Error> {
+    tokio::time::sleep(AUDIT_LOG_DELAY).await;
+    let logs = guild_id
+        .audit_logs(http, None, None, None, Some(AUDIT_LOG_LIMIT))
+        .await?;
+    let now = Timestamp::now().unix_timestamp();
+    Ok(logs
+        .entries
+        .into_iter()
+        .filter(|x| x.target_id.is_some_and(|id| id.get() == user_id.get()))
+        .filter(|x| now - x.id.created_at().unix_timestamp() <= AUDIT_LOG_WINDOW_SECONDS)
+        .find_map(|x| {
+            let kind = match x.action {
+                Action::Member(MemberAction::Kick) => RemovalKind::Kick,
+                Action::Member(MemberAction::BanAdd) => RemovalKind::Ban,
+                _ => return None,
+            };
+            Some(RemovalAction {
+                kind,
+                moderator: x.user_id,
+                reason: x.reason,
+            })
+        }))
+}
@@ -0,0 +1,40 @@
+/*!
+
+Users blocked from interacting with the bot by its owners.
+
+Blocked users are rejected by the global command check, and their messages and component interactions are ignored.
+
+*/
+
+use poise::serenity_prelude::UserId;
+use sea_orm::{DatabaseConnection, EntityTrait};
+use tracing::{debug, error};
+
+use crate::{Context, Error, entities::blocked_user, infrastructure::ids::id_to_string};
+
+/// Checks whether a user is blocked. Errors are logged and treated as not blocked.
+pub async fn is_blocked(db: &DatabaseConnection, user_id: UserId) -> bool {
+    match blocked_user::Entity::find_by_id(id_to_string(user_id))
+        .one(db)
+        .await
+    {
+        Ok(model) => model.is_some(),
+        Err(e) => {
+            error!("Failed to get blocked user: {}", e);
+            false
+        }
+    }
+}
+
+/// Global command check rejecting commands from blocked users. Bot owners are never blocked.
+pub async fn blocklist_check(ctx: Context<'_>) -> Result<bool, Error> {
+    let user_id = ctx.author().id;
+    if ctx.framework().options().owners.contains(&user_id) {
+        return Ok(true);
+    }
+    if is_blocked(&ctx.data().db_pool, user_id).await {
+        debug!("Ignoring command from blocked user {}", user_id);
+        return Ok(false);
+    }
+    Ok(true)
+}
@@ -0,0 +1,184 @@
+/*!
+
+Posts the guild count of the bot to bot list sites, such as top.gg, so their listings stay up to date.
+
+Each site is a [`StatsPoster`], enabled by setting its token in the environment: `TOPGG_TOKEN`, `DISCORD_BOTS_GG_TOKEN`
+or `DISCORDBOTLIST_TOKEN`. Stats are posted every `BOT_LIST_INTERVAL` seconds, the first time shortly after startup
+once the guilds of the bot are cached. To support another site, implement [`StatsPoster`] and add it to
+[`stats_posters`].
+
+*/
+
+use std::{env::var, time::Duration};
+
+use once_cell::sync::Lazy;
+use poise::serenity_prelude::{Context, UserId, async_trait};
+use serde_json::json;
+use tracing::{debug, info, warn};
+
+use crate::{
+    Error,
+    infrastructure::{
+        environment::{
+            DISCORD_BOTS_GG_TOKEN, DISCORDBOTLIST_TOKEN, TOPGG_TOKEN, bot_list_interval,
+        },
+        leader::is_leader,
+    },
+};
+
+/// Wait before the first post, so the guilds received after startup are counted.
+const INITIAL_DELAY: Duration = Duration::from_secs(60);
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+static HTTP: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+});
+
+/// Stats reported to bot lists.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BotStats {
+    pub bot_id: UserId,
+    pub guilds: usize,
+    pub shards: u32,
+}
+
+#[async_trait]
+pub trait StatsPoster: Send + Sync {
+    /// Name of the site, used in logs.
+    fn name(&self) -> &'static str;
+
+    async fn post(&self, stats: &BotStats) -> Result<(), Error>;
+}
+
+/// Gets the bot lists with a token set in the environment.
+pub fn stats_posters() -> Vec<Box<dyn StatsPoster>> {
+    fn non_empty(key: &str) -> Option<String> {
+        var(key)
+            .ok()
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty())
+    }
+
+    let mut posters: Vec<Box<dyn StatsPoster>> = vec![];
+    if let Some(token) = non_empty(TOPGG_TOKEN) {
+        posters.push(Box::new(TopGg { token }));
+    }
+    if let Some(token) = non_empty(DISCORD_BOTS_GG_TOKEN) {
+        posters.push(Box::new(DiscordBotsGg { token }));
+    }
+    if let Some(token) = non_empty(DISCORDBOTLIST_TOKEN) {
+        posters.push(Box::new(DiscordBotList { token }));
+    }
+    posters
+}
+
+pub struct TopGg {
+    token: String,
+}
+
+#[async_trait]
+impl StatsPoster for TopGg {
+    fn name(&self) -> &'static str {
+        "top.gg"
+    }
+
+    async fn post(&self, stats: &BotStats) -> Result<(), Error> {
+        HTTP.post(format!("https://top.gg/api/bots/{}/stats", stats.bot_id))
+            .header("Authorization", &self.token)
+            .json(&json!({ "server_count": stats.guilds, "shard_count": stats.shards }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+pub struct DiscordBotsGg {
+    token: String,
+}
+
+#[async_trait]
+impl StatsPoster for DiscordBotsGg {
+    fn name(&self) -> &'static str {
+        "discord.bots.gg"
+    }
+
+    async fn post(&self, stats: &BotStats) -> Result<(), Error> {
+        HTTP.post(format!(
+            "https://discord.bots.gg/api/v1/bots/{}/stats",
+            stats.bot_id
+        ))
+        .header("Authorization", &self.token)
+        .json(&json!({ "guildCount": stats.guilds, "shardCount": stats.shards }))
+        .send()
+        .await?
+        .error_for_status()?;
+        Ok(())
+    }
+}
+
+pub struct DiscordBotList {
+    token: String,
+}
+
+#[async_trait]
+impl StatsPoster for DiscordBotList {
+    fn name(&self) -> &'static str {
+        "discordbotlist.com"
+    }
+
+    async fn post(&self, stats: &BotStats) -> Result<(), Error> {
+        HTTP.post(format!(
+            "https://discordbotlist.com/api/v1/bots/{}/stats",
+            stats.bot_id
+        ))
+        .header("Authorization", format!("Bot {}", self.token))
+        .json(&json!({ "guilds": stats.guilds }))
+        .send()
+        .await?
+        .error_for_status()?;
+        Ok(())
+    }
+}
+
+fn collect_stats(ctx: &Context) -> BotStats {
+    BotStats {
+        bot_id: ctx.cache.current_user().id,
+        guilds: ctx.cache.guild_count(),
+        shards: ctx.cache.shard_count(),
+    }
+}
+
+/// Runs the stats poster for the lifetime of the bot, if a bot list token is configured.
+pub fn spawn_bot_list_stats(ctx: Context) {
+    let posters = stats_posters();
+    if posters.is_empty() {
+        debug!("No bot list tokens are set, bot list stats are disabled");
+        return;
+    }
+    let interval = bot_list_interval();
+    let names: Vec<&str> = posters.iter().map(|x| x.name()).collect();
+    info!("Posting stats to {} every {:?}", names.join(", "), interval);
+
+    tokio::spawn(async move {
+        tokio::time::sleep(INITIAL_DELAY).await;
+        loop {
+            if !is_leader() {
+                tokio::time::sleep(interval).await;
+                continue;
+            }
+            let stats = collect_stats(&ctx);
+            for poster in &posters {
+                match poster.post(&stats).await {
+                    Ok(()) => debug!("Posted {} guilds to {}", stats.guilds, poster.name()),
+                    Err(e) => warn!("Failed to post stats to {}: {:?}", poster.name(), e),
+                }
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
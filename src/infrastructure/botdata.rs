@@ -1,12 +1,66 @@
 use std::{
     collections::HashMap,
     sync::{Arc, RwLock},
+    time::Duration,
 };
 
 use sea_orm::DatabaseConnection;
 
-#[derive(Debug)]
+use crate::{
+    commands::gamestats::ResponseCache,
+    events::{
+        auto_slowmode::AutoSlowmodeCache,
+        guild_member::PendingGreetings,
+        raid_protection::RaidStateMap,
+        reaction_map::{GuildEmojiCache, ReactionMapCache},
+        responders::ResponderCooldowns,
+        snipe::SnipeCache,
+        translation::RecentTranslations,
+        unfurl::UnfurlCache,
+        voice_announcements::VoiceAnnouncementCache,
+    },
+    infrastructure::{
+        branding::BrandingCache, clock::SharedClock, game_sessions::GameSessions,
+        latency::LatencySamples, random::SharedRandom, recent_invocations::RecentInvocations,
+    },
+};
+
+/// Time allowed to connect to a server with the shared http client. Requests set their own overall timeout.
+const HTTP_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// State shared by every command and event handler. Cloning it is cheap, since its caches are shared, so tasks
+/// outliving an event can take their own copy.
+#[derive(Debug, Clone)]
 pub struct Data {
     pub db_pool: DatabaseConnection,
+    /// Shared http client, so requests reuse connections.
+    pub http: reqwest::Client,
     pub invoc_time: Arc<RwLock<HashMap<u64, std::time::Instant>>>,
+    pub raid_state: RaidStateMap,
+    pub latency: LatencySamples,
+    pub gamestats_cache: ResponseCache,
+    pub responder_cooldowns: ResponderCooldowns,
+    pub clock: SharedClock,
+    pub random: SharedRandom,
+    pub recent_invocations: RecentInvocations,
+    pub branding: BrandingCache,
+    pub game_sessions: GameSessions,
+    pub unfurl: UnfurlCache,
+    pub snipes: SnipeCache,
+    pub auto_slowmode: AutoSlowmodeCache,
+    pub translations: RecentTranslations,
+    pub reaction_maps: ReactionMapCache,
+    pub guild_emojis: GuildEmojiCache,
+    pub pending_greetings: PendingGreetings,
+    pub voice_announcements: VoiceAnnouncementCache,
+    #[cfg(feature = "ai")]
+    pub ai_rate_limits: crate::events::ai_chat::AiRateLimits,
+}
+
+/// Builds the http client shared through [`Data::http`].
+pub fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .connect_timeout(HTTP_CONNECT_TIMEOUT)
+        .build()
+        .unwrap_or_default()
 }
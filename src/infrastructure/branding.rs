@@ -0,0 +1,111 @@
+/*!
+
+Per-guild styling of the embeds sent by the bot, configured with `/branding`.
+
+Commands start their embeds from [`Branding::embed`] so they use the guild's accent color and footer. Embeds that
+set their own color or footer, such as status colors or page numbers, keep them.
+
+*/
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use poise::serenity_prelude::{Colour, CreateEmbed, CreateEmbedFooter, GuildId};
+use sea_orm::{DatabaseConnection, EntityTrait};
+use tracing::warn;
+
+use crate::{
+    Context, Error,
+    entities::guild_branding,
+    infrastructure::{colors, ids::id_to_string},
+};
+
+pub type BrandingCache = Arc<RwLock<HashMap<GuildId, Branding>>>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Branding {
+    pub accent: Colour,
+    pub footer: Option<String>,
+    pub nickname: Option<String>,
+}
+
+impl Default for Branding {
+    fn default() -> Self {
+        Branding {
+            accent: colors::slate(),
+            footer: None,
+            nickname: None,
+        }
+    }
+}
+
+impl Branding {
+    fn from_model(model: guild_branding::Model) -> Self {
+        Branding {
+            accent: model
+                .accent_color
+                .map(|x| Colour::new(x as u32))
+                .unwrap_or(colors::slate()),
+            footer: model.footer.filter(|x| !x.is_empty()),
+            nickname: model.nickname.filter(|x| !x.is_empty()),
+        }
+    }
+
+    /// An empty embed with the accent color and footer of the guild.
+    pub fn embed(&self) -> CreateEmbed {
+        let embed = CreateEmbed::new().color(self.accent);
+        match &self.footer {
+            Some(footer) => embed.footer(CreateEmbedFooter::new(footer)),
+            None => embed,
+        }
+    }
+}
+
+/// Loads the branding of a guild from the database, bypassing the cache.
+pub async fn load_branding(db: &DatabaseConnection, guild_id: GuildId) -> Result<Branding, Error> {
+    Ok(guild_branding::Entity::find_by_id(id_to_string(guild_id))
+        .one(db)
+        .await?
+        .map(Branding::from_model)
+        .unwrap_or_default())
+}
+
+/// Gets the branding of a guild, caching it. Falls back to the default branding if it can't be loaded.
+pub async fn get_branding(
+    db: &DatabaseConnection,
+    cache: &BrandingCache,
+    guild_id: GuildId,
+) -> Branding {
+    if let Some(branding) = cache.read().ok().and_then(|x| x.get(&guild_id).cloned()) {
+        return branding;
+    }
+    match load_branding(db, guild_id).await {
+        Ok(branding) => {
+            if let Ok(mut cache) = cache.write() {
+                cache.insert(guild_id, branding.clone());
+            }
+            branding
+        }
+        Err(e) => {
+            warn!("Failed to load branding of guild {}: {:?}", guild_id, e);
+            Branding::default()
+        }
+    }
+}
+
+/// Gets the branding of the guild a command was used in, or the default branding outside of guilds.
+pub async fn guild_branding(ctx: Context<'_>) -> Branding {
+    match ctx.guild_id() {
+        Some(guild_id) => get_branding(&ctx.data().db_pool, &ctx.data().branding, guild_id).await,
+        None => Branding::default(),
+    }
+}
+
+/// Drops the cached branding of a guild after it was changed.
+pub fn invalidate_branding(cache: &BrandingCache, guild_id: GuildId) {
+    if let Ok(mut cache) = cache.write() {
+        cache.remove(&guild_id);
+    }
+}
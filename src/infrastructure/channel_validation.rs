@@ -0,0 +1,117 @@
+/*!
+
+Checks that the bot can post member notifications to the configured channels.
+
+Channels are validated when they are configured, and periodically afterwards so that a permission revoked later on is
+reported instead of notifications silently failing to send.
+
+*/
+
+use std::{collections::HashSet, time::Duration};
+
+use poise::serenity_prelude::{Cache, ChannelType, Context, GuildChannel, GuildId, Permissions};
+use sea_orm::DatabaseConnection;
+use tracing::{debug, error, info, warn};
+
+use crate::{Error, infrastructure::leader::is_leader, services::notifications::NotificationRepo};
+
+/// Interval between validations of the configured notification channels.
+pub const VALIDATION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Permissions the bot needs to post member notifications.
+pub const NOTIFICATION_PERMISSIONS: Permissions = Permissions::VIEW_CHANNEL
+    .union(Permissions::SEND_MESSAGES)
+    .union(Permissions::EMBED_LINKS);
+
+/// Checks that a channel is a text or announcement channel the bot can post embeds to, describing the problem if it
+/// isn't. Channels of guilds that aren't cached yet are assumed to be fine.
+pub fn check_notification_channel(cache: &Cache, channel: &GuildChannel) -> Result<(), String> {
+    if !matches!(channel.kind, ChannelType::Text | ChannelType::News) {
+        return Err(format!(
+            "{} is not a text or announcement channel",
+            channel.name
+        ));
+    }
+
+    let bot_id = cache.current_user().id;
+    let Some(guild) = cache.guild(channel.guild_id) else {
+        debug!(
+            "Guild {} is not cached, skipping permission check",
+            channel.guild_id
+        );
+        return Ok(());
+    };
+    let Some(member) = guild.members.get(&bot_id) else {
+        debug!(
+            "Bot member of guild {} is not cached, skipping permission check",
+            channel.guild_id
+        );
+        return Ok(());
+    };
+    let missing = NOTIFICATION_PERMISSIONS - guild.user_permissions_in(channel, member);
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "The bot is missing the {} permission(s) in {}",
+            missing.get_permission_names().join(", "),
+            channel.name
+        ))
+    }
+}
+
+/// Validates every configured notification channel, warning about each one that became unusable.
+///
+/// `warned` holds the notifications that were already reported, so each is only reported again after it was fixed.
+pub async fn validate_notification_channels(
+    ctx: &Context,
+    db: &DatabaseConnection,
+    warned: &mut HashSet<(GuildId, bool)>,
+) -> Result<(), Error> {
+    let configured = NotificationRepo::new(db).all_channels().await?;
+    for (guild_id, join, channel_id) in configured {
+        let channel = {
+            let Some(guild) = ctx.cache.guild(guild_id) else {
+                continue;
+            };
+            guild.channels.get(&channel_id).cloned()
+        };
+
+        let problem = match &channel {
+            Some(channel) => check_notification_channel(&ctx.cache, channel).err(),
+            None => Some(format!("Channel {} no longer exists", channel_id)),
+        };
+        let kind = if join { "join" } else { "leave" };
+        match problem {
+            Some(problem) if warned.insert((guild_id, join)) => warn!(
+                "Member {} notifications of guild {} can't be sent: {}",
+                kind, guild_id, problem
+            ),
+            None if warned.remove(&(guild_id, join)) => info!(
+                "Member {} notifications of guild {} can be sent again",
+                kind, guild_id
+            ),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Validates the notification channels on a schedule for the lifetime of the bot.
+pub fn spawn_channel_validation(ctx: Context, db: DatabaseConnection) {
+    tokio::spawn(async move {
+        let mut warned = HashSet::new();
+        // The first validation is delayed so the guilds have time to be cached
+        let start = tokio::time::Instant::now() + VALIDATION_INTERVAL;
+        let mut interval = tokio::time::interval_at(start, VALIDATION_INTERVAL);
+        loop {
+            interval.tick().await;
+            if !is_leader() {
+                continue;
+            }
+            if let Err(e) = validate_notification_channels(&ctx, &db, &mut warned).await {
+                error!("Failed to validate member notification channels: {:?}", e);
+            }
+        }
+    });
+}
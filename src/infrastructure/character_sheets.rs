@@ -0,0 +1,182 @@
+/*!
+
+Rendering of the character sheets of `/character`.
+
+Sheets are free-form: players store any field they like. Fields known to the sheet template are laid out in sections
+like on a tabletop character sheet, with ability scores shown along their modifier, and every other field is listed
+after them. The `name` field titles the sheet.
+
+*/
+
+use poise::serenity_prelude::{CreateEmbed, CreateEmbedFooter};
+
+/// Longest field name, short enough to be typed in one go.
+pub const MAX_FIELD_LENGTH: usize = 32;
+
+pub const MAX_VALUE_LENGTH: usize = 100;
+
+/// Fields a sheet may have. Along with the length of the values, keeps sheets within the size of an embed.
+pub const MAX_FIELDS: usize = 30;
+
+/// Length of an embed field value.
+const EMBED_FIELD_LENGTH: usize = 1024;
+
+pub struct SheetSection {
+    pub title: &'static str,
+    pub fields: &'static [&'static str],
+    /// Whether the values are ability scores, shown with their modifier.
+    pub abilities: bool,
+}
+
+/// Sections of the sheet template, in display order.
+pub static SHEET_TEMPLATE: [SheetSection; 3] = [
+    SheetSection {
+        title: "Character",
+        fields: &["race", "class", "level", "background", "alignment"],
+        abilities: false,
+    },
+    SheetSection {
+        title: "Combat",
+        fields: &["hp", "ac", "speed", "initiative"],
+        abilities: false,
+    },
+    SheetSection {
+        title: "Abilities",
+        fields: &["str", "dex", "con", "int", "wis", "cha"],
+        abilities: true,
+    },
+];
+
+/// Field shown as the title of the sheet.
+pub const NAME_FIELD: &str = "name";
+
+/// Fields of the template, for autocompletion.
+pub fn template_fields() -> impl Iterator<Item = &'static str> {
+    std::iter::once(NAME_FIELD).chain(SHEET_TEMPLATE.iter().flat_map(|x| x.fields.iter().copied()))
+}
+
+/// Normalizes a field name to lowercase words separated by dashes, and checks its length.
+pub fn normalize_field(field: &str) -> Result<String, String> {
+    let field = field
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join("-")
+        .to_lowercase();
+    if field.is_empty() {
+        return Err("Field names can't be empty".to_string());
+    }
+    if field.chars().count() > MAX_FIELD_LENGTH {
+        return Err(format!(
+            "Field names are limited to {} characters",
+            MAX_FIELD_LENGTH
+        ));
+    }
+    if !field
+        .chars()
+        .all(|x| x.is_alphanumeric() || x == '-' || x == '_')
+    {
+        return Err(
+            "Field names can only contain letters, digits, dashes and underscores".to_string(),
+        );
+    }
+    Ok(field)
+}
+
+/// Modifier of an ability score, as in most d20 games.
+pub fn ability_modifier(score: &str) -> Option<i32> {
+    let score: i32 = score.trim().parse().ok()?;
+    Some((score - 10).div_euclid(2))
+}
+
+fn format_value(field: &str, value: &str, abilities: bool) -> String {
+    match ability_modifier(value).filter(|_| abilities) {
+        Some(x) => format!("**{}** {} ({:+})", field.to_uppercase(), value, x),
+        None => format!("**{}:** {}", capitalize(field), value),
+    }
+}
+
+fn capitalize(field: &str) -> String {
+    let mut chars = field.chars();
+    match chars.next() {
+        Some(x) => x.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Lays out the fields of a sheet in the sections of the template, followed by the other fields. Returns the title of
+/// each section and its lines.
+pub fn sheet_sections(fields: &[(String, String)]) -> Vec<(&'static str, Vec<String>)> {
+    let value = |name: &str| {
+        fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.as_str())
+    };
+    let mut sections: Vec<(&'static str, Vec<String>)> = SHEET_TEMPLATE
+        .iter()
+        .map(|section| {
+            let lines = section
+                .fields
+                .iter()
+                .filter_map(|field| {
+                    value(field).map(|value| format_value(field, value, section.abilities))
+                })
+                .collect();
+            (section.title, lines)
+        })
+        .collect();
+    let other = fields
+        .iter()
+        .filter(|(field, _)| field != NAME_FIELD && !template_fields().any(|x| x == field))
+        .map(|(field, value)| format_value(field, value, false))
+        .collect();
+    sections.push(("Other", other));
+    sections.retain(|(_, lines)| !lines.is_empty());
+    sections
+}
+
+/// Title of a sheet: its name field, or else the name of its owner.
+fn sheet_title(owner: &str, fields: &[(String, String)]) -> String {
+    fields
+        .iter()
+        .find(|(field, _)| field == NAME_FIELD)
+        .map(|(_, value)| value.clone())
+        .unwrap_or_else(|| format!("{}'s character", owner))
+}
+
+/// Renders a sheet in an embed.
+pub fn sheet_embed(embed: CreateEmbed, owner: &str, fields: &[(String, String)]) -> CreateEmbed {
+    let mut embed = embed
+        .title(sheet_title(owner, fields))
+        .footer(CreateEmbedFooter::new(format!("Played by {}", owner)));
+    for (title, lines) in sheet_sections(fields) {
+        // Long sections are split over several embed fields
+        let mut chunk = String::new();
+        let mut first = true;
+        for line in lines {
+            if !chunk.is_empty() && chunk.len() + line.len() + 1 > EMBED_FIELD_LENGTH {
+                embed = embed.field(if first { title } else { "\u{200b}" }, chunk, false);
+                chunk = String::new();
+                first = false;
+            }
+            if !chunk.is_empty() {
+                chunk.push('\n');
+            }
+            chunk.push_str(&line);
+        }
+        embed = embed.field(if first { title } else { "\u{200b}" }, chunk, true);
+    }
+    embed
+}
+
+/// Renders a sheet as Markdown text, for exports.
+pub fn sheet_text(owner: &str, fields: &[(String, String)]) -> String {
+    let mut text = format!("# {}\n\nPlayed by {}\n", sheet_title(owner, fields), owner);
+    for (title, lines) in sheet_sections(fields) {
+        text.push_str(&format!("\n## {}\n\n", title));
+        for line in lines {
+            text.push_str(&format!("- {}\n", line));
+        }
+    }
+    text
+}
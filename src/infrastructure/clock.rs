@@ -0,0 +1,86 @@
+/*!
+
+Source of the current time, injected through [`Data`](super::botdata::Data).
+
+Code that depends on the current time should read it from a [`Clock`] instead of calling `SystemTime::now` or
+`Instant::now` directly, so its behavior can be reproduced with a [`ManualClock`] without sleeping.
+
+*/
+
+use std::{
+    fmt::Debug,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+pub type SharedClock = Arc<dyn Clock>;
+
+pub trait Clock: Send + Sync + Debug {
+    /// Current wall clock time.
+    fn now(&self) -> SystemTime;
+
+    /// Current monotonic time, for measuring elapsed time.
+    fn instant(&self) -> Instant;
+
+    /// Current wall clock time in seconds since the unix epoch.
+    fn unix_timestamp(&self) -> i64 {
+        self.now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64
+    }
+}
+
+/// The real time of the system.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when it is advanced, used to simulate the passing of time.
+#[derive(Debug)]
+pub struct ManualClock {
+    start: SystemTime,
+    start_instant: Instant,
+    elapsed: RwLock<Duration>,
+}
+
+impl ManualClock {
+    /// Creates a clock stopped at a unix timestamp.
+    pub fn new(unix_timestamp: u64) -> Self {
+        ManualClock {
+            start: UNIX_EPOCH + Duration::from_secs(unix_timestamp),
+            start_instant: Instant::now(),
+            elapsed: RwLock::new(Duration::ZERO),
+        }
+    }
+
+    /// Moves the clock forward.
+    pub fn advance(&self, duration: Duration) {
+        if let Ok(mut elapsed) = self.elapsed.write() {
+            *elapsed += duration;
+        }
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.elapsed.read().map(|x| *x).unwrap_or_default()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        self.start + self.elapsed()
+    }
+
+    fn instant(&self) -> Instant {
+        self.start_instant + self.elapsed()
+    }
+}
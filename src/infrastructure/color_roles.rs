@@ -0,0 +1,128 @@
+/*!
+
+Self-assignable color roles, picked by members with `/colorrole`.
+
+Color roles have no permissions and only change the name color of their members. Members with the same color share
+one role, and a role is deleted as soon as nobody wears it anymore, so a guild only has as many color roles as
+colors in use. The number of distinct color roles on a guild is capped, configured with `/colorroles limit`.
+
+*/
+
+use poise::serenity_prelude::{GuildId, Http, RoleId, UserId, async_trait};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter, QuerySelect,
+};
+use tracing::{info, warn};
+
+use crate::{
+    Error,
+    entities::{color_role_settings, color_roles},
+    infrastructure::{
+        ids::{id_from_string, id_to_string},
+        retention::RetentionTask,
+    },
+};
+
+/// Maximum number of color roles on a guild that didn't configure a limit.
+pub const DEFAULT_MAX_COLOR_ROLES: i32 = 25;
+
+/// Gets the maximum number of distinct color roles of a guild. 0 means color roles are disabled.
+pub async fn get_max_color_roles(db: &DatabaseConnection, guild_id: GuildId) -> Result<i32, Error> {
+    Ok(
+        color_role_settings::Entity::find_by_id(id_to_string(guild_id))
+            .one(db)
+            .await?
+            .map(|x| x.max_roles)
+            .unwrap_or(DEFAULT_MAX_COLOR_ROLES),
+    )
+}
+
+/// The distinct color roles of a guild with their color.
+pub async fn guild_color_roles(
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+) -> Result<Vec<(RoleId, u32)>, Error> {
+    let rows: Vec<(String, i64)> = color_roles::Entity::find()
+        .select_only()
+        .column(color_roles::Column::RoleId)
+        .column(color_roles::Column::Color)
+        .filter(color_roles::Column::GuildId.eq(id_to_string(guild_id)))
+        .distinct()
+        .into_tuple()
+        .all(db)
+        .await?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(role_id, color)| Some((id_from_string(&role_id).ok()?, color as u32)))
+        .collect())
+}
+
+/// Forgets the color role of a member and deletes the role if no other member wears it.
+///
+/// The role is only taken from the member if `remove_from_member` is set, since members that left the guild lost
+/// their roles already.
+pub async fn release_color_role(
+    http: &Http,
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+    user_id: UserId,
+    remove_from_member: bool,
+) -> Result<(), Error> {
+    let Some(row) =
+        color_roles::Entity::find_by_id((id_to_string(guild_id), id_to_string(user_id)))
+            .one(db)
+            .await?
+    else {
+        return Ok(());
+    };
+    color_roles::Entity::delete_by_id((row.guild_id.clone(), row.user_id.clone()))
+        .exec(db)
+        .await?;
+    let role_id: RoleId = id_from_string(&row.role_id)?;
+
+    if remove_from_member
+        && let Err(e) = http
+            .remove_member_role(guild_id, user_id, role_id, Some("Color role changed"))
+            .await
+    {
+        warn!(
+            "Failed to remove color role {} from member {}: {:?}",
+            role_id, user_id, e
+        );
+    }
+
+    let wearers = color_roles::Entity::find()
+        .filter(color_roles::Column::GuildId.eq(row.guild_id))
+        .filter(color_roles::Column::RoleId.eq(row.role_id))
+        .count(db)
+        .await?;
+    if wearers == 0 {
+        match guild_id.delete_role(http, role_id).await {
+            Ok(_) => info!(
+                "Deleted unused color role {} on guild {}",
+                role_id, guild_id
+            ),
+            Err(e) => warn!("Failed to delete unused color role {}: {:?}", role_id, e),
+        }
+    }
+    Ok(())
+}
+
+/// Forgets the color roles of users who asked to be forgotten. Purges don't reach discord, so the roles themselves stay
+/// on the guild until removed by hand. Color roles are picked by hand, so they never expire.
+pub struct ColorRoleRetention;
+
+#[async_trait]
+impl RetentionTask for ColorRoleRetention {
+    fn name(&self) -> &'static str {
+        "color_roles"
+    }
+
+    async fn forget_user(&self, db: &DatabaseConnection, user_id: UserId) -> Result<u64, Error> {
+        let result = color_roles::Entity::delete_many()
+            .filter(color_roles::Column::UserId.eq(id_to_string(user_id)))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
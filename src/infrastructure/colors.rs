@@ -1,3 +1,7 @@
+use poise::serenity_prelude::Colour;
+
+use crate::Error;
+
 macro_rules! const_color {
     ($name:ident, $value:expr) => {
         paste::paste! {
@@ -29,3 +33,39 @@ const_color! { LIME,        0x00C100 }
 
 const_color! { BLACK, 0, 0, 0}
 const_color! { WHITE, 255, 255, 255}
+
+/// Named colors accepted wherever a color can be entered, such as `/branding set` and `/colorrole`.
+pub const PRESETS: &[(&str, u32)] = &[
+    ("orange", ORANGE),
+    ("purple", PURPLE),
+    ("green", GREEN),
+    ("slate", SLATE),
+    ("royal blue", ROYAL_BLUE),
+    ("red", RED),
+    ("lime", LIME),
+    ("black", BLACK),
+    ("white", WHITE),
+];
+
+/// Parses a hex color such as `#3E6775` or `3e6775`, or the name of one of the [`PRESETS`].
+pub fn parse_color(value: &str) -> Result<Colour, Error> {
+    let name = value.trim().to_lowercase().replace(['_', '-'], " ");
+    if let Some((_, preset)) = PRESETS.iter().find(|(x, _)| *x == name) {
+        return Ok(Colour::new(*preset));
+    }
+    let hex = value.trim().trim_start_matches('#');
+    if hex.len() != 6 {
+        return Err(format!(
+            "'{}' is not a hex color like #3E6775 or a color name",
+            value
+        )
+        .into());
+    }
+    u32::from_str_radix(hex, 16).map(Colour::new).map_err(|_| {
+        format!(
+            "'{}' is not a hex color like #3E6775 or a color name",
+            value
+        )
+        .into()
+    })
+}
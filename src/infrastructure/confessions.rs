@@ -0,0 +1,165 @@
+/*!
+
+Storage of anonymous confessions posted with `/confess`.
+
+The author of a confession is stored encrypted with AES-256-GCM, using the key in the `CONFESSION_KEY` environment
+variable (64 hex characters). Only staff can reveal the author with `/confession reveal` for abuse handling, and
+the mapping is deleted once the retention period of the `confessions` subsystem has passed, even for authors who
+asked to be forgotten, so abuse can still be traced. Confessions are unavailable when no key is configured.
+
+Each confession also stores a keyed hash of its author, so the confessions of a user can be found with an index
+instead of decrypting every author.
+
+*/
+
+use aes_gcm::{
+    Aes256Gcm, Key, Nonce,
+    aead::{Aead, KeyInit},
+};
+use hmac::{Hmac, Mac};
+use once_cell::sync::Lazy;
+use poise::serenity_prelude::{UserId, async_trait};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter};
+use sha2::Sha256;
+use std::env::var;
+use tracing::{info, warn};
+
+use crate::{
+    Error,
+    entities::confessions,
+    infrastructure::{environment::CONFESSION_KEY, retention::RetentionTask},
+};
+
+const SECONDS_PER_DAY: i64 = 60 * 60 * 24;
+
+/// Length of the random nonce stored in front of each encrypted author.
+const NONCE_LENGTH: usize = 12;
+
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_APPROVED: &str = "approved";
+pub const STATUS_DENIED: &str = "denied";
+
+static KEY: Lazy<Option<Vec<u8>>> = Lazy::new(|| {
+    let key = var(CONFESSION_KEY).ok()?;
+    let key = parse_key(key.trim());
+    if key.is_none() {
+        warn!(
+            "{} must be 64 hex characters, confessions are disabled",
+            CONFESSION_KEY
+        );
+    }
+    key
+});
+
+static CIPHER: Lazy<Option<Aes256Gcm>> = Lazy::new(|| {
+    KEY.as_ref()
+        .map(|key| Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)))
+});
+
+/// Key of the author hashes, derived from the confession key so the encryption key isn't reused as is.
+static HASH_KEY: Lazy<Option<Vec<u8>>> = Lazy::new(|| {
+    let key = KEY.as_ref()?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).ok()?;
+    mac.update(b"confession author hash");
+    Some(mac.finalize().into_bytes().to_vec())
+});
+
+fn parse_key(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+fn cipher() -> Result<&'static Aes256Gcm, Error> {
+    CIPHER.as_ref().ok_or_else(|| {
+        "Confessions are not available: the bot has no confession key configured".into()
+    })
+}
+
+/// Encrypts the id of the author of a confession, prefixed with the nonce used.
+pub fn encrypt_author(user_id: UserId) -> Result<Vec<u8>, Error> {
+    let nonce: [u8; NONCE_LENGTH] = rand::random();
+    let ciphertext = cipher()?
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            user_id.get().to_be_bytes().as_slice(),
+        )
+        .map_err(|_| "Failed to encrypt the confession author")?;
+    Ok([nonce.as_slice(), &ciphertext].concat())
+}
+
+/// Keyed hash of the id of the author of a confession, as lowercase hex. The hash can't be reversed or computed
+/// without the confession key.
+pub fn hash_author(user_id: UserId) -> Result<String, Error> {
+    let key = HASH_KEY.as_ref().ok_or_else(|| -> Error {
+        "Confessions are not available: the bot has no confession key configured".into()
+    })?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(&user_id.get().to_be_bytes());
+    Ok(format!("{:x}", mac.finalize().into_bytes()))
+}
+
+/// Decrypts the author of a confession stored by [`encrypt_author`].
+pub fn decrypt_author(author: &[u8]) -> Result<UserId, Error> {
+    if author.len() <= NONCE_LENGTH {
+        return Err("The confession author is malformed".into());
+    }
+    let (nonce, ciphertext) = author.split_at(NONCE_LENGTH);
+    let plaintext = cipher()?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "The confession author can't be decrypted with the configured key")?;
+    let bytes: [u8; 8] = plaintext
+        .try_into()
+        .map_err(|_| "The confession author is malformed")?;
+    Ok(UserId::new(u64::from_be_bytes(bytes)))
+}
+
+pub struct ConfessionRetention;
+
+#[async_trait]
+impl RetentionTask for ConfessionRetention {
+    fn name(&self) -> &'static str {
+        "confessions"
+    }
+
+    fn default_retention_days(&self) -> Option<u32> {
+        Some(90)
+    }
+
+    async fn expire(
+        &self,
+        db: &DatabaseConnection,
+        now: i64,
+        retention_days: u32,
+    ) -> Result<u64, Error> {
+        let result = confessions::Entity::delete_many()
+            .filter(
+                confessions::Column::CreatedAt.lt(now - retention_days as i64 * SECONDS_PER_DAY),
+            )
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    async fn forget_user(&self, db: &DatabaseConnection, user_id: UserId) -> Result<u64, Error> {
+        // Authors are kept until the confessions expire, so staff can still act on abusive confessions
+        let Ok(author_hash) = hash_author(user_id) else {
+            return Ok(0);
+        };
+        let kept = confessions::Entity::find()
+            .filter(confessions::Column::AuthorHash.eq(author_hash))
+            .count(db)
+            .await?;
+        if kept > 0 {
+            info!(
+                "Kept the author of {} confessions of user {} until they expire",
+                kept, user_id
+            );
+        }
+        Ok(0)
+    }
+}
@@ -0,0 +1,273 @@
+/*!
+
+Cron schedules of recurring [jobs](super::jobs), evaluated in UTC.
+
+Schedules use the usual five fields, `minute hour day-of-month month day-of-week`, each being `*`, a value, a range
+`a-b` or a list of them separated by commas, optionally followed by a step `/n`. Days of the week go from 0 (Sunday)
+to 6, and 7 is Sunday too. Like in most cron implementations, when both the day of the month and the day of the week
+are restricted, a day matching either runs the job. `@hourly`, `@daily`, `@weekly` and `@monthly` are accepted as
+shorthands.
+
+*/
+
+use std::fmt::Display;
+
+const SECONDS_PER_MINUTE: i64 = 60;
+
+const SECONDS_PER_DAY: i64 = 60 * 60 * 24;
+
+/// Days searched for the next run. Schedules that don't match within this time (such as February 30th) never run.
+const MAX_SEARCH_DAYS: i64 = 366 * 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronError(String);
+
+impl Display for CronError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for CronError {}
+
+/// Parses one field into a bit set of the values it matches.
+fn parse_field(field: &str, name: &str, min: u32, max: u32) -> Result<u64, CronError> {
+    let invalid = || CronError(format!("invalid {} field '{}'", name, field));
+    let mut values = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(invalid());
+        }
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (
+                    start.parse::<u32>().map_err(|_| invalid())?,
+                    end.parse::<u32>().map_err(|_| invalid())?,
+                ),
+                None => {
+                    let value = range.parse::<u32>().map_err(|_| invalid())?;
+                    (value, if part.contains('/') { max } else { value })
+                }
+            },
+        };
+        if start < min || end > max || start > end {
+            return Err(CronError(format!(
+                "{} field '{}' must be between {} and {}",
+                name, field, min, max
+            )));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            values |= 1 << value;
+        }
+    }
+    Ok(values)
+}
+
+/// Converts days since the unix epoch to a (year, month, day) date, using Howard Hinnant's algorithm.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    (yoe + era * 400 + (month <= 2) as i64, month, day)
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self, CronError> {
+        let expression = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            x => x,
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            return Err(CronError(format!(
+                "expected 5 fields (minute hour day-of-month month day-of-week), got {}",
+                fields.len()
+            )));
+        };
+        let mut days_of_week = parse_field(day_of_week, "day of week", 0, 7)?;
+        // Sunday is both 0 and 7
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+        Ok(Self {
+            minutes: parse_field(minute, "minute", 0, 59)?,
+            hours: parse_field(hour, "hour", 0, 23)?,
+            days_of_month: parse_field(day_of_month, "day of month", 1, 31)?,
+            months: parse_field(month, "month", 1, 12)?,
+            days_of_week,
+            any_day_of_month: day_of_month.starts_with('*'),
+            any_day_of_week: day_of_week.starts_with('*'),
+        })
+    }
+
+    fn matches_day(&self, days: i64) -> bool {
+        let (_, month, day_of_month) = civil_from_days(days);
+        // The unix epoch was a Thursday
+        let day_of_week = (days + 4).rem_euclid(7);
+        let matches_day_of_month = self.days_of_month & (1 << day_of_month) != 0;
+        let matches_day_of_week = self.days_of_week & (1 << day_of_week) != 0;
+        let matches_day = match (self.any_day_of_month, self.any_day_of_week) {
+            (true, true) => true,
+            (false, true) => matches_day_of_month,
+            (true, false) => matches_day_of_week,
+            (false, false) => matches_day_of_month || matches_day_of_week,
+        };
+        matches_day && self.months & (1 << month) != 0
+    }
+
+    /// Unix timestamp of the first run strictly after a timestamp, or None if the schedule never runs.
+    pub fn next_after(&self, timestamp: i64) -> Option<i64> {
+        let start = timestamp - timestamp.rem_euclid(SECONDS_PER_MINUTE) + SECONDS_PER_MINUTE;
+        let start_day = start.div_euclid(SECONDS_PER_DAY);
+        let start_minute = start.rem_euclid(SECONDS_PER_DAY) / SECONDS_PER_MINUTE;
+        for days in start_day..start_day + MAX_SEARCH_DAYS {
+            if !self.matches_day(days) {
+                continue;
+            }
+            let first_minute = if days == start_day { start_minute } else { 0 };
+            let minute_of_day = (first_minute..24 * 60)
+                .find(|x| self.hours & (1 << (x / 60)) != 0 && self.minutes & (1 << (x % 60)) != 0);
+            if let Some(minute_of_day) = minute_of_day {
+                return Some(days * SECONDS_PER_DAY + minute_of_day * SECONDS_PER_MINUTE);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-01-01 00:00 UTC, a Monday
+    const JANUARY_1ST: i64 = 1704067200;
+
+    fn next_after(expression: &str, timestamp: i64) -> Option<i64> {
+        CronSchedule::parse(expression)
+            .unwrap()
+            .next_after(timestamp)
+    }
+
+    #[test]
+    fn ranges_steps_and_lists_match() {
+        let cases = [
+            // 00:15
+            ("*/15 * * * *", JANUARY_1ST, Some(1704068100)),
+            // 00:30 -> 00:45
+            ("5/20 * * * *", 1704069000, Some(1704069900)),
+            // 09:30
+            ("30 9-17 * * *", JANUARY_1ST, Some(1704101400)),
+            // 10:00 -> 13:00
+            ("0 9-17/4 * * *", 1704103200, Some(1704114000)),
+            // 10:00 -> 20:00
+            ("0 8,20 * * *", 1704103200, Some(1704139200)),
+            // Friday 12:00 -> Monday
+            ("0 0 * * 1-5", 1704456000, Some(1704672000)),
+            // Sunday the 7th
+            ("0 0 * * 7", JANUARY_1ST, Some(1704585600)),
+            // Runs are strictly after the timestamp
+            ("@monthly", JANUARY_1ST, Some(1706745600)),
+            ("59 23 31 12 *", JANUARY_1ST, Some(1735689540)),
+            ("0 0 29 2 *", JANUARY_1ST, Some(1709164800)),
+            // March 1st 2024 -> February 29th 2028
+            ("0 0 29 2 *", 1709251200, Some(1835395200)),
+            ("0 0 30 2 *", JANUARY_1ST, None),
+        ];
+        for (expression, timestamp, expected) in cases {
+            assert_eq!(
+                next_after(expression, timestamp),
+                expected,
+                "{}",
+                expression
+            );
+        }
+        assert_eq!(
+            CronSchedule::parse("0 0 * * 0"),
+            CronSchedule::parse("0 0 * * 7")
+        );
+    }
+
+    #[test]
+    fn restricted_days_of_month_and_week_match_either() {
+        let cases = [
+            // The 13th only
+            ("0 0 13 * *", JANUARY_1ST, Some(1705104000)),
+            // Fridays only, the 5th
+            ("0 0 * * 5", JANUARY_1ST, Some(1704412800)),
+            // The 13th or Fridays: Friday the 5th, then Friday the 12th, then Saturday the 13th
+            ("0 0 13 * 5", JANUARY_1ST, Some(1704412800)),
+            ("0 0 13 * 5", 1704412800, Some(1705017600)),
+            ("0 0 13 * 5", 1705021200, Some(1705104000)),
+        ];
+        for (expression, timestamp, expected) in cases {
+            assert_eq!(
+                next_after(expression, timestamp),
+                expected,
+                "{}",
+                expression
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_expressions_are_refused() {
+        let cases = [
+            (
+                "",
+                "expected 5 fields (minute hour day-of-month month day-of-week), got 0",
+            ),
+            (
+                "* * * *",
+                "expected 5 fields (minute hour day-of-month month day-of-week), got 4",
+            ),
+            (
+                "@yearly",
+                "expected 5 fields (minute hour day-of-month month day-of-week), got 1",
+            ),
+            ("60 * * * *", "minute field '60' must be between 0 and 59"),
+            ("* 24 * * *", "hour field '24' must be between 0 and 23"),
+            (
+                "* * 0 * *",
+                "day of month field '0' must be between 1 and 31",
+            ),
+            ("* * * 13 *", "month field '13' must be between 1 and 12"),
+            ("* * * * 8", "day of week field '8' must be between 0 and 7"),
+            ("5-1 * * * *", "minute field '5-1' must be between 0 and 59"),
+            ("*/0 * * * *", "invalid minute field '*/0'"),
+            ("1,,2 * * * *", "invalid minute field '1,,2'"),
+            ("* mon * * *", "invalid hour field 'mon'"),
+        ];
+        for (expression, expected) in cases {
+            assert_eq!(
+                CronSchedule::parse(expression).unwrap_err().to_string(),
+                expected,
+                "{}",
+                expression
+            );
+        }
+    }
+}
@@ -0,0 +1,143 @@
+/*!
+
+Length limits Discord enforces on messages and embeds.
+
+Text saved by admins is checked against them when it is saved, so an over-limit notification or embed is rejected with
+the part that is too long, instead of failing every time it is sent. Text that only grows past a limit once its
+placeholders are filled in is truncated when it is rendered.
+
+*/
+
+/// Embeds are limited to 25 fields.
+pub const MAX_FIELDS: usize = 25;
+
+/// Total length of the text of an embed: its title, description, author, footer and fields.
+pub const MAX_EMBED_LENGTH: usize = 6000;
+
+/// Part of a message with a length limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessagePart {
+    Content,
+    Title,
+    Description,
+    Author,
+    Footer,
+    FieldName,
+    FieldValue,
+}
+
+impl MessagePart {
+    /// Maximum length of the part, in characters.
+    pub fn limit(&self) -> usize {
+        match self {
+            Self::Content => 2000,
+            Self::Title => 256,
+            Self::Description => 4096,
+            Self::Author => 256,
+            Self::Footer => 2048,
+            Self::FieldName => 256,
+            Self::FieldValue => 1024,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Content => "message content",
+            Self::Title => "title",
+            Self::Description => "description",
+            Self::Author => "author",
+            Self::Footer => "footer",
+            Self::FieldName => "field name",
+            Self::FieldValue => "field value",
+        }
+    }
+
+    /// Whether the part counts towards [`MAX_EMBED_LENGTH`].
+    fn in_embed(&self) -> bool {
+        *self != Self::Content
+    }
+
+    /// Checks that a text fits in the part, describing by how much it is over the limit if it doesn't.
+    pub fn check(&self, text: &str) -> Result<(), String> {
+        let length = text.chars().count();
+        if length <= self.limit() {
+            return Ok(());
+        }
+        Err(format!(
+            "The {} is {} characters long, {} over the limit of {}",
+            self.name(),
+            length,
+            length - self.limit(),
+            self.limit()
+        ))
+    }
+
+    /// Cuts a text down to the limit of the part, ending it with an ellipsis if it was too long.
+    pub fn truncate(&self, text: String) -> String {
+        if text.chars().count() <= self.limit() {
+            return text;
+        }
+        text.chars().take(self.limit() - 1).chain(['…']).collect()
+    }
+}
+
+/// Checks the parts of a message against their limits, and the embed against its total length. Fails with the first
+/// part over its limit.
+pub fn check_message_lengths(parts: &[(MessagePart, &str)]) -> Result<(), String> {
+    for (part, text) in parts {
+        part.check(text)?;
+    }
+    let fields = parts
+        .iter()
+        .filter(|(part, _)| *part == MessagePart::FieldName)
+        .count();
+    if fields > MAX_FIELDS {
+        return Err(format!(
+            "Embeds can have at most {} fields, this one has {}",
+            MAX_FIELDS, fields
+        ));
+    }
+    let total: usize = parts
+        .iter()
+        .filter(|(part, _)| part.in_embed())
+        .map(|(_, text)| text.chars().count())
+        .sum();
+    if total > MAX_EMBED_LENGTH {
+        return Err(format!(
+            "The text of the embed is {} characters long in total, {} over the limit of {}",
+            total,
+            total - MAX_EMBED_LENGTH,
+            MAX_EMBED_LENGTH
+        ));
+    }
+    Ok(())
+}
+
+/// Joins lines into as few texts as possible without going over a length, for messages split over several pages or
+/// messages. Lines longer than the length are truncated.
+pub fn split_lines(lines: &[String], max_length: usize) -> Vec<String> {
+    let mut texts = vec![];
+    let mut text = String::new();
+    let mut length = 0;
+    for line in lines {
+        let line: String = match line.chars().count() > max_length {
+            true => line.chars().take(max_length - 1).chain(['…']).collect(),
+            false => line.clone(),
+        };
+        let line_length = line.chars().count();
+        if !text.is_empty() && length + 1 + line_length > max_length {
+            texts.push(std::mem::take(&mut text));
+            length = 0;
+        }
+        if !text.is_empty() {
+            text.push('\n');
+            length += 1;
+        }
+        text.push_str(&line);
+        length += line_length;
+    }
+    if !text.is_empty() {
+        texts.push(text);
+    }
+    texts
+}
@@ -3,6 +3,7 @@ use poise::serenity_prelude::GuildId;
 use std::{
     env::var,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
 macro_rules! const_str {
@@ -18,10 +19,39 @@ const_str!(DISCORD_TOKEN);
 const_str!(LOG_LEVEL);
 const_str!(LOG_STYLE);
 const_str!(LOG_PATH);
+const_str!(LOG_PRIVACY);
 
 const_str!(OWNERS);
 
+const_str!(RIOT_API_KEY);
+
+const_str!(HEARTBEAT_URL);
+const_str!(HEARTBEAT_INTERVAL);
+
+const_str!(TOPGG_TOKEN);
+const_str!(DISCORD_BOTS_GG_TOKEN);
+const_str!(DISCORDBOTLIST_TOKEN);
+const_str!(BOT_LIST_INTERVAL);
+
+const_str!(CONFESSION_KEY);
+
+const_str!(AI_API_URL);
+const_str!(AI_API_KEY);
+const_str!(AI_MODEL);
+
+const_str!(DEEPL_API_KEY);
+const_str!(LIBRETRANSLATE_URL);
+const_str!(LIBRETRANSLATE_API_KEY);
+
+const_str!(JELLYFIN_URL);
+const_str!(JELLYFIN_API_KEY);
+const_str!(PLEX_URL);
+const_str!(PLEX_TOKEN);
+
+const_str!(CURSEFORGE_API_KEY);
+
 const_str!(DATABASE_URL);
+const_str!(AUTO_MIGRATE);
 
 pub fn env_var_with_context<K: AsRef<std::ffi::OsStr> + std::fmt::Display>(
     key: K,
@@ -29,6 +59,66 @@ pub fn env_var_with_context<K: AsRef<std::ffi::OsStr> + std::fmt::Display>(
     var(&key).context(format!("Failed to load environment variable {}", key))
 }
 
+/// Whether pending database migrations are applied at startup. Defaults to true.
+pub fn auto_migrate_enabled() -> bool {
+    var(AUTO_MIGRATE)
+        .map(|x| !matches!(x.trim().to_lowercase().as_str(), "false" | "0" | "no"))
+        .unwrap_or(true)
+}
+
+/// Whether logs start out redacted, before the setting stored in the database is loaded. Defaults to false.
+pub fn log_privacy_enabled() -> bool {
+    var(LOG_PRIVACY)
+        .map(|x| matches!(x.trim().to_lowercase().as_str(), "true" | "1" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Url heartbeats are posted to, or None if heartbeats are disabled.
+pub fn heartbeat_url() -> Option<String> {
+    var(HEARTBEAT_URL)
+        .ok()
+        .map(|x| x.trim().to_string())
+        .filter(|x| !x.is_empty())
+}
+
+/// Interval between heartbeats, in seconds. Defaults to 60 seconds.
+pub fn heartbeat_interval() -> Duration {
+    var(HEARTBEAT_INTERVAL)
+        .ok()
+        .and_then(|x| x.trim().parse::<u64>().ok())
+        .filter(|x| *x > 0)
+        .map(Duration::from_secs)
+        .unwrap_or(Duration::from_secs(60))
+}
+
+/// Interval between posts of stats to bot lists, in seconds. Defaults to 30 minutes.
+pub fn bot_list_interval() -> Duration {
+    var(BOT_LIST_INTERVAL)
+        .ok()
+        .and_then(|x| x.trim().parse::<u64>().ok())
+        .filter(|x| *x > 0)
+        .map(Duration::from_secs)
+        .unwrap_or(Duration::from_secs(60 * 30))
+}
+
+/// Chat completions endpoint of the language model used by the `ai` feature. Defaults to the OpenAI API.
+pub fn ai_api_url() -> String {
+    var(AI_API_URL)
+        .ok()
+        .map(|x| x.trim().to_string())
+        .filter(|x| !x.is_empty())
+        .unwrap_or("https://api.openai.com/v1/chat/completions".to_string())
+}
+
+/// Model requested from the language model endpoint. Defaults to `gpt-4o-mini`.
+pub fn ai_model() -> String {
+    var(AI_MODEL)
+        .ok()
+        .map(|x| x.trim().to_string())
+        .filter(|x| !x.is_empty())
+        .unwrap_or("gpt-4o-mini".to_string())
+}
+
 pub fn get_data_directory() -> PathBuf {
     let st: String = var(DATA_DIRECTORY).unwrap_or_else(|_| "./data".to_string());
     Path::new(st.as_str()).to_owned()
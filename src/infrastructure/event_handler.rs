@@ -1,15 +1,27 @@
 use std::time::Duration;
 
-use poise::serenity_prelude::{Context, FullEvent};
+use poise::serenity_prelude::{Context, FullEvent, Interaction};
 use tracing::{debug, info, warn};
 
 use crate::{
     Error,
     events::{
-        guild_member::{guild_member_add, guild_member_remove},
+        auto_slowmode, ban_sync, channel_activity,
+        emoji_stats::{track_message_emojis, track_reaction_emoji},
+        gallery,
+        guild_member::{self, guild_member_add, guild_member_remove},
+        interaction::on_component_interaction,
         message::on_message,
+        name_history, nickname_filter, reaction_map, snipe, translation, voice_activity,
+        voice_announcements,
     },
-    infrastructure::botdata::Data,
+    infrastructure::{
+        botdata::Data,
+        latency::{LatencyKind, gateway_latency, record_latency},
+        mod_digest::record_activity,
+        modules,
+    },
+    services::mod_digest::ModActivity,
 };
 
 pub async fn event_handler(
@@ -23,16 +35,84 @@ pub async fn event_handler(
             info!("Bot is ready. Logged in as {}", data_about_bot.user.name);
         }
         FullEvent::Message { new_message } => {
+            if let Err(e) = track_message_emojis(data, new_message).await {
+                warn!("Emoji stats message handler produced an error: {:?}", e);
+            }
+            if let Err(e) = gallery::on_message(ctx, data, new_message).await {
+                warn!("Gallery message handler produced an error: {:?}", e);
+            }
+            if let Err(e) = snipe::on_message(data, new_message).await {
+                warn!("Snipe message handler produced an error: {:?}", e);
+            }
+            if let Err(e) = channel_activity::on_message(data, new_message).await {
+                warn!(
+                    "Channel activity message handler produced an error: {:?}",
+                    e
+                );
+            }
+            if let Err(e) = auto_slowmode::on_message(ctx, data, new_message).await {
+                warn!(
+                    "Adaptive slowmode message handler produced an error: {:?}",
+                    e
+                );
+            }
             let result = on_message(ctx, framework, data, new_message).await;
             if let Err(e) = result {
                 warn!("Message handler produced an error: {:?}", e);
             }
         }
+        FullEvent::MessageDelete {
+            channel_id,
+            deleted_message_id,
+            ..
+        } => {
+            if let Err(e) = snipe::on_message_delete(data, *channel_id, *deleted_message_id) {
+                warn!("Snipe delete handler produced an error: {:?}", e);
+            }
+        }
+        FullEvent::MessageUpdate { event, .. } => {
+            if let Err(e) = snipe::on_message_update(data, event) {
+                warn!("Snipe edit handler produced an error: {:?}", e);
+            }
+        }
+        FullEvent::ReactionAdd { add_reaction } => {
+            let result = track_reaction_emoji(ctx, data, add_reaction).await;
+            if let Err(e) = result {
+                warn!("Emoji stats reaction handler produced an error: {:?}", e);
+            }
+            let result = translation::on_reaction_add(ctx, data, add_reaction).await;
+            if let Err(e) = result {
+                warn!("Translation reaction handler produced an error: {:?}", e);
+            }
+        }
         FullEvent::GuildMemberAddition { new_member } => {
             let result = guild_member_add(ctx, data, new_member).await;
             if let Err(e) = result {
                 warn!("Guild member added handler produced an error: {:?}", e);
             }
+            let result = nickname_filter::on_member_join(ctx, data, new_member).await;
+            if let Err(e) = result {
+                warn!("Nickname filter join handler produced an error: {:?}", e);
+            }
+        }
+        FullEvent::GuildMemberUpdate {
+            old_if_available,
+            event,
+            ..
+        } => {
+            let result =
+                name_history::on_member_update(data, old_if_available.as_ref(), event).await;
+            if let Err(e) = result {
+                warn!("Name history update handler produced an error: {:?}", e);
+            }
+            let result = nickname_filter::on_member_update(ctx, data, event).await;
+            if let Err(e) = result {
+                warn!("Nickname filter update handler produced an error: {:?}", e);
+            }
+            let result = guild_member::on_member_update(ctx, data, event).await;
+            if let Err(e) = result {
+                warn!("Guild member update handler produced an error: {:?}", e);
+            }
         }
         FullEvent::GuildMemberRemoval {
             guild_id,
@@ -44,31 +124,92 @@ pub async fn event_handler(
                 warn!("Guild member removed handler produced an error: {:?}", e);
             }
         }
+        FullEvent::GuildBanAddition {
+            guild_id,
+            banned_user,
+        } => {
+            record_activity(
+                &data.db_pool,
+                data.clock.as_ref(),
+                *guild_id,
+                ModActivity::Ban,
+            )
+            .await;
+            let result = ban_sync::on_ban_add(ctx, data, guild_id, banned_user).await;
+            if let Err(e) = result {
+                warn!("Ban sync handler produced an error: {:?}", e);
+            }
+        }
+        FullEvent::GuildBanRemoval { guild_id, .. } => {
+            record_activity(
+                &data.db_pool,
+                data.clock.as_ref(),
+                *guild_id,
+                ModActivity::Unban,
+            )
+            .await;
+        }
+        FullEvent::GuildEmojisUpdate {
+            guild_id,
+            current_state,
+        } => {
+            if let Err(e) = reaction_map::on_emojis_update(data, *guild_id, current_state) {
+                warn!(
+                    "Reaction map emoji update handler produced an error: {:?}",
+                    e
+                );
+            }
+        }
+        FullEvent::GuildCreate { guild, .. } => {
+            if let Err(e) = reaction_map::on_guild_available(data, guild) {
+                warn!("Reaction map guild handler produced an error: {:?}", e);
+            }
+            let result = voice_activity::on_guild_available(ctx, data, guild).await;
+            if let Err(e) = result {
+                warn!("Voice activity guild handler produced an error: {:?}", e);
+            }
+        }
+        FullEvent::GuildDelete { incomplete, .. } => {
+            if let Err(e) = reaction_map::on_guild_delete(data, incomplete) {
+                warn!(
+                    "Reaction map guild removal handler produced an error: {:?}",
+                    e
+                );
+            }
+        }
+        FullEvent::VoiceStateUpdate { old, new } => {
+            let result = voice_activity::on_voice_state_update(ctx, data, new).await;
+            if let Err(e) = result {
+                warn!("Voice activity handler produced an error: {:?}", e);
+            }
+            let result =
+                voice_announcements::on_voice_state_update(ctx, data, old.as_ref(), new).await;
+            if let Err(e) = result {
+                warn!("Voice announcement handler produced an error: {:?}", e);
+            }
+        }
         FullEvent::InteractionCreate { interaction } => {
-            let ping = match framework
-                .shard_manager
-                .runners
-                .lock()
+            let ping = gateway_latency(framework.shard_manager, ctx.shard_id)
                 .await
-                .get(&ctx.shard_id)
-            {
-                Some(runner) => runner.latency.unwrap_or(std::time::Duration::ZERO),
-                None => {
-                    tracing::error!(
-                        "current shard is not in shard_manager.runners, this shouldn't happen"
-                    );
-                    std::time::Duration::ZERO
-                }
-            };
+                .unwrap_or(Duration::ZERO);
             if ping > Duration::default() {
                 debug!(
                     "Ping measured for interaction type {:?}: {:?} ",
                     interaction.kind(),
                     ping
-                )
+                );
+                record_latency(&data.latency, LatencyKind::Gateway, ping);
+            }
+
+            if let Interaction::Component(component) = interaction {
+                let result = on_component_interaction(ctx, data, component).await;
+                if let Err(e) = result {
+                    warn!("Component interaction handler produced an error: {:?}", e);
+                }
             }
         }
         _ => {}
     }
+    modules::registry().on_event(ctx, event, data).await;
     Ok(())
 }
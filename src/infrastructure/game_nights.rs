@@ -0,0 +1,461 @@
+/*!
+
+The game nights of `/gamenight`, from their RSVP message to their temporary voice lobby.
+
+Scheduling a game night creates a Discord scheduled event and posts an RSVP message with buttons to join or leave it,
+pinging the game night role. Three one-shot jobs then run it, all scheduled with the id of the game night as payload:
+
+- [`GameNightReminderJob`] pings the members who RSVPed a few minutes before the start
+- [`GameNightStartJob`] creates the voice lobby in the configured category and starts the scheduled event
+- [`GameNightCleanupJob`] deletes the lobby some hours later, ends the event and forgets the game night
+
+Jobs of a game night that was cancelled find nothing to do and succeed.
+
+*/
+
+use poise::serenity_prelude::{
+    self as serenity, ButtonStyle, ChannelType, CreateActionRow, CreateAllowedMentions,
+    CreateButton, CreateChannel, CreateEmbed, CreateEmbedFooter, CreateInteractionResponse,
+    CreateInteractionResponseMessage, CreateMessage, EditMessage, EditScheduledEvent, Http,
+    Mentionable, ScheduledEventStatus, UserId, async_trait,
+};
+use sea_orm::DatabaseConnection;
+use tracing::{debug, info, warn};
+
+use crate::{
+    Error,
+    infrastructure::{
+        botdata::Data,
+        branding::{Branding, get_branding, load_branding},
+        jobs::{JobContext, JobHandler, cancel_job, schedule_job},
+        timestamps::TimestampStyle,
+    },
+    services::game_nights::{GameNight, GameNightRepo, GameNightSettings},
+};
+
+/// Prefix of the custom ids of the RSVP buttons, followed by `join:<id>` or `leave:<id>`.
+pub const GAME_NIGHT_ID_PREFIX: &str = "gamenight:";
+
+const REMINDER_JOB_KIND: &str = "game_night_reminder";
+const START_JOB_KIND: &str = "game_night_start";
+const CLEANUP_JOB_KIND: &str = "game_night_cleanup";
+
+/// Most attendees listed on the RSVP message.
+const MAX_LISTED_ATTENDEES: usize = 30;
+
+/// Discord limits channel and event names to 100 characters.
+const MAX_NAME_LENGTH: usize = 100;
+
+fn job_name(kind: &str, id: i32) -> String {
+    format!("{}:{}", kind, id)
+}
+
+fn truncate_name(name: String) -> String {
+    if name.chars().count() <= MAX_NAME_LENGTH {
+        return name;
+    }
+    name.chars()
+        .take(MAX_NAME_LENGTH - 1)
+        .chain(['…'])
+        .collect()
+}
+
+/// Name of the scheduled event and of the lobby of a game night.
+pub fn event_name(game: &str) -> String {
+    truncate_name(format!("🎮 {}", game))
+}
+
+/// Schedules the reminder and the start of a game night. The reminder is skipped when it would already be due.
+pub async fn schedule_game_night_jobs(
+    db: &DatabaseConnection,
+    night: &GameNight,
+    settings: &GameNightSettings,
+    now: i64,
+) -> Result<(), Error> {
+    let payload = night.id.to_string();
+    let remind_at = night.starts_at - settings.reminder_minutes as i64 * 60;
+    if settings.reminder_minutes > 0 && remind_at > now {
+        schedule_job(
+            db,
+            &job_name(REMINDER_JOB_KIND, night.id),
+            REMINDER_JOB_KIND,
+            remind_at,
+            &payload,
+        )
+        .await?;
+    }
+    schedule_job(
+        db,
+        &job_name(START_JOB_KIND, night.id),
+        START_JOB_KIND,
+        night.starts_at,
+        &payload,
+    )
+    .await
+}
+
+/// Cancels the pending jobs of a game night.
+pub async fn cancel_game_night_jobs(db: &DatabaseConnection, id: i32) -> Result<(), Error> {
+    for kind in [REMINDER_JOB_KIND, START_JOB_KIND, CLEANUP_JOB_KIND] {
+        cancel_job(db, &job_name(kind, id)).await?;
+    }
+    Ok(())
+}
+
+/// Mentions of the members going to a game night, separated by spaces.
+fn mention_list(attendees: &[UserId]) -> String {
+    attendees
+        .iter()
+        .map(|x| x.mention().to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn rsvp_embed(branding: &Branding, night: &GameNight, attendees: &[UserId]) -> CreateEmbed {
+    let mut going = attendees
+        .iter()
+        .take(MAX_LISTED_ATTENDEES)
+        .map(|x| x.mention().to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    if attendees.len() > MAX_LISTED_ATTENDEES {
+        going.push_str(&format!(
+            " and {} more",
+            attendees.len() - MAX_LISTED_ATTENDEES
+        ));
+    }
+    if going.is_empty() {
+        going = "Nobody yet, be the first!".to_string();
+    }
+    let lobby = match night.voice_channel_id {
+        Some(channel_id) => format!("Open: {}", channel_id.mention()),
+        None => "A voice channel opens when the game night starts.".to_string(),
+    };
+    branding
+        .embed()
+        .title(format!("Game night: {}", night.game))
+        .description(format!(
+            "{} is hosting a game night of **{}**. Let everyone know if you're in!",
+            night.host_id.mention(),
+            night.game
+        ))
+        .field(
+            "Starts",
+            format!(
+                "{} ({})",
+                TimestampStyle::LongDateTime.markup(night.starts_at),
+                TimestampStyle::Relative.markup(night.starts_at)
+            ),
+            false,
+        )
+        .field("Lobby", lobby, false)
+        .field(format!("Going ({})", attendees.len()), going, false)
+        .footer(CreateEmbedFooter::new(format!("Game night #{}", night.id)))
+}
+
+pub fn rsvp_buttons(id: i32) -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("{}join:{}", GAME_NIGHT_ID_PREFIX, id))
+            .label("I'm in")
+            .emoji('✅')
+            .style(ButtonStyle::Success),
+        CreateButton::new(format!("{}leave:{}", GAME_NIGHT_ID_PREFIX, id))
+            .label("Can't make it")
+            .style(ButtonStyle::Secondary),
+    ])]
+}
+
+/// Updates the RSVP message of a game night, without its buttons once the game night is over.
+async fn refresh_rsvp_message(
+    http: &Http,
+    db: &DatabaseConnection,
+    night: &GameNight,
+    closed: bool,
+) -> Result<(), Error> {
+    let Some(message_id) = night.message_id else {
+        return Ok(());
+    };
+    let attendees = GameNightRepo::new(db).attendees(night.id).await?;
+    let branding = load_branding(db, night.guild_id).await.unwrap_or_default();
+    let components = if closed {
+        vec![]
+    } else {
+        rsvp_buttons(night.id)
+    };
+    night
+        .channel_id
+        .edit_message(
+            http,
+            message_id,
+            EditMessage::new()
+                .embed(rsvp_embed(&branding, night, &attendees))
+                .components(components),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Ends a game night: closes its RSVP message, deletes its lobby, ends or deletes its scheduled event and forgets it.
+/// Failures on Discord's side are logged, since the channels and the event may have been deleted by hand.
+pub async fn close_game_night(
+    http: &Http,
+    db: &DatabaseConnection,
+    night: &GameNight,
+) -> Result<(), Error> {
+    if let Err(e) = refresh_rsvp_message(http, db, night, true).await {
+        warn!(
+            "Failed to close the RSVP message of game night {}: {:?}",
+            night.id, e
+        );
+    }
+    if let Some(channel_id) = night.voice_channel_id
+        && let Err(e) = channel_id.delete(http).await
+    {
+        warn!(
+            "Failed to delete the lobby of game night {}: {:?}",
+            night.id, e
+        );
+    }
+    if let Some(event_id) = night.event_id {
+        // Events that started are completed, the others can only be deleted
+        let result = if night.voice_channel_id.is_some() {
+            night
+                .guild_id
+                .edit_scheduled_event(
+                    http,
+                    event_id,
+                    EditScheduledEvent::new().status(ScheduledEventStatus::Completed),
+                )
+                .await
+                .map(|_| ())
+        } else {
+            night.guild_id.delete_scheduled_event(http, event_id).await
+        };
+        if let Err(e) = result {
+            warn!(
+                "Failed to end the scheduled event of game night {}: {:?}",
+                night.id, e
+            );
+        }
+    }
+    cancel_game_night_jobs(db, night.id).await?;
+    GameNightRepo::new(db).remove(night.id).await?;
+    Ok(())
+}
+
+async fn respond_ephemeral(
+    ctx: &serenity::Context,
+    interaction: &serenity::ComponentInteraction,
+    content: &str,
+) -> Result<(), Error> {
+    interaction
+        .create_response(
+            ctx,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .ephemeral(true),
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Joins or leaves a game night from the buttons of its RSVP message. `action` is the custom id without
+/// [`GAME_NIGHT_ID_PREFIX`].
+pub async fn handle_interaction(
+    ctx: &serenity::Context,
+    data: &Data,
+    interaction: &serenity::ComponentInteraction,
+    action: &str,
+) -> Result<(), Error> {
+    let (going, id) = match action.split_once(':') {
+        Some(("join", id)) => (true, id.parse::<i32>()?),
+        Some(("leave", id)) => (false, id.parse::<i32>()?),
+        _ => return Err(format!("Unknown game night action '{}'", action).into()),
+    };
+    let db = &data.db_pool;
+    let repo = GameNightRepo::new(db);
+    let night = match repo.get(id).await? {
+        Some(x) if Some(x.guild_id) == interaction.guild_id => x,
+        _ => return respond_ephemeral(ctx, interaction, "This game night is over").await,
+    };
+
+    let changed = repo
+        .set_rsvp(id, interaction.user.id, going, data.clock.unix_timestamp())
+        .await?;
+    if !changed {
+        let content = if going {
+            "You're already going to this game night"
+        } else {
+            "You weren't going to this game night"
+        };
+        return respond_ephemeral(ctx, interaction, content).await;
+    }
+    debug!(
+        "{} RSVPed {} to game night {}",
+        interaction.user.id,
+        if going { "yes" } else { "no" },
+        id
+    );
+
+    let attendees = repo.attendees(id).await?;
+    let branding = get_branding(db, &data.branding, night.guild_id).await;
+    interaction
+        .create_response(
+            ctx,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .embed(rsvp_embed(&branding, &night, &attendees))
+                    .components(rsvp_buttons(id)),
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Reads the game night a job was scheduled for. None if it was cancelled since.
+async fn job_game_night(ctx: &JobContext, payload: &str) -> Result<Option<GameNight>, Error> {
+    let night = GameNightRepo::new(&ctx.db).get(payload.parse()?).await?;
+    if night.is_none() {
+        debug!("Game night {} no longer exists", payload);
+    }
+    Ok(night)
+}
+
+/// Pings the members going to a game night shortly before it starts.
+pub struct GameNightReminderJob;
+
+#[async_trait]
+impl JobHandler for GameNightReminderJob {
+    fn kind(&self) -> &'static str {
+        REMINDER_JOB_KIND
+    }
+
+    async fn run(&self, ctx: &JobContext, payload: &str) -> Result<(), Error> {
+        let Some(night) = job_game_night(ctx, payload).await? else {
+            return Ok(());
+        };
+        let attendees = GameNightRepo::new(&ctx.db).attendees(night.id).await?;
+        if attendees.is_empty() {
+            debug!("Nobody to remind of game night {}", night.id);
+            return Ok(());
+        }
+        let mut message = CreateMessage::new()
+            .content(format!(
+                "⏰ **{}** starts {}! {}",
+                night.game,
+                TimestampStyle::Relative.markup(night.starts_at),
+                mention_list(&attendees)
+            ))
+            .allowed_mentions(CreateAllowedMentions::new().users(attendees));
+        if let Some(message_id) = night.message_id {
+            message = message.reference_message((night.channel_id, message_id));
+        }
+        night.channel_id.send_message(&ctx.http, message).await?;
+        Ok(())
+    }
+}
+
+/// Opens the voice lobby of a game night when it starts, and schedules its cleanup.
+pub struct GameNightStartJob;
+
+#[async_trait]
+impl JobHandler for GameNightStartJob {
+    fn kind(&self) -> &'static str {
+        START_JOB_KIND
+    }
+
+    async fn run(&self, ctx: &JobContext, payload: &str) -> Result<(), Error> {
+        let Some(mut night) = job_game_night(ctx, payload).await? else {
+            return Ok(());
+        };
+        let repo = GameNightRepo::new(&ctx.db);
+        let settings = repo.settings(night.guild_id).await?;
+
+        // A retry after a later failure reuses the lobby instead of opening a second one
+        let lobby = match night.voice_channel_id {
+            Some(channel_id) => channel_id,
+            None => {
+                let mut builder =
+                    CreateChannel::new(event_name(&night.game)).kind(ChannelType::Voice);
+                if let Some(category_id) = settings.category_id {
+                    builder = builder.category(category_id);
+                }
+                let channel = night.guild_id.create_channel(&ctx.http, builder).await?;
+                repo.set_voice_channel(night.id, channel.id).await?;
+                info!("Opened lobby {} for game night {}", channel.id, night.id);
+                night.voice_channel_id = Some(channel.id);
+                channel.id
+            }
+        };
+        schedule_job(
+            &ctx.db,
+            &job_name(CLEANUP_JOB_KIND, night.id),
+            CLEANUP_JOB_KIND,
+            night.starts_at.max(ctx.clock.unix_timestamp()) + settings.lobby_hours as i64 * 60 * 60,
+            payload,
+        )
+        .await?;
+
+        if let Some(event_id) = night.event_id
+            && let Err(e) = night
+                .guild_id
+                .edit_scheduled_event(
+                    &ctx.http,
+                    event_id,
+                    EditScheduledEvent::new()
+                        .status(ScheduledEventStatus::Active)
+                        .location(format!("#{}", event_name(&night.game))),
+                )
+                .await
+        {
+            warn!(
+                "Failed to start the scheduled event of game night {}: {:?}",
+                night.id, e
+            );
+        }
+        if let Err(e) = refresh_rsvp_message(&ctx.http, &ctx.db, &night, false).await {
+            warn!(
+                "Failed to update the RSVP message of game night {}: {:?}",
+                night.id, e
+            );
+        }
+
+        let attendees = repo.attendees(night.id).await?;
+        night
+            .channel_id
+            .send_message(
+                &ctx.http,
+                CreateMessage::new()
+                    .content(format!(
+                        "🎮 The lobby of **{}** is open, join {}! {}",
+                        night.game,
+                        lobby.mention(),
+                        mention_list(&attendees)
+                    ))
+                    .allowed_mentions(CreateAllowedMentions::new().users(attendees)),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+/// Deletes the voice lobby of a game night once it is over, and forgets the game night.
+pub struct GameNightCleanupJob;
+
+#[async_trait]
+impl JobHandler for GameNightCleanupJob {
+    fn kind(&self) -> &'static str {
+        CLEANUP_JOB_KIND
+    }
+
+    async fn run(&self, ctx: &JobContext, payload: &str) -> Result<(), Error> {
+        let Some(night) = job_game_night(ctx, payload).await? else {
+            return Ok(());
+        };
+        close_game_night(&ctx.http, &ctx.db, &night).await?;
+        info!("Closed game night {}", night.id);
+        Ok(())
+    }
+}
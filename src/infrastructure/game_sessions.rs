@@ -0,0 +1,135 @@
+/*!
+
+Registry of the channel games started with `/game`.
+
+A channel runs at most one game at a time. Sessions are kept in [`Data`](super::botdata::Data) for the message
+handler in [`games`](crate::events::games), and persisted in the `game_sessions` table after every change so running
+games are restored when the bot restarts.
+
+*/
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use migration::OnConflict;
+use poise::serenity_prelude::{ChannelId, GuildId, UserId};
+use sea_orm::{ActiveValue::Set, DatabaseConnection, EntityTrait};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    Error,
+    entities::game_sessions,
+    infrastructure::ids::{id_from_string, id_to_string},
+};
+
+pub type GameSessions = Arc<RwLock<HashMap<ChannelId, GameSession>>>;
+
+/// State of a game, stored as JSON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "game", rename_all = "snake_case")]
+pub enum Game {
+    /// The channel guesses a number between 1 and `max`, with hints whether the number is higher or lower.
+    NumberGuess { target: u64, max: u64, guesses: u32 },
+    /// The channel guesses the letters of a word before running out of lives.
+    Hangman {
+        word: String,
+        guessed: Vec<char>,
+        wrong_guesses: u32,
+    },
+}
+
+impl Game {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Game::NumberGuess { .. } => "number guess",
+            Game::Hangman { .. } => "hangman",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameSession {
+    pub guild_id: GuildId,
+    pub started_by: UserId,
+    pub started_at: i64,
+    pub game: Game,
+}
+
+fn from_model(model: game_sessions::Model) -> Result<(ChannelId, GameSession), Error> {
+    Ok((
+        id_from_string(&model.channel_id)?,
+        GameSession {
+            guild_id: id_from_string(&model.guild_id)?,
+            started_by: id_from_string(&model.started_by)?,
+            started_at: model.started_at,
+            game: serde_json::from_str(&model.state)?,
+        },
+    ))
+}
+
+/// Loads the games that were running when the bot stopped. Sessions that can't be read are skipped.
+pub async fn load_game_sessions(
+    db: &DatabaseConnection,
+) -> Result<HashMap<ChannelId, GameSession>, Error> {
+    Ok(game_sessions::Entity::find()
+        .all(db)
+        .await?
+        .into_iter()
+        .filter_map(|model| {
+            let channel_id = model.channel_id.clone();
+            from_model(model)
+                .inspect_err(|e| warn!("Skipping game session of channel {}: {:?}", channel_id, e))
+                .ok()
+        })
+        .collect())
+}
+
+/// Gets a copy of the game running in a channel.
+pub fn get_game_session(sessions: &GameSessions, channel_id: ChannelId) -> Option<GameSession> {
+    sessions
+        .read()
+        .ok()
+        .and_then(|x| x.get(&channel_id).cloned())
+}
+
+/// Stores the state of a game after it was started or changed.
+pub async fn save_game_session(
+    db: &DatabaseConnection,
+    channel_id: ChannelId,
+    session: &GameSession,
+) -> Result<(), Error> {
+    game_sessions::Entity::insert(game_sessions::ActiveModel {
+        channel_id: Set(id_to_string(channel_id)),
+        guild_id: Set(id_to_string(session.guild_id)),
+        started_by: Set(id_to_string(session.started_by)),
+        state: Set(serde_json::to_string(&session.game)?),
+        started_at: Set(session.started_at),
+    })
+    .on_conflict(
+        OnConflict::column(game_sessions::Column::ChannelId)
+            .update_columns([
+                game_sessions::Column::GuildId,
+                game_sessions::Column::StartedBy,
+                game_sessions::Column::State,
+                game_sessions::Column::StartedAt,
+            ])
+            .to_owned(),
+    )
+    .exec(db)
+    .await?;
+    Ok(())
+}
+
+/// Deletes the stored state of a game after it ended.
+pub async fn delete_game_session(
+    db: &DatabaseConnection,
+    channel_id: ChannelId,
+) -> Result<(), Error> {
+    game_sessions::Entity::delete_by_id(id_to_string(channel_id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
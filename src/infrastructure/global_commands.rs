@@ -0,0 +1,122 @@
+/*!
+
+Commands disabled on every guild by the bot owners with `/global-commands`, stored in the `bot_settings` table.
+
+Disabled commands are rejected by the global command check and left out when the application commands are registered
+again, so they disappear from the command list of clients without restarting the bot. Commands can still be removed
+entirely at startup with the `COMMAND_DISABLE_LIST` environment variable.
+
+*/
+
+use std::{collections::BTreeSet, sync::RwLock};
+
+use once_cell::sync::Lazy;
+use poise::serenity_prelude::{self as serenity, CacheHttp};
+use sea_orm::DatabaseConnection;
+use tracing::{debug, info};
+
+use crate::{
+    Context, Error, infrastructure::botdata::Data, services::bot_settings::BotSettingsRepo,
+};
+
+/// Name of the setting in the `bot_settings` table, holding the comma separated names of the disabled commands.
+const DISABLED_COMMANDS_SETTING: &str = "disabled_commands";
+
+/// Commands that can't be disabled, so disabled commands can always be enabled again.
+pub const PROTECTED_COMMANDS: [&str; 2] = ["global-commands", "register"];
+
+static DISABLED_COMMANDS: Lazy<RwLock<BTreeSet<String>>> = Lazy::new(Default::default);
+
+fn parse_disabled_commands(value: &str) -> BTreeSet<String> {
+    value
+        .split(',')
+        .map(|x| x.trim().to_lowercase())
+        .filter(|x| !x.is_empty())
+        .collect()
+}
+
+/// Gets the names of the commands disabled on every guild.
+pub fn disabled_commands() -> BTreeSet<String> {
+    match DISABLED_COMMANDS.read() {
+        Ok(x) => x.clone(),
+        Err(e) => e.into_inner().clone(),
+    }
+}
+
+pub fn is_command_disabled(name: &str) -> bool {
+    match DISABLED_COMMANDS.read() {
+        Ok(x) => x.contains(&name.to_lowercase()),
+        Err(e) => e.into_inner().contains(&name.to_lowercase()),
+    }
+}
+
+fn replace_disabled_commands(commands: BTreeSet<String>) {
+    match DISABLED_COMMANDS.write() {
+        Ok(mut x) => *x = commands,
+        Err(e) => *e.into_inner() = commands,
+    }
+}
+
+/// Loads the disabled commands stored in the database.
+pub async fn load_disabled_commands(db: &DatabaseConnection) -> Result<(), Error> {
+    let value = BotSettingsRepo::new(db)
+        .get(DISABLED_COMMANDS_SETTING)
+        .await?
+        .unwrap_or_default();
+    let commands = parse_disabled_commands(&value);
+    if !commands.is_empty() {
+        info!("Globally disabled commands: {:?}", commands);
+    }
+    replace_disabled_commands(commands);
+    Ok(())
+}
+
+/// Disables or enables a top level command on every guild, returning whether it changed.
+pub async fn set_command_disabled(
+    db: &DatabaseConnection,
+    name: &str,
+    disabled: bool,
+) -> Result<bool, Error> {
+    let name = name.to_lowercase();
+    let mut commands = disabled_commands();
+    let changed = if disabled {
+        commands.insert(name)
+    } else {
+        commands.remove(&name)
+    };
+    if changed {
+        let value = commands.iter().cloned().collect::<Vec<String>>().join(",");
+        BotSettingsRepo::new(db)
+            .set(DISABLED_COMMANDS_SETTING, &value)
+            .await?;
+        replace_disabled_commands(commands);
+    }
+    Ok(changed)
+}
+
+/// Global command check rejecting commands disabled on every guild, including their subcommands.
+pub async fn disabled_command_check(ctx: Context<'_>) -> Result<bool, Error> {
+    let name = ctx.command().qualified_name.as_str();
+    let root = name.split(' ').next().unwrap_or(name);
+    if is_command_disabled(root) {
+        debug!("Rejecting globally disabled command {}", root);
+        return Err(format!("`{}` is disabled by the bot owners", root).into());
+    }
+    Ok(true)
+}
+
+/// Registers the application commands of the bot globally, leaving out the disabled commands. Returns the number of
+/// commands registered.
+pub async fn register_enabled_commands(
+    http: impl CacheHttp,
+    commands: &[poise::Command<Data, Error>],
+) -> Result<usize, Error> {
+    let mut create_commands = vec![];
+    for command in commands.iter().filter(|x| !is_command_disabled(&x.name)) {
+        create_commands.extend(command.create_as_slash_command());
+        create_commands.extend(command.create_as_context_menu_command());
+    }
+    let count = create_commands.len();
+    serenity::Command::set_global_commands(http.http(), create_commands).await?;
+    Ok(count)
+}
@@ -0,0 +1,114 @@
+/*!
+
+Images of `/roll` and `/coinflip`, which guilds can replace with their own uploads through `/custom-media`.
+
+Each image has a key named after its bundled file, such as `d6-3` for `d6/d6-3.png` in the media directory. A guild's
+uploads are stored as user content (see [`crate::infrastructure::user_content`]) and mapped to the key they replace in
+the `guild_media` table. Images a guild didn't replace, or whose upload went missing from disk, fall back to the
+bundled media.
+
+*/
+
+use std::path::PathBuf;
+
+use poise::serenity_prelude::{CreateAttachment, GuildId};
+use sea_orm::DatabaseConnection;
+use tracing::{trace, warn};
+
+use crate::{
+    infrastructure::{environment::get_media_directory, user_content::user_content_path},
+    services::guild_media::GuildMediaRepo,
+};
+
+/// Extensions of the files that can replace an image.
+pub const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "gif", "webp"];
+
+pub const COIN_HEADS: &str = "coin-heads";
+
+pub const COIN_TAILS: &str = "coin-tails";
+
+pub const COIN_EDGE: &str = "coin-edge";
+
+pub fn dice_media_key(dice: &str, side: u8) -> String {
+    format!("{}-{}", dice, side)
+}
+
+/// Whether a filename has the extension of an image that can replace a bundled one.
+pub fn is_image_filename(filename: &str) -> bool {
+    std::path::Path::new(filename)
+        .extension()
+        .and_then(|x| x.to_str())
+        .is_some_and(|x| IMAGE_EXTENSIONS.contains(&x.to_lowercase().as_str()))
+}
+
+/// Path of the bundled image of a key: `d6-3` is `d6/d6-3.png` and `coin-heads` is `coin/coin-heads.png`.
+pub fn bundled_media_path(key: &str) -> PathBuf {
+    let folder = key.split('-').next().unwrap_or(key);
+    get_media_directory()
+        .join(folder)
+        .join(format!("{}.png", key))
+}
+
+/// Gets the path of the upload replacing an image in a guild, if it has one and it is still on disk.
+async fn guild_media_path(
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+    key: &str,
+) -> Option<PathBuf> {
+    let filename = match GuildMediaRepo::new(db).filename(guild_id, key).await {
+        Ok(x) => x?,
+        Err(e) => {
+            warn!(
+                "Failed to load the media {} of guild {}, using the bundled media: {:?}",
+                key, guild_id, e
+            );
+            return None;
+        }
+    };
+    let path = user_content_path(guild_id, &filename);
+    if !tokio::fs::try_exists(&path).await.unwrap_or(false) {
+        warn!(
+            "The media {} of guild {} is missing from {}, using the bundled media",
+            key,
+            guild_id,
+            path.display()
+        );
+        return None;
+    }
+    Some(path)
+}
+
+/// Creates the attachment of an image, using the upload of the guild if it replaced it, otherwise the bundled media.
+/// Returns None if neither exists.
+pub async fn media_attachment(
+    db: &DatabaseConnection,
+    guild_id: Option<GuildId>,
+    key: &str,
+) -> Option<CreateAttachment> {
+    if let Some(guild_id) = guild_id
+        && let Some(path) = guild_media_path(db, guild_id, key).await
+    {
+        match CreateAttachment::path(&path).await {
+            Ok(mut attachment) => {
+                // Named after the key so the embeds can refer to it, whatever the stored file is called
+                let ext = path.extension().and_then(|x| x.to_str()).unwrap_or("png");
+                attachment.filename = format!("{}.{}", key, ext.to_lowercase());
+                trace!("Using the media {} of guild {}", key, guild_id);
+                return Some(attachment);
+            }
+            Err(e) => warn!(
+                "Failed to read the media {} of guild {}, using the bundled media: {:?}",
+                key, guild_id, e
+            ),
+        }
+    }
+
+    let path = bundled_media_path(key);
+    match CreateAttachment::path(&path).await {
+        Ok(x) => Some(x),
+        Err(e) => {
+            trace!("No bundled media at {}: {:?}", path.display(), e);
+            None
+        }
+    }
+}
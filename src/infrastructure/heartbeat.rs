@@ -0,0 +1,131 @@
+/*!
+
+Posts heartbeats to an external uptime monitor, such as a healthchecks.io check or an Uptime Kuma push monitor.
+
+Heartbeats are only sent when `HEARTBEAT_URL` is set, every `HEARTBEAT_INTERVAL` seconds. Each heartbeat is a POST of
+a json summary of the bot, with `status`, `msg` and `ping` query parameters for monitors that read those instead. The
+status is `up` while at least one shard is connected to the gateway, and `down` otherwise.
+Failed heartbeats are retried with an exponential backoff, so an unreachable monitor isn't flooded with requests.
+
+*/
+
+use std::{sync::Arc, time::Duration};
+
+use poise::serenity_prelude::{ConnectionStage, Context, ShardManager};
+use serde::Serialize;
+use tracing::{debug, info, warn};
+
+use crate::{
+    Error,
+    infrastructure::environment::{heartbeat_interval, heartbeat_url},
+};
+
+/// Longest wait between heartbeats after repeated failures.
+const MAX_BACKOFF: Duration = Duration::from_secs(60 * 15);
+
+/// Timeout of a single heartbeat request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Serialize)]
+struct ShardHeartbeat {
+    id: u32,
+    stage: String,
+    latency_ms: Option<u128>,
+}
+
+#[derive(Debug, Serialize)]
+struct Heartbeat {
+    status: &'static str,
+    guilds: usize,
+    latency_ms: Option<u128>,
+    shards: Vec<ShardHeartbeat>,
+}
+
+async fn collect_heartbeat(ctx: &Context, shard_manager: &ShardManager) -> Heartbeat {
+    let runners = shard_manager.runners.lock().await;
+    let connected = runners
+        .values()
+        .any(|x| x.stage == ConnectionStage::Connected);
+    let shards: Vec<ShardHeartbeat> = runners
+        .iter()
+        .map(|(id, runner)| ShardHeartbeat {
+            id: id.0,
+            stage: runner.stage.to_string(),
+            latency_ms: runner.latency.map(|x| x.as_millis()),
+        })
+        .collect();
+    drop(runners);
+    let latencies: Vec<u128> = shards.iter().filter_map(|x| x.latency_ms).collect();
+    Heartbeat {
+        status: if connected { "up" } else { "down" },
+        guilds: ctx.cache.guild_count(),
+        latency_ms: (!latencies.is_empty())
+            .then(|| latencies.iter().sum::<u128>() / latencies.len() as u128),
+        shards,
+    }
+}
+
+async fn send_heartbeat(
+    http: &reqwest::Client,
+    url: &str,
+    heartbeat: &Heartbeat,
+) -> Result<(), Error> {
+    let ping = heartbeat
+        .latency_ms
+        .map(|x| x.to_string())
+        .unwrap_or_default();
+    let msg = format!("{} guilds", heartbeat.guilds);
+    http.post(url)
+        .query(&[
+            ("status", heartbeat.status),
+            ("msg", msg.as_str()),
+            ("ping", ping.as_str()),
+        ])
+        .json(heartbeat)
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Time to wait before the next heartbeat after a number of consecutive failures.
+fn next_delay(interval: Duration, failures: u32) -> Duration {
+    if failures == 0 {
+        return interval;
+    }
+    interval
+        .saturating_mul(2u32.saturating_pow(failures.min(16)))
+        .min(MAX_BACKOFF.max(interval))
+}
+
+/// Runs the heartbeat reporter for the lifetime of the bot, if a heartbeat url is configured.
+pub fn spawn_heartbeat(ctx: Context, shard_manager: Arc<ShardManager>) {
+    let Some(url) = heartbeat_url() else {
+        debug!("HEARTBEAT_URL is not set, heartbeats are disabled");
+        return;
+    };
+    let interval = heartbeat_interval();
+    info!("Sending heartbeats every {:?}", interval);
+
+    tokio::spawn(async move {
+        let http = reqwest::Client::new();
+        let mut failures: u32 = 0;
+        loop {
+            let heartbeat = collect_heartbeat(&ctx, &shard_manager).await;
+            match send_heartbeat(&http, &url, &heartbeat).await {
+                Ok(()) => {
+                    if failures > 0 {
+                        info!("Heartbeat succeeded after {} failures", failures);
+                    }
+                    failures = 0;
+                }
+                Err(e) => {
+                    failures = failures.saturating_add(1);
+                    warn!("Failed to send heartbeat ({} in a row): {:?}", failures, e);
+                }
+            }
+            tokio::time::sleep(next_delay(interval, failures)).await;
+        }
+    });
+}
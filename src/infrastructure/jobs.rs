@@ -0,0 +1,403 @@
+/*!
+
+Background jobs, persisted so they survive restarts.
+
+Each subsystem that runs work in the background registers a [`JobHandler`] in [`job_handlers`]. A handler with a
+[`schedule`](JobHandler::schedule) gets a recurring job, run on its [cron schedule](super::cron), that is registered
+when the bot starts. Other jobs run once, and are scheduled on demand with [`schedule_job`].
+
+Jobs are stored in the `jobs` table and polled on a fixed interval. A failed run is retried with an exponential
+backoff and some jitter, until the handler's maximum number of attempts is reached: a recurring job then waits for its
+next scheduled run, while a one-shot job is dropped. The jobs and their last errors are shown by `/jobs list`.
+
+*/
+
+use std::{sync::Arc, time::Duration};
+
+use migration::OnConflict;
+use poise::serenity_prelude::{Http, async_trait};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder,
+};
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    Error,
+    entities::jobs,
+    infrastructure::{
+        asset_storage::AssetRefreshJob,
+        clock::{Clock, SharedClock},
+        cron::CronSchedule,
+        game_nights::{GameNightCleanupJob, GameNightReminderJob, GameNightStartJob},
+        leader::is_leader,
+        random::{RandomSource, SharedRandom},
+        retention::RetentionJob,
+        wordle::WordleLeaderboardJob,
+    },
+};
+
+/// Interval between checks for due jobs.
+pub const JOB_POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Time a single run may take before it is considered failed.
+const JOB_TIMEOUT: Duration = Duration::from_secs(60 * 10);
+
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+
+/// Delay before the first retry, doubled after every failed attempt.
+const BASE_BACKOFF_SECONDS: i64 = 30;
+
+const MAX_BACKOFF_SECONDS: i64 = 60 * 60;
+
+/// Longest error message stored for a job.
+const MAX_ERROR_LENGTH: usize = 500;
+
+/// Everything a job may need to run.
+#[derive(Clone)]
+pub struct JobContext {
+    pub http: Arc<Http>,
+    pub db: DatabaseConnection,
+    pub clock: SharedClock,
+}
+
+#[async_trait]
+pub trait JobHandler: Send + Sync {
+    /// Kind of the jobs run by the handler, stored with each job.
+    fn kind(&self) -> &'static str;
+
+    /// Cron schedule of the recurring job of this kind, registered when the bot starts. None for handlers whose jobs
+    /// are only scheduled with [`schedule_job`].
+    fn schedule(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Number of attempts of a run before it is given up.
+    fn max_attempts(&self) -> u32 {
+        DEFAULT_MAX_ATTEMPTS
+    }
+
+    /// Runs a job with the payload it was scheduled with.
+    async fn run(&self, ctx: &JobContext, payload: &str) -> Result<(), Error>;
+}
+
+/// All registered job handlers.
+pub fn job_handlers() -> Vec<Box<dyn JobHandler>> {
+    vec![
+        Box::new(RetentionJob),
+        Box::new(WordleLeaderboardJob),
+        Box::new(GameNightReminderJob),
+        Box::new(GameNightStartJob),
+        Box::new(GameNightCleanupJob),
+        Box::new(AssetRefreshJob),
+    ]
+}
+
+/// Delay before retrying a job that failed a number of times in a row.
+fn backoff_seconds(attempts: u32, random: &dyn RandomSource) -> i64 {
+    let delay = BASE_BACKOFF_SECONDS
+        .saturating_mul(1 << attempts.saturating_sub(1).min(16))
+        .min(MAX_BACKOFF_SECONDS);
+    delay + random.random_range(0..=(delay as u64 / 4)) as i64
+}
+
+/// Registers the recurring job of every handler with a schedule. Jobs whose schedule didn't change keep their next
+/// run, so restarting the bot doesn't delay or repeat them.
+pub async fn register_recurring_jobs(
+    db: &DatabaseConnection,
+    clock: &dyn Clock,
+    handlers: &[Box<dyn JobHandler>],
+) -> Result<(), Error> {
+    for handler in handlers {
+        let Some(schedule) = handler.schedule() else {
+            continue;
+        };
+        let next_run_at =
+            match CronSchedule::parse(schedule).map(|x| x.next_after(clock.unix_timestamp())) {
+                Ok(Some(x)) => x,
+                Ok(None) => {
+                    warn!("Schedule of job {} never runs", handler.kind());
+                    continue;
+                }
+                Err(e) => {
+                    warn!("Job {} has an invalid schedule: {}", handler.kind(), e);
+                    continue;
+                }
+            };
+        let existing = jobs::Entity::find_by_id(handler.kind()).one(db).await?;
+        if existing.is_some_and(|x| x.schedule == schedule) {
+            continue;
+        }
+        jobs::Entity::insert(jobs::ActiveModel {
+            name: Set(handler.kind().to_string()),
+            kind: Set(handler.kind().to_string()),
+            schedule: Set(schedule.to_string()),
+            payload: Set(String::new()),
+            next_run_at: Set(next_run_at),
+            attempts: Set(0),
+            last_run_at: Set(0),
+            last_error: Set(String::new()),
+        })
+        .on_conflict(
+            OnConflict::column(jobs::Column::Name)
+                .update_columns([
+                    jobs::Column::Kind,
+                    jobs::Column::Schedule,
+                    jobs::Column::NextRunAt,
+                    jobs::Column::Attempts,
+                ])
+                .to_owned(),
+        )
+        .exec(db)
+        .await?;
+        info!(
+            "Registered recurring job {} ({}), next run at {}",
+            handler.kind(),
+            schedule,
+            next_run_at
+        );
+    }
+    Ok(())
+}
+
+/// Schedules a job to run once at a unix timestamp, replacing any job with the same name. Names should be prefixed
+/// with the kind of the job, like `announcement:42`, so they don't collide with other subsystems.
+pub async fn schedule_job(
+    db: &DatabaseConnection,
+    name: &str,
+    kind: &str,
+    run_at: i64,
+    payload: &str,
+) -> Result<(), Error> {
+    jobs::Entity::insert(jobs::ActiveModel {
+        name: Set(name.to_string()),
+        kind: Set(kind.to_string()),
+        schedule: Set(String::new()),
+        payload: Set(payload.to_string()),
+        next_run_at: Set(run_at),
+        attempts: Set(0),
+        last_run_at: Set(0),
+        last_error: Set(String::new()),
+    })
+    .on_conflict(
+        OnConflict::column(jobs::Column::Name)
+            .update_columns([
+                jobs::Column::Kind,
+                jobs::Column::Schedule,
+                jobs::Column::Payload,
+                jobs::Column::NextRunAt,
+                jobs::Column::Attempts,
+                jobs::Column::LastError,
+            ])
+            .to_owned(),
+    )
+    .exec(db)
+    .await?;
+    Ok(())
+}
+
+/// Cancels a job. Returns false if there was no job with the name.
+pub async fn cancel_job(db: &DatabaseConnection, name: &str) -> Result<bool, Error> {
+    Ok(jobs::Entity::delete_by_id(name)
+        .exec(db)
+        .await?
+        .rows_affected
+        > 0)
+}
+
+/// All jobs, soonest first.
+pub async fn list_jobs(db: &DatabaseConnection) -> Result<Vec<jobs::Model>, Error> {
+    Ok(jobs::Entity::find()
+        .order_by_asc(jobs::Column::NextRunAt)
+        .all(db)
+        .await?)
+}
+
+async fn run_job(
+    ctx: &JobContext,
+    handler: &dyn JobHandler,
+    job: &jobs::Model,
+) -> Result<(), Error> {
+    match tokio::time::timeout(JOB_TIMEOUT, handler.run(ctx, &job.payload)).await {
+        Ok(result) => result,
+        Err(_) => Err(format!("Timed out after {:?}", JOB_TIMEOUT).into()),
+    }
+}
+
+/// Runs every job that is due, returning the number of jobs that succeeded.
+pub async fn run_due_jobs(
+    ctx: &JobContext,
+    handlers: &[Box<dyn JobHandler>],
+    random: &dyn RandomSource,
+) -> Result<u64, Error> {
+    let due = jobs::Entity::find()
+        .filter(jobs::Column::NextRunAt.lte(ctx.clock.unix_timestamp()))
+        .order_by_asc(jobs::Column::NextRunAt)
+        .all(&ctx.db)
+        .await?;
+
+    let mut succeeded = 0;
+    for job in due {
+        let Some(handler) = handlers.iter().find(|x| x.kind() == job.kind) else {
+            // Left for a handler that may be registered again, rather than lost
+            debug!("Job {} has no handler for kind {}", job.name, job.kind);
+            continue;
+        };
+        let result = run_job(ctx, handler.as_ref(), &job).await;
+        let now = ctx.clock.unix_timestamp();
+        let schedule = match job.schedule.as_str() {
+            "" => None,
+            x => CronSchedule::parse(x).ok(),
+        };
+        let next_scheduled_run = schedule.and_then(|x| x.next_after(now));
+
+        let mut model = jobs::ActiveModel {
+            last_run_at: Set(now),
+            ..Default::default()
+        };
+        match result {
+            Ok(()) => {
+                succeeded += 1;
+                model.attempts = Set(0);
+                model.last_error = Set(String::new());
+                if let Some(next_run_at) = next_scheduled_run {
+                    model.next_run_at = Set(next_run_at);
+                }
+            }
+            Err(e) => {
+                let attempts = job.attempts as u32 + 1;
+                model.last_error = Set(e.to_string().chars().take(MAX_ERROR_LENGTH).collect());
+                if attempts < handler.max_attempts() {
+                    let delay = backoff_seconds(attempts, random);
+                    warn!(
+                        "Job {} failed (attempt {}), retrying in {}s: {:?}",
+                        job.name, attempts, delay, e
+                    );
+                    model.attempts = Set(attempts as i32);
+                    model.next_run_at = Set(now + delay);
+                } else {
+                    error!(
+                        "Job {} failed {} times, giving up: {:?}",
+                        job.name, attempts, e
+                    );
+                    model.attempts = Set(0);
+                    if let Some(next_run_at) = next_scheduled_run {
+                        model.next_run_at = Set(next_run_at);
+                    }
+                }
+            }
+        }
+
+        // The job may have been rescheduled or cancelled while it ran, in which case the new
+        // schedule is kept rather than overwritten with the outcome of the old run
+        let unchanged = Condition::all()
+            .add(jobs::Column::Name.eq(&job.name))
+            .add(jobs::Column::NextRunAt.eq(job.next_run_at));
+        // One-shot jobs are done once they succeeded or were given up, and so are recurring jobs
+        // that never run again
+        let rows = if !model.next_run_at.is_set() {
+            jobs::Entity::delete_many()
+                .filter(unchanged)
+                .exec(&ctx.db)
+                .await?
+                .rows_affected
+        } else {
+            jobs::Entity::update_many()
+                .set(model)
+                .filter(unchanged)
+                .exec(&ctx.db)
+                .await?
+                .rows_affected
+        };
+        if rows == 0 {
+            debug!("Job {} was rescheduled while it ran", job.name);
+        }
+    }
+
+    if succeeded > 0 {
+        debug!("Ran {} jobs", succeeded);
+    }
+    Ok(succeeded)
+}
+
+/// Registers the recurring jobs and runs the due jobs for the lifetime of the bot.
+pub fn spawn_job_runner(
+    http: Arc<Http>,
+    db: DatabaseConnection,
+    clock: SharedClock,
+    random: SharedRandom,
+) {
+    tokio::spawn(async move {
+        let handlers = job_handlers();
+        if let Err(e) = register_recurring_jobs(&db, clock.as_ref(), &handlers).await {
+            error!("Failed to register recurring jobs: {:?}", e);
+        }
+        let ctx = JobContext { http, db, clock };
+        let mut interval = tokio::time::interval(JOB_POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            if !is_leader() {
+                continue;
+            }
+            if let Err(e) = run_due_jobs(&ctx, &handlers, random.as_ref()).await {
+                error!("Failed to run due jobs: {:?}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        infrastructure::{clock::ManualClock, random::SeededRandom},
+        services::testing::migrated_db,
+    };
+
+    use super::*;
+
+    const START: i64 = 1_700_000_000;
+
+    /// Reschedules its own job an hour later while it runs if the payload asks for it, like a command editing a
+    /// pending job would.
+    struct ReschedulingJob;
+
+    #[async_trait]
+    impl JobHandler for ReschedulingJob {
+        fn kind(&self) -> &'static str {
+            "test"
+        }
+
+        async fn run(&self, ctx: &JobContext, payload: &str) -> Result<(), Error> {
+            if payload == "reschedule" {
+                let run_at = ctx.clock.unix_timestamp() + 3600;
+                schedule_job(&ctx.db, "test:reschedule", self.kind(), run_at, payload).await?;
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn jobs_rescheduled_while_running_are_kept() -> Result<(), Error> {
+        let db = migrated_db().await;
+        let ctx = JobContext {
+            http: Arc::new(Http::new("")),
+            db: db.clone(),
+            clock: Arc::new(ManualClock::new(START as u64)),
+        };
+        let handlers: Vec<Box<dyn JobHandler>> = vec![Box::new(ReschedulingJob)];
+        schedule_job(&db, "test:once", "test", START, "").await?;
+        schedule_job(&db, "test:reschedule", "test", START, "reschedule").await?;
+
+        assert_eq!(
+            run_due_jobs(&ctx, &handlers, &SeededRandom::new(1)).await?,
+            2
+        );
+        let jobs: Vec<(String, i64)> = list_jobs(&db)
+            .await?
+            .into_iter()
+            .map(|x| (x.name, x.next_run_at))
+            .collect();
+        assert_eq!(jobs, vec![("test:reschedule".to_string(), START + 3600)]);
+        Ok(())
+    }
+}
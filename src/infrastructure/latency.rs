@@ -0,0 +1,84 @@
+/*!
+
+Rolling latency samples of the gateway, the REST API and the database.
+
+Gateway samples are recorded for every interaction, while REST and database samples are recorded by `/ping`.
+Only the most recent samples are kept, in memory.
+
+*/
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use poise::serenity_prelude::{ShardId, ShardManager};
+use sea_orm::DatabaseConnection;
+use tracing::{error, warn};
+
+use crate::Error;
+
+/// Number of samples kept for each kind of latency.
+const LATENCY_SAMPLE_CAPACITY: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LatencyKind {
+    Gateway,
+    Rest,
+    Database,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LatencySummary {
+    pub count: usize,
+    pub average: Duration,
+    pub max: Duration,
+}
+
+pub type LatencySamples = Arc<RwLock<HashMap<LatencyKind, VecDeque<Duration>>>>;
+
+/// Records a latency sample, dropping the oldest sample once the capacity is reached.
+pub fn record_latency(samples: &LatencySamples, kind: LatencyKind, latency: Duration) {
+    let mut samples = match samples.write() {
+        Ok(x) => x,
+        Err(_) => {
+            warn!("Latency samples lock is poisoned");
+            return;
+        }
+    };
+    let samples = samples.entry(kind).or_default();
+    if samples.len() >= LATENCY_SAMPLE_CAPACITY {
+        samples.pop_front();
+    }
+    samples.push_back(latency);
+}
+
+/// Summarizes the recorded samples of a kind of latency, or None if there are none.
+pub fn latency_summary(samples: &LatencySamples, kind: LatencyKind) -> Option<LatencySummary> {
+    let samples = samples.read().ok()?;
+    let samples = samples.get(&kind).filter(|x| !x.is_empty())?;
+    Some(LatencySummary {
+        count: samples.len(),
+        average: samples.iter().sum::<Duration>() / samples.len() as u32,
+        max: samples.iter().max().copied().unwrap_or_default(),
+    })
+}
+
+/// Measures the round-trip time of a trivial database query.
+pub async fn ping_database(db: &DatabaseConnection) -> Result<Duration, Error> {
+    let start = Instant::now();
+    db.ping().await?;
+    Ok(start.elapsed())
+}
+
+/// Gets the latest heartbeat latency of a shard, or None if it hasn't been measured yet.
+pub async fn gateway_latency(shard_manager: &ShardManager, shard_id: ShardId) -> Option<Duration> {
+    match shard_manager.runners.lock().await.get(&shard_id) {
+        Some(runner) => runner.latency,
+        None => {
+            error!("current shard is not in shard_manager.runners, this shouldn't happen");
+            None
+        }
+    }
+}
@@ -0,0 +1,109 @@
+/*!
+
+Coordination of several instances of the bot running against the same database.
+
+Every instance serves its gateway events, but background tasks (the scheduler, job runner, monitors and stats
+posting) must only run once. Instances compete for a lease stored in the `leader_lease` table: the instance holding
+it is the leader and runs the background tasks, and renews the lease well before it expires. If the leader stops,
+its lease expires and another instance takes it over on its next attempt.
+
+Acquiring and renewing the lease is a single conditional upsert, so two instances can't both take an expired lease.
+A single instance simply always holds the lease.
+
+*/
+
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use migration::OnConflict;
+use once_cell::sync::Lazy;
+use sea_orm::{ActiveValue::Set, DatabaseConnection, EntityTrait, sea_query::Expr};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    Error,
+    entities::leader_lease,
+    infrastructure::clock::{Clock, SharedClock},
+};
+
+/// Name of the lease of the background tasks.
+const LEASE_NAME: &str = "background_tasks";
+
+/// Time a lease stays valid without being renewed.
+const LEASE_DURATION: Duration = Duration::from_secs(30);
+
+/// Interval between attempts to acquire or renew the lease. Well under the lease duration, so a slow database doesn't
+/// cost the leader its lease.
+const LEASE_RENEW_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Unique id of this instance, stored as the holder of the leases it holds.
+pub static INSTANCE_ID: Lazy<String> = Lazy::new(|| Uuid::new_v4().to_string());
+
+static IS_LEADER: AtomicBool = AtomicBool::new(false);
+
+/// Whether this instance currently runs the background tasks.
+pub fn is_leader() -> bool {
+    IS_LEADER.load(Ordering::Relaxed)
+}
+
+/// Acquires the lease if it is free or expired, or renews it if this instance holds it. Returns whether this instance
+/// holds the lease.
+pub async fn try_acquire_lease(db: &DatabaseConnection, clock: &dyn Clock) -> Result<bool, Error> {
+    let now = clock.unix_timestamp();
+    let rows = leader_lease::Entity::insert(leader_lease::ActiveModel {
+        name: Set(LEASE_NAME.to_string()),
+        holder: Set(INSTANCE_ID.clone()),
+        expires_at: Set(now + LEASE_DURATION.as_secs() as i64),
+    })
+    .on_conflict(
+        OnConflict::column(leader_lease::Column::Name)
+            .update_columns([
+                leader_lease::Column::Holder,
+                leader_lease::Column::ExpiresAt,
+            ])
+            .action_and_where(
+                Expr::col((leader_lease::Entity, leader_lease::Column::Holder))
+                    .eq(INSTANCE_ID.as_str())
+                    .or(Expr::col((leader_lease::Entity, leader_lease::Column::ExpiresAt)).lt(now)),
+            )
+            .to_owned(),
+    )
+    .exec_without_returning(db)
+    .await?;
+    Ok(rows > 0)
+}
+
+/// Attempts to acquire the lease, and updates whether this instance is the leader. Failing to reach the database
+/// counts as losing the lease, since another instance may take it over in the meantime.
+pub async fn campaign(db: &DatabaseConnection, clock: &dyn Clock) {
+    let leader = match try_acquire_lease(db, clock).await {
+        Ok(x) => x,
+        Err(e) => {
+            warn!("Failed to acquire the leader lease: {:?}", e);
+            false
+        }
+    };
+    let was_leader = IS_LEADER.swap(leader, Ordering::Relaxed);
+    match (was_leader, leader) {
+        (false, true) => info!("Instance {} is now the leader", *INSTANCE_ID),
+        (true, false) => warn!("Instance {} is no longer the leader", *INSTANCE_ID),
+        (false, false) => debug!("Instance {} is on standby", *INSTANCE_ID),
+        (true, true) => {}
+    }
+}
+
+/// Keeps competing for the lease for the lifetime of the bot. The first attempt should be made with [`campaign`]
+/// before spawning the background tasks, so a single instance runs them right away.
+pub fn spawn_leader_election(db: DatabaseConnection, clock: SharedClock) {
+    tokio::spawn(async move {
+        let start = tokio::time::Instant::now() + LEASE_RENEW_INTERVAL;
+        let mut interval = tokio::time::interval_at(start, LEASE_RENEW_INTERVAL);
+        loop {
+            interval.tick().await;
+            campaign(&db, clock.as_ref()).await;
+        }
+    });
+}
@@ -0,0 +1,135 @@
+/*!
+
+Translated names and descriptions of the commands, shown by the Discord client to users whose language is one of the
+[`LOCALES`]. Users of other languages see the English names and descriptions.
+
+Translations live in the JSON files of the `locales` directory, embedded in the binary. Each file maps the qualified
+name of a command, like `reactmap add`, to its translated `name` and `description`; either can be left out to keep
+the English one. Translations Discord would reject, like names with spaces or two subcommands with the same name, are
+skipped with a warning, since a single invalid command fails the registration of every command.
+
+*/
+
+use std::collections::{HashMap, HashSet};
+
+use serde::Deserialize;
+use tracing::{debug, warn};
+
+use crate::{Error, infrastructure::botdata::Data};
+
+/// Discord locales commands are translated to, and their translations.
+const LOCALES: [(&str, &str); 4] = [
+    ("fr", include_str!("../../locales/fr.json")),
+    ("de", include_str!("../../locales/de.json")),
+    ("es-ES", include_str!("../../locales/es.json")),
+    ("es-419", include_str!("../../locales/es.json")),
+];
+
+const MAX_NAME_LENGTH: usize = 32;
+
+const MAX_DESCRIPTION_LENGTH: usize = 100;
+
+#[derive(Deserialize)]
+struct CommandTranslation {
+    name: Option<String>,
+    description: Option<String>,
+}
+
+/// Translations of one locale, by qualified command name.
+type Translations = HashMap<String, CommandTranslation>;
+
+/// Whether Discord accepts a name for a command. Context menu commands may use any characters, while slash commands
+/// and their subcommands must be lowercase words.
+fn is_valid_name(command: &poise::Command<Data, Error>, name: &str) -> bool {
+    let length = name.chars().count();
+    if length == 0 || length > MAX_NAME_LENGTH {
+        return false;
+    }
+    if command.slash_action.is_none() && command.context_menu_action.is_some() {
+        return true;
+    }
+    name.chars()
+        .all(|c| c == '-' || c == '_' || (c.is_alphanumeric() && !c.is_uppercase()))
+}
+
+fn localize(
+    commands: &mut [poise::Command<Data, Error>],
+    parent: &str,
+    locale: &str,
+    translations: &Translations,
+    unused: &mut HashSet<String>,
+) {
+    // Discord rejects commands sharing a name in any locale, including the name of a command without a translation
+    let english_names: HashSet<String> = commands.iter().map(|x| x.name.clone()).collect();
+    let mut names = HashSet::new();
+    for command in commands.iter_mut() {
+        let qualified_name = match parent {
+            "" => command.name.clone(),
+            _ => format!("{} {}", parent, command.name),
+        };
+        if let Some(translation) = translations.get(&qualified_name) {
+            unused.remove(&qualified_name);
+            if let Some(name) = &translation.name {
+                if !is_valid_name(command, name) {
+                    warn!("Invalid {} name '{}' for /{}", locale, name, qualified_name);
+                } else if (english_names.contains(name) && *name != command.name)
+                    || !names.insert(name.clone())
+                {
+                    warn!(
+                        "The {} name '{}' of /{} is already used by another command",
+                        locale, name, qualified_name
+                    );
+                } else {
+                    command
+                        .name_localizations
+                        .insert(locale.to_string(), name.clone());
+                }
+            }
+            if let Some(description) = &translation.description {
+                let length = description.chars().count();
+                if command.description.is_none() {
+                    debug!("/{} has no description to translate", qualified_name);
+                } else if length == 0 || length > MAX_DESCRIPTION_LENGTH {
+                    warn!(
+                        "The {} description of /{} must be between 1 and {} characters",
+                        locale, qualified_name, MAX_DESCRIPTION_LENGTH
+                    );
+                } else {
+                    command
+                        .description_localizations
+                        .insert(locale.to_string(), description.clone());
+                }
+            }
+        }
+        localize(
+            &mut command.subcommands,
+            &qualified_name,
+            locale,
+            translations,
+            unused,
+        );
+    }
+}
+
+/// Adds the translated names and descriptions of every locale to the commands and their subcommands. Must be called
+/// before the commands are registered.
+pub fn localize_commands(commands: &mut [poise::Command<Data, Error>]) {
+    for (locale, source) in LOCALES {
+        let translations: Translations = match serde_json::from_str(source) {
+            Ok(x) => x,
+            Err(e) => {
+                warn!("Failed to parse the {} translations: {}", locale, e);
+                continue;
+            }
+        };
+        let mut unused: HashSet<String> = translations.keys().cloned().collect();
+        localize(commands, "", locale, &translations, &mut unused);
+        // Commands of disabled features or modules are expected to be missing
+        for qualified_name in unused {
+            debug!(
+                "The {} translations have an entry for /{}, which isn't loaded",
+                locale, qualified_name
+            );
+        }
+    }
+}
@@ -0,0 +1,56 @@
+/*!
+
+Runtime control of the log filter, used by `/log level`.
+
+The logger wraps its `EnvFilter` in a reload layer at startup and installs the handle with [`install_log_filter`], so
+the filter can be swapped without restarting the bot.
+
+*/
+
+use std::sync::OnceLock;
+
+use tracing_subscriber::{EnvFilter, Registry, reload::Handle};
+
+use crate::Error;
+
+/// Filter used when `LOG_LEVEL` is not set.
+pub const DEFAULT_LOG_FILTER: &str = "warn,imposterbot=info";
+
+struct LogFilter {
+    handle: Handle<EnvFilter, Registry>,
+    /// The filter the bot was started with, restored by `/log reset`.
+    initial: String,
+}
+
+static LOG_FILTER: OnceLock<LogFilter> = OnceLock::new();
+
+/// Installs the handle of the reloadable filter. Only the first handle installed is used.
+pub fn install_log_filter(handle: Handle<EnvFilter, Registry>, initial: String) {
+    let _ = LOG_FILTER.set(LogFilter { handle, initial });
+}
+
+fn log_filter() -> Result<&'static LogFilter, Error> {
+    LOG_FILTER
+        .get()
+        .ok_or("The log filter can't be changed at runtime".into())
+}
+
+/// Gets the active filter, in `EnvFilter` syntax.
+pub fn current_log_filter() -> Result<String, Error> {
+    Ok(log_filter()?.handle.with_current(|x| x.to_string())?)
+}
+
+/// Replaces the active filter, e.g. with `warn,imposterbot::events=trace`.
+pub fn set_log_filter(filter: &str) -> Result<(), Error> {
+    let parsed = EnvFilter::try_new(filter)
+        .map_err(|e| format!("Invalid log filter '{}': {}", filter, e))?;
+    log_filter()?.handle.reload(parsed)?;
+    Ok(())
+}
+
+/// Restores the filter the bot was started with, returning it.
+pub fn reset_log_filter() -> Result<String, Error> {
+    let initial = log_filter()?.initial.clone();
+    set_log_filter(&initial)?;
+    Ok(initial)
+}
@@ -0,0 +1,203 @@
+/*!
+
+Privacy mode for logs. While it is enabled, user identifiers are replaced by a short hash, so the lines of a user can
+still be correlated, and message content is replaced by its length.
+
+Fields recorded on spans and events are redacted by [`RedactingFields`], which formats the fields of the log layers.
+Values written into the message of an event must be wrapped with [`log_user!`](crate::log_user) or
+[`log_content!`](crate::log_content) instead, e.g. `info!("User {} ran {}", log_user!(&user.name), command)`.
+
+Privacy mode starts out as set by `LOG_PRIVACY`, and is then loaded from the `bot_settings` table, where
+`/log privacy` stores it.
+
+*/
+
+use std::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use sea_orm::DatabaseConnection;
+use sha2::{Digest, Sha256};
+use tracing::field::{Field, Visit};
+use tracing_subscriber::{
+    field::{RecordFields, VisitOutput},
+    fmt::{
+        FormatFields,
+        format::{DefaultVisitor, Writer},
+    },
+};
+
+use crate::{Error, services::bot_settings::BotSettingsRepo};
+
+/// Name of the setting in the `bot_settings` table.
+const LOG_PRIVACY_SETTING: &str = "log_privacy";
+
+/// Number of hex digits of the hash a user identifier is replaced with.
+const USER_HASH_LENGTH: usize = 10;
+
+/// Fields holding a user identifier, e.g. the `user` field filled by `record_ctx_fields!`.
+const USER_FIELDS: [&str; 3] = ["user", "username", "display_name"];
+
+/// Fields holding message content.
+const CONTENT_FIELDS: [&str; 2] = ["content", "text"];
+
+static PRIVACY_MODE: AtomicBool = AtomicBool::new(false);
+
+pub fn privacy_mode_enabled() -> bool {
+    PRIVACY_MODE.load(Ordering::Relaxed)
+}
+
+pub fn set_privacy_mode(enabled: bool) {
+    PRIVACY_MODE.store(enabled, Ordering::Relaxed);
+}
+
+/// Applies the privacy mode stored in the database, if any.
+pub async fn load_privacy_mode(db: &DatabaseConnection) -> Result<(), Error> {
+    if let Some(value) = BotSettingsRepo::new(db).get(LOG_PRIVACY_SETTING).await? {
+        set_privacy_mode(value.parse()?);
+    }
+    Ok(())
+}
+
+/// Applies a privacy mode and stores it so it outlives restarts.
+pub async fn save_privacy_mode(db: &DatabaseConnection, enabled: bool) -> Result<(), Error> {
+    BotSettingsRepo::new(db)
+        .set(LOG_PRIVACY_SETTING, &enabled.to_string())
+        .await?;
+    set_privacy_mode(enabled);
+    Ok(())
+}
+
+/// Replaces a user identifier with a short hash of it.
+pub fn redact_user(value: &str) -> String {
+    let hash = format!("{:x}", Sha256::digest(value.as_bytes()));
+    format!("user-{}", &hash[..USER_HASH_LENGTH])
+}
+
+/// Replaces message content with its length.
+pub fn redact_content(value: &str) -> String {
+    format!("<{} chars redacted>", value.chars().count())
+}
+
+/// A user identifier written to the logs, redacted in privacy mode. Created by [`log_user!`](crate::log_user).
+pub struct LoggedUser<'a>(pub &'a str);
+
+impl fmt::Display for LoggedUser<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if privacy_mode_enabled() {
+            f.write_str(&redact_user(self.0))
+        } else {
+            f.write_str(self.0)
+        }
+    }
+}
+
+/// Message content written to the logs, redacted in privacy mode. Created by [`log_content!`](crate::log_content).
+pub struct LoggedContent<'a>(pub &'a str);
+
+impl fmt::Display for LoggedContent<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if privacy_mode_enabled() {
+            f.write_str(&redact_content(self.0))
+        } else {
+            f.write_str(self.0)
+        }
+    }
+}
+
+/// Wraps a user name for a log message, so it is hashed in privacy mode.
+#[macro_export]
+macro_rules! log_user {
+    ($value:expr) => {
+        $crate::infrastructure::log_privacy::LoggedUser(::std::convert::AsRef::<str>::as_ref(
+            $value,
+        ))
+    };
+}
+
+/// Wraps message content for a log message, so it is left out in privacy mode.
+#[macro_export]
+macro_rules! log_content {
+    ($value:expr) => {
+        $crate::infrastructure::log_privacy::LoggedContent(::std::convert::AsRef::<str>::as_ref(
+            $value,
+        ))
+    };
+}
+
+enum Redaction {
+    User,
+    Content,
+}
+
+impl Redaction {
+    fn of(field: &Field) -> Option<Self> {
+        if !privacy_mode_enabled() {
+            None
+        } else if USER_FIELDS.contains(&field.name()) {
+            Some(Self::User)
+        } else if CONTENT_FIELDS.contains(&field.name()) {
+            Some(Self::Content)
+        } else {
+            None
+        }
+    }
+
+    fn apply(&self, value: &str) -> String {
+        match self {
+            Self::User => redact_user(value),
+            Self::Content => redact_content(value),
+        }
+    }
+}
+
+/// Formats fields like the default formatter of `tracing_subscriber`, redacting user and content fields in privacy
+/// mode.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RedactingFields;
+
+impl<'writer> FormatFields<'writer> for RedactingFields {
+    fn format_fields<R: RecordFields>(&self, writer: Writer<'writer>, fields: R) -> fmt::Result {
+        let mut visitor = RedactingVisitor {
+            inner: DefaultVisitor::new(writer, true),
+        };
+        fields.record(&mut visitor);
+        visitor.inner.finish()
+    }
+}
+
+struct RedactingVisitor<'a> {
+    inner: DefaultVisitor<'a>,
+}
+
+impl Visit for RedactingVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match Redaction::of(field) {
+            Some(redaction) => self
+                .inner
+                .record_debug(field, &format_args!("{}", redaction.apply(value))),
+            None => self.inner.record_str(field, value),
+        }
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        match Redaction::of(field) {
+            Some(redaction) => self.inner.record_debug(
+                field,
+                &format_args!("{}", redaction.apply(&value.to_string())),
+            ),
+            None => self.inner.record_error(field, value),
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        match Redaction::of(field) {
+            Some(redaction) => self.inner.record_debug(
+                field,
+                &format_args!("{}", redaction.apply(&format!("{:?}", value))),
+            ),
+            None => self.inner.record_debug(field, value),
+        }
+    }
+}
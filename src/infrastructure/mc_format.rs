@@ -0,0 +1,124 @@
+/*!
+
+Renders parts of a minecraft server status for Discord embeds.
+
+Server descriptions (the MOTD) are styled with `§` formatting codes, which Discord has no use for. Descriptions with
+codes are rendered as an `ansi` code block, where the Discord client shows colors, bold and underlined text.
+Discord only knows eight colors, so the sixteen minecraft colors are mapped to the closest one, and the styles Discord
+can't show, like italic or obfuscated text, are dropped.
+
+Server icons are sent as base64 PNG data URLs, decoded to be attached to the embed as its thumbnail.
+
+*/
+
+use base64::Engine;
+
+/// Character that starts a formatting code in minecraft text.
+const FORMATTING_CHAR: char = '§';
+
+/// Prefix of the data URL servers send their icon as.
+const FAVICON_PREFIX: &str = "data:image/png;base64,";
+
+/// Name of the attachment holding a server icon, for embeds to reference it with `attachment://`.
+pub const FAVICON_FILENAME: &str = "favicon.png";
+
+/// Style of the text following formatting codes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Style {
+    /// ANSI foreground color, or none for the default color.
+    color: Option<u8>,
+    bold: bool,
+    underline: bool,
+}
+
+impl Style {
+    fn ansi(&self) -> String {
+        let mut codes = vec!["0".to_string()];
+        if self.bold {
+            codes.push("1".into());
+        }
+        if self.underline {
+            codes.push("4".into());
+        }
+        if let Some(color) = self.color {
+            codes.push(color.to_string());
+        }
+        format!("\u{1b}[{}m", codes.join(";"))
+    }
+}
+
+/// ANSI color Discord shows closest to a minecraft color code.
+fn ansi_color(code: char) -> Option<u8> {
+    match code {
+        '0' | '7' | '8' => Some(30), // Black and grays
+        '4' | 'c' => Some(31),       // Reds
+        '2' | 'a' => Some(32),       // Greens
+        '6' | 'e' => Some(33),       // Gold and yellow
+        '1' | '9' => Some(34),       // Blues
+        '5' | 'd' => Some(35),       // Purples
+        '3' | 'b' => Some(36),       // Aquas
+        'f' => Some(37),             // White
+        _ => None,
+    }
+}
+
+/// Translates the minecraft formatting codes of a text into ANSI escape codes, in a code block Discord shows them in.
+/// Texts without formatting codes are returned as they are.
+pub fn formatted_to_ansi(text: &str) -> String {
+    if !text.contains(FORMATTING_CHAR) {
+        return text.to_string();
+    }
+    let mut result = String::new();
+    let mut current = Style::default();
+    let mut pending = Style::default();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != FORMATTING_CHAR {
+            // Escape codes are only written before text, so runs of codes result in a single escape code
+            if pending != current && !c.is_whitespace() {
+                result.push_str(&pending.ansi());
+                current = pending;
+            }
+            result.push(c);
+            // Keeps the text from closing the code block
+            if c == '`' {
+                result.push('\u{200b}');
+            }
+            continue;
+        }
+        let Some(code) = chars.next().map(|x| x.to_ascii_lowercase()) else {
+            break;
+        };
+        match code {
+            'l' => pending.bold = true,
+            'n' => pending.underline = true,
+            'r' => pending = Style::default(),
+            // Hex colors of some server software, written as `§x§r§r§g§g§b§b`, are shown in the default color
+            'x' => {
+                pending = Style::default();
+                for _ in 0..12 {
+                    chars.next();
+                }
+            }
+            // Colors reset the styles, as they do in minecraft
+            _ => {
+                if let Some(color) = ansi_color(code) {
+                    pending = Style {
+                        color: Some(color),
+                        ..Default::default()
+                    };
+                }
+            }
+        }
+    }
+    let lines: Vec<&str> = result.lines().map(|x| x.trim_end()).collect();
+    format!("```ansi\n{}\n```", lines.join("\n"))
+}
+
+/// Decodes the icon of a server, sent as a base64 PNG data URL. Malformed icons are ignored.
+pub fn decode_favicon(favicon: &str) -> Option<Vec<u8>> {
+    let data = favicon.strip_prefix(FAVICON_PREFIX)?;
+    // Older servers wrap the base64 data on several lines
+    let data: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+    base64::engine::general_purpose::STANDARD.decode(data).ok()
+}
@@ -0,0 +1,303 @@
+/*!
+
+Periodically pings every advertised minecraft server and records its player count, so the history can be charted
+with `/mc stats`.
+
+Servers going online or offline are sent to the outgoing webhooks of their guild. Servers with a status channel also
+get the status mirrored into the name of that voice channel. Discord only allows a
+channel to be renamed twice every ten minutes, so a channel is renamed at most once per
+[`STATUS_CHANNEL_RENAME_INTERVAL`], and only when its name would change.
+
+*/
+
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use poise::serenity_prelude::{
+    ChannelId, EditChannel, GuildId, Http, UserId, async_trait, futures,
+};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+};
+use serde_json::json;
+use tracing::{debug, error, warn};
+
+use crate::{
+    Error,
+    commands::minecraft::ping_registered_server,
+    entities::mc_server_samples,
+    infrastructure::{
+        clock::{Clock, SharedClock},
+        ids::{id_from_string, id_to_string},
+        leader::is_leader,
+        retention::RetentionTask,
+        webhooks::dispatch,
+    },
+    services::{mc_servers::McServerRepo, webhooks::WebhookEvent},
+};
+
+/// Interval between samples of each server.
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(60 * 5);
+
+/// Minimum time between two renames of a status channel, leaving room under Discord's limit of two renames every ten
+/// minutes. Renames past the limit are held back by the rate limiter, which would stall the monitor.
+pub const STATUS_CHANNEL_RENAME_INTERVAL: Duration = Duration::from_secs(60 * 10);
+
+/// Maximum number of characters of the server name shown in a status channel name. Channel names are limited to 100.
+const MAX_STATUS_CHANNEL_SERVER_NAME_LENGTH: usize = 80;
+
+const SECONDS_PER_DAY: i64 = 60 * 60 * 24;
+
+/// Result of pinging an advertised server.
+#[derive(Debug, Clone)]
+pub struct ServerStatus {
+    pub guild_id: String,
+    pub name: String,
+    pub online: bool,
+    pub players_online: i32,
+    pub players_max: i32,
+}
+
+/// The name last given to each status channel, and when it was given.
+type StatusChannelRenames = HashMap<ChannelId, (String, Instant)>;
+
+/// Whether each server was online at the previous sample, keyed by guild id and server name.
+type LastOnline = HashMap<(String, String), bool>;
+
+/// Pings every advertised server once and records the results, returning the status of each server.
+pub async fn sample_servers(
+    db: &DatabaseConnection,
+    clock: &dyn Clock,
+) -> Result<Vec<ServerStatus>, Error> {
+    let servers = McServerRepo::new(db).all().await?;
+    if servers.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let sampled_at = clock.unix_timestamp();
+    let statuses = futures::future::join_all(servers.into_iter().map(|server| async move {
+        let guild_id = server.guild_id.clone();
+        let name = server.name.clone();
+        let (online, players_online, players_max) = match ping_registered_server(server).await {
+            Ok(status) => (
+                true,
+                status.players.online.min(i32::MAX as u32) as i32,
+                status.players.max.min(i32::MAX as u32) as i32,
+            ),
+            Err(_) => (false, 0, 0),
+        };
+        ServerStatus {
+            guild_id,
+            name,
+            online,
+            players_online,
+            players_max,
+        }
+    }))
+    .await;
+
+    let samples = statuses
+        .iter()
+        .map(|status| mc_server_samples::ActiveModel {
+            guild_id: Set(status.guild_id.clone()),
+            name: Set(status.name.clone()),
+            sampled_at: Set(sampled_at),
+            online: Set(status.online),
+            players_online: Set(status.players_online),
+            players_max: Set(status.players_max),
+            ..Default::default()
+        });
+    mc_server_samples::Entity::insert_many(samples)
+        .exec(db)
+        .await?;
+    debug!("Recorded {} minecraft server samples", statuses.len());
+    Ok(statuses)
+}
+
+/// Name of a status channel for the given server status, e.g. `🟢 SMP: 12/40`.
+pub fn status_channel_name(status: &ServerStatus) -> String {
+    let name: String = status
+        .name
+        .chars()
+        .take(MAX_STATUS_CHANNEL_SERVER_NAME_LENGTH)
+        .collect();
+    if status.online {
+        format!(
+            "🟢 {}: {}/{}",
+            name, status.players_online, status.players_max
+        )
+    } else {
+        format!("🔴 {}: offline", name)
+    }
+}
+
+/// Sends the servers that went online or offline since the previous sample to the webhooks of their guild.
+fn dispatch_status_changes(
+    db: &DatabaseConnection,
+    clock: &SharedClock,
+    statuses: &[ServerStatus],
+    last_online: &mut LastOnline,
+) {
+    for status in statuses {
+        let key = (status.guild_id.clone(), status.name.clone());
+        let previous = last_online.insert(key, status.online);
+        if previous.is_none_or(|x| x == status.online) {
+            continue;
+        }
+        let Ok(guild_id) = id_from_string::<GuildId>(&status.guild_id) else {
+            continue;
+        };
+        dispatch(
+            db,
+            clock,
+            guild_id,
+            WebhookEvent::McStatus,
+            json!({
+                "server": status.name,
+                "online": status.online,
+                "players_online": status.players_online,
+                "players_max": status.players_max,
+            }),
+        );
+    }
+}
+
+/// Renames the status channels of the sampled servers, skipping channels renamed too recently.
+async fn update_status_channels(
+    http: &Http,
+    db: &DatabaseConnection,
+    clock: &dyn Clock,
+    statuses: &[ServerStatus],
+    renames: &mut StatusChannelRenames,
+) -> Result<(), Error> {
+    for channel in McServerRepo::new(db).status_channels().await? {
+        let Some(status) = statuses
+            .iter()
+            .find(|x| x.guild_id == channel.guild_id && x.name == channel.name)
+        else {
+            continue;
+        };
+        let Ok(channel_id) = id_from_string::<ChannelId>(&channel.channel_id) else {
+            warn!("Invalid status channel id '{}'", channel.channel_id);
+            continue;
+        };
+        let name = status_channel_name(status);
+        if let Some((last_name, renamed_at)) = renames.get(&channel_id)
+            && (*last_name == name
+                || clock.instant().saturating_duration_since(*renamed_at)
+                    < STATUS_CHANNEL_RENAME_INTERVAL)
+        {
+            continue;
+        }
+
+        match channel_id
+            .edit(http, EditChannel::new().name(name.clone()))
+            .await
+        {
+            Ok(_) => {
+                debug!("Renamed status channel {} to '{}'", channel_id, name);
+                renames.insert(channel_id, (name, clock.instant()));
+            }
+            Err(e) => warn!(
+                "Failed to rename status channel {} of server '{}': {:?}",
+                channel_id, channel.name, e
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Samples the advertised servers on a schedule for the lifetime of the bot, and keeps the status channels up to date.
+pub fn spawn_mc_monitor(http: Arc<Http>, db: DatabaseConnection, clock: SharedClock) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+        let mut renames = StatusChannelRenames::new();
+        let mut last_online = LastOnline::new();
+        loop {
+            interval.tick().await;
+            if !is_leader() {
+                continue;
+            }
+            let statuses = match sample_servers(&db, clock.as_ref()).await {
+                Ok(x) => x,
+                Err(e) => {
+                    error!("Failed to sample minecraft servers: {:?}", e);
+                    continue;
+                }
+            };
+            dispatch_status_changes(&db, &clock, &statuses, &mut last_online);
+            if let Err(e) =
+                update_status_channels(&http, &db, clock.as_ref(), &statuses, &mut renames).await
+            {
+                error!("Failed to update minecraft status channels: {:?}", e);
+            }
+        }
+    });
+}
+
+/// Gets the samples of a server recorded since the given unix timestamp, oldest first.
+pub async fn get_samples(
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+    name: &str,
+    since: i64,
+) -> Result<Vec<mc_server_samples::Model>, Error> {
+    Ok(mc_server_samples::Entity::find()
+        .filter(mc_server_samples::Column::GuildId.eq(id_to_string(guild_id)))
+        .filter(mc_server_samples::Column::Name.eq(name))
+        .filter(mc_server_samples::Column::SampledAt.gte(since))
+        .order_by_asc(mc_server_samples::Column::SampledAt)
+        .all(db)
+        .await?)
+}
+
+/// Deletes every sample of a server, used when the server is no longer advertised.
+pub async fn remove_samples(
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+    name: &str,
+) -> Result<u64, Error> {
+    let result = mc_server_samples::Entity::delete_many()
+        .filter(mc_server_samples::Column::GuildId.eq(id_to_string(guild_id)))
+        .filter(mc_server_samples::Column::Name.eq(name))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+pub struct McServerSampleRetention;
+
+#[async_trait]
+impl RetentionTask for McServerSampleRetention {
+    fn name(&self) -> &'static str {
+        "mc_server_samples"
+    }
+
+    fn default_retention_days(&self) -> Option<u32> {
+        Some(14)
+    }
+
+    async fn expire(
+        &self,
+        db: &DatabaseConnection,
+        now: i64,
+        retention_days: u32,
+    ) -> Result<u64, Error> {
+        let result = mc_server_samples::Entity::delete_many()
+            .filter(
+                mc_server_samples::Column::SampledAt
+                    .lt(now - retention_days as i64 * SECONDS_PER_DAY),
+            )
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    async fn forget_user(&self, _db: &DatabaseConnection, _user_id: UserId) -> Result<u64, Error> {
+        // Samples only count players, without naming them
+        Ok(0)
+    }
+}
@@ -0,0 +1,96 @@
+/*!
+
+Audio files bundled in the `opus` folder of the media directory, played with `/play media`.
+
+Operators can add or remove files without restarting the bot. The list of files is cached, and scanned again when the
+modification time of the folder changes, which happens whenever a file is added, removed or renamed in it.
+
+*/
+
+use std::{path::PathBuf, sync::Mutex, time::SystemTime};
+
+use once_cell::sync::Lazy;
+use tracing::{debug, warn};
+
+use crate::infrastructure::environment::get_media_directory;
+
+/// Extensions of the files that can be played.
+const AUDIO_EXTENSIONS: [&str; 6] = ["opus", "ogg", "mp3", "wav", "flac", "m4a"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaFile {
+    /// Name of the file without its extension, used to pick it.
+    pub name: String,
+    pub path: PathBuf,
+}
+
+#[derive(Debug)]
+struct MediaCache {
+    modified: SystemTime,
+    files: Vec<MediaFile>,
+}
+
+static MEDIA_CACHE: Lazy<Mutex<Option<MediaCache>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn media_folder() -> PathBuf {
+    get_media_directory().join("opus")
+}
+
+fn scan_media_folder(folder: &PathBuf) -> Vec<MediaFile> {
+    let entries = match std::fs::read_dir(folder) {
+        Ok(x) => x,
+        Err(e) => {
+            warn!("Failed to read media folder {}: {}", folder.display(), e);
+            return vec![];
+        }
+    };
+    let mut files: Vec<MediaFile> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            path.extension()
+                .and_then(|x| x.to_str())
+                .is_some_and(|x| AUDIO_EXTENSIONS.contains(&x.to_lowercase().as_str()))
+        })
+        .filter_map(|path| {
+            Some(MediaFile {
+                name: path.file_stem()?.to_str()?.to_string(),
+                path,
+            })
+        })
+        .collect();
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    debug!("Found {} media files in {}", files.len(), folder.display());
+    files
+}
+
+/// Gets the playable files of the media folder, sorted by name.
+pub fn media_files() -> Vec<MediaFile> {
+    let folder = media_folder();
+    let Ok(modified) = std::fs::metadata(&folder).and_then(|x| x.modified()) else {
+        return vec![];
+    };
+    let mut cache = match MEDIA_CACHE.lock() {
+        Ok(x) => x,
+        Err(e) => e.into_inner(),
+    };
+    match cache.as_ref() {
+        Some(cached) if cached.modified == modified => cached.files.clone(),
+        _ => {
+            let files = scan_media_folder(&folder);
+            *cache = Some(MediaCache {
+                modified,
+                files: files.clone(),
+            });
+            files
+        }
+    }
+}
+
+/// Finds a media file by name, ignoring case.
+pub fn find_media_file(name: &str) -> Option<MediaFile> {
+    media_files()
+        .into_iter()
+        .find(|x| x.name.eq_ignore_ascii_case(name.trim()))
+}
@@ -0,0 +1,503 @@
+/*!
+
+Shows what is playing on a Jellyfin or Plex server in a "movie night" embed, configured per guild with `/movie-night`.
+
+The servers are configured by the bot's operator in the environment: `JELLYFIN_URL` and `JELLYFIN_API_KEY` for
+Jellyfin, `PLEX_URL` and `PLEX_TOKEN` for Plex. Each guild picks one of them and a channel, and the sessions of the
+server are polled on a fixed interval: the embed is posted once, then edited whenever what is playing changes. If the
+message was deleted, a new one is posted.
+
+*/
+
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    env::var,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+use once_cell::sync::Lazy;
+use poise::{
+    ChoiceParameter,
+    serenity_prelude::{
+        CreateEmbed, CreateEmbedFooter, CreateMessage, EditMessage, GuildId, Http, MessageId,
+        async_trait,
+    },
+};
+use sea_orm::DatabaseConnection;
+use serde::Deserialize;
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    Error,
+    infrastructure::{
+        branding::load_branding,
+        environment::{JELLYFIN_API_KEY, JELLYFIN_URL, PLEX_TOKEN, PLEX_URL},
+        leader::is_leader,
+    },
+    services::movie_night::{MovieNight, MovieNightRepo},
+};
+
+/// Interval between polls of the media servers.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum number of sessions listed in the embed.
+const MAX_SESSIONS: usize = 10;
+
+static HTTP: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+});
+
+/// Media servers a guild can follow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, poise::ChoiceParameter)]
+pub enum MediaServerKind {
+    Jellyfin,
+    Plex,
+}
+
+impl MediaServerKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Jellyfin => "jellyfin",
+            Self::Plex => "plex",
+        }
+    }
+}
+
+impl FromStr for MediaServerKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        [Self::Jellyfin, Self::Plex]
+            .into_iter()
+            .find(|x| x.as_str() == value)
+            .ok_or_else(|| format!("Unknown media server '{}'", value))
+    }
+}
+
+/// Something playing on a media server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NowPlaying {
+    /// Name of the movie, or of the show and episode.
+    pub title: String,
+    pub user: String,
+    pub paused: bool,
+    pub position: Duration,
+    pub duration: Option<Duration>,
+}
+
+#[async_trait]
+pub trait MediaServer: Send + Sync {
+    fn kind(&self) -> MediaServerKind;
+
+    /// Gets what is currently playing on the server.
+    async fn now_playing(&self) -> Result<Vec<NowPlaying>, Error>;
+}
+
+fn non_empty(key: &str) -> Option<String> {
+    var(key)
+        .ok()
+        .map(|x| x.trim().to_string())
+        .filter(|x| !x.is_empty())
+}
+
+/// Gets a media server configured in the environment, or None if it isn't configured.
+pub fn media_server(kind: MediaServerKind) -> Option<Box<dyn MediaServer>> {
+    match kind {
+        MediaServerKind::Jellyfin => {
+            let url = non_empty(JELLYFIN_URL)?;
+            let api_key = non_empty(JELLYFIN_API_KEY)?;
+            Some(Box::new(Jellyfin {
+                url: url.trim_end_matches('/').to_string(),
+                api_key,
+            }))
+        }
+        MediaServerKind::Plex => {
+            let url = non_empty(PLEX_URL)?;
+            let token = non_empty(PLEX_TOKEN)?;
+            Some(Box::new(Plex {
+                url: url.trim_end_matches('/').to_string(),
+                token,
+            }))
+        }
+    }
+}
+
+/// Formats a duration as `1:02:03`, or `2:03` when shorter than an hour.
+fn format_position(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// Formats an episode as `Show — S01E02 Episode`, leaving out the parts the server didn't report.
+fn episode_title(
+    show: Option<&str>,
+    season: Option<u32>,
+    episode: Option<u32>,
+    name: &str,
+) -> String {
+    let number = match (season, episode) {
+        (Some(season), Some(episode)) => format!("S{:02}E{:02} ", season, episode),
+        _ => String::new(),
+    };
+    match show {
+        Some(show) => format!("{} — {}{}", show, number, name),
+        None => format!("{}{}", number, name),
+    }
+}
+
+pub struct Jellyfin {
+    url: String,
+    api_key: String,
+}
+
+/// Jellyfin reports times in ticks of 100 nanoseconds.
+const JELLYFIN_TICKS_PER_SECOND: u64 = 10_000_000;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct JellyfinSession {
+    user_name: Option<String>,
+    now_playing_item: Option<JellyfinItem>,
+    play_state: Option<JellyfinPlayState>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct JellyfinItem {
+    name: String,
+    #[serde(rename = "Type")]
+    kind: Option<String>,
+    series_name: Option<String>,
+    parent_index_number: Option<u32>,
+    index_number: Option<u32>,
+    production_year: Option<u32>,
+    run_time_ticks: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct JellyfinPlayState {
+    position_ticks: Option<u64>,
+    #[serde(default)]
+    is_paused: bool,
+}
+
+#[async_trait]
+impl MediaServer for Jellyfin {
+    fn kind(&self) -> MediaServerKind {
+        MediaServerKind::Jellyfin
+    }
+
+    async fn now_playing(&self) -> Result<Vec<NowPlaying>, Error> {
+        let sessions: Vec<JellyfinSession> = HTTP
+            .get(format!("{}/Sessions", self.url))
+            .header(
+                "Authorization",
+                format!("MediaBrowser Token=\"{}\"", self.api_key),
+            )
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(sessions
+            .into_iter()
+            .filter_map(|session| {
+                let item = session.now_playing_item?;
+                let title = match item.kind.as_deref() {
+                    Some("Episode") => episode_title(
+                        item.series_name.as_deref(),
+                        item.parent_index_number,
+                        item.index_number,
+                        &item.name,
+                    ),
+                    _ => match item.production_year {
+                        Some(year) => format!("{} ({})", item.name, year),
+                        None => item.name,
+                    },
+                };
+                let ticks_to_duration = |x: u64| Duration::from_secs(x / JELLYFIN_TICKS_PER_SECOND);
+                Some(NowPlaying {
+                    title,
+                    user: session.user_name.unwrap_or_default(),
+                    paused: session.play_state.as_ref().is_some_and(|x| x.is_paused),
+                    position: session
+                        .play_state
+                        .and_then(|x| x.position_ticks)
+                        .map(ticks_to_duration)
+                        .unwrap_or_default(),
+                    duration: item.run_time_ticks.map(ticks_to_duration),
+                })
+            })
+            .collect())
+    }
+}
+
+pub struct Plex {
+    url: String,
+    token: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PlexResponse {
+    media_container: PlexMediaContainer,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PlexMediaContainer {
+    #[serde(default)]
+    metadata: Vec<PlexMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlexMetadata {
+    title: String,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    grandparent_title: Option<String>,
+    parent_index: Option<u32>,
+    index: Option<u32>,
+    year: Option<u32>,
+    /// Milliseconds.
+    duration: Option<u64>,
+    /// Milliseconds.
+    view_offset: Option<u64>,
+    #[serde(rename = "User")]
+    user: Option<PlexUser>,
+    #[serde(rename = "Player")]
+    player: Option<PlexPlayer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlexUser {
+    title: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlexPlayer {
+    state: Option<String>,
+}
+
+#[async_trait]
+impl MediaServer for Plex {
+    fn kind(&self) -> MediaServerKind {
+        MediaServerKind::Plex
+    }
+
+    async fn now_playing(&self) -> Result<Vec<NowPlaying>, Error> {
+        let response: PlexResponse = HTTP
+            .get(format!("{}/status/sessions", self.url))
+            .header("X-Plex-Token", &self.token)
+            .header("Accept", "application/json")
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(response
+            .media_container
+            .metadata
+            .into_iter()
+            .map(|item| {
+                let title = match item.kind.as_deref() {
+                    Some("episode") => episode_title(
+                        item.grandparent_title.as_deref(),
+                        item.parent_index,
+                        item.index,
+                        &item.title,
+                    ),
+                    _ => match item.year {
+                        Some(year) => format!("{} ({})", item.title, year),
+                        None => item.title,
+                    },
+                };
+                NowPlaying {
+                    title,
+                    user: item.user.and_then(|x| x.title).unwrap_or_default(),
+                    paused: item
+                        .player
+                        .and_then(|x| x.state)
+                        .is_some_and(|x| x == "paused"),
+                    position: Duration::from_millis(item.view_offset.unwrap_or_default()),
+                    duration: item.duration.map(Duration::from_millis),
+                }
+            })
+            .collect())
+    }
+}
+
+/// Describes what is playing, one line per session.
+pub fn describe_now_playing(sessions: &[NowPlaying]) -> String {
+    if sessions.is_empty() {
+        return "Nothing is playing right now. 🍿".to_string();
+    }
+    let mut lines: Vec<String> = sessions
+        .iter()
+        .take(MAX_SESSIONS)
+        .map(|x| {
+            let state = if x.paused { "⏸️" } else { "▶️" };
+            let position = match x.duration {
+                Some(duration) => format!(
+                    "{} / {}",
+                    format_position(x.position),
+                    format_position(duration)
+                ),
+                None => format_position(x.position),
+            };
+            let user = if x.user.is_empty() {
+                String::new()
+            } else {
+                format!(" — {}", x.user)
+            };
+            format!("{} **{}** `{}`{}", state, x.title, position, user)
+        })
+        .collect();
+    if sessions.len() > MAX_SESSIONS {
+        lines.push(format!("...and {} more", sessions.len() - MAX_SESSIONS));
+    }
+    lines.join("\n")
+}
+
+async fn movie_night_embed(
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+    kind: MediaServerKind,
+    description: &str,
+) -> CreateEmbed {
+    load_branding(db, guild_id)
+        .await
+        .unwrap_or_default()
+        .embed()
+        .title("🎬 Movie night")
+        .description(description)
+        .footer(CreateEmbedFooter::new(format!(
+            "Now playing on {}",
+            kind.name()
+        )))
+}
+
+/// Edits the movie night message of a guild, or posts it if it doesn't exist yet or was deleted. Returns the id of
+/// the message.
+async fn update_movie_night(
+    http: &Http,
+    db: &DatabaseConnection,
+    movie_night: &MovieNight,
+    description: &str,
+) -> Result<MessageId, Error> {
+    let embed = movie_night_embed(db, movie_night.guild_id, movie_night.server, description).await;
+    if let Some(message_id) = movie_night.message_id {
+        match movie_night
+            .channel_id
+            .edit_message(http, message_id, EditMessage::new().embed(embed.clone()))
+            .await
+        {
+            Ok(_) => return Ok(message_id),
+            Err(e) => debug!(
+                "Failed to edit movie night message {} of guild {}, posting a new one: {:?}",
+                message_id, movie_night.guild_id, e
+            ),
+        }
+    }
+    let message = movie_night
+        .channel_id
+        .send_message(http, CreateMessage::new().embed(embed))
+        .await?;
+    MovieNightRepo::new(db)
+        .set_message(movie_night.guild_id, message.id)
+        .await?;
+    Ok(message.id)
+}
+
+/// Describes what is playing on a media server, or None if the server isn't configured or can't be reached.
+async fn describe_media_server(kind: MediaServerKind) -> Option<String> {
+    let server = media_server(kind)?;
+    match server.now_playing().await {
+        Ok(sessions) => Some(describe_now_playing(&sessions)),
+        Err(e) => {
+            warn!(
+                "Failed to get the sessions of {}: {:?}",
+                server.kind().name(),
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Polls the media servers followed by guilds and updates their movie night messages.
+///
+/// `posted` holds the message of each guild and the description last posted to it, so messages are only edited when
+/// what is playing changed.
+async fn poll_media_servers(
+    http: &Http,
+    db: &DatabaseConnection,
+    posted: &mut HashMap<GuildId, (MessageId, String)>,
+) -> Result<(), Error> {
+    let movie_nights = MovieNightRepo::new(db).all().await?;
+    let mut descriptions: HashMap<MediaServerKind, Option<String>> = HashMap::new();
+    for movie_night in movie_nights {
+        let description = match descriptions.entry(movie_night.server) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(describe_media_server(movie_night.server).await),
+        };
+        let Some(description) = description.as_ref() else {
+            continue;
+        };
+
+        if let Some((message_id, posted)) = posted.get(&movie_night.guild_id)
+            && movie_night.message_id == Some(*message_id)
+            && posted == description
+        {
+            continue;
+        }
+        match update_movie_night(http, db, &movie_night, description).await {
+            Ok(message_id) => {
+                posted.insert(movie_night.guild_id, (message_id, description.clone()));
+            }
+            Err(e) => warn!(
+                "Failed to update the movie night message of guild {}: {:?}",
+                movie_night.guild_id, e
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Keeps the movie night messages up to date for the lifetime of the bot.
+pub fn spawn_movie_night_relay(http: Arc<Http>, db: DatabaseConnection) {
+    if media_server(MediaServerKind::Jellyfin).is_none()
+        && media_server(MediaServerKind::Plex).is_none()
+    {
+        debug!("No media server is configured, movie night relay disabled");
+        return;
+    }
+    info!("Movie night relay enabled");
+    tokio::spawn(async move {
+        let mut posted = HashMap::new();
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            if !is_leader() {
+                continue;
+            }
+            if let Err(e) = poll_media_servers(&http, &db, &mut posted).await {
+                error!("Failed to update movie night messages: {:?}", e);
+            }
+        }
+    });
+}
@@ -0,0 +1,132 @@
+/*!
+
+The moderation digest, configured with `/configure digest`: a daily or weekly summary of the moderation activity on a
+guild, posted to its mod-log channel by the scheduler.
+
+Moderation features record their activity with [`record_activity`] as it happens, and the digest counts the activity
+since the previous digest.
+
+*/
+
+use poise::serenity_prelude::{GuildId, Http, UserId, async_trait};
+use sea_orm::DatabaseConnection;
+use tracing::{debug, warn};
+
+use crate::{
+    Error,
+    infrastructure::{
+        branding::load_branding, clock::Clock, mod_log::send_mod_log, retention::RetentionTask,
+    },
+    services::mod_digest::{DigestFrequency, DigestSchedule, ModActivity, ModDigestRepo},
+};
+
+const SECONDS_PER_DAY: i64 = 60 * 60 * 24;
+
+/// Records moderation activity for the digest. Failures are logged, since the activity already happened.
+pub async fn record_activity(
+    db: &DatabaseConnection,
+    clock: &dyn Clock,
+    guild_id: GuildId,
+    activity: ModActivity,
+) {
+    if let Err(e) = ModDigestRepo::new(db)
+        .record(guild_id, activity, clock.unix_timestamp())
+        .await
+    {
+        warn!(
+            "Failed to record {:?} for the digest of guild {}: {:?}",
+            activity, guild_id, e
+        );
+    }
+}
+
+async fn send_digest(
+    http: &Http,
+    db: &DatabaseConnection,
+    schedule: &DigestSchedule,
+    now: i64,
+) -> Result<(), Error> {
+    let counts = ModDigestRepo::new(db)
+        .counts_since(schedule.guild_id, schedule.last_sent_at)
+        .await?;
+    let title = match schedule.frequency {
+        DigestFrequency::Weekly => "Weekly moderation digest",
+        _ => "Daily moderation digest",
+    };
+    let branding = load_branding(db, schedule.guild_id)
+        .await
+        .unwrap_or_default();
+    let mut embed = branding.embed().title(title).description(format!(
+        "Moderation activity from <t:{}:f> to <t:{}:f>",
+        schedule.last_sent_at, now
+    ));
+    if counts.is_empty() {
+        embed = embed.field("Activity", "Nothing happened", false);
+    }
+    for (activity, count) in counts {
+        embed = embed.field(activity.describe(), count.to_string(), true);
+    }
+    send_mod_log(http, db, schedule.guild_id, embed).await;
+    Ok(())
+}
+
+/// Posts the digest of every guild whose digest is due. A digest that failed is not retried until the next period,
+/// so a broken guild doesn't post repeatedly once it is fixed.
+pub async fn run_due_digests(
+    http: &Http,
+    db: &DatabaseConnection,
+    clock: &dyn Clock,
+) -> Result<u64, Error> {
+    let repo = ModDigestRepo::new(db);
+    let now = clock.unix_timestamp();
+    let mut sent = 0;
+    for schedule in repo.schedules().await? {
+        if !schedule.is_due(now) {
+            continue;
+        }
+        match send_digest(http, db, &schedule, now).await {
+            Ok(_) => sent += 1,
+            Err(e) => warn!(
+                "Failed to send the moderation digest of guild {}: {:?}",
+                schedule.guild_id, e
+            ),
+        }
+        repo.mark_sent(schedule.guild_id, now).await?;
+    }
+
+    if sent > 0 {
+        debug!("Sent {} moderation digests", sent);
+    }
+    Ok(sent)
+}
+
+/// Expires the recorded moderation activity, which is kept for a month by default. Nothing is forgotten for a user,
+/// since activity is stored without the members involved.
+pub struct ModActivityRetention;
+
+#[async_trait]
+impl RetentionTask for ModActivityRetention {
+    fn name(&self) -> &'static str {
+        "mod_activity"
+    }
+
+    fn default_retention_days(&self) -> Option<u32> {
+        Some(30)
+    }
+
+    async fn expire(
+        &self,
+        db: &DatabaseConnection,
+        now: i64,
+        retention_days: u32,
+    ) -> Result<u64, Error> {
+        ModDigestRepo::new(db)
+            .remove_before(now - retention_days as i64 * SECONDS_PER_DAY)
+            .await
+    }
+
+    async fn forget_user(&self, _db: &DatabaseConnection, _user_id: UserId) -> Result<u64, Error> {
+        // Moderation activity is counted per guild, without who took the action
+        Ok(0)
+    }
+}
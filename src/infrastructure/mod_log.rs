@@ -0,0 +1,56 @@
+/*!
+
+The guild's mod-log channel, configured with `/configure_mod_log_channel`.
+
+Moderation features post embeds describing what they did to this channel, so staff can review automated actions.
+Guilds without a mod-log channel simply don't get these logs.
+
+*/
+
+use poise::serenity_prelude::{ChannelId, CreateEmbed, CreateMessage, GuildId, Http};
+use sea_orm::{DatabaseConnection, EntityTrait};
+use tracing::warn;
+
+use crate::{
+    Error,
+    entities::mod_log_channel,
+    infrastructure::ids::{id_from_string, id_to_string},
+};
+
+/// Gets the mod-log channel of a guild, if one is configured.
+pub async fn get_mod_log_channel(
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+) -> Result<Option<ChannelId>, Error> {
+    Ok(mod_log_channel::Entity::find_by_id(id_to_string(guild_id))
+        .one(db)
+        .await?
+        .and_then(|x| id_from_string(&x.channel_id).ok()))
+}
+
+/// Posts an embed to the mod-log channel of a guild. Failures are logged, since the action being logged already
+/// happened.
+pub async fn send_mod_log(
+    http: &Http,
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+    embed: CreateEmbed,
+) {
+    let channel = match get_mod_log_channel(db, guild_id).await {
+        Ok(Some(x)) => x,
+        Ok(None) => return,
+        Err(e) => {
+            warn!(
+                "Failed to get the mod-log channel of guild {}: {:?}",
+                guild_id, e
+            );
+            return;
+        }
+    };
+    if let Err(e) = channel
+        .send_message(http, CreateMessage::new().embed(embed))
+        .await
+    {
+        warn!("Failed to send mod-log to channel {}: {:?}", channel, e);
+    }
+}
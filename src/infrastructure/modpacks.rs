@@ -0,0 +1,365 @@
+/*!
+
+Metadata of the modpacks advertised minecraft servers link to, shown in their status embed.
+
+When the modpack of a server is a link to a Modrinth or CurseForge project, the name, latest version and icon of the
+project are fetched from the API of the site. CurseForge requires an API key, set with `CURSEFORGE_API_KEY`; without
+it, CurseForge links are shown as they are. The metadata is cached in the columns of the server and fetched again
+once it is older than [`METADATA_TTL_SECONDS`], so status embeds don't query the APIs every time.
+
+*/
+
+use std::{env::var, time::Duration};
+
+use once_cell::sync::Lazy;
+use reqwest::{StatusCode, Url};
+use sea_orm::{ActiveValue::Set, DatabaseConnection};
+use serde::Deserialize;
+use tracing::{debug, warn};
+
+use crate::{
+    Error, entities::mc_server, infrastructure::environment::CURSEFORGE_API_KEY,
+    services::mc_servers::McServerRepo,
+};
+
+/// Time the metadata of a modpack is cached for, after which it is fetched again on the next use.
+pub const METADATA_TTL_SECONDS: i64 = 24 * 60 * 60;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+const MODRINTH_API_URL: &str = "https://api.modrinth.com/v2";
+
+const CURSEFORGE_API_URL: &str = "https://api.curseforge.com/v1";
+
+/// Id of minecraft among the games of CurseForge.
+const CURSEFORGE_MINECRAFT_GAME_ID: u32 = 432;
+
+/// Sections of the Modrinth site that projects are linked from, e.g. `modrinth.com/modpack/<slug>`.
+const MODRINTH_PROJECT_TYPES: [&str; 7] = [
+    "modpack",
+    "mod",
+    "project",
+    "plugin",
+    "datapack",
+    "resourcepack",
+    "shader",
+];
+
+// Modrinth asks API clients to identify themselves
+static HTTP: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent(concat!(
+            "calebberhow/Imposterbot.RS/",
+            env!("CARGO_PKG_VERSION")
+        ))
+        .build()
+        .unwrap_or_default()
+});
+
+/// Sites modpacks are hosted on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModpackSource {
+    Modrinth,
+    CurseForge,
+}
+
+impl ModpackSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Modrinth => "Modrinth",
+            Self::CurseForge => "CurseForge",
+        }
+    }
+}
+
+/// A link to a project on a site modpacks are hosted on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModpackLink {
+    pub source: ModpackSource,
+    pub slug: String,
+    /// CurseForge class of the project, like modpacks or mods, from the section of the site it is linked from.
+    class_id: Option<u32>,
+}
+
+/// Metadata of a modpack, as fetched from the site it is hosted on.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModpackMetadata {
+    pub name: String,
+    /// Latest version of the modpack, empty if it has none.
+    pub version: String,
+    /// URL of the icon of the modpack, empty if it has none.
+    pub icon_url: String,
+}
+
+impl ModpackMetadata {
+    /// Reads the cached metadata of a server, if any.
+    pub fn from_server(server: &mc_server::Model) -> Option<Self> {
+        if server.modpack_name.is_empty() {
+            return None;
+        }
+        Some(Self {
+            name: server.modpack_name.clone(),
+            version: server.modpack_version.clone(),
+            icon_url: server.modpack_icon.clone(),
+        })
+    }
+}
+
+fn curseforge_api_key() -> Option<String> {
+    var(CURSEFORGE_API_KEY)
+        .ok()
+        .map(|x| x.trim().to_string())
+        .filter(|x| !x.is_empty())
+}
+
+/// Parses the modpack of a server as a link to a Modrinth or CurseForge project. Modpacks that aren't links to these
+/// sites are Ok(None), as are CurseForge links when no API key is configured, while links to these sites that don't
+/// point to a project are an error.
+pub fn parse_link(modpack: &str) -> Result<Option<ModpackLink>, String> {
+    let Ok(url) = Url::parse(modpack.trim()) else {
+        return Ok(None);
+    };
+    let host = url.host_str().unwrap_or_default();
+    let host = host.strip_prefix("www.").unwrap_or(host);
+    let segments: Vec<&str> = url
+        .path_segments()
+        .map(|x| x.filter(|x| !x.is_empty()).collect())
+        .unwrap_or_default();
+    match host {
+        "modrinth.com" => match segments.as_slice() {
+            [kind, slug, ..] if MODRINTH_PROJECT_TYPES.contains(kind) => Ok(Some(ModpackLink {
+                source: ModpackSource::Modrinth,
+                slug: slug.to_string(),
+                class_id: None,
+            })),
+            _ => Err(format!(
+                "'{}' isn't a link to a Modrinth project, like https://modrinth.com/modpack/<name>.",
+                modpack
+            )),
+        },
+        "curseforge.com" => match segments.as_slice() {
+            ["minecraft", class, slug, ..] => {
+                if curseforge_api_key().is_none() {
+                    return Ok(None);
+                }
+                let class_id = match *class {
+                    "modpacks" => Some(4471),
+                    "mc-mods" => Some(6),
+                    _ => None,
+                };
+                Ok(Some(ModpackLink {
+                    source: ModpackSource::CurseForge,
+                    slug: slug.to_string(),
+                    class_id,
+                }))
+            }
+            _ => Err(format!(
+                "'{}' isn't a link to a CurseForge project, like https://curseforge.com/minecraft/modpacks/<name>.",
+                modpack
+            )),
+        },
+        _ => Ok(None),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ModrinthProject {
+    title: String,
+    icon_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModrinthVersion {
+    version_number: String,
+}
+
+/// Fetches a Modrinth project, or None if it doesn't exist.
+async fn fetch_modrinth(slug: &str) -> Result<Option<ModpackMetadata>, Error> {
+    let response = HTTP
+        .get(format!("{}/project/{}", MODRINTH_API_URL, slug))
+        .send()
+        .await?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let project: ModrinthProject = response.error_for_status()?.json().await?;
+    // Versions are listed from the newest
+    let versions: Vec<ModrinthVersion> = HTTP
+        .get(format!("{}/project/{}/version", MODRINTH_API_URL, slug))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(Some(ModpackMetadata {
+        name: project.title,
+        version: versions
+            .into_iter()
+            .next()
+            .map(|x| x.version_number)
+            .unwrap_or_default(),
+        icon_url: project.icon_url.unwrap_or_default(),
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+struct CurseForgeSearch {
+    data: Vec<CurseForgeProject>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CurseForgeProject {
+    name: String,
+    slug: String,
+    logo: Option<CurseForgeLogo>,
+    #[serde(default)]
+    latest_files: Vec<CurseForgeFile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CurseForgeLogo {
+    thumbnail_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CurseForgeFile {
+    display_name: String,
+    /// ISO 8601 date, so dates compare as strings.
+    file_date: String,
+}
+
+/// Fetches a CurseForge project, or None if it doesn't exist.
+async fn fetch_curseforge(
+    api_key: &str,
+    slug: &str,
+    class_id: Option<u32>,
+) -> Result<Option<ModpackMetadata>, Error> {
+    let mut query = vec![
+        ("gameId", CURSEFORGE_MINECRAFT_GAME_ID.to_string()),
+        ("slug", slug.to_string()),
+    ];
+    if let Some(class_id) = class_id {
+        query.push(("classId", class_id.to_string()));
+    }
+    let search: CurseForgeSearch = HTTP
+        .get(format!("{}/mods/search", CURSEFORGE_API_URL))
+        .header("x-api-key", api_key)
+        .query(&query)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    // Slugs are only unique within a class, and the search may match other projects
+    let Some(project) = search.data.into_iter().find(|x| x.slug == slug) else {
+        return Ok(None);
+    };
+    Ok(Some(ModpackMetadata {
+        name: project.name,
+        version: project
+            .latest_files
+            .into_iter()
+            .max_by(|a, b| a.file_date.cmp(&b.file_date))
+            .map(|x| x.display_name)
+            .unwrap_or_default(),
+        icon_url: project.logo.map(|x| x.thumbnail_url).unwrap_or_default(),
+    }))
+}
+
+/// Fetches the metadata of a modpack, or None if the project doesn't exist.
+#[tracing::instrument(level = tracing::Level::DEBUG, err(level = tracing::Level::WARN))]
+pub async fn fetch_metadata(link: &ModpackLink) -> Result<Option<ModpackMetadata>, Error> {
+    match link.source {
+        ModpackSource::Modrinth => fetch_modrinth(&link.slug).await,
+        ModpackSource::CurseForge => {
+            let api_key = curseforge_api_key().ok_or("CurseForge requires an API key")?;
+            fetch_curseforge(&api_key, &link.slug, link.class_id).await
+        }
+    }
+}
+
+/// Validates a modpack given to `/mc add` or `/mc update`. Links to projects that don't exist are an error, and the
+/// metadata of the others is returned, to be cached right away. Failures to reach the site aren't errors, leaving the
+/// metadata to be fetched when the status of the server is next shown.
+pub async fn validate_modpack(modpack: &str) -> Result<Option<ModpackMetadata>, Error> {
+    let Some(link) = parse_link(modpack)? else {
+        return Ok(None);
+    };
+    match fetch_metadata(&link).await {
+        Ok(Some(metadata)) => Ok(Some(metadata)),
+        Ok(None) => Err(format!(
+            "The {} project '{}' doesn't exist.",
+            link.source.as_str(),
+            link.slug
+        )
+        .into()),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Sets the cached modpack metadata columns of a server, for the modpack to be fetched again later if there is no
+/// metadata.
+pub fn set_metadata(
+    model: &mut mc_server::ActiveModel,
+    metadata: Option<ModpackMetadata>,
+    now: i64,
+) {
+    let fetched_at = if metadata.is_some() { now } else { 0 };
+    let metadata = metadata.unwrap_or_default();
+    model.modpack_name = Set(metadata.name);
+    model.modpack_version = Set(metadata.version);
+    model.modpack_icon = Set(metadata.icon_url);
+    model.modpack_fetched_at = Set(fetched_at);
+}
+
+/// Fetches the modpack metadata of a server again if its cache is stale, returning the server with the fresh
+/// metadata. Failures are logged and leave the stale metadata in place until the next attempt.
+pub async fn refresh_metadata(
+    db: &DatabaseConnection,
+    mut server: mc_server::Model,
+    now: i64,
+) -> mc_server::Model {
+    if now - server.modpack_fetched_at < METADATA_TTL_SECONDS {
+        return server;
+    }
+    let Ok(Some(link)) = parse_link(&server.modpack) else {
+        return server;
+    };
+    debug!("Refreshing the modpack metadata of '{}'", server.name);
+    match fetch_metadata(&link).await {
+        Ok(Some(metadata)) => {
+            server.modpack_name = metadata.name;
+            server.modpack_version = metadata.version;
+            server.modpack_icon = metadata.icon_url;
+        }
+        // The project was deleted since the server was added
+        Ok(None) => {
+            server.modpack_name.clear();
+            server.modpack_version.clear();
+            server.modpack_icon.clear();
+        }
+        Err(_) => {}
+    }
+    // Failed attempts are cached too, so an unreachable site isn't queried on every status
+    server.modpack_fetched_at = now;
+    let model = mc_server::ActiveModel {
+        guild_id: Set(server.guild_id.clone()),
+        name: Set(server.name.clone()),
+        modpack_name: Set(server.modpack_name.clone()),
+        modpack_version: Set(server.modpack_version.clone()),
+        modpack_icon: Set(server.modpack_icon.clone()),
+        modpack_fetched_at: Set(now),
+        ..Default::default()
+    };
+    if let Err(e) = McServerRepo::new(db).update(model).await {
+        warn!(
+            "Failed to cache the modpack metadata of '{}': {}",
+            server.name, e
+        );
+    }
+    server
+}
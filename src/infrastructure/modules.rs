@@ -0,0 +1,118 @@
+/*!
+
+Bot modules bundle the commands, event hooks and database migrations of a feature, so features can be developed as
+separate modules or crates and registered at startup.
+
+The binary builds a [`ModuleRegistry`] and [`install`]s it before the database is initialized, so the migrations of
+every module are applied by [`Migrator`] along with the migrations of the `migration` crate.
+
+*/
+
+use std::sync::OnceLock;
+
+use migration::{MigrationTrait, MigratorTrait};
+use once_cell::sync::Lazy;
+use poise::serenity_prelude::{Context, FullEvent, async_trait};
+use tracing::warn;
+
+use crate::{Error, infrastructure::botdata::Data};
+
+#[async_trait]
+pub trait BotModule: Send + Sync {
+    /// Unique name of the module, used in logs.
+    fn name(&self) -> &'static str;
+
+    /// Commands added by the module.
+    fn commands(&self) -> Vec<poise::Command<Data, Error>> {
+        vec![]
+    }
+
+    /// Called for every gateway event, after the built in event handlers.
+    async fn on_event(
+        &self,
+        _ctx: &Context,
+        _event: &FullEvent,
+        _data: &Data,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Migrations creating the tables of the module. Their names must be unique across every module.
+    fn migrations(&self) -> Vec<Box<dyn MigrationTrait>> {
+        vec![]
+    }
+}
+
+/// The modules the bot was started with.
+#[derive(Default)]
+pub struct ModuleRegistry {
+    modules: Vec<Box<dyn BotModule>>,
+}
+
+impl ModuleRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(mut self, module: impl BotModule + 'static) -> Self {
+        self.modules.push(Box::new(module));
+        self
+    }
+
+    pub fn modules(&self) -> impl Iterator<Item = &dyn BotModule> {
+        self.modules.iter().map(|x| x.as_ref())
+    }
+
+    /// Commands of every module, in registration order.
+    pub fn commands(&self) -> Vec<poise::Command<Data, Error>> {
+        self.modules.iter().flat_map(|x| x.commands()).collect()
+    }
+
+    /// Migrations of every module, in registration order.
+    pub fn migrations(&self) -> Vec<Box<dyn MigrationTrait>> {
+        self.modules.iter().flat_map(|x| x.migrations()).collect()
+    }
+
+    /// Passes an event to every module, logging (but otherwise ignoring) the errors of each module.
+    pub async fn on_event(&self, ctx: &Context, event: &FullEvent, data: &Data) {
+        for module in &self.modules {
+            if let Err(e) = module.on_event(ctx, event, data).await {
+                warn!(
+                    "Module {} event handler produced an error: {:?}",
+                    module.name(),
+                    e
+                );
+            }
+        }
+    }
+}
+
+static REGISTRY: OnceLock<ModuleRegistry> = OnceLock::new();
+
+/// Used before a registry is installed.
+static EMPTY_REGISTRY: Lazy<ModuleRegistry> = Lazy::new(ModuleRegistry::new);
+
+/// Installs the modules the bot runs with. Only the first registry installed is used.
+pub fn install(registry: ModuleRegistry) -> &'static ModuleRegistry {
+    if REGISTRY.set(registry).is_err() {
+        warn!("A module registry is already installed, ignoring the new one");
+    }
+    self::registry()
+}
+
+/// Gets the installed modules, or no modules if none were installed.
+pub fn registry() -> &'static ModuleRegistry {
+    REGISTRY.get().unwrap_or(&EMPTY_REGISTRY)
+}
+
+/// Applies the migrations of the `migration` crate, followed by the migrations of the installed modules.
+pub struct Migrator;
+
+#[async_trait]
+impl MigratorTrait for Migrator {
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        let mut migrations = migration::Migrator::migrations();
+        migrations.extend(registry().migrations());
+        migrations
+    }
+}
@@ -0,0 +1,114 @@
+/*!
+
+Records the tracks played in voice channels, so they can be listed with `/play history` and queued again with
+`/play replay`.
+
+*/
+
+use poise::serenity_prelude::{GuildId, UserId, async_trait};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect,
+};
+use tracing::warn;
+
+use crate::{
+    Error,
+    entities::play_history,
+    infrastructure::{clock::Clock, ids::id_to_string, retention::RetentionTask},
+};
+
+const SECONDS_PER_DAY: i64 = 60 * 60 * 24;
+
+/// Records a played track, logging (but otherwise ignoring) any failure so playback is never interrupted.
+///
+/// `url` is the source the track can be replayed from, or empty if it can't be replayed.
+pub async fn record_play(
+    db: &DatabaseConnection,
+    clock: &dyn Clock,
+    guild_id: GuildId,
+    user_id: UserId,
+    title: &str,
+    url: &str,
+) {
+    let result = play_history::Entity::insert(play_history::ActiveModel {
+        guild_id: Set(id_to_string(guild_id)),
+        user_id: Set(id_to_string(user_id)),
+        title: Set(title.to_string()),
+        url: Set(url.to_string()),
+        played_at: Set(clock.unix_timestamp()),
+        ..Default::default()
+    })
+    .exec(db)
+    .await;
+    if let Err(e) = result {
+        warn!("Failed to record play history: {:?}", e);
+    }
+}
+
+/// Gets the most recently played tracks of a guild, newest first.
+pub async fn get_play_history(
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+    limit: u64,
+) -> Result<Vec<play_history::Model>, Error> {
+    Ok(play_history::Entity::find()
+        .filter(play_history::Column::GuildId.eq(id_to_string(guild_id)))
+        .order_by_desc(play_history::Column::Id)
+        .limit(limit)
+        .all(db)
+        .await?)
+}
+
+/// Gets a track by its position in the play history, where 1 is the most recently played track.
+pub async fn get_play_history_entry(
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+    index: u64,
+) -> Result<Option<play_history::Model>, Error> {
+    if index == 0 {
+        return Ok(None);
+    }
+    Ok(play_history::Entity::find()
+        .filter(play_history::Column::GuildId.eq(id_to_string(guild_id)))
+        .order_by_desc(play_history::Column::Id)
+        .offset(index - 1)
+        .one(db)
+        .await?)
+}
+
+pub struct PlayHistoryRetention;
+
+#[async_trait]
+impl RetentionTask for PlayHistoryRetention {
+    fn name(&self) -> &'static str {
+        "play_history"
+    }
+
+    fn default_retention_days(&self) -> Option<u32> {
+        Some(90)
+    }
+
+    async fn expire(
+        &self,
+        db: &DatabaseConnection,
+        now: i64,
+        retention_days: u32,
+    ) -> Result<u64, Error> {
+        let result = play_history::Entity::delete_many()
+            .filter(
+                play_history::Column::PlayedAt.lt(now - retention_days as i64 * SECONDS_PER_DAY),
+            )
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    async fn forget_user(&self, db: &DatabaseConnection, user_id: UserId) -> Result<u64, Error> {
+        let result = play_history::Entity::delete_many()
+            .filter(play_history::Column::UserId.eq(id_to_string(user_id)))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
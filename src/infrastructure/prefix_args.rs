@@ -0,0 +1,227 @@
+/*!
+
+Argument parsing for prefix commands with many optional parameters.
+
+Poise maps prefix arguments to parameters by position, which doesn't work well for commands where most parameters
+are optional. Such commands take their arguments as a single `#[rest]` string and parse it with [`PrefixArgs`],
+then call the same implementation as their slash command.
+
+Arguments are separated by whitespace. Double or single quotes group words into one argument, and a backslash
+escapes the next character inside double quotes. `--name value` and `--name=value` set a named parameter, and a
+`--name` without a value is a switch. Named parameters may be given in any order, and parameters that are not named
+are taken from the remaining arguments in order:
+
+```text
+!mc update survival --version "1.21 Tricky Trials" --clear-modpack
+```
+
+*/
+
+use std::str::FromStr;
+
+use crate::Error;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// `--name` or `--name=value`. Names are normalized to use underscores.
+    Flag(String, Option<String>),
+    Value(String),
+}
+
+/// Splits a prefix command argument string into words, honoring quotes.
+fn tokenize(input: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = vec![];
+    let mut chars = input.chars().peekable();
+    loop {
+        while chars.next_if(|x| x.is_whitespace()).is_some() {}
+        let Some(&first) = chars.peek() else {
+            return Ok(tokens);
+        };
+
+        let mut word = String::new();
+        let mut quoted = false;
+        while let Some(c) = chars.next_if(|x| !x.is_whitespace()) {
+            match c {
+                '"' | '\'' => {
+                    quoted = true;
+                    loop {
+                        match chars.next() {
+                            Some(x) if x == c => break,
+                            Some('\\') if c == '"' => {
+                                word.push(chars.next().ok_or("Unfinished escape sequence")?)
+                            }
+                            Some(x) => word.push(x),
+                            None => return Err(format!("Missing closing quote {}", c).into()),
+                        }
+                    }
+                }
+                _ => word.push(c),
+            }
+        }
+
+        // Quoted text is never a flag, so values may start with dashes
+        match word.strip_prefix("--") {
+            Some(flag) if first != '"' && first != '\'' && !flag.is_empty() => {
+                let (name, value) = match flag.split_once('=') {
+                    Some((name, value)) => (name, Some(value.to_string())),
+                    None => (flag, None),
+                };
+                tokens.push(Token::Flag(name.replace('-', "_"), value));
+            }
+            _ if quoted || !word.is_empty() => tokens.push(Token::Value(word)),
+            _ => {}
+        }
+    }
+}
+
+fn parse_value<T: FromStr>(name: &str, value: &str) -> Result<T, Error> {
+    value
+        .parse::<T>()
+        .map_err(|_| format!("Invalid value '{}' for `{}`", value, name).into())
+}
+
+/// Parsed arguments of a prefix command.
+///
+/// Switches must be taken before named or positional parameters, since a value following a switch would otherwise
+/// be taken as the value of the switch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrefixArgs {
+    tokens: Vec<Token>,
+}
+
+impl PrefixArgs {
+    pub fn parse(input: &str) -> Result<Self, Error> {
+        Ok(PrefixArgs {
+            tokens: tokenize(input)?,
+        })
+    }
+
+    /// Removes the token at the index if it is a value.
+    fn remove_value(&mut self, index: usize) -> Option<String> {
+        if !matches!(self.tokens.get(index), Some(Token::Value(_))) {
+            return None;
+        }
+        match self.tokens.remove(index) {
+            Token::Value(value) => Some(value),
+            Token::Flag(..) => None,
+        }
+    }
+
+    fn find_flag(&self, name: &str) -> Option<usize> {
+        self.tokens
+            .iter()
+            .position(|x| matches!(x, Token::Flag(flag, _) if flag == name))
+    }
+
+    /// Whether the switch `--name` was given. `--name=false` turns it off explicitly.
+    pub fn switch(&mut self, name: &str) -> Result<Option<bool>, Error> {
+        let Some(index) = self.find_flag(name) else {
+            return Ok(None);
+        };
+        match self.tokens.remove(index) {
+            Token::Flag(_, Some(value)) => parse_value(name, &value).map(Some),
+            _ => Ok(Some(true)),
+        }
+    }
+
+    /// The value of `--name value` or `--name=value`, if given.
+    pub fn named<T: FromStr>(&mut self, name: &str) -> Result<Option<T>, Error> {
+        let Some(index) = self.find_flag(name) else {
+            return Ok(None);
+        };
+        let value = match self.tokens.remove(index) {
+            Token::Flag(_, Some(value)) => value,
+            _ => self
+                .remove_value(index)
+                .ok_or_else(|| format!("Missing value for `--{}`", name))?,
+        };
+        parse_value(name, &value).map(Some)
+    }
+
+    /// The value of `--name`, or else the next positional argument.
+    pub fn take<T: FromStr>(&mut self, name: &str) -> Result<Option<T>, Error> {
+        if let Some(value) = self.named(name)? {
+            return Ok(Some(value));
+        }
+        let index = self
+            .tokens
+            .iter()
+            .position(|x| matches!(x, Token::Value(_)));
+        match index.and_then(|index| self.remove_value(index)) {
+            Some(value) => parse_value(name, &value).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Like [`take`](Self::take), but fails if the parameter is missing.
+    pub fn require<T: FromStr>(&mut self, name: &str) -> Result<T, Error> {
+        self.take(name)?
+            .ok_or_else(|| format!("Missing argument `{}`", name).into())
+    }
+
+    /// Fails if any argument was not taken, such as a misspelled flag.
+    pub fn finish(self) -> Result<(), Error> {
+        match self.tokens.first() {
+            None => Ok(()),
+            Some(Token::Flag(name, _)) => Err(format!("Unknown option `--{}`", name).into()),
+            Some(Token::Value(value)) => Err(format!("Unexpected argument '{}'", value).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(x: &str) -> Token {
+        Token::Value(x.to_string())
+    }
+
+    fn error_of(input: &str) -> String {
+        tokenize(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn empty_input_has_no_arguments() -> Result<(), Error> {
+        for input in ["", "   ", "\t\n"] {
+            assert!(tokenize(input)?.is_empty());
+            let mut args = PrefixArgs::parse(input)?;
+            assert_eq!(args.switch("clear")?, None);
+            assert_eq!(args.take::<String>("name")?, None);
+            assert_eq!(
+                args.require::<String>("name").unwrap_err().to_string(),
+                "Missing argument `name`"
+            );
+            args.finish()?;
+        }
+        // An empty quoted string is still an argument
+        assert_eq!(tokenize(r#""" ''"#)?, vec![value(""), value("")]);
+        Ok(())
+    }
+
+    #[test]
+    fn unterminated_quotes_are_refused() {
+        assert_eq!(error_of(r#"survival "1.21"#), "Missing closing quote \"");
+        assert_eq!(error_of("survival '1.21"), "Missing closing quote '");
+        assert_eq!(error_of(r#""a \" b"#), "Missing closing quote \"");
+        assert_eq!(error_of(r#""a \"#), "Unfinished escape sequence");
+        // The other kind of quote doesn't close it
+        assert_eq!(error_of(r#""it's"#), "Missing closing quote \"");
+    }
+
+    #[test]
+    fn surrounding_whitespace_is_ignored() -> Result<(), Error> {
+        assert_eq!(
+            tokenize("  survival \t --port  25566 --clear-modpack \n ")?,
+            vec![
+                value("survival"),
+                Token::Flag("port".to_string(), None),
+                value("25566"),
+                Token::Flag("clear_modpack".to_string(), None),
+            ]
+        );
+        // Whitespace inside quotes is kept
+        assert_eq!(tokenize(r#" " a b "  "#)?, vec![value(" a b ")]);
+        Ok(())
+    }
+}
@@ -0,0 +1,234 @@
+/*!
+
+HTTP client for urls supplied by users, such as links posted in messages or webhook endpoints.
+
+Requests can only reach public addresses: hosts resolving to loopback, private, link-local or other internal addresses
+are refused, so users can't make the bot probe the network it runs in or read cloud metadata endpoints. Host names are
+checked when they are resolved for a connection, so every redirect hop is checked, as are hosts whose DNS records change
+between the check and the request.
+
+*/
+
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use reqwest::{
+    Url,
+    dns::{Addrs, Name, Resolve, Resolving},
+    redirect,
+};
+
+/// Redirects followed before giving up.
+const MAX_REDIRECTS: usize = 5;
+
+/// Whether an address can be reached from the public internet.
+pub fn is_public_address(address: IpAddr) -> bool {
+    match address {
+        IpAddr::V4(x) => {
+            let [first, second, third, _] = x.octets();
+            !(x.is_private()
+                || x.is_loopback()
+                || x.is_link_local()
+                || x.is_broadcast()
+                || x.is_documentation()
+                || x.is_unspecified()
+                || x.is_multicast()
+                // Shared address space of carrier-grade NAT, 100.64.0.0/10
+                || (first == 100 && second & 0xc0 == 64)
+                // IETF protocol assignments, 192.0.0.0/24
+                || (first == 192 && second == 0 && third == 0)
+                // Benchmarking, 198.18.0.0/15
+                || (first == 198 && second & 0xfe == 18)
+                // Reserved for future use, 240.0.0.0/4
+                || first >= 240)
+        }
+        IpAddr::V6(x) => {
+            if let Some(x) = x.to_ipv4_mapped() {
+                return is_public_address(IpAddr::V4(x));
+            }
+            let segments = x.segments();
+            // NAT64 (64:ff9b::/96) and 6to4 (2002::/16) addresses reach the ipv4 address they embed
+            let embedded = match segments {
+                [0x64, 0xff9b, 0, 0, 0, 0, high, low] => Some((high, low)),
+                [0x2002, high, low, ..] => Some((high, low)),
+                _ => None,
+            };
+            if let Some((high, low)) = embedded {
+                let x = (u32::from(high) << 16) | u32::from(low);
+                return is_public_address(IpAddr::V4(x.into()));
+            }
+            let first = segments[0];
+            !(x.is_loopback()
+                || x.is_unspecified()
+                || x.is_multicast()
+                // Unique local addresses, fc00::/7
+                || first & 0xfe00 == 0xfc00
+                // Link-local addresses, fe80::/10
+                || first & 0xffc0 == 0xfe80
+                // Deprecated site-local addresses, fec0::/10
+                || first & 0xffc0 == 0xfec0)
+        }
+    }
+}
+
+/// Checks that a url uses http(s) and doesn't point to an internal ip address, describing the problem if it does. Host
+/// names are checked once they are resolved.
+pub fn check_public_url(url: &Url) -> Result<(), String> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("<{}> is not an http(s) url", url));
+    }
+    let Some(host) = url.host_str() else {
+        return Err(format!("<{}> has no host", url));
+    };
+    match host.trim_start_matches('[').trim_end_matches(']').parse() {
+        Ok(address) if !is_public_address(address) => {
+            Err(format!("<{}> points to an internal address", url))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Resolves host names to their public addresses only.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|x| is_public_address(x.ip()))
+                .collect();
+            if addresses.is_empty() {
+                return Err(
+                    format!("{} does not resolve to a public address", name.as_str()).into(),
+                );
+            }
+            let addresses: Addrs = Box::new(addresses.into_iter());
+            Ok(addresses)
+        })
+    }
+}
+
+/// Builds a client that only connects to public addresses, and checks every redirect.
+pub fn public_http_client(timeout: Duration) -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .dns_resolver(Arc::new(PublicResolver))
+        .redirect(redirect::Policy::custom(|attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                return attempt.error("too many redirects");
+            }
+            match check_public_url(attempt.url()) {
+                Ok(()) => attempt.follow(),
+                Err(e) => attempt.error(e),
+            }
+        }))
+        .build()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn internal_addresses_are_not_public() {
+        for address in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public_address(address.parse().unwrap()), "{}", address);
+        }
+        for address in ["1.1.1.1", "93.184.216.34", "2606:4700:4700::1111"] {
+            assert!(is_public_address(address.parse().unwrap()), "{}", address);
+        }
+    }
+
+    fn is_public(address: &str) -> bool {
+        is_public_address(address.parse().unwrap())
+    }
+
+    #[test]
+    fn carrier_grade_nat_is_not_public() {
+        assert!(!is_public("100.64.0.0"));
+        assert!(!is_public("100.127.255.255"));
+        assert!(is_public("100.63.255.255"));
+        assert!(is_public("100.128.0.0"));
+    }
+
+    #[test]
+    fn protocol_assignments_are_not_public() {
+        assert!(!is_public("192.0.0.0"));
+        assert!(!is_public("192.0.0.255"));
+        assert!(is_public("191.255.255.255"));
+        assert!(is_public("192.0.1.0"));
+    }
+
+    #[test]
+    fn benchmarking_addresses_are_not_public() {
+        assert!(!is_public("198.18.0.0"));
+        assert!(!is_public("198.19.255.255"));
+        assert!(is_public("198.17.255.255"));
+        assert!(is_public("198.20.0.0"));
+    }
+
+    #[test]
+    fn reserved_addresses_are_not_public() {
+        assert!(!is_public("240.0.0.0"));
+        assert!(!is_public("250.1.2.3"));
+        assert!(!is_public("255.255.255.254"));
+        assert!(is_public("223.255.255.255"));
+    }
+
+    #[test]
+    fn site_local_addresses_are_not_public() {
+        assert!(!is_public("fec0::1"));
+        assert!(!is_public("feff:ffff::1"));
+        assert!(is_public("2001:4860:4860::8888"));
+    }
+
+    #[test]
+    fn nat64_addresses_are_checked_as_ipv4() {
+        assert!(!is_public("64:ff9b::7f00:1"));
+        assert!(!is_public("64:ff9b::a9fe:a9fe"));
+        assert!(!is_public("64:ff9b::c0a8:101"));
+        assert!(is_public("64:ff9b::101:101"));
+    }
+
+    #[test]
+    fn six_to_four_addresses_are_checked_as_ipv4() {
+        assert!(!is_public("2002:7f00:1::1"));
+        assert!(!is_public("2002:a9fe:a9fe::"));
+        assert!(!is_public("2002:a00:1:1::1"));
+        assert!(is_public("2002:101:101::1"));
+    }
+
+    #[test]
+    fn urls_with_internal_ips_are_refused() {
+        for url in [
+            "http://127.0.0.1/",
+            "http://[::1]:8080/",
+            "http://169.254.169.254/latest/meta-data/",
+            "file:///etc/passwd",
+        ] {
+            assert!(
+                check_public_url(&Url::parse(url).unwrap()).is_err(),
+                "{}",
+                url
+            );
+        }
+        assert!(check_public_url(&Url::parse("https://example.com/page").unwrap()).is_ok());
+    }
+}
@@ -0,0 +1,76 @@
+/*!
+
+Source of randomness, injected through [`Data`](super::botdata::Data).
+
+Commands that pick random outcomes should draw them from a [`RandomSource`] instead of `rand::rng()`, so a
+[`SeededRandom`] can make their outcomes reproducible.
+
+*/
+
+use std::{
+    fmt::Debug,
+    ops::RangeInclusive,
+    sync::{Arc, Mutex},
+};
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+pub type SharedRandom = Arc<dyn RandomSource>;
+
+pub trait RandomSource: Send + Sync + Debug {
+    /// Uniformly distributed value in `[0, 1)`.
+    fn next_f64(&self) -> f64;
+
+    /// Uniformly distributed value in the range.
+    fn random_range(&self, range: RangeInclusive<u64>) -> u64;
+
+    /// True with the given probability, which is clamped to `[0, 1]`.
+    fn random_bool(&self, probability: f64) -> bool {
+        self.next_f64() < probability.clamp(0.0, 1.0)
+    }
+
+    /// Index of a random element of a collection of `len` elements, or None if it is empty.
+    fn random_index(&self, len: usize) -> Option<usize> {
+        (len > 0).then(|| self.random_range(0..=(len as u64 - 1)) as usize)
+    }
+}
+
+/// Randomness from the thread-local generator of `rand`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ThreadRandom;
+
+impl RandomSource for ThreadRandom {
+    fn next_f64(&self) -> f64 {
+        rand::rng().random()
+    }
+
+    fn random_range(&self, range: RangeInclusive<u64>) -> u64 {
+        rand::rng().random_range(range)
+    }
+}
+
+/// Reproducible randomness from a seeded generator.
+#[derive(Debug)]
+pub struct SeededRandom(Mutex<StdRng>);
+
+impl SeededRandom {
+    pub fn new(seed: u64) -> Self {
+        SeededRandom(Mutex::new(StdRng::seed_from_u64(seed)))
+    }
+}
+
+impl RandomSource for SeededRandom {
+    fn next_f64(&self) -> f64 {
+        match self.0.lock() {
+            Ok(mut rng) => rng.random(),
+            Err(e) => e.into_inner().random(),
+        }
+    }
+
+    fn random_range(&self, range: RangeInclusive<u64>) -> u64 {
+        match self.0.lock() {
+            Ok(mut rng) => rng.random_range(range),
+            Err(e) => e.into_inner().random_range(range),
+        }
+    }
+}
@@ -0,0 +1,58 @@
+/*!
+
+Parameters of recently sent `/roll` and `/coinflip` results, keyed by the id of the result message.
+
+The "Roll again" and "Flip again" message context menu commands look up the parameters of the message they are used
+on, so the command can be repeated in one click. Only the most recent invocations are kept, in memory.
+
+*/
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, RwLock},
+};
+
+use poise::serenity_prelude::MessageId;
+use tracing::warn;
+
+use crate::commands::roll::Dice;
+
+/// Number of invocations kept.
+const RECENT_INVOCATION_CAPACITY: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Invocation {
+    Roll { dice: Dice },
+    Coinflip { probability: Option<f64> },
+}
+
+pub type RecentInvocations = Arc<RwLock<VecDeque<(MessageId, Invocation)>>>;
+
+/// Records the parameters of a result message, dropping the oldest invocation once the capacity is reached.
+pub fn record_invocation(
+    recent: &RecentInvocations,
+    message_id: MessageId,
+    invocation: Invocation,
+) {
+    let mut recent = match recent.write() {
+        Ok(x) => x,
+        Err(_) => {
+            warn!("Recent invocations lock is poisoned");
+            return;
+        }
+    };
+    if recent.len() >= RECENT_INVOCATION_CAPACITY {
+        recent.pop_front();
+    }
+    recent.push_back((message_id, invocation));
+}
+
+/// Gets the parameters of a result message, or None if it isn't a recent result.
+pub fn get_invocation(recent: &RecentInvocations, message_id: MessageId) -> Option<Invocation> {
+    let recent = recent.read().ok()?;
+    recent
+        .iter()
+        .rev()
+        .find(|(id, _)| *id == message_id)
+        .map(|(_, invocation)| *invocation)
+}
@@ -0,0 +1,188 @@
+/*!
+
+Data retention and user data purging.
+
+Each subsystem that stores data registers a [`RetentionTask`] in [`retention_tasks`]. Tasks expire rows older than
+the retention period configured for the subsystem (or the task's default), and delete all data stored about a user
+when they request it with `/privacy forget-me`. Old rows are expired daily by the [`RetentionJob`].
+
+*/
+
+use poise::serenity_prelude::{UserId, async_trait};
+use sea_orm::{DatabaseConnection, EntityTrait};
+use tracing::{info, warn};
+
+use crate::{
+    Error,
+    commands::define::DefinitionCacheRetention,
+    entities::retention_policy,
+    events::{
+        ban_sync::BanSyncRetention, emoji_stats::EmojiStatsRetention,
+        guild_member::KnownMemberRetention, voice_activity::VoiceSessionRetention,
+    },
+    infrastructure::{
+        color_roles::ColorRoleRetention,
+        confessions::ConfessionRetention,
+        jobs::{JobContext, JobHandler},
+        mc_monitor::McServerSampleRetention,
+        mod_digest::ModActivityRetention,
+        play_history::PlayHistoryRetention,
+        scheduler::{ScheduledAnnouncementRetention, TempRoleRetention},
+        webhooks::WebhookDeliveryRetention,
+    },
+    services::{
+        channel_activity::ChannelActivityRetention, character_sheets::CharacterSheetRetention,
+        game_nights::GameNightRetention, game_servers::GameServerRetention,
+        mc_servers::McServerRetention, name_history::NameHistoryRetention,
+        playlists::PlaylistRetention, roll_history::RollHistoryRetention,
+        user_timezones::UserTimezoneRetention, wordle::WordleRetention,
+    },
+};
+
+/// Cron schedule of the retention runs: daily, at a quiet hour.
+const RETENTION_SCHEDULE: &str = "0 4 * * *";
+
+#[async_trait]
+pub trait RetentionTask: Send + Sync {
+    /// Name of the subsystem, used as the key of its retention policy.
+    fn name(&self) -> &'static str;
+
+    /// Retention period used when no policy is configured. None keeps data forever.
+    fn default_retention_days(&self) -> Option<u32> {
+        None
+    }
+
+    /// Deletes rows older than the retention period before `now`, a unix timestamp, returning the number of rows
+    /// deleted.
+    async fn expire(
+        &self,
+        _db: &DatabaseConnection,
+        _now: i64,
+        _retention_days: u32,
+    ) -> Result<u64, Error> {
+        Ok(0)
+    }
+
+    /// Deletes all data stored about a user, returning the number of rows deleted or anonymized. Required, so every
+    /// subsystem has to decide what `/privacy forget-me` does to its data.
+    ///
+    /// Moderation and abuse records (bans, moderator actions, anything staff rely on to act against abuse) are never
+    /// deleted: the user is anonymized where they acted as a moderator, and records about them are kept until they
+    /// expire, so asking to be forgotten can't be used to escape moderation.
+    async fn forget_user(&self, db: &DatabaseConnection, user_id: UserId) -> Result<u64, Error>;
+}
+
+/// All registered retention tasks.
+pub fn retention_tasks() -> Vec<Box<dyn RetentionTask>> {
+    vec![
+        Box::new(EmojiStatsRetention),
+        Box::new(PlayHistoryRetention),
+        Box::new(McServerSampleRetention),
+        Box::new(DefinitionCacheRetention),
+        Box::new(VoiceSessionRetention),
+        Box::new(ConfessionRetention),
+        Box::new(KnownMemberRetention),
+        Box::new(BanSyncRetention),
+        Box::new(ModActivityRetention),
+        Box::new(PlaylistRetention),
+        Box::new(WebhookDeliveryRetention),
+        Box::new(NameHistoryRetention),
+        Box::new(WordleRetention),
+        Box::new(UserTimezoneRetention),
+        Box::new(ChannelActivityRetention),
+        Box::new(GameNightRetention),
+        Box::new(RollHistoryRetention),
+        Box::new(CharacterSheetRetention),
+        Box::new(ColorRoleRetention),
+        Box::new(TempRoleRetention),
+        Box::new(ScheduledAnnouncementRetention),
+        Box::new(McServerRetention),
+        Box::new(GameServerRetention),
+    ]
+}
+
+/// Gets the retention period of a subsystem in days, falling back to the task default. None keeps data forever.
+pub async fn get_retention_days(
+    db: &DatabaseConnection,
+    task: &dyn RetentionTask,
+) -> Result<Option<u32>, Error> {
+    let policy = retention_policy::Entity::find_by_id(task.name())
+        .one(db)
+        .await?;
+    Ok(match policy {
+        Some(policy) if policy.retention_days > 0 => Some(policy.retention_days as u32),
+        Some(_) => None,
+        None => task.default_retention_days(),
+    })
+}
+
+/// Expires old rows of every subsystem according to its retention policy, relative to `now`, a unix timestamp.
+pub async fn run_retention(db: &DatabaseConnection, now: i64) -> Result<u64, Error> {
+    let mut total = 0;
+    for task in retention_tasks() {
+        let days = match get_retention_days(db, task.as_ref()).await? {
+            Some(x) => x,
+            None => continue,
+        };
+        match task.expire(db, now, days).await {
+            Ok(deleted) => {
+                if deleted > 0 {
+                    info!("Expired {} rows from {}", deleted, task.name());
+                }
+                total += deleted;
+            }
+            Err(e) => warn!("Retention task {} produced an error: {:?}", task.name(), e),
+        }
+    }
+    Ok(total)
+}
+
+/// Deletes all data stored about a user from every subsystem. Every task runs even if another one fails, and the
+/// subsystems that failed are reported together.
+pub async fn forget_user(db: &DatabaseConnection, user_id: UserId) -> Result<u64, Error> {
+    let mut total = 0;
+    let mut failed = Vec::new();
+    for task in retention_tasks() {
+        match task.forget_user(db, user_id).await {
+            Ok(deleted) => total += deleted,
+            Err(e) => {
+                warn!(
+                    "Retention task {} failed to forget user {}: {:?}",
+                    task.name(),
+                    user_id,
+                    e
+                );
+                failed.push(task.name());
+            }
+        }
+    }
+    info!("Purged {} rows of data for user {}", total, user_id);
+    if !failed.is_empty() {
+        return Err(format!(
+            "Deleted {} records, but failed to delete your data from: {}. Please try again later.",
+            total,
+            failed.join(", ")
+        )
+        .into());
+    }
+    Ok(total)
+}
+
+/// Runs the retention tasks on a schedule.
+pub struct RetentionJob;
+
+#[async_trait]
+impl JobHandler for RetentionJob {
+    fn kind(&self) -> &'static str {
+        "retention"
+    }
+
+    fn schedule(&self) -> Option<&'static str> {
+        Some(RETENTION_SCHEDULE)
+    }
+
+    async fn run(&self, ctx: &JobContext, _payload: &str) -> Result<(), Error> {
+        run_retention(&ctx.db, ctx.clock.unix_timestamp()).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,284 @@
+/*!
+
+Runs work that was scheduled for later, such as announcements created with `/announce`, the removal of roles
+assigned with `/temprole` and the moderation digests configured with `/configure digest`.
+
+Scheduled items are stored in the database so they survive restarts, and are polled on a fixed interval.
+Items that became due while the bot was offline are sent as soon as it starts again.
+
+*/
+
+use std::{sync::Arc, time::Duration};
+
+use poise::serenity_prelude::{ChannelId, GuildId, Http, Mentionable, RoleId, UserId, async_trait};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, sea_query::Expr,
+};
+use tracing::{debug, error, warn};
+
+use crate::{
+    Error,
+    commands::announce::announcement_message,
+    entities::{scheduled_announcement, temp_roles},
+    infrastructure::{
+        branding::load_branding,
+        clock::{Clock, SharedClock},
+        colors,
+        ids::{id_from_string, id_to_string},
+        leader::is_leader,
+        mod_digest::run_due_digests,
+        mod_log::send_mod_log,
+        retention::RetentionTask,
+    },
+};
+
+/// Interval between checks for due items.
+pub const SCHEDULER_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Sends every scheduled announcement that is due. Announcements are removed once sending was attempted, so a
+/// deleted channel or missing permission doesn't cause them to be retried forever.
+pub async fn run_due_announcements(
+    http: &Http,
+    db: &DatabaseConnection,
+    clock: &dyn Clock,
+) -> Result<u64, Error> {
+    let due = scheduled_announcement::Entity::find()
+        .filter(scheduled_announcement::Column::SendAt.lte(clock.unix_timestamp()))
+        .order_by_asc(scheduled_announcement::Column::SendAt)
+        .all(db)
+        .await?;
+
+    let mut sent = 0;
+    for announcement in due {
+        let role = id_from_string::<RoleId>(&announcement.role_id).ok();
+        let ids = id_from_string::<GuildId>(&announcement.guild_id).and_then(|guild_id| {
+            id_from_string::<ChannelId>(&announcement.channel_id).map(|x| (guild_id, x))
+        });
+        match ids {
+            Ok((guild_id, channel_id)) => {
+                let branding = load_branding(db, guild_id).await.unwrap_or_default();
+                match channel_id
+                    .send_message(
+                        http,
+                        announcement_message(&announcement.message, &branding, guild_id, role),
+                    )
+                    .await
+                {
+                    Ok(_) => sent += 1,
+                    Err(e) => warn!(
+                        "Failed to send scheduled announcement {} to channel {}: {:?}",
+                        announcement.id, announcement.channel_id, e
+                    ),
+                }
+            }
+            Err(e) => warn!(
+                "Scheduled announcement {} has an invalid guild or channel id: {:?}",
+                announcement.id, e
+            ),
+        }
+        scheduled_announcement::Entity::delete_by_id(announcement.id)
+            .exec(db)
+            .await?;
+    }
+
+    if sent > 0 {
+        debug!("Sent {} scheduled announcements", sent);
+    }
+    Ok(sent)
+}
+
+/// Removes every temporary role that expired and logs it to the guild's mod-log channel. Like announcements, rows are
+/// removed once removal was attempted, since a member that left or a deleted role can't be fixed by retrying.
+pub async fn run_expired_temp_roles(
+    http: &Http,
+    db: &DatabaseConnection,
+    clock: &dyn Clock,
+) -> Result<u64, Error> {
+    let expired = temp_roles::Entity::find()
+        .filter(temp_roles::Column::ExpiresAt.lte(clock.unix_timestamp()))
+        .order_by_asc(temp_roles::Column::ExpiresAt)
+        .all(db)
+        .await?;
+
+    let mut removed = 0;
+    for temp_role in expired {
+        let ids = id_from_string::<GuildId>(&temp_role.guild_id).and_then(|guild_id| {
+            let user_id = id_from_string::<UserId>(&temp_role.user_id)?;
+            let role_id = id_from_string::<RoleId>(&temp_role.role_id)?;
+            Ok((guild_id, user_id, role_id))
+        });
+        match ids {
+            Ok((guild_id, user_id, role_id)) => {
+                let result = http
+                    .remove_member_role(guild_id, user_id, role_id, Some("Temporary role expired"))
+                    .await;
+                let branding = load_branding(db, guild_id).await.unwrap_or_default();
+                let embed = match &result {
+                    Ok(_) => {
+                        removed += 1;
+                        branding
+                            .embed()
+                            .title("Temporary role expired")
+                            .description(format!(
+                                "Removed {} from {}",
+                                role_id.mention(),
+                                user_id.mention()
+                            ))
+                    }
+                    Err(e) => {
+                        warn!(
+                            "Failed to remove temporary role {} from member {} on guild {}: {:?}",
+                            role_id, user_id, guild_id, e
+                        );
+                        branding
+                            .embed()
+                            .color(colors::red())
+                            .title("Temporary role could not be removed")
+                            .description(format!(
+                                "Failed to remove {} from {}: {}",
+                                role_id.mention(),
+                                user_id.mention(),
+                                e
+                            ))
+                    }
+                };
+                send_mod_log(http, db, guild_id, embed).await;
+            }
+            Err(e) => warn!(
+                "Temporary role of guild {} has an invalid id: {:?}",
+                temp_role.guild_id, e
+            ),
+        }
+        temp_roles::Entity::delete_by_id((
+            temp_role.guild_id,
+            temp_role.user_id,
+            temp_role.role_id,
+        ))
+        .exec(db)
+        .await?;
+    }
+
+    if removed > 0 {
+        debug!("Removed {} expired temporary roles", removed);
+    }
+    Ok(removed)
+}
+
+/// Removes users who asked to be forgotten as the author of the announcements they scheduled. The announcements are
+/// still sent, since they're posted on behalf of the guild.
+pub struct ScheduledAnnouncementRetention;
+
+#[async_trait]
+impl RetentionTask for ScheduledAnnouncementRetention {
+    fn name(&self) -> &'static str {
+        "scheduled_announcements"
+    }
+
+    async fn forget_user(&self, db: &DatabaseConnection, user_id: UserId) -> Result<u64, Error> {
+        let result = scheduled_announcement::Entity::update_many()
+            .col_expr(scheduled_announcement::Column::AuthorId, Expr::value(""))
+            .filter(scheduled_announcement::Column::AuthorId.eq(id_to_string(user_id)))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
+
+/// Removes users who asked to be forgotten as the moderator of the temporary roles they assigned. Temporary roles
+/// assigned to the user are kept, since their row is what takes the role away again, and are deleted when they expire.
+pub struct TempRoleRetention;
+
+#[async_trait]
+impl RetentionTask for TempRoleRetention {
+    fn name(&self) -> &'static str {
+        "temp_roles"
+    }
+
+    async fn forget_user(&self, db: &DatabaseConnection, user_id: UserId) -> Result<u64, Error> {
+        let result = temp_roles::Entity::update_many()
+            .col_expr(temp_roles::Column::AssignedBy, Expr::value(""))
+            .filter(temp_roles::Column::AssignedBy.eq(id_to_string(user_id)))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
+
+/// Runs the scheduler for the lifetime of the bot.
+pub fn spawn_scheduler(http: Arc<Http>, db: DatabaseConnection, clock: SharedClock) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SCHEDULER_INTERVAL);
+        loop {
+            interval.tick().await;
+            if !is_leader() {
+                continue;
+            }
+            if let Err(e) = run_due_announcements(&http, &db, clock.as_ref()).await {
+                error!("Failed to run scheduled announcements: {:?}", e);
+            }
+            if let Err(e) = run_expired_temp_roles(&http, &db, clock.as_ref()).await {
+                error!("Failed to remove expired temporary roles: {:?}", e);
+            }
+            if let Err(e) = run_due_digests(&http, &db, clock.as_ref()).await {
+                error!("Failed to send moderation digests: {:?}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{ActiveValue::Set, PaginatorTrait};
+
+    use crate::{infrastructure::clock::ManualClock, services::testing::migrated_db};
+
+    use super::*;
+
+    const START: u64 = 1_700_000_000;
+
+    #[tokio::test]
+    async fn items_run_once_they_are_due() -> Result<(), Error> {
+        let db = migrated_db().await;
+        let http = Http::new("");
+        let clock = ManualClock::new(START);
+
+        // Invalid ids are removed without reaching discord
+        scheduled_announcement::Entity::insert(scheduled_announcement::ActiveModel {
+            guild_id: Set("invalid".to_string()),
+            channel_id: Set("invalid".to_string()),
+            author_id: Set("1".to_string()),
+            message: Set("Hello".to_string()),
+            role_id: Set(String::new()),
+            send_at: Set(START as i64 + 60),
+            ..Default::default()
+        })
+        .exec(&db)
+        .await?;
+        temp_roles::Entity::insert(temp_roles::ActiveModel {
+            guild_id: Set("invalid".to_string()),
+            user_id: Set("1".to_string()),
+            role_id: Set("2".to_string()),
+            assigned_by: Set("3".to_string()),
+            reason: Set(None),
+            expires_at: Set(START as i64 + 120),
+        })
+        .exec(&db)
+        .await?;
+
+        run_due_announcements(&http, &db, &clock).await?;
+        run_expired_temp_roles(&http, &db, &clock).await?;
+        assert_eq!(scheduled_announcement::Entity::find().count(&db).await?, 1);
+        assert_eq!(temp_roles::Entity::find().count(&db).await?, 1);
+
+        clock.advance(Duration::from_secs(60));
+        run_due_announcements(&http, &db, &clock).await?;
+        run_expired_temp_roles(&http, &db, &clock).await?;
+        assert_eq!(scheduled_announcement::Entity::find().count(&db).await?, 0);
+        assert_eq!(temp_roles::Entity::find().count(&db).await?, 1);
+
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(run_expired_temp_roles(&http, &db, &clock).await?, 0);
+        assert_eq!(temp_roles::Entity::find().count(&db).await?, 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,337 @@
+/*!
+
+Status queries of the game servers advertised with `/server`, one [`ServerPinger`] per [`GameProtocol`].
+
+- Source: the A2S_INFO query of Steam games (Counter-Strike, Garry's Mod, Rust, Valheim...), over UDP.
+- FiveM: the `dynamic.json` endpoint of the http server FiveM servers run on their game port.
+- Terraria: the status endpoint of the TShock REST API, which has to be enabled in the TShock configuration.
+- Minecraft: the server list ping, as used by `/mc`.
+
+*/
+
+use std::{net::SocketAddr, str::FromStr, time::Duration};
+
+use async_minecraft_ping::{ConnectionConfig, ServerDescription};
+use once_cell::sync::Lazy;
+use poise::serenity_prelude::async_trait;
+use serde::Deserialize;
+use tokio::{
+    net::{UdpSocket, lookup_host},
+    time::timeout,
+};
+
+use crate::{Error, infrastructure::mc_format};
+
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+static HTTP: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(PING_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+});
+
+/// Protocols game servers are queried with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, poise::ChoiceParameter)]
+pub enum GameProtocol {
+    #[name = "Source (Steam)"]
+    Source,
+    #[name = "FiveM"]
+    FiveM,
+    #[name = "Terraria (TShock)"]
+    Terraria,
+    Minecraft,
+}
+
+impl GameProtocol {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Source => "source",
+            Self::FiveM => "fivem",
+            Self::Terraria => "terraria",
+            Self::Minecraft => "minecraft",
+        }
+    }
+
+    /// Port queried when a server doesn't set one.
+    pub fn default_port(&self) -> u16 {
+        match self {
+            Self::Source => 27015,
+            Self::FiveM => 30120,
+            Self::Terraria => 7878, // Port of the TShock REST API, not of the game
+            Self::Minecraft => 25565,
+        }
+    }
+}
+
+impl FromStr for GameProtocol {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        [Self::Source, Self::FiveM, Self::Terraria, Self::Minecraft]
+            .into_iter()
+            .find(|x| x.as_str() == value)
+            .ok_or_else(|| format!("Unknown protocol '{}'", value))
+    }
+}
+
+/// Status of a game server, as reported by the server. Protocols leave out what they don't report.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerStatus {
+    /// Name the server reports, like the hostname of a Source server or the MOTD of a minecraft server.
+    pub name: Option<String>,
+    pub game: Option<String>,
+    pub map: Option<String>,
+    pub version: Option<String>,
+    pub players: u32,
+    pub max_players: u32,
+}
+
+#[async_trait]
+pub trait ServerPinger: Send + Sync {
+    fn protocol(&self) -> GameProtocol;
+
+    /// Queries the status of a server, failing if it can't be reached.
+    async fn ping(&self, address: &str, port: u16) -> Result<ServerStatus, Error>;
+}
+
+/// Gets the pinger of a protocol.
+pub fn pinger(protocol: GameProtocol) -> Box<dyn ServerPinger> {
+    match protocol {
+        GameProtocol::Source => Box::new(SourcePinger),
+        GameProtocol::FiveM => Box::new(FiveMPinger),
+        GameProtocol::Terraria => Box::new(TerrariaPinger),
+        GameProtocol::Minecraft => Box::new(MinecraftPinger),
+    }
+}
+
+pub struct SourcePinger;
+
+/// Header of single packet responses. Split responses start with `FE FF FF FF` instead, which A2S_INFO responses are
+/// too small for.
+const SOURCE_SINGLE_PACKET: [u8; 4] = [0xFF; 4];
+
+const SOURCE_INFO_REQUEST: &[u8] = b"\xFF\xFF\xFF\xFFTSource Engine Query\0";
+
+const SOURCE_CHALLENGE_RESPONSE: u8 = b'A';
+
+const SOURCE_INFO_RESPONSE: u8 = b'I';
+
+/// Reads the fields of an A2S_INFO response in order.
+struct SourceReader<'a> {
+    data: &'a [u8],
+}
+
+impl SourceReader<'_> {
+    fn byte(&mut self) -> Result<u8, Error> {
+        let (first, rest) = self
+            .data
+            .split_first()
+            .ok_or("The server sent a truncated response")?;
+        self.data = rest;
+        Ok(*first)
+    }
+
+    fn skip(&mut self, count: usize) -> Result<(), Error> {
+        if self.data.len() < count {
+            return Err("The server sent a truncated response".into());
+        }
+        self.data = &self.data[count..];
+        Ok(())
+    }
+
+    /// Reads a null terminated string.
+    fn string(&mut self) -> Result<String, Error> {
+        let end = self
+            .data
+            .iter()
+            .position(|x| *x == 0)
+            .ok_or("The server sent a truncated response")?;
+        let value = String::from_utf8_lossy(&self.data[..end]).to_string();
+        self.data = &self.data[end + 1..];
+        Ok(value)
+    }
+}
+
+fn non_empty(value: String) -> Option<String> {
+    Some(value).filter(|x| !x.trim().is_empty())
+}
+
+/// Parses an A2S_INFO response, without the packet header.
+fn parse_source_info(data: &[u8]) -> Result<ServerStatus, Error> {
+    let mut reader = SourceReader { data };
+    if reader.byte()? != SOURCE_INFO_RESPONSE {
+        return Err("The server sent an unexpected response".into());
+    }
+    reader.skip(1)?; // Protocol version
+    let name = reader.string()?;
+    let map = reader.string()?;
+    reader.string()?; // Game folder
+    let game = reader.string()?;
+    reader.skip(2)?; // Steam app id
+    let players = reader.byte()?;
+    let max_players = reader.byte()?;
+    let bots = reader.byte()?;
+    reader.skip(4)?; // Server type, environment, visibility and VAC
+    let version = reader.string().ok();
+    Ok(ServerStatus {
+        name: non_empty(name),
+        game: non_empty(game),
+        map: non_empty(map),
+        version: version.and_then(non_empty),
+        // Bots count as players in the response
+        players: players.saturating_sub(bots) as u32,
+        max_players: max_players as u32,
+    })
+}
+
+#[async_trait]
+impl ServerPinger for SourcePinger {
+    fn protocol(&self) -> GameProtocol {
+        GameProtocol::Source
+    }
+
+    async fn ping(&self, address: &str, port: u16) -> Result<ServerStatus, Error> {
+        let target: SocketAddr = lookup_host((address, port))
+            .await?
+            .next()
+            .ok_or("The address of the server couldn't be resolved")?;
+        let local: SocketAddr = match target {
+            SocketAddr::V4(_) => "0.0.0.0:0".parse()?,
+            SocketAddr::V6(_) => "[::]:0".parse()?,
+        };
+        let socket = UdpSocket::bind(local).await?;
+        socket.connect(target).await?;
+
+        let mut request = SOURCE_INFO_REQUEST.to_vec();
+        let mut buffer = [0u8; 1400];
+        // Servers may answer with a challenge first, to be sent back along with the request
+        for _ in 0..2 {
+            socket.send(&request).await?;
+            let length = timeout(PING_TIMEOUT, socket.recv(&mut buffer))
+                .await
+                .map_err(|_| "The server didn't answer in time")??;
+            let packet = &buffer[..length];
+            let Some(payload) = packet.strip_prefix(&SOURCE_SINGLE_PACKET) else {
+                return Err("The server sent an unexpected response".into());
+            };
+            match payload.first() {
+                Some(&SOURCE_CHALLENGE_RESPONSE) if payload.len() >= 5 => {
+                    request = SOURCE_INFO_REQUEST.to_vec();
+                    request.extend_from_slice(&payload[1..5]);
+                }
+                _ => return parse_source_info(payload),
+            }
+        }
+        Err("The server kept sending challenges".into())
+    }
+}
+
+pub struct FiveMPinger;
+
+#[derive(Debug, Deserialize)]
+struct FiveMDynamic {
+    hostname: Option<String>,
+    gametype: Option<String>,
+    mapname: Option<String>,
+    clients: u32,
+    /// Sent as a string by most servers.
+    sv_maxclients: serde_json::Value,
+}
+
+#[async_trait]
+impl ServerPinger for FiveMPinger {
+    fn protocol(&self) -> GameProtocol {
+        GameProtocol::FiveM
+    }
+
+    async fn ping(&self, address: &str, port: u16) -> Result<ServerStatus, Error> {
+        let status: FiveMDynamic = HTTP
+            .get(format!("http://{}:{}/dynamic.json", address, port))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let max_players = match status.sv_maxclients {
+            serde_json::Value::Number(x) => x.as_u64().unwrap_or(0) as u32,
+            serde_json::Value::String(x) => x.trim().parse().unwrap_or(0),
+            _ => 0,
+        };
+        Ok(ServerStatus {
+            name: status.hostname.and_then(non_empty),
+            game: status.gametype.and_then(non_empty),
+            map: status.mapname.and_then(non_empty),
+            version: None,
+            players: status.clients,
+            max_players,
+        })
+    }
+}
+
+pub struct TerrariaPinger;
+
+#[derive(Debug, Deserialize)]
+struct TShockStatus {
+    name: Option<String>,
+    world: Option<String>,
+    serverversion: Option<String>,
+    playercount: u32,
+    maxplayers: u32,
+}
+
+#[async_trait]
+impl ServerPinger for TerrariaPinger {
+    fn protocol(&self) -> GameProtocol {
+        GameProtocol::Terraria
+    }
+
+    async fn ping(&self, address: &str, port: u16) -> Result<ServerStatus, Error> {
+        let status: TShockStatus = HTTP
+            .get(format!("http://{}:{}/v2/server/status", address, port))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(ServerStatus {
+            name: status.name.and_then(non_empty),
+            game: Some("Terraria".into()),
+            map: status.world.and_then(non_empty),
+            version: status.serverversion.and_then(non_empty),
+            players: status.playercount,
+            max_players: status.maxplayers,
+        })
+    }
+}
+
+pub struct MinecraftPinger;
+
+#[async_trait]
+impl ServerPinger for MinecraftPinger {
+    fn protocol(&self) -> GameProtocol {
+        GameProtocol::Minecraft
+    }
+
+    async fn ping(&self, address: &str, port: u16) -> Result<ServerStatus, Error> {
+        let connection = ConnectionConfig::build(address).with_port(port);
+        let status = timeout(PING_TIMEOUT, async {
+            connection.connect().await?.status().await
+        })
+        .await
+        .map_err(|_| "The server didn't answer in time")??
+        .status;
+        let description = match status.description {
+            ServerDescription::Plain(text) => text,
+            ServerDescription::Object { text } => text,
+        };
+        Ok(ServerStatus {
+            name: non_empty(mc_format::formatted_to_ansi(&description)),
+            game: Some("Minecraft".into()),
+            map: None,
+            version: non_empty(status.version.name),
+            players: status.players.online as u32,
+            max_players: status.players.max as u32,
+        })
+    }
+}
@@ -0,0 +1,271 @@
+/*!
+
+Reading the times users give to `/timestamp` and formatting them as Discord timestamps, which every member sees in
+their own timezone.
+
+Times are read in the timezone the user saved with `/timezone`, an offset from UTC such as `UTC+2` or `-05:00`. Fixed
+offsets don't follow daylight saving time, so users update theirs when their clocks change. Accepted times:
+
+- `now`, or a delay such as `in 2h`, `1d12h` or `30m ago`
+- a time such as `18:30`, `6pm` or `noon`, on the next day it comes
+- a day such as `today`, `tomorrow`, `friday` or `2026-12-31`, optionally followed by a time (`friday at 8pm`)
+- an ISO 8601 date and time with its own offset, such as `2026-12-31T23:00:00+01:00`, read as it is
+
+*/
+
+use poise::serenity_prelude::Timestamp;
+
+use crate::infrastructure::util::parse_duration;
+
+const SECONDS_PER_DAY: i64 = 60 * 60 * 24;
+
+/// Offsets range from UTC-12:00 to UTC+14:00, in minutes.
+const MIN_UTC_OFFSET: i32 = -12 * 60;
+const MAX_UTC_OFFSET: i32 = 14 * 60;
+
+const WEEKDAYS: [&str; 7] = [
+    "sunday",
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+];
+
+/// Display styles of Discord timestamps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum TimestampStyle {
+    #[name = "Short time (16:20)"]
+    ShortTime,
+    #[name = "Long time (16:20:30)"]
+    LongTime,
+    #[name = "Short date (20/04/2021)"]
+    ShortDate,
+    #[name = "Long date (20 April 2021)"]
+    LongDate,
+    #[name = "Short date and time (20 April 2021 16:20)"]
+    ShortDateTime,
+    #[name = "Long date and time (Tuesday, 20 April 2021 16:20)"]
+    LongDateTime,
+    #[name = "Relative (2 months ago)"]
+    Relative,
+}
+
+impl TimestampStyle {
+    pub const ALL: [Self; 7] = [
+        Self::ShortTime,
+        Self::LongTime,
+        Self::ShortDate,
+        Self::LongDate,
+        Self::ShortDateTime,
+        Self::LongDateTime,
+        Self::Relative,
+    ];
+
+    /// Letter of the style in timestamp markup.
+    pub fn as_char(&self) -> char {
+        match self {
+            Self::ShortTime => 't',
+            Self::LongTime => 'T',
+            Self::ShortDate => 'd',
+            Self::LongDate => 'D',
+            Self::ShortDateTime => 'f',
+            Self::LongDateTime => 'F',
+            Self::Relative => 'R',
+        }
+    }
+
+    /// Timestamp markup of a unix timestamp, like `<t:1618932000:R>`.
+    pub fn markup(&self, timestamp: i64) -> String {
+        format!("<t:{}:{}>", timestamp, self.as_char())
+    }
+}
+
+/// Parses an offset from UTC such as `UTC+2`, `-05:00`, `+0530` or `GMT`, returning it in minutes.
+pub fn parse_utc_offset(value: &str) -> Result<i32, String> {
+    let invalid = || {
+        format!(
+            "Invalid timezone '{}', expected an offset from UTC such as UTC+2, -05:00 or +05:30",
+            value
+        )
+    };
+    let trimmed = value.trim().to_uppercase();
+    let offset = trimmed
+        .strip_prefix("UTC")
+        .or_else(|| trimmed.strip_prefix("GMT"))
+        .unwrap_or(&trimmed)
+        .trim();
+    if offset.is_empty() || offset == "Z" {
+        return Ok(0);
+    }
+    let (sign, offset) = if let Some(rest) = offset.strip_prefix('+') {
+        (1, rest)
+    } else if let Some(rest) = offset.strip_prefix('-') {
+        (-1, rest)
+    } else {
+        return Err(invalid());
+    };
+    if !offset.chars().all(|c| c.is_ascii_digit() || c == ':') {
+        return Err(invalid());
+    }
+    let (hours, minutes) = match offset.split_once(':') {
+        Some((hours, minutes)) => (hours, minutes),
+        None if offset.len() > 2 => offset.split_at(offset.len() - 2),
+        None => (offset, "0"),
+    };
+    let hours: i32 = hours.parse().map_err(|_| invalid())?;
+    let minutes: i32 = minutes.parse().map_err(|_| invalid())?;
+    if !(0..60).contains(&minutes) {
+        return Err(invalid());
+    }
+    let offset = sign * (hours * 60 + minutes);
+    if !(MIN_UTC_OFFSET..=MAX_UTC_OFFSET).contains(&offset) {
+        return Err(format!(
+            "Offsets from UTC range from UTC-12:00 to UTC+14:00, not '{}'",
+            value
+        ));
+    }
+    Ok(offset)
+}
+
+/// Formats an offset from UTC in minutes, like `UTC+05:30`.
+pub fn format_utc_offset(offset: i32) -> String {
+    format!(
+        "UTC{}{:02}:{:02}",
+        if offset < 0 { '-' } else { '+' },
+        offset.abs() / 60,
+        offset.abs() % 60
+    )
+}
+
+/// Parses a time of day such as `18:30`, `6pm`, `6:30am` or `noon` into seconds since midnight.
+fn parse_time_of_day(value: &str) -> Option<i64> {
+    match value {
+        "noon" => return Some(12 * 60 * 60),
+        "midnight" => return Some(0),
+        _ => {}
+    }
+    let (value, half) = if let Some(x) = value.strip_suffix("am") {
+        (x.trim(), Some(0))
+    } else if let Some(x) = value.strip_suffix("pm") {
+        (x.trim(), Some(12))
+    } else {
+        (value, None)
+    };
+    let mut parts = value.split(':');
+    let hours: i64 = parts.next()?.parse().ok()?;
+    let minutes: i64 = parts.next().map_or(Some(0), |x| x.parse().ok())?;
+    let seconds: i64 = parts.next().map_or(Some(0), |x| x.parse().ok())?;
+    if parts.next().is_some() || !(0..60).contains(&minutes) || !(0..60).contains(&seconds) {
+        return None;
+    }
+    let hours = match half {
+        // 12am is midnight and 12pm is noon
+        Some(half) if (1..=12).contains(&hours) => hours % 12 + half,
+        Some(_) => return None,
+        None if (0..24).contains(&hours) => hours,
+        None => return None,
+    };
+    Some(hours * 60 * 60 + minutes * 60 + seconds)
+}
+
+/// Parses a date and time given by a user into a unix timestamp, reading it in the timezone of the user. See the
+/// module documentation for the accepted formats.
+pub fn parse_datetime(value: &str, now: i64, utc_offset: i32) -> Result<i64, String> {
+    let invalid = || {
+        format!(
+            "Couldn't read '{}' as a time, try e.g. 18:30, tomorrow 6pm, friday at 20:00, 2026-12-31 23:00 or in 2h",
+            value.trim()
+        )
+    };
+    let value = value.trim().to_lowercase();
+    if value.is_empty() {
+        return Err(invalid());
+    }
+    if value == "now" {
+        return Ok(now);
+    }
+    // ISO 8601 with an offset of its own
+    if let Ok(timestamp) = Timestamp::parse(&value.to_uppercase()) {
+        return Ok(timestamp.unix_timestamp());
+    }
+    if let Some(delay) = value.strip_prefix("in ") {
+        return parse_duration(delay)
+            .map(|x| now.saturating_add(x))
+            .ok_or_else(invalid);
+    }
+    if let Some(delay) = value.strip_suffix(" ago") {
+        return parse_duration(delay)
+            .map(|x| now.saturating_sub(x))
+            .ok_or_else(invalid);
+    }
+    if let Some(delay) = parse_duration(&value) {
+        return Ok(now.saturating_add(delay));
+    }
+
+    let offset_seconds = utc_offset as i64 * 60;
+    let local_now = now + offset_seconds;
+    let today = local_now.div_euclid(SECONDS_PER_DAY);
+    // "2026-12-31T23:00" is a date and a time too
+    let value = match value.split_once('t') {
+        Some((date, time))
+            if date.len() == 10 && date.starts_with(|c: char| c.is_ascii_digit()) =>
+        {
+            format!("{} {}", date, time)
+        }
+        _ => value,
+    };
+    let mut words: Vec<&str> = value.split_whitespace().filter(|x| *x != "at").collect();
+
+    // Days are read along with whether they are a weekday, which comes back every week
+    let first = words.first().copied().unwrap_or_default();
+    let day = if let Ok(date) = Timestamp::parse(&format!("{}T00:00:00Z", first)) {
+        Some((date.unix_timestamp().div_euclid(SECONDS_PER_DAY), false))
+    } else {
+        match first {
+            "today" => Some((today, false)),
+            "tomorrow" => Some((today + 1, false)),
+            "yesterday" => Some((today - 1, false)),
+            _ => WEEKDAYS
+                .iter()
+                .position(|x| *x == first || (first.len() >= 3 && x.starts_with(first)))
+                .map(|weekday| {
+                    // The unix epoch was a thursday
+                    let current = (today + 4).rem_euclid(7);
+                    (today + (weekday as i64 - current).rem_euclid(7), true)
+                }),
+        }
+    };
+    if day.is_some() {
+        words.remove(0);
+    }
+    let time = if words.is_empty() {
+        None
+    } else {
+        Some(parse_time_of_day(&words.concat()).ok_or_else(invalid)?)
+    };
+
+    let local = match (day, time) {
+        (Some((day, weekly)), time) => {
+            let local = day * SECONDS_PER_DAY + time.unwrap_or(0);
+            // Today's weekday at a time that already passed means the same weekday next week
+            if weekly && local <= local_now {
+                local + 7 * SECONDS_PER_DAY
+            } else {
+                local
+            }
+        }
+        // A time alone is the next time the clock shows it
+        (None, Some(time)) => {
+            let local = today * SECONDS_PER_DAY + time;
+            if local <= local_now {
+                local + SECONDS_PER_DAY
+            } else {
+                local
+            }
+        }
+        (None, None) => return Err(invalid()),
+    };
+    Ok(local - offset_seconds)
+}
@@ -0,0 +1,267 @@
+/*!
+
+Translation of text between languages, used by `/translate` and flag reactions.
+
+Translations are made by a [`TranslationProvider`], picked from the environment by [`translation_provider`]: DeepL if
+`DEEPL_API_KEY` is set, otherwise a LibreTranslate instance at `LIBRETRANSLATE_URL` (with the optional
+`LIBRETRANSLATE_API_KEY`). The source language is detected by the provider.
+
+*/
+
+use std::{env::var, time::Duration};
+
+use once_cell::sync::Lazy;
+use poise::{ChoiceParameter, serenity_prelude::async_trait};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    Error,
+    infrastructure::environment::{DEEPL_API_KEY, LIBRETRANSLATE_API_KEY, LIBRETRANSLATE_URL},
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+static HTTP: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+});
+
+/// Languages text can be translated to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, poise::ChoiceParameter)]
+pub enum Language {
+    #[default]
+    English,
+    Spanish,
+    French,
+    German,
+    Italian,
+    Portuguese,
+    Dutch,
+    Polish,
+    Russian,
+    Ukrainian,
+    Turkish,
+    Swedish,
+    Japanese,
+    Korean,
+    Chinese,
+    Arabic,
+    Hindi,
+    Indonesian,
+}
+
+impl Language {
+    /// ISO 639-1 code of the language.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::English => "en",
+            Self::Spanish => "es",
+            Self::French => "fr",
+            Self::German => "de",
+            Self::Italian => "it",
+            Self::Portuguese => "pt",
+            Self::Dutch => "nl",
+            Self::Polish => "pl",
+            Self::Russian => "ru",
+            Self::Ukrainian => "uk",
+            Self::Turkish => "tr",
+            Self::Swedish => "sv",
+            Self::Japanese => "ja",
+            Self::Korean => "ko",
+            Self::Chinese => "zh",
+            Self::Arabic => "ar",
+            Self::Hindi => "hi",
+            Self::Indonesian => "id",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        // Providers may add a region, such as `EN-US`
+        let code = code.split(['-', '_']).next()?.to_lowercase();
+        (0..).map_while(Self::from_index).find(|x| x.code() == code)
+    }
+
+    /// Gets the language of the country of a flag emoji, such as 🇫🇷.
+    pub fn from_flag(emoji: &str) -> Option<Self> {
+        let country: String = emoji
+            .chars()
+            .map(|x| match x as u32 {
+                // Regional indicator symbols A to Z
+                0x1F1E6..=0x1F1FF => char::from_u32(x as u32 - 0x1F1E6 + 'A' as u32),
+                _ => None,
+            })
+            .collect::<Option<String>>()?;
+        let language = match country.as_str() {
+            "US" | "GB" | "AU" | "CA" | "NZ" | "IE" => Self::English,
+            "ES" | "MX" | "AR" | "CO" | "CL" | "PE" => Self::Spanish,
+            "FR" => Self::French,
+            "DE" | "AT" => Self::German,
+            "IT" => Self::Italian,
+            "PT" | "BR" => Self::Portuguese,
+            "NL" => Self::Dutch,
+            "PL" => Self::Polish,
+            "RU" => Self::Russian,
+            "UA" => Self::Ukrainian,
+            "TR" => Self::Turkish,
+            "SE" => Self::Swedish,
+            "JP" => Self::Japanese,
+            "KR" => Self::Korean,
+            "CN" | "TW" => Self::Chinese,
+            "SA" | "EG" | "AE" => Self::Arabic,
+            "IN" => Self::Hindi,
+            "ID" => Self::Indonesian,
+            _ => return None,
+        };
+        Some(language)
+    }
+}
+
+/// Describes a language code returned by a provider, by name if it is a known language.
+pub fn describe_language(code: &str) -> String {
+    match Language::from_code(code) {
+        Some(language) => language.name().to_string(),
+        None => code.to_uppercase(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Translation {
+    pub text: String,
+    /// Code of the detected source language, if the provider reported it.
+    pub source: Option<String>,
+}
+
+#[async_trait]
+pub trait TranslationProvider: Send + Sync {
+    /// Name of the service, credited under translations.
+    fn name(&self) -> &'static str;
+
+    /// Translates text to a language, detecting the language of the text.
+    async fn translate(&self, text: &str, target: Language) -> Result<Translation, Error>;
+}
+
+/// Gets the provider configured in the environment, or None if translations are not configured.
+pub fn translation_provider() -> Option<Box<dyn TranslationProvider>> {
+    fn non_empty(key: &str) -> Option<String> {
+        var(key)
+            .ok()
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty())
+    }
+
+    if let Some(api_key) = non_empty(DEEPL_API_KEY) {
+        return Some(Box::new(DeepL { api_key }));
+    }
+    non_empty(LIBRETRANSLATE_URL).map(|url| -> Box<dyn TranslationProvider> {
+        Box::new(LibreTranslate {
+            url: url.trim_end_matches('/').to_string(),
+            api_key: non_empty(LIBRETRANSLATE_API_KEY),
+        })
+    })
+}
+
+pub struct DeepL {
+    api_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeepLResponse {
+    translations: Vec<DeepLTranslation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeepLTranslation {
+    detected_source_language: Option<String>,
+    text: String,
+}
+
+#[async_trait]
+impl TranslationProvider for DeepL {
+    fn name(&self) -> &'static str {
+        "DeepL"
+    }
+
+    async fn translate(&self, text: &str, target: Language) -> Result<Translation, Error> {
+        // Keys of the free API end with `:fx` and use a separate host
+        let url = if self.api_key.ends_with(":fx") {
+            "https://api-free.deepl.com/v2/translate"
+        } else {
+            "https://api.deepl.com/v2/translate"
+        };
+        // DeepL needs a variant for English and Portuguese targets
+        let target = match target {
+            Language::English => "EN-US".to_string(),
+            Language::Portuguese => "PT-BR".to_string(),
+            x => x.code().to_uppercase(),
+        };
+        let response: DeepLResponse = HTTP
+            .post(url)
+            .header("Authorization", format!("DeepL-Auth-Key {}", self.api_key))
+            .json(&json!({ "text": [text], "target_lang": target }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let translation = response
+            .translations
+            .into_iter()
+            .next()
+            .ok_or("DeepL returned no translation")?;
+        Ok(Translation {
+            text: translation.text,
+            source: translation.detected_source_language,
+        })
+    }
+}
+
+pub struct LibreTranslate {
+    url: String,
+    api_key: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LibreTranslateResponse {
+    translated_text: String,
+    detected_language: Option<LibreTranslateLanguage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LibreTranslateLanguage {
+    language: String,
+}
+
+#[async_trait]
+impl TranslationProvider for LibreTranslate {
+    fn name(&self) -> &'static str {
+        "LibreTranslate"
+    }
+
+    async fn translate(&self, text: &str, target: Language) -> Result<Translation, Error> {
+        let mut body = json!({
+            "q": text,
+            "source": "auto",
+            "target": target.code(),
+            "format": "text",
+        });
+        if let Some(api_key) = &self.api_key {
+            body["api_key"] = json!(api_key);
+        }
+        let response: LibreTranslateResponse = HTTP
+            .post(format!("{}/translate", self.url))
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(Translation {
+            text: response.translated_text,
+            source: response.detected_language.map(|x| x.language),
+        })
+    }
+}
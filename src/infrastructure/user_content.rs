@@ -0,0 +1,317 @@
+/*!
+
+Storage of the files uploaded by users, such as the images of member notifications.
+
+Files are content-addressed: they are named after the SHA-256 of their content and stored once in a directory shared by
+every guild, so an image uploaded by several guilds, or uploaded again with an edited notification, takes up space only
+once. The references to each file are counted in the `user_content_blobs` table (see
+[`crate::services::user_content::UserContentRepo`]), and a file is only removed from disk once nothing references it.
+
+Files uploaded before content-addressed storage keep their random name in the directory of their guild, and are removed
+directly as before.
+
+*/
+
+use std::{path::PathBuf, time::Duration};
+
+use poise::serenity_prelude::{Attachment, GuildId};
+use sea_orm::{DatabaseConnection, TransactionTrait};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
+use tracing::{trace, warn};
+use uuid::Uuid;
+
+use crate::{
+    Error,
+    infrastructure::{
+        botdata::Data,
+        environment::{get_data_directory, get_guild_user_content_directory},
+    },
+    services::{unit_of_work::UnitOfWork, user_content::UserContentRepo},
+};
+
+/// Length of a SHA-256 in hexadecimal.
+const HASH_LENGTH: usize = 64;
+
+/// Largest attachment downloaded as user content. Files are re-uploaded to the asset storage channel, and bots can't
+/// upload anything larger than this to discord.
+pub const MAX_ATTACHMENT_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Time allowed to download an attachment, from connecting until the whole file is received.
+pub const ATTACHMENT_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Directory of the content-addressed files, shared by every guild.
+pub fn get_user_content_blob_directory() -> PathBuf {
+    get_data_directory().join("user_content").join("blobs")
+}
+
+/// Names a file after the SHA-256 of its content, keeping the extension of the uploaded file.
+pub fn content_addressed_filename(hash: &str, original_filename: &str) -> String {
+    match std::path::Path::new(original_filename).extension() {
+        Some(ext) => format!("{}.{}", hash, ext.display()),
+        None => hash.to_string(),
+    }
+}
+
+/// Whether a stored filename is content-addressed, rather than a file uploaded before content-addressed storage.
+pub fn is_content_addressed(filename: &str) -> bool {
+    let hash = filename.split('.').next().unwrap_or_default();
+    hash.len() == HASH_LENGTH && hash.chars().all(|x| matches!(x, '0'..='9' | 'a'..='f'))
+}
+
+/// Gets the path of a user content file used by a guild.
+pub fn user_content_path(guild_id: GuildId, filename: &str) -> PathBuf {
+    if is_content_addressed(filename) {
+        get_user_content_blob_directory().join(filename)
+    } else {
+        get_guild_user_content_directory(guild_id).join(filename)
+    }
+}
+
+/// Removes a content-addressed file from disk if nothing references it.
+///
+/// The reference check and the removal happen in a write transaction, and uploads record their reference in a write
+/// transaction before putting the file in place (see [`create_file_from_attachment_safe`]). Either an upload of the
+/// same file waits until the file is removed and stores it again, or its reference is committed before the check.
+async fn remove_unreferenced_blob(db: &DatabaseConnection, filename: &str) -> Result<(), Error> {
+    let txn = db.begin().await?;
+    let repo = UserContentRepo::new(&txn);
+    repo.forget_unreferenced(filename).await?;
+    if !repo.is_referenced(filename).await? {
+        remove_file_if_exists(get_user_content_blob_directory().join(filename)).await?;
+    }
+    txn.commit().await?;
+    Ok(())
+}
+
+async fn remove_file_if_exists(path: PathBuf) -> Result<(), Error> {
+    match tokio::fs::remove_file(path).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Removes user content files from disk once nothing references them anymore, logging (but otherwise ignoring) any
+/// failures. Content-addressed files are only removed if they are no longer referenced, so their references must be
+/// released with [`UserContentRepo::release`] first.
+pub async fn remove_user_content_files(
+    db: DatabaseConnection,
+    guild_id: GuildId,
+    mut files: Vec<String>,
+) {
+    if files.is_empty() {
+        return;
+    }
+    files.sort();
+    files.dedup();
+
+    let mut errors: Vec<String> = vec![];
+    for file in files {
+        let result = if is_content_addressed(&file) {
+            remove_unreferenced_blob(&db, &file).await
+        } else {
+            remove_file_if_exists(user_content_path(guild_id, &file)).await
+        };
+        if let Err(e) = result {
+            errors.push(format!("{}: {}", file, e));
+        }
+    }
+
+    if !errors.is_empty() {
+        warn!(
+            "Encountered errors attempting to remove user content files: {}",
+            errors.join(", ")
+        );
+    }
+}
+
+/// Creates a file on disk for an attachment submitted via discord API, then returns the name of the file.
+///
+/// Files are content-addressed (see the [module documentation](self)): the attachment is written to a temporary file while it is hashed. A reference to the file is recorded in the unit of work, then the temporary file is renamed after its hash, or discarded if an identical file is already stored.
+///
+/// This method is 'safe', as in it ensures that a partially written file is removed if an error occurs, and that the created file is removed if the unit of work is rolled back and nothing else references it.
+///
+/// Since a discord attachment only contains a url to the content hosted on the discord CDN, this function will perform an HTTP request to download the content and write it to disk. The download is streamed and aborted once it exceeds [`MAX_ATTACHMENT_BYTES`] or [`ATTACHMENT_DOWNLOAD_TIMEOUT`].
+pub async fn create_file_from_attachment_safe(
+    data: &Data,
+    guild_id: &GuildId,
+    attachment: Attachment,
+    uow: &UnitOfWork,
+) -> Result<String, Error> {
+    #[derive(Debug)]
+    enum CreateAttachmentFileError {
+        DiscordApiError,
+        TooLarge(Option<String>),
+        TimedOut(Option<String>),
+        FlushError(String, crate::Error),
+        WriteError(String, crate::Error),
+        CreateFileError(crate::Error),
+    }
+
+    async fn try_create_file(
+        http: &reqwest::Client,
+        attachment: Attachment,
+    ) -> Result<(String, String), CreateAttachmentFileError> {
+        trace!("Creating file for attachment: {:?}", &attachment);
+        if u64::from(attachment.size) > MAX_ATTACHMENT_BYTES {
+            return Err(CreateAttachmentFileError::TooLarge(None));
+        }
+        let path = get_user_content_blob_directory();
+        trace!(
+            "Ensuring user content directory exists: {}",
+            &path.display()
+        );
+        tokio::fs::create_dir_all(&path)
+            .await
+            .map_err(|x| CreateAttachmentFileError::CreateFileError(x.into()))?;
+        let temp_filename = format!(".{}.tmp", Uuid::new_v4());
+        trace!("Downloading file attachment");
+        let mut response = http
+            .get(&attachment.url)
+            .timeout(ATTACHMENT_DOWNLOAD_TIMEOUT)
+            .send()
+            .await
+            .map_err(|x| match x.is_timeout() {
+                true => CreateAttachmentFileError::TimedOut(None),
+                false => CreateAttachmentFileError::DiscordApiError,
+            })?;
+        if !response.status().is_success() {
+            warn!("Discord returned non-success api response");
+            return Err(CreateAttachmentFileError::DiscordApiError);
+        }
+        if response
+            .content_length()
+            .is_some_and(|x| x > MAX_ATTACHMENT_BYTES)
+        {
+            return Err(CreateAttachmentFileError::TooLarge(None));
+        }
+        trace!("Response: {:?}", response);
+        trace!(
+            "Creating file: {} at path {}",
+            &path.display(),
+            &temp_filename
+        );
+        let mut file = tokio::fs::File::create_new(&path.join(&temp_filename))
+            .await
+            .map_err(|x| CreateAttachmentFileError::CreateFileError(x.into()))?;
+        let mut hasher = Sha256::new();
+        let mut written: u64 = 0;
+        while let Some(chunk) = response.chunk().await.map_err(|x| match x.is_timeout() {
+            true => CreateAttachmentFileError::TimedOut(Some(temp_filename.clone())),
+            false => CreateAttachmentFileError::WriteError(temp_filename.clone(), x.into()),
+        })? {
+            written += chunk.len() as u64;
+            if written > MAX_ATTACHMENT_BYTES {
+                return Err(CreateAttachmentFileError::TooLarge(Some(
+                    temp_filename.clone(),
+                )));
+            }
+            hasher.update(&chunk);
+            file.write_all(&chunk).await.map_err(|x| {
+                CreateAttachmentFileError::WriteError(temp_filename.clone(), x.into())
+            })?;
+        }
+        file.flush()
+            .await
+            .map_err(|x| CreateAttachmentFileError::FlushError(temp_filename.clone(), x.into()))?;
+
+        let filename =
+            content_addressed_filename(&format!("{:x}", hasher.finalize()), &attachment.filename);
+        Ok((temp_filename, filename))
+    }
+
+    /// Puts a downloaded file in place under its content-addressed name.
+    async fn store_file(temp_filename: &str, filename: &str) -> Result<(), Error> {
+        let path = get_user_content_blob_directory();
+        if tokio::fs::try_exists(path.join(filename))
+            .await
+            .unwrap_or(false)
+        {
+            trace!(
+                "File {} is already stored, discarding the duplicate",
+                filename
+            );
+            if let Err(e) = tokio::fs::remove_file(path.join(temp_filename)).await {
+                warn!("Failed to remove duplicate file {}: {:?}", temp_filename, e);
+            }
+        } else {
+            tokio::fs::rename(path.join(temp_filename), path.join(filename)).await?;
+        }
+        Ok(())
+    }
+
+    let attachment_name = attachment.filename.clone();
+    match try_create_file(&data.http, attachment).await {
+        Ok((temp_filename, file_name)) => {
+            // The reference is rolled back with the unit of work, leaving the file unreferenced unless shared
+            uow.on_rollback(remove_user_content_files(
+                data.db_pool.clone(),
+                *guild_id,
+                vec![file_name.clone()],
+            ));
+            // The reference is recorded before the file is put in place, so the file can't be removed in between
+            // because another notification released its last reference (see `remove_unreferenced_blob`)
+            let stored = async {
+                UserContentRepo::new(uow.txn())
+                    .add_reference(&file_name)
+                    .await?;
+                store_file(&temp_filename, &file_name).await
+            }
+            .await;
+            if let Err(e) = stored {
+                if let Err(e) =
+                    remove_file_if_exists(get_user_content_blob_directory().join(&temp_filename))
+                        .await
+                {
+                    warn!(
+                        "Failed to remove downloaded file {}: {:?}",
+                        temp_filename, e
+                    );
+                }
+                return Err(e);
+            }
+            Ok(file_name)
+        }
+        Err(error) => {
+            warn!("Failed to create file: {:?}", error);
+            let remove_file = match &error {
+                CreateAttachmentFileError::DiscordApiError => None,
+                CreateAttachmentFileError::TooLarge(f) => f.clone(),
+                CreateAttachmentFileError::TimedOut(f) => f.clone(),
+                CreateAttachmentFileError::FlushError(f, _) => Some(f.clone()),
+                CreateAttachmentFileError::WriteError(f, _) => Some(f.clone()),
+                CreateAttachmentFileError::CreateFileError(_) => None,
+            };
+
+            if let Some(f) = remove_file
+                && let Err(e) =
+                    tokio::fs::remove_file(get_user_content_blob_directory().join(&f)).await
+            {
+                warn!("Failed to remove partially written file {}: {:?}", f, e);
+            }
+
+            Err(match error {
+                CreateAttachmentFileError::DiscordApiError => None,
+                CreateAttachmentFileError::TooLarge(_) => Some(
+                    format!(
+                        "Attachment `{}` is larger than the {} MiB limit.",
+                        attachment_name,
+                        MAX_ATTACHMENT_BYTES / (1024 * 1024)
+                    )
+                    .into(),
+                ),
+                CreateAttachmentFileError::TimedOut(_) => Some(
+                    format!(
+                        "Downloading attachment `{}` timed out, please try again.",
+                        attachment_name
+                    )
+                    .into(),
+                ),
+                CreateAttachmentFileError::FlushError(_, e) => Some(e),
+                CreateAttachmentFileError::WriteError(_, e) => Some(e),
+                CreateAttachmentFileError::CreateFileError(e) => Some(e),
+            }
+            .unwrap_or_else(|| "Failed to save attachment.".into()))
+        }
+    }
+}
@@ -1,12 +1,18 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, time::Duration};
 
 use poise::{
     CreateReply,
-    serenity_prelude::{ChannelId, CreateMessage, Typing},
+    serenity_prelude::{
+        ButtonStyle, ChannelId, ComponentInteractionCollector, CreateActionRow, CreateButton,
+        CreateEmbed, CreateEmbedFooter, CreateInteractionResponse,
+        CreateInteractionResponseMessage, CreateMessage, Typing,
+    },
 };
-use tracing::trace;
+use tracing::{error, trace};
 
-use crate::{Context as ImposterbotContext, Error};
+use crate::{
+    Context as ImposterbotContext, Error, services::command_settings::CommandSettingsRepo,
+};
 
 /// Creates a lazily initialized static regex variable with a constant regex expression.
 #[macro_export]
@@ -122,6 +128,30 @@ pub async fn send_message_from_reply(
     Ok(())
 }
 
+/// Parses a duration such as `30m`, `2h` or `1d12h` into seconds. Returns None for invalid or zero durations.
+pub fn parse_duration(value: &str) -> Option<i64> {
+    let mut seconds: i64 = 0;
+    let mut amount = String::new();
+    for c in value.trim().chars() {
+        if c.is_ascii_digit() {
+            amount.push(c);
+            continue;
+        }
+        let unit = match c.to_ascii_lowercase() {
+            'd' => 60 * 60 * 24,
+            'h' => 60 * 60,
+            'm' => 60,
+            _ => return None,
+        };
+        let amount = std::mem::take(&mut amount).parse::<i64>().ok()?;
+        seconds = seconds.saturating_add(amount.saturating_mul(unit));
+    }
+    if !amount.is_empty() || seconds == 0 {
+        return None;
+    }
+    Some(seconds)
+}
+
 /// Appropriately indicates to the end user that imposterbot is working on a response.
 /// - For Application (/) commands, this is a message in response to the interation that says "Imposterbot is thinking..."
 /// - For prefix commands, this is indicated by "Imposterbot is typing" hint, as if a real person is typing a message.
@@ -145,3 +175,157 @@ pub async fn defer_or_broadcast(
         )),
     }
 }
+
+/// Resolves whether the reply of the invoked command is ephemeral, given the `ephemeral` option the user chose and
+/// the default of the command.
+///
+/// Guilds can force the replies of a command to be ephemeral or public with `/configure ephemeral`, in which case the
+/// option of the user is ignored. Errors are treated as if the guild left the choice to the user.
+pub async fn resolve_ephemeral(
+    ctx: ImposterbotContext<'_>,
+    explicit: Option<bool>,
+    default: bool,
+) -> bool {
+    let Some(guild_id) = ctx.guild_id() else {
+        return explicit.unwrap_or(default);
+    };
+    let command = &ctx.command().qualified_name;
+    match CommandSettingsRepo::new(&ctx.data().db_pool)
+        .ephemeral_mode(guild_id, command)
+        .await
+    {
+        Ok(mode) => mode.resolve(explicit, default),
+        Err(e) => {
+            error!("Failed to get ephemeral mode of command {}: {}", command, e);
+            explicit.unwrap_or(default)
+        }
+    }
+}
+
+/// Posts a list of embeds as a single message with first/previous/next/last buttons.
+///
+/// Only the invoking user can change pages. The buttons are removed once they have not been pressed for the timeout.
+pub struct Paginator {
+    pages: Vec<CreateEmbed>,
+    timeout: Duration,
+    ephemeral: bool,
+}
+
+impl Paginator {
+    pub fn new(pages: Vec<CreateEmbed>) -> Self {
+        Self {
+            pages,
+            timeout: Duration::from_secs(300),
+            ephemeral: false,
+        }
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn ephemeral(mut self, ephemeral: bool) -> Self {
+        self.ephemeral = ephemeral;
+        self
+    }
+
+    fn page(&self, index: usize) -> CreateEmbed {
+        self.pages[index]
+            .clone()
+            .footer(CreateEmbedFooter::new(format!(
+                "Page {}/{}",
+                index + 1,
+                self.pages.len()
+            )))
+    }
+
+    fn buttons(&self, id: u64, index: usize) -> Vec<CreateActionRow> {
+        let last = self.pages.len() - 1;
+        let button = |action: &str, emoji: char, disabled: bool| {
+            CreateButton::new(format!("{}{}", id, action))
+                .emoji(emoji)
+                .style(ButtonStyle::Secondary)
+                .disabled(disabled)
+        };
+        vec![CreateActionRow::Buttons(vec![
+            button("first", '⏮', index == 0),
+            button("prev", '◀', index == 0),
+            button("next", '▶', index == last),
+            button("last", '⏭', index == last),
+        ])]
+    }
+
+    /// Sends the first page and handles page changes until the timeout elapses.
+    pub async fn send(self, ctx: ImposterbotContext<'_>) -> Result<(), Error> {
+        if self.pages.is_empty() {
+            return Err("Nothing to show".into());
+        }
+        if self.pages.len() == 1 {
+            let reply = CreateReply::default()
+                .embed(self.pages[0].clone())
+                .ephemeral(self.ephemeral);
+            trace!("Sending reply: {:?}", DebuggableReply::new(&reply));
+            ctx.send(reply).await?;
+            return Ok(());
+        }
+
+        let id = ctx.id();
+        let mut index = 0;
+        let reply = CreateReply::default()
+            .embed(self.page(index))
+            .components(self.buttons(id, index))
+            .ephemeral(self.ephemeral);
+        trace!("Sending reply: {:?}", DebuggableReply::new(&reply));
+        let handle = ctx.send(reply).await?;
+
+        while let Some(press) = ComponentInteractionCollector::new(ctx)
+            .filter(move |press| press.data.custom_id.starts_with(&id.to_string()))
+            .timeout(self.timeout)
+            .await
+        {
+            if press.user.id != ctx.author().id {
+                press
+                    .create_response(
+                        ctx,
+                        CreateInteractionResponse::Message(
+                            CreateInteractionResponseMessage::new()
+                                .content("Only the user who ran this command can change pages.")
+                                .ephemeral(true),
+                        ),
+                    )
+                    .await?;
+                continue;
+            }
+
+            let last = self.pages.len() - 1;
+            index = match press.data.custom_id.trim_start_matches(&id.to_string()) {
+                "first" => 0,
+                "prev" => index.saturating_sub(1),
+                "next" => (index + 1).min(last),
+                "last" => last,
+                _ => index,
+            };
+            press
+                .create_response(
+                    ctx,
+                    CreateInteractionResponse::UpdateMessage(
+                        CreateInteractionResponseMessage::new()
+                            .embed(self.page(index))
+                            .components(self.buttons(id, index)),
+                    ),
+                )
+                .await?;
+        }
+
+        handle
+            .edit(
+                ctx,
+                CreateReply::default()
+                    .embed(self.page(index))
+                    .components(vec![]),
+            )
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+/*!
+
+Fetching of web pages linked in messages, to read their title and OpenGraph metadata.
+
+Pages are fetched with a short timeout and only their start is read, since the metadata is in the `<head>` of the
+page. Only html pages are read, and only from public addresses since the links are posted by members.
+
+*/
+
+use std::{collections::HashMap, time::Duration};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    Error,
+    infrastructure::public_http::{check_public_url, public_http_client},
+    lazy_regex,
+};
+
+lazy_regex! { LINK_REGEX, r"https?://[^\s<>]+" }
+lazy_regex! { TITLE_REGEX, r"(?is)<title[^>]*>(.*?)</title>" }
+lazy_regex! { META_REGEX, r"(?is)<meta\s[^>]*>" }
+lazy_regex! { ATTRIBUTE_REGEX, r#"(?is)([a-z][a-z0-9:_-]*)\s*=\s*(?:"([^"]*)"|'([^']*)')"# }
+
+/// Only the start of a page is read to find its metadata.
+const MAX_PAGE_BYTES: usize = 64 * 1024;
+
+const PAGE_TIMEOUT: Duration = Duration::from_secs(5);
+
+static HTTP: Lazy<reqwest::Client> = Lazy::new(|| public_http_client(PAGE_TIMEOUT));
+
+/// The http(s) links in a text, in order.
+pub fn find_links(text: &str) -> impl Iterator<Item = regex::Match<'_>> {
+    LINK_REGEX.find_iter(text)
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+}
+
+/// Fetches the start of an html page, reading at most [`MAX_PAGE_BYTES`]. Returns None for other content types.
+pub async fn fetch_html(url: &str) -> Result<Option<String>, Error> {
+    let url = reqwest::Url::parse(url)?;
+    check_public_url(&url)?;
+    let mut response = HTTP.get(url).send().await?.error_for_status()?;
+    let is_html = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|x| x.to_str().ok())
+        .is_some_and(|x| x.contains("text/html"));
+    if !is_html {
+        return Ok(None);
+    }
+
+    let mut body = Vec::new();
+    while body.len() < MAX_PAGE_BYTES
+        && let Some(chunk) = response.chunk().await?
+    {
+        body.extend_from_slice(&chunk);
+    }
+    Ok(Some(String::from_utf8_lossy(&body).into_owned()))
+}
+
+/// The contents of the `<title>` tag of a page.
+pub fn page_title(html: &str) -> Option<String> {
+    TITLE_REGEX
+        .captures(html)
+        .map(|x| decode_entities(x[1].trim()))
+}
+
+/// The OpenGraph properties of a page, such as `og:title`, by property name.
+///
+/// The first value of a property wins. `twitter:` properties are included, since some sites only set those.
+pub fn open_graph(html: &str) -> HashMap<String, String> {
+    let mut properties = HashMap::new();
+    for tag in META_REGEX.find_iter(html) {
+        let mut name = None;
+        let mut content = None;
+        for attribute in ATTRIBUTE_REGEX.captures_iter(tag.as_str()) {
+            let value = attribute.get(2).or(attribute.get(3)).map(|x| x.as_str());
+            match attribute[1].to_lowercase().as_str() {
+                "property" | "name" => name = value.map(|x| x.to_lowercase()),
+                "content" => content = value.map(decode_entities),
+                _ => {}
+            }
+        }
+        if let (Some(name), Some(content)) = (name, content)
+            && (name.starts_with("og:") || name.starts_with("twitter:"))
+        {
+            properties.entry(name).or_insert(content);
+        }
+    }
+    properties
+}
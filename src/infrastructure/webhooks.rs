@@ -0,0 +1,250 @@
+/*!
+
+Outgoing webhooks: guild events POSTed as JSON to the URLs registered with `/webhook`.
+
+Each payload is signed with the secret of the webhook, an HMAC-SHA256 of the body sent in the
+`X-Imposterbot-Signature` header as `sha256=<hex>`, so receivers can check that the payload came from the bot.
+Webhooks are delivered with the [public http client](crate::infrastructure::public_http), so they can't reach
+addresses internal to the network the bot runs in.
+Deliveries that fail with a network error, a 5xx or a 429 are retried with exponential backoff, and the outcome of every
+delivery is logged in the `webhook_deliveries` table.
+
+*/
+
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use once_cell::sync::Lazy;
+use poise::serenity_prelude::{GuildId, UserId, async_trait, futures};
+use reqwest::{StatusCode, Url};
+use sea_orm::DatabaseConnection;
+use serde_json::json;
+use sha2::Sha256;
+use tracing::{debug, warn};
+
+use crate::{
+    Error,
+    infrastructure::{
+        clock::{Clock, SharedClock},
+        public_http::{check_public_url, public_http_client},
+        retention::RetentionTask,
+    },
+    services::webhooks::{Delivery, Webhook, WebhookEvent, WebhookRepo},
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum number of attempts of a delivery.
+const MAX_ATTEMPTS: u32 = 4;
+
+/// Wait before the first retry, doubled after every failed attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+
+pub const EVENT_HEADER: &str = "X-Imposterbot-Event";
+pub const SIGNATURE_HEADER: &str = "X-Imposterbot-Signature";
+pub const DELIVERY_HEADER: &str = "X-Imposterbot-Delivery";
+
+const SECONDS_PER_DAY: i64 = 60 * 60 * 24;
+
+static HTTP: Lazy<reqwest::Client> = Lazy::new(|| public_http_client(REQUEST_TIMEOUT));
+
+/// HMAC-SHA256 of a payload, as lowercase hex.
+pub fn sign(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("{:x}", mac.finalize().into_bytes())
+}
+
+/// POSTs a payload to a webhook, retrying failures that may be temporary.
+async fn deliver(webhook: &Webhook, event: WebhookEvent, body: &[u8]) -> Delivery {
+    // Webhooks registered before urls were checked may point anywhere, so they're checked again on every delivery
+    let url = match Url::parse(&webhook.url)
+        .map_err(|e| e.to_string())
+        .and_then(|x| check_public_url(&x).map(|_| x))
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return Delivery {
+                webhook_id: webhook.id,
+                event,
+                attempts: 0,
+                status_code: None,
+                error: Some(e),
+            };
+        }
+    };
+    let signature = format!("sha256={}", sign(webhook.secret.as_bytes(), body));
+    // Retries share the id, so receivers can tell them apart from new events
+    let delivery_id = uuid::Uuid::new_v4().to_string();
+    let mut backoff = INITIAL_BACKOFF;
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let result = HTTP
+            .post(url.clone())
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, event.as_str())
+            .header(SIGNATURE_HEADER, &signature)
+            .header(DELIVERY_HEADER, &delivery_id)
+            .body(body.to_vec())
+            .send()
+            .await;
+        let (status_code, error, retry) = match result {
+            Ok(response) if response.status().is_success() => {
+                (Some(response.status().as_u16()), None, false)
+            }
+            Ok(response) => {
+                let status = response.status();
+                (
+                    Some(status.as_u16()),
+                    Some(format!("Responded with {}", status)),
+                    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS,
+                )
+            }
+            Err(e) => (None, Some(e.to_string()), true),
+        };
+        if !retry || attempts >= MAX_ATTEMPTS {
+            return Delivery {
+                webhook_id: webhook.id,
+                event,
+                attempts,
+                status_code,
+                error,
+            };
+        }
+        debug!(
+            "Webhook {} delivery attempt {} failed, retrying in {:?}",
+            webhook.id, attempts, backoff
+        );
+        tokio::time::sleep(backoff).await;
+        backoff *= 2;
+    }
+}
+
+/// Delivers an event to every webhook of a guild subscribed to it, and logs the deliveries.
+async fn deliver_event(
+    db: &DatabaseConnection,
+    clock: &dyn Clock,
+    guild_id: GuildId,
+    event: WebhookEvent,
+    timestamp: i64,
+    data: serde_json::Value,
+) -> Result<(), Error> {
+    let repo = WebhookRepo::new(db);
+    let webhooks = repo.subscribed(guild_id, event).await?;
+    if webhooks.is_empty() {
+        return Ok(());
+    }
+
+    let body = serde_json::to_vec(&json!({
+        "event": event.as_str(),
+        "guild_id": guild_id.to_string(),
+        "timestamp": timestamp,
+        "data": data,
+    }))?;
+    let deliveries =
+        futures::future::join_all(webhooks.iter().map(|x| deliver(x, event, &body))).await;
+    for delivery in deliveries {
+        if let Some(error) = &delivery.error {
+            warn!(
+                "Failed to deliver {} to webhook {} of guild {} after {} attempt(s): {}",
+                event.as_str(),
+                delivery.webhook_id,
+                guild_id,
+                delivery.attempts,
+                error
+            );
+        }
+        repo.record_delivery(guild_id, &delivery, clock.unix_timestamp())
+            .await?;
+    }
+    Ok(())
+}
+
+/// Sends an event to the webhooks of a guild in the background, so retries never hold up the caller.
+///
+/// `data` holds the details of the event, sent along with its name, the guild and the current time.
+pub fn dispatch(
+    db: &DatabaseConnection,
+    clock: &SharedClock,
+    guild_id: GuildId,
+    event: WebhookEvent,
+    data: serde_json::Value,
+) {
+    let db = db.clone();
+    let clock = clock.clone();
+    let timestamp = clock.unix_timestamp();
+    tokio::spawn(async move {
+        if let Err(e) = deliver_event(&db, clock.as_ref(), guild_id, event, timestamp, data).await {
+            warn!(
+                "Failed to deliver {} to the webhooks of guild {}: {:?}",
+                event.as_str(),
+                guild_id,
+                e
+            );
+        }
+    });
+}
+
+/// Expires the delivery log, which is kept for a month by default. Deliveries don't store the members involved.
+pub struct WebhookDeliveryRetention;
+
+#[async_trait]
+impl RetentionTask for WebhookDeliveryRetention {
+    fn name(&self) -> &'static str {
+        "webhook_deliveries"
+    }
+
+    fn default_retention_days(&self) -> Option<u32> {
+        Some(30)
+    }
+
+    async fn expire(
+        &self,
+        db: &DatabaseConnection,
+        now: i64,
+        retention_days: u32,
+    ) -> Result<u64, Error> {
+        WebhookRepo::new(db)
+            .remove_deliveries_before(now - retention_days as i64 * SECONDS_PER_DAY)
+            .await
+    }
+
+    async fn forget_user(&self, _db: &DatabaseConnection, _user_id: UserId) -> Result<u64, Error> {
+        // Deliveries don't store who triggered the event
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signatures_match_rfc_4231() {
+        // Test cases 1, 2, 6 and 7 of RFC 4231, which use the whole HMAC-SHA256 output
+        assert_eq!(
+            sign(&[0x0b; 20], b"Hi There"),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+        assert_eq!(
+            sign(b"Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            sign(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            ),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+        assert_eq!(
+            sign(
+                &[0xaa; 131],
+                b"This is a test using a larger than block-size key and a larger than block-size data. The key \
+                  needs to be hashed before being used by the HMAC algorithm."
+            ),
+            "9b09ffa71b942fcb27635fbcd5b0e944bfdc63644f0713938a7f51535c3a35e2"
+        );
+    }
+}
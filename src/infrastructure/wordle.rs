@@ -0,0 +1,258 @@
+/*!
+
+The daily word game of `/wordle`.
+
+Every guild gets its own word each day, picked from [`WORDLE_WORDS`] by hashing the guild and the day, so the word
+doesn't need to be stored and is the same for every member of the guild. Days start at midnight UTC. Members have
+[`MAX_GUESSES`] guesses of five letters to find the word, each answered with a row of colored squares: green for a
+letter in the right place, yellow for a letter elsewhere in the word and black for a letter that isn't in it.
+
+Guilds with a leaderboard channel get the results of the previous day posted there by the [`WordleLeaderboardJob`]
+shortly after midnight, along with the word.
+
+*/
+
+use poise::serenity_prelude::{ChannelId, CreateMessage, GuildId, Http, Mentionable, async_trait};
+use sea_orm::DatabaseConnection;
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+
+use crate::{
+    Error,
+    infrastructure::{
+        branding::load_branding,
+        jobs::{JobContext, JobHandler},
+    },
+    services::wordle::{WordleGame, WordleRepo},
+};
+
+const SECONDS_PER_DAY: i64 = 60 * 60 * 24;
+
+pub const WORD_LENGTH: usize = 5;
+
+pub const MAX_GUESSES: usize = 6;
+
+/// Most players listed on the daily leaderboard.
+const MAX_LEADERBOARD_PLAYERS: usize = 10;
+
+/// Cron schedule of the leaderboard posts, a few minutes after the day ends.
+const LEADERBOARD_SCHEDULE: &str = "5 0 * * *";
+
+/// Words of the game, in lowercase.
+pub const WORDLE_WORDS: &[&str] = &[
+    "about", "actor", "adult", "agent", "alarm", "album", "alert", "alien", "apple", "arena",
+    "badge", "baker", "beach", "beard", "blade", "blame", "blank", "blast", "blend", "block",
+    "bloom", "board", "boost", "brain", "brave", "bread", "brick", "bride", "brush", "build",
+    "cabin", "camel", "candy", "cargo", "chain", "chair", "chalk", "charm", "chase", "cheek",
+    "chess", "chief", "child", "cider", "claim", "class", "clean", "clerk", "climb", "clock",
+    "cloud", "coach", "coast", "coral", "couch", "crane", "crash", "cream", "crowd", "crown",
+    "curve", "dance", "delta", "depth", "diary", "dough", "draft", "drain", "dream", "drift",
+    "drink", "eagle", "earth", "elbow", "ember", "empty", "fable", "faint", "feast", "fence",
+    "fever", "field", "flame", "flask", "fleet", "flock", "flour", "fluid", "focus", "forge",
+    "frame", "fresh", "frost", "fruit", "ghost", "giant", "glass", "globe", "glove", "grape",
+    "grass", "great", "guard", "guide", "habit", "heart", "honey", "horse", "hotel", "house",
+    "image", "index", "input", "ivory", "jelly", "jewel", "juice", "knife", "label", "lemon",
+    "light", "linen", "lodge", "lucky", "lunar", "magic", "mango", "maple", "march", "medal",
+    "metal", "minor", "model", "money", "month", "motor", "mouse", "music", "nerve", "night",
+    "noble", "novel", "ocean", "olive", "orbit", "order", "paint", "panel", "party", "peace",
+    "pearl", "piano", "pilot", "pizza", "plane", "plant", "plate", "point", "power", "press",
+    "pride", "prism", "quest", "quiet", "radar", "raven", "river", "robin", "robot", "round",
+    "royal", "salad", "scale", "scout", "shade", "shelf", "shell", "shirt", "skill", "slice",
+    "smile", "smoke", "snake", "solar", "sound", "space", "spark", "spice", "spoon", "sport",
+    "squad", "stack", "stage", "steam", "stone", "storm", "story", "sugar", "sweet", "sword",
+    "table", "tiger", "toast", "tower", "trace", "track", "trail", "train", "treat", "trend",
+    "tribe", "truck", "tulip", "unity", "vault", "venom", "video", "vivid", "voice", "wagon",
+    "watch", "water", "whale", "wheat", "wheel", "world", "yacht", "youth", "zebra",
+];
+
+/// Day of a unix timestamp, as the number of days since the unix epoch.
+pub fn day_of(timestamp: i64) -> i64 {
+    timestamp.div_euclid(SECONDS_PER_DAY)
+}
+
+/// Word of a guild on a day.
+pub fn daily_word(guild_id: GuildId, day: i64) -> &'static str {
+    let digest = Sha256::digest(format!("{}:{}", guild_id, day).as_bytes());
+    let mut seed = [0u8; 8];
+    seed.copy_from_slice(&digest[..8]);
+    WORDLE_WORDS[(u64::from_le_bytes(seed) % WORDLE_WORDS.len() as u64) as usize]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LetterScore {
+    /// The letter is in the word at this place.
+    Correct,
+    /// The letter is in the word at another place.
+    Present,
+    Absent,
+}
+
+impl LetterScore {
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            Self::Correct => "🟩",
+            Self::Present => "🟨",
+            Self::Absent => "⬛",
+        }
+    }
+}
+
+/// Scores each letter of a guess. Letters repeated in the guess are only marked present as many times as the word
+/// contains them, after the letters in the right place.
+pub fn score_guess(guess: &str, word: &str) -> Vec<LetterScore> {
+    let guess: Vec<char> = guess.chars().collect();
+    let word: Vec<char> = word.chars().collect();
+    let mut scores = vec![LetterScore::Absent; guess.len()];
+    let mut unmatched: Vec<char> = vec![];
+    for (i, c) in word.iter().enumerate() {
+        if guess.get(i) == Some(c) {
+            scores[i] = LetterScore::Correct;
+        } else {
+            unmatched.push(*c);
+        }
+    }
+    for (i, c) in guess.iter().enumerate() {
+        if scores[i] == LetterScore::Correct {
+            continue;
+        }
+        if let Some(position) = unmatched.iter().position(|x| x == c) {
+            unmatched.remove(position);
+            scores[i] = LetterScore::Present;
+        }
+    }
+    scores
+}
+
+/// Checks a guess, returning it in lowercase.
+pub fn validate_guess(guess: &str) -> Result<String, String> {
+    let guess = guess.trim().to_lowercase();
+    if guess.chars().count() != WORD_LENGTH || !guess.chars().all(|c| c.is_ascii_lowercase()) {
+        return Err(format!(
+            "Guesses must be words of {} letters, without accents.",
+            WORD_LENGTH
+        ));
+    }
+    Ok(guess)
+}
+
+/// Rows of squares of the guesses of a game, without the letters, to share the game without spoiling the word.
+pub fn result_grid(game: &WordleGame, word: &str) -> String {
+    game.guesses
+        .iter()
+        .map(|guess| {
+            score_guess(guess, word)
+                .iter()
+                .map(|x| x.emoji())
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Rows of squares of the guesses of a game, each followed by the letters of the guess.
+pub fn board(game: &WordleGame, word: &str) -> String {
+    game.guesses
+        .iter()
+        .map(|guess| {
+            let squares: String = score_guess(guess, word).iter().map(|x| x.emoji()).collect();
+            let letters: Vec<String> = guess.to_uppercase().chars().map(String::from).collect();
+            format!("{} `{}`", squares, letters.join(" "))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Score of a finished game, like `4/6` or `X/6`.
+pub fn score_label(game: &WordleGame) -> String {
+    if game.solved {
+        format!("{}/{}", game.guesses.len(), MAX_GUESSES)
+    } else {
+        format!("X/{}", MAX_GUESSES)
+    }
+}
+
+/// Posts the results of a day in the leaderboard channel of a guild. Days without finished games are skipped.
+async fn post_leaderboard(
+    http: &Http,
+    db: &DatabaseConnection,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    day: i64,
+) -> Result<(), Error> {
+    let mut games = WordleRepo::new(db).finished_games(guild_id, day).await?;
+    if games.is_empty() {
+        debug!(
+            "Nobody played the wordle of guild {} on day {}",
+            guild_id, day
+        );
+        return Ok(());
+    }
+    let players = games.len();
+    let solved = games.iter().filter(|(_, x)| x.solved).count();
+    // Winners first, by their number of guesses, then by who finished first
+    games.sort_by_key(|(_, x)| (!x.solved, x.guesses.len(), x.finished_at));
+    let mut lines: Vec<String> = games
+        .iter()
+        .take(MAX_LEADERBOARD_PLAYERS)
+        .enumerate()
+        .map(|(i, (user_id, game))| {
+            let medal = match i {
+                0 if game.solved => "🥇".to_string(),
+                1 if game.solved => "🥈".to_string(),
+                2 if game.solved => "🥉".to_string(),
+                _ => format!("{}.", i + 1),
+            };
+            format!("{} {} — {}", medal, user_id.mention(), score_label(game))
+        })
+        .collect();
+    if players > MAX_LEADERBOARD_PLAYERS {
+        lines.push(format!("...and {} more", players - MAX_LEADERBOARD_PLAYERS));
+    }
+    let branding = load_branding(db, guild_id).await.unwrap_or_default();
+    let embed = branding
+        .embed()
+        .title("Yesterday's wordle")
+        .description(format!(
+            "The word was **{}**. {} of {} players found it.\n\n{}",
+            daily_word(guild_id, day).to_uppercase(),
+            solved,
+            players,
+            lines.join("\n")
+        ));
+    channel_id
+        .send_message(http, CreateMessage::new().embed(embed))
+        .await?;
+    Ok(())
+}
+
+/// Posts the results of the previous day in the leaderboard channel of every guild that configured one.
+pub struct WordleLeaderboardJob;
+
+#[async_trait]
+impl JobHandler for WordleLeaderboardJob {
+    fn kind(&self) -> &'static str {
+        "wordle_leaderboard"
+    }
+
+    fn schedule(&self) -> Option<&'static str> {
+        Some(LEADERBOARD_SCHEDULE)
+    }
+
+    // Retrying would post the leaderboards of the guilds that succeeded again
+    fn max_attempts(&self) -> u32 {
+        1
+    }
+
+    async fn run(&self, ctx: &JobContext, _payload: &str) -> Result<(), Error> {
+        let day = day_of(ctx.clock.unix_timestamp()) - 1;
+        for (guild_id, channel_id) in WordleRepo::new(&ctx.db).leaderboard_channels().await? {
+            if let Err(e) = post_leaderboard(&ctx.http, &ctx.db, guild_id, channel_id, day).await {
+                warn!(
+                    "Failed to post the wordle leaderboard of guild {}: {:?}",
+                    guild_id, e
+                );
+            }
+        }
+        Ok(())
+    }
+}
@@ -3,28 +3,176 @@ use crate::infrastructure::botdata;
 pub mod entities;
 
 pub mod commands {
+    pub mod activity;
+    #[cfg(feature = "ai")]
+    pub mod ai;
+    pub mod alias;
+    pub mod announce;
+    pub mod audit;
+    pub mod autoresponse;
+    pub mod ban_sync;
+    pub mod blocklist;
+    pub mod branding;
     pub mod builtins;
+    pub mod character;
     pub mod coinflip;
+    pub mod colorrole;
+    pub mod confess;
+    pub mod configure;
+    pub mod custom_media;
+    pub mod database;
+    pub mod define;
+    pub mod embed;
+    pub mod emojistats;
+    pub mod fun;
+    pub mod gallery;
+    pub mod game;
+    pub mod game_server;
+    pub mod gamenight;
+    pub mod gamestats;
+    pub mod global_commands;
+    pub mod jobs;
+    pub mod log;
+    #[cfg(feature = "voice")]
+    pub mod lyrics;
     pub mod member_management;
     pub mod minecraft;
+    pub mod movie_night;
+    pub mod namehistory;
+    pub mod ping;
+    #[cfg(feature = "youtube")]
+    pub mod playlist;
+    pub mod privacy;
+    pub mod quickpoll;
+    pub mod raffle;
+    pub mod reaction_map;
     pub mod roll;
+    pub mod snipe;
+    pub mod timestamp;
+    pub mod translate;
+    pub mod unfurl;
     #[cfg(feature = "voice")]
     pub mod voice;
+    #[cfg(feature = "youtube")]
+    pub mod voice_limits;
+    pub mod voicestats;
+    pub mod webhook;
+    pub mod wordle;
 }
 
 pub mod infrastructure {
+    pub mod asset_storage;
+    pub mod audit_log;
+    pub mod blocklist;
+    pub mod bot_lists;
     pub mod botdata;
+    pub mod branding;
+    pub mod channel_validation;
+    pub mod character_sheets;
+    pub mod clock;
+    pub mod color_roles;
     pub mod colors;
+    pub mod confessions;
+    pub mod cron;
+    pub mod embed_limits;
     pub mod environment;
     pub mod event_handler;
+    pub mod game_nights;
+    pub mod game_sessions;
+    pub mod global_commands;
+    pub mod guild_media;
+    pub mod heartbeat;
     pub mod ids;
+    pub mod jobs;
+    pub mod latency;
+    pub mod leader;
+    pub mod localization;
+    pub mod log_filter;
+    pub mod log_privacy;
+    pub mod mc_format;
+    pub mod mc_monitor;
+    #[cfg(feature = "voice")]
+    pub mod media_library;
+    pub mod media_servers;
+    pub mod mod_digest;
+    pub mod mod_log;
+    pub mod modpacks;
+    pub mod modules;
+    pub mod play_history;
+    pub mod prefix_args;
+    pub mod public_http;
+    pub mod random;
+    pub mod recent_invocations;
+    pub mod retention;
+    pub mod scheduler;
+    pub mod server_ping;
+    pub mod timestamps;
+    pub mod translation;
+    pub mod user_content;
     pub mod util;
+    pub mod web_page;
+    pub mod webhooks;
+    pub mod wordle;
 }
 
 pub mod events {
+    #[cfg(feature = "ai")]
+    pub mod ai_chat;
+    pub mod aliases;
+    pub mod auto_slowmode;
+    pub mod ban_sync;
+    pub mod channel_activity;
+    pub mod emoji_stats;
+    pub mod gallery;
+    pub mod games;
     pub mod guild_member;
+    pub mod interaction;
     pub mod message;
+    pub mod name_history;
+    pub mod nickname_filter;
+    pub mod raid_protection;
+    pub mod reaction_map;
+    pub mod responders;
+    pub mod screening;
+    pub mod snipe;
+    pub mod translation;
+    pub mod unfurl;
+    pub mod voice_activity;
+    pub mod voice_announcements;
+    pub mod webhooks;
+}
+
+pub mod services {
+    #[cfg(feature = "ai")]
+    pub mod ai_settings;
+    pub mod ban_sync;
+    pub mod bot_settings;
+    pub mod channel_activity;
+    pub mod character_sheets;
+    pub mod coinflip;
+    pub mod command_settings;
+    pub mod game_nights;
+    pub mod game_servers;
+    pub mod guild_media;
+    pub mod mc_servers;
+    pub mod mod_digest;
+    pub mod movie_night;
+    pub mod name_history;
+    pub mod notifications;
+    pub mod playlists;
+    pub mod roles;
+    pub mod roll_history;
+    pub mod saved_embeds;
+    pub mod screening;
+    #[cfg(test)]
+    pub mod testing;
+    pub mod unit_of_work;
+    pub mod user_content;
+    pub mod user_timezones;
+    pub mod webhooks;
+    pub mod wordle;
 }
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
 pub type Context<'a> = poise::Context<'a, botdata::Data, Error>;
+pub type ApplicationContext<'a> = poise::ApplicationContext<'a, botdata::Data, Error>;
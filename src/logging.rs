@@ -1,8 +1,12 @@
 use std::path::PathBuf;
 
-use imposterbot::infrastructure::environment::{self, get_data_directory};
+use imposterbot::infrastructure::{
+    environment::{self, get_data_directory, log_privacy_enabled},
+    log_filter::{DEFAULT_LOG_FILTER, install_log_filter},
+    log_privacy::{RedactingFields, set_privacy_mode},
+};
 use tracing::{error, info};
-use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, reload, util::SubscriberInitExt};
 
 /// Initializes the logger and returns a boxed reference to resources that if dropped will stop the logger.
 pub fn init_logger() -> Box<dyn std::any::Any> {
@@ -39,7 +43,14 @@ fn init_tracing() -> Box<dyn std::any::Any> {
     let (non_blocking_writer, guard) = tracing_appender::non_blocking(file_appender);
 
     let env_filter = EnvFilter::try_from_env(environment::LOG_LEVEL)
-        .unwrap_or_else(|_| EnvFilter::new("warn,imposterbot=info"));
+        .unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_FILTER));
+    // The filter can be swapped at runtime with `/log level`
+    let initial_filter = env_filter.to_string();
+    let (env_filter, filter_handle) = reload::Layer::new(env_filter);
+    install_log_filter(filter_handle, initial_filter);
+
+    // User identifiers and message content are redacted by the field formatter while privacy mode is enabled
+    set_privacy_mode(log_privacy_enabled());
 
     let do_log_path = get_log_path_var();
     tracing_subscriber::registry()
@@ -49,6 +60,7 @@ fn init_tracing() -> Box<dyn std::any::Any> {
             fmt::layer()
                 .with_writer(non_blocking_writer)
                 .with_ansi(false)
+                .fmt_fields(RedactingFields)
                 .with_file(do_log_path)
                 .with_line_number(do_log_path)
                 .with_target(!do_log_path)
@@ -59,6 +71,7 @@ fn init_tracing() -> Box<dyn std::any::Any> {
             fmt::layer()
                 .with_writer(std::io::stdout)
                 .with_ansi(true)
+                .fmt_fields(RedactingFields)
                 .with_file(do_log_path)
                 .with_line_number(do_log_path)
                 .with_target(!do_log_path)
@@ -3,11 +3,13 @@ mod database;
 mod logging;
 mod shutdown;
 
+use imposterbot::infrastructure::modules;
 use tracing::info;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let _guard = logging::init_logger();
+    modules::install(client::bot_modules());
     let db = database::init_database().await?;
 
     let mut client = client::create_serenity_client(db).await?;
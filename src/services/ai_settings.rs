@@ -0,0 +1,130 @@
+/*!
+
+Data access for the conversational replies of the `ai` feature: whether a guild enabled them, and how many tokens of
+its daily budget were used.
+
+Usage is counted per day since the unix epoch, and starts over on the first reply of a new day.
+
+*/
+
+use migration::OnConflict;
+use poise::serenity_prelude::GuildId;
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    sea_query::Expr,
+};
+
+use crate::{Error, entities::ai_settings, infrastructure::ids::id_to_string};
+
+pub const DEFAULT_DAILY_TOKEN_BUDGET: u32 = 20_000;
+
+const SECONDS_PER_DAY: i64 = 60 * 60 * 24;
+
+/// Gets the day a unix timestamp falls on, counted in days since the unix epoch.
+pub fn budget_day(timestamp: i64) -> i64 {
+    timestamp.div_euclid(SECONDS_PER_DAY)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AiSettings {
+    pub enabled: bool,
+    pub daily_token_budget: u32,
+    /// Tokens used today.
+    pub tokens_used: u32,
+}
+
+impl Default for AiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            daily_token_budget: DEFAULT_DAILY_TOKEN_BUDGET,
+            tokens_used: 0,
+        }
+    }
+}
+
+impl AiSettings {
+    fn from_model(model: ai_settings::Model, today: i64) -> Self {
+        Self {
+            enabled: model.enabled,
+            daily_token_budget: model.daily_token_budget.max(0) as u32,
+            tokens_used: if model.budget_day == today {
+                model.tokens_used.max(0) as u32
+            } else {
+                0
+            },
+        }
+    }
+
+    pub fn budget_exhausted(&self) -> bool {
+        self.tokens_used >= self.daily_token_budget
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AiSettingsRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> AiSettingsRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    /// Gets the settings of a guild, with the tokens used on the day of the `now` unix timestamp.
+    pub async fn settings(&self, guild_id: GuildId, now: i64) -> Result<AiSettings, Error> {
+        Ok(ai_settings::Entity::find_by_id(id_to_string(guild_id))
+            .one(self.db)
+            .await?
+            .map(|x| AiSettings::from_model(x, budget_day(now)))
+            .unwrap_or_default())
+    }
+
+    /// Enables or disables the replies of a guild and changes its budget, keeping the tokens used today.
+    pub async fn configure(
+        &self,
+        guild_id: GuildId,
+        enabled: bool,
+        daily_token_budget: u32,
+    ) -> Result<(), Error> {
+        ai_settings::Entity::insert(ai_settings::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            enabled: Set(enabled),
+            daily_token_budget: Set(daily_token_budget as i64),
+            tokens_used: Set(0),
+            budget_day: Set(0),
+        })
+        .on_conflict(
+            OnConflict::column(ai_settings::Column::GuildId)
+                .update_columns([
+                    ai_settings::Column::Enabled,
+                    ai_settings::Column::DailyTokenBudget,
+                ])
+                .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Adds tokens to the usage of a guild on the day of the `now` unix timestamp.
+    pub async fn add_usage(&self, guild_id: GuildId, tokens: u32, now: i64) -> Result<(), Error> {
+        let today = budget_day(now);
+        // Usage of a previous day is replaced rather than added to
+        ai_settings::Entity::update_many()
+            .col_expr(
+                ai_settings::Column::TokensUsed,
+                Expr::case(
+                    ai_settings::Column::BudgetDay.eq(today),
+                    Expr::col(ai_settings::Column::TokensUsed).add(tokens as i64),
+                )
+                .finally(tokens as i64)
+                .into(),
+            )
+            .col_expr(ai_settings::Column::BudgetDay, Expr::value(today))
+            .filter(ai_settings::Column::GuildId.eq(id_to_string(guild_id)))
+            .exec(self.db)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,272 @@
+/*!
+
+Data access for the ban sync network: the sync group each guild joined, and the bans mirrored between partner guilds.
+
+Guilds join a group by entering the same shared secret. Only a hash of the secret is stored, so a group can't be
+joined without knowing it, even with access to the database.
+
+*/
+
+use migration::OnConflict;
+use poise::serenity_prelude::{ChannelId, GuildId, UserId};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, EntityTrait,
+    QueryFilter, QueryOrder, QuerySelect, sea_query::Expr,
+};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    Error,
+    entities::{ban_sync, ban_sync_member},
+    infrastructure::ids::{id_from_string, id_to_string},
+};
+
+/// The ban is waiting for approval on the partner guild.
+pub const STATUS_PENDING: &str = "pending";
+/// The user was banned on the partner guild.
+pub const STATUS_APPLIED: &str = "applied";
+/// A moderator of the partner guild rejected the ban.
+pub const STATUS_REJECTED: &str = "rejected";
+/// The bot failed to ban the user on the partner guild.
+pub const STATUS_FAILED: &str = "failed";
+
+/// Gets the key of the sync group joined with a shared secret.
+pub fn group_key(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+/// How a guild takes part in its sync group.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BanSyncMembership {
+    pub group_key: String,
+    /// Bans of partner guilds are mirrored immediately instead of proposed in the review channel.
+    pub auto_apply: bool,
+    pub review_channel: Option<ChannelId>,
+}
+
+impl BanSyncMembership {
+    fn from_model(model: ban_sync_member::Model) -> Self {
+        Self {
+            group_key: model.group_key,
+            auto_apply: model.auto_apply,
+            review_channel: model
+                .review_channel_id
+                .and_then(|x| id_from_string(&x).ok()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BanSyncRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> BanSyncRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    pub async fn membership(&self, guild_id: GuildId) -> Result<Option<BanSyncMembership>, Error> {
+        Ok(ban_sync_member::Entity::find_by_id(id_to_string(guild_id))
+            .one(self.db)
+            .await?
+            .map(BanSyncMembership::from_model))
+    }
+
+    /// Joins a sync group, or changes how the guild takes part in it. A guild is part of one group at most.
+    pub async fn join(
+        &self,
+        guild_id: GuildId,
+        membership: &BanSyncMembership,
+    ) -> Result<(), Error> {
+        ban_sync_member::Entity::insert(ban_sync_member::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            group_key: Set(membership.group_key.clone()),
+            auto_apply: Set(membership.auto_apply),
+            review_channel_id: Set(membership.review_channel.map(id_to_string)),
+        })
+        .on_conflict(
+            OnConflict::column(ban_sync_member::Column::GuildId)
+                .update_columns([
+                    ban_sync_member::Column::GroupKey,
+                    ban_sync_member::Column::AutoApply,
+                    ban_sync_member::Column::ReviewChannelId,
+                ])
+                .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Leaves the sync group of a guild, returning whether it was part of one.
+    pub async fn leave(&self, guild_id: GuildId) -> Result<bool, Error> {
+        let result = ban_sync_member::Entity::delete_by_id(id_to_string(guild_id))
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Gets the other guilds of a sync group, skipping rows that can't be parsed.
+    pub async fn partners(
+        &self,
+        group_key: &str,
+        guild_id: GuildId,
+    ) -> Result<Vec<(GuildId, BanSyncMembership)>, Error> {
+        Ok(ban_sync_member::Entity::find()
+            .filter(ban_sync_member::Column::GroupKey.eq(group_key))
+            .filter(ban_sync_member::Column::GuildId.ne(id_to_string(guild_id)))
+            .all(self.db)
+            .await?
+            .into_iter()
+            .filter_map(|x| {
+                let guild_id = id_from_string(&x.guild_id).ok()?;
+                Some((guild_id, BanSyncMembership::from_model(x)))
+            })
+            .collect())
+    }
+
+    /// Gets whether a ban of a user on a guild was already synced from a partner guild, or is waiting for approval.
+    pub async fn is_synced(&self, guild_id: GuildId, user_id: UserId) -> Result<bool, Error> {
+        Ok(ban_sync::Entity::find()
+            .select_only()
+            .column(ban_sync::Column::Id)
+            .filter(ban_sync::Column::TargetGuildId.eq(id_to_string(guild_id)))
+            .filter(ban_sync::Column::UserId.eq(id_to_string(user_id)))
+            .filter(ban_sync::Column::Status.is_in([STATUS_PENDING, STATUS_APPLIED]))
+            .into_tuple::<i32>()
+            .one(self.db)
+            .await?
+            .is_some())
+    }
+
+    /// Records a ban to mirror to a partner guild, returning its id.
+    pub async fn insert(&self, entry: ban_sync::ActiveModel) -> Result<i32, Error> {
+        Ok(ban_sync::Entity::insert(entry)
+            .exec(self.db)
+            .await?
+            .last_insert_id)
+    }
+
+    pub async fn find(&self, id: i32) -> Result<Option<ban_sync::Model>, Error> {
+        Ok(ban_sync::Entity::find_by_id(id).one(self.db).await?)
+    }
+
+    /// Moves a synced ban from the `from` status to the `to` status, returning whether it was still in the `from`
+    /// status. Only the first of two moderators resolving a proposal at the same time changes it.
+    pub async fn resolve(
+        &self,
+        id: i32,
+        from: &str,
+        to: &str,
+        resolved_by: Option<UserId>,
+        resolved_at: i64,
+    ) -> Result<bool, Error> {
+        let result = ban_sync::Entity::update_many()
+            .col_expr(ban_sync::Column::Status, Expr::value(to))
+            .col_expr(
+                ban_sync::Column::ResolvedBy,
+                Expr::value(resolved_by.map(id_to_string)),
+            )
+            .col_expr(ban_sync::Column::ResolvedAt, Expr::value(resolved_at))
+            .filter(ban_sync::Column::Id.eq(id))
+            .filter(ban_sync::Column::Status.eq(from))
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Gets the latest bans synced from or to a guild, newest first.
+    pub async fn recent(
+        &self,
+        guild_id: GuildId,
+        limit: u64,
+    ) -> Result<Vec<ban_sync::Model>, Error> {
+        Ok(ban_sync::Entity::find()
+            .filter(
+                Condition::any()
+                    .add(ban_sync::Column::SourceGuildId.eq(id_to_string(guild_id)))
+                    .add(ban_sync::Column::TargetGuildId.eq(id_to_string(guild_id))),
+            )
+            .order_by_desc(ban_sync::Column::Id)
+            .limit(limit)
+            .all(self.db)
+            .await?)
+    }
+
+    /// Forgets the synced bans created before a unix timestamp, returning the number of bans forgotten.
+    pub async fn remove_before(&self, timestamp: i64) -> Result<u64, Error> {
+        let result = ban_sync::Entity::delete_many()
+            .filter(ban_sync::Column::CreatedAt.lt(timestamp))
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// Removes a user as the moderator of the bans they made or resolved, returning the number of rows changed.
+    ///
+    /// Bans of the user themselves are moderation records and are kept until they expire, so a banned user can't
+    /// withdraw a proposal before the partner guilds reviewed it.
+    pub async fn forget_user(&self, user_id: UserId) -> Result<u64, Error> {
+        let user_id = id_to_string(user_id);
+        let mut rows = ban_sync::Entity::update_many()
+            .col_expr(
+                ban_sync::Column::ModeratorId,
+                Expr::value(Option::<String>::None),
+            )
+            .filter(ban_sync::Column::ModeratorId.eq(&user_id))
+            .exec(self.db)
+            .await?
+            .rows_affected;
+        rows += ban_sync::Entity::update_many()
+            .col_expr(
+                ban_sync::Column::ResolvedBy,
+                Expr::value(Option::<String>::None),
+            )
+            .filter(ban_sync::Column::ResolvedBy.eq(&user_id))
+            .exec(self.db)
+            .await?
+            .rows_affected;
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::services::testing::migrated_db;
+
+    use super::*;
+
+    fn ban(user_id: &str, moderator_id: &str, resolved_by: Option<&str>) -> ban_sync::ActiveModel {
+        ban_sync::ActiveModel {
+            source_guild_id: Set("1".to_string()),
+            target_guild_id: Set("2".to_string()),
+            user_id: Set(user_id.to_string()),
+            moderator_id: Set(Some(moderator_id.to_string())),
+            reason: Set(None),
+            status: Set("pending".to_string()),
+            created_at: Set(0),
+            resolved_by: Set(resolved_by.map(str::to_string)),
+            resolved_at: Set(None),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn forgetting_a_user_keeps_their_bans() -> Result<(), Error> {
+        let db = migrated_db().await;
+        let repo = BanSyncRepo::new(&db);
+        let banned = repo.insert(ban("10", "20", None)).await?;
+        let moderated = repo.insert(ban("30", "10", Some("10"))).await?;
+
+        assert_eq!(repo.forget_user(UserId::new(10)).await?, 2);
+        let banned = repo.find(banned).await?.unwrap();
+        assert_eq!(banned.user_id, "10");
+        assert_eq!(banned.moderator_id.as_deref(), Some("20"));
+        let moderated = repo.find(moderated).await?.unwrap();
+        assert_eq!(moderated.user_id, "30");
+        assert_eq!(moderated.moderator_id, None);
+        assert_eq!(moderated.resolved_by, None);
+        Ok(())
+    }
+}
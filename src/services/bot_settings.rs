@@ -0,0 +1,44 @@
+/*!
+
+Data access for settings that apply to the whole bot rather than to a guild, stored as name / value pairs.
+
+*/
+
+use migration::OnConflict;
+use sea_orm::{ActiveValue::Set, ConnectionTrait, DatabaseConnection, EntityTrait};
+
+use crate::{Error, entities::bot_settings};
+
+#[derive(Debug, Clone, Copy)]
+pub struct BotSettingsRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> BotSettingsRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    /// Gets the value of a setting, or None if it was never set.
+    pub async fn get(&self, name: &str) -> Result<Option<String>, Error> {
+        Ok(bot_settings::Entity::find_by_id(name.to_string())
+            .one(self.db)
+            .await?
+            .map(|x| x.value))
+    }
+
+    pub async fn set(&self, name: &str, value: &str) -> Result<(), Error> {
+        bot_settings::Entity::insert(bot_settings::ActiveModel {
+            name: Set(name.to_string()),
+            value: Set(value.to_string()),
+        })
+        .on_conflict(
+            OnConflict::column(bot_settings::Column::Name)
+                .update_column(bot_settings::Column::Value)
+                .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+/*!
+
+Data access for the message counts of `/activity`, kept per channel and per hour so activity can be reported over any
+window and broken down by the hour of the day.
+
+*/
+
+use migration::{Expr, OnConflict};
+use poise::serenity_prelude::{ChannelId, GuildId, UserId, async_trait};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QuerySelect,
+};
+
+use crate::{
+    Error,
+    entities::channel_activity,
+    infrastructure::{
+        ids::{id_from_string, id_to_string},
+        retention::RetentionTask,
+    },
+};
+
+pub const SECONDS_PER_HOUR: i64 = 60 * 60;
+
+/// Hour of a unix timestamp, as the number of hours since the unix epoch.
+pub fn hour_of(timestamp: i64) -> i64 {
+    timestamp.div_euclid(SECONDS_PER_HOUR)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ChannelActivityRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> ChannelActivityRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    /// Counts a message sent in a channel during an hour.
+    pub async fn record_message(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        hour: i64,
+    ) -> Result<(), Error> {
+        channel_activity::Entity::insert(channel_activity::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            hour: Set(hour),
+            channel_id: Set(id_to_string(channel_id)),
+            messages: Set(1),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                channel_activity::Column::GuildId,
+                channel_activity::Column::Hour,
+                channel_activity::Column::ChannelId,
+            ])
+            .value(
+                channel_activity::Column::Messages,
+                Expr::col(channel_activity::Column::Messages).add(1),
+            )
+            .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Gets the number of messages sent in each channel of a guild since an hour, from the most active channel.
+    pub async fn channel_totals(
+        &self,
+        guild_id: GuildId,
+        since_hour: i64,
+    ) -> Result<Vec<(ChannelId, i64)>, Error> {
+        let mut totals: Vec<(ChannelId, i64)> = channel_activity::Entity::find()
+            .select_only()
+            .column(channel_activity::Column::ChannelId)
+            .column_as(channel_activity::Column::Messages.sum(), "messages")
+            .filter(channel_activity::Column::GuildId.eq(id_to_string(guild_id)))
+            .filter(channel_activity::Column::Hour.gte(since_hour))
+            .group_by(channel_activity::Column::ChannelId)
+            .into_tuple::<(String, i64)>()
+            .all(self.db)
+            .await?
+            .into_iter()
+            .filter_map(|(id, messages)| Some((id_from_string(&id).ok()?, messages)))
+            .collect();
+        totals.sort_by_key(|(_, messages)| std::cmp::Reverse(*messages));
+        Ok(totals)
+    }
+
+    /// Gets the number of messages sent on a guild during each hour since an hour, for the hours with messages.
+    pub async fn hourly_totals(
+        &self,
+        guild_id: GuildId,
+        since_hour: i64,
+    ) -> Result<Vec<(i64, i64)>, Error> {
+        Ok(channel_activity::Entity::find()
+            .select_only()
+            .column(channel_activity::Column::Hour)
+            .column_as(channel_activity::Column::Messages.sum(), "messages")
+            .filter(channel_activity::Column::GuildId.eq(id_to_string(guild_id)))
+            .filter(channel_activity::Column::Hour.gte(since_hour))
+            .group_by(channel_activity::Column::Hour)
+            .into_tuple::<(i64, i64)>()
+            .all(self.db)
+            .await?)
+    }
+
+    /// Deletes the counts of the hours before an hour, returning the number of rows deleted.
+    pub async fn remove_before(&self, hour: i64) -> Result<u64, Error> {
+        let result = channel_activity::Entity::delete_many()
+            .filter(channel_activity::Column::Hour.lt(hour))
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
+
+/// Expires hourly message counts. Counts are not associated with users.
+pub struct ChannelActivityRetention;
+
+#[async_trait]
+impl RetentionTask for ChannelActivityRetention {
+    fn name(&self) -> &'static str {
+        "channel_activity"
+    }
+
+    fn default_retention_days(&self) -> Option<u32> {
+        Some(90)
+    }
+
+    async fn expire(
+        &self,
+        db: &DatabaseConnection,
+        now: i64,
+        retention_days: u32,
+    ) -> Result<u64, Error> {
+        ChannelActivityRepo::new(db)
+            .remove_before(hour_of(now) - retention_days as i64 * 24)
+            .await
+    }
+
+    async fn forget_user(&self, _db: &DatabaseConnection, _user_id: UserId) -> Result<u64, Error> {
+        // Messages are counted per channel, not per member
+        Ok(0)
+    }
+}
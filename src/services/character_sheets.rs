@@ -0,0 +1,128 @@
+/*!
+
+Data access for the character sheets of `/character`: simple stats stored per member and per guild, as fields with a
+value such as `class: Wizard` or `str: 14`.
+
+*/
+
+use migration::OnConflict;
+use poise::serenity_prelude::{GuildId, UserId, async_trait};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder,
+};
+
+use crate::{
+    Error,
+    entities::character_sheet_fields,
+    infrastructure::{ids::id_to_string, retention::RetentionTask},
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct CharacterSheetRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> CharacterSheetRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    /// Gets the fields of the sheet of a member and their values, by field name.
+    pub async fn fields(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+    ) -> Result<Vec<(String, String)>, Error> {
+        Ok(character_sheet_fields::Entity::find()
+            .filter(character_sheet_fields::Column::GuildId.eq(id_to_string(guild_id)))
+            .filter(character_sheet_fields::Column::UserId.eq(id_to_string(user_id)))
+            .order_by_asc(character_sheet_fields::Column::Field)
+            .all(self.db)
+            .await?
+            .into_iter()
+            .map(|x| (x.field, x.value))
+            .collect())
+    }
+
+    pub async fn set(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        field: &str,
+        value: &str,
+        now: i64,
+    ) -> Result<(), Error> {
+        character_sheet_fields::Entity::insert(character_sheet_fields::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            user_id: Set(id_to_string(user_id)),
+            field: Set(field.to_string()),
+            value: Set(value.to_string()),
+            updated_at: Set(now),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                character_sheet_fields::Column::GuildId,
+                character_sheet_fields::Column::UserId,
+                character_sheet_fields::Column::Field,
+            ])
+            .update_columns([
+                character_sheet_fields::Column::Value,
+                character_sheet_fields::Column::UpdatedAt,
+            ])
+            .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Removes a field from the sheet of a member. Returns false if the sheet didn't have it.
+    pub async fn remove(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        field: &str,
+    ) -> Result<bool, Error> {
+        let result = character_sheet_fields::Entity::delete_by_id((
+            id_to_string(guild_id),
+            id_to_string(user_id),
+            field.to_string(),
+        ))
+        .exec(self.db)
+        .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Deletes the whole sheet of a member, returning the number of fields deleted.
+    pub async fn clear(&self, guild_id: GuildId, user_id: UserId) -> Result<u64, Error> {
+        let result = character_sheet_fields::Entity::delete_many()
+            .filter(character_sheet_fields::Column::GuildId.eq(id_to_string(guild_id)))
+            .filter(character_sheet_fields::Column::UserId.eq(id_to_string(user_id)))
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    pub async fn forget_user(&self, user_id: UserId) -> Result<u64, Error> {
+        let result = character_sheet_fields::Entity::delete_many()
+            .filter(character_sheet_fields::Column::UserId.eq(id_to_string(user_id)))
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
+
+/// Deletes the character sheets of users who asked to be forgotten. Sheets are kept until their owner clears them.
+pub struct CharacterSheetRetention;
+
+#[async_trait]
+impl RetentionTask for CharacterSheetRetention {
+    fn name(&self) -> &'static str {
+        "character_sheets"
+    }
+
+    async fn forget_user(&self, db: &DatabaseConnection, user_id: UserId) -> Result<u64, Error> {
+        CharacterSheetRepo::new(db).forget_user(user_id).await
+    }
+}
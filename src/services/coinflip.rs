@@ -0,0 +1,100 @@
+/*!
+
+Data access for the coin flip settings of each guild: the labels of the faces of the coin, and how often it lands on
+its edge.
+
+*/
+
+use migration::OnConflict;
+use poise::serenity_prelude::GuildId;
+use sea_orm::{ActiveValue::Set, ConnectionTrait, DatabaseConnection, EntityTrait};
+
+use crate::{Error, entities::coinflip_settings, infrastructure::ids::id_to_string};
+
+pub const DEFAULT_HEADS_LABEL: &str = "heads";
+
+pub const DEFAULT_TAILS_LABEL: &str = "tails";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CoinflipSettings {
+    /// Empty for [`DEFAULT_HEADS_LABEL`].
+    pub heads_label: String,
+    /// Empty for [`DEFAULT_TAILS_LABEL`].
+    pub tails_label: String,
+    /// The coin lands on its edge once in this many flips, 0 for never.
+    pub edge_odds: u32,
+}
+
+impl CoinflipSettings {
+    pub fn heads(&self) -> &str {
+        match self.heads_label.as_str() {
+            "" => DEFAULT_HEADS_LABEL,
+            x => x,
+        }
+    }
+
+    pub fn tails(&self) -> &str {
+        match self.tails_label.as_str() {
+            "" => DEFAULT_TAILS_LABEL,
+            x => x,
+        }
+    }
+
+    /// Probability of the coin landing on its edge.
+    pub fn edge_probability(&self) -> f64 {
+        match self.edge_odds {
+            0 => 0.0,
+            x => 1.0 / x as f64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CoinflipRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> CoinflipRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    pub async fn settings(&self, guild_id: GuildId) -> Result<CoinflipSettings, Error> {
+        Ok(
+            coinflip_settings::Entity::find_by_id(id_to_string(guild_id))
+                .one(self.db)
+                .await?
+                .map(|x| CoinflipSettings {
+                    heads_label: x.heads_label,
+                    tails_label: x.tails_label,
+                    edge_odds: x.edge_odds.max(0) as u32,
+                })
+                .unwrap_or_default(),
+        )
+    }
+
+    pub async fn save_settings(
+        &self,
+        guild_id: GuildId,
+        settings: &CoinflipSettings,
+    ) -> Result<(), Error> {
+        coinflip_settings::Entity::insert(coinflip_settings::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            heads_label: Set(settings.heads_label.clone()),
+            tails_label: Set(settings.tails_label.clone()),
+            edge_odds: Set(settings.edge_odds as i32),
+        })
+        .on_conflict(
+            OnConflict::column(coinflip_settings::Column::GuildId)
+                .update_columns([
+                    coinflip_settings::Column::HeadsLabel,
+                    coinflip_settings::Column::TailsLabel,
+                    coinflip_settings::Column::EdgeOdds,
+                ])
+                .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+/*!
+
+Data access for per-guild overrides of how commands behave, such as whether their replies are ephemeral.
+
+Commands are identified by their qualified name, e.g. `mc status`.
+
+*/
+
+use migration::OnConflict;
+use poise::serenity_prelude::GuildId;
+use sea_orm::{ActiveValue::Set, ConnectionTrait, DatabaseConnection, EntityTrait};
+
+use crate::{Error, entities::ephemeral_settings, infrastructure::ids::id_to_string};
+
+/// Whether the replies of a command are visible to the invoking user only.
+#[derive(Debug, Clone, Copy, PartialEq, Default, poise::ChoiceParameter)]
+pub enum EphemeralMode {
+    /// Replies are always visible to the invoking user only.
+    #[name = "on"]
+    On,
+    /// Replies are always visible to everyone.
+    #[name = "off"]
+    Off,
+    /// The `ephemeral` option of the command decides, falling back to the command's default.
+    #[default]
+    #[name = "user-choice"]
+    UserChoice,
+}
+
+impl EphemeralMode {
+    /// Resolves whether a reply is ephemeral, given the option the user chose and the default of the command.
+    pub fn resolve(&self, explicit: Option<bool>, default: bool) -> bool {
+        match self {
+            Self::On => true,
+            Self::Off => false,
+            Self::UserChoice => explicit.unwrap_or(default),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CommandSettingsRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> CommandSettingsRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    pub async fn ephemeral_mode(
+        &self,
+        guild_id: GuildId,
+        command: &str,
+    ) -> Result<EphemeralMode, Error> {
+        Ok(
+            ephemeral_settings::Entity::find_by_id((id_to_string(guild_id), command.to_string()))
+                .one(self.db)
+                .await?
+                .map(|x| {
+                    if x.ephemeral {
+                        EphemeralMode::On
+                    } else {
+                        EphemeralMode::Off
+                    }
+                })
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Overrides the ephemeral mode of a command. [`EphemeralMode::UserChoice`] removes the override.
+    pub async fn set_ephemeral_mode(
+        &self,
+        guild_id: GuildId,
+        command: &str,
+        mode: EphemeralMode,
+    ) -> Result<(), Error> {
+        let ephemeral = match mode {
+            EphemeralMode::On => true,
+            EphemeralMode::Off => false,
+            EphemeralMode::UserChoice => {
+                ephemeral_settings::Entity::delete_by_id((
+                    id_to_string(guild_id),
+                    command.to_string(),
+                ))
+                .exec(self.db)
+                .await?;
+                return Ok(());
+            }
+        };
+        ephemeral_settings::Entity::insert(ephemeral_settings::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            command: Set(command.to_string()),
+            ephemeral: Set(ephemeral),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                ephemeral_settings::Column::GuildId,
+                ephemeral_settings::Column::Command,
+            ])
+            .update_column(ephemeral_settings::Column::Ephemeral)
+            .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,296 @@
+/*!
+
+Data access for the game nights of `/gamenight`: the scheduled game nights, the members who RSVPed to them and the
+settings of each guild.
+
+*/
+
+use migration::OnConflict;
+use poise::serenity_prelude::{
+    ChannelId, GuildId, MessageId, RoleId, ScheduledEventId, UserId, async_trait,
+};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder,
+};
+
+use crate::{
+    Error,
+    entities::{game_night_rsvps, game_night_settings, game_nights},
+    infrastructure::{
+        ids::{id_from_string, id_to_string},
+        retention::RetentionTask,
+    },
+};
+
+/// Reads an optional id, stored as an empty string when missing.
+fn optional_id<T: From<u64>>(value: &str) -> Option<T> {
+    id_from_string(value).ok()
+}
+
+fn optional_id_to_string<T: Into<u64>>(value: Option<T>) -> String {
+    value.map(id_to_string).unwrap_or_default()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameNight {
+    pub id: i32,
+    pub guild_id: GuildId,
+    /// Channel of the RSVP message, where reminders are posted.
+    pub channel_id: ChannelId,
+    pub message_id: Option<MessageId>,
+    pub host_id: UserId,
+    pub game: String,
+    /// Unix timestamp.
+    pub starts_at: i64,
+    pub event_id: Option<ScheduledEventId>,
+    /// Temporary voice channel, created when the game night starts.
+    pub voice_channel_id: Option<ChannelId>,
+}
+
+impl GameNight {
+    fn from_model(model: game_nights::Model) -> Result<Self, Error> {
+        Ok(Self {
+            id: model.id,
+            guild_id: id_from_string(&model.guild_id)?,
+            channel_id: id_from_string(&model.channel_id)?,
+            message_id: optional_id(&model.message_id),
+            host_id: id_from_string(&model.host_id)?,
+            game: model.game,
+            starts_at: model.starts_at,
+            event_id: optional_id(&model.event_id),
+            voice_channel_id: optional_id(&model.voice_channel_id),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameNightSettings {
+    /// Role pinged when a game night is scheduled without a role.
+    pub role_id: Option<RoleId>,
+    /// Category the lobbies are created in.
+    pub category_id: Option<ChannelId>,
+    /// Minutes before the start at which members who RSVPed are reminded, 0 for no reminder.
+    pub reminder_minutes: u32,
+    /// Hours the lobby is kept after the start.
+    pub lobby_hours: u32,
+}
+
+impl Default for GameNightSettings {
+    fn default() -> Self {
+        Self {
+            role_id: None,
+            category_id: None,
+            reminder_minutes: 15,
+            lobby_hours: 6,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GameNightRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> GameNightRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    pub async fn settings(&self, guild_id: GuildId) -> Result<GameNightSettings, Error> {
+        Ok(
+            game_night_settings::Entity::find_by_id(id_to_string(guild_id))
+                .one(self.db)
+                .await?
+                .map(|x| GameNightSettings {
+                    role_id: optional_id(&x.role_id),
+                    category_id: optional_id(&x.category_id),
+                    reminder_minutes: x.reminder_minutes.max(0) as u32,
+                    lobby_hours: x.lobby_hours.max(0) as u32,
+                })
+                .unwrap_or_default(),
+        )
+    }
+
+    pub async fn save_settings(
+        &self,
+        guild_id: GuildId,
+        settings: &GameNightSettings,
+    ) -> Result<(), Error> {
+        game_night_settings::Entity::insert(game_night_settings::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            role_id: Set(optional_id_to_string(settings.role_id)),
+            category_id: Set(optional_id_to_string(settings.category_id)),
+            reminder_minutes: Set(settings.reminder_minutes as i32),
+            lobby_hours: Set(settings.lobby_hours as i32),
+        })
+        .on_conflict(
+            OnConflict::column(game_night_settings::Column::GuildId)
+                .update_columns([
+                    game_night_settings::Column::RoleId,
+                    game_night_settings::Column::CategoryId,
+                    game_night_settings::Column::ReminderMinutes,
+                    game_night_settings::Column::LobbyHours,
+                ])
+                .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Stores a new game night, before its RSVP message and scheduled event exist. Returns its id.
+    pub async fn create(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        host_id: UserId,
+        game: &str,
+        starts_at: i64,
+    ) -> Result<i32, Error> {
+        let result = game_nights::Entity::insert(game_nights::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            channel_id: Set(id_to_string(channel_id)),
+            message_id: Set(String::new()),
+            host_id: Set(id_to_string(host_id)),
+            game: Set(game.to_string()),
+            starts_at: Set(starts_at),
+            event_id: Set(String::new()),
+            voice_channel_id: Set(String::new()),
+            ..Default::default()
+        })
+        .exec(self.db)
+        .await?;
+        Ok(result.last_insert_id)
+    }
+
+    pub async fn get(&self, id: i32) -> Result<Option<GameNight>, Error> {
+        game_nights::Entity::find_by_id(id)
+            .one(self.db)
+            .await?
+            .map(GameNight::from_model)
+            .transpose()
+    }
+
+    /// Gets the game nights of a guild, from the soonest.
+    pub async fn list(&self, guild_id: GuildId) -> Result<Vec<GameNight>, Error> {
+        game_nights::Entity::find()
+            .filter(game_nights::Column::GuildId.eq(id_to_string(guild_id)))
+            .order_by_asc(game_nights::Column::StartsAt)
+            .all(self.db)
+            .await?
+            .into_iter()
+            .map(GameNight::from_model)
+            .collect()
+    }
+
+    pub async fn set_message(&self, id: i32, message_id: MessageId) -> Result<(), Error> {
+        game_nights::Entity::update(game_nights::ActiveModel {
+            id: Set(id),
+            message_id: Set(id_to_string(message_id)),
+            ..Default::default()
+        })
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn set_event(&self, id: i32, event_id: ScheduledEventId) -> Result<(), Error> {
+        game_nights::Entity::update(game_nights::ActiveModel {
+            id: Set(id),
+            event_id: Set(id_to_string(event_id)),
+            ..Default::default()
+        })
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn set_voice_channel(&self, id: i32, channel_id: ChannelId) -> Result<(), Error> {
+        game_nights::Entity::update(game_nights::ActiveModel {
+            id: Set(id),
+            voice_channel_id: Set(id_to_string(channel_id)),
+            ..Default::default()
+        })
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Saves whether a member is going to a game night. Returns false if their answer didn't change.
+    pub async fn set_rsvp(
+        &self,
+        id: i32,
+        user_id: UserId,
+        going: bool,
+        now: i64,
+    ) -> Result<bool, Error> {
+        if !going {
+            let result = game_night_rsvps::Entity::delete_by_id((id, id_to_string(user_id)))
+                .exec(self.db)
+                .await?;
+            return Ok(result.rows_affected > 0);
+        }
+        let result = game_night_rsvps::Entity::insert(game_night_rsvps::ActiveModel {
+            game_night_id: Set(id),
+            user_id: Set(id_to_string(user_id)),
+            responded_at: Set(now),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                game_night_rsvps::Column::GameNightId,
+                game_night_rsvps::Column::UserId,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .exec_without_returning(self.db)
+        .await?;
+        Ok(result > 0)
+    }
+
+    /// Gets the members going to a game night, in the order they answered.
+    pub async fn attendees(&self, id: i32) -> Result<Vec<UserId>, Error> {
+        Ok(game_night_rsvps::Entity::find()
+            .filter(game_night_rsvps::Column::GameNightId.eq(id))
+            .order_by_asc(game_night_rsvps::Column::RespondedAt)
+            .all(self.db)
+            .await?
+            .into_iter()
+            .filter_map(|x| id_from_string(&x.user_id).ok())
+            .collect())
+    }
+
+    /// Deletes a game night and its RSVPs. Returns false if there was no game night with the id.
+    pub async fn remove(&self, id: i32) -> Result<bool, Error> {
+        game_night_rsvps::Entity::delete_many()
+            .filter(game_night_rsvps::Column::GameNightId.eq(id))
+            .exec(self.db)
+            .await?;
+        let result = game_nights::Entity::delete_by_id(id).exec(self.db).await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    pub async fn forget_user(&self, user_id: UserId) -> Result<u64, Error> {
+        let result = game_night_rsvps::Entity::delete_many()
+            .filter(game_night_rsvps::Column::UserId.eq(id_to_string(user_id)))
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
+
+/// Deletes the RSVPs of users who asked to be forgotten. Game nights and their RSVPs are deleted when their lobby
+/// closes, so they don't need to expire.
+pub struct GameNightRetention;
+
+#[async_trait]
+impl RetentionTask for GameNightRetention {
+    fn name(&self) -> &'static str {
+        "game_night_rsvps"
+    }
+
+    async fn forget_user(&self, db: &DatabaseConnection, user_id: UserId) -> Result<u64, Error> {
+        GameNightRepo::new(db).forget_user(user_id).await
+    }
+}
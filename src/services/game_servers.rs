@@ -0,0 +1,108 @@
+/*!
+
+Data access for the game servers advertised on each guild with `/server`.
+
+*/
+
+use poise::serenity_prelude::{GuildId, UserId, async_trait};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder, QuerySelect, sea_query::Expr,
+};
+
+use crate::{
+    Error,
+    entities::game_server,
+    infrastructure::{ids::id_to_string, retention::RetentionTask},
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct GameServerRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> GameServerRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    pub async fn find(
+        &self,
+        guild_id: GuildId,
+        name: &str,
+    ) -> Result<Option<game_server::Model>, Error> {
+        Ok(
+            game_server::Entity::find_by_id((id_to_string(guild_id), name.to_string()))
+                .one(self.db)
+                .await?,
+        )
+    }
+
+    /// Gets the servers advertised on a guild, ordered by name.
+    pub async fn list(&self, guild_id: GuildId) -> Result<Vec<game_server::Model>, Error> {
+        Ok(game_server::Entity::find()
+            .filter(game_server::Column::GuildId.eq(id_to_string(guild_id)))
+            .order_by_asc(game_server::Column::Name)
+            .all(self.db)
+            .await?)
+    }
+
+    /// Gets the names of the servers on a guild, in alphabetical order.
+    pub async fn names(&self, guild_id: GuildId) -> Result<Vec<String>, Error> {
+        Ok(game_server::Entity::find()
+            .select_only()
+            .column(game_server::Column::Name)
+            .filter(game_server::Column::GuildId.eq(id_to_string(guild_id)))
+            .order_by_asc(game_server::Column::Name)
+            .into_tuple()
+            .all(self.db)
+            .await?)
+    }
+
+    pub async fn insert(&self, server: game_server::Model) -> Result<(), Error> {
+        game_server::Entity::insert(server.into_active_model())
+            .exec(self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Updates the columns of a server that are set in `server`.
+    pub async fn update(&self, server: game_server::ActiveModel) -> Result<(), Error> {
+        game_server::Entity::update(server).exec(self.db).await?;
+        Ok(())
+    }
+
+    /// Removes a server, returning whether it existed.
+    pub async fn remove(&self, guild_id: GuildId, name: &str) -> Result<bool, Error> {
+        let result = game_server::Entity::delete_by_id((id_to_string(guild_id), name.to_string()))
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Removes a user as the owner of the servers they added, returning the number of servers changed. The servers
+    /// stay advertised.
+    pub async fn forget_owner(&self, user_id: UserId) -> Result<u64, Error> {
+        let result = game_server::Entity::update_many()
+            .col_expr(game_server::Column::OwnerId, Expr::value(""))
+            .filter(game_server::Column::OwnerId.eq(id_to_string(user_id)))
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
+
+/// Removes users who asked to be forgotten as the owner of their game servers. Servers are managed by hand, so they
+/// never expire.
+pub struct GameServerRetention;
+
+#[async_trait]
+impl RetentionTask for GameServerRetention {
+    fn name(&self) -> &'static str {
+        "game_servers"
+    }
+
+    async fn forget_user(&self, db: &DatabaseConnection, user_id: UserId) -> Result<u64, Error> {
+        GameServerRepo::new(db).forget_owner(user_id).await
+    }
+}
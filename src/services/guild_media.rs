@@ -0,0 +1,82 @@
+/*!
+
+Data access for the media a guild uploaded to replace the bundled dice and coin images, keyed by the name of the
+bundled image they replace, such as `d6-3` or `coin-heads`.
+
+*/
+
+use migration::OnConflict;
+use poise::serenity_prelude::GuildId;
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder,
+};
+
+use crate::{Error, entities::guild_media, infrastructure::ids::id_to_string};
+
+#[derive(Debug, Clone, Copy)]
+pub struct GuildMediaRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> GuildMediaRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    /// Gets the user content file replacing a bundled image, if the guild uploaded one.
+    pub async fn filename(&self, guild_id: GuildId, key: &str) -> Result<Option<String>, Error> {
+        Ok(
+            guild_media::Entity::find_by_id((id_to_string(guild_id), key.to_string()))
+                .one(self.db)
+                .await?
+                .map(|x| x.filename),
+        )
+    }
+
+    /// Gets the bundled images a guild replaced and the files replacing them, by key.
+    pub async fn list(&self, guild_id: GuildId) -> Result<Vec<(String, String)>, Error> {
+        Ok(guild_media::Entity::find()
+            .filter(guild_media::Column::GuildId.eq(id_to_string(guild_id)))
+            .order_by_asc(guild_media::Column::MediaKey)
+            .all(self.db)
+            .await?
+            .into_iter()
+            .map(|x| (x.media_key, x.filename))
+            .collect())
+    }
+
+    /// Replaces a bundled image with a user content file. Returns the file it replaced before, if any.
+    pub async fn set(
+        &self,
+        guild_id: GuildId,
+        key: &str,
+        filename: &str,
+    ) -> Result<Option<String>, Error> {
+        let previous = self.filename(guild_id, key).await?;
+        guild_media::Entity::insert(guild_media::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            media_key: Set(key.to_string()),
+            filename: Set(filename.to_string()),
+        })
+        .on_conflict(
+            OnConflict::columns([guild_media::Column::GuildId, guild_media::Column::MediaKey])
+                .update_column(guild_media::Column::Filename)
+                .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(previous)
+    }
+
+    /// Goes back to the bundled image. Returns the file that replaced it, if any.
+    pub async fn remove(&self, guild_id: GuildId, key: &str) -> Result<Option<String>, Error> {
+        let previous = self.filename(guild_id, key).await?;
+        if previous.is_some() {
+            guild_media::Entity::delete_by_id((id_to_string(guild_id), key.to_string()))
+                .exec(self.db)
+                .await?;
+        }
+        Ok(previous)
+    }
+}
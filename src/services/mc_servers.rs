@@ -0,0 +1,372 @@
+/*!
+
+Data access for the minecraft servers advertised on each guild and who may manage them.
+
+*/
+
+use migration::OnConflict;
+use poise::serenity_prelude::{ChannelId, GuildId, RoleId, UserId, async_trait};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait,
+    IntoActiveModel, QueryFilter, QueryOrder, QuerySelect, SqlErr, sea_query::Expr,
+};
+
+use crate::{
+    Error,
+    entities::{mc_server, mc_settings, mc_status_channel},
+    infrastructure::{
+        ids::{id_from_string, id_to_string},
+        retention::RetentionTask,
+    },
+};
+
+/// Attempts at giving a new server an id, in case a server added at the same time takes the same id.
+const INSERT_ATTEMPTS: usize = 5;
+
+#[derive(Debug, Clone, Copy)]
+pub struct McServerRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> McServerRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    pub async fn find(
+        &self,
+        guild_id: GuildId,
+        name: &str,
+    ) -> Result<Option<mc_server::Model>, Error> {
+        Ok(
+            mc_server::Entity::find_by_id((id_to_string(guild_id), name.to_string()))
+                .one(self.db)
+                .await?,
+        )
+    }
+
+    /// Gets the servers advertised on a guild, ordered by name.
+    pub async fn list(&self, guild_id: GuildId) -> Result<Vec<mc_server::Model>, Error> {
+        Ok(mc_server::Entity::find()
+            .filter(mc_server::Column::GuildId.eq(id_to_string(guild_id)))
+            .order_by_asc(mc_server::Column::Name)
+            .all(self.db)
+            .await?)
+    }
+
+    /// Gets the servers advertised on every guild.
+    pub async fn all(&self) -> Result<Vec<mc_server::Model>, Error> {
+        Ok(mc_server::Entity::find().all(self.db).await?)
+    }
+
+    /// Gets the names of the servers on a guild, in alphabetical order.
+    pub async fn names(&self, guild_id: GuildId) -> Result<Vec<String>, Error> {
+        Ok(mc_server::Entity::find()
+            .select_only()
+            .column(mc_server::Column::Name)
+            .filter(mc_server::Column::GuildId.eq(id_to_string(guild_id)))
+            .order_by_asc(mc_server::Column::Name)
+            .into_tuple()
+            .all(self.db)
+            .await?)
+    }
+
+    /// Finds a server by its numeric id, which unlike its name fits in the custom id of a button.
+    pub async fn find_by_server_id(
+        &self,
+        guild_id: GuildId,
+        id: i64,
+    ) -> Result<Option<mc_server::Model>, Error> {
+        Ok(mc_server::Entity::find()
+            .filter(mc_server::Column::GuildId.eq(id_to_string(guild_id)))
+            .filter(mc_server::Column::Id.eq(id))
+            .one(self.db)
+            .await?)
+    }
+
+    /// Adds a server, returning the id assigned to it. The id of `server` is ignored.
+    ///
+    /// Ids are assigned after the largest one in use, so the insert is retried if a server added at the same time
+    /// took the id first.
+    pub async fn insert(&self, server: mc_server::Model) -> Result<i64, Error> {
+        let guild_id = server.guild_id.clone();
+        let name = server.name.clone();
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let max: Option<Option<i64>> = mc_server::Entity::find()
+                .select_only()
+                .column_as(mc_server::Column::Id.max(), "max")
+                .into_tuple()
+                .one(self.db)
+                .await?;
+            let id = max.flatten().unwrap_or(0) + 1;
+            let mut model = server.clone().into_active_model();
+            model.id = Set(id);
+            let error = match mc_server::Entity::insert(model).exec(self.db).await {
+                Ok(_) => return Ok(id),
+                Err(e) => e,
+            };
+            let id_taken = matches!(error.sql_err(), Some(SqlErr::UniqueConstraintViolation(_)))
+                && mc_server::Entity::find_by_id((guild_id.clone(), name.clone()))
+                    .one(self.db)
+                    .await?
+                    .is_none();
+            if !id_taken || attempts >= INSERT_ATTEMPTS {
+                return Err(error.into());
+            }
+        }
+    }
+
+    /// Updates the columns of a server that are set in `server`.
+    pub async fn update(&self, server: mc_server::ActiveModel) -> Result<(), Error> {
+        mc_server::Entity::update(server).exec(self.db).await?;
+        Ok(())
+    }
+
+    /// Removes a server, returning whether it existed.
+    pub async fn remove(&self, guild_id: GuildId, name: &str) -> Result<bool, Error> {
+        let result = mc_server::Entity::delete_by_id((id_to_string(guild_id), name.to_string()))
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Removes a user as the owner of the servers they added, returning the number of servers changed. The servers
+    /// stay advertised, and can still be managed by the manager role.
+    pub async fn forget_owner(&self, user_id: UserId) -> Result<u64, Error> {
+        let result = mc_server::Entity::update_many()
+            .col_expr(mc_server::Column::OwnerId, Expr::value(""))
+            .filter(mc_server::Column::OwnerId.eq(id_to_string(user_id)))
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// Gets the role whose members may manage every server of a guild, if one is configured.
+    pub async fn manager_role(&self, guild_id: GuildId) -> Result<Option<RoleId>, Error> {
+        Ok(mc_settings::Entity::find_by_id(id_to_string(guild_id))
+            .one(self.db)
+            .await?
+            .and_then(|x| id_from_string(&x.manager_role_id).ok()))
+    }
+
+    pub async fn set_manager_role(
+        &self,
+        guild_id: GuildId,
+        role_id: Option<RoleId>,
+    ) -> Result<(), Error> {
+        mc_settings::Entity::insert(mc_settings::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            manager_role_id: Set(role_id.map(id_to_string).unwrap_or_default()),
+        })
+        .on_conflict(
+            OnConflict::column(mc_settings::Column::GuildId)
+                .update_column(mc_settings::Column::ManagerRoleId)
+                .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Gets the voice channels mirroring the status of a server, on every guild.
+    pub async fn status_channels(&self) -> Result<Vec<mc_status_channel::Model>, Error> {
+        Ok(mc_status_channel::Entity::find().all(self.db).await?)
+    }
+
+    /// Sets the voice channel whose name mirrors the status of a server, or stops mirroring it.
+    pub async fn set_status_channel(
+        &self,
+        guild_id: GuildId,
+        name: &str,
+        channel_id: Option<ChannelId>,
+    ) -> Result<(), Error> {
+        let Some(channel_id) = channel_id else {
+            mc_status_channel::Entity::delete_by_id((id_to_string(guild_id), name.to_string()))
+                .exec(self.db)
+                .await?;
+            return Ok(());
+        };
+        mc_status_channel::Entity::insert(mc_status_channel::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            name: Set(name.to_string()),
+            channel_id: Set(id_to_string(channel_id)),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                mc_status_channel::Column::GuildId,
+                mc_status_channel::Column::Name,
+            ])
+            .update_column(mc_status_channel::Column::ChannelId)
+            .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+}
+
+/// Removes users who asked to be forgotten as the owner of their minecraft servers. Servers are managed by hand, so
+/// they never expire.
+pub struct McServerRetention;
+
+#[async_trait]
+impl RetentionTask for McServerRetention {
+    fn name(&self) -> &'static str {
+        "mc_servers"
+    }
+
+    async fn forget_user(&self, db: &DatabaseConnection, user_id: UserId) -> Result<u64, Error> {
+        McServerRepo::new(db).forget_owner(user_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::services::testing::migrated_db;
+
+    use super::*;
+
+    fn server(guild_id: GuildId, name: &str) -> mc_server::Model {
+        mc_server::Model {
+            guild_id: id_to_string(guild_id),
+            name: name.to_string(),
+            address: format!("{}.example.com", name),
+            port: 25565,
+            version: "1.21".to_string(),
+            modpack: String::new(),
+            custom_description: String::new(),
+            instructions: String::new(),
+            thumbnail: String::new(),
+            owner_id: String::new(),
+            modpack_name: String::new(),
+            modpack_version: String::new(),
+            modpack_icon: String::new(),
+            modpack_fetched_at: 0,
+            id: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn servers_are_listed_by_name() -> Result<(), Error> {
+        let db = migrated_db().await;
+        let repo = McServerRepo::new(&db);
+        let (guild, other_guild) = (GuildId::new(1), GuildId::new(2));
+
+        let survival = repo.insert(server(guild, "survival")).await?;
+        let creative = repo.insert(server(guild, "creative")).await?;
+        let skyblock = repo.insert(server(other_guild, "skyblock")).await?;
+        assert!(repo.insert(server(guild, "creative")).await.is_err());
+        assert_ne!(survival, creative);
+        assert_ne!(creative, skyblock);
+
+        assert_eq!(repo.names(guild).await?, vec!["creative", "survival"]);
+        let listed: Vec<(String, i64)> = repo
+            .list(guild)
+            .await?
+            .into_iter()
+            .map(|x| (x.name, x.id))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                ("creative".to_string(), creative),
+                ("survival".to_string(), survival)
+            ]
+        );
+        assert_eq!(repo.all().await?.len(), 3);
+        assert_eq!(
+            repo.find(guild, "survival").await?,
+            Some(mc_server::Model {
+                id: survival,
+                ..server(guild, "survival")
+            })
+        );
+        assert_eq!(repo.find(guild, "skyblock").await?, None);
+        assert_eq!(
+            repo.find_by_server_id(guild, creative)
+                .await?
+                .map(|x| x.name),
+            Some("creative".to_string())
+        );
+        assert_eq!(repo.find_by_server_id(guild, skyblock).await?, None);
+
+        let mut update = server(guild, "survival").into_active_model();
+        update.id = Set(survival);
+        update.port = Set(25566);
+        repo.update(update).await?;
+        assert_eq!(
+            repo.find(guild, "survival").await?.map(|x| x.port),
+            Some(25566)
+        );
+
+        assert!(repo.remove(guild, "survival").await?);
+        assert!(!repo.remove(guild, "survival").await?);
+        assert_eq!(repo.names(guild).await?, vec!["creative"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn forgotten_owners_are_removed() -> Result<(), Error> {
+        let db = migrated_db().await;
+        let repo = McServerRepo::new(&db);
+        let guild = GuildId::new(1);
+        let (owner, other_owner) = (UserId::new(10), UserId::new(11));
+
+        for (name, owner) in [
+            ("survival", owner),
+            ("creative", owner),
+            ("skyblock", other_owner),
+        ] {
+            repo.insert(mc_server::Model {
+                owner_id: id_to_string(owner),
+                ..server(guild, name)
+            })
+            .await?;
+        }
+
+        assert_eq!(repo.forget_owner(owner).await?, 2);
+        let owners: Vec<(String, String)> = repo
+            .list(guild)
+            .await?
+            .into_iter()
+            .map(|x| (x.name, x.owner_id))
+            .collect();
+        assert_eq!(
+            owners,
+            vec![
+                ("creative".to_string(), String::new()),
+                ("skyblock".to_string(), id_to_string(other_owner)),
+                ("survival".to_string(), String::new()),
+            ]
+        );
+        assert_eq!(repo.forget_owner(owner).await?, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn manager_role_and_status_channels_are_replaced() -> Result<(), Error> {
+        let db = migrated_db().await;
+        let repo = McServerRepo::new(&db);
+        let guild = GuildId::new(1);
+
+        assert_eq!(repo.manager_role(guild).await?, None);
+        repo.set_manager_role(guild, Some(RoleId::new(10))).await?;
+        repo.set_manager_role(guild, Some(RoleId::new(11))).await?;
+        assert_eq!(repo.manager_role(guild).await?, Some(RoleId::new(11)));
+        repo.set_manager_role(guild, None).await?;
+        assert_eq!(repo.manager_role(guild).await?, None);
+
+        repo.set_status_channel(guild, "survival", Some(ChannelId::new(20)))
+            .await?;
+        repo.set_status_channel(guild, "survival", Some(ChannelId::new(21)))
+            .await?;
+        let channels = repo.status_channels().await?;
+        assert_eq!(channels.len(), 1);
+        assert_eq!(channels[0].channel_id, id_to_string(ChannelId::new(21)));
+        repo.set_status_channel(guild, "survival", None).await?;
+        assert!(repo.status_channels().await?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,253 @@
+/*!
+
+Data access for the moderation digest: the moderation activity seen on each guild, and how often the digest of that
+activity is posted to the guild's mod-log channel.
+
+Activity is stored as counts only, without the members involved, since the digest just summarizes it.
+
+*/
+
+use migration::OnConflict;
+use poise::serenity_prelude::GuildId;
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QuerySelect, sea_query::Expr,
+};
+
+use crate::{
+    Error,
+    entities::{mod_activity, mod_digest_settings},
+    infrastructure::ids::{id_from_string, id_to_string},
+};
+
+const SECONDS_PER_DAY: i64 = 60 * 60 * 24;
+
+/// How often the digest of a guild is posted.
+#[derive(Debug, Clone, Copy, PartialEq, Default, poise::ChoiceParameter)]
+pub enum DigestFrequency {
+    #[default]
+    #[name = "off"]
+    Off,
+    #[name = "daily"]
+    Daily,
+    #[name = "weekly"]
+    Weekly,
+}
+
+impl DigestFrequency {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+        }
+    }
+
+    fn from_str(value: &str) -> Self {
+        match value {
+            "daily" => Self::Daily,
+            "weekly" => Self::Weekly,
+            _ => Self::Off,
+        }
+    }
+
+    /// Seconds between two digests, or None if no digest is posted.
+    pub fn period_seconds(&self) -> Option<i64> {
+        match self {
+            Self::Off => None,
+            Self::Daily => Some(SECONDS_PER_DAY),
+            Self::Weekly => Some(7 * SECONDS_PER_DAY),
+        }
+    }
+}
+
+/// Kinds of moderation activity counted in the digest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModActivity {
+    Join,
+    Leave,
+    Ban,
+    Unban,
+    /// A member was renamed by the nickname filter.
+    NicknameFiltered,
+    /// Raid protection locked the guild down.
+    RaidLockdown,
+}
+
+impl ModActivity {
+    /// Every kind, in the order they are listed in the digest.
+    pub const ALL: [Self; 6] = [
+        Self::Join,
+        Self::Leave,
+        Self::Ban,
+        Self::Unban,
+        Self::NicknameFiltered,
+        Self::RaidLockdown,
+    ];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Join => "join",
+            Self::Leave => "leave",
+            Self::Ban => "ban",
+            Self::Unban => "unban",
+            Self::NicknameFiltered => "nickname_filtered",
+            Self::RaidLockdown => "raid_lockdown",
+        }
+    }
+
+    fn from_str(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|x| x.as_str() == value)
+    }
+
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Self::Join => "Members joined",
+            Self::Leave => "Members left",
+            Self::Ban => "Bans",
+            Self::Unban => "Unbans",
+            Self::NicknameFiltered => "Nicknames filtered",
+            Self::RaidLockdown => "Raid lockdowns",
+        }
+    }
+}
+
+/// A guild's digest settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DigestSchedule {
+    pub guild_id: GuildId,
+    pub frequency: DigestFrequency,
+    /// Unix timestamp of the previous digest, or of when the digest was enabled.
+    pub last_sent_at: i64,
+}
+
+impl DigestSchedule {
+    fn from_model(model: mod_digest_settings::Model) -> Option<Self> {
+        Some(Self {
+            guild_id: id_from_string(&model.guild_id).ok()?,
+            frequency: DigestFrequency::from_str(&model.frequency),
+            last_sent_at: model.last_sent_at,
+        })
+    }
+
+    /// Whether the next digest is due at the `now` unix timestamp.
+    pub fn is_due(&self, now: i64) -> bool {
+        self.frequency
+            .period_seconds()
+            .is_some_and(|period| now - self.last_sent_at >= period)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ModDigestRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> ModDigestRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    pub async fn record(
+        &self,
+        guild_id: GuildId,
+        activity: ModActivity,
+        created_at: i64,
+    ) -> Result<(), Error> {
+        mod_activity::Entity::insert(mod_activity::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            kind: Set(activity.as_str().to_string()),
+            created_at: Set(created_at),
+            ..Default::default()
+        })
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Counts the activity of each kind on a guild since a unix timestamp. Kinds without activity are left out.
+    pub async fn counts_since(
+        &self,
+        guild_id: GuildId,
+        since: i64,
+    ) -> Result<Vec<(ModActivity, u64)>, Error> {
+        let counts: Vec<(String, i64)> = mod_activity::Entity::find()
+            .select_only()
+            .column(mod_activity::Column::Kind)
+            .column_as(Expr::col(mod_activity::Column::Id).count(), "count")
+            .filter(mod_activity::Column::GuildId.eq(id_to_string(guild_id)))
+            .filter(mod_activity::Column::CreatedAt.gte(since))
+            .group_by(mod_activity::Column::Kind)
+            .into_tuple()
+            .all(self.db)
+            .await?;
+        let mut counts: Vec<(ModActivity, u64)> = counts
+            .into_iter()
+            .filter_map(|(kind, count)| Some((ModActivity::from_str(&kind)?, count.max(0) as u64)))
+            .collect();
+        counts.sort_by_key(|(kind, _)| ModActivity::ALL.iter().position(|x| x == kind));
+        Ok(counts)
+    }
+
+    /// Deletes the activity older than a unix timestamp, returning the number of rows deleted.
+    pub async fn remove_before(&self, timestamp: i64) -> Result<u64, Error> {
+        let result = mod_activity::Entity::delete_many()
+            .filter(mod_activity::Column::CreatedAt.lt(timestamp))
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// Changes how often the digest of a guild is posted. The first digest covers the activity from `now` on.
+    pub async fn set_frequency(
+        &self,
+        guild_id: GuildId,
+        frequency: DigestFrequency,
+        now: i64,
+    ) -> Result<(), Error> {
+        if frequency == DigestFrequency::Off {
+            mod_digest_settings::Entity::delete_by_id(id_to_string(guild_id))
+                .exec(self.db)
+                .await?;
+            return Ok(());
+        }
+        mod_digest_settings::Entity::insert(mod_digest_settings::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            frequency: Set(frequency.as_str().to_string()),
+            last_sent_at: Set(now),
+        })
+        .on_conflict(
+            OnConflict::column(mod_digest_settings::Column::GuildId)
+                .update_columns([
+                    mod_digest_settings::Column::Frequency,
+                    mod_digest_settings::Column::LastSentAt,
+                ])
+                .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Gets the digest settings of every guild that enabled the digest.
+    pub async fn schedules(&self) -> Result<Vec<DigestSchedule>, Error> {
+        Ok(mod_digest_settings::Entity::find()
+            .all(self.db)
+            .await?
+            .into_iter()
+            .filter_map(DigestSchedule::from_model)
+            .collect())
+    }
+
+    pub async fn mark_sent(&self, guild_id: GuildId, sent_at: i64) -> Result<(), Error> {
+        mod_digest_settings::Entity::update_many()
+            .col_expr(
+                mod_digest_settings::Column::LastSentAt,
+                Expr::value(sent_at),
+            )
+            .filter(mod_digest_settings::Column::GuildId.eq(id_to_string(guild_id)))
+            .exec(self.db)
+            .await?;
+        Ok(())
+    }
+}
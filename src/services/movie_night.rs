@@ -0,0 +1,127 @@
+/*!
+
+Data access for the movie night channels of each guild, configured with `/movie-night`.
+
+*/
+
+use migration::OnConflict;
+use poise::serenity_prelude::{ChannelId, GuildId, MessageId};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    sea_query::Expr,
+};
+use tracing::warn;
+
+use crate::{
+    Error,
+    entities::movie_night,
+    infrastructure::{
+        ids::{id_from_string, id_to_string},
+        media_servers::MediaServerKind,
+    },
+};
+
+/// A channel showing what is playing on a media server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MovieNight {
+    pub guild_id: GuildId,
+    pub channel_id: ChannelId,
+    pub server: MediaServerKind,
+    /// Message kept up to date, None until it is posted.
+    pub message_id: Option<MessageId>,
+}
+
+impl TryFrom<movie_night::Model> for MovieNight {
+    type Error = Error;
+
+    fn try_from(value: movie_night::Model) -> Result<Self, Self::Error> {
+        Ok(Self {
+            guild_id: id_from_string(&value.guild_id)?,
+            channel_id: id_from_string(&value.channel_id)?,
+            server: value.server.parse()?,
+            message_id: id_from_string(&value.message_id).ok(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MovieNightRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> MovieNightRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    pub async fn get(&self, guild_id: GuildId) -> Result<Option<MovieNight>, Error> {
+        movie_night::Entity::find_by_id(id_to_string(guild_id))
+            .one(self.db)
+            .await?
+            .map(MovieNight::try_from)
+            .transpose()
+    }
+
+    /// Gets the movie night channels of every guild, skipping invalid rows.
+    pub async fn all(&self) -> Result<Vec<MovieNight>, Error> {
+        Ok(movie_night::Entity::find()
+            .all(self.db)
+            .await?
+            .into_iter()
+            .filter_map(|x| {
+                let guild_id = x.guild_id.clone();
+                MovieNight::try_from(x)
+                    .inspect_err(|e| warn!("Invalid movie night of guild {}: {:?}", guild_id, e))
+                    .ok()
+            })
+            .collect())
+    }
+
+    /// Shows what is playing on a media server in a channel, replacing the previous configuration. A new message is
+    /// posted on the next poll.
+    pub async fn enable(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        server: MediaServerKind,
+    ) -> Result<(), Error> {
+        movie_night::Entity::insert(movie_night::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            channel_id: Set(id_to_string(channel_id)),
+            server: Set(server.as_str().to_string()),
+            message_id: Set(String::new()),
+        })
+        .on_conflict(
+            OnConflict::column(movie_night::Column::GuildId)
+                .update_columns([
+                    movie_night::Column::ChannelId,
+                    movie_night::Column::Server,
+                    movie_night::Column::MessageId,
+                ])
+                .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Stops showing what is playing on a guild, returning whether it was enabled.
+    pub async fn disable(&self, guild_id: GuildId) -> Result<bool, Error> {
+        let result = movie_night::Entity::delete_by_id(id_to_string(guild_id))
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    pub async fn set_message(&self, guild_id: GuildId, message_id: MessageId) -> Result<(), Error> {
+        movie_night::Entity::update_many()
+            .col_expr(
+                movie_night::Column::MessageId,
+                Expr::value(id_to_string(message_id)),
+            )
+            .filter(movie_night::Column::GuildId.eq(id_to_string(guild_id)))
+            .exec(self.db)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+/*!
+
+Data access for the past names of members: username, display name and nickname changes seen on each guild, reviewed
+by moderators with `/namehistory`.
+
+Empty names mean there was no name, e.g. a member without a nickname.
+
+*/
+
+use std::str::FromStr;
+
+use poise::serenity_prelude::{GuildId, UserId, async_trait};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect,
+};
+use tracing::info;
+
+use crate::{
+    Error,
+    entities::name_history,
+    infrastructure::{ids::id_to_string, retention::RetentionTask},
+};
+
+const SECONDS_PER_DAY: i64 = 60 * 60 * 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameKind {
+    Username,
+    /// The display name of the account, shown when the member has no nickname.
+    GlobalName,
+    Nickname,
+}
+
+impl NameKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Username => "username",
+            Self::GlobalName => "global_name",
+            Self::Nickname => "nickname",
+        }
+    }
+
+    /// Name shown to moderators.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Username => "Username",
+            Self::GlobalName => "Display name",
+            Self::Nickname => "Nickname",
+        }
+    }
+}
+
+impl FromStr for NameKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        [Self::Username, Self::GlobalName, Self::Nickname]
+            .into_iter()
+            .find(|x| x.as_str() == value)
+            .ok_or_else(|| format!("Unknown name kind '{}'", value))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NameChange {
+    pub kind: NameKind,
+    pub old_name: String,
+    pub new_name: String,
+    pub changed_at: i64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct NameHistoryRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> NameHistoryRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    pub async fn record(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        change: &NameChange,
+    ) -> Result<(), Error> {
+        name_history::Entity::insert(name_history::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            user_id: Set(id_to_string(user_id)),
+            kind: Set(change.kind.as_str().to_string()),
+            old_name: Set(change.old_name.clone()),
+            new_name: Set(change.new_name.clone()),
+            changed_at: Set(change.changed_at),
+            ..Default::default()
+        })
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Gets the latest name changes of a member of a guild, newest first.
+    pub async fn history(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        limit: u64,
+    ) -> Result<Vec<NameChange>, Error> {
+        Ok(name_history::Entity::find()
+            .filter(name_history::Column::GuildId.eq(id_to_string(guild_id)))
+            .filter(name_history::Column::UserId.eq(id_to_string(user_id)))
+            .order_by_desc(name_history::Column::ChangedAt)
+            .order_by_desc(name_history::Column::Id)
+            .limit(limit)
+            .all(self.db)
+            .await?
+            .into_iter()
+            .filter_map(|x| {
+                Some(NameChange {
+                    kind: x.kind.parse().ok()?,
+                    old_name: x.old_name,
+                    new_name: x.new_name,
+                    changed_at: x.changed_at,
+                })
+            })
+            .collect())
+    }
+
+    /// Deletes the changes older than a unix timestamp, returning the number of rows deleted.
+    pub async fn remove_before(&self, timestamp: i64) -> Result<u64, Error> {
+        let result = name_history::Entity::delete_many()
+            .filter(name_history::Column::ChangedAt.lt(timestamp))
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// Counts the changes recorded for a user on any guild.
+    pub async fn count_user_changes(&self, user_id: UserId) -> Result<u64, Error> {
+        Ok(name_history::Entity::find()
+            .filter(name_history::Column::UserId.eq(id_to_string(user_id)))
+            .count(self.db)
+            .await?)
+    }
+}
+
+/// Expires past names, which are kept for 90 days by default. Names are a moderation record, used to recognise members
+/// who come back under another name, so they are kept until they expire when a user asks to be forgotten.
+pub struct NameHistoryRetention;
+
+#[async_trait]
+impl RetentionTask for NameHistoryRetention {
+    fn name(&self) -> &'static str {
+        "name_history"
+    }
+
+    fn default_retention_days(&self) -> Option<u32> {
+        Some(90)
+    }
+
+    async fn expire(
+        &self,
+        db: &DatabaseConnection,
+        now: i64,
+        retention_days: u32,
+    ) -> Result<u64, Error> {
+        NameHistoryRepo::new(db)
+            .remove_before(now - retention_days as i64 * SECONDS_PER_DAY)
+            .await
+    }
+
+    async fn forget_user(&self, db: &DatabaseConnection, user_id: UserId) -> Result<u64, Error> {
+        let kept = NameHistoryRepo::new(db).count_user_changes(user_id).await?;
+        if kept > 0 {
+            info!(
+                "Kept {} past names of user {} until they expire",
+                kept, user_id
+            );
+        }
+        Ok(0)
+    }
+}
@@ -0,0 +1,570 @@
+/*!
+
+Data access for member join, leave and return notifications: the channels they are sent to, their message variants,
+how bot accounts are handled, when join notifications are sent, and the members seen joining before.
+
+Return notifications are sent to members who joined a guild before. They are stored as join notifications with
+negative variants, counting down from [`RETURN_PRIMARY_VARIANT`], and use the join channel.
+
+*/
+
+use migration::OnConflict;
+use poise::serenity_prelude::{ChannelId, GuildId, UserId};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection,
+    EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+};
+
+use crate::{
+    Error,
+    entities::{
+        bot_member_settings, greeting_settings, known_members, leave_notification_settings,
+        member_notification_channel, member_notification_message, rejoin_settings,
+    },
+    infrastructure::ids::{id_from_string, id_to_string},
+};
+
+/// Variant of a member notification edited by the single-message configuration commands.
+pub const PRIMARY_VARIANT: i32 = 0;
+
+/// Variant of the return notification edited by the single-message configuration commands.
+pub const RETURN_PRIMARY_VARIANT: i32 = -1;
+
+/// How join and leave notifications and default roles are handled for bot accounts on a guild.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum BotMemberMode {
+    /// Bots are treated like any other member.
+    #[default]
+    Notify,
+    /// Bots get no notifications and no default roles.
+    Suppress,
+    /// Notifications of bots are sent to a separate channel.
+    Route(ChannelId),
+}
+
+/// How members who leave and rejoin a guild are welcomed.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RejoinSettings {
+    /// Members who rejoin within this many hours of their previous join aren't welcomed again. 0 disables this.
+    pub window_hours: i32,
+    /// Members who rejoin within the window get the return notification instead of no message.
+    pub welcome_back: bool,
+}
+
+/// When the join notification of a new member is sent.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct GreetingSettings {
+    /// Seconds between a member joining and their join notification. 0 sends it right away.
+    pub delay_seconds: i32,
+    /// The join notification waits until the member passed the membership screening of the guild, so they can see
+    /// the channel it is sent to.
+    pub wait_for_pending: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct NotificationRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> NotificationRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    /// Gets the channel join (or leave) notifications of a guild are sent to.
+    pub async fn channel(&self, guild_id: GuildId, join: bool) -> Result<Option<ChannelId>, Error> {
+        Ok(
+            member_notification_channel::Entity::find_by_id((id_to_string(guild_id), join))
+                .one(self.db)
+                .await?
+                .map(|x| id_from_string(&x.channel_id))
+                .transpose()?,
+        )
+    }
+
+    pub async fn set_channel(
+        &self,
+        guild_id: GuildId,
+        join: bool,
+        channel_id: ChannelId,
+    ) -> Result<(), Error> {
+        member_notification_channel::Entity::insert(member_notification_channel::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            join: Set(join),
+            channel_id: Set(id_to_string(channel_id)),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                member_notification_channel::Column::GuildId,
+                member_notification_channel::Column::Join,
+            ])
+            .update_columns([member_notification_channel::Column::ChannelId])
+            .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn remove_channel(&self, guild_id: GuildId, join: bool) -> Result<(), Error> {
+        member_notification_channel::Entity::delete_by_id((id_to_string(guild_id), join))
+            .exec(self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Gets the notification channels of every guild, skipping rows that can't be parsed.
+    pub async fn all_channels(&self) -> Result<Vec<(GuildId, bool, ChannelId)>, Error> {
+        Ok(member_notification_channel::Entity::find()
+            .all(self.db)
+            .await?
+            .into_iter()
+            .filter_map(|x| {
+                Some((
+                    id_from_string(&x.guild_id).ok()?,
+                    x.join,
+                    id_from_string(&x.channel_id).ok()?,
+                ))
+            })
+            .collect())
+    }
+
+    pub async fn variant(
+        &self,
+        guild_id: GuildId,
+        join: bool,
+        variant: i32,
+    ) -> Result<Option<member_notification_message::Model>, Error> {
+        Ok(
+            member_notification_message::Entity::find_by_id((
+                id_to_string(guild_id),
+                join,
+                variant,
+            ))
+            .one(self.db)
+            .await?,
+        )
+    }
+
+    /// Gets the join (or leave) notification variants of a guild, ordered by variant. Return variants are left out.
+    pub async fn variants(
+        &self,
+        guild_id: GuildId,
+        join: bool,
+    ) -> Result<Vec<member_notification_message::Model>, Error> {
+        Ok(member_notification_message::Entity::find()
+            .filter(member_notification_message::Column::GuildId.eq(id_to_string(guild_id)))
+            .filter(member_notification_message::Column::Join.eq(join))
+            .filter(member_notification_message::Column::Variant.gte(PRIMARY_VARIANT))
+            .order_by_asc(member_notification_message::Column::Variant)
+            .all(self.db)
+            .await?)
+    }
+
+    /// Gets the return notification variants of a guild, starting with the primary return variant.
+    pub async fn return_variants(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<Vec<member_notification_message::Model>, Error> {
+        Ok(member_notification_message::Entity::find()
+            .filter(member_notification_message::Column::GuildId.eq(id_to_string(guild_id)))
+            .filter(member_notification_message::Column::Join.eq(true))
+            .filter(member_notification_message::Column::Variant.lte(RETURN_PRIMARY_VARIANT))
+            .order_by_desc(member_notification_message::Column::Variant)
+            .all(self.db)
+            .await?)
+    }
+
+    /// Gets every notification variant of a guild, including return variants, join variants first.
+    pub async fn guild_variants(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<Vec<member_notification_message::Model>, Error> {
+        Ok(member_notification_message::Entity::find()
+            .filter(member_notification_message::Column::GuildId.eq(id_to_string(guild_id)))
+            .order_by_desc(member_notification_message::Column::Join)
+            .order_by_asc(member_notification_message::Column::Variant)
+            .all(self.db)
+            .await?)
+    }
+
+    /// Returns the index for a new notification variant, one past the highest existing variant.
+    pub async fn next_variant(&self, guild_id: GuildId, join: bool) -> Result<i32, Error> {
+        let max: Option<Option<i32>> = member_notification_message::Entity::find()
+            .select_only()
+            .column_as(member_notification_message::Column::Variant.max(), "max")
+            .filter(member_notification_message::Column::GuildId.eq(id_to_string(guild_id)))
+            .filter(member_notification_message::Column::Join.eq(join))
+            .filter(member_notification_message::Column::Variant.gte(PRIMARY_VARIANT))
+            .into_tuple()
+            .one(self.db)
+            .await?;
+        Ok(max.flatten().map(|x| x + 1).unwrap_or(PRIMARY_VARIANT))
+    }
+
+    /// Returns the variant for a new return notification variant, one below the lowest existing return variant.
+    pub async fn next_return_variant(&self, guild_id: GuildId) -> Result<i32, Error> {
+        let min: Option<Option<i32>> = member_notification_message::Entity::find()
+            .select_only()
+            .column_as(member_notification_message::Column::Variant.min(), "min")
+            .filter(member_notification_message::Column::GuildId.eq(id_to_string(guild_id)))
+            .filter(member_notification_message::Column::Join.eq(true))
+            .filter(member_notification_message::Column::Variant.lte(RETURN_PRIMARY_VARIANT))
+            .into_tuple()
+            .one(self.db)
+            .await?;
+        Ok(min
+            .flatten()
+            .map(|x| x - 1)
+            .unwrap_or(RETURN_PRIMARY_VARIANT))
+    }
+
+    pub async fn insert_variant(
+        &self,
+        model: member_notification_message::ActiveModel,
+    ) -> Result<(), Error> {
+        member_notification_message::Entity::insert(model)
+            .exec(self.db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn update_variant(
+        &self,
+        model: member_notification_message::ActiveModel,
+    ) -> Result<(), Error> {
+        model.update(self.db).await?;
+        Ok(())
+    }
+
+    /// Deletes a notification variant, returning whether it existed.
+    pub async fn delete_variant(
+        &self,
+        guild_id: GuildId,
+        join: bool,
+        variant: i32,
+    ) -> Result<bool, Error> {
+        let result = member_notification_message::Entity::delete_by_id((
+            id_to_string(guild_id),
+            join,
+            variant,
+        ))
+        .exec(self.db)
+        .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Gets how bot accounts are handled on a guild.
+    pub async fn bot_member_mode(&self, guild_id: GuildId) -> Result<BotMemberMode, Error> {
+        let model = bot_member_settings::Entity::find_by_id(id_to_string(guild_id))
+            .one(self.db)
+            .await?;
+        Ok(match model {
+            Some(model) if model.suppress => BotMemberMode::Suppress,
+            Some(model) => match id_from_string::<ChannelId>(&model.channel_id) {
+                Ok(channel) => BotMemberMode::Route(channel),
+                Err(_) => BotMemberMode::Notify,
+            },
+            None => BotMemberMode::Notify,
+        })
+    }
+
+    pub async fn set_bot_member_mode(
+        &self,
+        guild_id: GuildId,
+        mode: &BotMemberMode,
+    ) -> Result<(), Error> {
+        let (suppress, channel_id) = match mode {
+            BotMemberMode::Notify => (false, String::new()),
+            BotMemberMode::Suppress => (true, String::new()),
+            BotMemberMode::Route(channel) => (false, id_to_string(*channel)),
+        };
+        bot_member_settings::Entity::insert(bot_member_settings::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            suppress: Set(suppress),
+            channel_id: Set(channel_id),
+        })
+        .on_conflict(
+            OnConflict::column(bot_member_settings::Column::GuildId)
+                .update_columns([
+                    bot_member_settings::Column::Suppress,
+                    bot_member_settings::Column::ChannelId,
+                ])
+                .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Gets whether leave notifications of a guild show the moderator and reason of kicks and bans.
+    pub async fn staff_mode(&self, guild_id: GuildId) -> Result<bool, Error> {
+        Ok(
+            leave_notification_settings::Entity::find_by_id(id_to_string(guild_id))
+                .one(self.db)
+                .await?
+                .is_some_and(|x| x.staff_mode),
+        )
+    }
+
+    pub async fn set_staff_mode(&self, guild_id: GuildId, enabled: bool) -> Result<(), Error> {
+        leave_notification_settings::Entity::insert(leave_notification_settings::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            staff_mode: Set(enabled),
+        })
+        .on_conflict(
+            OnConflict::column(leave_notification_settings::Column::GuildId)
+                .update_column(leave_notification_settings::Column::StaffMode)
+                .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn rejoin_settings(&self, guild_id: GuildId) -> Result<RejoinSettings, Error> {
+        Ok(rejoin_settings::Entity::find_by_id(id_to_string(guild_id))
+            .one(self.db)
+            .await?
+            .map(|x| RejoinSettings {
+                window_hours: x.window_hours,
+                welcome_back: x.welcome_back,
+            })
+            .unwrap_or_default())
+    }
+
+    pub async fn set_rejoin_settings(
+        &self,
+        guild_id: GuildId,
+        settings: RejoinSettings,
+    ) -> Result<(), Error> {
+        rejoin_settings::Entity::insert(rejoin_settings::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            window_hours: Set(settings.window_hours),
+            welcome_back: Set(settings.welcome_back),
+        })
+        .on_conflict(
+            OnConflict::column(rejoin_settings::Column::GuildId)
+                .update_columns([
+                    rejoin_settings::Column::WindowHours,
+                    rejoin_settings::Column::WelcomeBack,
+                ])
+                .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn greeting_settings(&self, guild_id: GuildId) -> Result<GreetingSettings, Error> {
+        Ok(
+            greeting_settings::Entity::find_by_id(id_to_string(guild_id))
+                .one(self.db)
+                .await?
+                .map(|x| GreetingSettings {
+                    delay_seconds: x.delay_seconds,
+                    wait_for_pending: x.wait_for_pending,
+                })
+                .unwrap_or_default(),
+        )
+    }
+
+    pub async fn set_greeting_settings(
+        &self,
+        guild_id: GuildId,
+        settings: GreetingSettings,
+    ) -> Result<(), Error> {
+        greeting_settings::Entity::insert(greeting_settings::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            delay_seconds: Set(settings.delay_seconds),
+            wait_for_pending: Set(settings.wait_for_pending),
+        })
+        .on_conflict(
+            OnConflict::column(greeting_settings::Column::GuildId)
+                .update_columns([
+                    greeting_settings::Column::DelaySeconds,
+                    greeting_settings::Column::WaitForPending,
+                ])
+                .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Records that a member joined a guild, returning the time of their previous join if they were seen before.
+    pub async fn record_join(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        joined_at: i64,
+    ) -> Result<Option<i64>, Error> {
+        let previous =
+            known_members::Entity::find_by_id((id_to_string(guild_id), id_to_string(user_id)))
+                .one(self.db)
+                .await?
+                .map(|x| x.joined_at);
+        known_members::Entity::insert(known_members::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            user_id: Set(id_to_string(user_id)),
+            joined_at: Set(joined_at),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                known_members::Column::GuildId,
+                known_members::Column::UserId,
+            ])
+            .update_column(known_members::Column::JoinedAt)
+            .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(previous)
+    }
+
+    /// Forgets the members whose latest join is before a unix timestamp, returning the number of members forgotten.
+    pub async fn remove_known_members_before(&self, timestamp: i64) -> Result<u64, Error> {
+        let result = known_members::Entity::delete_many()
+            .filter(known_members::Column::JoinedAt.lt(timestamp))
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// Forgets the joins of a user on every guild, returning the number of guilds they were forgotten on.
+    pub async fn remove_user_joins(&self, user_id: UserId) -> Result<u64, Error> {
+        let result = known_members::Entity::delete_many()
+            .filter(known_members::Column::UserId.eq(id_to_string(user_id)))
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::IntoActiveModel;
+
+    use crate::services::testing::migrated_db;
+
+    use super::*;
+
+    fn message(guild_id: GuildId, join: bool, variant: i32) -> member_notification_message::Model {
+        member_notification_message::Model {
+            guild_id: id_to_string(guild_id),
+            join,
+            variant,
+            content: format!("Variant {}", variant),
+            title: String::new(),
+            description: String::new(),
+            thumbnail_is_file: false,
+            thumbnail_url: String::new(),
+            image_is_file: false,
+            image_url: String::new(),
+            author: String::new(),
+            author_icon_is_file: false,
+            author_icon_url: String::new(),
+            footer: String::new(),
+            footer_icon_is_file: false,
+            footer_icon_url: String::new(),
+        }
+    }
+
+    fn variants_of(models: Vec<member_notification_message::Model>) -> Vec<(bool, i32)> {
+        models.into_iter().map(|x| (x.join, x.variant)).collect()
+    }
+
+    #[tokio::test]
+    async fn channels_are_replaced_per_kind() -> Result<(), Error> {
+        let db = migrated_db().await;
+        let repo = NotificationRepo::new(&db);
+        let guild = GuildId::new(1);
+
+        assert_eq!(repo.channel(guild, true).await?, None);
+        repo.set_channel(guild, true, ChannelId::new(10)).await?;
+        repo.set_channel(guild, true, ChannelId::new(11)).await?;
+        repo.set_channel(guild, false, ChannelId::new(12)).await?;
+        assert_eq!(repo.channel(guild, true).await?, Some(ChannelId::new(11)));
+        assert_eq!(repo.channel(guild, false).await?, Some(ChannelId::new(12)));
+        assert_eq!(repo.all_channels().await?.len(), 2);
+
+        repo.remove_channel(guild, true).await?;
+        assert_eq!(repo.channel(guild, true).await?, None);
+        assert_eq!(
+            repo.all_channels().await?,
+            vec![(guild, false, ChannelId::new(12))]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn return_variants_are_kept_apart() -> Result<(), Error> {
+        let db = migrated_db().await;
+        let repo = NotificationRepo::new(&db);
+        let guild = GuildId::new(1);
+
+        assert_eq!(repo.next_variant(guild, true).await?, PRIMARY_VARIANT);
+        assert_eq!(
+            repo.next_return_variant(guild).await?,
+            RETURN_PRIMARY_VARIANT
+        );
+
+        for (join, variant) in [(true, 0), (true, 1), (false, 0), (true, -1), (true, -2)] {
+            repo.insert_variant(message(guild, join, variant).into_active_model())
+                .await?;
+        }
+        repo.insert_variant(message(GuildId::new(2), true, 5).into_active_model())
+            .await?;
+
+        assert_eq!(
+            variants_of(repo.variants(guild, true).await?),
+            [(true, 0), (true, 1)]
+        );
+        assert_eq!(
+            variants_of(repo.variants(guild, false).await?),
+            [(false, 0)]
+        );
+        assert_eq!(
+            variants_of(repo.return_variants(guild).await?),
+            [(true, -1), (true, -2)]
+        );
+        assert_eq!(
+            variants_of(repo.guild_variants(guild).await?),
+            [(true, -2), (true, -1), (true, 0), (true, 1), (false, 0)]
+        );
+        assert_eq!(repo.next_variant(guild, true).await?, 2);
+        assert_eq!(repo.next_variant(guild, false).await?, 1);
+        assert_eq!(repo.next_return_variant(guild).await?, -3);
+
+        let mut update = message(guild, true, 1).into_active_model();
+        update.content = Set("Updated".to_string());
+        repo.update_variant(update).await?;
+        assert_eq!(
+            repo.variant(guild, true, 1).await?.map(|x| x.content),
+            Some("Updated".to_string())
+        );
+
+        assert!(repo.delete_variant(guild, true, 1).await?);
+        assert!(!repo.delete_variant(guild, true, 1).await?);
+        assert_eq!(repo.variant(guild, true, 1).await?, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn bot_member_mode_round_trips() -> Result<(), Error> {
+        let db = migrated_db().await;
+        let repo = NotificationRepo::new(&db);
+        let guild = GuildId::new(1);
+
+        assert_eq!(repo.bot_member_mode(guild).await?, BotMemberMode::Notify);
+        for mode in [
+            BotMemberMode::Suppress,
+            BotMemberMode::Route(ChannelId::new(10)),
+            BotMemberMode::Notify,
+        ] {
+            repo.set_bot_member_mode(guild, &mode).await?;
+            assert_eq!(repo.bot_member_mode(guild).await?, mode);
+        }
+        Ok(())
+    }
+}
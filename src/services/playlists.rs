@@ -0,0 +1,158 @@
+/*!
+
+Data access for the playlists users build with `/playlist`: named lists of tracks, owned by a user and usable on any
+guild.
+
+Tracks are kept in the order they were added, which is the order of their ids.
+
+*/
+
+use poise::serenity_prelude::{UserId, async_trait};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect, sea_query::Expr,
+};
+
+use crate::{
+    Error,
+    entities::{playlist_tracks, user_playlists},
+    infrastructure::{ids::id_to_string, retention::RetentionTask},
+};
+
+/// Maximum number of playlists of a user.
+pub const MAX_PLAYLISTS: usize = 25;
+
+/// Maximum number of tracks in a playlist.
+pub const MAX_PLAYLIST_TRACKS: u64 = 100;
+
+/// Maximum length of the name of a playlist.
+pub const MAX_PLAYLIST_NAME_LENGTH: usize = 50;
+
+#[derive(Debug, Clone, Copy)]
+pub struct PlaylistRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> PlaylistRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    /// Gets the playlists of a user, in alphabetical order.
+    pub async fn playlists(&self, user_id: UserId) -> Result<Vec<user_playlists::Model>, Error> {
+        Ok(user_playlists::Entity::find()
+            .filter(user_playlists::Column::UserId.eq(id_to_string(user_id)))
+            .order_by_asc(user_playlists::Column::Name)
+            .all(self.db)
+            .await?)
+    }
+
+    pub async fn find(
+        &self,
+        user_id: UserId,
+        name: &str,
+    ) -> Result<Option<user_playlists::Model>, Error> {
+        Ok(user_playlists::Entity::find()
+            .filter(user_playlists::Column::UserId.eq(id_to_string(user_id)))
+            .filter(user_playlists::Column::Name.eq(name))
+            .one(self.db)
+            .await?)
+    }
+
+    /// Creates an empty playlist, returning its id.
+    pub async fn create(&self, user_id: UserId, name: &str, created_at: i64) -> Result<i32, Error> {
+        Ok(user_playlists::Entity::insert(user_playlists::ActiveModel {
+            user_id: Set(id_to_string(user_id)),
+            name: Set(name.to_string()),
+            created_at: Set(created_at),
+            ..Default::default()
+        })
+        .exec(self.db)
+        .await?
+        .last_insert_id)
+    }
+
+    /// Deletes a playlist and its tracks.
+    pub async fn delete(&self, playlist_id: i32) -> Result<(), Error> {
+        playlist_tracks::Entity::delete_many()
+            .filter(playlist_tracks::Column::PlaylistId.eq(playlist_id))
+            .exec(self.db)
+            .await?;
+        user_playlists::Entity::delete_by_id(playlist_id)
+            .exec(self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Gets the tracks of a playlist, in the order they were added.
+    pub async fn tracks(&self, playlist_id: i32) -> Result<Vec<playlist_tracks::Model>, Error> {
+        Ok(playlist_tracks::Entity::find()
+            .filter(playlist_tracks::Column::PlaylistId.eq(playlist_id))
+            .order_by_asc(playlist_tracks::Column::Id)
+            .all(self.db)
+            .await?)
+    }
+
+    pub async fn track_count(&self, playlist_id: i32) -> Result<u64, Error> {
+        let count: Option<i64> = playlist_tracks::Entity::find()
+            .select_only()
+            .column_as(Expr::col(playlist_tracks::Column::Id).count(), "count")
+            .filter(playlist_tracks::Column::PlaylistId.eq(playlist_id))
+            .into_tuple()
+            .one(self.db)
+            .await?;
+        Ok(count.unwrap_or_default().max(0) as u64)
+    }
+
+    /// Adds a track to the end of a playlist.
+    pub async fn add_track(
+        &self,
+        playlist_id: i32,
+        title: &str,
+        url: &str,
+        duration_seconds: Option<u64>,
+    ) -> Result<(), Error> {
+        playlist_tracks::Entity::insert(playlist_tracks::ActiveModel {
+            playlist_id: Set(playlist_id),
+            title: Set(title.to_string()),
+            url: Set(url.to_string()),
+            duration_seconds: Set(duration_seconds.map(|x| x.min(i32::MAX as u64) as i32)),
+            ..Default::default()
+        })
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Deletes every playlist of a user, returning the number of rows deleted.
+    pub async fn forget_user(&self, user_id: UserId) -> Result<u64, Error> {
+        let mut deleted = 0;
+        for playlist in self.playlists(user_id).await? {
+            deleted += playlist_tracks::Entity::delete_many()
+                .filter(playlist_tracks::Column::PlaylistId.eq(playlist.id))
+                .exec(self.db)
+                .await?
+                .rows_affected;
+        }
+        deleted += user_playlists::Entity::delete_many()
+            .filter(user_playlists::Column::UserId.eq(id_to_string(user_id)))
+            .exec(self.db)
+            .await?
+            .rows_affected;
+        Ok(deleted)
+    }
+}
+
+/// Deletes the playlists of users who asked to be forgotten. Playlists are built by hand, so they never expire.
+pub struct PlaylistRetention;
+
+#[async_trait]
+impl RetentionTask for PlaylistRetention {
+    fn name(&self) -> &'static str {
+        "user_playlists"
+    }
+
+    async fn forget_user(&self, db: &DatabaseConnection, user_id: UserId) -> Result<u64, Error> {
+        PlaylistRepo::new(db).forget_user(user_id).await
+    }
+}
@@ -0,0 +1,100 @@
+/*!
+
+Data access for the default roles given to new members when they join.
+
+*/
+
+use migration::OnConflict;
+use poise::serenity_prelude::{GuildId, RoleId};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+};
+
+use crate::{
+    Error,
+    entities::welcome_roles,
+    infrastructure::ids::{id_from_string, id_to_string},
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct RolesRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> RolesRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    /// Gets the roles given to new members of a guild, skipping rows that can't be parsed.
+    pub async fn default_roles(&self, guild_id: GuildId) -> Result<Vec<RoleId>, Error> {
+        Ok(welcome_roles::Entity::find()
+            .filter(welcome_roles::Column::GuildId.eq(id_to_string(guild_id)))
+            .all(self.db)
+            .await?
+            .iter()
+            .filter_map(|x| id_from_string(&x.role_id).ok())
+            .collect())
+    }
+
+    pub async fn add_default_role(&self, guild_id: GuildId, role_id: RoleId) -> Result<(), Error> {
+        welcome_roles::Entity::insert(welcome_roles::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            role_id: Set(id_to_string(role_id)),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                welcome_roles::Column::GuildId,
+                welcome_roles::Column::RoleId,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .exec_without_returning(self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Stops giving a role to new members, returning whether it was a default role.
+    pub async fn remove_default_role(
+        &self,
+        guild_id: GuildId,
+        role_id: RoleId,
+    ) -> Result<bool, Error> {
+        let result =
+            welcome_roles::Entity::delete_by_id((id_to_string(guild_id), id_to_string(role_id)))
+                .exec(self.db)
+                .await?;
+        Ok(result.rows_affected > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::services::testing::migrated_db;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn default_roles_are_added_and_removed() -> Result<(), Error> {
+        let db = migrated_db().await;
+        let repo = RolesRepo::new(&db);
+        let (guild, other_guild) = (GuildId::new(1), GuildId::new(2));
+        let (first, second) = (RoleId::new(10), RoleId::new(11));
+
+        repo.add_default_role(guild, first).await?;
+        repo.add_default_role(guild, second).await?;
+        repo.add_default_role(guild, first).await?;
+        repo.add_default_role(other_guild, first).await?;
+
+        let mut roles = repo.default_roles(guild).await?;
+        roles.sort();
+        assert_eq!(roles, vec![first, second]);
+
+        assert!(repo.remove_default_role(guild, first).await?);
+        assert!(!repo.remove_default_role(guild, first).await?);
+        assert_eq!(repo.default_roles(guild).await?, vec![second]);
+        assert_eq!(repo.default_roles(other_guild).await?, vec![first]);
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+/*!
+
+Data access for the outcomes of `/roll`, kept per member so `/rollstats` can show how lucky they have been over time.
+
+Rolls are recorded per guild, so a group's stats only count the rolls made on its server. Rolls made in direct
+messages are recorded without a guild.
+
+*/
+
+use std::collections::BTreeMap;
+
+use poise::serenity_prelude::{GuildId, UserId, async_trait};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QuerySelect, Select, sea_query::Expr,
+};
+
+use crate::{
+    Error,
+    entities::roll_history,
+    infrastructure::{ids::id_to_string, retention::RetentionTask},
+};
+
+/// Stored guild id of the rolls made in direct messages.
+fn guild_key(guild_id: Option<GuildId>) -> String {
+    guild_id.map(id_to_string).unwrap_or_default()
+}
+
+/// Outcomes of the rolls of a member with one type of die.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DieStats {
+    pub sides: u32,
+    pub rolls: i64,
+    /// Sum of the rolled values.
+    pub total: i64,
+    /// Rolls that landed on 1.
+    pub ones: i64,
+    /// Rolls that landed on the highest side, e.g. natural 20s on a D20.
+    pub maxes: i64,
+}
+
+impl DieStats {
+    pub fn average(&self) -> f64 {
+        match self.rolls {
+            0 => 0.0,
+            x => self.total as f64 / x as f64,
+        }
+    }
+
+    /// Average of a fair die with these sides.
+    pub fn expected_average(&self) -> f64 {
+        (self.sides as f64 + 1.0) / 2.0
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RollHistoryRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> RollHistoryRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    pub async fn record(
+        &self,
+        guild_id: Option<GuildId>,
+        user_id: UserId,
+        sides: u32,
+        value: u32,
+        now: i64,
+    ) -> Result<(), Error> {
+        roll_history::Entity::insert(roll_history::ActiveModel {
+            guild_id: Set(guild_key(guild_id)),
+            user_id: Set(id_to_string(user_id)),
+            sides: Set(sides as i32),
+            value: Set(value as i32),
+            rolled_at: Set(now),
+            ..Default::default()
+        })
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Counts the selected rolls of each type of die.
+    async fn count_by_sides(
+        &self,
+        query: Select<roll_history::Entity>,
+    ) -> Result<Vec<(i32, i64)>, Error> {
+        Ok(query
+            .select_only()
+            .column(roll_history::Column::Sides)
+            .column_as(roll_history::Column::Id.count(), "rolls")
+            .group_by(roll_history::Column::Sides)
+            .into_tuple::<(i32, i64)>()
+            .all(self.db)
+            .await?)
+    }
+
+    /// Gets the outcomes of the rolls of a member, by type of die from the fewest sides.
+    pub async fn stats(
+        &self,
+        guild_id: Option<GuildId>,
+        user_id: UserId,
+    ) -> Result<Vec<DieStats>, Error> {
+        let rolls = || {
+            roll_history::Entity::find()
+                .filter(roll_history::Column::GuildId.eq(guild_key(guild_id)))
+                .filter(roll_history::Column::UserId.eq(id_to_string(user_id)))
+        };
+
+        let mut stats: BTreeMap<i32, DieStats> = rolls()
+            .select_only()
+            .column(roll_history::Column::Sides)
+            .column_as(roll_history::Column::Id.count(), "rolls")
+            .column_as(roll_history::Column::Value.sum(), "total")
+            .group_by(roll_history::Column::Sides)
+            .into_tuple::<(i32, i64, i64)>()
+            .all(self.db)
+            .await?
+            .into_iter()
+            .map(|(sides, rolls, total)| {
+                let stats = DieStats {
+                    sides: sides.max(0) as u32,
+                    rolls,
+                    total,
+                    ..Default::default()
+                };
+                (sides, stats)
+            })
+            .collect();
+        let ones = rolls().filter(roll_history::Column::Value.eq(1));
+        for (sides, count) in self.count_by_sides(ones).await? {
+            if let Some(x) = stats.get_mut(&sides) {
+                x.ones = count;
+            }
+        }
+        let maxes = rolls()
+            .filter(Expr::col(roll_history::Column::Value).equals(roll_history::Column::Sides));
+        for (sides, count) in self.count_by_sides(maxes).await? {
+            if let Some(x) = stats.get_mut(&sides) {
+                x.maxes = count;
+            }
+        }
+        Ok(stats.into_values().collect())
+    }
+
+    /// Deletes the rolls made before a unix timestamp, returning the number of rows deleted.
+    pub async fn remove_before(&self, timestamp: i64) -> Result<u64, Error> {
+        let result = roll_history::Entity::delete_many()
+            .filter(roll_history::Column::RolledAt.lt(timestamp))
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    pub async fn forget_user(&self, user_id: UserId) -> Result<u64, Error> {
+        let result = roll_history::Entity::delete_many()
+            .filter(roll_history::Column::UserId.eq(id_to_string(user_id)))
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
+
+/// Deletes the roll history of users who asked to be forgotten. Rolls are kept until a retention period is configured,
+/// since their stats are meant to span campaigns.
+pub struct RollHistoryRetention;
+
+#[async_trait]
+impl RetentionTask for RollHistoryRetention {
+    fn name(&self) -> &'static str {
+        "roll_history"
+    }
+
+    async fn expire(
+        &self,
+        db: &DatabaseConnection,
+        now: i64,
+        retention_days: u32,
+    ) -> Result<u64, Error> {
+        RollHistoryRepo::new(db)
+            .remove_before(now - retention_days as i64 * 24 * 60 * 60)
+            .await
+    }
+
+    async fn forget_user(&self, db: &DatabaseConnection, user_id: UserId) -> Result<u64, Error> {
+        RollHistoryRepo::new(db).forget_user(user_id).await
+    }
+}
@@ -0,0 +1,118 @@
+/*!
+
+Data access for the embed drafts saved with `/embed create`, so an embed can be posted again or edited later.
+
+*/
+
+use migration::OnConflict;
+use poise::serenity_prelude::GuildId;
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder,
+};
+
+use crate::{Error, entities::saved_embeds, infrastructure::ids::id_to_string};
+
+/// Maximum number of drafts saved by a guild.
+pub const MAX_SAVED_EMBEDS: usize = 25;
+
+/// Maximum length of the name of a draft.
+pub const MAX_EMBED_NAME_LENGTH: usize = 32;
+
+/// The text of an embed as typed in the embed builder. Empty strings stand for missing parts.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EmbedDraft {
+    pub title: String,
+    pub description: String,
+    pub color: String,
+    pub image_url: String,
+    /// One field per line, as `name | value`.
+    pub fields: String,
+}
+
+impl From<saved_embeds::Model> for EmbedDraft {
+    fn from(value: saved_embeds::Model) -> Self {
+        Self {
+            title: value.title,
+            description: value.description,
+            color: value.color,
+            image_url: value.image_url,
+            fields: value.fields,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SavedEmbedRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> SavedEmbedRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    pub async fn get(&self, guild_id: GuildId, name: &str) -> Result<Option<EmbedDraft>, Error> {
+        Ok(
+            saved_embeds::Entity::find_by_id((id_to_string(guild_id), name.to_string()))
+                .one(self.db)
+                .await?
+                .map(EmbedDraft::from),
+        )
+    }
+
+    /// Gets the names of the drafts of a guild, alphabetically.
+    pub async fn names(&self, guild_id: GuildId) -> Result<Vec<String>, Error> {
+        Ok(saved_embeds::Entity::find()
+            .filter(saved_embeds::Column::GuildId.eq(id_to_string(guild_id)))
+            .order_by_asc(saved_embeds::Column::Name)
+            .all(self.db)
+            .await?
+            .into_iter()
+            .map(|x| x.name)
+            .collect())
+    }
+
+    /// Saves a draft, replacing the draft with the same name.
+    pub async fn save(
+        &self,
+        guild_id: GuildId,
+        name: &str,
+        draft: &EmbedDraft,
+        updated_at: i64,
+    ) -> Result<(), Error> {
+        saved_embeds::Entity::insert(saved_embeds::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            name: Set(name.to_string()),
+            title: Set(draft.title.clone()),
+            description: Set(draft.description.clone()),
+            color: Set(draft.color.clone()),
+            image_url: Set(draft.image_url.clone()),
+            fields: Set(draft.fields.clone()),
+            updated_at: Set(updated_at),
+        })
+        .on_conflict(
+            OnConflict::columns([saved_embeds::Column::GuildId, saved_embeds::Column::Name])
+                .update_columns([
+                    saved_embeds::Column::Title,
+                    saved_embeds::Column::Description,
+                    saved_embeds::Column::Color,
+                    saved_embeds::Column::ImageUrl,
+                    saved_embeds::Column::Fields,
+                    saved_embeds::Column::UpdatedAt,
+                ])
+                .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Deletes a draft, returning whether it existed.
+    pub async fn delete(&self, guild_id: GuildId, name: &str) -> Result<bool, Error> {
+        let result = saved_embeds::Entity::delete_by_id((id_to_string(guild_id), name.to_string()))
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+}
@@ -0,0 +1,146 @@
+/*!
+
+Data access for the screening of new members: the staff channel their answers are reviewed in, and the questions they
+are asked when they join.
+
+*/
+
+use migration::OnConflict;
+use poise::serenity_prelude::{ChannelId, GuildId};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder,
+};
+
+use crate::{
+    Error,
+    entities::{screening_questions, screening_settings},
+    infrastructure::ids::{id_from_string, id_to_string},
+};
+
+/// Maximum number of questions, the number of text inputs a modal can hold.
+pub const MAX_QUESTIONS: usize = 5;
+
+/// Maximum length of a question, the length of a modal text input label.
+pub const MAX_QUESTION_LENGTH: usize = 45;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Screening {
+    pub review_channel: ChannelId,
+    pub questions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ScreeningRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> ScreeningRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    /// Gets the screening of a guild, or None if screening is disabled or has no questions.
+    pub async fn screening(&self, guild_id: GuildId) -> Result<Option<Screening>, Error> {
+        let Some(settings) = screening_settings::Entity::find_by_id(id_to_string(guild_id))
+            .one(self.db)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let questions = self.questions(guild_id).await?;
+        if questions.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Screening {
+            review_channel: id_from_string(&settings.review_channel_id)?,
+            questions,
+        }))
+    }
+
+    /// Gets the review channel of a guild, or None if screening is disabled.
+    pub async fn review_channel(&self, guild_id: GuildId) -> Result<Option<ChannelId>, Error> {
+        Ok(
+            screening_settings::Entity::find_by_id(id_to_string(guild_id))
+                .one(self.db)
+                .await?
+                .map(|x| id_from_string(&x.review_channel_id))
+                .transpose()?,
+        )
+    }
+
+    /// Enables screening on a guild, posting answers to a staff channel.
+    pub async fn enable(&self, guild_id: GuildId, review_channel: ChannelId) -> Result<(), Error> {
+        screening_settings::Entity::insert(screening_settings::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            review_channel_id: Set(id_to_string(review_channel)),
+        })
+        .on_conflict(
+            OnConflict::column(screening_settings::Column::GuildId)
+                .update_column(screening_settings::Column::ReviewChannelId)
+                .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Disables screening on a guild, keeping its questions. Returns whether screening was enabled.
+    pub async fn disable(&self, guild_id: GuildId) -> Result<bool, Error> {
+        let result = screening_settings::Entity::delete_by_id(id_to_string(guild_id))
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Gets the questions of a guild, in the order they are asked.
+    pub async fn questions(&self, guild_id: GuildId) -> Result<Vec<String>, Error> {
+        Ok(self
+            .question_models(guild_id)
+            .await?
+            .into_iter()
+            .map(|x| x.question)
+            .collect())
+    }
+
+    async fn question_models(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<Vec<screening_questions::Model>, Error> {
+        Ok(screening_questions::Entity::find()
+            .filter(screening_questions::Column::GuildId.eq(id_to_string(guild_id)))
+            .order_by_asc(screening_questions::Column::Id)
+            .all(self.db)
+            .await?)
+    }
+
+    pub async fn add_question(&self, guild_id: GuildId, question: &str) -> Result<(), Error> {
+        screening_questions::Entity::insert(screening_questions::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            question: Set(question.to_string()),
+            ..Default::default()
+        })
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Removes a question by its 1-based number in [`Self::questions`], returning the removed question.
+    pub async fn remove_question(
+        &self,
+        guild_id: GuildId,
+        number: usize,
+    ) -> Result<Option<String>, Error> {
+        let models = self.question_models(guild_id).await?;
+        let Some(model) = number
+            .checked_sub(1)
+            .and_then(|x| models.into_iter().nth(x))
+        else {
+            return Ok(None);
+        };
+        screening_questions::Entity::delete_by_id(model.id)
+            .exec(self.db)
+            .await?;
+        Ok(Some(model.question))
+    }
+}
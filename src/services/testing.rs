@@ -0,0 +1,23 @@
+/*!
+
+Helpers for testing the repositories against a migrated in-memory sqlite database.
+
+*/
+
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{ConnectOptions, Database, DatabaseConnection};
+
+/// Opens an empty in-memory database with every migration applied.
+pub async fn migrated_db() -> DatabaseConnection {
+    // Every connection to an in-memory database opens a new, empty database
+    let options = ConnectOptions::new("sqlite::memory:")
+        .max_connections(1)
+        .to_owned();
+    let db = Database::connect(options)
+        .await
+        .expect("In-memory database can be opened");
+    Migrator::up(&db, None)
+        .await
+        .expect("Migrations apply to an empty database");
+    db
+}
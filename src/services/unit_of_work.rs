@@ -0,0 +1,85 @@
+/*!
+
+Groups the database writes of a multi-step change into one transaction, along with the side effects that have to be
+undone if the change fails.
+
+Repositories are created on [`UnitOfWork::txn`] so their writes only become visible once the unit of work commits.
+Side effects outside the database, such as files written to disk, register a compensation with
+[`UnitOfWork::on_rollback`], which runs if the unit of work is rolled back instead.
+
+*/
+
+use std::{future::Future, sync::Mutex};
+
+use poise::serenity_prelude::futures::future::BoxFuture;
+use sea_orm::{DatabaseConnection, DatabaseTransaction, TransactionTrait};
+use tracing::{error, trace};
+
+use crate::Error;
+
+pub struct UnitOfWork {
+    txn: DatabaseTransaction,
+    compensations: Mutex<Vec<BoxFuture<'static, ()>>>,
+}
+
+impl UnitOfWork {
+    pub async fn begin(db: &DatabaseConnection) -> Result<Self, Error> {
+        Ok(Self {
+            txn: db.begin().await?,
+            compensations: Mutex::new(vec![]),
+        })
+    }
+
+    /// The transaction the database writes of this unit of work go to.
+    pub fn txn(&self) -> &DatabaseTransaction {
+        &self.txn
+    }
+
+    /// Registers a side effect to undo if the unit of work is rolled back. Compensations run in reverse order.
+    pub fn on_rollback(&self, compensation: impl Future<Output = ()> + Send + 'static) {
+        self.compensations
+            .lock()
+            .expect("Compensations lock should not be poisoned")
+            .push(Box::pin(compensation));
+    }
+
+    /// Commits the database writes. The compensations run if the commit fails, since nothing was written then.
+    pub async fn commit(self) -> Result<(), Error> {
+        let Self { txn, compensations } = self;
+        if let Err(e) = txn.commit().await {
+            run_compensations(compensations).await;
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// Rolls back the database writes and runs the compensations of the registered side effects.
+    pub async fn rollback(self) {
+        let Self { txn, compensations } = self;
+        if let Err(e) = txn.rollback().await {
+            error!("Failed to roll back transaction: {}", e);
+        }
+        run_compensations(compensations).await;
+    }
+
+    /// Commits the unit of work if `result` is a success, rolling it back otherwise.
+    pub async fn finish<T>(self, result: Result<T, Error>) -> Result<T, Error> {
+        match result {
+            Ok(value) => self.commit().await.map(|_| value),
+            Err(e) => {
+                self.rollback().await;
+                Err(e)
+            }
+        }
+    }
+}
+
+async fn run_compensations(compensations: Mutex<Vec<BoxFuture<'static, ()>>>) {
+    let compensations = compensations
+        .into_inner()
+        .expect("Compensations lock should not be poisoned");
+    trace!("Running {} compensation(s)", compensations.len());
+    for compensation in compensations.into_iter().rev() {
+        compensation.await;
+    }
+}
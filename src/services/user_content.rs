@@ -0,0 +1,86 @@
+/*!
+
+Data access for the reference counts of the content-addressed user content files, so a file shared by several
+notifications or guilds is only removed from disk once nothing uses it anymore.
+
+*/
+
+use migration::OnConflict;
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    sea_query::Expr,
+};
+
+use crate::{Error, entities::user_content_blobs};
+
+#[derive(Debug, Clone, Copy)]
+pub struct UserContentRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> UserContentRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    /// Records a new reference to a file.
+    pub async fn add_reference(&self, filename: &str) -> Result<(), Error> {
+        user_content_blobs::Entity::insert(user_content_blobs::ActiveModel {
+            filename: Set(filename.to_string()),
+            ref_count: Set(1),
+        })
+        .on_conflict(
+            OnConflict::column(user_content_blobs::Column::Filename)
+                .value(
+                    user_content_blobs::Column::RefCount,
+                    Expr::col(user_content_blobs::Column::RefCount).add(1),
+                )
+                .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Removes one reference to each file, forgetting the files that are no longer referenced. A file listed twice
+    /// loses two references.
+    pub async fn release(&self, filenames: &[String]) -> Result<(), Error> {
+        if filenames.is_empty() {
+            return Ok(());
+        }
+        for filename in filenames {
+            user_content_blobs::Entity::update_many()
+                .col_expr(
+                    user_content_blobs::Column::RefCount,
+                    Expr::col(user_content_blobs::Column::RefCount).sub(1),
+                )
+                .filter(user_content_blobs::Column::Filename.eq(filename))
+                .exec(self.db)
+                .await?;
+        }
+        user_content_blobs::Entity::delete_many()
+            .filter(user_content_blobs::Column::Filename.is_in(filenames.iter().cloned()))
+            .filter(user_content_blobs::Column::RefCount.lte(0))
+            .exec(self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Forgets a file if it is no longer referenced. Being a write, it also waits for the transactions that write
+    /// references to finish.
+    pub async fn forget_unreferenced(&self, filename: &str) -> Result<(), Error> {
+        user_content_blobs::Entity::delete_many()
+            .filter(user_content_blobs::Column::Filename.eq(filename))
+            .filter(user_content_blobs::Column::RefCount.lte(0))
+            .exec(self.db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn is_referenced(&self, filename: &str) -> Result<bool, Error> {
+        Ok(user_content_blobs::Entity::find_by_id(filename.to_string())
+            .one(self.db)
+            .await?
+            .is_some())
+    }
+}
@@ -0,0 +1,80 @@
+/*!
+
+Data access for the timezones users save with `/timezone`, as offsets from UTC used to read the times they give to
+`/timestamp`.
+
+*/
+
+use migration::OnConflict;
+use poise::serenity_prelude::{UserId, async_trait};
+use sea_orm::{ActiveValue::Set, ConnectionTrait, DatabaseConnection, EntityTrait};
+
+use crate::{
+    Error,
+    entities::user_timezones,
+    infrastructure::{ids::id_to_string, retention::RetentionTask},
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct UserTimezoneRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> UserTimezoneRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    /// Gets the offset from UTC of a user in minutes, or None if they haven't saved a timezone.
+    pub async fn utc_offset(&self, user_id: UserId) -> Result<Option<i32>, Error> {
+        Ok(user_timezones::Entity::find_by_id(id_to_string(user_id))
+            .one(self.db)
+            .await?
+            .map(|x| x.utc_offset))
+    }
+
+    /// Saves the offset from UTC of a user in minutes, or forgets it.
+    pub async fn set_utc_offset(
+        &self,
+        user_id: UserId,
+        utc_offset: Option<i32>,
+    ) -> Result<(), Error> {
+        let Some(utc_offset) = utc_offset else {
+            self.forget_user(user_id).await?;
+            return Ok(());
+        };
+        user_timezones::Entity::insert(user_timezones::ActiveModel {
+            user_id: Set(id_to_string(user_id)),
+            utc_offset: Set(utc_offset),
+        })
+        .on_conflict(
+            OnConflict::column(user_timezones::Column::UserId)
+                .update_column(user_timezones::Column::UtcOffset)
+                .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn forget_user(&self, user_id: UserId) -> Result<u64, Error> {
+        let result = user_timezones::Entity::delete_by_id(id_to_string(user_id))
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
+
+/// Deletes the timezone of users who asked to be forgotten. Timezones are set by hand, so they never expire.
+pub struct UserTimezoneRetention;
+
+#[async_trait]
+impl RetentionTask for UserTimezoneRetention {
+    fn name(&self) -> &'static str {
+        "user_timezones"
+    }
+
+    async fn forget_user(&self, db: &DatabaseConnection, user_id: UserId) -> Result<u64, Error> {
+        UserTimezoneRepo::new(db).forget_user(user_id).await
+    }
+}
@@ -0,0 +1,222 @@
+/*!
+
+Data access for the outgoing webhooks of each guild, registered with `/webhook`, and the log of their deliveries.
+
+*/
+
+use std::str::FromStr;
+
+use poise::serenity_prelude::GuildId;
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect,
+};
+
+use crate::{
+    Error,
+    entities::{guild_webhooks, webhook_deliveries},
+    infrastructure::ids::id_to_string,
+};
+
+/// Maximum number of webhooks of a guild.
+pub const MAX_WEBHOOKS: usize = 5;
+
+/// Guild events a webhook can be notified of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WebhookEvent {
+    MemberJoin,
+    MemberLeave,
+    /// Bans, unbans, nickname filter renames and raid lockdowns.
+    ModAction,
+    /// An advertised minecraft server went online or offline.
+    McStatus,
+}
+
+impl WebhookEvent {
+    pub const ALL: [Self; 4] = [
+        Self::MemberJoin,
+        Self::MemberLeave,
+        Self::ModAction,
+        Self::McStatus,
+    ];
+
+    /// Name of the event, sent in payloads.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::MemberJoin => "member_join",
+            Self::MemberLeave => "member_leave",
+            Self::ModAction => "mod_action",
+            Self::McStatus => "mc_status",
+        }
+    }
+}
+
+impl FromStr for WebhookEvent {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|x| x.as_str() == value)
+            .ok_or_else(|| format!("Unknown webhook event '{}'", value))
+    }
+}
+
+/// A webhook registered on a guild.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Webhook {
+    pub id: i32,
+    pub url: String,
+    /// Key the payloads are signed with.
+    pub secret: String,
+    pub events: Vec<WebhookEvent>,
+}
+
+impl From<guild_webhooks::Model> for Webhook {
+    fn from(value: guild_webhooks::Model) -> Self {
+        Self {
+            id: value.id,
+            url: value.url,
+            secret: value.secret,
+            events: value
+                .events
+                .split(',')
+                .filter_map(|x| x.parse().ok())
+                .collect(),
+        }
+    }
+}
+
+/// Outcome of delivering an event to a webhook.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delivery {
+    pub webhook_id: i32,
+    pub event: WebhookEvent,
+    pub attempts: u32,
+    /// None when no response was received.
+    pub status_code: Option<u16>,
+    /// Why the delivery failed, None if it succeeded.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WebhookRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> WebhookRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    /// Gets the webhooks of a guild, oldest first.
+    pub async fn list(&self, guild_id: GuildId) -> Result<Vec<Webhook>, Error> {
+        Ok(guild_webhooks::Entity::find()
+            .filter(guild_webhooks::Column::GuildId.eq(id_to_string(guild_id)))
+            .order_by_asc(guild_webhooks::Column::Id)
+            .all(self.db)
+            .await?
+            .into_iter()
+            .map(Webhook::from)
+            .collect())
+    }
+
+    /// Gets the webhooks of a guild notified of an event.
+    pub async fn subscribed(
+        &self,
+        guild_id: GuildId,
+        event: WebhookEvent,
+    ) -> Result<Vec<Webhook>, Error> {
+        Ok(self
+            .list(guild_id)
+            .await?
+            .into_iter()
+            .filter(|x| x.events.contains(&event))
+            .collect())
+    }
+
+    /// Registers a webhook, returning its id.
+    pub async fn add(
+        &self,
+        guild_id: GuildId,
+        url: &str,
+        secret: &str,
+        events: &[WebhookEvent],
+        created_at: i64,
+    ) -> Result<i32, Error> {
+        let events: Vec<&str> = events.iter().map(|x| x.as_str()).collect();
+        Ok(guild_webhooks::Entity::insert(guild_webhooks::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            url: Set(url.to_string()),
+            secret: Set(secret.to_string()),
+            events: Set(events.join(",")),
+            created_at: Set(created_at),
+            ..Default::default()
+        })
+        .exec(self.db)
+        .await?
+        .last_insert_id)
+    }
+
+    /// Removes a webhook of a guild and its deliveries, returning whether it existed.
+    pub async fn remove(&self, guild_id: GuildId, webhook_id: i32) -> Result<bool, Error> {
+        let result = guild_webhooks::Entity::delete_many()
+            .filter(guild_webhooks::Column::GuildId.eq(id_to_string(guild_id)))
+            .filter(guild_webhooks::Column::Id.eq(webhook_id))
+            .exec(self.db)
+            .await?;
+        if result.rows_affected == 0 {
+            return Ok(false);
+        }
+        webhook_deliveries::Entity::delete_many()
+            .filter(webhook_deliveries::Column::WebhookId.eq(webhook_id))
+            .exec(self.db)
+            .await?;
+        Ok(true)
+    }
+
+    pub async fn record_delivery(
+        &self,
+        guild_id: GuildId,
+        delivery: &Delivery,
+        delivered_at: i64,
+    ) -> Result<(), Error> {
+        webhook_deliveries::Entity::insert(webhook_deliveries::ActiveModel {
+            webhook_id: Set(delivery.webhook_id),
+            guild_id: Set(id_to_string(guild_id)),
+            event: Set(delivery.event.as_str().to_string()),
+            delivered_at: Set(delivered_at),
+            attempts: Set(delivery.attempts.min(i32::MAX as u32) as i32),
+            status_code: Set(delivery.status_code.map(i32::from)),
+            success: Set(delivery.error.is_none()),
+            error: Set(delivery.error.clone().unwrap_or_default()),
+            ..Default::default()
+        })
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Gets the latest deliveries of a guild, newest first.
+    pub async fn recent_deliveries(
+        &self,
+        guild_id: GuildId,
+        limit: u64,
+    ) -> Result<Vec<webhook_deliveries::Model>, Error> {
+        Ok(webhook_deliveries::Entity::find()
+            .filter(webhook_deliveries::Column::GuildId.eq(id_to_string(guild_id)))
+            .order_by_desc(webhook_deliveries::Column::Id)
+            .limit(limit)
+            .all(self.db)
+            .await?)
+    }
+
+    /// Deletes the deliveries older than a unix timestamp, returning the number of rows deleted.
+    pub async fn remove_deliveries_before(&self, timestamp: i64) -> Result<u64, Error> {
+        let result = webhook_deliveries::Entity::delete_many()
+            .filter(webhook_deliveries::Column::DeliveredAt.lt(timestamp))
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
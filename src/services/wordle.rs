@@ -0,0 +1,301 @@
+/*!
+
+Data access for the daily word game of `/wordle`: the game of each member on each day, their streaks and the channel
+the daily leaderboard is posted in.
+
+*/
+
+use migration::OnConflict;
+use poise::serenity_prelude::{ChannelId, GuildId, UserId, async_trait};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder,
+};
+
+use crate::{
+    Error,
+    entities::{wordle_games, wordle_settings, wordle_stats},
+    infrastructure::{
+        ids::{id_from_string, id_to_string},
+        retention::RetentionTask,
+        wordle::day_of,
+    },
+};
+
+/// Game of a member on a day.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WordleGame {
+    pub guesses: Vec<String>,
+    pub solved: bool,
+    /// Unix timestamp the game was won or lost at, 0 while it is played.
+    pub finished_at: i64,
+}
+
+impl WordleGame {
+    pub fn is_finished(&self) -> bool {
+        self.finished_at > 0
+    }
+
+    fn from_model(model: wordle_games::Model) -> Self {
+        Self {
+            guesses: model
+                .guesses
+                .split(',')
+                .filter(|x| !x.is_empty())
+                .map(|x| x.to_string())
+                .collect(),
+            solved: model.solved,
+            finished_at: model.finished_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WordleStats {
+    pub played: u32,
+    pub wins: u32,
+    /// Days in a row with a win, up to `last_win_day`.
+    pub current_streak: u32,
+    pub max_streak: u32,
+    /// Days since unix epoch, None before the first win.
+    pub last_win_day: Option<i64>,
+}
+
+impl WordleStats {
+    /// Streak still alive on a day: a streak ends when a day passes without a win.
+    pub fn streak_on(&self, day: i64) -> u32 {
+        match self.last_win_day {
+            Some(last) if last >= day - 1 => self.current_streak,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WordleRepo<'a, C = DatabaseConnection> {
+    db: &'a C,
+}
+
+impl<'a, C: ConnectionTrait> WordleRepo<'a, C> {
+    pub fn new(db: &'a C) -> Self {
+        Self { db }
+    }
+
+    /// Gets the game of a member on a day, or an empty game if they haven't guessed yet.
+    pub async fn game(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        day: i64,
+    ) -> Result<WordleGame, Error> {
+        Ok(
+            wordle_games::Entity::find_by_id((id_to_string(guild_id), day, id_to_string(user_id)))
+                .one(self.db)
+                .await?
+                .map(WordleGame::from_model)
+                .unwrap_or_default(),
+        )
+    }
+
+    pub async fn save_game(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        day: i64,
+        game: &WordleGame,
+    ) -> Result<(), Error> {
+        wordle_games::Entity::insert(wordle_games::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            day: Set(day),
+            user_id: Set(id_to_string(user_id)),
+            guesses: Set(game.guesses.join(",")),
+            solved: Set(game.solved),
+            finished_at: Set(game.finished_at),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                wordle_games::Column::GuildId,
+                wordle_games::Column::Day,
+                wordle_games::Column::UserId,
+            ])
+            .update_columns([
+                wordle_games::Column::Guesses,
+                wordle_games::Column::Solved,
+                wordle_games::Column::FinishedAt,
+            ])
+            .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Gets the finished games of a guild on a day, in the order they were finished.
+    pub async fn finished_games(
+        &self,
+        guild_id: GuildId,
+        day: i64,
+    ) -> Result<Vec<(UserId, WordleGame)>, Error> {
+        Ok(wordle_games::Entity::find()
+            .filter(wordle_games::Column::GuildId.eq(id_to_string(guild_id)))
+            .filter(wordle_games::Column::Day.eq(day))
+            .filter(wordle_games::Column::FinishedAt.gt(0))
+            .order_by_asc(wordle_games::Column::FinishedAt)
+            .all(self.db)
+            .await?
+            .into_iter()
+            .filter_map(|x| Some((id_from_string(&x.user_id).ok()?, WordleGame::from_model(x))))
+            .collect())
+    }
+
+    pub async fn stats(&self, guild_id: GuildId, user_id: UserId) -> Result<WordleStats, Error> {
+        Ok(
+            wordle_stats::Entity::find_by_id((id_to_string(guild_id), id_to_string(user_id)))
+                .one(self.db)
+                .await?
+                .map(|x| WordleStats {
+                    played: x.played.max(0) as u32,
+                    wins: x.wins.max(0) as u32,
+                    current_streak: x.current_streak.max(0) as u32,
+                    max_streak: x.max_streak.max(0) as u32,
+                    last_win_day: Some(x.last_win_day).filter(|x| *x >= 0),
+                })
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Counts a finished game in the stats of a member, extending their streak if they won the day after their last
+    /// win. Returns the updated stats.
+    pub async fn record_result(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        day: i64,
+        solved: bool,
+    ) -> Result<WordleStats, Error> {
+        let mut stats = self.stats(guild_id, user_id).await?;
+        stats.played += 1;
+        if solved {
+            stats.wins += 1;
+            stats.current_streak = stats.streak_on(day) + 1;
+            stats.max_streak = stats.max_streak.max(stats.current_streak);
+            stats.last_win_day = Some(day);
+        } else {
+            stats.current_streak = 0;
+        }
+        wordle_stats::Entity::insert(wordle_stats::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            user_id: Set(id_to_string(user_id)),
+            played: Set(stats.played as i32),
+            wins: Set(stats.wins as i32),
+            current_streak: Set(stats.current_streak as i32),
+            max_streak: Set(stats.max_streak as i32),
+            last_win_day: Set(stats.last_win_day.unwrap_or(-1)),
+        })
+        .on_conflict(
+            OnConflict::columns([wordle_stats::Column::GuildId, wordle_stats::Column::UserId])
+                .update_columns([
+                    wordle_stats::Column::Played,
+                    wordle_stats::Column::Wins,
+                    wordle_stats::Column::CurrentStreak,
+                    wordle_stats::Column::MaxStreak,
+                    wordle_stats::Column::LastWinDay,
+                ])
+                .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(stats)
+    }
+
+    /// Gets the channels the daily leaderboard is posted in, on every guild.
+    pub async fn leaderboard_channels(&self) -> Result<Vec<(GuildId, ChannelId)>, Error> {
+        Ok(wordle_settings::Entity::find()
+            .all(self.db)
+            .await?
+            .into_iter()
+            .filter_map(|x| {
+                Some((
+                    id_from_string(&x.guild_id).ok()?,
+                    id_from_string(&x.channel_id).ok()?,
+                ))
+            })
+            .collect())
+    }
+
+    /// Sets the channel the daily leaderboard of a guild is posted in, or stops posting it.
+    pub async fn set_leaderboard_channel(
+        &self,
+        guild_id: GuildId,
+        channel_id: Option<ChannelId>,
+    ) -> Result<(), Error> {
+        let Some(channel_id) = channel_id else {
+            wordle_settings::Entity::delete_by_id(id_to_string(guild_id))
+                .exec(self.db)
+                .await?;
+            return Ok(());
+        };
+        wordle_settings::Entity::insert(wordle_settings::ActiveModel {
+            guild_id: Set(id_to_string(guild_id)),
+            channel_id: Set(id_to_string(channel_id)),
+        })
+        .on_conflict(
+            OnConflict::column(wordle_settings::Column::GuildId)
+                .update_column(wordle_settings::Column::ChannelId)
+                .to_owned(),
+        )
+        .exec(self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Deletes the games played before a day, returning the number of rows deleted.
+    pub async fn remove_before(&self, day: i64) -> Result<u64, Error> {
+        let result = wordle_games::Entity::delete_many()
+            .filter(wordle_games::Column::Day.lt(day))
+            .exec(self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    pub async fn forget_user(&self, user_id: UserId) -> Result<u64, Error> {
+        let games = wordle_games::Entity::delete_many()
+            .filter(wordle_games::Column::UserId.eq(id_to_string(user_id)))
+            .exec(self.db)
+            .await?;
+        let stats = wordle_stats::Entity::delete_many()
+            .filter(wordle_stats::Column::UserId.eq(id_to_string(user_id)))
+            .exec(self.db)
+            .await?;
+        Ok(games.rows_affected + stats.rows_affected)
+    }
+}
+
+/// Expires past games, which are kept for 30 days by default. Streaks are kept in the stats of each member.
+pub struct WordleRetention;
+
+#[async_trait]
+impl RetentionTask for WordleRetention {
+    fn name(&self) -> &'static str {
+        "wordle_games"
+    }
+
+    fn default_retention_days(&self) -> Option<u32> {
+        Some(30)
+    }
+
+    async fn expire(
+        &self,
+        db: &DatabaseConnection,
+        now: i64,
+        retention_days: u32,
+    ) -> Result<u64, Error> {
+        WordleRepo::new(db)
+            .remove_before(day_of(now) - retention_days as i64)
+            .await
+    }
+
+    async fn forget_user(&self, db: &DatabaseConnection, user_id: UserId) -> Result<u64, Error> {
+        WordleRepo::new(db).forget_user(user_id).await
+    }
+}